@@ -1,10 +1,13 @@
-use apm::{CommandStarted, CommandResult};
+use apm::{CommandStarted, CommandResult, ConnectionEstablished};
+#[cfg(feature = "metrics")]
+use apm::metrics;
 use Client;
 use error::Result;
 
 pub trait EventRunner {
     fn run_start_hooks(&self, hook: &CommandStarted) -> Result<()>;
     fn run_completion_hooks(&self, hook: &CommandResult) -> Result<()>;
+    fn run_connection_hooks(&self, hook: &ConnectionEstablished) -> Result<()>;
 }
 
 impl EventRunner for Client {
@@ -13,6 +16,13 @@ impl EventRunner for Client {
     }
 
     fn run_completion_hooks(&self, hook: &CommandResult) -> Result<()> {
+        #[cfg(feature = "metrics")]
+        metrics::command_completed(hook);
+
         self.listener.run_completion_hooks(self.clone(), hook)
     }
+
+    fn run_connection_hooks(&self, hook: &ConnectionEstablished) -> Result<()> {
+        self.listener.run_connection_hooks(self.clone(), hook)
+    }
 }
@@ -9,18 +9,25 @@ use separator::Separatable;
 pub struct CommandStarted {
     pub command: Document,
     pub database_name: String,
+    /// The collection the command acts on, if any.
+    pub collection_name: Option<String>,
     pub command_name: String,
     pub request_id: i64,
+    /// Shared by every command sent on behalf of the same logical operation.
+    pub operation_id: i64,
     pub connection_string: String,
+    /// The server the command was sent to, as `host:port`.
+    pub host: String,
 }
 
 impl Display for CommandStarted {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
         write!(
             fmt,
-            "COMMAND.{} {} STARTED: {}",
+            "COMMAND.{} {} op#{} STARTED: {}",
             self.command_name,
             self.connection_string,
+            self.operation_id,
             self.command
         )
     }
@@ -33,18 +40,60 @@ pub enum CommandResult<'a> {
         duration: u64,
         reply: Document,
         command_name: String,
+        database_name: String,
+        collection_name: Option<String>,
         request_id: i64,
+        operation_id: i64,
         connection_string: String,
+        host: String,
     },
     Failure {
         duration: u64,
         command_name: String,
+        database_name: String,
+        collection_name: Option<String>,
         failure: &'a MongoError,
         request_id: i64,
+        operation_id: i64,
         connection_string: String,
+        host: String,
     },
 }
 
+impl<'a> CommandResult<'a> {
+    pub fn command_name(&self) -> &str {
+        match *self {
+            CommandResult::Success { ref command_name, .. } |
+            CommandResult::Failure { ref command_name, .. } => command_name,
+        }
+    }
+
+    pub fn database_name(&self) -> &str {
+        match *self {
+            CommandResult::Success { ref database_name, .. } |
+            CommandResult::Failure { ref database_name, .. } => database_name,
+        }
+    }
+
+    /// The collection the command acted on, if any.
+    pub fn collection_name(&self) -> Option<&str> {
+        match *self {
+            CommandResult::Success { ref collection_name, .. } |
+            CommandResult::Failure { ref collection_name, .. } => {
+                collection_name.as_deref()
+            }
+        }
+    }
+
+    /// The server the command was sent to, as `host:port`.
+    pub fn host(&self) -> &str {
+        match *self {
+            CommandResult::Success { ref host, .. } |
+            CommandResult::Failure { ref host, .. } => host,
+        }
+    }
+}
+
 impl<'a> Display for CommandResult<'a> {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
         match *self {
@@ -52,14 +101,16 @@ impl<'a> Display for CommandResult<'a> {
                 duration,
                 ref reply,
                 ref command_name,
+                operation_id,
                 ref connection_string,
                 ..
             } => {
                 write!(
                     fmt,
-                    "COMMAND.{} {} COMPLETED: {} ({} ns)",
+                    "COMMAND.{} {} op#{} COMPLETED: {} ({} ns)",
                     command_name,
                     connection_string,
+                    operation_id,
                     reply,
                     duration.separated_string()
                 )
@@ -68,14 +119,16 @@ impl<'a> Display for CommandResult<'a> {
                 duration,
                 ref command_name,
                 failure,
+                operation_id,
                 ref connection_string,
                 ..
             } => {
                 write!(
                     fmt,
-                    "COMMAND.{} {} FAILURE: {} ({} ns)",
+                    "COMMAND.{} {} op#{} FAILURE: {} ({} ns)",
                     command_name,
                     connection_string,
+                    operation_id,
                     failure,
                     duration.separated_string()
                 )
@@ -83,3 +136,45 @@ impl<'a> Display for CommandResult<'a> {
         }
     }
 }
+
+/// Contains the information about a connection that was established, with the time each phase
+/// took, in nanoseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionEstablished {
+    /// The host connected to, as `host:port`.
+    pub host: String,
+    /// The address the host name resolved to.
+    pub connection_string: String,
+    /// The server's id for the connection, reported by MongoDB 4.2 or later.
+    pub server_connection_id: Option<i64>,
+    pub dns_duration: u64,
+    pub tcp_duration: u64,
+    /// Absent for unencrypted connections.
+    pub tls_duration: Option<u64>,
+    /// The isMaster handshake that sends the client's metadata.
+    pub handshake_duration: u64,
+}
+
+impl Display for ConnectionEstablished {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "CONNECTION {} {} ", self.host, self.connection_string)?;
+
+        match self.server_connection_id {
+            Some(id) => write!(fmt, "conn{} ", id)?,
+            None => fmt.write_str("conn? ")?,
+        }
+
+        write!(
+            fmt,
+            "ESTABLISHED: dns {} ns, tcp {} ns, ",
+            self.dns_duration.separated_string(),
+            self.tcp_duration.separated_string()
+        )?;
+
+        if let Some(tls_duration) = self.tls_duration {
+            write!(fmt, "tls {} ns, ", tls_duration.separated_string())?;
+        }
+
+        write!(fmt, "handshake {} ns", self.handshake_duration.separated_string())
+    }
+}
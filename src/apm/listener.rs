@@ -1,41 +1,161 @@
-use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::RwLock;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 
-use apm::event::{CommandStarted, CommandResult};
+use apm::event::{CommandStarted, CommandResult, ConnectionEstablished};
 use Client;
 use error::Result;
 
 pub type StartHook = fn(Client, &CommandStarted);
 pub type CompletionHook = fn(Client, &CommandResult);
+pub type ConnectionHook = fn(Client, &ConnectionEstablished);
 
+/// Which commands a hook runs for; by default, every command.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HookFilter {
+    /// Only commands against these databases.
+    pub databases: Option<Vec<String>>,
+    /// Only commands against these collections, by name without the database. Commands that
+    /// act on no collection, such as `is_master`, are skipped.
+    pub collections: Option<Vec<String>>,
+    /// Only commands with these names, such as `find` or `insert_one`.
+    pub command_names: Option<Vec<String>>,
+    /// Never commands with these names, such as `is_master` or `get_more`.
+    pub excluded_command_names: Option<Vec<String>>,
+}
+
+impl HookFilter {
+    pub fn new() -> HookFilter {
+        Default::default()
+    }
+
+    /// Whether a hook with this filter runs for a command.
+    pub fn matches(
+        &self,
+        database_name: &str,
+        collection_name: Option<&str>,
+        command_name: &str,
+    ) -> bool {
+        let allows = |names: &Option<Vec<String>>, name: Option<&str>| match *names {
+            Some(ref names) => name.is_some_and(|name| names.iter().any(|n| n == name)),
+            None => true,
+        };
+
+        let excluded = self.excluded_command_names
+            .iter()
+            .flatten()
+            .any(|name| name == command_name);
+
+        allows(&self.databases, Some(database_name)) &&
+            allows(&self.collections, collection_name) &&
+            allows(&self.command_names, Some(command_name)) && !excluded
+    }
+}
+
+/// Keeps a hook registered until removed or dropped.
+#[must_use = "dropping the handle removes the hook"]
+pub struct HookHandle {
+    listener: Weak<Listener>,
+    id: usize,
+}
+
+impl fmt::Debug for HookHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HookHandle").field("id", &self.id).finish()
+    }
+}
+
+impl HookHandle {
+    /// Removes the hook; it is not run for any event that starts afterwards. Handles may be
+    /// removed or dropped from within a hook.
+    pub fn remove(self) {}
+
+    /// Keeps the hook registered for as long as the client's servers are in use, as the
+    /// `add_*_hook` methods do.
+    pub fn detach(mut self) {
+        self.listener = Weak::new();
+    }
+}
+
+impl Drop for HookHandle {
+    fn drop(&mut self) {
+        if let Some(listener) = self.listener.upgrade() {
+            let _ = listener.remove_hook(self.id);
+        }
+    }
+}
+
+struct Registered<T> {
+    id: usize,
+    hook: T,
+    filter: Option<HookFilter>,
+}
+
+// Hooks run in the order they were added.
+//
+// Removed hooks are only marked as such while hooks are running, since the hook lists are locked
+// for reading then, and are dropped from the lists once no hooks are running.
 pub struct Listener {
+    next_id: AtomicUsize,
+    removed: Mutex<HashSet<usize>>,
+    has_removed: AtomicBool,
     no_start_hooks: AtomicBool,
     no_completion_hooks: AtomicBool,
-    start_hooks: RwLock<Vec<StartHook>>,
-    completion_hooks: RwLock<Vec<CompletionHook>>,
+    no_connection_hooks: AtomicBool,
+    start_hooks: RwLock<Vec<Registered<StartHook>>>,
+    completion_hooks: RwLock<Vec<Registered<CompletionHook>>>,
+    connection_hooks: RwLock<Vec<Registered<ConnectionHook>>>,
 }
 
 impl Listener {
     pub fn new() -> Listener {
         Listener {
+            next_id: AtomicUsize::new(0),
+            removed: Mutex::new(HashSet::new()),
+            has_removed: AtomicBool::new(false),
             no_start_hooks: AtomicBool::new(true),
             no_completion_hooks: AtomicBool::new(true),
+            no_connection_hooks: AtomicBool::new(true),
             start_hooks: RwLock::new(Vec::new()),
             completion_hooks: RwLock::new(Vec::new()),
+            connection_hooks: RwLock::new(Vec::new()),
         }
     }
 
     pub fn add_start_hook(&self, hook: StartHook) -> Result<()> {
-        let mut guard = self.start_hooks.write()?;
-        self.no_start_hooks.store(false, Ordering::SeqCst);
-        Ok(guard.deref_mut().push(hook))
+        self.push_start_hook(hook, None).map(|_| ())
     }
 
     pub fn add_completion_hook(&self, hook: CompletionHook) -> Result<()> {
-        let mut guard = self.completion_hooks.write()?;
-        self.no_completion_hooks.store(false, Ordering::SeqCst);
-        Ok(guard.deref_mut().push(hook))
+        self.push_completion_hook(hook, None).map(|_| ())
+    }
+
+    pub fn add_connection_hook(&self, hook: ConnectionHook) -> Result<()> {
+        self.push_connection_hook(hook).map(|_| ())
+    }
+
+    pub fn register_start_hook(
+        self: &Arc<Self>,
+        hook: StartHook,
+        filter: Option<HookFilter>,
+    ) -> Result<HookHandle> {
+        let id = self.push_start_hook(hook, filter)?;
+        Ok(HookHandle { listener: Arc::downgrade(self), id })
+    }
+
+    pub fn register_completion_hook(
+        self: &Arc<Self>,
+        hook: CompletionHook,
+        filter: Option<HookFilter>,
+    ) -> Result<HookHandle> {
+        let id = self.push_completion_hook(hook, filter)?;
+        Ok(HookHandle { listener: Arc::downgrade(self), id })
+    }
+
+    pub fn register_connection_hook(self: &Arc<Self>, hook: ConnectionHook) -> Result<HookHandle> {
+        let id = self.push_connection_hook(hook)?;
+        Ok(HookHandle { listener: Arc::downgrade(self), id })
     }
 
     pub fn run_start_hooks(&self, client: Client, started: &CommandStarted) -> Result<()> {
@@ -43,12 +163,22 @@ impl Listener {
             return Ok(());
         }
 
-        let guard = self.start_hooks.read()?;
+        {
+            let guard = self.start_hooks.read()?;
+            let collection_name = started.collection_name.as_deref();
 
-        for hook in guard.deref().iter() {
-            hook(client.clone(), started);
+            for registered in guard.iter() {
+                let matches = registered.filter.as_ref().map_or(true, |filter| {
+                    filter.matches(&started.database_name, collection_name, &started.command_name)
+                });
+
+                if matches && !self.is_removed(registered.id) {
+                    (registered.hook)(client.clone(), started);
+                }
+            }
         }
 
+        self.sweep_removed();
         Ok(())
     }
 
@@ -57,12 +187,120 @@ impl Listener {
             return Ok(());
         }
 
-        let guard = self.completion_hooks.read()?;
+        {
+            let guard = self.completion_hooks.read()?;
+
+            for registered in guard.iter() {
+                let matches = registered.filter.as_ref().map_or(true, |filter| {
+                    filter.matches(
+                        result.database_name(),
+                        result.collection_name(),
+                        result.command_name(),
+                    )
+                });
+
+                if matches && !self.is_removed(registered.id) {
+                    (registered.hook)(client.clone(), result);
+                }
+            }
+        }
+
+        self.sweep_removed();
+        Ok(())
+    }
+
+    pub fn run_connection_hooks(
+        &self,
+        client: Client,
+        established: &ConnectionEstablished,
+    ) -> Result<()> {
+        if self.no_connection_hooks.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        {
+            let guard = self.connection_hooks.read()?;
 
-        for hook in guard.deref().iter() {
-            hook(client.clone(), result);
+            for registered in guard.iter() {
+                if !self.is_removed(registered.id) {
+                    (registered.hook)(client.clone(), established);
+                }
+            }
         }
 
+        self.sweep_removed();
         Ok(())
     }
+
+    fn push_start_hook(&self, hook: StartHook, filter: Option<HookFilter>) -> Result<usize> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut guard = self.start_hooks.write()?;
+        guard.push(Registered { id, hook, filter });
+        self.no_start_hooks.store(false, Ordering::SeqCst);
+        Ok(id)
+    }
+
+    fn push_completion_hook(
+        &self,
+        hook: CompletionHook,
+        filter: Option<HookFilter>,
+    ) -> Result<usize> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut guard = self.completion_hooks.write()?;
+        guard.push(Registered { id, hook, filter });
+        self.no_completion_hooks.store(false, Ordering::SeqCst);
+        Ok(id)
+    }
+
+    fn push_connection_hook(&self, hook: ConnectionHook) -> Result<usize> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut guard = self.connection_hooks.write()?;
+        guard.push(Registered { id, hook, filter: None });
+        self.no_connection_hooks.store(false, Ordering::SeqCst);
+        Ok(id)
+    }
+
+    // Removes the hook with `id`, whichever kind it is.
+    fn remove_hook(&self, id: usize) -> Result<()> {
+        self.removed.lock()?.insert(id);
+        self.has_removed.store(true, Ordering::SeqCst);
+        self.sweep_removed();
+        Ok(())
+    }
+
+    fn is_removed(&self, id: usize) -> bool {
+        self.has_removed.load(Ordering::SeqCst) &&
+            self.removed.lock().unwrap_or_else(|err| err.into_inner()).contains(&id)
+    }
+
+    // Drops removed hooks from the lists, unless hooks are running, in which case the lists are
+    // swept once they finish.
+    fn sweep_removed(&self) {
+        if !self.has_removed.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let (mut start_hooks, mut completion_hooks, mut connection_hooks) = match (
+            self.start_hooks.try_write(),
+            self.completion_hooks.try_write(),
+            self.connection_hooks.try_write(),
+        ) {
+            (Ok(start), Ok(completion), Ok(connection)) => (start, completion, connection),
+            _ => return,
+        };
+
+        let mut removed = self.removed.lock().unwrap_or_else(|err| err.into_inner());
+
+        start_hooks.retain(|registered| !removed.contains(&registered.id));
+        self.no_start_hooks.store(start_hooks.is_empty(), Ordering::SeqCst);
+
+        completion_hooks.retain(|registered| !removed.contains(&registered.id));
+        self.no_completion_hooks.store(completion_hooks.is_empty(), Ordering::SeqCst);
+
+        connection_hooks.retain(|registered| !removed.contains(&registered.id));
+        self.no_connection_hooks.store(connection_hooks.is_empty(), Ordering::SeqCst);
+
+        removed.clear();
+        self.has_removed.store(false, Ordering::SeqCst);
+    }
 }
@@ -0,0 +1,179 @@
+//! Driver metrics, published through the `metrics` crate facade.
+//!
+//! With the `metrics` feature, the driver records the following to whichever recorder the
+//! application installs, such as `metrics-exporter-prometheus`:
+//!
+//! - `mongodb_commands_total`, a counter of completed commands labelled by `command` and
+//!   `outcome` (`success` or `failure`);
+//! - `mongodb_command_duration_seconds`, a histogram of command latency labelled by `command`;
+//! - `mongodb_pool_checkouts_total`, a counter of connections checked out of each server's pool,
+//!   labelled by `address`;
+//! - `mongodb_connections_open`, a gauge of the open pooled connections to each server, labelled
+//!   by `address`;
+//! - `mongodb_server_selection_duration_seconds`, a histogram of the time taken to select a
+//!   server and check out a connection, labelled by `operation` (`read` or `write`) and
+//!   `outcome`.
+//!
+//! Like hooks, metrics leave out suppressed commands such as authentication.
+use apm::CommandResult;
+use connstring::Host;
+
+use std::time::Duration;
+
+pub const COMMANDS_TOTAL: &str = "mongodb_commands_total";
+pub const COMMAND_DURATION_SECONDS: &str = "mongodb_command_duration_seconds";
+pub const POOL_CHECKOUTS_TOTAL: &str = "mongodb_pool_checkouts_total";
+pub const CONNECTIONS_OPEN: &str = "mongodb_connections_open";
+pub const SERVER_SELECTION_DURATION_SECONDS: &str = "mongodb_server_selection_duration_seconds";
+
+/// Records a completed command's outcome and latency.
+pub fn command_completed(result: &CommandResult) {
+    let (outcome, duration) = match *result {
+        CommandResult::Success { duration, .. } => ("success", duration),
+        CommandResult::Failure { duration, .. } => ("failure", duration),
+    };
+
+    let command = result.command_name().to_owned();
+    ::metrics::counter!(COMMANDS_TOTAL, "command" => command.clone(), "outcome" => outcome)
+        .increment(1);
+    ::metrics::histogram!(COMMAND_DURATION_SECONDS, "command" => command)
+        .record(duration as f64 / 1e9);
+}
+
+/// Records a connection checked out of `host`'s pool.
+pub fn connection_checked_out(host: &Host) {
+    ::metrics::counter!(POOL_CHECKOUTS_TOTAL, "address" => address(host)).increment(1);
+}
+
+/// Records the number of open pooled connections to `host`.
+pub fn connections_open(host: &Host, open: usize) {
+    ::metrics::gauge!(CONNECTIONS_OPEN, "address" => address(host)).set(open as f64);
+}
+
+/// Records the time taken to select a server, and whether one was found.
+pub fn server_selected(write: bool, elapsed: Duration, succeeded: bool) {
+    let operation = if write { "write" } else { "read" };
+    let outcome = if succeeded { "success" } else { "failure" };
+
+    ::metrics::histogram!(
+        SERVER_SELECTION_DURATION_SECONDS,
+        "operation" => operation,
+        "outcome" => outcome
+    ).record(elapsed.as_secs_f64());
+}
+
+fn address(host: &Host) -> String {
+    format!("{}:{}", host.host_name, host.port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{command_completed, connections_open};
+    use apm::CommandResult;
+    use bson::doc;
+    use connstring::Host;
+
+    use metrics::{Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName,
+                  Metadata, Recorder, SharedString, Unit};
+
+    use std::sync::{Arc, Mutex};
+
+    // Records each update as `name{label=value,...} value`.
+    #[derive(Default)]
+    struct Updates(Arc<Mutex<Vec<String>>>);
+
+    struct Handle(Key, Arc<Mutex<Vec<String>>>);
+
+    impl Handle {
+        fn push(&self, value: f64) {
+            let labels: Vec<_> = self.0
+                .labels()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect();
+            let update = format!("{}{{{}}} {}", self.0.name(), labels.join(","), value);
+            self.1.lock().unwrap().push(update);
+        }
+    }
+
+    impl CounterFn for Handle {
+        fn increment(&self, value: u64) {
+            self.push(value as f64);
+        }
+
+        fn absolute(&self, value: u64) {
+            self.push(value as f64);
+        }
+    }
+
+    impl GaugeFn for Handle {
+        fn increment(&self, value: f64) {
+            self.push(value);
+        }
+
+        fn decrement(&self, value: f64) {
+            self.push(-value);
+        }
+
+        fn set(&self, value: f64) {
+            self.push(value);
+        }
+    }
+
+    impl HistogramFn for Handle {
+        fn record(&self, value: f64) {
+            self.push(value);
+        }
+    }
+
+    impl Recorder for Updates {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata) -> Counter {
+            Counter::from_arc(Arc::new(Handle(key.clone(), self.0.clone())))
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata) -> Gauge {
+            Gauge::from_arc(Arc::new(Handle(key.clone(), self.0.clone())))
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata) -> Histogram {
+            Histogram::from_arc(Arc::new(Handle(key.clone(), self.0.clone())))
+        }
+    }
+
+    #[test]
+    fn published_metrics() {
+        let recorder = Updates::default();
+        let host = Host {
+            host_name: String::from("db.example.com"),
+            ipc: String::new(),
+            port: 27017,
+        };
+
+        ::metrics::with_local_recorder(&recorder, || {
+            command_completed(&CommandResult::Success {
+                duration: 1_500_000,
+                reply: doc! { "ok": 1 },
+                command_name: String::from("find"),
+                database_name: String::from("app"),
+                collection_name: Some(String::from("users")),
+                request_id: 1,
+                operation_id: 1,
+                connection_string: String::from("127.0.0.1:27017"),
+                host: host.to_string(),
+            });
+            connections_open(&host, 3);
+        });
+
+        assert_eq!(
+            vec![
+                "mongodb_commands_total{command=find,outcome=success} 1",
+                "mongodb_command_duration_seconds{command=find} 0.0015",
+                "mongodb_connections_open{address=db.example.com:27017} 3",
+            ],
+            *recorder.0.lock().unwrap()
+        );
+    }
+}
@@ -3,11 +3,28 @@
 //! The APM module provides an intuitive interface for monitoring and responding to runtime
 //! information about commands being executed on the server. All non-suppressed commands trigger
 //! start and completion hooks defined on the client. Each non-suppressed command is also logged,
-//! if a log file was specified during instantiation of the client.
+//! if a log file was specified during instantiation of the client. Commands sent on behalf of the
+//! same logical operation share an operation id; see `Operation`.
+//!
+//! Hooks run in the order they were added. Hooks added with the `register_*_hook` methods can be
+//! limited to some databases, collections or command names with a `HookFilter`, and are removed
+//! when their `HookHandle` is removed or dropped.
+//!
+//! With the `metrics` feature, command outcomes and latencies, pool checkouts, open connections
+//! and server selection times are also published through the `metrics` crate; see the `metrics`
+//! submodule for the metric names.
+//!
+//! Every new connection, including those opened by server monitors, triggers the connection hooks
+//! and is logged with the time spent resolving the host, connecting, negotiating TLS and sending
+//! the handshake, so that slow connection establishment can be traced to its phase.
 pub mod client;
 mod event;
 mod listener;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod operation;
 
 pub use self::client::EventRunner;
-pub use self::event::{CommandStarted, CommandResult};
-pub use self::listener::Listener;
+pub use self::event::{CommandStarted, CommandResult, ConnectionEstablished};
+pub use self::listener::{HookFilter, HookHandle, Listener};
+pub use self::operation::Operation;
@@ -0,0 +1,62 @@
+//! Operation ids correlating the commands of a logical operation.
+//!
+//! A single call into the driver may send several commands: `insert_many` splits large inputs
+//! into batches, `bulk_write` sends one command per batch, and a cursor issues getMores long after
+//! its initial query. Every command started on behalf of the same call carries the same operation
+//! id in its monitoring events and log lines, regardless of how many servers were tried or which
+//! connections were used along the way.
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_OPERATION_ID: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    static CURRENT_OPERATION: Cell<Option<i64>> = const { Cell::new(None) };
+}
+
+/// Marks the current thread as running a logical operation until dropped.
+///
+/// Operations nest: starting an operation while another is active on the same thread reuses the
+/// outer operation's id, so helpers built on other public methods stay a single operation.
+#[derive(Debug)]
+pub struct Operation {
+    id: i64,
+    outermost: bool,
+}
+
+impl Operation {
+    /// Starts an operation on the current thread, or joins the one already active.
+    pub fn start() -> Operation {
+        match Operation::current_id() {
+            Some(id) => Operation { id, outermost: false },
+            None => {
+                let id = next_id();
+                CURRENT_OPERATION.with(|current| current.set(Some(id)));
+                Operation { id, outermost: true }
+            }
+        }
+    }
+
+    /// The id attached to every command this operation sends.
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Returns the id of the operation active on the current thread, if any.
+    pub fn current_id() -> Option<i64> {
+        CURRENT_OPERATION.with(Cell::get)
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if self.outermost {
+            CURRENT_OPERATION.with(|current| current.set(None));
+        }
+    }
+}
+
+// Allocates an id no other operation in this process has used.
+pub fn next_id() -> i64 {
+    NEXT_OPERATION_ID.fetch_add(1, Ordering::SeqCst) as i64
+}
@@ -0,0 +1,36 @@
+//! Macros that generate builders.
+
+// Defines a builder for an options struct that implements `Default`, with a chainable setter
+// for each listed field and `build` to return the options. Setters take anything that converts
+// into the field's type, so optional fields can be set without wrapping values in `Some`.
+macro_rules! options_builder {
+    ($options:ident, $builder:ident { $($field:ident: $ty:ty,)* }) => {
+        impl $options {
+            /// Returns a builder that starts from the default options.
+            pub fn builder() -> $builder {
+                $builder { options: $options::default() }
+            }
+        }
+
+        #[doc = concat!("Builds `", stringify!($options), "` one option at a time.")]
+        #[derive(Clone, Debug, Default)]
+        pub struct $builder {
+            options: $options,
+        }
+
+        impl $builder {
+            $(
+                #[doc = concat!("Sets `", stringify!($field), "`.")]
+                pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+                    self.options.$field = $field.into();
+                    self
+                }
+            )*
+
+            /// Returns the options that were set.
+            pub fn build(self) -> $options {
+                self.options
+            }
+        }
+    };
+}
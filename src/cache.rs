@@ -0,0 +1,202 @@
+//! Read-through caching in a TTL collection.
+//!
+//! A `CachedCollection<T>` stores serialized values of `T` by string key in a collection whose
+//! documents expire through a TTL index. On a miss, `get_or_load` runs the caller's loader and
+//! stores its result. Concurrent misses for the same key within a process are coalesced: one
+//! caller runs the loader while the others wait for, and then read, its result.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate serde_derive;
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::cache::CachedCollection;
+//! # use mongodb::db::ThreadedDatabase;
+//! #
+//! #[derive(Serialize, Deserialize)]
+//! struct Profile {
+//!     name: String,
+//! }
+//!
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let cache = CachedCollection::new(&client.db("app").collection("profile_cache"), None);
+//! cache.ensure_indexes().unwrap();
+//!
+//! let profile: Profile = cache
+//!     .get_or_load("user:42", || Ok(Profile { name: String::from("Ada") }))
+//!     .unwrap();
+//! # }
+//! ```
+use bson::{self, Bson, doc};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use coll::Collection;
+use coll::options::{IndexOptions, UpdateOptions};
+use db::{Database, ThreadedDatabase};
+use Error::{DecoderError, EncoderError, WriteError};
+use Result;
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// The default lifetime of a cached value; 60000 ms.
+pub const DEFAULT_CACHE_TTL_MS: i64 = 60000;
+
+/// Options for cache behavior.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CacheOptions {
+    /// How long a stored value remains valid; defaults to 60000 ms.
+    pub ttl_ms: Option<i64>,
+}
+
+impl CacheOptions {
+    pub fn new() -> CacheOptions {
+        Default::default()
+    }
+}
+
+/// A typed cache stored in a TTL-indexed collection.
+///
+/// Clones share the same in-process coalescing of concurrent loads.
+#[derive(Debug)]
+pub struct CachedCollection<T> {
+    db: Database,
+    coll: String,
+    ttl_ms: i64,
+    // One mutex per key currently being loaded.
+    flights: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for CachedCollection<T> {
+    fn clone(&self) -> Self {
+        CachedCollection {
+            db: self.db.clone(),
+            coll: self.coll.clone(),
+            ttl_ms: self.ttl_ms,
+            flights: self.flights.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> CachedCollection<T> {
+    /// Creates a cache stored in the given collection.
+    pub fn new(coll: &Collection, options: Option<CacheOptions>) -> CachedCollection<T> {
+        let options = options.unwrap_or_default();
+
+        CachedCollection {
+            db: coll.db.clone(),
+            coll: coll.name(),
+            ttl_ms: options.ttl_ms.unwrap_or(DEFAULT_CACHE_TTL_MS),
+            flights: Arc::new(Mutex::new(HashMap::new())),
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the collection backing the cache.
+    pub fn collection(&self) -> Collection {
+        self.db.collection(&self.coll)
+    }
+
+    /// Creates the TTL index that removes expired values.
+    ///
+    /// The server removes expired documents periodically rather than immediately, so reads
+    /// also check expiry themselves.
+    pub fn ensure_indexes(&self) -> Result<()> {
+        let mut options = IndexOptions::new();
+        options.expire_after_seconds = Some(0);
+
+        self.collection()
+            .create_index(doc!{ "expires_at": 1 }, Some(options))
+            .map(drop)
+    }
+
+    /// Returns the cached value for `key`, or `None` if it is absent or expired.
+    pub fn get(&self, key: &str) -> Result<Option<T>> {
+        let filter = doc! {
+            "_id": key,
+            "expires_at": { "$gt": Utc::now() },
+        };
+
+        let doc = match self.collection().find_one(Some(filter), None)? {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+
+        let value = doc.get("value").cloned().unwrap_or(Bson::Null);
+        bson::from_bson(value).map(Some).map_err(DecoderError)
+    }
+
+    /// Stores `value` under `key`, replacing any existing value and resetting its expiry.
+    pub fn insert(&self, key: &str, value: &T) -> Result<()> {
+        let value = bson::to_bson(value).map_err(EncoderError)?;
+        let expires_at = Utc::now() + Duration::milliseconds(self.ttl_ms);
+
+        let mut options = UpdateOptions::new();
+        options.upsert = Some(true);
+
+        let result = self.collection().replace_one(
+            doc!{ "_id": key },
+            doc! {
+                "value": value,
+                "expires_at": expires_at,
+            },
+            Some(options),
+        )?;
+
+        match result.write_exception {
+            Some(exception) => Err(WriteError(exception)),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the value stored under `key`, returning whether one existed.
+    pub fn invalidate(&self, key: &str) -> Result<bool> {
+        let result = self.collection().delete_one(doc!{ "_id": key }, None)?;
+        Ok(result.deleted_count > 0)
+    }
+
+    /// Returns the cached value for `key`, calling `loader` and caching its result on a miss.
+    ///
+    /// Errors returned by `loader` are passed through and nothing is cached.
+    pub fn get_or_load<F>(&self, key: &str, loader: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        if let Some(value) = self.get(key)? {
+            return Ok(value);
+        }
+
+        let flight = self.flights
+            .lock()?
+            .entry(key.to_owned())
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone();
+
+        let result = {
+            let _loading = flight.lock()?;
+
+            // Another caller may have loaded the value while we waited.
+            match self.get(key) {
+                Ok(Some(value)) => Ok(value),
+                Ok(None) => loader().and_then(|value| {
+                    self.insert(key, &value)?;
+                    Ok(value)
+                }),
+                Err(err) => Err(err),
+            }
+        };
+
+        // Forget the flight once nobody else is waiting on it.
+        let mut flights = self.flights.lock()?;
+        if Arc::strong_count(&flight) == 2 {
+            flights.remove(key);
+        }
+
+        result
+    }
+}
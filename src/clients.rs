@@ -0,0 +1,108 @@
+//! A process-wide registry of shared clients.
+//!
+//! A `Client` is meant to be created once and shared: each one runs a monitor thread per server
+//! and keeps its own connection pools. Creating a client per request, as is easy to do in a web
+//! handler, opens new connections and monitors every time. `get_or_init` instead returns the
+//! same client for every call with an equivalent connection string, creating it on first use.
+//!
+//! Connection strings are equivalent if they name the same hosts, credentials, database and
+//! options, regardless of the order of hosts, the case of host names and option names, or a
+//! trailing slash.
+//!
+//! ```no_run
+//! # use mongodb::clients;
+//! # use mongodb::db::ThreadedDatabase;
+//! # use mongodb::ThreadedClient;
+//! fn handle_request() {
+//!     let client = clients::get_or_init("mongodb://localhost:27017").unwrap();
+//!     let names = client.db("app").collection_names(None).unwrap();
+//!     println!("{:?}", names);
+//! }
+//! ```
+use {Client, ClientOptions, Result, ThreadedClient};
+use connstring::{self, ConnectionString};
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+static CLIENTS: Mutex<BTreeMap<RegistryKey, Client>> = Mutex::new(BTreeMap::new());
+
+// The parts of a connection string that determine which client it needs.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RegistryKey {
+    hosts: Vec<(String, u16, String)>,
+    srv: Option<String>,
+    user: Option<String>,
+    password: Option<String>,
+    database: Option<String>,
+    options: BTreeMap<String, String>,
+    read_pref_tags: Vec<String>,
+}
+
+impl RegistryKey {
+    fn new(config: &ConnectionString) -> RegistryKey {
+        let mut hosts: Vec<_> = config
+            .hosts
+            .iter()
+            .map(|host| (host.host_name.clone(), host.port, host.ipc.clone()))
+            .collect();
+        hosts.sort();
+        hosts.dedup();
+
+        let (options, read_pref_tags) = match config.options {
+            Some(ref options) => {
+                let map = options
+                    .options
+                    .iter()
+                    .map(|(key, value)| (key.to_ascii_lowercase(), value.clone()))
+                    .collect();
+                (map, options.read_pref_tags.clone())
+            }
+            None => (BTreeMap::new(), Vec::new()),
+        };
+
+        RegistryKey {
+            hosts,
+            srv: config.srv.clone(),
+            user: config.user.clone(),
+            password: config.password.clone(),
+            database: config.database.clone(),
+            options,
+            read_pref_tags,
+        }
+    }
+}
+
+/// Returns the shared client for `uri`, connecting it if this is the first request for an
+/// equivalent connection string.
+pub fn get_or_init(uri: &str) -> Result<Client> {
+    get_or_init_with_options(uri, ClientOptions::new)
+}
+
+/// Returns the shared client for `uri`, connecting it with the options returned by `options` if
+/// this is the first request for an equivalent connection string. Later requests share the
+/// client as it was first configured.
+pub fn get_or_init_with_options<F>(uri: &str, options: F) -> Result<Client>
+where
+    F: FnOnce() -> ClientOptions,
+{
+    let config = connstring::parse(uri)?;
+    let key = RegistryKey::new(&config);
+
+    let mut clients = CLIENTS.lock()?;
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+
+    let client = Client::with_config(config, Some(options()), None)?;
+    clients.insert(key, client.clone());
+    Ok(client)
+}
+
+/// Removes the shared client for `uri` from the registry, returning it if there was one. The
+/// next request for an equivalent connection string creates a new client; holders of the removed
+/// client may keep using it.
+pub fn remove(uri: &str) -> Result<Option<Client>> {
+    let key = RegistryKey::new(&connstring::parse(uri)?);
+    Ok(CLIENTS.lock()?.remove(&key))
+}
@@ -0,0 +1,107 @@
+//! Sources of time.
+//!
+//! The driver reads the time through a `Clock` when timing server selection, measuring round
+//! trips to monitored servers, recording when servers were last checked, and enforcing operation
+//! deadlines. Clients use the `SystemClock` unless another is given in `ClientOptions`.
+//!
+//! A `MockClock` only moves when advanced, and its `sleep` advances it rather than blocking, so
+//! that tests of time-based behavior such as server selection timeouts and staleness run
+//! deterministically and without waiting.
+//!
+//! ```
+//! # extern crate mongodb;
+//! # use mongodb::clock::{Clock, MockClock};
+//! # use std::time::Duration;
+//! #
+//! # fn main() {
+//! let clock = MockClock::new();
+//! let start = clock.now();
+//! clock.sleep(Duration::from_secs(30));
+//! assert_eq!(Duration::from_secs(30), clock.now() - start);
+//! # }
+//! ```
+use chrono::{self, DateTime, Utc};
+
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A source of monotonic and wall-clock time.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current monotonic time, used to measure elapsed time.
+    fn now(&self) -> Instant;
+    /// Returns the current wall-clock time, used alongside timestamps reported by servers.
+    fn utc_now(&self) -> DateTime<Utc>;
+    /// Blocks the current thread for `duration`.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The operating system's clocks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// A clock that only moves when advanced.
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    start_utc: DateTime<Utc>,
+    elapsed: Mutex<Duration>,
+}
+
+impl MockClock {
+    /// Creates a clock stopped at the current time.
+    pub fn new() -> MockClock {
+        MockClock {
+            start: Instant::now(),
+            start_utc: Utc::now(),
+            elapsed: Mutex::new(Duration::from_secs(0)),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let mut elapsed = self.elapsed.lock().unwrap_or_else(|err| err.into_inner());
+        *elapsed += duration;
+    }
+
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        MockClock::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        let elapsed = chrono::Duration::from_std(self.elapsed())
+            .expect("Mock clock advanced out of range.");
+        self.start_utc + elapsed
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+}
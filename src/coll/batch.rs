@@ -127,6 +127,34 @@ impl From<WriteModel> for Batch {
     }
 }
 
+impl From<DeleteModel> for WriteModel {
+    fn from(model: DeleteModel) -> WriteModel {
+        if model.multi {
+            WriteModel::DeleteMany { filter: model.filter }
+        } else {
+            WriteModel::DeleteOne { filter: model.filter }
+        }
+    }
+}
+
+impl From<UpdateModel> for WriteModel {
+    fn from(model: UpdateModel) -> WriteModel {
+        if model.multi {
+            WriteModel::UpdateMany {
+                filter: model.filter,
+                update: model.update,
+                upsert: model.upsert,
+            }
+        } else {
+            WriteModel::UpdateOne {
+                filter: model.filter,
+                update: model.update,
+                upsert: model.upsert,
+            }
+        }
+    }
+}
+
 impl Batch {
     pub fn len(&self) -> i64 {
         let length = match *self {
@@ -138,6 +166,17 @@ impl Batch {
         length as i64
     }
 
+    /// Returns the models the batch was built from.
+    pub fn into_models(self) -> Vec<WriteModel> {
+        match self {
+            Batch::Insert(docs) => {
+                docs.into_iter().map(|document| WriteModel::InsertOne { document }).collect()
+            }
+            Batch::Delete(models) => models.into_iter().map(WriteModel::from).collect(),
+            Batch::Update(models) => models.into_iter().map(WriteModel::from).collect(),
+        }
+    }
+
     /// Attempts to merge another model into this batch.
     ///
     /// # Arguments
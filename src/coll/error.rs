@@ -2,9 +2,14 @@
 use bson::{self, Bson};
 use super::options::WriteModel;
 use common::WriteConcern;
-use {Error, Result};
+use error::parse_labels;
+use {Error, ErrorCode, Result};
 use std::{error, fmt};
 
+// Legacy codes that servers have also used to report duplicate key errors.
+const DUPLICATE_KEY_ON_UPDATE: i32 = 11001;
+const DUPLICATE_KEY_LEGACY: i32 = 12582;
+
 /// The error type for Write-related MongoDB operations.
 #[derive(Debug, Clone, PartialEq)]
 pub struct WriteException {
@@ -19,6 +24,10 @@ pub struct WriteConcernError {
     pub code: i32,
     pub details: WriteConcern,
     pub message: String,
+    /// Additional information reported by the server, such as whether the write timed out.
+    pub err_info: Option<bson::Document>,
+    /// Labels classifying the error, such as `RetryableWriteError`.
+    pub labels: Vec<String>,
 }
 
 /// The error struct for a write-related error.
@@ -46,6 +55,15 @@ pub struct BulkWriteError {
     pub code: i32,
     pub message: String,
     pub request: Option<WriteModel>,
+    /// Additional information reported by the server, such as the document that failed
+    /// validation.
+    pub err_info: Option<bson::Document>,
+}
+
+// Whether a server error code reports a duplicate key.
+fn is_duplicate_key_code(code: i32) -> bool {
+    code == ErrorCode::DuplicateKey as i32 || code == DUPLICATE_KEY_ON_UPDATE ||
+        code == DUPLICATE_KEY_LEGACY
 }
 
 impl error::Error for WriteException {
@@ -137,6 +155,11 @@ impl WriteException {
         }
     }
 
+    /// Whether the write failed because it would have duplicated a unique key.
+    pub fn is_duplicate_key(&self) -> bool {
+        self.write_error.iter().any(WriteError::is_duplicate_key)
+    }
+
     /// Downgrades a BulkWriteException into a WriteException, retrieving the
     /// last write error to emulate the behavior of continue_on_error.
     pub fn with_bulk_exception(bulk_exception: BulkWriteException) -> WriteException {
@@ -174,6 +197,8 @@ impl WriteConcernError {
             code: code,
             details: details,
             message: message.to_string(),
+            err_info: None,
+            labels: Vec::new(),
         }
     }
 
@@ -181,7 +206,10 @@ impl WriteConcernError {
     pub fn parse(error: bson::Document, write_concern: WriteConcern) -> Result<WriteConcernError> {
         match (error.get("code"), error.get("errmsg")) {
             (Some(&Bson::I32(code)), Some(&Bson::String(ref message))) => {
-                Ok(WriteConcernError::new(code, write_concern, message))
+                let mut wc_err = WriteConcernError::new(code, write_concern, message);
+                wc_err.err_info = parse_err_info(&error);
+                wc_err.labels = parse_labels(&error);
+                Ok(wc_err)
             }
             _ => Err(Error::ResponseError(format!(
                 "WriteConcernError document is invalid: {:?}",
@@ -200,6 +228,11 @@ impl WriteError {
         }
     }
 
+    /// Whether the write failed because it would have duplicated a unique key.
+    pub fn is_duplicate_key(&self) -> bool {
+        is_duplicate_key_code(self.code)
+    }
+
     /// Parses a Bson document into a WriteError.
     pub fn parse(error: bson::Document) -> Result<WriteError> {
         if let Some(&Bson::I32(code)) = error.get("code") {
@@ -226,16 +259,24 @@ impl BulkWriteError {
             code: code,
             message: message.to_string(),
             request: request,
+            err_info: None,
         }
     }
 
+    /// Whether the write failed because it would have duplicated a unique key.
+    pub fn is_duplicate_key(&self) -> bool {
+        is_duplicate_key_code(self.code)
+    }
+
     /// Parses a Bson document into a BulkWriteError.
     pub fn parse(error: bson::Document) -> Result<BulkWriteError> {
         match (error.get("index"), error.get("code"), error.get("errmsg")) {
             (Some(&Bson::I32(index)),
              Some(&Bson::I32(code)),
              Some(&Bson::String(ref message))) => {
-                Ok(BulkWriteError::new(index, code, message, None))
+                let mut err = BulkWriteError::new(index, code, message, None);
+                err.err_info = parse_err_info(&error);
+                Ok(err)
             }
             _ => Err(Error::ResponseError(
                 format!("WriteError document is invalid: {:?}", error),
@@ -271,6 +312,11 @@ impl BulkWriteException {
         }
     }
 
+    /// Whether any of the writes failed because it would have duplicated a unique key.
+    pub fn is_duplicate_key(&self) -> bool {
+        self.write_errors.iter().any(BulkWriteError::is_duplicate_key)
+    }
+
     /// Adds a model to the vector of unprocessed models
     pub fn add_unproccessed_model(&mut self, model: WriteModel) {
         self.unprocessed_requests.push(model);
@@ -322,9 +368,16 @@ impl BulkWriteException {
         write_concern: WriteConcern,
     ) -> Result<()> {
 
-        // Parse out any write concern errors.
+        // Parse out any write concern errors. Servers before MongoDB 4.4 label them in the
+        // reply rather than in the error itself.
         let wc_err = if let Some(&Bson::Document(ref error)) = result.get("writeConcernError") {
-            Some(WriteConcernError::parse(error.clone(), write_concern)?)
+            let mut wc_err = WriteConcernError::parse(error.clone(), write_concern)?;
+            for label in parse_labels(&result) {
+                if !wc_err.labels.contains(&label) {
+                    wc_err.labels.push(label);
+                }
+            }
+            Some(wc_err)
         } else {
             None
         };
@@ -365,3 +418,11 @@ impl BulkWriteException {
         }
     }
 }
+
+// Extracts the optional `errInfo` document from a server error.
+fn parse_err_info(error: &bson::Document) -> Option<bson::Document> {
+    match error.get("errInfo") {
+        Some(&Bson::Document(ref info)) => Some(info.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,117 @@
+//! Streaming a collection's documents to and from files; see `Collection::export_to_writer`.
+use bson;
+use serde_json::{self, Value};
+
+use coll::Collection;
+use coll::options::{ExportFormat, FindOptions};
+use ext_json::{self, Mode};
+use Error::{ArgumentError, BulkWriteError};
+use Result;
+
+use std::io::{self, Read, Write};
+
+// How many documents an export reads, and an import inserts, at a time.
+const EXPORT_BATCH_SIZE: usize = 1000;
+
+/// Writes every document of `coll` to `writer`, returning how many were written.
+pub fn export<W: Write>(coll: &Collection, mut writer: W, format: ExportFormat) -> Result<i64> {
+    let mut options = FindOptions::new();
+    options.batch_size = Some(EXPORT_BATCH_SIZE as i32);
+
+    let mut exported = 0;
+    for doc in coll.find(None, Some(options))? {
+        let doc = doc?;
+        match format {
+            ExportFormat::Bson => bson::encode_document(&mut writer, &doc)?,
+            ExportFormat::ExtendedJson => {
+                writer.write_all(ext_json::to_string(&doc, Mode::Canonical).as_bytes())?;
+                writer.write_all(b"\n")?;
+            }
+        }
+
+        exported += 1;
+    }
+
+    writer.flush()?;
+    Ok(exported)
+}
+
+/// Inserts the documents read from `reader` into `coll`, returning how many were inserted.
+pub fn import<R: Read>(coll: &Collection, reader: R, format: ExportFormat) -> Result<i64> {
+    let mut importer = Importer {
+        coll,
+        batch: Vec::with_capacity(EXPORT_BATCH_SIZE),
+        imported: 0,
+    };
+
+    match format {
+        ExportFormat::Bson => {
+            let mut reader = reader;
+            while let Some(doc) = read_bson_document(&mut reader)? {
+                importer.push(doc)?;
+            }
+        }
+        ExportFormat::ExtendedJson => {
+            for value in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+                let value = value.map_err(|err| {
+                    ArgumentError(format!("Invalid extended JSON: {}", err))
+                })?;
+
+                importer.push(ext_json::document_from_value(value)?)?;
+            }
+        }
+    }
+
+    importer.flush()?;
+    Ok(importer.imported)
+}
+
+// Inserts documents in batches, so that only one batch is held in memory.
+struct Importer<'a> {
+    coll: &'a Collection,
+    batch: Vec<bson::Document>,
+    imported: i64,
+}
+
+impl<'a> Importer<'a> {
+    fn push(&mut self, doc: bson::Document) -> Result<()> {
+        self.batch.push(doc);
+        if self.batch.len() >= EXPORT_BATCH_SIZE {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+
+        let docs: Vec<_> = self.batch.drain(..).collect();
+        let count = docs.len() as i64;
+        let result = self.coll.insert_many(docs, None)?;
+        if let Some(exception) = result.bulk_write_exception {
+            return Err(BulkWriteError(exception));
+        }
+
+        self.imported += count;
+        Ok(())
+    }
+}
+
+// Reads the next BSON document, or `None` at the end of the stream.
+fn read_bson_document<R: Read>(reader: &mut R) -> Result<Option<bson::Document>> {
+    let mut len = [0; 4];
+    let mut read = 0;
+    while read < len.len() {
+        match reader.read(&mut len[read..])? {
+            0 if read == 0 => return Ok(None),
+            0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            n => read += n,
+        }
+    }
+
+    let mut chained = (&len[..]).chain(reader);
+    Ok(Some(bson::decode_document(&mut chained)?))
+}
@@ -0,0 +1,142 @@
+//! Builders for query filters.
+//!
+//! Each function returns a `Filter` on one field, and filters combine with `and` and `or`:
+//!
+//! ```
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::coll::filter;
+//! # fn main() {
+//! let filter = filter::eq("director", "Spielberg").and(filter::gt("year", 1980));
+//!
+//! assert_eq!(
+//!     doc! { "$and": [{ "director": "Spielberg" }, { "year": { "$gt": 1980 } }] },
+//!     filter.into_document()
+//! );
+//! # }
+//! ```
+//!
+//! A `Filter` can be passed anywhere a collection method takes a filter document.
+use bson::{self, Bson, doc};
+
+use super::options::IntoDocument;
+
+/// A query filter.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter(bson::Document);
+
+impl Filter {
+    /// A filter that matches every document.
+    pub fn new() -> Filter {
+        Filter(bson::Document::new())
+    }
+
+    /// Matches the documents that match both this filter and `other`.
+    pub fn and(self, other: Filter) -> Filter {
+        self.combine("$and", other)
+    }
+
+    /// Matches the documents that match this filter, `other`, or both.
+    pub fn or(self, other: Filter) -> Filter {
+        self.combine("$or", other)
+    }
+
+    /// Returns the filter as a document.
+    pub fn into_document(self) -> bson::Document {
+        self.0
+    }
+
+    // Joins two filters with `op`, adding to the clauses of either filter that already joins
+    // others with `op` rather than nesting it.
+    fn combine(self, op: &str, other: Filter) -> Filter {
+        let mut clauses = self.clauses(op);
+        clauses.extend(other.clauses(op));
+        Filter(doc! { op: clauses })
+    }
+
+    fn clauses(mut self, op: &str) -> Vec<Bson> {
+        if self.0.len() == 1 && matches!(self.0.get(op), Some(Bson::Array(_))) {
+            if let Some(Bson::Array(clauses)) = self.0.remove(op) {
+                return clauses;
+            }
+        }
+
+        vec![Bson::Document(self.0)]
+    }
+}
+
+impl From<Filter> for bson::Document {
+    fn from(filter: Filter) -> Self {
+        filter.0
+    }
+}
+
+impl From<Filter> for Option<bson::Document> {
+    fn from(filter: Filter) -> Self {
+        Some(filter.0)
+    }
+}
+
+impl IntoDocument for Filter {
+    fn into_document(self) -> bson::Document {
+        self.0
+    }
+}
+
+// Builds `{ field: { op: value } }`.
+fn operator(field: &str, op: &str, value: Bson) -> Filter {
+    Filter(doc! { field: { op: value } })
+}
+
+/// Matches documents where `field` equals `value`.
+pub fn eq(field: &str, value: impl Into<Bson>) -> Filter {
+    Filter(doc! { field: value.into() })
+}
+
+/// Matches documents where `field` does not equal `value`, including those without `field`.
+pub fn ne(field: &str, value: impl Into<Bson>) -> Filter {
+    operator(field, "$ne", value.into())
+}
+
+/// Matches documents where `field` is greater than `value`.
+pub fn gt(field: &str, value: impl Into<Bson>) -> Filter {
+    operator(field, "$gt", value.into())
+}
+
+/// Matches documents where `field` is greater than or equal to `value`.
+pub fn gte(field: &str, value: impl Into<Bson>) -> Filter {
+    operator(field, "$gte", value.into())
+}
+
+/// Matches documents where `field` is less than `value`.
+pub fn lt(field: &str, value: impl Into<Bson>) -> Filter {
+    operator(field, "$lt", value.into())
+}
+
+/// Matches documents where `field` is less than or equal to `value`.
+pub fn lte(field: &str, value: impl Into<Bson>) -> Filter {
+    operator(field, "$lte", value.into())
+}
+
+/// Matches documents where `field` equals any of `values`.
+pub fn in_<V: Into<Bson>>(field: &str, values: impl IntoIterator<Item = V>) -> Filter {
+    let values: Vec<Bson> = values.into_iter().map(Into::into).collect();
+    operator(field, "$in", Bson::Array(values))
+}
+
+/// Matches documents where `field` equals none of `values`, including those without `field`.
+pub fn nin<V: Into<Bson>>(field: &str, values: impl IntoIterator<Item = V>) -> Filter {
+    let values: Vec<Bson> = values.into_iter().map(Into::into).collect();
+    operator(field, "$nin", Bson::Array(values))
+}
+
+/// Matches documents that have `field`, if `exists` is true, or that lack it otherwise.
+pub fn exists(field: &str, exists: bool) -> Filter {
+    operator(field, "$exists", Bson::Boolean(exists))
+}
+
+/// Matches documents where the string in `field` matches the regular expression `pattern`,
+/// applied with `options` such as `"i"`.
+pub fn regex(field: &str, pattern: &str, options: &str) -> Filter {
+    Filter(doc! { field: Bson::RegExp(String::from(pattern), String::from(options)) })
+}
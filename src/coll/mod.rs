@@ -1,28 +1,64 @@
 //! Interface for collection-level operations.
 mod batch;
 pub mod error;
+mod export;
+pub mod filter;
 pub mod options;
+pub mod query;
 pub mod results;
+pub mod stage;
+pub mod tail;
+pub mod update;
 
-use bson::{self, Bson, bson, doc, oid};
+use bson::{self, Bson, bson, doc};
 use command_type::CommandType;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 
 use self::batch::{Batch, DeleteModel, UpdateModel};
 use self::error::{BulkWriteException, WriteException};
 use self::options::*;
+use self::query::{Delete, Query, Update};
 use self::results::*;
+use self::stage::Pipeline;
+use self::tail::Tail;
 
 use ThreadedClient;
-use common::{merge_options, ReadPreference, WriteConcern};
-use cursor::Cursor;
+use common::{merge_options, ReadMode, ReadPreference, WriteConcern, WriteConcernErrorPolicy};
+use cursor::{self, Cursor, RawCursor, TypedCursor};
+#[cfg(feature = "stream")]
+use cursor_stream::{CursorStream, Executor};
 use db::{Database, ThreadedDatabase};
+use db::commands::{CollectionStats, ValidationResult};
+use object_id;
+use rollup::RollupSpec;
+use timeout::Deadline;
+use uuid::UuidRepresentation;
 
-use Result;
-use Error::{ArgumentError, DecoderError, ResponseError, OperationError, BulkWriteError};
+use {Error, ErrorCode, Operation, Result};
+use Error::{ArgumentError, DecoderError, EncoderError, ResponseError, OperationError,
+            BulkWriteError};
 
 use wire_protocol::flags::OpQueryFlags;
 use std::collections::{BTreeMap, VecDeque};
-use std::iter::FromIterator;
+use std::io::{Read, Write};
+use std::iter::{self, FromIterator};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+// How many `_id`s a parallel scan samples for each cursor it returns.
+const SCAN_SAMPLES_PER_CURSOR: usize = 10;
+
+// A query for the documents matching a `find`, and how to send it.
+struct Find {
+    namespace: String,
+    flags: OpQueryFlags,
+    query: bson::Document,
+    options: FindOptions,
+    is_cmd_cursor: bool,
+    read_preference: ReadPreference,
+}
 
 /// Interfaces with a MongoDB collection.
 #[derive(Debug)]
@@ -68,6 +104,20 @@ impl Collection {
         self.db.client.get_req_id()
     }
 
+    /// Returns a handle to the collection that stores UUIDs in `representation`, or leaves them
+    /// as they are if `None`; see the `uuid` module.
+    pub fn with_uuid_representation(
+        &self,
+        representation: Option<UuidRepresentation>,
+    ) -> Collection {
+        self.db.with_uuid_representation(representation).collection_with_prefs(
+            &self.name(),
+            false,
+            Some(self.read_preference.clone()),
+            Some(self.write_concern),
+        )
+    }
+
     /// Extracts the collection name from the namespace.
     /// If the namespace is invalid, this method will panic.
     pub fn name(&self) -> String {
@@ -90,20 +140,105 @@ impl Collection {
         self.db.drop_collection(&self.name())
     }
 
-    /// Runs an aggregation framework pipeline.
+    /// Returns storage statistics for the collection, with sizes divided by `scale`, such as
+    /// 1024 for kilobytes.
+    pub fn stats(&self, scale: Option<i32>) -> Result<CollectionStats> {
+        let mut spec = doc! { "collStats": self.name() };
+
+        if let Some(scale) = scale {
+            spec.insert("scale", scale);
+        }
+
+        self.run_command_checked(spec)
+    }
+
+    /// Checks the collection's documents and indexes for corruption. A `full` validation is
+    /// more thorough, but takes an exclusive lock on the collection while it runs.
+    pub fn validate(&self, full: bool) -> Result<ValidationResult> {
+        self.run_command_checked(doc! { "validate": self.name(), "full": full })
+    }
+
+    // Runs a command on the primary and deserializes its reply, failing on errors the server
+    // reports in the reply, such as a missing collection.
+    fn run_command_checked<T: DeserializeOwned>(&self, spec: bson::Document) -> Result<T> {
+        let primary = ReadPreference::new(ReadMode::Primary, None);
+        let mut reply = self.db.run_command(spec, Some(primary))?;
+
+        if let Some(Bson::String(msg)) = reply.remove("errmsg") {
+            return Err(OperationError(msg));
+        }
+
+        bson::from_bson(Bson::Document(reply)).map_err(DecoderError)
+    }
+
+    /// Permanently deletes the collection from the database, acknowledged according to
+    /// `write_concern` or the collection's write concern. If `ignore_not_found` is set, dropping
+    /// a collection that does not exist succeeds.
+    pub fn drop_with_write_concern(
+        &self,
+        write_concern: Option<WriteConcern>,
+        ignore_not_found: bool,
+    ) -> Result<()> {
+        let wc = write_concern.unwrap_or(self.write_concern);
+        let cmd = doc! {
+            "drop": self.name(),
+            "writeConcern": wc.to_bson(),
+        };
+        let mut result = self.db.command(cmd, CommandType::DropCollection, None)?;
+
+        let not_found = match result.get("code") {
+            Some(&Bson::I32(code)) => code == ErrorCode::NamespaceNotFound as i32,
+            // Servers before 3.2 report no code.
+            _ => result.get_str("errmsg") == Ok("ns not found"),
+        };
+
+        match result.remove("errmsg") {
+            Some(Bson::String(_)) if not_found && ignore_not_found => Ok(()),
+            Some(Bson::String(msg)) => Err(OperationError(msg)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Renames the collection within its database, returning a collection representation under
+    /// the new name with the same read and write controls. If `drop_target` is set, an existing
+    /// collection named `new_name` is dropped first; otherwise the rename fails.
+    pub fn rename(&self, new_name: &str, drop_target: bool) -> Result<Collection> {
+        let cmd = doc! {
+            "renameCollection": &self.namespace,
+            "to": format!("{}.{}", self.db.name, new_name),
+            "dropTarget": drop_target,
+            "writeConcern": self.write_concern.to_bson(),
+        };
+        let admin = self.db.client.db("admin");
+        let mut result = admin.command(cmd, CommandType::RenameCollection, None)?;
+
+        match result.remove("errmsg") {
+            Some(Bson::String(msg)) => Err(OperationError(msg)),
+            _ => Ok(Collection::new(
+                self.db.clone(),
+                new_name,
+                false,
+                Some(self.read_preference.clone()),
+                Some(self.write_concern),
+            )),
+        }
+    }
+
+    /// Runs an aggregation framework pipeline, given as a `Pipeline` built from `stage` functions
+    /// or as a `Vec` of stage documents.
     pub fn aggregate(
         &self,
-        pipeline: Vec<bson::Document>,
-        options: Option<AggregateOptions>,
+        pipeline: impl Into<Pipeline>,
+        options: impl Into<Option<AggregateOptions>>,
     ) -> Result<Cursor> {
-        let pipeline_map: Vec<_> = pipeline.into_iter().map(Bson::Document).collect();
-
+        let options = options.into();
         let mut spec = doc! {
             "aggregate": self.name(),
-            "pipeline": pipeline_map
+            "pipeline": pipeline.into()
         };
 
         let mut read_preference = self.read_preference.clone();
+        let mut timeout_ms = None;
 
         match options {
             Some(aggregate_options) => {
@@ -111,6 +246,7 @@ impl Collection {
                     read_preference = read_preference_option.clone();
                 }
 
+                timeout_ms = aggregate_options.timeout_ms;
                 spec = merge_options(spec, aggregate_options);
             }
             None => {
@@ -118,19 +254,229 @@ impl Collection {
             }
         };
 
-        self.db.command_cursor(
+        Cursor::command_cursor_with_timeout(
+            self.db.client.clone(),
+            &self.db.name,
             spec,
             CommandType::Aggregate,
             read_preference,
+            timeout_ms,
         )
     }
 
+    /// Rolls up the collection's measurements into buckets of time, as described by `spec`.
+    /// Requires MongoDB 5.0 or later.
+    pub fn rollup(&self, spec: &RollupSpec, options: Option<AggregateOptions>) -> Result<Cursor> {
+        self.aggregate(spec.pipeline()?, options)
+    }
+
+    /// Runs an aggregation pipeline ending in `$out` or `$merge`, which writes its results to a
+    /// collection rather than returning them.
+    pub fn aggregate_to_collection(
+        &self,
+        pipeline: impl Into<Pipeline>,
+        options: Option<AggregateToCollectionOptions>,
+    ) -> Result<()> {
+        self.aggregate_to_collection_with_progress(pipeline, options, |_| ())
+    }
+
+    /// Runs an aggregation pipeline ending in `$out` or `$merge`, calling `progress` with the
+    /// server's report on the aggregation at every `progress_interval_ms` while it runs.
+    ///
+    /// Progress is read from `currentOp`, which requires MongoDB 3.6 or later and, to see the
+    /// operations of other users, the `inprog` privilege. Reports are skipped while the
+    /// aggregation cannot be found or `currentOp` fails; the aggregation itself is unaffected.
+    pub fn aggregate_to_collection_with_progress<F>(
+        &self,
+        pipeline: impl Into<Pipeline>,
+        options: Option<AggregateToCollectionOptions>,
+        mut progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&AggregateProgress),
+    {
+        let pipeline = pipeline.into().into_documents();
+        let writes_output = match pipeline.last().and_then(|stage| stage.keys().next()) {
+            Some(key) => key == "$out" || key == "$merge",
+            None => false,
+        };
+
+        if !writes_output {
+            return Err(ArgumentError(String::from(
+                "The last stage of the pipeline must be $out or $merge.",
+            )));
+        }
+
+        let options = options.unwrap_or_default();
+        let interval = Duration::from_millis(options.progress_interval_ms.unwrap_or(1000));
+
+        // The comment identifies the aggregation in currentOp.
+        let comment = format!("aggregate_to_collection {}", object_id::generate().to_hex());
+        let pipeline_map: Vec<_> = pipeline.into_iter().map(Bson::Document).collect();
+        let spec = doc! {
+            "aggregate": self.name(),
+            "pipeline": pipeline_map,
+            "cursor": {},
+            "comment": comment.clone(),
+        };
+
+        let mut spec = merge_options(spec, options);
+
+        if !spec.contains_key("writeConcern") {
+            spec.insert("writeConcern", self.write_concern.to_bson());
+        }
+
+        let db = self.db.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let primary = ReadPreference::new(ReadMode::Primary, None);
+            let _ = sender.send(db.run_command(spec, Some(primary)));
+        });
+
+        let admin = self.db.client.db("admin");
+        let filter = doc! { "currentOp": 1, "command.comment": comment };
+
+        let mut reply = loop {
+            match receiver.recv_timeout(interval) {
+                Ok(result) => break result?,
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(OperationError(String::from(
+                        "The aggregation thread exited without a reply.",
+                    )))
+                }
+            }
+
+            let op = match admin.run_command(filter.clone(), None) {
+                Ok(mut current) => match current.remove("inprog") {
+                    Some(Bson::Array(ops)) => ops.into_iter().next(),
+                    _ => None,
+                },
+                Err(_) => None,
+            };
+
+            if let Some(Bson::Document(op)) = op {
+                progress(&AggregateProgress::from_current_op(op));
+            }
+        };
+
+        match reply.remove("errmsg") {
+            Some(Bson::String(msg)) => Err(OperationError(msg)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs a map-reduce over the collection with the JavaScript functions `map` and `reduce`.
+    ///
+    /// Inline results are returned in a cursor; otherwise the results are written to the
+    /// collection named in `options.out`, which is returned. Map-reduce is deprecated as of
+    /// MongoDB 5.0 in favor of aggregation pipelines.
+    pub fn map_reduce(
+        &self,
+        map: &str,
+        reduce: &str,
+        options: Option<MapReduceOptions>,
+    ) -> Result<MapReduceResult> {
+        let options = options.unwrap_or_default();
+        let out = options.out.clone().unwrap_or(MapReduceOutput::Inline);
+        let timeout_ms = options.timeout_ms;
+
+        let read_preference = match out {
+            MapReduceOutput::Inline => {
+                options
+                    .read_preference
+                    .clone()
+                    .unwrap_or_else(|| self.read_preference.clone())
+            }
+            MapReduceOutput::Collection { .. } => ReadPreference::new(ReadMode::Primary, None),
+        };
+
+        let spec = doc! {
+            "mapReduce": self.name(),
+            "map": Bson::JavaScriptCode(String::from(map)),
+            "reduce": Bson::JavaScriptCode(String::from(reduce)),
+        };
+
+        let mut spec = merge_options(spec, options);
+
+        if out != MapReduceOutput::Inline && !spec.contains_key("writeConcern") {
+            spec.insert("writeConcern", self.write_concern.to_bson());
+        }
+
+        // Sent as a query on `$cmd`, as `command_with_timeout` would, keeping the cursor to
+        // learn which server the inline results came from.
+        let find_options = FindOptions {
+            batch_size: Some(1),
+            limit: Some(1),
+            read_preference: Some(read_preference),
+            timeout_ms,
+            ..FindOptions::new()
+        };
+        let mut cursor = self.db.collection("$cmd").find_with_command_type(
+            Some(spec.clone()),
+            Some(find_options),
+            CommandType::RunCommand,
+        )?;
+        let address = cursor.address();
+        let mut reply = match cursor.next() {
+            Some(reply) => reply?,
+            None => {
+                return Err(OperationError(
+                    format!("Failed to execute command with spec {:?}.", spec),
+                ))
+            }
+        };
+
+        if let Some(Bson::String(msg)) = reply.remove("errmsg") {
+            return Err(OperationError(msg));
+        }
+
+        match out {
+            MapReduceOutput::Inline => {
+                let results = match reply.remove("results") {
+                    Some(Bson::Array(results)) => results,
+                    _ => {
+                        return Err(ResponseError(String::from(
+                            "No results received from inline map-reduce.",
+                        )))
+                    }
+                };
+
+                let docs = results
+                    .into_iter()
+                    .filter_map(|result| match result {
+                        Bson::Document(doc) => Some(doc),
+                        _ => None,
+                    })
+                    .collect();
+
+                Ok(MapReduceResult::Inline(Cursor::from_documents(
+                    self.db.client.clone(),
+                    self.namespace.clone(),
+                    address,
+                    docs,
+                    CommandType::RunCommand,
+                )))
+            }
+            MapReduceOutput::Collection { name, db, .. } => {
+                let db = match db {
+                    Some(db) => self.db.client.db(&db),
+                    None => self.db.clone(),
+                };
+
+                Ok(MapReduceResult::Collection(db.collection(&name)))
+            }
+        }
+    }
+
     /// Gets the number of documents matching the filter.
     pub fn count(
         &self,
-        filter: Option<bson::Document>,
-        options: Option<CountOptions>,
+        filter: impl IntoDocument,
+        options: impl Into<Option<CountOptions>>,
     ) -> Result<i64> {
+        let filter = Some(filter.into_document());
+        let options = options.into();
         let mut spec = doc! {
             "count": self.name()
         };
@@ -140,19 +486,22 @@ impl Collection {
         }
 
         let mut read_preference = self.read_preference.clone();
+        let mut timeout_ms = None;
 
         if let Some(count_options) = options {
             if let Some(ref read_preference_option) = count_options.read_preference {
                 read_preference = read_preference_option.clone();
             }
 
+            timeout_ms = count_options.timeout_ms;
             spec = merge_options(spec, count_options);
         }
 
-        let result = self.db.command(
+        let result = self.db.command_with_timeout(
             spec,
             CommandType::Count,
             Some(read_preference),
+            timeout_ms,
         )?;
         match result.get("n") {
             Some(&Bson::I32(n)) => Ok(n as i64),
@@ -163,13 +512,170 @@ impl Collection {
         }
     }
 
+    /// Explains how the server would run `find`, in the detail `verbosity` asks for.
+    pub fn explain_find(
+        &self,
+        filter: Option<bson::Document>,
+        options: Option<FindOptions>,
+        verbosity: ExplainVerbosity,
+    ) -> Result<ExplainSummary> {
+        let mut options = options.unwrap_or_default();
+        let read_preference = options
+            .read_preference
+            .clone()
+            .unwrap_or_else(|| self.read_preference.clone());
+        let timeout_ms = options.timeout_ms;
+
+        let mut spec = doc! {
+            "find": self.name(),
+            "filter": filter.unwrap_or_default(),
+        };
+
+        // A negative limit means a single batch of at most that many documents.
+        if let Some(limit) = options.limit {
+            if limit < 0 {
+                options.limit = Some(-limit);
+                spec.insert("singleBatch", true);
+            }
+        }
+
+        let spec = merge_options(spec, options);
+        self.explain(spec, verbosity, read_preference, timeout_ms)
+    }
+
+    /// Explains how the server would run `aggregate`, in the detail `verbosity` asks for.
+    /// Pipelines ending in `$out` or `$merge` write nothing when explained.
+    pub fn explain_aggregate(
+        &self,
+        pipeline: impl Into<Pipeline>,
+        options: Option<AggregateOptions>,
+        verbosity: ExplainVerbosity,
+    ) -> Result<ExplainSummary> {
+        let mut spec = doc! {
+            "aggregate": self.name(),
+            "pipeline": pipeline.into(),
+        };
+
+        let mut read_preference = self.read_preference.clone();
+        let mut timeout_ms = None;
+
+        match options {
+            Some(options) => {
+                if let Some(ref read_preference_option) = options.read_preference {
+                    read_preference = read_preference_option.clone();
+                }
+
+                timeout_ms = options.timeout_ms;
+                spec = merge_options(spec, options);
+            }
+            None => {
+                spec.insert("cursor", bson::Document::new());
+            }
+        }
+
+        self.explain(spec, verbosity, read_preference, timeout_ms)
+    }
+
+    /// Explains how the server would run `count`, in the detail `verbosity` asks for.
+    pub fn explain_count(
+        &self,
+        filter: Option<bson::Document>,
+        options: Option<CountOptions>,
+        verbosity: ExplainVerbosity,
+    ) -> Result<ExplainSummary> {
+        let mut spec = doc! { "count": self.name() };
+
+        if let Some(filter) = filter {
+            spec.insert("query", filter);
+        }
+
+        let mut read_preference = self.read_preference.clone();
+        let mut timeout_ms = None;
+
+        if let Some(options) = options {
+            if let Some(ref read_preference_option) = options.read_preference {
+                read_preference = read_preference_option.clone();
+            }
+
+            timeout_ms = options.timeout_ms;
+            spec = merge_options(spec, options);
+        }
+
+        self.explain(spec, verbosity, read_preference, timeout_ms)
+    }
+
+    /// Explains how the server would run an update of the first document, or all documents if
+    /// `multi` is set, that match `filter`, without applying it.
+    pub fn explain_update<U: Into<UpdateModifications>>(
+        &self,
+        filter: bson::Document,
+        update: U,
+        multi: bool,
+        verbosity: ExplainVerbosity,
+    ) -> Result<ExplainSummary> {
+        let update = update.into();
+        Collection::validate_update(&update)?;
+
+        let statement = doc! { "q": filter, "u": Bson::from(update), "multi": multi };
+        let spec = doc! {
+            "update": self.name(),
+            "updates": [statement],
+        };
+
+        let primary = ReadPreference::new(ReadMode::Primary, None);
+        self.explain(spec, verbosity, primary, None)
+    }
+
+    /// Explains how the server would run a deletion of the first document, or all documents if
+    /// `multi` is set, that match `filter`, without applying it.
+    pub fn explain_delete(
+        &self,
+        filter: bson::Document,
+        multi: bool,
+        verbosity: ExplainVerbosity,
+    ) -> Result<ExplainSummary> {
+        let statement = doc! { "q": filter, "limit": if multi { 0 } else { 1 } };
+        let spec = doc! {
+            "delete": self.name(),
+            "deletes": [statement],
+        };
+
+        let primary = ReadPreference::new(ReadMode::Primary, None);
+        self.explain(spec, verbosity, primary, None)
+    }
+
+    // Runs the command in `spec` as an explain command.
+    fn explain(
+        &self,
+        spec: bson::Document,
+        verbosity: ExplainVerbosity,
+        read_preference: ReadPreference,
+        timeout_ms: Option<i64>,
+    ) -> Result<ExplainSummary> {
+        let cmd = doc! {
+            "explain": spec,
+            "verbosity": verbosity.to_str(),
+        };
+
+        let reply = self.db.command_with_timeout(
+            cmd,
+            CommandType::RunCommand,
+            Some(read_preference),
+            timeout_ms,
+        )?;
+
+        Ok(ExplainSummary::from_document(reply))
+    }
+
     /// Finds the distinct values for a specified field across a single collection.
     pub fn distinct(
         &self,
         field_name: &str,
-        filter: Option<bson::Document>,
-        options: Option<DistinctOptions>,
+        filter: impl IntoDocument,
+        options: impl Into<Option<DistinctOptions>>,
     ) -> Result<Vec<Bson>> {
+        let filter = Some(filter.into_document());
+        let options = options.into();
         let mut spec = doc! {
             "distinct": self.name(),
             "key": field_name,
@@ -179,14 +685,17 @@ impl Collection {
             spec.insert("query", filter_doc);
         }
 
-        let read_preference = options.and_then(|o| o.read_preference).unwrap_or_else(|| {
+        let options = options.unwrap_or_default();
+        let read_preference = options.read_preference.clone().unwrap_or_else(|| {
             self.read_preference.clone()
         });
+        let timeout_ms = options.timeout_ms;
 
-        let result = self.db.command(
-            spec,
+        let result = self.db.command_with_timeout(
+            merge_options(spec, options),
             CommandType::Distinct,
             Some(read_preference),
+            timeout_ms,
         )?;
         match result.get("values") {
             Some(&Bson::Array(ref vals)) => Ok(vals.to_owned()),
@@ -196,32 +705,143 @@ impl Collection {
         }
     }
 
+    /// Starts a find to be built one option at a time. See the `query` module.
+    pub fn query(&self) -> Query<'_> {
+        Query::new(self)
+    }
+
     /// Returns a list of documents within the collection that match the filter.
     pub fn find(
         &self,
-        filter: Option<bson::Document>,
-        options: Option<FindOptions>,
+        filter: impl IntoDocument,
+        options: impl Into<Option<FindOptions>>,
     ) -> Result<Cursor> {
+        let filter = Some(filter.into_document());
+        let options = options.into();
         self.find_with_command_type(filter, options, CommandType::Find)
     }
 
+    /// Returns the documents within the collection that match the filter as a `Stream`, read
+    /// on a worker run by `executor`. See the `cursor_stream` module.
+    #[cfg(feature = "stream")]
+    pub fn find_stream<E: Executor>(
+        &self,
+        filter: impl IntoDocument,
+        options: Option<FindOptions>,
+        executor: E,
+    ) -> Result<CursorStream> {
+        self.find(filter, options).map(|cursor| cursor.into_stream(executor))
+    }
+
+    /// Returns the documents within the collection that match the filter, deserialized into
+    /// `T`.
+    pub fn find_as<T: DeserializeOwned>(
+        &self,
+        filter: impl IntoDocument,
+        options: impl Into<Option<FindOptions>>,
+    ) -> Result<TypedCursor<T>> {
+        self.find(filter, options).map(Cursor::deserialize)
+    }
+
+    /// Returns the documents within the collection that match the filter as the server encoded
+    /// them, to be read without decoding them in full. See the `raw` module.
+    pub fn find_raw(
+        &self,
+        filter: impl IntoDocument,
+        options: impl Into<Option<FindOptions>>,
+    ) -> Result<RawCursor> {
+        let filter = Some(filter.into_document());
+        let options = options.into();
+        let find = self.prepare_find(filter, options);
+        RawCursor::query(
+            self.db.client.clone(),
+            find.namespace,
+            find.flags,
+            find.query,
+            find.options,
+            CommandType::Find,
+            find.is_cmd_cursor,
+            find.read_preference,
+        )
+    }
+
     fn find_with_command_type(
         &self,
         filter: Option<bson::Document>,
         options: Option<FindOptions>,
         cmd_type: CommandType,
     ) -> Result<Cursor> {
-        let find_options = options.unwrap_or_default();
+        let find = self.prepare_find(filter, options);
+        Cursor::query(
+            self.db.client.clone(),
+            find.namespace,
+            find.flags,
+            find.query,
+            find.options,
+            cmd_type,
+            find.is_cmd_cursor,
+            find.read_preference,
+        )
+    }
+
+    // Builds the query that finds the matching documents, as a legacy query where the options
+    // allow it and as a `find` command otherwise.
+    fn prepare_find(&self, filter: Option<bson::Document>, options: Option<FindOptions>) -> Find {
+        let mut find_options = options.unwrap_or_default();
+
+        // Only the find command takes the read concern of a causally consistent session.
+        if let Some(ref session) = self.db.client.session {
+            find_options.read_concern = session.read_concern(find_options.read_concern);
+        }
+
+        if find_options.requires_find_command() {
+            return self.find_command(filter, find_options);
+        }
+
         let flags = OpQueryFlags::with_find_options(&find_options);
 
-        let doc = match find_options.sort {
-            Some(ref sort_opt) => {
-                doc! {
-                    "$query": filter.unwrap_or_default(),
-                    "$orderby": sort_opt.clone(),
-                }
-            }
-            None => filter.unwrap_or_default(),
+        // OP_QUERY takes query modifiers alongside a wrapped `$query` document.
+        let mut modifiers = bson::Document::new();
+
+        if let Some(ref sort_opt) = find_options.sort {
+            modifiers.insert("$orderby", sort_opt.clone());
+        }
+
+        if let Some(max_time_ms) = find_options.max_time_ms {
+            modifiers.insert("$maxTimeMS", max_time_ms);
+        }
+
+        if let Some(ref hint_doc) = find_options.hint_doc {
+            modifiers.insert("$hint", hint_doc.clone());
+        } else if let Some(ref hint) = find_options.hint {
+            modifiers.insert("$hint", hint.clone());
+        }
+
+        if let Some(ref comment) = find_options.comment {
+            modifiers.insert("$comment", comment.clone());
+        }
+
+        if let Some(ref max) = find_options.max {
+            modifiers.insert("$max", max.clone());
+        }
+
+        if let Some(ref min) = find_options.min {
+            modifiers.insert("$min", min.clone());
+        }
+
+        if let Some(return_key) = find_options.return_key {
+            modifiers.insert("$returnKey", return_key);
+        }
+
+        if let Some(show_record_id) = find_options.show_record_id {
+            modifiers.insert("$showDiskLoc", show_record_id);
+        }
+
+        let doc = if modifiers.is_empty() {
+            filter.unwrap_or_default()
+        } else {
+            let query = doc! { "$query": filter.unwrap_or_default() };
+            merge_options(query, modifiers)
         };
 
         let read_preference = match find_options.read_preference {
@@ -229,27 +849,192 @@ impl Collection {
             None => self.read_preference.clone(),
         };
 
-        Cursor::query(
-            self.db.client.clone(),
-            self.namespace.to_owned(),
+        Find {
+            namespace: self.namespace.to_owned(),
             flags,
-            doc,
-            find_options,
-            cmd_type,
-            false,
+            query: doc,
+            options: find_options,
+            is_cmd_cursor: false,
             read_preference,
-        )
+        }
+    }
+
+    // Builds a `find` command, for options that OP_QUERY cannot express.
+    fn find_command(&self, filter: Option<bson::Document>, mut find_options: FindOptions) -> Find {
+        let read_preference = match find_options.read_preference {
+            Some(ref read_preference_option) => read_preference_option.clone(),
+            None => self.read_preference.clone(),
+        };
+
+        // The cursor stops after `limit` documents itself; the command's own options are
+        // serialized into the spec rather than the OP_QUERY message.
+        let mut cursor_options = FindOptions::new();
+        cursor_options.batch_size = Some(1);
+        cursor_options.limit = find_options.limit.map(i64::abs);
+        cursor_options.timeout_ms = find_options.timeout_ms;
+        cursor_options.get_more_batch_size = find_options.get_more_batch_size;
+        cursor_options.adaptive_batch_size = find_options.adaptive_batch_size;
+
+        let mut spec = doc! {
+            "find": self.name(),
+            "filter": filter.unwrap_or_default(),
+        };
+
+        // A negative limit means a single batch of at most that many documents.
+        if let Some(limit) = find_options.limit {
+            if limit < 0 {
+                find_options.limit = Some(-limit);
+                spec.insert("singleBatch", true);
+            }
+        }
+
+        Find {
+            namespace: format!("{}.$cmd", self.db.name),
+            flags: OpQueryFlags::empty(),
+            query: merge_options(spec, find_options),
+            options: cursor_options,
+            is_cmd_cursor: true,
+            read_preference,
+        }
+    }
+
+    /// Splits the collection into up to `num_cursors` ranges of `_id` and returns a cursor over
+    /// each, so that a large collection can be read in parallel, such as from a rayon pool.
+    ///
+    /// The ranges are chosen from a random sample of `_id`s, so they hold roughly equal numbers
+    /// of documents; fewer cursors are returned if the sample has too few distinct `_id`s. Since
+    /// range queries only match `_id`s of the same type as their bounds, the first cursor also
+    /// reads every document whose `_id` is of another type, and a collection whose sampled
+    /// `_id`s are not all of one type is read by a single cursor. Each cursor is independent and
+    /// can be moved to its own thread.
+    ///
+    /// The options apply to every cursor, so a limit or skip is rejected.
+    pub fn parallel_scan(
+        &self,
+        num_cursors: usize,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<Cursor>> {
+        if num_cursors == 0 {
+            return Err(ArgumentError(String::from("A parallel scan needs at least one cursor.")));
+        }
+
+        if let Some(ref options) = options {
+            if options.limit.is_some() || options.skip.is_some() {
+                return Err(ArgumentError(String::from(
+                    "A parallel scan does not support a limit or skip.",
+                )));
+            }
+        }
+
+        let bounds = self.scan_bounds(num_cursors)?;
+        let mut cursors = Vec::with_capacity(bounds.len() + 1);
+        let mut lower = None;
+
+        for upper in bounds.into_iter().map(Some).chain(iter::once(None)) {
+            let range = match (lower, upper.clone()) {
+                (Some(lower), Some(upper)) => Some(doc! { "$gte": lower, "$lt": upper }),
+                (Some(lower), None) => Some(doc! { "$gte": lower }),
+                // `$not` also matches the `_id`s of other types than the bounds.
+                (None, Some(upper)) => Some(doc! { "$not": { "$gte": upper } }),
+                (None, None) => None,
+            };
+
+            let filter = range.map(|range| doc! { "_id": range });
+            cursors.push(self.find(filter, options.clone())?);
+            lower = upper;
+        }
+
+        Ok(cursors)
+    }
+
+    // Picks up to `n - 1` `_id`s that split the collection into ranges of about equal size.
+    fn scan_bounds(&self, n: usize) -> Result<Vec<Bson>> {
+        if n == 1 {
+            return Ok(Vec::new());
+        }
+
+        let pipeline = vec![
+            doc! { "$sample": { "size": (n * SCAN_SAMPLES_PER_CURSOR) as i64 } },
+            doc! { "$project": { "_id": 1 } },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+
+        let mut ids = Vec::new();
+        for doc in self.aggregate(pipeline, None)? {
+            if let Some(id) = doc?.get("_id") {
+                ids.push(id.clone());
+            }
+        }
+
+        let element_type = ids.first().map(Bson::element_type);
+        if ids.is_empty() || ids.iter().any(|id| Some(id.element_type()) != element_type) {
+            return Ok(Vec::new());
+        }
+
+        let mut bounds: Vec<_> = (1..n)
+            .map(|i| ids[i * ids.len() / n].clone())
+            .filter(|id| Some(id) != ids.first())
+            .collect();
+
+        bounds.dedup();
+        Ok(bounds)
+    }
+
+    /// Writes every document in the collection to `writer`, reading them in batches, and
+    /// returns how many were written. The writer is flushed at the end; wrap files in a
+    /// `BufWriter`.
+    pub fn export_to_writer<W: Write>(&self, writer: W, format: ExportFormat) -> Result<i64> {
+        export::export(self, writer, format)
+    }
+
+    /// Inserts every document read from `reader`, in the format `export_to_writer` writes, and
+    /// returns how many were inserted. Documents are inserted in batches as they are read, so
+    /// at most one batch is held in memory.
+    pub fn import_from_reader<R: Read>(&self, reader: R, format: ExportFormat) -> Result<i64> {
+        export::import(self, reader, format)
+    }
+
+    /// Returns a blocking iterator over the documents of a capped collection that match the
+    /// filter, which waits for new documents as they are inserted.
+    ///
+    /// The cursor is reopened after the last document returned if the server closes it, or
+    /// reports `CappedPositionLost` because the collection wrapped around past the cursor's
+    /// position; documents overwritten before they were read are skipped.
+    pub fn tail(&self, filter: Option<bson::Document>, options: Option<TailOptions>) -> Tail {
+        let coll = self.db.collection_with_prefs(
+            &self.name(),
+            false,
+            Some(self.read_preference.clone()),
+            Some(self.write_concern),
+        );
+
+        Tail::new(coll, filter.unwrap_or_default(), options.unwrap_or_default())
     }
 
     /// Returns the first document within the collection that matches the filter, or None.
     pub fn find_one(
         &self,
-        filter: Option<bson::Document>,
-        options: Option<FindOptions>,
+        filter: impl IntoDocument,
+        options: impl Into<Option<FindOptions>>,
     ) -> Result<Option<bson::Document>> {
+        let filter = Some(filter.into_document());
+        let options = options.into();
         self.find_one_with_command_type(filter, options, CommandType::Find)
     }
 
+    /// Returns the first document within the collection that matches the filter, deserialized
+    /// into `T`, or None.
+    pub fn find_one_as<T: DeserializeOwned>(
+        &self,
+        filter: impl IntoDocument,
+        options: impl Into<Option<FindOptions>>,
+    ) -> Result<Option<T>> {
+        match self.find_one(filter, options)? {
+            Some(doc) => bson::from_bson(Bson::Document(doc)).map(Some).map_err(DecoderError),
+            None => Ok(None),
+        }
+    }
+
     pub fn find_one_with_command_type(
         &self,
         filter: Option<bson::Document>,
@@ -277,8 +1062,8 @@ impl Collection {
         &self,
         filter: bson::Document,
         options: bson::Document,
-        _max_time_ms: Option<i64>,
         write_concern: Option<WriteConcern>,
+        timeout_ms: Option<i64>,
         cmd_type: CommandType,
     ) -> Result<Option<bson::Document>> {
         let mut cmd = doc! {
@@ -288,7 +1073,9 @@ impl Collection {
 
         cmd = merge_options(cmd, options);
 
-        let res = self.db.command(cmd, cmd_type, None)?;
+        let res = self.db
+            .command_with_timeout(cmd, cmd_type, None, timeout_ms)
+            .map_err(Collection::classify_find_and_modify_error)?;
         let wc = write_concern.unwrap_or_else(|| self.write_concern.clone());
         WriteException::validate_write_result(res.clone(), wc)?;
 
@@ -300,16 +1087,33 @@ impl Collection {
         Ok(doc)
     }
 
+    // Surfaces the failures that optimistic concurrency loops and transactions branch on as
+    // their own variants.
+    fn classify_find_and_modify_error(err: Error) -> Error {
+        match err {
+            Error::ServerError(err) => {
+                if err.code == ErrorCode::WriteConflict as i32 {
+                    Error::WriteConflict(err)
+                } else if err.code == ErrorCode::DocumentValidationFailure as i32 {
+                    Error::DocumentValidationFailure(err)
+                } else {
+                    Error::ServerError(err)
+                }
+            }
+            err => err,
+        }
+    }
+
     /// Finds a single document and deletes it, returning the original.
     pub fn find_one_and_delete(
         &self,
-        filter: bson::Document,
-        options: Option<FindOneAndDeleteOptions>,
+        filter: impl IntoDocument,
+        options: impl Into<Option<FindOneAndDeleteOptions>>,
     ) -> Result<Option<bson::Document>> {
-        let (max_time_ms, write_concern) = match options {
-            Some(ref opts) => (opts.max_time_ms, opts.write_concern.clone()),
-            None => (None, None),
-        };
+        let filter = filter.into_document();
+        let options = options.into();
+        let write_concern = options.as_ref().and_then(|opts| opts.write_concern.clone());
+        let timeout_ms = options.as_ref().and_then(|opts| opts.timeout_ms);
 
         let mut options_doc = doc! { "remove": true };
 
@@ -320,26 +1124,31 @@ impl Collection {
         self.find_and_modify(
             filter,
             options_doc,
-            max_time_ms,
             write_concern,
+            timeout_ms,
             CommandType::FindOneAndDelete,
         )
     }
 
     /// Finds a single document and replaces it, returning either the original
     /// or replaced document.
+    ///
+    /// Conflicts with concurrent writes fail with `Error::WriteConflict`, and documents the
+    /// collection's validator rejects with `Error::DocumentValidationFailure`;
+    /// `Error::retry_hint` tells whether to try again.
     pub fn find_one_and_replace(
         &self,
-        filter: bson::Document,
-        replacement: bson::Document,
-        options: Option<FindOneAndUpdateOptions>,
+        filter: impl IntoDocument,
+        replacement: impl IntoDocument,
+        options: impl Into<Option<FindOneAndUpdateOptions>>,
     ) -> Result<Option<bson::Document>> {
+        let filter = filter.into_document();
+        let replacement = replacement.into_document();
+        let options = options.into();
         Collection::validate_replace(&replacement)?;
 
-        let (max_time_ms, write_concern) = match options {
-            Some(ref opts) => (opts.max_time_ms, opts.write_concern.clone()),
-            None => (None, None),
-        };
+        let write_concern = options.as_ref().and_then(|opts| opts.write_concern.clone());
+        let timeout_ms = options.as_ref().and_then(|opts| opts.timeout_ms);
 
         let mut options_doc = doc! { "update": replacement };
 
@@ -350,28 +1159,33 @@ impl Collection {
         self.find_and_modify(
             filter,
             options_doc,
-            max_time_ms,
             write_concern,
+            timeout_ms,
             CommandType::FindOneAndReplace,
         )
     }
 
     /// Finds a single document and updates it, returning either the original
     /// or updated document.
-    pub fn find_one_and_update(
+    ///
+    /// Conflicts with concurrent writes fail with `Error::WriteConflict`, and documents the
+    /// collection's validator rejects with `Error::DocumentValidationFailure`;
+    /// `Error::retry_hint` tells whether to try again.
+    pub fn find_one_and_update<U: Into<UpdateModifications>>(
         &self,
-        filter: bson::Document,
-        update: bson::Document,
-        options: Option<FindOneAndUpdateOptions>,
+        filter: impl IntoDocument,
+        update: U,
+        options: impl Into<Option<FindOneAndUpdateOptions>>,
     ) -> Result<Option<bson::Document>> {
+        let filter = filter.into_document();
+        let options = options.into();
+        let update = update.into();
         Collection::validate_update(&update)?;
 
-        let (max_time_ms, write_concern) = match options {
-            Some(ref opts) => (opts.max_time_ms, opts.write_concern.clone()),
-            None => (None, None),
-        };
+        let write_concern = options.as_ref().and_then(|opts| opts.write_concern.clone());
+        let timeout_ms = options.as_ref().and_then(|opts| opts.timeout_ms);
 
-        let mut options_doc = doc! { "update": update };
+        let mut options_doc = doc! { "update": Bson::from(update) };
 
         if let Some(find_one_and_update_options) = options {
             options_doc = merge_options(options_doc, find_one_and_update_options);
@@ -380,8 +1194,8 @@ impl Collection {
         self.find_and_modify(
             filter,
             options_doc,
-            max_time_ms,
             write_concern,
+            timeout_ms,
             CommandType::FindOneAndUpdate,
         )
     }
@@ -476,9 +1290,10 @@ impl Collection {
         documents: Vec<bson::Document>,
         start_index: i64,
         ordered: bool,
+        timeout_ms: Option<i64>,
         result: &mut BulkWriteResult,
         exception: &mut BulkWriteException,
-    ) -> bool {
+    ) -> (bool, Option<bson::Document>) {
         let models = documents
             .iter()
             .cloned()
@@ -487,16 +1302,26 @@ impl Collection {
 
         let options = Some(InsertManyOptions {
             ordered: Some(ordered),
-            ..Default::default()
+            write_concern: Some(self.write_concern),
+            timeout_ms,
+            ..InsertManyOptions::new()
         });
 
-        match self.insert_many(documents, options) {
-            Ok(insert_result) => {
-                result.process_insert_many_result(insert_result, models, start_index, exception)
+        // Batches report write concern errors in the result, since they were still applied.
+        let policy = WriteConcernErrorPolicy::Report;
+        match self.insert_many_with_reply(documents, options, policy) {
+            Ok((insert_result, reply)) => {
+                let ok = result.process_insert_many_result(
+                    insert_result,
+                    models,
+                    start_index,
+                    exception,
+                );
+                (ok, Some(reply))
             }
             Err(_) => {
                 exception.add_unproccessed_models(models);
-                false
+                (false, None)
             }
         }
     }
@@ -505,25 +1330,28 @@ impl Collection {
         &self,
         models: Vec<DeleteModel>,
         ordered: bool,
+        timeout_ms: Option<i64>,
         result: &mut BulkWriteResult,
         exception: &mut BulkWriteException,
-    ) -> bool {
-        let original_models = models
-            .iter()
-            .map(|model| if model.multi {
-                WriteModel::DeleteMany { filter: model.filter.clone() }
-            } else {
-                WriteModel::DeleteOne { filter: model.filter.clone() }
-            })
-            .collect();
-
-        match self.bulk_delete(models, ordered, None, CommandType::DeleteMany) {
-            Ok(bulk_delete_result) => {
-                result.process_bulk_delete_result(bulk_delete_result, original_models, exception)
+    ) -> (bool, Option<bson::Document>) {
+        let original_models = models.iter().cloned().map(WriteModel::from).collect();
+
+        let wc = Some(self.write_concern);
+        let options = bson::Document::new();
+        let policy = WriteConcernErrorPolicy::Report;
+        let cmd_type = CommandType::DeleteMany;
+        match self.bulk_delete(models, ordered, options, wc, policy, timeout_ms, cmd_type) {
+            Ok((bulk_delete_result, reply)) => {
+                let ok = result.process_bulk_delete_result(
+                    bulk_delete_result,
+                    original_models,
+                    exception,
+                );
+                (ok, Some(reply))
             }
             Err(_) => {
                 exception.add_unproccessed_models(original_models);
-                false
+                (false, None)
             }
         }
     }
@@ -533,63 +1361,80 @@ impl Collection {
         models: Vec<UpdateModel>,
         start_index: i64,
         ordered: bool,
+        timeout_ms: Option<i64>,
         result: &mut BulkWriteResult,
         exception: &mut BulkWriteException,
-    ) -> bool {
-        let original_models = models
-            .iter()
-            .map(|model| if model.multi {
-                WriteModel::UpdateMany {
-                    filter: model.filter.clone(),
-                    update: model.update.clone(),
-                    upsert: model.upsert.clone(),
-                }
-            } else {
-                WriteModel::UpdateOne {
-                    filter: model.filter.clone(),
-                    update: model.update.clone(),
-                    upsert: model.upsert.clone(),
-                }
-            })
-            .collect();
-
-        match self.bulk_update(models, ordered, None, CommandType::UpdateMany) {
-            Ok(bulk_update_result) => {
-                result.process_bulk_update_result(
+    ) -> (bool, Option<bson::Document>) {
+        let original_models = models.iter().cloned().map(WriteModel::from).collect();
+
+        let wc = Some(self.write_concern);
+        let policy = WriteConcernErrorPolicy::Report;
+        let cmd_type = CommandType::UpdateMany;
+        match self.bulk_update(models, ordered, wc, policy, timeout_ms, cmd_type) {
+            Ok((bulk_update_result, reply)) => {
+                let ok = result.process_bulk_update_result(
                     bulk_update_result,
                     original_models,
                     start_index,
                     exception,
-                )
+                );
+                (ok, Some(reply))
             }
             Err(_) => {
                 exception.add_unproccessed_models(original_models);
-                false
+                (false, None)
             }
         }
     }
 
+    // Executes a batch, returning whether it succeeded and the server's reply, if any.
     fn execute_batch(
         &self,
         batch: Batch,
         start_index: i64,
         ordered: bool,
+        timeout_ms: Option<i64>,
         result: &mut BulkWriteResult,
         exception: &mut BulkWriteException,
-    ) -> bool {
+    ) -> (bool, Option<bson::Document>) {
         match batch {
             Batch::Insert(docs) => {
-                self.execute_insert_batch(docs, start_index, ordered, result, exception)
+                self.execute_insert_batch(docs, start_index, ordered, timeout_ms, result, exception)
+            }
+            Batch::Delete(models) => {
+                self.execute_delete_batch(models, ordered, timeout_ms, result, exception)
             }
-            Batch::Delete(models) => self.execute_delete_batch(models, ordered, result, exception),
             Batch::Update(models) => {
-                self.execute_update_batch(models, start_index, ordered, result, exception)
+                self.execute_update_batch(models, start_index, ordered, timeout_ms, result, exception)
             }
         }
     }
 
     /// Sends a batch of writes to the server at the same time.
     pub fn bulk_write(&self, requests: Vec<WriteModel>, ordered: bool) -> BulkWriteResult {
+        let options = BulkWriteOptions {
+            ordered: Some(ordered),
+            ..BulkWriteOptions::new()
+        };
+        self.bulk_write_with_options(requests, Some(options))
+    }
+
+    /// Sends a batch of writes to the server at the same time, split into as many commands as
+    /// the server's limits require.
+    ///
+    /// Write concern errors are reported in the result's exception. If the client's
+    /// `write_concern_error_policy` is `Fail`, no further commands are sent after one hits a
+    /// write concern error.
+    pub fn bulk_write_with_options(
+        &self,
+        requests: Vec<WriteModel>,
+        options: Option<BulkWriteOptions>,
+    ) -> BulkWriteResult {
+        let _operation = Operation::start();
+        let options = options.unwrap_or_default();
+        let ordered = options.ordered.unwrap_or(true);
+        let verbose = options.verbose_results.unwrap_or(false);
+
         let batches = if ordered {
             Collection::get_ordered_batches(VecDeque::from_iter(requests.into_iter()))
         } else {
@@ -598,25 +1443,72 @@ impl Collection {
 
         let mut result = BulkWriteResult::new();
         let mut exception = BulkWriteException::new(Vec::new(), Vec::new(), Vec::new(), None);
+        let mut batch_replies = Vec::new();
 
         let mut start_index = 0;
+        let clock = self.db.client.clock.clone();
+        let deadline = Deadline::after_ms_on(clock.clone(), options.timeout_ms);
+        let fail_on_write_concern_error =
+            self.db.client.write_concern_error_policy == WriteConcernErrorPolicy::Fail;
 
         for batch in batches {
             let length = batch.len();
-            let success =
-                self.execute_batch(batch, start_index, ordered, &mut result, &mut exception);
+
+            // Each batch has what is left of the bulk write's timeout. Once that has run out,
+            // the remaining batches are not sent.
+            let timeout_ms = match deadline.remaining_ms("bulk write") {
+                Ok(timeout_ms) => timeout_ms,
+                Err(_) => {
+                    exception.add_unproccessed_models(batch.into_models());
+                    if ordered {
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            let start = clock.now();
+            let (success, reply) = self.execute_batch(
+                batch,
+                start_index,
+                ordered,
+                timeout_ms,
+                &mut result,
+                &mut exception,
+            );
+
+            if verbose {
+                batch_replies.push(BatchReply {
+                    start_index,
+                    len: length,
+                    reply,
+                    duration: clock.now() - start,
+                });
+            }
 
             if !success && ordered {
                 break;
             }
 
+            // Every batch sent was applied, but none after a write concern error are sent when
+            // the client fails on them.
+            if exception.write_concern_error.is_some() && fail_on_write_concern_error {
+                break;
+            }
+
             start_index += length;
         }
 
-        if !exception.unprocessed_requests.is_empty() {
+        if !exception.unprocessed_requests.is_empty() || exception.write_concern_error.is_some() {
             result.bulk_write_exception = Some(exception);
         }
 
+        if verbose {
+            result.batch_replies = Some(batch_replies);
+        }
+
+        result.acknowledged = self.write_concern.is_acknowledged();
+
         result
     }
 
@@ -626,9 +1518,10 @@ impl Collection {
         docs: Vec<bson::Document>,
         options: Option<InsertManyOptions>,
         write_concern: Option<WriteConcern>,
+        policy: WriteConcernErrorPolicy,
         cmd_type: CommandType,
-    ) -> Result<(Vec<Bson>, Option<BulkWriteException>)> {
-
+    ) -> Result<(Vec<Bson>, Option<BulkWriteException>, bson::Document)> {
+        let _operation = Operation::start();
         let wc = write_concern.unwrap_or_else(|| self.write_concern.clone());
         let mut converted_docs = Vec::with_capacity(docs.len());
         let mut ids = Vec::with_capacity(docs.len());
@@ -637,7 +1530,7 @@ impl Collection {
             let id = match doc.get("_id").cloned() {
                 Some(id) => id,
                 None => {
-                    let id = oid::ObjectId::new()?;
+                    let id = object_id::generate();
                     doc.insert("_id", id.clone());
                     Bson::ObjectId(id)
                 },
@@ -651,41 +1544,89 @@ impl Collection {
             "documents": converted_docs
         };
 
+        let timeout_ms = options.as_ref().and_then(|opts| opts.timeout_ms);
         if let Some(insert_options) = options {
             cmd = merge_options(cmd, insert_options);
         }
 
-        let result = self.db.command(cmd, cmd_type, None)?;
+        // Unacknowledged writes have no reply; an empty one stands in for it.
+        if !wc.is_acknowledged() {
+            cmd.insert("writeConcern", wc.to_bson());
+            self.send_unacknowledged(cmd, cmd_type, timeout_ms)?;
+            return Ok((ids, None, bson::Document::new()));
+        }
 
-        // Intercept bulk write exceptions and insert into the result
-        let exception_res = BulkWriteException::validate_bulk_write_result(result.clone(), wc);
-        let exception = match exception_res {
-            Ok(()) => None,
-            Err(BulkWriteError(err)) => Some(err),
-            Err(e) => return Err(e),
-        };
+        let result = self.db.command_with_timeout(cmd, cmd_type, None, timeout_ms)?;
+        let exception = Collection::intercept_write_exception(result.clone(), wc, policy)?;
 
-        Ok((ids, exception))
+        Ok((ids, exception, result))
+    }
+
+    // Sends a write command without waiting for a reply, for writes with a `w: 0` write concern.
+    fn send_unacknowledged(
+        &self,
+        cmd: bson::Document,
+        cmd_type: CommandType,
+        timeout_ms: Option<i64>,
+    ) -> Result<()> {
+        cursor::send_unacknowledged(&self.db.client, &self.db.name, cmd, cmd_type, timeout_ms)
+    }
+
+    // Separates write exceptions from other failures so that they can be returned in the
+    // result, unless `policy` asks for write concern errors to fail the operation.
+    fn intercept_write_exception(
+        result: bson::Document,
+        write_concern: WriteConcern,
+        policy: WriteConcernErrorPolicy,
+    ) -> Result<Option<BulkWriteException>> {
+        match BulkWriteException::validate_bulk_write_result(result, write_concern) {
+            Ok(()) => Ok(None),
+            Err(BulkWriteError(err)) => {
+                if policy == WriteConcernErrorPolicy::Fail && err.write_concern_error.is_some() {
+                    Err(BulkWriteError(err))
+                } else {
+                    Ok(Some(err))
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Reports a failed single write as a write error rather than a bulk write error.
+    fn downgrade_bulk_error(err: Error) -> Error {
+        match err {
+            BulkWriteError(exception) => {
+                Error::WriteError(WriteException::with_bulk_exception(exception))
+            }
+            err => err,
+        }
     }
 
     /// Inserts the provided document. If the document is missing an identifier,
     /// the driver should generate one.
     pub fn insert_one(
         &self,
-        doc: bson::Document,
-        write_concern: Option<WriteConcern>,
+        doc: impl IntoDocument,
+        write_concern: impl Into<Option<WriteConcern>>,
     ) -> Result<InsertOneResult> {
+        let doc = doc.into_document();
+        let write_concern = write_concern.into();
+        let acknowledged = write_concern
+            .as_ref()
+            .unwrap_or(&self.write_concern)
+            .is_acknowledged();
         let options = InsertManyOptions {
             write_concern: write_concern.clone(),
             ..Default::default()
         };
 
-        let (ids, bulk_exception) = self.insert(
+        let (ids, bulk_exception, _) = self.insert(
             vec![doc],
             Some(options),
             write_concern,
+            self.db.client.write_concern_error_policy,
             CommandType::InsertOne,
-        )?;
+        ).map_err(Collection::downgrade_bulk_error)?;
 
         if ids.is_empty() {
             return Err(OperationError(
@@ -709,7 +1650,9 @@ impl Collection {
             None => Some(ids[0].to_owned()),
         };
 
-        Ok(InsertOneResult::new(id, exception))
+        let mut result = InsertOneResult::new(id, exception);
+        result.acknowledged = acknowledged;
+        Ok(result)
     }
 
     /// Inserts the provided documents. If any documents are missing an identifier,
@@ -717,17 +1660,34 @@ impl Collection {
     pub fn insert_many(
         &self,
         docs: Vec<bson::Document>,
-        options: Option<InsertManyOptions>,
+        options: impl Into<Option<InsertManyOptions>>,
     ) -> Result<InsertManyResult> {
+        let options = options.into();
+        let policy = self.db.client.write_concern_error_policy;
+        self.insert_many_with_reply(docs, options, policy).map(|(result, _)| result)
+    }
+
+    // Inserts documents, returning the server's reply along with the result.
+    fn insert_many_with_reply(
+        &self,
+        docs: Vec<bson::Document>,
+        options: Option<InsertManyOptions>,
+        policy: WriteConcernErrorPolicy,
+    ) -> Result<(InsertManyResult, bson::Document)> {
         let write_concern = options.as_ref().map_or(
             None,
             |opts| opts.write_concern.clone(),
         );
+        let acknowledged = write_concern
+            .as_ref()
+            .unwrap_or(&self.write_concern)
+            .is_acknowledged();
 
-        let (ids, exception) = self.insert(
+        let (ids, exception, reply) = self.insert(
             docs,
             options,
             write_concern,
+            policy,
             CommandType::InsertMany,
         )?;
 
@@ -741,7 +1701,48 @@ impl Collection {
             }
         }
 
-        Ok(InsertManyResult::new(Some(map), exception))
+        let mut result = InsertManyResult::new(Some(map), exception);
+        result.acknowledged = acknowledged;
+        Ok((result, reply))
+    }
+
+    /// Serializes a value into a document and inserts it.
+    ///
+    /// The value must serialize to a BSON document. If it has no `_id` field, one is generated
+    /// and returned in the result.
+    pub fn insert_one_as<T: Serialize>(
+        &self,
+        value: &T,
+        write_concern: Option<WriteConcern>,
+    ) -> Result<InsertOneResult> {
+        self.insert_one(Collection::to_document(value)?, write_concern)
+    }
+
+    /// Serializes values into documents and inserts them.
+    ///
+    /// Each value must serialize to a BSON document. Values without an `_id` field have one
+    /// generated, which is returned in the result.
+    pub fn insert_many_as<T: Serialize>(
+        &self,
+        values: &[T],
+        options: Option<InsertManyOptions>,
+    ) -> Result<InsertManyResult> {
+        let docs = values
+            .iter()
+            .map(Collection::to_document)
+            .collect::<Result<Vec<_>>>()?;
+
+        self.insert_many(docs, options)
+    }
+
+    fn to_document<T: Serialize>(value: &T) -> Result<bson::Document> {
+        match bson::to_bson(value).map_err(EncoderError)? {
+            Bson::Document(doc) => Ok(doc),
+            other => Err(ArgumentError(format!(
+                "Expected a value that serializes to a document, but got {}.",
+                other
+            ))),
+        }
     }
 
     // Sends a batch of delete ops to the server at once.
@@ -749,9 +1750,12 @@ impl Collection {
         &self,
         models: Vec<DeleteModel>,
         ordered: bool,
+        options: bson::Document,
         write_concern: Option<WriteConcern>,
+        policy: WriteConcernErrorPolicy,
+        timeout_ms: Option<i64>,
         cmd_type: CommandType,
-    ) -> Result<BulkDeleteResult> {
+    ) -> Result<(BulkDeleteResult, bson::Document)> {
 
         let wc = write_concern.unwrap_or_else(|| self.write_concern.clone());
         let deletes: Vec<_> = models
@@ -768,25 +1772,26 @@ impl Collection {
             "ordered": ordered,
             "writeConcern": wc.to_bson(),
         };
-        let result = self.db.command(cmd, cmd_type, None)?;
-
-        // Intercept write exceptions and insert into the result
-        let exception_res = BulkWriteException::validate_bulk_write_result(result.clone(), wc);
-        let exception = match exception_res {
-            Ok(()) => None,
-            Err(BulkWriteError(err)) => Some(err),
-            Err(e) => return Err(e),
-        };
+        let cmd = merge_options(cmd, options);
+
+        if !wc.is_acknowledged() {
+            self.send_unacknowledged(cmd, cmd_type, timeout_ms)?;
+            return Ok((BulkDeleteResult::unacknowledged(), bson::Document::new()));
+        }
+
+        let result = self.db.command_with_timeout(cmd, cmd_type, None, timeout_ms)?;
 
-        Ok(BulkDeleteResult::new(result, exception))
+        let exception = Collection::intercept_write_exception(result.clone(), wc, policy)?;
+
+        Ok((BulkDeleteResult::new(result.clone(), exception), result))
     }
 
     // Internal deletion helper function.
-    fn delete(
+    fn delete_documents(
         &self,
         filter: bson::Document,
         multi: bool,
-        write_concern: Option<WriteConcern>,
+        options: DeleteOptions,
     ) -> Result<DeleteResult> {
         let cmd_type = if multi {
             CommandType::DeleteMany
@@ -794,32 +1799,72 @@ impl Collection {
             CommandType::DeleteOne
         };
 
+        let write_concern = options.write_concern;
+        let timeout_ms = options.timeout_ms;
+
         self.bulk_delete(
             vec![DeleteModel::new(filter, multi)],
             true,
+            bson::Document::from(options),
             write_concern,
+            self.db.client.write_concern_error_policy,
+            timeout_ms,
             cmd_type,
-        ).map(
-            DeleteResult::with_bulk_result
-        )
+        ).map(|(result, _)| DeleteResult::with_bulk_result(result))
+        .map_err(Collection::downgrade_bulk_error)
+    }
+
+    /// Starts a delete to be built one option at a time. See the `query` module.
+    pub fn delete(&self) -> Delete<'_> {
+        Delete::new(self)
     }
 
     /// Deletes a single document.
     pub fn delete_one(
         &self,
-        filter: bson::Document,
-        write_concern: Option<WriteConcern>,
+        filter: impl IntoDocument,
+        write_concern: impl Into<Option<WriteConcern>>,
+    ) -> Result<DeleteResult> {
+        let options = DeleteOptions {
+            write_concern: write_concern.into(),
+            ..DeleteOptions::new()
+        };
+        self.delete_one_with_options(filter, options)
+    }
+
+    /// Deletes a single document, with options such as a comment or variables for the filter.
+    pub fn delete_one_with_options(
+        &self,
+        filter: impl IntoDocument,
+        options: impl Into<Option<DeleteOptions>>,
     ) -> Result<DeleteResult> {
-        self.delete(filter, false, write_concern)
+        let filter = filter.into_document();
+        let options = options.into().unwrap_or_default();
+        self.delete_documents(filter, false, options)
     }
 
     /// Deletes multiple documents.
     pub fn delete_many(
         &self,
-        filter: bson::Document,
-        write_concern: Option<WriteConcern>,
+        filter: impl IntoDocument,
+        write_concern: impl Into<Option<WriteConcern>>,
     ) -> Result<DeleteResult> {
-        self.delete(filter, true, write_concern)
+        let options = DeleteOptions {
+            write_concern: write_concern.into(),
+            ..DeleteOptions::new()
+        };
+        self.delete_many_with_options(filter, options)
+    }
+
+    /// Deletes multiple documents, with options such as a comment or variables for the filter.
+    pub fn delete_many_with_options(
+        &self,
+        filter: impl IntoDocument,
+        options: impl Into<Option<DeleteOptions>>,
+    ) -> Result<DeleteResult> {
+        let filter = filter.into_document();
+        let options = options.into().unwrap_or_default();
+        self.delete_documents(filter, true, options)
     }
 
     // Sends a batch of replace and update ops to the server at once.
@@ -828,43 +1873,70 @@ impl Collection {
         models: Vec<UpdateModel>,
         ordered: bool,
         write_concern: Option<WriteConcern>,
+        policy: WriteConcernErrorPolicy,
+        timeout_ms: Option<i64>,
         cmd_type: CommandType,
-    ) -> Result<BulkUpdateResult> {
-        let wc = write_concern.unwrap_or_else(|| self.write_concern.clone());
+    ) -> Result<(BulkUpdateResult, bson::Document)> {
         let updates: Vec<_> = models
             .into_iter()
             .map(|model| Bson::Document(bson::Document::from(model)))
             .collect();
 
+        let options = bson::Document::new();
+        self.send_update_statements(
+            updates,
+            ordered,
+            options,
+            write_concern,
+            policy,
+            timeout_ms,
+            cmd_type,
+        )
+    }
+
+    // Sends update statements, each a document of `q`, `u` and flags, to the server at once.
+    fn send_update_statements(
+        &self,
+        updates: Vec<Bson>,
+        ordered: bool,
+        options: bson::Document,
+        write_concern: Option<WriteConcern>,
+        policy: WriteConcernErrorPolicy,
+        timeout_ms: Option<i64>,
+        cmd_type: CommandType,
+    ) -> Result<(BulkUpdateResult, bson::Document)> {
+        let wc = write_concern.unwrap_or_else(|| self.write_concern.clone());
         let cmd = doc! {
             "update": self.name(),
             "updates": updates,
             "ordered": ordered,
             "writeConcern": wc.to_bson()
         };
+        let cmd = merge_options(cmd, options);
 
-        let result = self.db.command(cmd, cmd_type, None)?;
+        if !wc.is_acknowledged() {
+            self.send_unacknowledged(cmd, cmd_type, timeout_ms)?;
+            return Ok((BulkUpdateResult::unacknowledged(), bson::Document::new()));
+        }
 
-        // Intercept write exceptions and insert into the result
-        let exception_res = BulkWriteException::validate_bulk_write_result(result.clone(), wc);
-        let exception = match exception_res {
-            Ok(()) => None,
-            Err(BulkWriteError(err)) => Some(err),
-            Err(e) => return Err(e),
-        };
+        let result = self.db.command_with_timeout(cmd, cmd_type, None, timeout_ms)?;
+
+        let exception = Collection::intercept_write_exception(result.clone(), wc, policy)?;
 
-        Ok(BulkUpdateResult::new(result, exception))
+        Ok((BulkUpdateResult::new(result.clone(), exception), result))
     }
 
     // Internal update helper function.
-    fn update(
+    fn update_documents(
         &self,
         filter: bson::Document,
-        update: bson::Document,
-        upsert: Option<bool>,
+        update: UpdateModifications,
         multi: bool,
-        write_concern: Option<WriteConcern>,
+        options: UpdateOptions,
     ) -> Result<UpdateResult> {
+        let upsert = options.upsert;
+        let write_concern = options.write_concern;
+        let timeout_ms = options.timeout_ms;
 
         let cmd_type = if multi {
             CommandType::UpdateMany
@@ -872,74 +1944,88 @@ impl Collection {
             CommandType::UpdateOne
         };
 
-        self.bulk_update(
-            vec![UpdateModel::new(filter, update, upsert, multi)],
+        let statement = match update {
+            UpdateModifications::Document(update) => {
+                bson::Document::from(UpdateModel::new(filter, update, upsert, multi))
+            }
+            pipeline => {
+                let mut statement = doc! { "q": filter, "u": Bson::from(pipeline) };
+                if let Some(upsert) = upsert {
+                    statement.insert("upsert", upsert);
+                }
+                if multi {
+                    statement.insert("multi", true);
+                }
+                statement
+            }
+        };
+
+        self.send_update_statements(
+            vec![Bson::Document(statement)],
             true,
+            bson::Document::from(options),
             write_concern,
+            self.db.client.write_concern_error_policy,
+            timeout_ms,
             cmd_type,
-        ).map(
-            UpdateResult::with_bulk_result
-        )
+        ).map(|(result, _)| UpdateResult::with_bulk_result(result))
+        .map_err(Collection::downgrade_bulk_error)
+    }
+
+    /// Starts an update to be built one operator at a time. See the `query` module.
+    pub fn update(&self) -> Update<'_> {
+        Update::new(self)
     }
 
     /// Replaces a single document.
     pub fn replace_one(
         &self,
-        filter: bson::Document,
-        replacement: bson::Document,
-        options: Option<ReplaceOptions>,
+        filter: impl IntoDocument,
+        replacement: impl IntoDocument,
+        options: impl Into<Option<ReplaceOptions>>,
     ) -> Result<UpdateResult> {
+        let filter = filter.into_document();
+        let replacement = replacement.into_document();
+        let options = options.into();
         let options = options.unwrap_or_default();
 
         Collection::validate_replace(&replacement)?;
 
-        self.update(
-            filter,
-            replacement,
-            options.upsert,
-            false,
-            options.write_concern,
-        )
+        self.update_documents(filter, UpdateModifications::Document(replacement), false, options)
     }
 
-    /// Updates a single document.
-    pub fn update_one(
+    /// Updates a single document, with either update operators or an update pipeline.
+    pub fn update_one<U: Into<UpdateModifications>>(
         &self,
-        filter: bson::Document,
-        update: bson::Document,
-        options: Option<UpdateOptions>,
+        filter: impl IntoDocument,
+        update: U,
+        options: impl Into<Option<UpdateOptions>>,
     ) -> Result<UpdateResult> {
+        let filter = filter.into_document();
+        let options = options.into();
         let options = options.unwrap_or_default();
+        let update = update.into();
 
         Collection::validate_update(&update)?;
 
-        self.update(
-            filter,
-            update,
-            options.upsert,
-            false,
-            options.write_concern
-        )
+        self.update_documents(filter, update, false, options)
     }
 
-    /// Updates multiple documents.
-    pub fn update_many(
+    /// Updates multiple documents, with either update operators or an update pipeline.
+    pub fn update_many<U: Into<UpdateModifications>>(
         &self,
-        filter: bson::Document,
-        update: bson::Document,
-        options: Option<UpdateOptions>,
+        filter: impl IntoDocument,
+        update: U,
+        options: impl Into<Option<UpdateOptions>>,
     ) -> Result<UpdateResult> {
+        let filter = filter.into_document();
+        let options = options.into();
         let options = options.unwrap_or_default();
+        let update = update.into();
 
         Collection::validate_update(&update)?;
 
-        self.update(
-            filter,
-            update,
-            options.upsert,
-            true,
-            options.write_concern
-        )
+        self.update_documents(filter, update, true, options)
     }
 
     fn validate_replace(replacement: &bson::Document) -> Result<()> {
@@ -953,12 +2039,34 @@ impl Collection {
         Ok(())
     }
 
-    fn validate_update(update: &bson::Document) -> Result<()> {
-        for key in update.keys() {
-            if !key.starts_with('$') {
-                return Err(ArgumentError(
-                    String::from("Update only works with $ operators."),
-                ));
+    fn validate_update(update: &UpdateModifications) -> Result<()> {
+        let pipeline = match *update {
+            UpdateModifications::Document(ref update) => {
+                for key in update.keys() {
+                    if !key.starts_with('$') {
+                        return Err(ArgumentError(
+                            String::from("Update only works with $ operators."),
+                        ));
+                    }
+                }
+                return Ok(());
+            }
+            UpdateModifications::Pipeline(ref pipeline) => pipeline,
+        };
+
+        if pipeline.is_empty() {
+            return Err(ArgumentError(String::from("Update pipeline cannot be empty.")));
+        }
+
+        for stage in pipeline {
+            let mut keys = stage.keys();
+            match (keys.next(), keys.next()) {
+                (Some(key), None) if key.starts_with('$') => (),
+                _ => {
+                    return Err(ArgumentError(String::from(
+                        "Each stage of an update pipeline must be a single $ stage.",
+                    )))
+                }
             }
         }
         Ok(())
@@ -980,6 +2088,16 @@ impl Collection {
         Ok(result.swap_remove(0))
     }
 
+    /// Create a text index over `fields`, each paired with its weight, for `$text` queries.
+    /// A collection can have at most one text index.
+    pub fn create_text_index(
+        &self,
+        fields: &[(&str, i32)],
+        default_language: Option<&str>,
+    ) -> Result<String> {
+        self.create_index_model(IndexModel::text(fields, default_language))
+    }
+
     /// Create multiple indexes.
     pub fn create_indexes(&self, models: Vec<IndexModel>) -> Result<Vec<String>> {
         let mut names = Vec::with_capacity(models.len());
@@ -1002,6 +2120,31 @@ impl Collection {
         }
     }
 
+    /// Create an index on `field` that removes each document `expire_after_seconds` after the
+    /// date stored in that field, returning the index name.
+    pub fn create_ttl_index(&self, field: &str, expire_after_seconds: i32) -> Result<String> {
+        let mut options = IndexOptions::new();
+        options.expire_after_seconds = Some(expire_after_seconds);
+
+        self.create_index(doc! { field: 1 }, Some(options))
+    }
+
+    /// Change the expiry of the named TTL index.
+    pub fn update_ttl(&self, name: &str, expire_after_seconds: i32) -> Result<()> {
+        let cmd = doc! {
+            "collMod": self.name(),
+            "index": {
+                "name": name,
+                "expireAfterSeconds": expire_after_seconds,
+            },
+        };
+        let mut result = self.db.command(cmd, CommandType::CollMod, None)?;
+        match result.remove("errmsg") {
+            Some(Bson::String(msg)) => Err(OperationError(msg)),
+            _ => Ok(()),
+        }
+    }
+
     /// Drop an index.
     pub fn drop_index(&self, keys: bson::Document, options: Option<IndexOptions>) -> Result<()> {
         let model = IndexModel::new(keys, options);
@@ -1,6 +1,9 @@
 //! Options for collection-level operations.
+//!
+//! Each options struct starts from its `Default` and can be built with chained setters, such as
+//! `FindOptions::builder().limit(10).sort(doc! { "year": -1 }).build()`.
 use bson::{self, Bson, bson, doc};
-use common::{ReadPreference, WriteConcern};
+use common::{ReadConcern, ReadPreference, WriteConcern};
 use Error::ArgumentError;
 use Result;
 
@@ -34,6 +37,43 @@ impl ReturnDocument {
     }
 }
 
+/// How much detail `explain` reports about an operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExplainVerbosity {
+    /// The plan the query planner chose, without running it.
+    QueryPlanner,
+    /// The chosen plan and the work done running it. Explained writes are not applied.
+    ExecutionStats,
+    /// Also the work done evaluating the rejected plans while choosing.
+    AllPlansExecution,
+}
+
+impl ExplainVerbosity {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            ExplainVerbosity::QueryPlanner => "queryPlanner",
+            ExplainVerbosity::ExecutionStats => "executionStats",
+            ExplainVerbosity::AllPlansExecution => "allPlansExecution",
+        }
+    }
+}
+
+/// How `Collection::export_to_writer` writes documents and `import_from_reader` reads them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    /// Concatenated BSON documents, as `mongodump` writes them.
+    Bson,
+    /// One canonical extended JSON document per line, as `mongoexport` writes them. See the
+    /// `ext_json` module.
+    ExtendedJson,
+}
+
+impl Default for ExportFormat {
+    fn default() -> Self {
+        ExportFormat::Bson
+    }
+}
+
 /// Marker interface for writes that can be batched together.
 #[derive(Debug, Clone, PartialEq)]
 pub enum WriteModel {
@@ -57,16 +97,105 @@ pub enum WriteModel {
     },
 }
 
+/// A document passed to a collection method by value or by reference. A borrowed document is
+/// cloned into the command that sends it, so callers need not clone it themselves.
+pub trait IntoDocument {
+    /// Returns the document, cloning it if it is borrowed.
+    fn into_document(self) -> bson::Document;
+}
+
+impl IntoDocument for bson::Document {
+    fn into_document(self) -> bson::Document {
+        self
+    }
+}
+
+impl<'a> IntoDocument for &'a bson::Document {
+    fn into_document(self) -> bson::Document {
+        self.clone()
+    }
+}
+
+/// `None` stands for an empty document, such as a filter that matches every document.
+impl IntoDocument for Option<bson::Document> {
+    fn into_document(self) -> bson::Document {
+        self.unwrap_or_default()
+    }
+}
+
+/// The changes an update makes to each matched document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateModifications {
+    /// A document of update operators, such as `$set` and `$inc`.
+    Document(bson::Document),
+    /// An aggregation pipeline, which can compute new values from the document's existing
+    /// fields. Stages are limited to `$addFields`, `$set`, `$project`, `$unset`, `$replaceRoot`
+    /// and `$replaceWith`. Requires MongoDB 4.2 or later.
+    Pipeline(Vec<bson::Document>),
+}
+
+impl From<bson::Document> for UpdateModifications {
+    fn from(update: bson::Document) -> Self {
+        UpdateModifications::Document(update)
+    }
+}
+
+impl<'a> From<&'a bson::Document> for UpdateModifications {
+    fn from(update: &'a bson::Document) -> Self {
+        UpdateModifications::Document(update.clone())
+    }
+}
+
+impl From<Vec<bson::Document>> for UpdateModifications {
+    fn from(pipeline: Vec<bson::Document>) -> Self {
+        UpdateModifications::Pipeline(pipeline)
+    }
+}
+
+impl From<UpdateModifications> for Bson {
+    fn from(update: UpdateModifications) -> Self {
+        match update {
+            UpdateModifications::Document(update) => Bson::Document(update),
+            UpdateModifications::Pipeline(pipeline) => {
+                Bson::Array(pipeline.into_iter().map(Bson::Document).collect())
+            }
+        }
+    }
+}
+
 /// Options for aggregation queries.
-#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct AggregateOptions {
     pub allow_disk_use: Option<bool>,
     pub use_cursor: Option<bool>,
     pub batch_size: i32,
     pub max_time_ms: Option<i64>,
+    /// Whether a final `$out` or `$merge` stage may write documents the output collection's
+    /// validator would reject.
+    pub bypass_document_validation: Option<bool>,
+    /// Tags the command in the profiler, currentOp and server logs. Values other than strings
+    /// require MongoDB 4.4 or later.
+    pub comment: Option<Bson>,
+    /// Variables accessible to the pipeline's expressions as `$$<name>` (MongoDB 5.0+).
+    pub let_vars: Option<bson::Document>,
+    pub read_concern: Option<ReadConcern>,
     pub read_preference: Option<ReadPreference>,
+    pub timeout_ms: Option<i64>,
 }
 
+options_builder!(AggregateOptions, AggregateOptionsBuilder {
+    allow_disk_use: Option<bool>,
+    use_cursor: Option<bool>,
+    batch_size: i32,
+    max_time_ms: Option<i64>,
+    bypass_document_validation: Option<bool>,
+    comment: Option<Bson>,
+    let_vars: Option<bson::Document>,
+    read_concern: Option<ReadConcern>,
+    read_preference: Option<ReadPreference>,
+    timeout_ms: Option<i64>,
+});
+
 impl AggregateOptions {
     pub fn new() -> Self {
         Default::default()
@@ -87,9 +216,89 @@ impl From<AggregateOptions> for bson::Document {
         let cursor = doc! { "batchSize": options.batch_size };
         document.insert("cursor", cursor);
 
-        // maxTimeMS is not currently used by the driver.
+        if let Some(max_time_ms) = options.max_time_ms {
+            document.insert("maxTimeMS", max_time_ms);
+        }
+
+        if let Some(bypass_document_validation) = options.bypass_document_validation {
+            document.insert("bypassDocumentValidation", bypass_document_validation);
+        }
+
+        if let Some(comment) = options.comment {
+            document.insert("comment", comment);
+        }
+
+        if let Some(let_vars) = options.let_vars {
+            document.insert("let", let_vars);
+        }
+
+        if let Some(read_concern) = options.read_concern {
+            document.insert("readConcern", read_concern.to_bson());
+        }
+
+        // read_preference and timeout_ms are used directly by Collection::aggregate.
+
+        document
+    }
+}
+
+/// Options for `Collection::aggregate_to_collection`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AggregateToCollectionOptions {
+    pub allow_disk_use: Option<bool>,
+    pub max_time_ms: Option<i64>,
+    /// Whether to write documents the output collection's validator would reject.
+    pub bypass_document_validation: Option<bool>,
+    /// Variables accessible to the pipeline's expressions as `$$<name>` (MongoDB 5.0+). There
+    /// is no `comment`, since the driver tags the aggregation with its own to follow its
+    /// progress.
+    pub let_vars: Option<bson::Document>,
+    /// Defaults to the collection's write concern.
+    pub write_concern: Option<WriteConcern>,
+    /// How often to report progress while the aggregation runs; defaults to one second.
+    pub progress_interval_ms: Option<u64>,
+}
+
+options_builder!(AggregateToCollectionOptions, AggregateToCollectionOptionsBuilder {
+    allow_disk_use: Option<bool>,
+    max_time_ms: Option<i64>,
+    bypass_document_validation: Option<bool>,
+    let_vars: Option<bson::Document>,
+    write_concern: Option<WriteConcern>,
+    progress_interval_ms: Option<u64>,
+});
+
+impl AggregateToCollectionOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl From<AggregateToCollectionOptions> for bson::Document {
+    fn from(options: AggregateToCollectionOptions) -> Self {
+        let mut document = bson::Document::new();
+
+        if let Some(allow_disk_use) = options.allow_disk_use {
+            document.insert("allowDiskUse", allow_disk_use);
+        }
+
+        if let Some(max_time_ms) = options.max_time_ms {
+            document.insert("maxTimeMS", max_time_ms);
+        }
+
+        if let Some(bypass_document_validation) = options.bypass_document_validation {
+            document.insert("bypassDocumentValidation", bypass_document_validation);
+        }
+
+        if let Some(let_vars) = options.let_vars {
+            document.insert("let", let_vars);
+        }
+
+        if let Some(write_concern) = options.write_concern {
+            document.insert("writeConcern", write_concern.to_bson());
+        }
 
-        // read_preference is used directly by Collection::aggregate.
+        // progress_interval_ms is used directly by Collection::aggregate_to_collection.
 
         document
     }
@@ -104,8 +313,19 @@ pub struct CountOptions {
     pub hint_doc: Option<bson::Document>,
     pub max_time_ms: Option<i64>,
     pub read_preference: Option<ReadPreference>,
+    pub timeout_ms: Option<i64>,
 }
 
+options_builder!(CountOptions, CountOptionsBuilder {
+    skip: Option<i64>,
+    limit: Option<i64>,
+    hint: Option<String>,
+    hint_doc: Option<bson::Document>,
+    max_time_ms: Option<i64>,
+    read_preference: Option<ReadPreference>,
+    timeout_ms: Option<i64>,
+});
+
 impl CountOptions {
     pub fn new() -> Self {
         Default::default()
@@ -132,27 +352,208 @@ impl From<CountOptions> for bson::Document {
             document.insert("hint_doc", hint_doc);
         }
 
-        // maxTimeMS is not currently used by the driver.
+        if let Some(max_time_ms) = options.max_time_ms {
+            document.insert("maxTimeMS", max_time_ms);
+        }
 
-        // read_preference is used directly by Collection::count.
+        // read_preference and timeout_ms are used directly by Collection::count.
 
         document
     }
 }
 
 /// Options for distinct queries.
-#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct DistinctOptions {
     pub max_time_ms: Option<i64>,
+    pub collation: Option<bson::Document>,
+    pub read_concern: Option<ReadConcern>,
     pub read_preference: Option<ReadPreference>,
+    pub timeout_ms: Option<i64>,
 }
 
+options_builder!(DistinctOptions, DistinctOptionsBuilder {
+    max_time_ms: Option<i64>,
+    collation: Option<bson::Document>,
+    read_concern: Option<ReadConcern>,
+    read_preference: Option<ReadPreference>,
+    timeout_ms: Option<i64>,
+});
+
 impl DistinctOptions {
     pub fn new() -> Self {
         Default::default()
     }
 }
 
+impl From<DistinctOptions> for bson::Document {
+    fn from(options: DistinctOptions) -> Self {
+        let mut document = bson::Document::new();
+
+        if let Some(max_time_ms) = options.max_time_ms {
+            document.insert("maxTimeMS", max_time_ms);
+        }
+
+        if let Some(collation) = options.collation {
+            document.insert("collation", collation);
+        }
+
+        if let Some(read_concern) = options.read_concern {
+            document.insert("readConcern", read_concern.to_bson());
+        }
+
+        // read_preference and timeout_ms are used directly by Collection::distinct.
+
+        document
+    }
+}
+
+/// How a map-reduce combines its results with an existing output collection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MapReduceAction {
+    /// Replaces the collection's contents with the results.
+    Replace,
+    /// Adds the results, overwriting existing documents with the same key.
+    Merge,
+    /// Adds the results, running the reduce function over a result and the existing document
+    /// with the same key.
+    Reduce,
+}
+
+impl MapReduceAction {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            MapReduceAction::Replace => "replace",
+            MapReduceAction::Merge => "merge",
+            MapReduceAction::Reduce => "reduce",
+        }
+    }
+}
+
+/// Where a map-reduce writes its results.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapReduceOutput {
+    /// Returns the results in the reply, which is limited to 16 MB.
+    Inline,
+    /// Writes the results to the collection `name`, in the database `db` if one is given and
+    /// otherwise in the source collection's database.
+    Collection {
+        name: String,
+        db: Option<String>,
+        action: MapReduceAction,
+    },
+}
+
+impl MapReduceOutput {
+    fn to_bson(&self) -> Bson {
+        match *self {
+            MapReduceOutput::Inline => Bson::Document(doc! { "inline": 1 }),
+            MapReduceOutput::Collection { ref name, ref db, action } => {
+                let mut out = doc! { action.to_str(): name.clone() };
+
+                if let Some(ref db) = *db {
+                    out.insert("db", db.clone());
+                }
+
+                Bson::Document(out)
+            }
+        }
+    }
+}
+
+/// Options for map-reduce operations.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapReduceOptions {
+    /// Defaults to inline output.
+    pub out: Option<MapReduceOutput>,
+    /// Selects the documents passed to the map function.
+    pub query: Option<bson::Document>,
+    pub sort: Option<bson::Document>,
+    pub limit: Option<i64>,
+    /// JavaScript run on each result after it is reduced.
+    pub finalize: Option<String>,
+    /// Global variables available to the map, reduce and finalize functions.
+    pub scope: Option<bson::Document>,
+    pub js_mode: Option<bool>,
+    pub bypass_document_validation: Option<bool>,
+    pub max_time_ms: Option<i64>,
+    /// Used only with inline output; results written to a collection are read on the primary.
+    pub read_preference: Option<ReadPreference>,
+    /// Used only when writing to a collection; defaults to the collection's write concern.
+    pub write_concern: Option<WriteConcern>,
+    pub timeout_ms: Option<i64>,
+}
+
+options_builder!(MapReduceOptions, MapReduceOptionsBuilder {
+    out: Option<MapReduceOutput>,
+    query: Option<bson::Document>,
+    sort: Option<bson::Document>,
+    limit: Option<i64>,
+    finalize: Option<String>,
+    scope: Option<bson::Document>,
+    js_mode: Option<bool>,
+    bypass_document_validation: Option<bool>,
+    max_time_ms: Option<i64>,
+    read_preference: Option<ReadPreference>,
+    write_concern: Option<WriteConcern>,
+    timeout_ms: Option<i64>,
+});
+
+impl MapReduceOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl From<MapReduceOptions> for bson::Document {
+    fn from(options: MapReduceOptions) -> Self {
+        let out = options.out.unwrap_or(MapReduceOutput::Inline);
+        let mut document = doc! { "out": out.to_bson() };
+
+        if let Some(query) = options.query {
+            document.insert("query", query);
+        }
+
+        if let Some(sort) = options.sort {
+            document.insert("sort", sort);
+        }
+
+        if let Some(limit) = options.limit {
+            document.insert("limit", limit);
+        }
+
+        if let Some(finalize) = options.finalize {
+            document.insert("finalize", Bson::JavaScriptCode(finalize));
+        }
+
+        if let Some(scope) = options.scope {
+            document.insert("scope", scope);
+        }
+
+        if let Some(js_mode) = options.js_mode {
+            document.insert("jsMode", js_mode);
+        }
+
+        if let Some(bypass_document_validation) = options.bypass_document_validation {
+            document.insert("bypassDocumentValidation", bypass_document_validation);
+        }
+
+        if let Some(max_time_ms) = options.max_time_ms {
+            document.insert("maxTimeMS", max_time_ms);
+        }
+
+        if out != MapReduceOutput::Inline {
+            if let Some(write_concern) = options.write_concern {
+                document.insert("writeConcern", write_concern.to_bson());
+            }
+        }
+
+        // read_preference and timeout_ms are used directly by Collection::map_reduce.
+
+        document
+    }
+}
+
 /// Options for collection queries.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct FindOptions {
@@ -163,31 +564,97 @@ pub struct FindOptions {
     pub limit: Option<i64>,
     pub cursor_type: CursorType,
     pub batch_size: Option<i32>,
-    pub comment: Option<String>,
+    /// How many documents each getMore requests; defaults to the size of the first batch.
+    pub get_more_batch_size: Option<i32>,
+    /// Whether getMores grow their batch size toward filling a 16MB reply, based on the size of
+    /// the documents read so far. See `Cursor::set_adaptive_batch_size`.
+    pub adaptive_batch_size: bool,
+    /// Tags the query in the profiler, currentOp and server logs. Values other than strings
+    /// require MongoDB 4.4 or later, and are sent with the `find` command.
+    pub comment: Option<Bson>,
     pub max_time_ms: Option<i64>,
     pub modifiers: Option<bson::Document>,
     pub projection: Option<bson::Document>,
     pub sort: Option<bson::Document>,
+    /// The name of the index to use, such as one that avoids a blocking in-memory sort.
+    pub hint: Option<String>,
+    /// The key pattern of the index to use; takes precedence over `hint`.
+    pub hint_doc: Option<bson::Document>,
+    /// The exclusive upper bound of a specific index.
+    pub max: Option<bson::Document>,
+    /// The inclusive lower bound of a specific index.
+    pub min: Option<bson::Document>,
+    /// Whether to return only the index keys of the matched documents.
+    pub return_key: Option<bool>,
+    /// Whether to add a `$recordId` field to each returned document.
+    pub show_record_id: Option<bool>,
+    /// Whether blocking sorts may write temporary files to disk (MongoDB 4.4+). Sorts that
+    /// exceed the server's memory limit without it fail with
+    /// `ErrorCode::QueryExceededMemoryLimitNoDiskUseAllowed`.
+    pub allow_disk_use: Option<bool>,
+    /// Variables accessible to `$expr` in the filter as `$$<name>` (MongoDB 5.0+).
+    pub let_vars: Option<bson::Document>,
+    pub read_concern: Option<ReadConcern>,
     pub read_preference: Option<ReadPreference>,
+    /// Client-side limit on the total time of the query and its getMores.
+    pub timeout_ms: Option<i64>,
 }
 
+options_builder!(FindOptions, FindOptionsBuilder {
+    allow_partial_results: bool,
+    no_cursor_timeout: bool,
+    oplog_replay: bool,
+    skip: Option<i64>,
+    limit: Option<i64>,
+    cursor_type: CursorType,
+    batch_size: Option<i32>,
+    get_more_batch_size: Option<i32>,
+    adaptive_batch_size: bool,
+    comment: Option<Bson>,
+    max_time_ms: Option<i64>,
+    modifiers: Option<bson::Document>,
+    projection: Option<bson::Document>,
+    sort: Option<bson::Document>,
+    hint: Option<String>,
+    hint_doc: Option<bson::Document>,
+    max: Option<bson::Document>,
+    min: Option<bson::Document>,
+    return_key: Option<bool>,
+    show_record_id: Option<bool>,
+    allow_disk_use: Option<bool>,
+    let_vars: Option<bson::Document>,
+    read_concern: Option<ReadConcern>,
+    read_preference: Option<ReadPreference>,
+    timeout_ms: Option<i64>,
+});
+
 impl FindOptions {
     /// Creates a new FindOptions struct with default parameters.
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Whether these options can only be sent with the `find` command, rather than as legacy
+    /// OP_QUERY modifiers.
+    pub fn requires_find_command(&self) -> bool {
+        let typed_comment = match self.comment {
+            Some(Bson::String(_)) | None => false,
+            Some(_) => true,
+        };
+
+        typed_comment || self.allow_disk_use.is_some() || self.let_vars.is_some() ||
+            self.read_concern.is_some()
+    }
 }
 
 impl From<FindOptions> for bson::Document {
     fn from(options: FindOptions) -> Self {
         let mut document = bson::Document::new();
 
-        // `allow_partial_results`, `no_cursor_timeout`, `oplog_relay`, and `cursor_type` are used by
-        // wire_protocol::OpQueryFlags.
-        //
-        // `max_time_ms` and `modifiers` are not currently used by the driver.
+        // `modifiers` are not currently used by the driver.
         //
-        // read_preference is used directly by Collection::find_with_command_type.
+        // read_preference is used directly by Collection::find_with_command_type, and
+        // timeout_ms, get_more_batch_size and adaptive_batch_size by Cursor::query.
 
         if let Some(projection) = options.projection {
             document.insert("projection", projection);
@@ -209,10 +676,194 @@ impl From<FindOptions> for bson::Document {
             document.insert("sort", sort);
         }
 
+        if let Some(hint_doc) = options.hint_doc {
+            document.insert("hint", hint_doc);
+        } else if let Some(hint) = options.hint {
+            document.insert("hint", hint);
+        }
+
+        if let Some(max_time_ms) = options.max_time_ms {
+            document.insert("maxTimeMS", max_time_ms);
+        }
+
+        if let Some(comment) = options.comment {
+            document.insert("comment", comment);
+        }
+
+        if let Some(max) = options.max {
+            document.insert("max", max);
+        }
+
+        if let Some(min) = options.min {
+            document.insert("min", min);
+        }
+
+        if let Some(return_key) = options.return_key {
+            document.insert("returnKey", return_key);
+        }
+
+        if let Some(show_record_id) = options.show_record_id {
+            document.insert("showRecordId", show_record_id);
+        }
+
+        if let Some(allow_disk_use) = options.allow_disk_use {
+            document.insert("allowDiskUse", allow_disk_use);
+        }
+
+        if let Some(let_vars) = options.let_vars {
+            document.insert("let", let_vars);
+        }
+
+        if let Some(read_concern) = options.read_concern {
+            document.insert("readConcern", read_concern.to_bson());
+        }
+
+        // Legacy queries send these as wire_protocol::OpQueryFlags instead.
+        if options.cursor_type != CursorType::NonTailable {
+            document.insert("tailable", true);
+        }
+
+        if options.cursor_type == CursorType::TailableAwait {
+            document.insert("awaitData", true);
+        }
+
+        if options.oplog_replay {
+            document.insert("oplogReplay", true);
+        }
+
+        if options.no_cursor_timeout {
+            document.insert("noCursorTimeout", true);
+        }
+
+        if options.allow_partial_results {
+            document.insert("allowPartialResults", true);
+        }
+
         document
     }
 }
 
+/// Options for tailing a capped collection.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TailOptions {
+    /// A field whose values increase in insertion order, used to resume after the last document
+    /// returned when the cursor must be reopened; defaults to `_id`.
+    pub resume_field: Option<String>,
+    /// Starts after the document whose `resume_field` has this value, rather than at the
+    /// beginning of the collection.
+    pub resume_after: Option<Bson>,
+    pub batch_size: Option<i32>,
+    pub projection: Option<bson::Document>,
+    /// Whether to let the server find the starting point of an oplog query efficiently, for
+    /// filters on `ts`. Ignored by MongoDB 4.4 and later, which always do so.
+    pub oplog_replay: bool,
+    /// How long to wait before reopening a cursor the server closed, such as one opened on an
+    /// empty collection; defaults to one second.
+    pub retry_interval_ms: Option<u64>,
+    /// Ends iteration after this long without a new document; by default, iteration blocks
+    /// until the next document arrives.
+    pub idle_timeout_ms: Option<u64>,
+    pub read_preference: Option<ReadPreference>,
+}
+
+options_builder!(TailOptions, TailOptionsBuilder {
+    resume_field: Option<String>,
+    resume_after: Option<Bson>,
+    batch_size: Option<i32>,
+    projection: Option<bson::Document>,
+    oplog_replay: bool,
+    retry_interval_ms: Option<u64>,
+    idle_timeout_ms: Option<u64>,
+    read_preference: Option<ReadPreference>,
+});
+
+impl TailOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+/// A `$text` query, which matches documents whose text index contains the terms of `search`.
+///
+/// `to_filter` builds the query filter, to which other conditions can be added; `sort_by_score`
+/// returns the matches most relevant first. Text queries require a text index on the collection.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextSearchOptions {
+    /// The terms to search for. Quoted phrases must match exactly, and terms prefixed with `-`
+    /// exclude the documents containing them.
+    pub search: String,
+    /// The language that determines stop words and stemming; defaults to the index's default
+    /// language. `none` disables stemming.
+    pub language: Option<String>,
+    pub case_sensitive: Option<bool>,
+    pub diacritic_sensitive: Option<bool>,
+}
+
+options_builder!(TextSearchOptions, TextSearchOptionsBuilder {
+    search: String,
+    language: Option<String>,
+    case_sensitive: Option<bool>,
+    diacritic_sensitive: Option<bool>,
+});
+
+impl TextSearchOptions {
+    /// Creates a search for the terms in `search`.
+    pub fn new(search: &str) -> Self {
+        TextSearchOptions {
+            search: String::from(search),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the query filter for the search.
+    pub fn to_filter(&self) -> bson::Document {
+        let mut text = doc! { "$search": self.search.clone() };
+
+        if let Some(ref language) = self.language {
+            text.insert("$language", language.clone());
+        }
+
+        if let Some(case_sensitive) = self.case_sensitive {
+            text.insert("$caseSensitive", case_sensitive);
+        }
+
+        if let Some(diacritic_sensitive) = self.diacritic_sensitive {
+            text.insert("$diacriticSensitive", diacritic_sensitive);
+        }
+
+        doc! { "$text": text }
+    }
+
+    /// Returns the projection or sort specification for the relevance score of a match, stored
+    /// in `field`.
+    pub fn score(field: &str) -> bson::Document {
+        doc! { field: { "$meta": "textScore" } }
+    }
+
+    /// Adds the relevance score of each match to `options` as `field`, and sorts the matches
+    /// by it, most relevant first, before any sort already in `options`. The score is added to
+    /// any projection already in `options`, whether it includes or excludes fields.
+    pub fn sort_by_score(field: &str, options: Option<FindOptions>) -> FindOptions {
+        let mut options = options.unwrap_or_default();
+
+        let mut projection = options.projection.take().unwrap_or_default();
+        projection.insert(field, doc! { "$meta": "textScore" });
+        options.projection = Some(projection);
+
+        let mut sort = TextSearchOptions::score(field);
+        if let Some(existing) = options.sort.take() {
+            for (key, value) in existing {
+                if key != field {
+                    sort.insert(key, value);
+                }
+            }
+        }
+        options.sort = Some(sort);
+
+        options
+    }
+}
+
 /// Options for `findOneAndDelete` operations.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct FindOneAndDeleteOptions {
@@ -220,8 +871,24 @@ pub struct FindOneAndDeleteOptions {
     pub projection: Option<bson::Document>,
     pub sort: Option<bson::Document>,
     pub write_concern: Option<WriteConcern>,
+    /// Tags the command in the profiler, currentOp and server logs. Values other than strings
+    /// require MongoDB 4.4 or later.
+    pub comment: Option<Bson>,
+    /// Variables accessible to `$expr` in the filter as `$$<name>` (MongoDB 5.0+).
+    pub let_vars: Option<bson::Document>,
+    pub timeout_ms: Option<i64>,
 }
 
+options_builder!(FindOneAndDeleteOptions, FindOneAndDeleteOptionsBuilder {
+    max_time_ms: Option<i64>,
+    projection: Option<bson::Document>,
+    sort: Option<bson::Document>,
+    write_concern: Option<WriteConcern>,
+    comment: Option<Bson>,
+    let_vars: Option<bson::Document>,
+    timeout_ms: Option<i64>,
+});
+
 impl FindOneAndDeleteOptions {
     pub fn new() -> Self {
         Default::default()
@@ -232,7 +899,9 @@ impl From<FindOneAndDeleteOptions> for bson::Document {
     fn from(options: FindOneAndDeleteOptions) -> Self {
         let mut document = bson::Document::new();
 
-        // max_time_ms is not currently used by the driver
+        if let Some(max_time_ms) = options.max_time_ms {
+            document.insert("maxTimeMS", max_time_ms);
+        }
 
         if let Some(projection) = options.projection {
             document.insert("fields", projection);
@@ -246,6 +915,16 @@ impl From<FindOneAndDeleteOptions> for bson::Document {
             document.insert("writeConcern", write_concern.to_bson());
         }
 
+        if let Some(comment) = options.comment {
+            document.insert("comment", comment);
+        }
+
+        if let Some(let_vars) = options.let_vars {
+            document.insert("let", let_vars);
+        }
+
+        // timeout_ms is used directly by Collection::find_and_modify.
+
         document
     }
 }
@@ -258,9 +937,31 @@ pub struct FindOneAndUpdateOptions {
     pub projection: Option<bson::Document>,
     pub sort: Option<bson::Document>,
     pub upsert: Option<bool>,
+    /// Whether to write documents the collection's validator would reject.
+    pub bypass_document_validation: Option<bool>,
     pub write_concern: Option<WriteConcern>,
+    /// Tags the command in the profiler, currentOp and server logs. Values other than strings
+    /// require MongoDB 4.4 or later.
+    pub comment: Option<Bson>,
+    /// Variables accessible to `$expr` in the filter and to an update pipeline as `$$<name>`
+    /// (MongoDB 5.0+).
+    pub let_vars: Option<bson::Document>,
+    pub timeout_ms: Option<i64>,
 }
 
+options_builder!(FindOneAndUpdateOptions, FindOneAndUpdateOptionsBuilder {
+    return_document: Option<ReturnDocument>,
+    max_time_ms: Option<i64>,
+    projection: Option<bson::Document>,
+    sort: Option<bson::Document>,
+    upsert: Option<bool>,
+    bypass_document_validation: Option<bool>,
+    write_concern: Option<WriteConcern>,
+    comment: Option<Bson>,
+    let_vars: Option<bson::Document>,
+    timeout_ms: Option<i64>,
+});
+
 impl FindOneAndUpdateOptions {
     pub fn new() -> Self {
         Default::default()
@@ -275,7 +976,9 @@ impl From<FindOneAndUpdateOptions> for bson::Document {
             document.insert("new", return_document.as_bool());
         }
 
-        // max_time_ms is not currently used by the driver
+        if let Some(max_time_ms) = options.max_time_ms {
+            document.insert("maxTimeMS", max_time_ms);
+        }
 
         if let Some(projection) = options.projection {
             document.insert("fields", projection);
@@ -289,10 +992,24 @@ impl From<FindOneAndUpdateOptions> for bson::Document {
             document.insert("upsert", upsert);
         }
 
+        if let Some(bypass_document_validation) = options.bypass_document_validation {
+            document.insert("bypassDocumentValidation", bypass_document_validation);
+        }
+
         if let Some(write_concern) = options.write_concern {
             document.insert("writeConcern", write_concern.to_bson());
         }
 
+        if let Some(comment) = options.comment {
+            document.insert("comment", comment);
+        }
+
+        if let Some(let_vars) = options.let_vars {
+            document.insert("let", let_vars);
+        }
+
+        // timeout_ms is used directly by Collection::find_and_modify.
+
         document
     }
 }
@@ -353,6 +1070,25 @@ pub struct IndexOptions {
     pub bucket_size: Option<i32>,
 }
 
+options_builder!(IndexOptions, IndexOptionsBuilder {
+    background: Option<bool>,
+    expire_after_seconds: Option<i32>,
+    name: Option<String>,
+    sparse: Option<bool>,
+    storage_engine: Option<bson::Document>,
+    unique: Option<bool>,
+    version: Option<i32>,
+    default_language: Option<String>,
+    language_override: Option<String>,
+    text_version: Option<i32>,
+    weights: Option<bson::Document>,
+    sphere_version: Option<i32>,
+    bits: Option<i32>,
+    max: Option<f64>,
+    min: Option<f64>,
+    bucket_size: Option<i32>,
+});
+
 impl IndexOptions {
     pub fn new() -> Self {
         Default::default()
@@ -413,6 +1149,26 @@ impl IndexModel {
         Ok(name)
     }
 
+    /// Creates a model for a text index over `fields`, each paired with its weight, the
+    /// factor its matches count for in the relevance score. The field `$**` indexes every string
+    /// field. `default_language` determines stop words and stemming; the server defaults to
+    /// `english`.
+    pub fn text(fields: &[(&str, i32)], default_language: Option<&str>) -> IndexModel {
+        let mut keys = bson::Document::new();
+        let mut weights = bson::Document::new();
+
+        for &(field, weight) in fields {
+            keys.insert(field, "text");
+            weights.insert(field, weight);
+        }
+
+        let mut options = IndexOptions::new();
+        options.weights = Some(weights);
+        options.default_language = default_language.map(String::from);
+
+        IndexModel::new(keys, Some(options))
+    }
+
     /// Converts the model to its BSON document representation.
     pub fn to_bson(&self) -> Result<bson::Document> {
         let mut doc = doc!{ "key": self.keys.clone() };
@@ -472,13 +1228,49 @@ impl IndexModel {
     }
 }
 
+/// Options for bulk write operations.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BulkWriteOptions {
+    /// Whether to stop at the first failed write; defaults to true.
+    pub ordered: Option<bool>,
+    /// Whether to record the server's reply to each batch, and how long each batch took, in
+    /// `BulkWriteResult::batch_replies`; defaults to false.
+    pub verbose_results: Option<bool>,
+    /// Client-side limit on the total time of every batch the bulk write sends. Batches not yet
+    /// sent when it runs out are reported as unprocessed.
+    pub timeout_ms: Option<i64>,
+}
+
+options_builder!(BulkWriteOptions, BulkWriteOptionsBuilder {
+    ordered: Option<bool>,
+    verbose_results: Option<bool>,
+    timeout_ms: Option<i64>,
+});
+
+impl BulkWriteOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
 /// Options for insertMany operations.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct InsertManyOptions {
     pub ordered: Option<bool>,
+    /// Whether to write documents the collection's validator would reject.
+    pub bypass_document_validation: Option<bool>,
     pub write_concern: Option<WriteConcern>,
+    /// Client-side limit on the total time of the insert.
+    pub timeout_ms: Option<i64>,
 }
 
+options_builder!(InsertManyOptions, InsertManyOptionsBuilder {
+    ordered: Option<bool>,
+    bypass_document_validation: Option<bool>,
+    write_concern: Option<WriteConcern>,
+    timeout_ms: Option<i64>,
+});
+
 impl InsertManyOptions {
     pub fn new() -> Self {
         Default::default()
@@ -493,27 +1285,120 @@ impl From<InsertManyOptions> for bson::Document {
             document.insert("ordered", ordered);
         }
 
+        if let Some(bypass_document_validation) = options.bypass_document_validation {
+            document.insert("bypassDocumentValidation", bypass_document_validation);
+        }
+
         if let Some(write_concern) = options.write_concern {
             document.insert("writeConcern", write_concern.to_bson());
         }
 
+        // timeout_ms is used directly by Collection::insert_one and Collection::insert_many.
+
         document
     }
 }
 
 /// Options for update operations.
-#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct UpdateOptions {
     pub upsert: Option<bool>,
+    /// Whether to write documents the collection's validator would reject.
+    pub bypass_document_validation: Option<bool>,
+    /// Tags the command in the profiler, currentOp and server logs. Values other than strings
+    /// require MongoDB 4.4 or later.
+    pub comment: Option<Bson>,
+    /// Variables accessible to `$expr` in the filter and to an update pipeline as `$$<name>`
+    /// (MongoDB 5.0+).
+    pub let_vars: Option<bson::Document>,
     pub write_concern: Option<WriteConcern>,
+    /// Client-side limit on the total time of the update.
+    pub timeout_ms: Option<i64>,
 }
 
+options_builder!(UpdateOptions, UpdateOptionsBuilder {
+    upsert: Option<bool>,
+    bypass_document_validation: Option<bool>,
+    comment: Option<Bson>,
+    let_vars: Option<bson::Document>,
+    write_concern: Option<WriteConcern>,
+    timeout_ms: Option<i64>,
+});
+
 impl UpdateOptions {
     pub fn new() -> UpdateOptions {
         Default::default()
     }
 }
 
+impl From<UpdateOptions> for bson::Document {
+    fn from(options: UpdateOptions) -> Self {
+        let mut document = bson::Document::new();
+
+        if let Some(bypass_document_validation) = options.bypass_document_validation {
+            document.insert("bypassDocumentValidation", bypass_document_validation);
+        }
+
+        if let Some(comment) = options.comment {
+            document.insert("comment", comment);
+        }
+
+        if let Some(let_vars) = options.let_vars {
+            document.insert("let", let_vars);
+        }
+
+        // upsert is set on each update statement, and write_concern and timeout_ms are used
+        // directly by Collection::update_one and Collection::update_many.
+
+        document
+    }
+}
+
+/// Options for delete operations.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeleteOptions {
+    /// Tags the command in the profiler, currentOp and server logs. Values other than strings
+    /// require MongoDB 4.4 or later.
+    pub comment: Option<Bson>,
+    /// Variables accessible to `$expr` in the filter as `$$<name>` (MongoDB 5.0+).
+    pub let_vars: Option<bson::Document>,
+    pub write_concern: Option<WriteConcern>,
+    /// Client-side limit on the total time of the delete.
+    pub timeout_ms: Option<i64>,
+}
+
+options_builder!(DeleteOptions, DeleteOptionsBuilder {
+    comment: Option<Bson>,
+    let_vars: Option<bson::Document>,
+    write_concern: Option<WriteConcern>,
+    timeout_ms: Option<i64>,
+});
+
+impl DeleteOptions {
+    pub fn new() -> DeleteOptions {
+        Default::default()
+    }
+}
+
+impl From<DeleteOptions> for bson::Document {
+    fn from(options: DeleteOptions) -> Self {
+        let mut document = bson::Document::new();
+
+        if let Some(comment) = options.comment {
+            document.insert("comment", comment);
+        }
+
+        if let Some(let_vars) = options.let_vars {
+            document.insert("let", let_vars);
+        }
+
+        // write_concern and timeout_ms are used directly by Collection::delete_one and
+        // Collection::delete_many.
+
+        document
+    }
+}
+
 pub type ReplaceOptions = UpdateOptions;
 
 #[cfg(test)]
@@ -542,6 +1427,68 @@ mod test {
         opts
     }
 
+    #[test]
+    fn builders_set_options() {
+        let options = FindOptions::builder()
+            .limit(10)
+            .sort(doc! { "year": -1 })
+            .allow_partial_results(true)
+            .build();
+
+        let mut expected = FindOptions::new();
+        expected.limit = Some(10);
+        expected.sort = Some(doc! { "year": -1 });
+        expected.allow_partial_results = true;
+        assert_eq!(expected, options);
+
+        assert_eq!(UpdateOptions::new(), UpdateOptions::builder().build());
+        assert_eq!(Some(true), UpdateOptions::builder().upsert(true).build().upsert);
+    }
+
+    #[test]
+    fn bypass_document_validation_is_serialized() {
+        let insert = InsertManyOptions::builder().bypass_document_validation(true).build();
+        let document = bson::Document::from(insert);
+        assert_eq!(Some(&Bson::Boolean(true)), document.get("bypassDocumentValidation"));
+
+        let find_one_and_update = FindOneAndUpdateOptions::builder()
+            .bypass_document_validation(false)
+            .build();
+        let document = bson::Document::from(find_one_and_update);
+        assert_eq!(Some(&Bson::Boolean(false)), document.get("bypassDocumentValidation"));
+
+        let aggregate = AggregateToCollectionOptions::builder()
+            .bypass_document_validation(true)
+            .build();
+        let document = bson::Document::from(aggregate);
+        assert_eq!(Some(&Bson::Boolean(true)), document.get("bypassDocumentValidation"));
+
+        let document = bson::Document::from(AggregateOptions::new());
+        assert!(!document.contains_key("bypassDocumentValidation"));
+    }
+
+    #[test]
+    fn comment_and_let_are_serialized() {
+        let update = UpdateOptions::builder()
+            .upsert(true)
+            .comment(Bson::from("nightly import"))
+            .let_vars(doc! { "cutoff": 10 })
+            .build();
+        assert_eq!(
+            doc! { "comment": "nightly import", "let": { "cutoff": 10 } },
+            bson::Document::from(update)
+        );
+
+        let delete = DeleteOptions::builder().comment(Bson::I32(7)).build();
+        assert_eq!(doc! { "comment": 7 }, bson::Document::from(delete));
+
+        let mut find = FindOptions::new();
+        find.comment = Some(Bson::from("legacy"));
+        assert!(!find.requires_find_command());
+        find.comment = Some(Bson::Document(doc! { "job": "import" }));
+        assert!(find.requires_find_command());
+    }
+
     #[test]
     fn serde_and_manual_serialization_should_match_with_defaults() {
         let keys = doc!{"test_field": -1};
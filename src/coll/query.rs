@@ -0,0 +1,324 @@
+//! Fluent builders for finds, updates and deletes.
+//!
+//! `Collection::query`, `Collection::update` and `Collection::delete` start a builder that
+//! collects a filter and options one call at a time, and sends the operation when it is run:
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! #
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! # let coll = client.db("test").collection("movies");
+//! #
+//! let cursor = coll.query()
+//!     .filter(doc! { "director": "Spielberg" })
+//!     .sort(doc! { "year": -1 })
+//!     .limit(10)
+//!     .run()
+//!     .unwrap();
+//!
+//! coll.update()
+//!     .filter(doc! { "title": "Jaws" })
+//!     .set("year", 1975)
+//!     .inc("views", 1)
+//!     .one()
+//!     .unwrap();
+//!
+//! coll.delete().filter(doc! { "year": { "$lt": 1950 } }).many().unwrap();
+//! # }
+//! ```
+//!
+//! Updates and deletes need a filter, so that leaving one out does not change every document;
+//! `all` asks for every document explicitly.
+use bson::{self, Bson};
+use serde::de::DeserializeOwned;
+
+use super::Collection;
+use super::options::{CountOptions, DeleteOptions, FindOptions, IntoDocument, UpdateModifications,
+                     UpdateOptions};
+use super::results::{DeleteResult, UpdateResult};
+use super::update;
+use common::WriteConcern;
+use cursor::{Cursor, TypedCursor};
+use Error::ArgumentError;
+use Result;
+
+/// A find, built by `Collection::query`.
+#[derive(Clone, Debug)]
+pub struct Query<'a> {
+    coll: &'a Collection,
+    filter: Option<bson::Document>,
+    options: FindOptions,
+}
+
+impl<'a> Query<'a> {
+    pub fn new(coll: &'a Collection) -> Query<'a> {
+        Query {
+            coll,
+            filter: None,
+            options: FindOptions::new(),
+        }
+    }
+
+    /// Matches only the documents that match `filter`.
+    pub fn filter(mut self, filter: impl IntoDocument) -> Self {
+        self.filter = Some(filter.into_document());
+        self
+    }
+
+    /// Returns the documents in the order given by `sort`.
+    pub fn sort(mut self, sort: impl IntoDocument) -> Self {
+        self.options.sort = Some(sort.into_document());
+        self
+    }
+
+    /// Returns at most `limit` documents.
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.options.limit = Some(limit);
+        self
+    }
+
+    /// Skips the first `skip` matching documents.
+    pub fn skip(mut self, skip: i64) -> Self {
+        self.options.skip = Some(skip);
+        self
+    }
+
+    /// Returns only the fields that `projection` includes.
+    pub fn projection(mut self, projection: impl IntoDocument) -> Self {
+        self.options.projection = Some(projection.into_document());
+        self
+    }
+
+    /// Fetches `batch_size` documents in each batch.
+    pub fn batch_size(mut self, batch_size: i32) -> Self {
+        self.options.batch_size = Some(batch_size);
+        self
+    }
+
+    /// Tags the find in the profiler, currentOp and server logs.
+    pub fn comment(mut self, comment: impl Into<Bson>) -> Self {
+        self.options.comment = Some(comment.into());
+        self
+    }
+
+    /// Replaces the options set so far, for options without a method of their own.
+    pub fn options(mut self, options: FindOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Runs the find, returning a cursor over the matching documents.
+    pub fn run(self) -> Result<Cursor> {
+        self.coll.find(self.filter, self.options)
+    }
+
+    /// Runs the find, deserializing each matching document into a `T`.
+    pub fn run_as<T: DeserializeOwned>(self) -> Result<TypedCursor<T>> {
+        self.run().map(Cursor::deserialize)
+    }
+
+    /// Returns the first matching document, if any.
+    pub fn one(self) -> Result<Option<bson::Document>> {
+        self.coll.find_one(self.filter, self.options)
+    }
+
+    /// Counts the matching documents, within the skip and limit set.
+    pub fn count(self) -> Result<i64> {
+        let options = CountOptions {
+            skip: self.options.skip,
+            limit: self.options.limit,
+            read_preference: self.options.read_preference,
+            timeout_ms: self.options.timeout_ms,
+            ..CountOptions::new()
+        };
+
+        self.coll.count(self.filter, options)
+    }
+}
+
+/// An update, built by `Collection::update`.
+///
+/// The update is either assembled from operators with methods such as `set` and `inc`, or given
+/// whole with `with`, such as an `update::Update`.
+#[derive(Clone, Debug)]
+pub struct Update<'a> {
+    coll: &'a Collection,
+    filter: Option<bson::Document>,
+    update: Option<UpdateModifications>,
+    options: UpdateOptions,
+}
+
+impl<'a> Update<'a> {
+    pub fn new(coll: &'a Collection) -> Update<'a> {
+        Update {
+            coll,
+            filter: None,
+            update: None,
+            options: UpdateOptions::new(),
+        }
+    }
+
+    /// Updates only the documents that match `filter`.
+    pub fn filter(mut self, filter: impl IntoDocument) -> Self {
+        self.filter = Some(filter.into_document());
+        self
+    }
+
+    /// Updates every document, in place of a filter.
+    pub fn all(mut self) -> Self {
+        self.filter = Some(bson::Document::new());
+        self
+    }
+
+    /// Sets the update to a document of operators or a pipeline, replacing any operators added
+    /// so far.
+    pub fn with(mut self, update: impl Into<UpdateModifications>) -> Self {
+        self.update = Some(update.into());
+        self
+    }
+
+    /// Sets `field` to `value`.
+    pub fn set(self, field: &str, value: impl Into<Bson>) -> Self {
+        self.operators(|update| update.set(field, value))
+    }
+
+    /// Increments `field` by `amount`.
+    pub fn inc(self, field: &str, amount: impl Into<Bson>) -> Self {
+        self.operators(|update| update.inc(field, amount))
+    }
+
+    /// Removes `field`.
+    pub fn unset(self, field: &str) -> Self {
+        self.operators(|update| update.unset(field))
+    }
+
+    /// Appends `value` to the array in `field`.
+    pub fn push(self, field: &str, value: impl Into<Bson>) -> Self {
+        self.operators(|update| update.push(field, value))
+    }
+
+    /// Inserts a document if none match the filter.
+    pub fn upsert(mut self, upsert: bool) -> Self {
+        self.options.upsert = Some(upsert);
+        self
+    }
+
+    /// Tags the update in the profiler, currentOp and server logs.
+    pub fn comment(mut self, comment: impl Into<Bson>) -> Self {
+        self.options.comment = Some(comment.into());
+        self
+    }
+
+    /// Makes `let_vars` accessible to the filter and an update pipeline as `$$<name>`.
+    pub fn let_vars(mut self, let_vars: bson::Document) -> Self {
+        self.options.let_vars = Some(let_vars);
+        self
+    }
+
+    /// Acknowledges the update according to `write_concern` instead of the collection's.
+    pub fn write_concern(mut self, write_concern: WriteConcern) -> Self {
+        self.options.write_concern = Some(write_concern);
+        self
+    }
+
+    /// Updates the first matching document.
+    pub fn one(self) -> Result<UpdateResult> {
+        let filter = required_filter(self.filter)?;
+        let update = Update::modifications(self.update)?;
+        self.coll.update_one(filter, update, self.options)
+    }
+
+    /// Updates every matching document.
+    pub fn many(self) -> Result<UpdateResult> {
+        let filter = required_filter(self.filter)?;
+        let update = Update::modifications(self.update)?;
+        self.coll.update_many(filter, update, self.options)
+    }
+
+    // Adds an operator to the update with `add`, starting an operator update if a pipeline was
+    // given before.
+    fn operators(mut self, add: impl FnOnce(update::Update) -> update::Update) -> Self {
+        let operators = match self.update.take() {
+            Some(UpdateModifications::Document(update)) => update::Update::from(update),
+            _ => update::Update::new(),
+        };
+
+        self.update = Some(add(operators).into());
+        self
+    }
+
+    fn modifications(update: Option<UpdateModifications>) -> Result<UpdateModifications> {
+        update.ok_or_else(|| ArgumentError(String::from("No update was given.")))
+    }
+}
+
+/// A delete, built by `Collection::delete`.
+#[derive(Clone, Debug)]
+pub struct Delete<'a> {
+    coll: &'a Collection,
+    filter: Option<bson::Document>,
+    options: DeleteOptions,
+}
+
+impl<'a> Delete<'a> {
+    pub fn new(coll: &'a Collection) -> Delete<'a> {
+        Delete {
+            coll,
+            filter: None,
+            options: DeleteOptions::new(),
+        }
+    }
+
+    /// Deletes only the documents that match `filter`.
+    pub fn filter(mut self, filter: impl IntoDocument) -> Self {
+        self.filter = Some(filter.into_document());
+        self
+    }
+
+    /// Deletes every document, in place of a filter.
+    pub fn all(mut self) -> Self {
+        self.filter = Some(bson::Document::new());
+        self
+    }
+
+    /// Tags the delete in the profiler, currentOp and server logs.
+    pub fn comment(mut self, comment: impl Into<Bson>) -> Self {
+        self.options.comment = Some(comment.into());
+        self
+    }
+
+    /// Makes `let_vars` accessible to the filter as `$$<name>`.
+    pub fn let_vars(mut self, let_vars: bson::Document) -> Self {
+        self.options.let_vars = Some(let_vars);
+        self
+    }
+
+    /// Acknowledges the delete according to `write_concern` instead of the collection's.
+    pub fn write_concern(mut self, write_concern: WriteConcern) -> Self {
+        self.options.write_concern = Some(write_concern);
+        self
+    }
+
+    /// Deletes the first matching document.
+    pub fn one(self) -> Result<DeleteResult> {
+        let filter = required_filter(self.filter)?;
+        self.coll.delete_one_with_options(filter, self.options)
+    }
+
+    /// Deletes every matching document.
+    pub fn many(self) -> Result<DeleteResult> {
+        let filter = required_filter(self.filter)?;
+        self.coll.delete_many_with_options(filter, self.options)
+    }
+}
+
+// Returns the filter of an update or delete, which must be given or asked to match everything.
+fn required_filter(filter: Option<bson::Document>) -> Result<bson::Document> {
+    filter.ok_or_else(|| {
+        ArgumentError(String::from("No filter was given; use all() to match every document."))
+    })
+}
@@ -2,8 +2,11 @@
 use bson;
 use bson::Bson;
 use std::collections::BTreeMap;
+use std::time::Duration;
+use super::Collection;
 use super::error::{BulkWriteException, WriteException};
 use super::options::WriteModel;
+use cursor::Cursor;
 
 /// Results for a bulk write operation.
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -17,6 +20,194 @@ pub struct BulkWriteResult {
     pub upserted_count: i32,
     pub upserted_ids: BTreeMap<i64, Bson>,
     pub bulk_write_exception: Option<BulkWriteException>,
+    /// The reply to each batch sent, if `verbose_results` was set.
+    pub batch_replies: Option<Vec<BatchReply>>,
+}
+
+/// The server's reply to one batch of a bulk write.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchReply {
+    /// The index of the batch's first write in the requests.
+    pub start_index: i64,
+    /// The number of writes in the batch.
+    pub len: i64,
+    /// The server's reply, or None if the batch failed without one.
+    pub reply: Option<bson::Document>,
+    /// The time from sending the batch to receiving the reply.
+    pub duration: Duration,
+}
+
+/// A report on a running `aggregate_to_collection`, read from `currentOp`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateProgress {
+    /// How long the aggregation has been running, as reported by the server.
+    pub secs_running: i64,
+    /// The number of documents the aggregation has examined so far, if the server reports it.
+    pub docs_examined: Option<i64>,
+    /// The operation's full `currentOp` entry.
+    pub op: bson::Document,
+}
+
+impl AggregateProgress {
+    /// Reads the progress of an aggregation from its `currentOp` entry.
+    pub fn from_current_op(op: bson::Document) -> AggregateProgress {
+        let docs_examined = match op.get("docsExamined") {
+            Some(&Bson::I32(n)) => Some(i64::from(n)),
+            Some(&Bson::I64(n)) => Some(n),
+            _ => None,
+        };
+
+        AggregateProgress {
+            secs_running: i64::from(reply_count(&op, "secs_running")),
+            docs_examined,
+            op,
+        }
+    }
+}
+
+/// A stage of a query plan, as reported by `explain`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStage {
+    /// The kind of stage, such as `COLLSCAN`, `IXSCAN` or `FETCH`.
+    pub stage: String,
+    /// The index an index scan reads.
+    pub index_name: Option<String>,
+    /// The key pattern of the index an index scan reads.
+    pub key_pattern: Option<bson::Document>,
+    /// The stages this one reads from. On a sharded cluster, the plans of the shards are the
+    /// children of the merging stage.
+    pub children: Vec<PlanStage>,
+}
+
+impl PlanStage {
+    /// Reads a stage and its children from an explained plan.
+    pub fn from_document(plan: &bson::Document) -> PlanStage {
+        // Servers using the slot-based engine wrap the classic stage tree in `queryPlan`.
+        if let Ok(query_plan) = plan.get_document("queryPlan") {
+            return PlanStage::from_document(query_plan);
+        }
+
+        let mut children = Vec::new();
+
+        if let Ok(input) = plan.get_document("inputStage") {
+            children.push(PlanStage::from_document(input));
+        }
+
+        for key in &["inputStages", "shards"] {
+            if let Ok(inputs) = plan.get_array(key) {
+                for input in inputs {
+                    if let Bson::Document(ref input) = *input {
+                        let input = input.get_document("winningPlan").unwrap_or(input);
+                        children.push(PlanStage::from_document(input));
+                    }
+                }
+            }
+        }
+
+        PlanStage {
+            stage: plan.get_str("stage").unwrap_or_default().to_owned(),
+            index_name: plan.get_str("indexName").ok().map(String::from),
+            key_pattern: plan.get_document("keyPattern").ok().cloned(),
+            children,
+        }
+    }
+
+    /// Returns the names of the indexes read by this stage and its children.
+    pub fn index_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.index_name.iter().cloned().collect();
+
+        for child in &self.children {
+            for name in child.index_names() {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        names
+    }
+
+    /// Whether this stage or any of its children scans the whole collection.
+    pub fn has_collection_scan(&self) -> bool {
+        self.stage == "COLLSCAN" || self.children.iter().any(PlanStage::has_collection_scan)
+    }
+}
+
+/// The work done to run an explained operation, reported with the `ExecutionStats` and
+/// `AllPlansExecution` verbosities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainExecutionStats {
+    pub n_returned: i64,
+    pub execution_time_millis: i64,
+    pub total_keys_examined: i64,
+    pub total_docs_examined: i64,
+}
+
+/// A summary of the reply to `explain`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainSummary {
+    /// The plan the query planner chose, absent for aggregations that run no query, such as
+    /// those starting with `$collStats`.
+    pub winning_plan: Option<PlanStage>,
+    /// The names of the indexes the winning plan reads.
+    pub index_names: Vec<String>,
+    pub execution_stats: Option<ExplainExecutionStats>,
+    /// The full reply, for details the summary does not cover.
+    pub raw: bson::Document,
+}
+
+impl ExplainSummary {
+    /// Summarizes the reply to `explain`.
+    pub fn from_document(raw: bson::Document) -> ExplainSummary {
+        let (winning_plan, execution_stats) = {
+            // Aggregations whose first stages run as a query report it in a `$cursor` stage.
+            let source = raw
+                .get_array("stages")
+                .ok()
+                .and_then(|stages| stages.first())
+                .and_then(Bson::as_document)
+                .and_then(|first| first.get_document("$cursor").ok())
+                .unwrap_or(&raw);
+
+            let winning_plan = source
+                .get_document("queryPlanner")
+                .and_then(|planner| planner.get_document("winningPlan"))
+                .ok()
+                .map(PlanStage::from_document);
+
+            let execution_stats = source.get_document("executionStats").ok().map(|stats| {
+                ExplainExecutionStats {
+                    n_returned: explain_count(stats, "nReturned"),
+                    execution_time_millis: explain_count(stats, "executionTimeMillis"),
+                    total_keys_examined: explain_count(stats, "totalKeysExamined"),
+                    total_docs_examined: explain_count(stats, "totalDocsExamined"),
+                }
+            });
+
+            (winning_plan, execution_stats)
+        };
+
+        let index_names = match winning_plan {
+            Some(ref plan) => plan.index_names(),
+            None => Vec::new(),
+        };
+
+        ExplainSummary {
+            winning_plan,
+            index_names,
+            execution_stats,
+            raw,
+        }
+    }
+}
+
+/// The results of a map-reduce.
+#[derive(Debug)]
+pub enum MapReduceResult {
+    /// The results of an inline map-reduce.
+    Inline(Cursor),
+    /// The collection a map-reduce wrote its results to.
+    Collection(Collection),
 }
 
 /// Results for a bulk delete operation.
@@ -84,6 +275,7 @@ impl BulkWriteResult {
             upserted_count: 0,
             upserted_ids: BTreeMap::new(),
             bulk_write_exception: None,
+            batch_replies: None,
         }
     }
 
@@ -196,47 +388,57 @@ impl BulkWriteResult {
 impl BulkDeleteResult {
     /// Extracts server reply information into a result.
     pub fn new(doc: bson::Document, exception: Option<BulkWriteException>) -> BulkDeleteResult {
-        let n = match doc.get("n") {
-            Some(&Bson::I32(n)) => n,
-            _ => 0,
-        };
-
         BulkDeleteResult {
             acknowledged: true,
-            deleted_count: n,
+            deleted_count: reply_count(&doc, "n"),
             write_exception: exception,
         }
     }
+
+    /// A placeholder result for a delete sent with a `w: 0` write concern, which the server
+    /// does not reply to.
+    pub fn unacknowledged() -> BulkDeleteResult {
+        BulkDeleteResult {
+            acknowledged: false,
+            deleted_count: 0,
+            write_exception: None,
+        }
+    }
 }
 
 impl BulkUpdateResult {
     /// Extracts server reply information into a result.
-    pub fn new(doc: bson::Document, exception: Option<BulkWriteException>) -> BulkUpdateResult {
-        let n = match doc.get("n") {
-            Some(&Bson::I32(n)) => n,
-            _ => 0,
-        };
+    pub fn new(
+        mut doc: bson::Document,
+        exception: Option<BulkWriteException>,
+    ) -> BulkUpdateResult {
+        let n = reply_count(&doc, "n");
 
-        let (n_upserted, id) = match doc.get("upserted") {
-            Some(&Bson::Array(ref arr)) => (arr.len() as i32, Some(arr[0].clone())),
+        let (n_upserted, upserted) = match doc.remove("upserted") {
+            Some(Bson::Array(arr)) => (arr.len() as i32, Some(Bson::Array(arr))),
             _ => (0, None),
         };
 
-        let n_matched = n - n_upserted;
-
-        let n_modified = match doc.get("nModified") {
-            Some(&Bson::I32(n)) => n,
-            _ => 0,
-        };
-
         BulkUpdateResult {
             acknowledged: true,
-            matched_count: n_matched,
-            modified_count: n_modified,
-            upserted_ids: id,
+            matched_count: n - n_upserted,
+            modified_count: reply_count(&doc, "nModified"),
+            upserted_ids: upserted,
             write_exception: exception,
         }
     }
+
+    /// A placeholder result for an update sent with a `w: 0` write concern, which the server
+    /// does not reply to.
+    pub fn unacknowledged() -> BulkUpdateResult {
+        BulkUpdateResult {
+            acknowledged: false,
+            matched_count: 0,
+            modified_count: 0,
+            upserted_ids: None,
+            write_exception: None,
+        }
+    }
 }
 
 impl InsertOneResult {
@@ -267,14 +469,9 @@ impl InsertManyResult {
 impl DeleteResult {
     /// Extracts server reply information into a result.
     pub fn new(doc: bson::Document, exception: Option<WriteException>) -> DeleteResult {
-        let n = match doc.get("n") {
-            Some(&Bson::I32(n)) => n,
-            _ => 0,
-        };
-
         DeleteResult {
             acknowledged: true,
-            deleted_count: n,
+            deleted_count: reply_count(&doc, "n"),
             write_exception: exception,
         }
     }
@@ -296,28 +493,13 @@ impl DeleteResult {
 impl UpdateResult {
     /// Extracts server reply information into a result.
     pub fn new(doc: bson::Document, exception: Option<WriteException>) -> UpdateResult {
-        let n = match doc.get("n") {
-            Some(&Bson::I32(n)) => n,
-            _ => 0,
-        };
-
-        let (n_upserted, id) = match doc.get("upserted") {
-            Some(&Bson::Array(ref arr)) => (arr.len() as i32, Some(arr[0].clone())),
-            _ => (0, None),
-        };
-
-        let n_matched = n - n_upserted;
-
-        let n_modified = match doc.get("nModified") {
-            Some(&Bson::I32(n)) => n,
-            _ => 0,
-        };
+        let result = BulkUpdateResult::new(doc, None);
 
         UpdateResult {
-            acknowledged: true,
-            matched_count: n_matched,
-            modified_count: n_modified,
-            upserted_id: id,
+            acknowledged: result.acknowledged,
+            matched_count: result.matched_count,
+            modified_count: result.modified_count,
+            upserted_id: result.upserted_ids.and_then(first_upserted_id),
             write_exception: exception,
         }
     }
@@ -332,8 +514,36 @@ impl UpdateResult {
             acknowledged: result.acknowledged,
             matched_count: result.matched_count,
             modified_count: result.modified_count,
-            upserted_id: result.upserted_ids,
+            upserted_id: result.upserted_ids.and_then(first_upserted_id),
             write_exception: exception,
         }
     }
 }
+
+// Reads a count from a write command reply, which servers may encode as any integer type.
+fn reply_count(doc: &bson::Document, key: &str) -> i32 {
+    match doc.get(key) {
+        Some(&Bson::I32(n)) => n,
+        Some(&Bson::I64(n)) => n as i32,
+        _ => 0,
+    }
+}
+
+// Returns the id of the first document in an `upserted` array of `{ index, _id }` documents.
+fn first_upserted_id(upserted: Bson) -> Option<Bson> {
+    match upserted {
+        Bson::Array(arr) => arr.into_iter().next().and_then(first_upserted_id),
+        Bson::Document(mut doc) => doc.remove("_id"),
+        _ => None,
+    }
+}
+
+// Reads a statistic from an explain reply, which servers may encode as any numeric type.
+fn explain_count(doc: &bson::Document, key: &str) -> i64 {
+    match doc.get(key).cloned() {
+        Some(Bson::I32(n)) => i64::from(n),
+        Some(Bson::I64(n)) => n,
+        Some(Bson::FloatingPoint(n)) => n as i64,
+        _ => 0,
+    }
+}
@@ -0,0 +1,175 @@
+//! Builders for aggregation pipeline stages.
+//!
+//! Each function returns one `Stage`, and stages collect into a `Pipeline`:
+//!
+//! ```
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::coll::{filter, stage};
+//! # fn main() {
+//! let pipeline = stage::match_(filter::gt("year", 1980))
+//!     .then(stage::unwind("tags"))
+//!     .then(stage::group("$tags", doc! { "count": { "$sum": 1 } }))
+//!     .then(stage::sort(doc! { "count": -1 }))
+//!     .then(stage::limit(3));
+//!
+//! assert_eq!(
+//!     vec![
+//!         doc! { "$match": { "year": { "$gt": 1980 } } },
+//!         doc! { "$unwind": "$tags" },
+//!         doc! { "$group": { "_id": "$tags", "count": { "$sum": 1 } } },
+//!         doc! { "$sort": { "count": -1 } },
+//!         doc! { "$limit": 3i64 },
+//!     ],
+//!     pipeline.into_documents()
+//! );
+//! # }
+//! ```
+//!
+//! A `Pipeline` can be passed anywhere a collection method takes a pipeline, as can a plain
+//! `Vec` of stage documents.
+use bson::{self, Bson, doc};
+
+use std::iter::FromIterator;
+
+use super::options::IntoDocument;
+
+/// A single stage of an aggregation pipeline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stage(bson::Document);
+
+impl Stage {
+    /// Starts a pipeline with this stage followed by `next`.
+    pub fn then(self, next: Stage) -> Pipeline {
+        Pipeline(vec![self.0, next.0])
+    }
+
+    /// Returns the stage as a document.
+    pub fn into_document(self) -> bson::Document {
+        self.0
+    }
+}
+
+impl From<Stage> for bson::Document {
+    fn from(stage: Stage) -> Self {
+        stage.0
+    }
+}
+
+/// An aggregation pipeline.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pipeline(Vec<bson::Document>);
+
+impl Pipeline {
+    /// A pipeline without any stages, which returns every document unchanged.
+    pub fn new() -> Pipeline {
+        Pipeline(Vec::new())
+    }
+
+    /// Adds `stage` to the end of the pipeline.
+    pub fn then(mut self, stage: Stage) -> Pipeline {
+        self.0.push(stage.0);
+        self
+    }
+
+    /// Returns the stages of the pipeline as documents.
+    pub fn into_documents(self) -> Vec<bson::Document> {
+        self.0
+    }
+}
+
+impl From<Stage> for Pipeline {
+    fn from(stage: Stage) -> Self {
+        Pipeline(vec![stage.0])
+    }
+}
+
+impl From<Vec<bson::Document>> for Pipeline {
+    fn from(stages: Vec<bson::Document>) -> Self {
+        Pipeline(stages)
+    }
+}
+
+impl From<Pipeline> for Vec<bson::Document> {
+    fn from(pipeline: Pipeline) -> Self {
+        pipeline.0
+    }
+}
+
+impl From<Pipeline> for Bson {
+    fn from(pipeline: Pipeline) -> Self {
+        Bson::Array(pipeline.0.into_iter().map(Bson::Document).collect())
+    }
+}
+
+impl FromIterator<Stage> for Pipeline {
+    fn from_iter<I: IntoIterator<Item = Stage>>(stages: I) -> Self {
+        Pipeline(stages.into_iter().map(|stage| stage.0).collect())
+    }
+}
+
+/// Passes on only the documents that match `filter`.
+pub fn match_(filter: impl IntoDocument) -> Stage {
+    Stage(doc! { "$match": filter.into_document() })
+}
+
+/// Groups documents by the value of the expression `id`, computing each field of `fields` with
+/// an accumulator such as `{ "$sum": 1 }`.
+pub fn group(id: impl Into<Bson>, fields: bson::Document) -> Stage {
+    let mut group = doc! { "_id": id.into() };
+    for (key, value) in fields {
+        group.insert(key, value);
+    }
+    Stage(doc! { "$group": group })
+}
+
+/// Reshapes each document to the fields included or computed by `projection`.
+pub fn project(projection: impl IntoDocument) -> Stage {
+    Stage(doc! { "$project": projection.into_document() })
+}
+
+/// Orders the documents by `sort`.
+pub fn sort(sort: impl IntoDocument) -> Stage {
+    Stage(doc! { "$sort": sort.into_document() })
+}
+
+/// Passes on only the first `limit` documents.
+pub fn limit(limit: i64) -> Stage {
+    Stage(doc! { "$limit": limit })
+}
+
+/// Skips the first `skip` documents.
+pub fn skip(skip: i64) -> Stage {
+    Stage(doc! { "$skip": skip })
+}
+
+/// Adds to each document an array named `as_`, holding the documents of the collection `from`
+/// whose `foreign_field` equals the document's `local_field`.
+pub fn lookup(from: &str, local_field: &str, foreign_field: &str, as_: &str) -> Stage {
+    Stage(doc! {
+        "$lookup": {
+            "from": from,
+            "localField": local_field,
+            "foreignField": foreign_field,
+            "as": as_,
+        }
+    })
+}
+
+/// Replaces each document with one copy per element of the array in `field`, holding that
+/// element in place of the array. `field` is a field name, without the leading `$`.
+pub fn unwind(field: &str) -> Stage {
+    Stage(doc! { "$unwind": format!("${}", field) })
+}
+
+/// Writes the documents to the collection `coll`, replacing its contents. Must be the last
+/// stage of the pipeline.
+pub fn out(coll: &str) -> Stage {
+    Stage(doc! { "$out": coll })
+}
+
+/// Merges the documents into the collection `coll`, replacing documents with the same `_id` and
+/// inserting the rest. Must be the last stage of the pipeline. Requires MongoDB 4.2 or later.
+pub fn merge(coll: &str) -> Stage {
+    Stage(doc! { "$merge": { "into": coll } })
+}
@@ -0,0 +1,176 @@
+//! Tailing capped collections.
+use bson::{self, Bson, doc};
+
+use super::Collection;
+use super::options::{CursorType, FindOptions, TailOptions};
+use cursor::Cursor;
+use error::ErrorCode;
+use Error::{self, OperationError};
+use Result;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_RETRY_INTERVAL_MS: u64 = 1000;
+
+/// A blocking iterator over the documents of a capped collection, created by
+/// `Collection::tail`.
+///
+/// Each call to `next` waits until a document arrives, or until the idle timeout passes, after
+/// which iteration ends. Errors other than lost cursor positions are returned as they occur,
+/// and iteration can continue past them.
+#[derive(Debug)]
+pub struct Tail {
+    coll: Collection,
+    filter: bson::Document,
+    options: TailOptions,
+    resume_field: String,
+    // The resume field's value in the last document returned.
+    last: Option<Bson>,
+    cursor: Option<Cursor>,
+    // When the last document was returned, or the tail was created.
+    last_active: Instant,
+}
+
+impl Tail {
+    pub fn new(coll: Collection, filter: bson::Document, options: TailOptions) -> Tail {
+        Tail {
+            coll,
+            filter,
+            resume_field: options.resume_field.clone().unwrap_or_else(|| String::from("_id")),
+            last: options.resume_after.clone(),
+            options,
+            cursor: None,
+            last_active: Instant::now(),
+        }
+    }
+
+    /// Returns the collection being tailed.
+    pub fn collection(&self) -> &Collection {
+        &self.coll
+    }
+
+    /// Returns the resume field's value in the last document returned, from which a later tail
+    /// can continue by setting `TailOptions::resume_after`.
+    pub fn last_position(&self) -> Option<&Bson> {
+        self.last.as_ref()
+    }
+
+    // Opens a cursor on the documents after the last one returned.
+    fn open(&self) -> Result<Cursor> {
+        let filter = match self.last {
+            Some(ref last) => {
+                let after = doc! { self.resume_field.clone(): { "$gt": last.clone() } };
+
+                if self.filter.is_empty() {
+                    after
+                } else {
+                    doc! { "$and": [self.filter.clone(), after] }
+                }
+            }
+            None => self.filter.clone(),
+        };
+
+        let mut options = FindOptions::new();
+        options.cursor_type = CursorType::TailableAwait;
+        options.oplog_replay = self.options.oplog_replay;
+        options.batch_size = self.options.batch_size;
+        options.projection = self.options.projection.clone();
+        options.read_preference = self.options.read_preference.clone();
+        // Tailing continues indefinitely, so the client's default timeout does not apply.
+        options.timeout_ms = Some(0);
+
+        self.coll.find(Some(filter), Some(options))
+    }
+
+    fn is_idle(&self) -> bool {
+        match self.options.idle_timeout_ms {
+            Some(ms) => self.last_active.elapsed() >= Duration::from_millis(ms),
+            None => false,
+        }
+    }
+
+    // Waits before reopening a closed cursor, unless that would outlast the idle timeout.
+    fn wait_to_reopen(&self) -> bool {
+        let interval = Duration::from_millis(
+            self.options.retry_interval_ms.unwrap_or(DEFAULT_RETRY_INTERVAL_MS),
+        );
+
+        if let Some(ms) = self.options.idle_timeout_ms {
+            if self.last_active.elapsed() + interval > Duration::from_millis(ms) {
+                return false;
+            }
+        }
+
+        thread::sleep(interval);
+        true
+    }
+}
+
+impl Iterator for Tail {
+    type Item = Result<bson::Document>;
+
+    fn next(&mut self) -> Option<Result<bson::Document>> {
+        loop {
+            let mut cursor = match self.cursor.take() {
+                Some(cursor) => cursor,
+                None => {
+                    match self.open() {
+                        Ok(cursor) => cursor,
+                        Err(err) => return Some(Err(err)),
+                    }
+                }
+            };
+
+            match cursor.next() {
+                Some(Ok(doc)) => {
+                    // Legacy getMore replies report errors as documents.
+                    if let Ok(message) = doc.get_str("$err") {
+                        if doc.get_i32("code") == Ok(ErrorCode::CappedPositionLost as i32) {
+                            continue;
+                        }
+                        return Some(Err(OperationError(String::from(message))));
+                    }
+
+                    if let Some(value) = doc.get(&self.resume_field) {
+                        self.last = Some(value.clone());
+                    }
+
+                    self.cursor = Some(cursor);
+                    self.last_active = Instant::now();
+                    return Some(Ok(doc));
+                }
+                Some(Err(err)) => {
+                    if is_position_lost(&err) {
+                        continue;
+                    }
+                    return Some(Err(err));
+                }
+                None => {
+                    if self.is_idle() {
+                        return None;
+                    }
+
+                    // A getMore that waited without new documents leaves the cursor open.
+                    if cursor.id() != 0 {
+                        self.cursor = Some(cursor);
+                    } else if !self.wait_to_reopen() {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Whether an error reports that the cursor's position was overwritten or its cursor was lost.
+fn is_position_lost(err: &Error) -> bool {
+    match *err {
+        Error::ServerError(ref err) => {
+            err.code == ErrorCode::CappedPositionLost as i32 ||
+                err.code == ErrorCode::CursorNotFound as i32
+        }
+        Error::CursorNotFoundError => true,
+        _ => false,
+    }
+}
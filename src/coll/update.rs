@@ -0,0 +1,177 @@
+//! Builders for update documents.
+//!
+//! Each function starts an `Update` with one operator, and each method adds another:
+//!
+//! ```
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::coll::update;
+//! # fn main() {
+//! let update = update::set("x", 3).inc("n", 1).push("tags", "new");
+//!
+//! assert_eq!(
+//!     doc! { "$set": { "x": 3 }, "$inc": { "n": 1 }, "$push": { "tags": "new" } },
+//!     update.into_document()
+//! );
+//! # }
+//! ```
+//!
+//! An `Update` can be passed anywhere a collection method takes an update.
+use bson::{self, Bson};
+
+use super::options::UpdateModifications;
+
+/// A document of update operators.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Update(bson::Document);
+
+impl Update {
+    /// An update without any operators, to add them to.
+    pub fn new() -> Update {
+        Update(bson::Document::new())
+    }
+
+    /// Sets `field` to `value`.
+    pub fn set(self, field: &str, value: impl Into<Bson>) -> Update {
+        self.operator("$set", field, value.into())
+    }
+
+    /// Removes `field`.
+    pub fn unset(self, field: &str) -> Update {
+        self.operator("$unset", field, Bson::String(String::new()))
+    }
+
+    /// Increments `field` by `amount`.
+    pub fn inc(self, field: &str, amount: impl Into<Bson>) -> Update {
+        self.operator("$inc", field, amount.into())
+    }
+
+    /// Multiplies `field` by `factor`.
+    pub fn mul(self, field: &str, factor: impl Into<Bson>) -> Update {
+        self.operator("$mul", field, factor.into())
+    }
+
+    /// Sets `field` to `value` if `value` is less than its current value.
+    pub fn min(self, field: &str, value: impl Into<Bson>) -> Update {
+        self.operator("$min", field, value.into())
+    }
+
+    /// Sets `field` to `value` if `value` is greater than its current value.
+    pub fn max(self, field: &str, value: impl Into<Bson>) -> Update {
+        self.operator("$max", field, value.into())
+    }
+
+    /// Renames `field` to `new_name`.
+    pub fn rename(self, field: &str, new_name: &str) -> Update {
+        self.operator("$rename", field, Bson::String(String::from(new_name)))
+    }
+
+    /// Sets `field` to the current date.
+    pub fn current_date(self, field: &str) -> Update {
+        self.operator("$currentDate", field, Bson::Boolean(true))
+    }
+
+    /// Appends `value` to the array in `field`.
+    pub fn push(self, field: &str, value: impl Into<Bson>) -> Update {
+        self.operator("$push", field, value.into())
+    }
+
+    /// Appends `value` to the array in `field` unless the array already holds it.
+    pub fn add_to_set(self, field: &str, value: impl Into<Bson>) -> Update {
+        self.operator("$addToSet", field, value.into())
+    }
+
+    /// Removes every element equal to `value` from the array in `field`.
+    pub fn pull(self, field: &str, value: impl Into<Bson>) -> Update {
+        self.operator("$pull", field, value.into())
+    }
+
+    /// Returns the update as a document.
+    pub fn into_document(self) -> bson::Document {
+        self.0
+    }
+
+    // Adds `field: value` to the document of `op`.
+    fn operator(mut self, op: &str, field: &str, value: Bson) -> Update {
+        let mut fields = match self.0.remove(op) {
+            Some(Bson::Document(fields)) => fields,
+            _ => bson::Document::new(),
+        };
+        fields.insert(field, value);
+        self.0.insert(op, fields);
+        self
+    }
+}
+
+impl From<bson::Document> for Update {
+    fn from(update: bson::Document) -> Self {
+        Update(update)
+    }
+}
+
+impl From<Update> for bson::Document {
+    fn from(update: Update) -> Self {
+        update.0
+    }
+}
+
+impl From<Update> for UpdateModifications {
+    fn from(update: Update) -> Self {
+        UpdateModifications::Document(update.0)
+    }
+}
+
+/// Starts an update that sets `field` to `value`.
+pub fn set(field: &str, value: impl Into<Bson>) -> Update {
+    Update::new().set(field, value)
+}
+
+/// Starts an update that removes `field`.
+pub fn unset(field: &str) -> Update {
+    Update::new().unset(field)
+}
+
+/// Starts an update that increments `field` by `amount`.
+pub fn inc(field: &str, amount: impl Into<Bson>) -> Update {
+    Update::new().inc(field, amount)
+}
+
+/// Starts an update that multiplies `field` by `factor`.
+pub fn mul(field: &str, factor: impl Into<Bson>) -> Update {
+    Update::new().mul(field, factor)
+}
+
+/// Starts an update that lowers `field` to `value`.
+pub fn min(field: &str, value: impl Into<Bson>) -> Update {
+    Update::new().min(field, value)
+}
+
+/// Starts an update that raises `field` to `value`.
+pub fn max(field: &str, value: impl Into<Bson>) -> Update {
+    Update::new().max(field, value)
+}
+
+/// Starts an update that renames `field` to `new_name`.
+pub fn rename(field: &str, new_name: &str) -> Update {
+    Update::new().rename(field, new_name)
+}
+
+/// Starts an update that sets `field` to the current date.
+pub fn current_date(field: &str) -> Update {
+    Update::new().current_date(field)
+}
+
+/// Starts an update that appends `value` to the array in `field`.
+pub fn push(field: &str, value: impl Into<Bson>) -> Update {
+    Update::new().push(field, value)
+}
+
+/// Starts an update that adds `value` to the set in `field`.
+pub fn add_to_set(field: &str, value: impl Into<Bson>) -> Update {
+    Update::new().add_to_set(field, value)
+}
+
+/// Starts an update that removes `value` from the array in `field`.
+pub fn pull(field: &str, value: impl Into<Bson>) -> Update {
+    Update::new().pull(field, value)
+}
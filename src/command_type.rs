@@ -5,6 +5,7 @@
 pub enum CommandType {
     Aggregate,
     BuildInfo,
+    CollMod,
     Count,
     CreateCollection,
     CreateIndexes,
@@ -29,6 +30,8 @@ pub enum CommandType {
     ListCollections,
     ListDatabases,
     ListIndexes,
+    RenameCollection,
+    RunCommand,
     Suppressed,
     UpdateMany,
     UpdateOne,
@@ -39,6 +42,7 @@ impl CommandType {
         match *self {
             CommandType::Aggregate => "aggregate",
             CommandType::BuildInfo => "buildinfo",
+            CommandType::CollMod => "coll_mod",
             CommandType::Count => "count",
             CommandType::CreateCollection => "create_collection",
             CommandType::CreateIndexes => "create_indexes",
@@ -63,14 +67,88 @@ impl CommandType {
             CommandType::ListCollections => "list_collections",
             CommandType::ListDatabases => "list_databases",
             CommandType::ListIndexes => "list_indexes",
+            CommandType::RenameCollection => "rename_collection",
+            CommandType::RunCommand => "run_command",
             CommandType::Suppressed => "suppressed",
             CommandType::UpdateMany => "update_many",
             CommandType::UpdateOne => "update_one",
         }
     }
 
+    /// Returns the command type of a database command, given the command's name as the first key
+    /// of its specification. Commands without a more specific type are `RunCommand`.
+    pub fn from_command_name(name: &str) -> CommandType {
+        match name {
+            "aggregate" => CommandType::Aggregate,
+            "buildInfo" | "buildinfo" => CommandType::BuildInfo,
+            "collMod" => CommandType::CollMod,
+            "count" => CommandType::Count,
+            "create" => CommandType::CreateCollection,
+            "createIndexes" => CommandType::CreateIndexes,
+            "createUser" => CommandType::CreateUser,
+            "delete" => CommandType::DeleteMany,
+            "distinct" => CommandType::Distinct,
+            "dropAllUsersFromDatabase" => CommandType::DropAllUsers,
+            "drop" => CommandType::DropCollection,
+            "dropDatabase" => CommandType::DropDatabase,
+            "dropIndexes" | "deleteIndexes" => CommandType::DropIndexes,
+            "dropUser" => CommandType::DropUser,
+            "find" => CommandType::Find,
+            "findAndModify" | "findandmodify" => CommandType::FindOneAndUpdate,
+            "usersInfo" => CommandType::GetUsers,
+            "insert" => CommandType::InsertMany,
+            "isMaster" | "ismaster" => CommandType::IsMaster,
+            "listCollections" => CommandType::ListCollections,
+            "listDatabases" => CommandType::ListDatabases,
+            "listIndexes" => CommandType::ListIndexes,
+            "renameCollection" => CommandType::RenameCollection,
+            "update" => CommandType::UpdateMany,
+            _ => CommandType::RunCommand,
+        }
+    }
+
+    /// Whether federated endpoints, which serve queries over archived or external data, can
+    /// execute commands of this type.
+    pub fn is_federated_supported(&self) -> bool {
+        match *self {
+            CommandType::CollMod |
+            CommandType::CreateIndexes |
+            CommandType::CreateUser |
+            CommandType::DeleteMany |
+            CommandType::DeleteOne |
+            CommandType::DropAllUsers |
+            CommandType::DropIndexes |
+            CommandType::DropUser |
+            CommandType::FindOneAndDelete |
+            CommandType::FindOneAndReplace |
+            CommandType::FindOneAndUpdate |
+            CommandType::GetUser |
+            CommandType::GetUsers |
+            CommandType::InsertMany |
+            CommandType::InsertOne |
+            CommandType::RenameCollection |
+            CommandType::UpdateMany |
+            CommandType::UpdateOne => false,
+            CommandType::Aggregate |
+            CommandType::BuildInfo |
+            CommandType::Count |
+            CommandType::CreateCollection |
+            CommandType::Distinct |
+            CommandType::DropCollection |
+            CommandType::DropDatabase |
+            CommandType::Find |
+            CommandType::IsMaster |
+            CommandType::ListCollections |
+            CommandType::ListDatabases |
+            CommandType::ListIndexes |
+            CommandType::RunCommand |
+            CommandType::Suppressed => true,
+        }
+    }
+
     pub fn is_write_command(&self) -> bool {
         match *self {
+            CommandType::CollMod |
             CommandType::CreateCollection |
             CommandType::CreateIndexes |
             CommandType::CreateUser |
@@ -86,6 +164,7 @@ impl CommandType {
             CommandType::FindOneAndUpdate |
             CommandType::InsertMany |
             CommandType::InsertOne |
+            CommandType::RenameCollection |
             CommandType::UpdateMany |
             CommandType::UpdateOne => true,
             CommandType::Aggregate |
@@ -99,6 +178,7 @@ impl CommandType {
             CommandType::ListCollections |
             CommandType::ListDatabases |
             CommandType::ListIndexes |
+            CommandType::RunCommand |
             CommandType::Suppressed => false,
         }
     }
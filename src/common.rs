@@ -4,7 +4,9 @@ use Result;
 
 use bson::{self, Bson, bson, doc};
 use std::collections::BTreeMap;
+use std::mem;
 use std::str::FromStr;
+use std::time::Duration;
 
 /// Indicates how a server should be selected during read operations.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -34,46 +36,237 @@ impl FromStr for ReadMode {
     }
 }
 
+/// The tags a replica set member must have, each with the given value, to be selected for a
+/// read. An empty tag set matches every member.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TagSet(BTreeMap<String, String>);
+
+impl TagSet {
+    pub fn new() -> TagSet {
+        TagSet(BTreeMap::new())
+    }
+
+    /// Requires the tag `key` to have the value `value`.
+    pub fn insert(mut self, key: &str, value: &str) -> TagSet {
+        self.0.insert(String::from(key), String::from(value));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether a member with `tags` has every tag of this set.
+    pub fn matches(&self, tags: &BTreeMap<String, String>) -> bool {
+        self.0.iter().all(|(key, value)| tags.get(key) == Some(value))
+    }
+
+    pub fn to_document(&self) -> bson::Document {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.clone(), Bson::String(value.clone())))
+            .collect()
+    }
+}
+
+impl From<BTreeMap<String, String>> for TagSet {
+    fn from(tags: BTreeMap<String, String>) -> Self {
+        TagSet(tags)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ReadPreference {
     /// Indicates how a server should be selected during read operations.
     pub mode: ReadMode,
-    /// Filters servers based on the first tag set that matches at least one server.
-    pub tag_sets: Vec<BTreeMap<String, String>>,
+    /// Filters servers based on the first tag set that matches at least one server. Not allowed
+    /// with `Primary` mode.
+    pub tag_sets: Vec<TagSet>,
+    /// Whether mongos sends each read to two eligible members and returns the first reply, for
+    /// sharded reads on MongoDB 4.4 or later. Not allowed with `Primary` mode; mongos hedges
+    /// `Nearest` reads unless this is false.
+    pub hedge: Option<bool>,
 }
 
 impl ReadPreference {
-    pub fn new(mode: ReadMode, tag_sets: Option<Vec<BTreeMap<String, String>>>) -> ReadPreference {
+    pub fn new(mode: ReadMode, tag_sets: Option<Vec<TagSet>>) -> ReadPreference {
         ReadPreference {
             mode: mode,
             tag_sets: tag_sets.unwrap_or_else(Vec::new),
+            hedge: None,
         }
     }
 
+    /// Adds `tag_set` after the tag sets already given, to be tried if none of them match.
+    pub fn with_tag_set(mut self, tag_set: TagSet) -> ReadPreference {
+        self.tag_sets.push(tag_set);
+        self
+    }
+
+    /// Sets whether mongos hedges reads.
+    pub fn with_hedge(mut self, enabled: bool) -> ReadPreference {
+        self.hedge = Some(enabled);
+        self
+    }
+
+    /// Checks that the options are allowed with the mode.
+    pub fn validate(&self) -> Result<()> {
+        if self.mode != ReadMode::Primary {
+            return Ok(());
+        }
+
+        if self.tag_sets.iter().any(|tag_set| !tag_set.is_empty()) {
+            return Err(ArgumentError(String::from(
+                "Tag sets cannot be used with the Primary read mode.",
+            )));
+        }
+
+        if self.hedge.is_some() {
+            return Err(ArgumentError(String::from(
+                "Hedged reads cannot be used with the Primary read mode.",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the `$readPreference` document sent to mongos.
     pub fn to_document(&self) -> bson::Document {
-        let mut doc = doc! { "mode": stringify!(self.mode).to_ascii_lowercase() };
+        let mode = match self.mode {
+            ReadMode::Primary => "primary",
+            ReadMode::PrimaryPreferred => "primaryPreferred",
+            ReadMode::Secondary => "secondary",
+            ReadMode::SecondaryPreferred => "secondaryPreferred",
+            ReadMode::Nearest => "nearest",
+        };
+        let mut doc = doc! { "mode": mode };
         let bson_tag_sets: Vec<_> = self.tag_sets
             .iter()
-            .map(|map| {
-                let mut bson_map = bson::Document::new();
-                for (key, val) in map.iter() {
-                    bson_map.insert(&key[..], Bson::String(val.to_owned()));
-                }
-                Bson::Document(bson_map)
-            })
+            .map(|tag_set| Bson::Document(tag_set.to_document()))
             .collect();
 
-        doc.insert("tag_sets", Bson::Array(bson_tag_sets));
+        if !bson_tag_sets.is_empty() {
+            doc.insert("tags", Bson::Array(bson_tag_sets));
+        }
+
+        if let Some(enabled) = self.hedge {
+            doc.insert("hedge", doc! { "enabled": enabled });
+        }
+
         doc
     }
 }
 
+/// Indicates the consistency and isolation properties of the data returned by a read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReadConcernLevel {
+    Local,
+    Available,
+    Majority,
+    Linearizable,
+    Snapshot,
+}
+
+impl ReadConcernLevel {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ReadConcernLevel::Local => "local",
+            ReadConcernLevel::Available => "available",
+            ReadConcernLevel::Majority => "majority",
+            ReadConcernLevel::Linearizable => "linearizable",
+            ReadConcernLevel::Snapshot => "snapshot",
+        }
+    }
+}
+
+impl FromStr for ReadConcernLevel {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "local" => ReadConcernLevel::Local,
+            "available" => ReadConcernLevel::Available,
+            "majority" => ReadConcernLevel::Majority,
+            "linearizable" => ReadConcernLevel::Linearizable,
+            "snapshot" => ReadConcernLevel::Snapshot,
+            _ => {
+                return Err(ArgumentError(
+                    format!("Could not convert '{}' to ReadConcernLevel.", s),
+                ))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReadConcern {
+    /// The requested isolation level.
+    pub level: ReadConcernLevel,
+    /// The cluster time, as a BSON timestamp, to read at with the `snapshot` level; the server
+    /// picks the majority-committed time if none is given. Requires MongoDB 5.0 or later.
+    pub at_cluster_time: Option<i64>,
+    /// The cluster time, as a BSON timestamp, that the read must reflect; the server waits until
+    /// it has caught up to the time. Set by causally consistent sessions. Requires MongoDB 3.6 or
+    /// later.
+    pub after_cluster_time: Option<i64>,
+}
+
+impl ReadConcern {
+    pub fn new(level: ReadConcernLevel) -> ReadConcern {
+        ReadConcern {
+            level,
+            at_cluster_time: None,
+            after_cluster_time: None,
+        }
+    }
+
+    pub fn to_bson(&self) -> bson::Document {
+        let mut document = doc! { "level": self.level.as_str() };
+
+        if let Some(time) = self.at_cluster_time {
+            document.insert("atClusterTime", Bson::TimeStamp(time));
+        }
+
+        if let Some(time) = self.after_cluster_time {
+            document.insert("afterClusterTime", Bson::TimeStamp(time));
+        }
+
+        document
+    }
+}
+
+/// How many replica set members must apply a write before the server acknowledges it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Acknowledgment {
+    /// This many members, counting the primary. With 0, writes are sent without waiting for
+    /// any reply.
+    Nodes(i32),
+    /// A majority of the data-bearing voting members.
+    Majority,
+}
+
+impl Acknowledgment {
+    pub fn to_bson(&self) -> Bson {
+        match *self {
+            Acknowledgment::Nodes(n) => Bson::I32(n),
+            Acknowledgment::Majority => Bson::String(String::from("majority")),
+        }
+    }
+}
+
+impl From<i32> for Acknowledgment {
+    fn from(n: i32) -> Self {
+        Acknowledgment::Nodes(n)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct WriteConcern {
-    /// Write replication
-    pub w: i32,
-    /// Used in conjunction with 'w'. Propagation timeout in ms.
-    pub w_timeout: i32,
+    /// Write replication. Unacknowledged writes are sent without waiting for any reply, and
+    /// their results have `acknowledged` set to false and no counts or errors.
+    pub w: Acknowledgment,
+    /// How long the server waits for replication before reporting a write concern error; by
+    /// default, it waits indefinitely. Has no effect on a single server.
+    pub w_timeout: Option<Duration>,
     /// If true, will block until write operations have been committed to journal.
     pub j: bool,
     /// If true and server is not journaling, blocks until server has synced all data files to disk.
@@ -81,21 +274,101 @@ pub struct WriteConcern {
 }
 
 impl WriteConcern {
+    /// Writes acknowledged by the primary alone.
     pub fn new() -> WriteConcern {
         WriteConcern {
-            w: 1,
-            w_timeout: 0,
+            w: Acknowledgment::Nodes(1),
+            w_timeout: None,
             j: false,
             fsync: false,
         }
     }
 
+    /// Writes acknowledged by the primary alone; the same as `new`.
+    pub fn acknowledged() -> WriteConcern {
+        WriteConcern::new()
+    }
+
+    /// Writes sent without waiting for a reply.
+    pub fn unacknowledged() -> WriteConcern {
+        WriteConcern {
+            w: Acknowledgment::Nodes(0),
+            ..WriteConcern::new()
+        }
+    }
+
+    /// Writes acknowledged by a majority of the replica set, which survive a failover.
+    pub fn majority() -> WriteConcern {
+        WriteConcern {
+            w: Acknowledgment::Majority,
+            ..WriteConcern::new()
+        }
+    }
+
+    /// Writes acknowledged by a majority of the replica set, reporting a write concern error if
+    /// replication takes longer than `timeout`.
+    pub fn majority_with_timeout(timeout: Duration) -> WriteConcern {
+        WriteConcern {
+            w_timeout: Some(timeout),
+            ..WriteConcern::majority()
+        }
+    }
+
+    /// Whether the server replies to writes.
+    pub fn is_acknowledged(&self) -> bool {
+        self.w != Acknowledgment::Nodes(0)
+    }
+
+    /// Checks that the server can honor the write concern.
+    pub fn validate(&self) -> Result<()> {
+        if let Acknowledgment::Nodes(n) = self.w {
+            if n < 0 {
+                return Err(ArgumentError(format!("Write concern w must not be negative: {}.", n)));
+            }
+
+            if n == 0 && self.j {
+                return Err(ArgumentError(String::from(
+                    "Write concern cannot wait for the journal without acknowledging writes.",
+                )));
+            }
+        }
+
+        if let Some(timeout) = self.w_timeout {
+            if timeout.as_millis() > i32::MAX as u128 {
+                return Err(ArgumentError(format!(
+                    "Write concern wtimeout of {:?} is too long; it must fit in 32-bit \
+                     milliseconds.",
+                    timeout
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn to_bson(&self) -> bson::Document {
-        doc! {
-            "w": self.w,
-            "wtimeout": self.w_timeout,
+        let mut document = doc! {
+            "w": self.w.to_bson(),
             "j": self.j,
+        };
+
+        if let Some(timeout) = self.w_timeout {
+            document.insert("wtimeout", WriteConcern::timeout_ms(timeout));
         }
+
+        document
+    }
+
+    // Converts a wtimeout to whole milliseconds, rounding up so that a short timeout does not
+    // become 0, which the server takes to mean no timeout. Timeouts too long for `validate` are
+    // clamped rather than wrapped.
+    fn timeout_ms(timeout: Duration) -> i32 {
+        let mut ms = timeout.as_millis();
+        if timeout.subsec_nanos() % 1_000_000 != 0 {
+            ms += 1;
+        }
+
+        ms.clamp(1, i32::MAX as u128) as i32
     }
 }
 
@@ -105,13 +378,82 @@ impl Default for WriteConcern {
     }
 }
 
+/// How a write concern error is surfaced when the write itself succeeded, such as when a
+/// majority write times out waiting for replication.
+///
+/// The policy is set per client, with `ClientOptions::write_concern_error_policy` or
+/// `ThreadedClient::with_write_concern_error_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WriteConcernErrorPolicy {
+    /// Return the result, with the error in its write exception field.
+    Report,
+    /// Fail the operation with the write concern error. Bulk writes, which return no error,
+    /// still report it in their exception, but send no further batches.
+    Fail,
+}
+
+impl Default for WriteConcernErrorPolicy {
+    fn default() -> Self {
+        WriteConcernErrorPolicy::Report
+    }
+}
+
+/// Key-order helpers for documents whose field order is significant to the server, such as
+/// commands, sort specifications and index keys.
+pub trait DocumentExt {
+    /// Moves `key` to the front of the document, keeping the order of the remaining keys.
+    /// Returns false if the document has no such key.
+    fn ensure_first_key(&mut self, key: &str) -> bool;
+}
+
+impl DocumentExt for bson::Document {
+    fn ensure_first_key(&mut self, key: &str) -> bool {
+        if self.keys().next().map(String::as_str) == Some(key) {
+            return true;
+        }
+
+        let value = match self.remove(key) {
+            Some(value) => value,
+            None => return false,
+        };
+
+        let rest = mem::replace(self, bson::Document::new());
+        self.insert(key, value);
+
+        for (k, v) in rest {
+            self.insert_bson(k, v);
+        }
+
+        true
+    }
+}
+
+/// Appends `options` to `document`, such as a command.
+///
+/// Options that repeat a key of `document` replace its value in place, so the command name
+/// stays first and the order of the caller's keys is preserved.
 pub fn merge_options<T: Into<bson::Document>>(
     document: bson::Document,
     options: T,
 ) -> bson::Document {
-    let options_doc: bson::Document = options.into();
-    document
+    let mut options_doc: bson::Document = options.into();
+    let first_key = document.keys().next().cloned();
+
+    let mut merged: bson::Document = document
         .into_iter()
-        .chain(options_doc.into_iter())
-        .collect()
+        .map(|(key, value)| {
+            let value = options_doc.remove(&key).unwrap_or(value);
+            (key, value)
+        })
+        .collect();
+
+    for (key, value) in options_doc {
+        merged.insert_bson(key, value);
+    }
+
+    if let Some(first_key) = first_key {
+        merged.ensure_first_key(&first_key);
+    }
+
+    merged
 }
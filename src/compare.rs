@@ -0,0 +1,150 @@
+//! Structural comparison of BSON values.
+//!
+//! These comparisons are intended for test assertions, where the exact BSON type of a number
+//! often depends on the server version or the encoder rather than on what is being tested. By
+//! default, numbers compare by value regardless of type, arrays compare element by element in
+//! order, and documents must have the same fields in the same order.
+//!
+//! ```
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use bson::Bson;
+//! # use mongodb::compare::{self, CompareOptions};
+//! #
+//! # fn main() {
+//! let actual = doc! { "n": 1i64, "tags": ["a", "b"] };
+//! assert!(compare::document_eq(&actual, &doc! { "n": 1, "tags": ["a", "b"] }, None));
+//! assert!(!compare::document_eq(&actual, &doc! { "n": 1, "tags": ["b", "a"] }, None));
+//!
+//! let mut options = CompareOptions::new();
+//! options.ignore_field_order = Some(true);
+//! assert!(compare::document_eq(&actual, &doc! { "tags": ["a", "b"], "n": 1.0 }, Some(options)));
+//! # }
+//! ```
+use bson::{Bson, Document};
+#[cfg(feature = "decimal128")]
+use decimal;
+
+#[cfg(feature = "decimal128")]
+use std::cmp::Ordering;
+
+// How many representable doubles apart two floats may be and still compare equal.
+const MAX_ULPS: u64 = 4;
+
+/// Options for comparing BSON values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CompareOptions {
+    /// Whether 32-bit integers, 64-bit integers, doubles and, with the `decimal128` feature,
+    /// decimals compare by numeric value; defaults to true. Otherwise, numbers must also have
+    /// the same type.
+    pub numeric_type_insensitive: Option<bool>,
+    /// Whether document fields may appear in any order; defaults to false.
+    pub ignore_field_order: Option<bool>,
+}
+
+impl CompareOptions {
+    pub fn new() -> CompareOptions {
+        Default::default()
+    }
+}
+
+/// Compares BSON values to numbers regardless of their numeric type.
+pub trait NumEq {
+    /// Whether the value is a number equal to `f`, allowing for rounding error.
+    fn float_eq(&self, f: f64) -> bool;
+    /// Whether the value is a number equal to `i`.
+    fn int_eq(&self, i: i64) -> bool;
+}
+
+impl NumEq for Bson {
+    fn float_eq(&self, f: f64) -> bool {
+        match *self {
+            Bson::FloatingPoint(ff) => ulps_eq(ff, f),
+            Bson::I32(i) => ulps_eq(f64::from(i), f),
+            Bson::I64(i) => ulps_eq(i as f64, f),
+            _ => false,
+        }
+    }
+
+    fn int_eq(&self, i: i64) -> bool {
+        match *self {
+            Bson::FloatingPoint(f) => ulps_eq(f, i as f64),
+            Bson::I32(ii) => i == i64::from(ii),
+            Bson::I64(ii) => i == ii,
+            _ => false,
+        }
+    }
+}
+
+// Whether two doubles are within a few representable values of each other.
+fn ulps_eq(a: f64, b: f64) -> bool {
+    if a == b {
+        return true;
+    }
+
+    if a.is_nan() || b.is_nan() || a.is_sign_positive() != b.is_sign_positive() {
+        return false;
+    }
+
+    a.to_bits().abs_diff(b.to_bits()) <= MAX_ULPS
+}
+
+/// Whether two BSON values are equal.
+pub fn bson_eq(b1: &Bson, b2: &Bson, options: Option<CompareOptions>) -> bool {
+    let options = options.unwrap_or_default();
+    eq(b1, b2, &options)
+}
+
+/// Whether two documents are equal.
+pub fn document_eq(d1: &Document, d2: &Document, options: Option<CompareOptions>) -> bool {
+    let options = options.unwrap_or_default();
+    doc_eq(d1, d2, &options)
+}
+
+fn eq(b1: &Bson, b2: &Bson, options: &CompareOptions) -> bool {
+    if options.numeric_type_insensitive.unwrap_or(true) {
+        #[cfg(feature = "decimal128")]
+        {
+            if let (&Bson::Decimal128(_), _) | (_, &Bson::Decimal128(_)) = (b1, b2) {
+                return decimal::compare(b1, b2) == Some(Ordering::Equal);
+            }
+        }
+
+        match *b1 {
+            Bson::FloatingPoint(f) => return b2.float_eq(f),
+            Bson::I32(i) => return b2.int_eq(i64::from(i)),
+            Bson::I64(i) => return b2.int_eq(i),
+            _ => (),
+        }
+    }
+
+    match (b1, b2) {
+        (Bson::FloatingPoint(f1), Bson::FloatingPoint(f2)) => ulps_eq(*f1, *f2),
+        (Bson::Array(arr1), Bson::Array(arr2)) => {
+            arr1.len() == arr2.len() &&
+                arr1.iter().zip(arr2).all(|(v1, v2)| eq(v1, v2, options))
+        }
+        (Bson::Document(doc1), Bson::Document(doc2)) => doc_eq(doc1, doc2, options),
+        (Bson::JavaScriptCodeWithScope(s1, doc1), Bson::JavaScriptCodeWithScope(s2, doc2)) => {
+            s1 == s2 && doc_eq(doc1, doc2, options)
+        }
+        _ => b1 == b2,
+    }
+}
+
+fn doc_eq(d1: &Document, d2: &Document, options: &CompareOptions) -> bool {
+    if d1.len() != d2.len() {
+        return false;
+    }
+
+    if options.ignore_field_order.unwrap_or(false) {
+        return d1.iter().all(|(key, v1)| match d2.get(key) {
+            Some(v2) => eq(v1, v2, options),
+            None => false,
+        });
+    }
+
+    d1.iter().zip(d2.iter()).all(|((k1, v1), (k2, v2))| {
+        k1 == k2 && eq(v1, v2, options)
+    })
+}
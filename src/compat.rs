@@ -0,0 +1,25 @@
+//! A stable import path for the 0.x client API.
+//!
+//! Applications written against the `ThreadedClient` and `ThreadedDatabase` traits, with clients
+//! created by `connect(host, port)` or `with_uri(uri)`, can import everything they use from this
+//! module instead of from the crate root and its submodules:
+//!
+//! ```no_run
+//! use mongodb::compat::*;
+//!
+//! let client = Client::connect("localhost", 27017).unwrap();
+//! let names = client.db("app").collection_names(None).unwrap();
+//! ```
+//!
+//! The names here currently refer to the driver's own API. When the client API is redesigned,
+//! they will be kept as thin wrappers over the new API, so that code importing from `compat`
+//! keeps compiling unchanged while it migrates at its own pace.
+pub use {Client, ClientOptions, CommandType, Error, ErrorCode, Result, ThreadedClient};
+pub use coll::Collection;
+pub use coll::options::{AggregateOptions, CountOptions, DistinctOptions, FindOneAndDeleteOptions,
+                        FindOneAndUpdateOptions, FindOptions, IndexModel, IndexOptions,
+                        InsertManyOptions, ReturnDocument, UpdateOptions};
+pub use common::{ReadConcern, ReadConcernLevel, ReadMode, ReadPreference, WriteConcern};
+pub use connstring::ConnectionString;
+pub use cursor::{Cursor, TypedCursor};
+pub use db::{Database, ThreadedDatabase};
@@ -2,9 +2,11 @@
 use Result;
 use Error::ArgumentError;
 use std::collections::BTreeMap;
+use std::fmt;
 
 pub const DEFAULT_PORT: u16 = 27017;
 pub const URI_SCHEME: &'static str = "mongodb://";
+pub const SRV_URI_SCHEME: &str = "mongodb+srv://";
 
 /// Encapsulates the hostname and port of a host.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -18,8 +20,8 @@ pub struct Host {
 }
 
 impl Host {
-    // Creates a new Host struct.
-    fn new(host_name: String, port: u16) -> Host {
+    /// Creates a host with the given hostname and port.
+    pub fn new(host_name: String, port: u16) -> Host {
         Host {
             host_name: host_name,
             port: port,
@@ -40,6 +42,17 @@ impl Host {
     }
 }
 
+impl fmt::Display for Host {
+    /// Formats the host as `host:port`, or as its file path if it is an IPC host.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.has_ipc() {
+            f.write_str(&self.ipc)
+        } else {
+            write!(f, "{}:{}", self.host_name, self.port)
+        }
+    }
+}
+
 /// Encapsulates the options and read preference tags of a MongoDB connection.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ConnectionOptions {
@@ -69,6 +82,9 @@ impl ConnectionOptions {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ConnectionString {
     pub hosts: Vec<Host>,
+    /// The hostname of a `mongodb+srv://` connection string, whose SRV records list the hosts.
+    /// The hosts are empty until the records are looked up; see the `srv` module.
+    pub srv: Option<String>,
     pub string: Option<String>,
     pub user: Option<String>,
     pub password: Option<String>,
@@ -87,6 +103,7 @@ impl ConnectionString {
     fn with_host(host: Host) -> ConnectionString {
         ConnectionString {
             hosts: vec![host],
+            srv: None,
             string: None,
             user: None,
             password: None,
@@ -100,16 +117,22 @@ impl ConnectionString {
 /// Parses a MongoDB connection string URI as defined by
 /// [the manual](http://docs.mongodb.org/manual/reference/connection-string/).
 pub fn parse(address: &str) -> Result<ConnectionString> {
-    if !address.starts_with(URI_SCHEME) {
+    let is_srv = address.starts_with(SRV_URI_SCHEME);
+    if !is_srv && !address.starts_with(URI_SCHEME) {
         return Err(ArgumentError(String::from(
-            "MongoDB connection string must start with 'mongodb://'.",
+            "MongoDB connection string must start with 'mongodb://' or 'mongodb+srv://'.",
         )));
     }
 
     // Remove scheme
-    let addr = &address[URI_SCHEME.len()..];
+    let addr = if is_srv {
+        &address[SRV_URI_SCHEME.len()..]
+    } else {
+        &address[URI_SCHEME.len()..]
+    };
 
     let hosts: Vec<Host>;
+    let mut srv: Option<String> = None;
     let mut user: Option<String> = None;
     let mut password: Option<String> = None;
     let mut database: Option<String> = Some(String::from("test"));
@@ -137,14 +160,21 @@ pub fn parse(address: &str) -> Result<ConnectionString> {
     }
 
     // Split on authentication and hosts
-    if host_str.contains('@') {
+    let host_list = if host_str.contains('@') {
         let (user_info, host_string) = rpartition(host_str, "@");
         let (u, p) = parse_user_info(user_info)?;
         user = Some(String::from(u));
         password = Some(String::from(p));
-        hosts = split_hosts(host_string)?;
+        host_string
+    } else {
+        host_str
+    };
+
+    if is_srv {
+        srv = Some(parse_srv_host(host_list)?);
+        hosts = Vec::new();
     } else {
-        hosts = split_hosts(host_str)?;
+        hosts = split_hosts(host_list)?;
     }
 
     let mut opts = "";
@@ -169,6 +199,7 @@ pub fn parse(address: &str) -> Result<ConnectionString> {
 
     Ok(ConnectionString {
         hosts: hosts,
+        srv,
         string: Some(String::from(address)),
         user: user,
         password: password,
@@ -178,6 +209,27 @@ pub fn parse(address: &str) -> Result<ConnectionString> {
     })
 }
 
+// Checks that an SRV connection string has a single host without a port, with at least three
+// labels so that its records can be held to its domain.
+fn parse_srv_host(host_list: &str) -> Result<String> {
+    if host_list.contains(',') {
+        return Err(ArgumentError(String::from(
+            "A 'mongodb+srv://' connection string must have exactly one host.",
+        )));
+    }
+
+    let labels: Vec<_> = host_list.split('.').collect();
+    if host_list.contains(':') || labels.len() < 3 || labels.iter().any(|label| label.is_empty()) {
+        return Err(ArgumentError(format!(
+            "A 'mongodb+srv://' connection string needs a hostname with a domain and no port, \
+             such as 'cluster0.example.com', but got '{}'.",
+            host_list
+        )));
+    }
+
+    Ok(host_list.to_ascii_lowercase())
+}
+
 // Parse user information of the form user:password
 fn parse_user_info(user_info: &str) -> Result<(&str, &str)> {
     let (user, password) = rpartition(user_info, ":");
@@ -24,23 +24,49 @@
 //! # }
 //! ```
 use {Client, CommandType, Error, ErrorCode, Result, ThreadedClient};
-use apm::{CommandStarted, CommandResult, EventRunner};
+use error::{CollectLimit, CommandError, ServerError};
+use apm::{operation, CommandStarted, CommandResult, EventRunner, Operation};
 
 use bson::{self, bson, doc, Bson};
-use common::{merge_options, ReadMode, ReadPreference};
+use serde::de::DeserializeOwned;
+use common::{merge_options, ReadPreference};
+use connstring::Host;
 use coll::options::FindOptions;
+#[cfg(feature = "stream")]
+use cursor_stream::{self, CursorStream, Executor};
 use pool::PooledStream;
+use raw::{RawBson, RawDocumentBuf};
 use time;
-use wire_protocol::flags::OpQueryFlags;
-use wire_protocol::operations::Message;
+use timeout::Deadline;
+use trace_context;
+use uuid;
+use wire_protocol::flags::{OpMsgFlags, OpQueryFlags, OpReplyFlags};
+use wire_protocol::limits::DecodeLimits;
+use wire_protocol::operations::{Message, RawReply};
 
-use std::{ i32, usize };
+use std::{ cmp, i32, usize };
 use std::mem::size_of;
 use std::collections::vec_deque::VecDeque;
+use std::marker::PhantomData;
+use std::thread::{self, JoinHandle};
 
 // Allows the server to decide the batch size.
 pub const DEFAULT_BATCH_SIZE: i32 = 0;
 
+// Codes used before MongoDB 4.4 for sorts and groups that exceeded the memory limit.
+const SORT_EXCEEDED_MEMORY_LIMIT: i32 = 16819;
+const GROUP_EXCEEDED_MEMORY_LIMIT: i32 = 16945;
+
+// The most a getMore reply holds; adaptive batches grow toward filling one.
+const MAX_REPLY_BYTES: usize = 16 * 1024 * 1024;
+
+// The first wire version, that of MongoDB 3.6, to support OP_MSG.
+const OP_MSG_WIRE_VERSION: i64 = 6;
+
+// The documents of a getMore reply, the cursor id to continue from, and the reply's length in
+// bytes.
+type Batch = (VecDeque<bson::Document>, i64, usize);
+
 /// Maintains a connection to the server and lazily returns documents from a
 /// query.
 #[derive(Debug)]
@@ -49,8 +75,15 @@ pub struct Cursor {
     client: Client,
     // The namespace to read and write from.
     namespace: String,
+    // The server the query was sent to, which holds the cursor.
+    address: Host,
     // How many documents to fetch at a given time from the server.
     batch_size: i32,
+    // Whether each getMore sizes its batch from the documents read so far.
+    adaptive_batch_size: bool,
+    // The documents and reply bytes read by getMores so far.
+    docs_read: usize,
+    bytes_read: usize,
     // Uniquely identifies the cursor being returned by the reply.
     cursor_id: i64,
     // An upper bound on the total number of documents this cursor should return.
@@ -59,36 +92,432 @@ pub struct Cursor {
     count: i32,
     // A cache for documents received from the query that have not yet been returned.
     buffer: VecDeque<bson::Document>,
-    read_preference: ReadPreference,
     cmd_type: CommandType,
+    // Correlates the initial query with every getMore in monitoring events.
+    operation_id: i64,
+    // Bounds the cursor's lifetime, including every getMore.
+    deadline: Deadline,
+    // Whether to fetch the next batch in the background.
+    prefetching: bool,
+    // The in-flight background fetch of the next batch, if any.
+    prefetch: Option<JoinHandle<Result<Batch>>>,
+    // The cluster time a snapshot read was served at.
+    at_cluster_time: Option<i64>,
+    // An error `next_buffered` met after collecting documents, returned by its next call.
+    deferred_error: Option<Error>,
 }
 
 macro_rules! try_or_emit {
-    ($cmd_type:expr, $cmd_name:expr, $req_id:expr, $connstring:expr, $result:expr, $client:expr) =>
+    ($cmd_type:expr, $cmd_name:expr, $db_name:expr, $coll_name:expr, $req_id:expr, $op_id:expr,
+     $connstring:expr, $host:expr, $init_time:expr, $result:expr, $client:expr) =>
     {
         match $result {
             Ok(val) => val,
             Err(e) => {
+                let duration = time::precise_time_ns() - $init_time;
+
                 if $cmd_type != CommandType::Suppressed {
                     let hook_result = $client.run_completion_hooks(&CommandResult::Failure {
-                        duration: 0,
-                        command_name: String::from($cmd_name),
+                        duration,
+                        command_name: String::from(&$cmd_name[..]),
+                        database_name: $db_name.clone(),
+                        collection_name: $coll_name.clone(),
                         failure: &e,
                         request_id: $req_id as i64,
-                        connection_string: $connstring,
+                        operation_id: $op_id,
+                        connection_string: $connstring.clone(),
+                        host: $host.clone(),
                     });
 
                     if hook_result.is_err() {
+                        let e = with_context(
+                            e,
+                            $connstring,
+                            &$cmd_name[..],
+                            $req_id,
+                            $op_id,
+                            duration,
+                        );
                         return Err(Error::EventListenerError(Some(Box::new(e))));
                     }
                 }
 
-                return Err(e)
+                return Err(with_context(e, $connstring, &$cmd_name[..], $req_id, $op_id, duration))
             }
         }
     };
 }
 
+// Records where and when a command was sent on failures to send it or to read its reply, which
+// would otherwise not say which server or operation they came from.
+fn with_context(
+    err: Error,
+    host: String,
+    command_name: &str,
+    request_id: i32,
+    operation_id: i64,
+    duration: u64,
+) -> Error {
+    match err {
+        Error::IoError(_) |
+        Error::OperationError(_) |
+        Error::ResponseError(_) |
+        Error::DecoderError(_) => {
+            Error::CommandError(CommandError {
+                host,
+                command_name: String::from(command_name),
+                request_id: i64::from(request_id),
+                operation_id,
+                duration,
+                cause: Box::new(err),
+            })
+        }
+        err => err,
+    }
+}
+
+// Prepares a query or command to send: has reads in a causally consistent session wait for the
+// session's operation time, stores its UUIDs in the client's representation and encrypts its
+// fields that the client's schema map marks as encrypted.
+fn encode_query(
+    client: &Client,
+    namespace: &str,
+    mut query: bson::Document,
+) -> Result<bson::Document> {
+    if let Some(time) = client.session.as_ref().and_then(|session| session.after_cluster_time()) {
+        match query.get_mut("$query") {
+            Some(Bson::Document(command)) => read_after(command, time),
+            _ => read_after(&mut query, time),
+        }
+    }
+
+    let query = match client.uuid_representation {
+        Some(representation) => uuid::encode_document(query, representation),
+        None => query,
+    };
+
+    encrypt_query(client, namespace, query)
+}
+
+// Adds `afterClusterTime` to the read concern of a read command.
+fn read_after(command: &mut bson::Document, time: i64) {
+    let is_read = matches!(
+        command.keys().next().map(String::as_str),
+        Some("find") | Some("aggregate") | Some("count") | Some("distinct")
+    );
+
+    if !is_read {
+        return;
+    }
+
+    let mut read_concern = match command.get("readConcern") {
+        Some(Bson::Document(read_concern)) => read_concern.clone(),
+        _ => bson::Document::new(),
+    };
+
+    // Snapshot reads at a given time already see a consistent point.
+    if !read_concern.contains_key("atClusterTime") {
+        read_concern.insert("afterClusterTime", Bson::TimeStamp(time));
+        command.insert("readConcern", read_concern);
+    }
+}
+
+// Selects a server for a query and checks out a stream to it, returning the stream with the
+// flags and query adjusted for the selected server.
+fn acquire_query_stream(
+    client: &Client,
+    flags: OpQueryFlags,
+    query: bson::Document,
+    options: &FindOptions,
+    cmd_type: CommandType,
+    read_pref: &ReadPreference,
+) -> Result<(PooledStream, OpQueryFlags, bson::Document)> {
+    let deadline =
+        Deadline::after_ms_on(client.clock.clone(), options.timeout_ms.or(client.timeout_ms));
+
+    // Select a server stream from the topology.
+    let (mut stream, slave_ok, send_read_pref) = if cmd_type.is_write_command() {
+        (client.acquire_write_stream_with_deadline(deadline.clone())?, false, false)
+    } else {
+        client.acquire_stream_with_deadline(read_pref.to_owned(), deadline.clone())?
+    };
+    stream.set_deadline(deadline)?;
+
+    if !cmd_type.is_federated_supported() && client.is_federated() {
+        return Err(Error::Unsupported(format!(
+            "{} is not supported by federated endpoints.",
+            cmd_type.to_str()
+        )));
+    }
+
+    // Set slave_ok flag based on the result from server selection.
+    let new_flags = if slave_ok {
+        flags | OpQueryFlags::SLAVE_OK
+    } else {
+        flags
+    };
+
+    // Send $readPreference to mongos based on the result from server selection.
+    let new_query = if !send_read_pref {
+        query
+    } else if query.contains_key("$query") {
+        // Query is already formatted as a $query document; add onto it.
+        let mut query = query;
+        query.insert("$readPreference", read_pref.to_document());
+        query
+    } else {
+        // Convert the query to a $query document.
+        doc! {
+            "$query": query,
+            "$readPreference": read_pref.to_document(),
+        }
+    };
+
+    Ok((stream, new_flags, new_query))
+}
+
+/// Sends a write command with an unacknowledged (`w: 0`) write concern to the primary as an
+/// OP_MSG with the `moreToCome` flag, returning as soon as it is written. The server sends no
+/// reply, so neither the write's outcome nor its errors are reported; completion hooks see
+/// `{ ok: 1 }`.
+///
+/// Servers older than 3.6 do not support OP_MSG, so the command is sent to them as an ordinary
+/// command, whose `{ ok: 1 }` reply is read and dropped.
+///
+/// Sending is bounded by `timeout_ms`, or the client's timeout if `None`.
+pub fn send_unacknowledged(
+    client: &Client,
+    db_name: &str,
+    command: bson::Document,
+    cmd_type: CommandType,
+    timeout_ms: Option<i64>,
+) -> Result<()> {
+    let namespace = format!("{}.$cmd", db_name);
+    let timeout_ms = timeout_ms.or(client.timeout_ms);
+    let deadline = Deadline::after_ms_on(client.clock.clone(), timeout_ms);
+    let mut stream = client.acquire_write_stream_with_deadline(deadline.clone())?;
+    stream.set_deadline(deadline)?;
+
+    if client.max_wire_version(stream.host()).unwrap_or(0) < OP_MSG_WIRE_VERSION {
+        let options = FindOptions {
+            batch_size: Some(1),
+            limit: Some(1),
+            ..FindOptions::new()
+        };
+        let mut cursor = Cursor::query_with_stream(
+            &mut stream,
+            client.clone(),
+            namespace,
+            OpQueryFlags::empty(),
+            command,
+            options,
+            cmd_type,
+            false,
+            None,
+        )?;
+
+        return match cursor.next() {
+            Some(Err(err)) => Err(err),
+            _ => Ok(()),
+        };
+    }
+
+    let command = trace_query(client, &namespace, cmd_type, command);
+    let mut command = encode_query(client, &namespace, command)?;
+    command.insert("$db", db_name);
+
+    let req_id = client.get_req_id();
+    let operation_id = Operation::current_id().unwrap_or_else(operation::next_id);
+    let (db_name, collection_name, started) =
+        describe_query(&namespace, &command, &FindOptions::new(), cmd_type, true);
+    let cmd_name = cmd_type.to_str();
+    let connstring = stream.get_socket().get_ref().peer_addr()?.to_string();
+    let host = stream.host().to_string();
+
+    let init_time = time::precise_time_ns();
+    let message = Message::new_msg(req_id, OpMsgFlags::MORE_TO_COME, command)?;
+
+    if cmd_type != CommandType::Suppressed {
+        let hook_result = client.run_start_hooks(&CommandStarted {
+            command: started,
+            database_name: db_name.clone(),
+            collection_name: collection_name.clone(),
+            command_name: String::from(cmd_name),
+            request_id: req_id as i64,
+            operation_id,
+            connection_string: connstring.clone(),
+            host: host.clone(),
+        });
+
+        if hook_result.is_err() {
+            return Err(Error::EventListenerError(None));
+        }
+    }
+
+    let written = stream.write_message(&message);
+    try_or_emit!(
+        cmd_type,
+        cmd_name,
+        db_name,
+        collection_name,
+        req_id,
+        operation_id,
+        connstring,
+        host,
+        init_time,
+        stream.check(written),
+        client
+    );
+
+    if cmd_type != CommandType::Suppressed {
+        let _hook_result = client.run_completion_hooks(&CommandResult::Success {
+            duration: time::precise_time_ns() - init_time,
+            reply: doc! { "ok": 1 },
+            command_name: String::from(cmd_name),
+            database_name: db_name,
+            collection_name,
+            request_id: req_id as i64,
+            operation_id,
+            connection_string: connstring,
+            host,
+        });
+    }
+
+    Ok(())
+}
+
+// Returns the database, collection and command that monitoring events report for a query.
+fn describe_query(
+    namespace: &str,
+    query: &bson::Document,
+    options: &FindOptions,
+    cmd_type: CommandType,
+    is_cmd_cursor: bool,
+) -> (String, Option<String>, bson::Document) {
+    let index = namespace.find('.').unwrap_or_else(|| namespace.len());
+    let db_name = String::from(&namespace[..index]);
+    let coll_name = String::from(&namespace[index + 1..]);
+
+    let filter = match query.get("$query") {
+        Some(&Bson::Document(ref doc)) => doc.clone(),
+        _ => query.clone(),
+    };
+
+    // Commands name their collection first; queries are sent to the collection itself.
+    let collection_name = match filter.iter().next() {
+        Some((_, Bson::String(name))) if coll_name == "$cmd" => Some(name.clone()),
+        _ if coll_name == "$cmd" => None,
+        _ => Some(coll_name.clone()),
+    };
+
+    // Command cursors already carry the `find` command itself.
+    let command = match cmd_type {
+        CommandType::Find if !is_cmd_cursor => {
+            let document = doc! {
+                "find": coll_name,
+                "filter": filter
+            };
+
+            merge_options(document, options.clone())
+        }
+        _ => query.clone(),
+    };
+
+    (db_name, collection_name, command)
+}
+
+// Attaches the current span to the comment of a query or command, if the client traces them.
+fn trace_query(
+    client: &Client,
+    namespace: &str,
+    cmd_type: CommandType,
+    query: bson::Document,
+) -> bson::Document {
+    // Authentication and monitoring stay out of application traces.
+    if cmd_type == CommandType::Suppressed || cmd_type == CommandType::IsMaster {
+        return query;
+    }
+
+    let span = match client.trace_context {
+        Some(ref trace_context) => trace_context.current_span(),
+        None => None,
+    };
+
+    match span {
+        Some(span) => {
+            let wire_version = client.capabilities().ok().and_then(|caps| caps.wire_version);
+            trace_context::attach(query, namespace, &span, wire_version)
+        }
+        None => query,
+    }
+}
+
+// Reverses `encode_query` for a batch read from the server.
+fn decode_batch(
+    client: &Client,
+    batch: VecDeque<bson::Document>,
+) -> Result<VecDeque<bson::Document>> {
+    let batch = decrypt_batch(client, batch)?;
+
+    match client.uuid_representation {
+        Some(representation) => {
+            Ok(batch.into_iter().map(|doc| uuid::decode_document(doc, representation)).collect())
+        }
+        None => Ok(batch),
+    }
+}
+
+// Encrypts the fields of a query or command that the client's schema map marks as encrypted.
+#[cfg(feature = "encryption")]
+fn encrypt_query(
+    client: &Client,
+    namespace: &str,
+    query: bson::Document,
+) -> Result<bson::Document> {
+    match client.encrypter {
+        Some(ref encrypter) => encrypter.encrypt_query(namespace, query),
+        None => Ok(query),
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encrypt_query(_: &Client, _: &str, query: bson::Document) -> Result<bson::Document> {
+    Ok(query)
+}
+
+// Decrypts the encrypted values in a batch read from the server.
+#[cfg(feature = "encryption")]
+fn decrypt_batch(
+    client: &Client,
+    batch: VecDeque<bson::Document>,
+) -> Result<VecDeque<bson::Document>> {
+    match client.encrypter {
+        Some(ref encrypter) => {
+            batch.into_iter().map(|doc| encrypter.decrypt_document(doc)).collect()
+        }
+        None => Ok(batch),
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+fn decrypt_batch(
+    _: &Client,
+    batch: VecDeque<bson::Document>,
+) -> Result<VecDeque<bson::Document>> {
+    Ok(batch)
+}
+
+// Whether `decode_batch` changes the documents the client reads, which a raw cursor cannot do.
+#[cfg(feature = "encryption")]
+fn decodes_batches(client: &Client) -> bool {
+    client.uuid_representation.is_some() || client.encrypter.is_some()
+}
+
+#[cfg(not(feature = "encryption"))]
+fn decodes_batches(client: &Client) -> bool {
+    client.uuid_representation.is_some()
+}
+
 impl Cursor {
     /// Construcs a new Cursor for a database command.
     ///
@@ -109,9 +538,23 @@ impl Cursor {
         doc: bson::Document,
         cmd_type: CommandType,
         read_pref: ReadPreference,
+    ) -> Result<Cursor> {
+        Cursor::command_cursor_with_timeout(client, db, doc, cmd_type, read_pref, None)
+    }
+
+    /// Constructs a new Cursor for a database command, bounding the command and any subsequent
+    /// getMores by `timeout_ms` instead of the client's default.
+    pub fn command_cursor_with_timeout(
+        client: Client,
+        db: &str,
+        doc: bson::Document,
+        cmd_type: CommandType,
+        read_pref: ReadPreference,
+        timeout_ms: Option<i64>,
     ) -> Result<Cursor> {
         let mut options = FindOptions::new();
         options.batch_size = Some(1);
+        options.timeout_ms = timeout_ms;
 
         Cursor::query(
             client.clone(),
@@ -125,30 +568,53 @@ impl Cursor {
         )
     }
 
+    /// Constructs an exhausted Cursor over documents already read from a command reply, such as
+    /// the results of an inline map-reduce, sent to the server at `address`.
+    pub fn from_documents(
+        client: Client,
+        namespace: String,
+        address: Host,
+        docs: Vec<bson::Document>,
+        cmd_type: CommandType,
+    ) -> Cursor {
+        Cursor {
+            client,
+            namespace,
+            address,
+            batch_size: docs.len() as i32,
+            adaptive_batch_size: false,
+            docs_read: 0,
+            bytes_read: 0,
+            cursor_id: 0,
+            limit: 0,
+            count: 0,
+            buffer: VecDeque::from(docs),
+            cmd_type,
+            operation_id: Operation::current_id().unwrap_or_else(operation::next_id),
+            deadline: Deadline::none(),
+            prefetching: false,
+            prefetch: None,
+            at_cluster_time: None,
+            deferred_error: None,
+        }
+    }
+
     fn get_bson_and_cid_from_message(
         message: Message,
     ) -> Result<(bson::Document, VecDeque<bson::Document>, i64)> {
         match message {
             Message::OpReply {
+                flags,
                 cursor_id: cid,
                 documents: docs,
                 ..
             } => {
-                let out_doc = if let Some(out_doc) = docs.get(0) {
-                    if let Some(&Bson::I32(code)) = out_doc.get("code") {
-                        // If command doesn't exist or namespace not found, return
-                        // an empty array instead of throwing an error.
-                        if code != ErrorCode::CommandNotFound as i32 &&
-                            code != ErrorCode::NamespaceNotFound as i32
-                        {
-                            if let Some(&Bson::String(ref msg)) = out_doc.get("errmsg") {
-                                return Err(Error::OperationError(msg.to_owned()));
-                            }
-                        }
+                let out_doc = match docs.first() {
+                    Some(out_doc) => {
+                        Cursor::check_reply(out_doc, flags)?;
+                        out_doc.clone()
                     }
-                    out_doc.clone()
-                } else {
-                    bson::Document::new()
+                    None => bson::Document::new(),
                 };
 
                 Ok((out_doc, docs.into_iter().collect(), cid))
@@ -157,6 +623,67 @@ impl Cursor {
         }
     }
 
+    // Returns the error reported by a reply's first document, if any.
+    fn check_reply(out_doc: &bson::Document, flags: OpReplyFlags) -> Result<()> {
+        // A query's results may have a `code` field of their own, so only a reply that reports a
+        // failure is classified by it.
+        if !Cursor::is_failure(out_doc, flags) {
+            return Ok(());
+        }
+
+        if let Some(&Bson::I32(code)) = out_doc.get("code") {
+            // Operations interrupted by maxTimeMS are reported distinctly, so callers can retry
+            // or back off.
+            if code == ErrorCode::ExceededTimeLimit as i32 {
+                return Err(Error::CodedError(ErrorCode::ExceededTimeLimit));
+            }
+
+            // So are sorts and groups that need more memory than the server allows without
+            // spilling to disk.
+            if Cursor::is_memory_limit_code(code) {
+                return Err(Error::CodedError(
+                    ErrorCode::QueryExceededMemoryLimitNoDiskUseAllowed,
+                ));
+            }
+
+            // If command doesn't exist or namespace not found, return an empty array instead of
+            // throwing an error.
+            if code != ErrorCode::CommandNotFound as i32 &&
+                code != ErrorCode::NamespaceNotFound as i32
+            {
+                if let Some(err) = ServerError::parse(out_doc) {
+                    return Err(Error::ServerError(err));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Whether a reply reports a failure: the server flagged the query as failed, or its first
+    // document is a command reply with `ok: 0` or an error message.
+    fn is_failure(out_doc: &bson::Document, flags: OpReplyFlags) -> bool {
+        if flags.contains(OpReplyFlags::QUERY_FAILURE) || out_doc.contains_key("$err") ||
+            out_doc.contains_key("errmsg")
+        {
+            return true;
+        }
+
+        match out_doc.get("ok") {
+            Some(&Bson::I32(ok)) => ok == 0,
+            Some(&Bson::I64(ok)) => ok == 0,
+            Some(&Bson::FloatingPoint(ok)) => ok == 0.0,
+            _ => false,
+        }
+    }
+
+    // Whether a server error code reports a query that exceeded its memory limit, including
+    // the codes used for sorts and groups before MongoDB 4.4.
+    fn is_memory_limit_code(code: i32) -> bool {
+        code == ErrorCode::QueryExceededMemoryLimitNoDiskUseAllowed as i32 ||
+            code == SORT_EXCEEDED_MEMORY_LIMIT || code == GROUP_EXCEEDED_MEMORY_LIMIT
+    }
+
     fn get_bson_and_cursor_info_from_command_message(
         message: Message,
     ) -> Result<(bson::Document, VecDeque<bson::Document>, i64, String)> {
@@ -218,42 +745,15 @@ impl Cursor {
         read_pref: ReadPreference,
     ) -> Result<Cursor> {
 
-        // Select a server stream from the topology.
-        let (mut stream, slave_ok, send_read_pref) = if cmd_type.is_write_command() {
-            (client.acquire_write_stream()?, false, false)
-        } else {
-            client.acquire_stream(read_pref.to_owned())?
-        };
-
-        // Set slave_ok flag based on the result from server selection.
-        let new_flags = if slave_ok {
-            flags | OpQueryFlags::SLAVE_OK
-        } else {
-            flags
-        };
-
-        // Send read_preference to the server based on the result from server selection.
-        let new_query = if !send_read_pref {
-            query
-        } else if query.contains_key("$query") {
-            // Query is already formatted as a $query document; add onto it.
-            let mut query = query;
-            query.insert("read_preference", read_pref.to_document());
-            query
-        } else {
-            // Convert the query to a $query document.
-            doc! {
-                "$query": query,
-                "read_preference": read_pref.to_document(),
-            }
-        };
+        let (mut stream, flags, query) =
+            acquire_query_stream(&client, flags, query, &options, cmd_type, &read_pref)?;
 
         Cursor::query_with_stream(
             &mut stream,
             client,
             namespace,
-            new_flags,
-            new_query,
+            flags,
+            query,
             options,
             cmd_type,
             is_cmd_cursor,
@@ -261,6 +761,8 @@ impl Cursor {
         )
     }
 
+    /// Executes a query on `stream`, whose server also answers the cursor's getMores. The read
+    /// preference is unused, since the server has already been chosen.
     pub fn query_with_stream(
         stream: &mut PooledStream,
         client: Client,
@@ -270,34 +772,19 @@ impl Cursor {
         options: FindOptions,
         cmd_type: CommandType,
         is_cmd_cursor: bool,
-        read_pref: Option<ReadPreference>,
+        _read_pref: Option<ReadPreference>,
     ) -> Result<Cursor> {
 
-        let socket = stream.get_socket();
+        let query = trace_query(&client, &namespace, cmd_type, query);
+        let query = encode_query(&client, &namespace, query)?;
         let req_id = client.get_req_id();
+        let operation_id = Operation::current_id().unwrap_or_else(operation::next_id);
 
-        let index = namespace.find('.').unwrap_or_else(|| namespace.len());
-        let db_name = String::from(&namespace[..index]);
-        let coll_name = String::from(&namespace[index + 1..]);
+        let (db_name, collection_name, command) =
+            describe_query(&namespace, &query, &options, cmd_type, is_cmd_cursor);
         let cmd_name = cmd_type.to_str();
-        let connstring = socket.get_ref().peer_addr()?.to_string();
-
-        let filter = match query.get("$query") {
-            Some(&Bson::Document(ref doc)) => doc.clone(),
-            _ => query.clone(),
-        };
-
-        let command = match cmd_type {
-            CommandType::Find => {
-                let document = doc! {
-                    "find": coll_name,
-                    "filter": filter
-                };
-
-                merge_options(document, options.clone())
-            }
-            _ => query.clone(),
-        };
+        let connstring = stream.get_socket().get_ref().peer_addr()?.to_string();
+        let host = stream.host().to_string();
 
         let init_time = time::precise_time_ns();
         let message = Message::new_query(
@@ -313,10 +800,13 @@ impl Cursor {
         if cmd_type != CommandType::Suppressed {
             let hook_result = client.run_start_hooks(&CommandStarted {
                 command: command,
-                database_name: db_name,
+                database_name: db_name.clone(),
+                collection_name: collection_name.clone(),
                 command_name: String::from(cmd_name),
                 request_id: req_id as i64,
+                operation_id,
                 connection_string: connstring.clone(),
+                host: host.clone(),
             });
 
             if hook_result.is_err() {
@@ -324,20 +814,32 @@ impl Cursor {
             }
         }
 
+        let written = stream.write_message(&message);
         try_or_emit!(
             cmd_type,
             cmd_name,
+            db_name,
+            collection_name,
             req_id,
+            operation_id,
             connstring,
-            message.write(socket),
+            host,
+            init_time,
+            stream.check(written),
             client
         );
+        let read = stream.read_reply(&client.decode_limits);
         let reply = try_or_emit!(
             cmd_type,
             cmd_name,
+            db_name,
+            collection_name,
             req_id,
+            operation_id,
             connstring,
-            Message::read(socket),
+            host,
+            init_time,
+            stream.check(read),
             client
         );
 
@@ -347,8 +849,13 @@ impl Cursor {
             try_or_emit!(
                 cmd_type,
                 cmd_name,
+                db_name,
+                collection_name,
                 req_id,
+                operation_id,
                 connstring,
+                host,
+                init_time,
                 Cursor::get_bson_and_cursor_info_from_command_message(reply),
                 client
             )
@@ -356,14 +863,30 @@ impl Cursor {
             let (doc, buf, id) = try_or_emit!(
                 cmd_type,
                 cmd_name,
+                db_name,
+                collection_name,
                 req_id,
+                operation_id,
                 connstring,
+                host,
+                init_time,
                 Cursor::get_bson_and_cid_from_message(reply),
                 client
             );
             (doc, buf, id, namespace)
         };
 
+        if let Some(ref session) = client.session {
+            if let Ok(time) = doc.get_time_stamp("operationTime") {
+                session.advance_operation_time(time);
+            }
+        }
+
+        let at_cluster_time = doc
+            .get_document("cursor")
+            .ok()
+            .and_then(|cursor| cursor.get_time_stamp("atClusterTime").ok());
+
         let reply = match cmd_type {
             CommandType::Find => doc! {
                 "cursor": {
@@ -381,14 +904,15 @@ impl Cursor {
                 duration: fin_time - init_time,
                 reply: reply,
                 command_name: String::from(cmd_name),
+                database_name: db_name,
+                collection_name,
                 request_id: req_id as i64,
+                operation_id,
                 connection_string: connstring,
+                host,
             });
         }
 
-        let read_preference =
-            read_pref.unwrap_or_else(|| ReadPreference::new(ReadMode::Primary, None));
-
         // Check if actual batch size fits into an `i32`.
         if size_of::<i32>() <= size_of::<usize>() && buf.len() > i32::MAX as usize {
             return Err(Error::DefaultError(
@@ -396,65 +920,154 @@ impl Cursor {
             ));
         }
 
+        let buf = decode_batch(&client, buf)?;
+
         Ok(Cursor {
             client: client,
             namespace: namespace,
-            batch_size: buf.len() as i32,
+            address: stream.host().clone(),
+            batch_size: options.get_more_batch_size.unwrap_or(buf.len() as i32),
+            adaptive_batch_size: options.adaptive_batch_size,
+            docs_read: 0,
+            bytes_read: 0,
             cursor_id: cursor_id,
             limit: options.limit.unwrap_or(0) as i32,
             count: 0,
             buffer: buf,
-            read_preference: read_preference,
             cmd_type: cmd_type.clone(),
+            operation_id,
+            deadline: stream.deadline(),
+            prefetching: false,
+            prefetch: None,
+            at_cluster_time,
+            deferred_error: None,
         })
     }
 
+    // Describes the getMore that fetches the cursor's next batch.
+    fn get_more(&self) -> GetMore {
+        GetMore {
+            client: self.client.clone(),
+            namespace: self.namespace.clone(),
+            batch_size: self.batch_size,
+            cursor_id: self.cursor_id,
+            address: self.address.clone(),
+            cmd_type: self.cmd_type,
+            operation_id: self.operation_id,
+            deadline: self.deadline.clone(),
+        }
+    }
+
     fn get_from_stream(&mut self) -> Result<()> {
-        let (mut stream, _, _) = self.client.acquire_stream(self.read_preference.to_owned())?;
-        let socket = stream.get_socket();
+        let result = match self.prefetch.take() {
+            Some(handle) => handle.join().unwrap_or_else(|_| {
+                Err(Error::OperationError(
+                    String::from("Cursor prefetch thread panicked."),
+                ))
+            }),
+            None => self.get_more().run(),
+        };
 
-        let req_id = self.client.get_req_id();
-        let get_more = Message::new_get_more(
-            req_id,
-            self.namespace.to_owned(),
-            self.batch_size,
-            self.cursor_id,
-        );
+        let (docs, cursor_id, bytes) = result?;
+        self.cursor_id = cursor_id;
 
-        let index = self.namespace.rfind('.').unwrap_or_else(
-            || self.namespace.len(),
-        );
-        let db_name = String::from(&self.namespace[..index]);
-        let cmd_name = String::from("get_more");
-        let connstring = socket.get_ref().peer_addr()?.to_string();
+        if self.adaptive_batch_size {
+            self.docs_read += docs.len();
+            self.bytes_read += bytes;
+            self.batch_size = adapt_batch_size(self.batch_size, self.docs_read, self.bytes_read);
+        }
 
-        if self.cmd_type != CommandType::Suppressed {
-            let hook_result = self.client.run_start_hooks(&CommandStarted {
-                command: doc! { "cursor_id": self.cursor_id },
-                database_name: db_name,
-                command_name: cmd_name.clone(),
-                request_id: req_id as i64,
-                connection_string: connstring.clone(),
-            });
+        self.buffer.extend(decode_batch(&self.client, docs)?);
+        Ok(())
+    }
 
-            if hook_result.is_err() {
-                return Err(Error::EventListenerError(None));
+    // Starts fetching the next batch on a helper thread, if prefetching is enabled and the
+    // server may still have documents the limit allows.
+    fn start_prefetch(&mut self) {
+        let exhausted = self.limit > 0 &&
+            i64::from(self.count) + self.buffer.len() as i64 >= i64::from(self.limit);
+
+        if !self.prefetching || self.prefetch.is_some() || self.cursor_id == 0 ||
+            self.limit == 1 || exhausted
+        {
+            return;
+        }
+
+        let get_more = self.get_more();
+        self.prefetch = Some(thread::spawn(move || get_more.run()));
+    }
+
+    /// Converts the cursor into one that deserializes each document into a `T`.
+    pub fn deserialize<T: DeserializeOwned>(self) -> TypedCursor<T> {
+        TypedCursor {
+            cursor: self,
+            marker: PhantomData,
+        }
+    }
+
+    /// Deserializes the cursor's remaining documents into a vector, failing with
+    /// `Error::CollectLimitExceeded` rather than gathering more than `max_docs` documents or more
+    /// than `max_bytes` bytes of BSON.
+    ///
+    /// Unlike `collect`, this bounds the memory a large result set can take. The limits are
+    /// checked as each document arrives, so the cursor stops fetching batches once one is
+    /// exceeded.
+    pub fn collect_typed<T: DeserializeOwned>(
+        &mut self,
+        max_docs: usize,
+        max_bytes: usize,
+    ) -> Result<Vec<T>> {
+        let mut results = Vec::new();
+        let mut total_bytes = 0;
+        let mut buffer = Vec::new();
+
+        for result in self {
+            let doc = result?;
+            if results.len() == max_docs {
+                return Err(Error::CollectLimitExceeded(CollectLimit::Documents(max_docs)));
+            }
+
+            buffer.clear();
+            bson::encode_document(&mut buffer, &doc)?;
+            total_bytes += buffer.len();
+            if total_bytes > max_bytes {
+                return Err(Error::CollectLimitExceeded(CollectLimit::Bytes(max_bytes)));
             }
+
+            results.push(bson::from_bson(Bson::Document(doc))?);
         }
 
-        try_or_emit!(
-            self.cmd_type,
-            cmd_name,
-            req_id,
-            connstring,
-            get_more.write(socket.get_mut()),
-            self.client
-        );
-        let reply = Message::read(socket.get_mut())?;
+        Ok(results)
+    }
 
-        let (_, v, _) = Cursor::get_bson_and_cid_from_message(reply)?;
-        self.buffer.extend(v);
-        Ok(())
+    /// Enables or disables background prefetching.
+    ///
+    /// While prefetching, the cursor requests its next batch on a helper thread as soon as it
+    /// starts returning documents from the current one, so that iteration does not wait for a
+    /// round trip between batches. This holds a second connection from the pool while the
+    /// request is in flight, and fetches one batch ahead even if iteration stops early.
+    pub fn set_prefetch(&mut self, prefetch: bool) {
+        self.prefetching = prefetch;
+    }
+
+    /// Sets how many documents each subsequent getMore requests, leaving the size of batches
+    /// already read or in flight unchanged. 0 lets the server decide.
+    pub fn set_batch_size(&mut self, batch_size: i32) {
+        self.batch_size = batch_size;
+    }
+
+    /// Enables or disables adaptive batch sizes.
+    ///
+    /// While adaptive, each getMore requests up to twice as many documents as the last, capped
+    /// at how many documents of the average size read so far fit in a 16MB reply. Large result
+    /// sets then take fewer round trips without a batch size tuned to their documents.
+    pub fn set_adaptive_batch_size(&mut self, adaptive: bool) {
+        self.adaptive_batch_size = adaptive;
+    }
+
+    /// Returns how many documents the next getMore requests; 0 lets the server decide.
+    pub fn batch_size(&self) -> i32 {
+        self.batch_size
     }
 
     /// Attempts to read a specified number of BSON documents from the cursor.
@@ -515,6 +1128,69 @@ impl Cursor {
         Ok(self.buffer.drain(..).collect())
     }
 
+    /// Returns the next document and any others already read from the server, reading a batch
+    /// only if none are left. An empty vector means the cursor is exhausted.
+    ///
+    /// An error met after some documents were collected is returned by the next call, after
+    /// those documents.
+    pub fn next_buffered(&mut self) -> Result<Vec<bson::Document>> {
+        if let Some(err) = self.deferred_error.take() {
+            return Err(err);
+        }
+
+        let mut docs = Vec::new();
+        while docs.is_empty() || !self.buffer.is_empty() {
+            match self.next() {
+                Some(Ok(doc)) => docs.push(doc),
+                Some(Err(err)) => {
+                    if docs.is_empty() {
+                        return Err(err);
+                    }
+                    self.deferred_error = Some(err);
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        Ok(docs)
+    }
+
+    /// Iterates the cursor on a worker run by `executor`, returning its documents as a `Stream`.
+    /// See the `cursor_stream` module.
+    #[cfg(feature = "stream")]
+    pub fn into_stream<E: Executor>(self, executor: E) -> CursorStream {
+        // Read ahead at most one batch.
+        let capacity = if self.batch_size > 0 {
+            self.batch_size as usize
+        } else {
+            cursor_stream::DEFAULT_CAPACITY
+        };
+
+        cursor_stream::spawn(self, capacity, executor)
+    }
+
+    /// Returns the server's id for the cursor, or 0 once the server has closed it.
+    pub fn id(&self) -> i64 {
+        self.cursor_id
+    }
+
+    /// Returns the operation id shared by the cursor's initial query and all of its getMores.
+    pub fn operation_id(&self) -> i64 {
+        self.operation_id
+    }
+
+    /// Returns the server the cursor's query was sent to, as selected by its read preference.
+    pub fn address(&self) -> Host {
+        self.address.clone()
+    }
+
+    /// Returns the cluster time, as a BSON timestamp, that the server read at if the cursor was
+    /// opened with a `snapshot` read concern.
+    pub fn at_cluster_time(&self) -> Option<i64> {
+        self.at_cluster_time
+    }
+
     /// Checks whether there are any more documents for the cursor to return.
     ///
     /// # Return value
@@ -543,6 +1219,332 @@ impl Iterator for Cursor {
     /// there are no more documents to return; or an Error if the request for
     /// another document fails.
     fn next(&mut self) -> Option<Result<bson::Document>> {
+        match self.has_next() {
+            Ok(true) => {
+                self.count += 1;
+                let doc = self.buffer.pop_front();
+                self.start_prefetch();
+                doc.map(Ok)
+            }
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// A cursor that deserializes each document it returns into a `T`.
+///
+/// A document that does not deserialize yields an `Error::DecoderError`; iteration can continue
+/// past it.
+#[derive(Debug)]
+pub struct TypedCursor<T> {
+    cursor: Cursor,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedCursor<T> {
+    /// Returns the underlying cursor, which yields documents.
+    pub fn into_inner(self) -> Cursor {
+        self.cursor
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for TypedCursor<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        self.cursor.next().map(|result| {
+            result.and_then(|doc| {
+                bson::from_bson(Bson::Document(doc)).map_err(Error::DecoderError)
+            })
+        })
+    }
+}
+
+/// A cursor that returns each document as the server encoded it, leaving its fields to be parsed
+/// as they are read. See the `raw` module.
+///
+/// Completion hooks see the cursor's id but not its documents, which are never decoded.
+///
+/// Clients with a `uuid_representation` or automatic encryption cannot open raw cursors, since
+/// their documents must be decoded before they are read.
+#[derive(Debug)]
+pub struct RawCursor {
+    client: Client,
+    namespace: String,
+    address: Host,
+    batch_size: i32,
+    adaptive_batch_size: bool,
+    docs_read: usize,
+    bytes_read: usize,
+    cursor_id: i64,
+    limit: i32,
+    count: i32,
+    buffer: VecDeque<RawDocumentBuf>,
+    cmd_type: CommandType,
+    operation_id: i64,
+    deadline: Deadline,
+}
+
+impl RawCursor {
+    /// Executes a query, as `Cursor::query` does, returning a cursor over its raw documents.
+    pub fn query(
+        client: Client,
+        namespace: String,
+        flags: OpQueryFlags,
+        query: bson::Document,
+        options: FindOptions,
+        cmd_type: CommandType,
+        is_cmd_cursor: bool,
+        read_pref: ReadPreference,
+    ) -> Result<RawCursor> {
+        if decodes_batches(&client) {
+            return Err(Error::ArgumentError(String::from(
+                "Raw cursors cannot be opened by a client with a uuid_representation or automatic \
+                 encryption, whose documents must be decoded.",
+            )));
+        }
+
+        let (mut stream, flags, query) =
+            acquire_query_stream(&client, flags, query, &options, cmd_type, &read_pref)?;
+
+        let query = trace_query(&client, &namespace, cmd_type, query);
+        let query = encode_query(&client, &namespace, query)?;
+        let req_id = client.get_req_id();
+        let operation_id = Operation::current_id().unwrap_or_else(operation::next_id);
+
+        let (db_name, collection_name, command) =
+            describe_query(&namespace, &query, &options, cmd_type, is_cmd_cursor);
+        let cmd_name = cmd_type.to_str();
+        let connstring = stream.get_socket().get_ref().peer_addr()?.to_string();
+        let host = stream.host().to_string();
+
+        let init_time = time::precise_time_ns();
+        let message = Message::new_query(
+            req_id,
+            flags,
+            namespace.clone(),
+            options.skip.unwrap_or(0) as i32,
+            options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+            query,
+            options.projection,
+        )?;
+
+        if cmd_type != CommandType::Suppressed {
+            let hook_result = client.run_start_hooks(&CommandStarted {
+                command: command,
+                database_name: db_name.clone(),
+                collection_name: collection_name.clone(),
+                command_name: String::from(cmd_name),
+                request_id: req_id as i64,
+                operation_id,
+                connection_string: connstring.clone(),
+                host: host.clone(),
+            });
+
+            if hook_result.is_err() {
+                return Err(Error::EventListenerError(None));
+            }
+        }
+
+        let written = stream.write_message(&message);
+        try_or_emit!(
+            cmd_type,
+            cmd_name,
+            db_name,
+            collection_name,
+            req_id,
+            operation_id,
+            connstring,
+            host,
+            init_time,
+            stream.check(written),
+            client
+        );
+        let read = stream.read_raw_reply(&client.decode_limits);
+        let reply = try_or_emit!(
+            cmd_type,
+            cmd_name,
+            db_name,
+            collection_name,
+            req_id,
+            operation_id,
+            connstring,
+            host,
+            init_time,
+            stream.check(read),
+            client
+        );
+
+        let fin_time = time::precise_time_ns();
+
+        if let Some(ref session) = client.session {
+            let first = reply.documents.first().map(|doc| doc.as_raw());
+            if let Some(Ok(Some(RawBson::TimeStamp(time)))) =
+                first.map(|doc| doc.get("operationTime"))
+            {
+                session.advance_operation_time(time);
+            }
+        }
+
+        let parsed = if is_cmd_cursor {
+            RawCursor::command_batch(reply)
+        } else {
+            RawCursor::batch(reply).map(|(buf, id)| (buf, id, namespace))
+        };
+
+        let (buf, cursor_id, namespace) = try_or_emit!(
+            cmd_type,
+            cmd_name,
+            db_name,
+            collection_name,
+            req_id,
+            operation_id,
+            connstring,
+            host,
+            init_time,
+            parsed,
+            client
+        );
+
+        if cmd_type != CommandType::Suppressed {
+            let _hook_result = client.run_completion_hooks(&CommandResult::Success {
+                duration: fin_time - init_time,
+                reply: doc! {
+                    "cursor": { "id": cursor_id, "ns": &namespace },
+                    "ok": 1
+                },
+                command_name: String::from(cmd_name),
+                database_name: db_name,
+                collection_name,
+                request_id: req_id as i64,
+                operation_id,
+                connection_string: connstring,
+                host,
+            });
+        }
+
+        Ok(RawCursor {
+            client: client,
+            namespace: namespace,
+            address: stream.host().clone(),
+            batch_size: options.get_more_batch_size.unwrap_or(buf.len() as i32),
+            adaptive_batch_size: options.adaptive_batch_size,
+            docs_read: 0,
+            bytes_read: 0,
+            cursor_id: cursor_id,
+            limit: options.limit.unwrap_or(0) as i32,
+            count: 0,
+            buffer: buf,
+            cmd_type: cmd_type,
+            operation_id,
+            deadline: stream.deadline(),
+        })
+    }
+
+    /// Returns the server's id for the cursor, or 0 once the server has closed it.
+    pub fn id(&self) -> i64 {
+        self.cursor_id
+    }
+
+    /// Returns the operation id shared by the cursor's initial query and all of its getMores.
+    pub fn operation_id(&self) -> i64 {
+        self.operation_id
+    }
+
+    /// Returns the server the cursor's query was sent to, as selected by its read preference.
+    pub fn address(&self) -> Host {
+        self.address.clone()
+    }
+
+    /// Checks whether there are any more documents for the cursor to return.
+    pub fn has_next(&mut self) -> Result<bool> {
+        if self.limit > 0 && self.count >= self.limit {
+            Ok(false)
+        } else {
+            if self.buffer.is_empty() && self.limit != 1 && self.cursor_id != 0 {
+                self.get_from_stream()?;
+            }
+            Ok(!self.buffer.is_empty())
+        }
+    }
+
+    fn get_from_stream(&mut self) -> Result<()> {
+        let get_more = GetMore {
+            client: self.client.clone(),
+            namespace: self.namespace.clone(),
+            batch_size: self.batch_size,
+            cursor_id: self.cursor_id,
+            address: self.address.clone(),
+            cmd_type: self.cmd_type,
+            operation_id: self.operation_id,
+            deadline: self.deadline.clone(),
+        };
+
+        let (docs, cursor_id, bytes) = get_more.run_with(PooledStream::read_raw_reply, |reply| {
+            let bytes = reply.header.message_length as usize;
+            RawCursor::batch(reply).map(|(docs, cursor_id)| (docs, cursor_id, bytes))
+        })?;
+        self.cursor_id = cursor_id;
+
+        if self.adaptive_batch_size {
+            self.docs_read += docs.len();
+            self.bytes_read += bytes;
+            self.batch_size = adapt_batch_size(self.batch_size, self.docs_read, self.bytes_read);
+        }
+
+        self.buffer.extend(docs);
+        Ok(())
+    }
+
+    // Takes the documents and cursor id from a reply, failing if its first document reports an
+    // error as `Cursor::get_bson_and_cid_from_message` would.
+    fn batch(reply: RawReply) -> Result<(VecDeque<RawDocumentBuf>, i64)> {
+        if let Some(first) = reply.documents.first() {
+            // Only a document with an error code needs decoding to check it.
+            if let Some(RawBson::I32(_)) = first.as_raw().get("code")? {
+                Cursor::check_reply(&first.to_document()?, reply.flags)?;
+            }
+        }
+
+        Ok((VecDeque::from(reply.documents), reply.cursor_id))
+    }
+
+    // Takes the first batch, cursor id and namespace from a command's reply.
+    fn command_batch(reply: RawReply) -> Result<(VecDeque<RawDocumentBuf>, i64, String)> {
+        let (docs, _) = RawCursor::batch(reply)?;
+        let first = match docs.front() {
+            Some(first) => first.as_raw(),
+            None => return Err(Error::CursorNotFoundError),
+        };
+
+        let cursor = match first.get("cursor")? {
+            Some(RawBson::Document(cursor)) => cursor,
+            _ => return Err(Error::CursorNotFoundError),
+        };
+
+        match (cursor.get("id")?, cursor.get("ns")?, cursor.get("firstBatch")?) {
+            (Some(RawBson::I64(id)),
+             Some(RawBson::String(ns)),
+             Some(RawBson::Array(batch))) => {
+                let mut buf = VecDeque::new();
+                for value in batch {
+                    if let RawBson::Document(doc) = value? {
+                        buf.push_back(doc.to_raw_document_buf());
+                    }
+                }
+
+                Ok((buf, id, String::from(ns)))
+            }
+            _ => Err(Error::CursorNotFoundError),
+        }
+    }
+}
+
+impl Iterator for RawCursor {
+    type Item = Result<RawDocumentBuf>;
+
+    fn next(&mut self) -> Option<Result<RawDocumentBuf>> {
         match self.has_next() {
             Ok(true) => {
                 self.count += 1;
@@ -553,3 +1555,137 @@ impl Iterator for Cursor {
         }
     }
 }
+
+// Doubles a batch size, capped at how many documents of the average size read so far fit in one
+// reply. A size left to the server grows from the number of documents read so far instead.
+fn adapt_batch_size(batch_size: i32, docs_read: usize, bytes_read: usize) -> i32 {
+    if docs_read == 0 {
+        return batch_size;
+    }
+
+    let average = cmp::max(bytes_read / docs_read, 1);
+    let fits = cmp::max(MAX_REPLY_BYTES / average, 1);
+    let grown = if batch_size > 0 {
+        batch_size as usize * 2
+    } else {
+        docs_read * 2
+    };
+
+    cmp::min(grown, fits) as i32
+}
+
+// A getMore request for a cursor's next batch, which can be sent from another thread.
+struct GetMore {
+    client: Client,
+    namespace: String,
+    batch_size: i32,
+    cursor_id: i64,
+    // The server the cursor lives on, which every getMore must reach.
+    address: Host,
+    cmd_type: CommandType,
+    operation_id: i64,
+    deadline: Deadline,
+}
+
+impl GetMore {
+    fn run(self) -> Result<Batch> {
+        self.run_with(PooledStream::read_reply, |reply| {
+            let bytes = match reply {
+                Message::OpReply { ref header, .. } => header.message_length as usize,
+                _ => 0,
+            };
+            let (_, docs, cursor_id) = Cursor::get_bson_and_cid_from_message(reply)?;
+            Ok((docs, cursor_id, bytes))
+        })
+    }
+
+    // Sends the getMore, reading its reply with `read` and taking the batch from it with `parse`.
+    fn run_with<M, T, R, P>(self, read: R, parse: P) -> Result<T>
+    where
+        R: FnOnce(&mut PooledStream, &DecodeLimits) -> Result<M>,
+        P: FnOnce(M) -> Result<T>,
+    {
+        let mut stream = self.client.acquire_stream_on(&self.address, self.deadline.clone())?;
+        stream.set_deadline(self.deadline.clone())?;
+
+        let req_id = self.client.get_req_id();
+        let get_more = Message::new_get_more(
+            req_id,
+            self.namespace.clone(),
+            self.batch_size,
+            self.cursor_id,
+        );
+
+        let index = self.namespace.find('.').unwrap_or_else(
+            || self.namespace.len(),
+        );
+        let db_name = String::from(&self.namespace[..index]);
+        let collection_name = self.namespace.get(index + 1..).map(String::from);
+        let cmd_name = String::from("get_more");
+        let connstring = stream.get_socket().get_ref().peer_addr()?.to_string();
+        let host = stream.host().to_string();
+
+        if self.cmd_type != CommandType::Suppressed {
+            let hook_result = self.client.run_start_hooks(&CommandStarted {
+                command: doc! { "cursor_id": self.cursor_id },
+                database_name: db_name.clone(),
+                collection_name: collection_name.clone(),
+                command_name: cmd_name.clone(),
+                request_id: req_id as i64,
+                operation_id: self.operation_id,
+                connection_string: connstring.clone(),
+                host: host.clone(),
+            });
+
+            if hook_result.is_err() {
+                return Err(Error::EventListenerError(None));
+            }
+        }
+
+        let init_time = time::precise_time_ns();
+        let written = stream.write_message(&get_more);
+        try_or_emit!(
+            self.cmd_type,
+            cmd_name,
+            db_name,
+            collection_name,
+            req_id,
+            self.operation_id,
+            connstring,
+            host,
+            init_time,
+            stream.check(written),
+            self.client
+        );
+        let limits = self.client.decode_limits;
+        let read = read(&mut stream, &limits);
+        let reply = try_or_emit!(
+            self.cmd_type,
+            cmd_name,
+            db_name,
+            collection_name,
+            req_id,
+            self.operation_id,
+            connstring,
+            host,
+            init_time,
+            stream.check(read),
+            self.client
+        );
+
+        let batch = try_or_emit!(
+            self.cmd_type,
+            cmd_name,
+            db_name,
+            collection_name,
+            req_id,
+            self.operation_id,
+            connstring,
+            host,
+            init_time,
+            parse(reply),
+            self.client
+        );
+        Ok(batch)
+    }
+}
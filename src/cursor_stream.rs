@@ -0,0 +1,122 @@
+//! Consuming a blocking cursor as a `Stream`.
+//!
+//! With the `stream` feature, `Cursor::into_stream` iterates a cursor on a worker thread and
+//! hands its documents to a `CursorStream`, a `futures::Stream`, so async code can consume query
+//! results without blocking its executor or plumbing channels by hand. `Collection::find_stream`
+//! does the same for a query.
+//!
+//! The worker runs on whatever `Executor` is given: a `ThreadExecutor` starts a thread, and any
+//! function taking the work does too, such as one handing it to Tokio's `spawn_blocking`. The
+//! worker reads ahead at most one batch, waiting while the stream's buffer is full, and stops
+//! once the stream is dropped.
+//!
+//! ```no_run
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::cursor_stream::ThreadExecutor;
+//! # use mongodb::db::ThreadedDatabase;
+//! #
+//! # fn main() {
+//! let client = Client::connect("localhost", 27017).unwrap();
+//! let events = client.db("app").collection("events");
+//! let stream = events.find_stream(None, None, ThreadExecutor).unwrap();
+//! # }
+//! ```
+use bson;
+use futures_core::Stream;
+
+use Result;
+use cursor::Cursor;
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+/// The documents a stream buffers for a cursor without a batch size: the size of the server's
+/// default first batch.
+pub const DEFAULT_CAPACITY: usize = 101;
+
+/// Runs a stream's worker, which blocks while it reads from the server.
+pub trait Executor {
+    fn execute(&self, work: Box<dyn FnOnce() + Send>);
+}
+
+impl<F: Fn(Box<dyn FnOnce() + Send>)> Executor for F {
+    fn execute(&self, work: Box<dyn FnOnce() + Send>) {
+        self(work)
+    }
+}
+
+/// Runs each worker on a new thread.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThreadExecutor;
+
+impl Executor for ThreadExecutor {
+    fn execute(&self, work: Box<dyn FnOnce() + Send>) {
+        thread::spawn(work);
+    }
+}
+
+/// The documents of a cursor iterated on a worker, as a `Stream`.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct CursorStream {
+    receiver: Receiver<Result<bson::Document>>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+/// Starts iterating `cursor` on `executor`, buffering up to `capacity` documents.
+pub fn spawn<E: Executor>(cursor: Cursor, capacity: usize, executor: E) -> CursorStream {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    let waker = Arc::new(Mutex::new(None));
+    let worker_waker = waker.clone();
+
+    executor.execute(Box::new(move || run(cursor, sender, &worker_waker)));
+    CursorStream { receiver, waker }
+}
+
+// Sends each of the cursor's documents to the stream until it runs out or the stream is dropped.
+fn run(cursor: Cursor, sender: SyncSender<Result<bson::Document>>, waker: &Mutex<Option<Waker>>) {
+    for result in cursor {
+        if sender.send(result).is_err() {
+            return;
+        }
+
+        wake(waker);
+    }
+
+    // Wake the stream once more to see the channel close.
+    drop(sender);
+    wake(waker);
+}
+
+fn wake(waker: &Mutex<Option<Waker>>) {
+    if let Some(waker) = waker.lock().ok().and_then(|mut waker| waker.take()) {
+        waker.wake();
+    }
+}
+
+impl Stream for CursorStream {
+    type Item = Result<bson::Document>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        match self.receiver.try_recv() {
+            Ok(result) => return Poll::Ready(Some(result)),
+            Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+            Err(TryRecvError::Empty) => (),
+        }
+
+        if let Ok(mut waker) = self.waker.lock() {
+            *waker = Some(cx.waker().clone());
+        }
+
+        // The worker may have sent a document before the waker was stored.
+        match self.receiver.try_recv() {
+            Ok(result) => Poll::Ready(Some(result)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+}
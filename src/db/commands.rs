@@ -0,0 +1,362 @@
+//! Typed replies to common server commands.
+//!
+//! These can be read with `ThreadedDatabase::run_command_as` or
+//! `ThreadedClient::admin_command_as`. Each covers the fields most applications need; the rest
+//! of the reply is ignored. Fields that not every server version or deployment reports are
+//! optional. `ThreadedDatabase::list_collections` yields a `CollectionSpecification` for each
+//! collection or view, and `ThreadedDatabase::slow_queries` a `ProfileEntry` for each operation
+//! the profiler recorded. `ThreadedClient::server_status`, `build_info` and `host_info` read the
+//! replies used for monitoring.
+use bson;
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// The reply to `buildInfo`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct BuildInfo {
+    pub version: String,
+    #[serde(rename = "gitVersion")]
+    pub git_version: String,
+    /// The version as `[major, minor, patch, release]`.
+    #[serde(rename = "versionArray")]
+    pub version_array: Vec<i32>,
+    pub bits: i32,
+    pub debug: bool,
+    #[serde(rename = "maxBsonObjectSize")]
+    pub max_bson_object_size: i32,
+    #[serde(rename = "storageEngines")]
+    pub storage_engines: Option<Vec<String>>,
+    /// Enterprise modules compiled into the server, such as `enterprise`.
+    #[serde(default)]
+    pub modules: Vec<String>,
+    pub allocator: Option<String>,
+    #[serde(rename = "javascriptEngine")]
+    pub javascript_engine: Option<String>,
+}
+
+/// Connection counts from `serverStatus`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerConnections {
+    pub current: i64,
+    pub available: i64,
+    #[serde(rename = "totalCreated")]
+    pub total_created: Option<i64>,
+}
+
+/// Operation counts from `serverStatus`, since the process started.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct OpCounters {
+    pub insert: i64,
+    pub query: i64,
+    pub update: i64,
+    pub delete: i64,
+    pub getmore: i64,
+    pub command: i64,
+}
+
+/// Memory use from `serverStatus`, in megabytes.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerMemory {
+    /// Whether the process was compiled as 32 or 64 bit.
+    pub bits: i32,
+    #[serde(rename = "resident")]
+    pub resident_mb: i64,
+    #[serde(rename = "virtual")]
+    pub virtual_mb: i64,
+    /// Whether the platform reports memory use; the sizes are absent if not.
+    #[serde(default)]
+    pub supported: bool,
+}
+
+/// The reply to `serverStatus`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ServerStatus {
+    pub host: String,
+    pub version: String,
+    /// The server process, either `mongod` or `mongos`.
+    pub process: String,
+    pub pid: i64,
+    /// Seconds since the process started.
+    pub uptime: f64,
+    #[serde(rename = "uptimeMillis")]
+    pub uptime_millis: i64,
+    pub connections: Option<ServerConnections>,
+    pub opcounters: Option<OpCounters>,
+    pub mem: Option<ServerMemory>,
+    /// Replication state, present on replica set members.
+    pub repl: Option<bson::Document>,
+    /// Storage engine statistics, present on `mongod`.
+    #[serde(rename = "wiredTiger")]
+    pub wired_tiger: Option<bson::Document>,
+}
+
+/// The machine described by `hostInfo`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct HostSystem {
+    pub hostname: String,
+    /// The width of memory addresses, in bits.
+    #[serde(rename = "cpuAddrSize")]
+    pub cpu_addr_size: i32,
+    #[serde(rename = "memSizeMB")]
+    pub mem_size_mb: i64,
+    /// The memory available to the process, which may be less than the machine's in a
+    /// container; reported by servers running 4.0 or later.
+    #[serde(rename = "memLimitMB")]
+    pub mem_limit_mb: Option<i64>,
+    #[serde(rename = "numCores")]
+    pub num_cores: i32,
+    #[serde(rename = "cpuArch")]
+    pub cpu_arch: Option<String>,
+    #[serde(rename = "numaEnabled", default)]
+    pub numa_enabled: bool,
+}
+
+/// The operating system described by `hostInfo`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct HostOs {
+    /// The kind of operating system, such as `Linux` or `Windows`.
+    #[serde(rename = "type")]
+    pub os_type: String,
+    pub name: String,
+    pub version: String,
+}
+
+/// The reply to `hostInfo`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct HostInfo {
+    pub system: HostSystem,
+    pub os: HostOs,
+    /// Platform-specific details, such as the kernel version and page size.
+    pub extra: Option<bson::Document>,
+}
+
+/// The reply to `collStats`. Sizes are in bytes divided by the scale the statistics were
+/// requested with.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CollectionStats {
+    pub ns: String,
+    /// The number of documents.
+    pub count: i64,
+    /// The total size of the documents, uncompressed.
+    pub size: i64,
+    /// The average size of a document, absent for empty collections.
+    #[serde(rename = "avgObjSize")]
+    pub avg_obj_size: Option<f64>,
+    /// The storage allocated for the documents, including free space.
+    #[serde(rename = "storageSize")]
+    pub storage_size: i64,
+    #[serde(rename = "nindexes")]
+    pub num_indexes: i32,
+    #[serde(rename = "totalIndexSize")]
+    pub total_index_size: i64,
+    /// The size of each index, by name.
+    #[serde(rename = "indexSizes", default)]
+    pub index_sizes: BTreeMap<String, i64>,
+    #[serde(default)]
+    pub capped: bool,
+    #[serde(rename = "scaleFactor")]
+    pub scale_factor: Option<i32>,
+}
+
+/// The reply to `dbStats`. Sizes are in bytes divided by the scale the statistics were
+/// requested with; servers report some of them as doubles.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DatabaseStats {
+    pub db: String,
+    pub collections: i64,
+    /// Reported by servers running 3.4 or later.
+    pub views: Option<i64>,
+    /// The number of documents across all collections.
+    pub objects: i64,
+    #[serde(rename = "avgObjSize")]
+    pub avg_obj_size: f64,
+    /// The total size of the documents, uncompressed.
+    #[serde(rename = "dataSize")]
+    pub data_size: f64,
+    /// The storage allocated for the documents, including free space.
+    #[serde(rename = "storageSize")]
+    pub storage_size: f64,
+    pub indexes: i64,
+    #[serde(rename = "indexSize")]
+    pub index_size: f64,
+    /// The storage allocated for documents and indexes, reported by servers running 4.4 or
+    /// later.
+    #[serde(rename = "totalSize")]
+    pub total_size: Option<f64>,
+    #[serde(rename = "scaleFactor")]
+    pub scale_factor: Option<f64>,
+}
+
+/// The reply to `validate`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ValidationResult {
+    pub ns: String,
+    /// Whether the collection and its indexes passed validation.
+    pub valid: bool,
+    #[serde(default)]
+    pub errors: Vec<String>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// The number of documents checked.
+    pub nrecords: Option<i64>,
+    /// The number of keys in each index, by name.
+    #[serde(rename = "keysPerIndex", default)]
+    pub keys_per_index: BTreeMap<String, i64>,
+}
+
+/// A member in the reply to `replSetGetStatus`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplSetMember {
+    #[serde(rename = "_id")]
+    pub id: i32,
+    /// The member's address, as `host:port`.
+    pub name: String,
+    /// 1 if the member is up, or 0 if it is down.
+    pub health: f64,
+    /// The member's replica set state, such as 1 for a primary or 2 for a secondary.
+    pub state: i32,
+    #[serde(rename = "stateStr")]
+    pub state_str: String,
+    /// Seconds the member has been up, as seen by the member answering the command.
+    pub uptime: Option<i64>,
+    /// The round trip time to the member; absent for the member answering the command.
+    #[serde(rename = "pingMs")]
+    pub ping_ms: Option<i64>,
+    /// The member this one replicates from, as `host:port`, or empty if none.
+    #[serde(rename = "syncSourceHost")]
+    pub sync_source_host: Option<String>,
+    /// Whether this is the member answering the command.
+    #[serde(rename = "self", default)]
+    pub is_self: bool,
+}
+
+/// The reply to `replSetGetStatus`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplSetGetStatus {
+    /// The replica set name.
+    pub set: String,
+    /// The replica set state of the member answering the command.
+    #[serde(rename = "myState")]
+    pub my_state: i32,
+    pub term: Option<i64>,
+    pub members: Vec<ReplSetMember>,
+}
+
+/// The kind of namespace described by a `CollectionSpecification`.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum CollectionType {
+    Collection,
+    View,
+    Timeseries,
+    /// A kind of namespace this driver does not know about.
+    #[serde(other)]
+    Other,
+}
+
+impl Default for CollectionType {
+    // Servers before 3.4, which have no views, omit the type.
+    fn default() -> Self {
+        CollectionType::Collection
+    }
+}
+
+/// Information about a collection in a `CollectionSpecification`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CollectionInfo {
+    /// Whether the collection is read-only, as views are.
+    #[serde(rename = "readOnly", default)]
+    pub read_only: bool,
+    /// The collection's UUID, reported by servers running 3.6 or later for collections other
+    /// than views.
+    pub uuid: Option<bson::Bson>,
+}
+
+/// A collection or view, as listed by `listCollections`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CollectionSpecification {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub collection_type: CollectionType,
+    /// The options the collection was created with, such as `capped` or, for a view, `viewOn`
+    /// and `pipeline`.
+    #[serde(default)]
+    pub options: bson::Document,
+    /// Reported by servers running 3.4 or later.
+    pub info: Option<CollectionInfo>,
+    /// The specification of the index on `_id`, absent for views.
+    #[serde(rename = "idIndex")]
+    pub id_index: Option<bson::Document>,
+}
+
+/// Which operations the database profiler records in `system.profile`.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(try_from = "i32")]
+pub enum ProfilingLevel {
+    Off,
+    /// Records operations slower than the slow operation threshold.
+    SlowOperations,
+    All,
+}
+
+impl ProfilingLevel {
+    /// The level's number in the `profile` command.
+    pub fn to_i32(self) -> i32 {
+        match self {
+            ProfilingLevel::Off => 0,
+            ProfilingLevel::SlowOperations => 1,
+            ProfilingLevel::All => 2,
+        }
+    }
+}
+
+impl TryFrom<i32> for ProfilingLevel {
+    type Error = String;
+
+    fn try_from(level: i32) -> Result<Self, Self::Error> {
+        match level {
+            0 => Ok(ProfilingLevel::Off),
+            1 => Ok(ProfilingLevel::SlowOperations),
+            2 => Ok(ProfilingLevel::All),
+            _ => Err(format!("Unknown profiling level {}.", level)),
+        }
+    }
+}
+
+/// The profiler settings reported by the `profile` command.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ProfilingStatus {
+    #[serde(rename = "was")]
+    pub level: ProfilingLevel,
+    /// The threshold in milliseconds above which operations are slow.
+    #[serde(rename = "slowms")]
+    pub slow_ms: i64,
+    /// The fraction of slow operations recorded, reported by servers running 3.6 or later.
+    #[serde(rename = "sampleRate")]
+    pub sample_rate: Option<f64>,
+}
+
+/// An operation recorded by the database profiler in `system.profile`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ProfileEntry {
+    /// The kind of operation, such as `query`, `command` or `update`.
+    pub op: String,
+    pub ns: String,
+    /// How long the operation took, in milliseconds.
+    pub millis: i64,
+    /// When the operation ran.
+    pub ts: bson::UtcDateTime,
+    /// The command the operation ran, reported by servers running 3.6 or later.
+    pub command: Option<bson::Document>,
+    #[serde(rename = "planSummary")]
+    pub plan_summary: Option<String>,
+    /// The number of documents scanned, reported by servers running 3.2 or later.
+    #[serde(rename = "docsExamined")]
+    pub docs_examined: Option<i64>,
+    #[serde(rename = "keysExamined")]
+    pub keys_examined: Option<i64>,
+    pub nreturned: Option<i64>,
+    /// The user that ran the operation, if authentication is enabled.
+    pub user: Option<String>,
+}
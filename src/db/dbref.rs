@@ -0,0 +1,94 @@
+//! References from one document to another by collection and id.
+use bson::{self, Bson, doc};
+
+use Error::ArgumentError;
+use Result;
+
+/// A reference to a document, stored as `{ $ref, $id, $db }`.
+///
+/// `ThreadedDatabase::dereference` fetches the document a reference points to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DBRef {
+    /// The collection holding the document.
+    pub collection: String,
+    /// The document's `_id`.
+    pub id: Bson,
+    /// The database holding the collection, if not the one the reference is stored in.
+    pub database: Option<String>,
+    /// Any other fields stored with the reference.
+    pub extra: bson::Document,
+}
+
+impl DBRef {
+    pub fn new(collection: &str, id: Bson) -> DBRef {
+        DBRef {
+            collection: String::from(collection),
+            id,
+            database: None,
+            extra: bson::Document::new(),
+        }
+    }
+
+    /// Whether a document has the `$ref` and `$id` fields of a reference.
+    pub fn is_dbref(doc: &bson::Document) -> bool {
+        doc.get_str("$ref").is_ok() && doc.contains_key("$id")
+    }
+
+    /// Parses a `{ $ref, $id, $db }` document.
+    pub fn from_document(doc: &bson::Document) -> Result<DBRef> {
+        let collection = doc.get_str("$ref").map_err(|_| {
+            ArgumentError(String::from("A DBRef needs a $ref string."))
+        })?;
+
+        let id = doc.get("$id").cloned().ok_or_else(|| {
+            ArgumentError(String::from("A DBRef needs an $id."))
+        })?;
+
+        let database = match doc.get("$db") {
+            Some(Bson::String(database)) => Some(database.clone()),
+            Some(_) => return Err(ArgumentError(String::from("A DBRef's $db must be a string."))),
+            None => None,
+        };
+
+        let mut extra = bson::Document::new();
+        for (key, value) in doc {
+            match key.as_str() {
+                "$ref" | "$id" | "$db" => (),
+                _ => {
+                    extra.insert(key.clone(), value.clone());
+                }
+            }
+        }
+
+        Ok(DBRef {
+            collection: String::from(collection),
+            id,
+            database,
+            extra,
+        })
+    }
+
+    /// Returns the reference as a `{ $ref, $id, $db }` document.
+    pub fn to_document(&self) -> bson::Document {
+        let mut doc = doc! {
+            "$ref": self.collection.clone(),
+            "$id": self.id.clone(),
+        };
+
+        if let Some(ref database) = self.database {
+            doc.insert("$db", database.clone());
+        }
+
+        for (key, value) in &self.extra {
+            doc.insert(key.clone(), value.clone());
+        }
+
+        doc
+    }
+}
+
+impl From<DBRef> for bson::Document {
+    fn from(dbref: DBRef) -> Self {
+        dbref.to_document()
+    }
+}
@@ -0,0 +1,207 @@
+//! Loading of test fixture data; see the `db` module documentation for the formats.
+use bson::{self, Bson, doc};
+use serde_json::{self, Value};
+
+use coll::options::UpdateOptions;
+use db::{Database, ThreadedDatabase};
+use db::options::{FixtureFormat, FixtureMode, LoadFixturesOptions};
+use Error::{ArgumentError, BulkWriteError, WriteError};
+use Result;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+// Loads fixtures into collections of a database, truncating each collection at most once.
+struct Loader<'a> {
+    db: &'a Database,
+    mode: FixtureMode,
+    truncated: BTreeSet<String>,
+    loaded: BTreeMap<String, i64>,
+}
+
+impl<'a> Loader<'a> {
+    fn new(db: &'a Database, mode: FixtureMode) -> Loader<'a> {
+        Loader {
+            db,
+            mode,
+            truncated: BTreeSet::new(),
+            loaded: BTreeMap::new(),
+        }
+    }
+
+    fn load(&mut self, coll_name: &str, docs: Vec<bson::Document>) -> Result<()> {
+        let coll = self.db.collection(coll_name);
+
+        if self.mode == FixtureMode::Truncate && self.truncated.insert(coll_name.to_owned()) {
+            coll.delete_many(doc!{}, None)?;
+        }
+
+        *self.loaded.entry(coll_name.to_owned()).or_insert(0) += docs.len() as i64;
+
+        let mut inserts = Vec::new();
+
+        for doc in docs {
+            let id = match doc.get("_id") {
+                Some(id) if self.mode == FixtureMode::Merge => id.clone(),
+                _ => {
+                    inserts.push(doc);
+                    continue;
+                }
+            };
+
+            let mut options = UpdateOptions::new();
+            options.upsert = Some(true);
+
+            let result = coll.replace_one(doc!{ "_id": id }, doc, Some(options))?;
+            if let Some(exception) = result.write_exception {
+                return Err(WriteError(exception));
+            }
+        }
+
+        if inserts.is_empty() {
+            return Ok(());
+        }
+
+        let result = coll.insert_many(inserts, None)?;
+        match result.bulk_write_exception {
+            Some(exception) => Err(BulkWriteError(exception)),
+            None => Ok(()),
+        }
+    }
+
+    // Loads a document mapping collection names to arrays of fixtures.
+    fn load_document(&mut self, fixtures: bson::Document) -> Result<()> {
+        for (coll_name, docs) in fixtures {
+            let docs = fixture_array(&coll_name, docs)?;
+            self.load(&coll_name, docs)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn fixture_array(coll_name: &str, value: Bson) -> Result<Vec<bson::Document>> {
+    let values = match value {
+        Bson::Array(values) => values,
+        _ => {
+            return Err(ArgumentError(format!(
+                "Fixtures for collection '{}' must be an array of documents.",
+                coll_name
+            )))
+        }
+    };
+
+    values
+        .into_iter()
+        .map(|value| match value {
+            Bson::Document(doc) => Ok(doc),
+            _ => Err(ArgumentError(format!(
+                "Fixtures for collection '{}' must be an array of documents.",
+                coll_name
+            ))),
+        })
+        .collect()
+}
+
+fn json_values<R: Read>(reader: R) -> Result<Vec<Value>> {
+    serde_json::Deserializer::from_reader(reader)
+        .into_iter::<Value>()
+        .map(|value| {
+            value.map_err(|err| ArgumentError(format!("Invalid fixture JSON: {}", err)))
+        })
+        .collect()
+}
+
+fn bson_documents<R: Read>(mut reader: R) -> Result<Vec<bson::Document>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    let len = bytes.len() as u64;
+    let mut cursor = Cursor::new(bytes);
+    let mut docs = Vec::new();
+
+    while cursor.position() < len {
+        docs.push(bson::decode_document(&mut cursor)?);
+    }
+
+    Ok(docs)
+}
+
+fn json_document(value: Value) -> Result<bson::Document> {
+    match Bson::from(value) {
+        Bson::Document(doc) => Ok(doc),
+        other => Err(ArgumentError(
+            format!("Expected a fixture document, found {}.", other),
+        )),
+    }
+}
+
+/// Loads a fixture stream into `db`, returning how many documents were loaded per collection.
+pub fn load_fixtures<R: Read>(
+    db: &Database,
+    reader: R,
+    options: Option<LoadFixturesOptions>,
+) -> Result<BTreeMap<String, i64>> {
+    let options = options.unwrap_or_default();
+    let mut loader = Loader::new(db, options.mode.unwrap_or_default());
+
+    let docs = match options.format.unwrap_or_default() {
+        FixtureFormat::Json => {
+            json_values(reader)?
+                .into_iter()
+                .map(json_document)
+                .collect::<Result<Vec<_>>>()?
+        }
+        FixtureFormat::Bson => bson_documents(reader)?,
+    };
+
+    for doc in docs {
+        loader.load_document(doc)?;
+    }
+
+    Ok(loader.loaded)
+}
+
+/// Loads a fixture directory into `db`, returning how many documents were loaded per
+/// collection.
+pub fn load_fixtures_dir(
+    db: &Database,
+    dir: &Path,
+    options: Option<LoadFixturesOptions>,
+) -> Result<BTreeMap<String, i64>> {
+    let options = options.unwrap_or_default();
+    let mut loader = Loader::new(db, options.mode.unwrap_or_default());
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        paths.push(entry?.path());
+    }
+
+    // Load in a stable order, so fixtures spanning related collections behave the same everywhere.
+    paths.sort();
+
+    for path in paths {
+        let coll_name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(stem) => stem.to_owned(),
+            None => continue,
+        };
+
+        let docs = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => {
+                let mut docs = Vec::new();
+                for value in json_values(fs::File::open(&path)?)? {
+                    docs.extend(fixture_array(&coll_name, Bson::from(value))?);
+                }
+                docs
+            }
+            Some("bson") => bson_documents(fs::File::open(&path)?)?,
+            _ => continue,
+        };
+
+        loader.load(&coll_name, docs)?;
+    }
+
+    Ok(loader.loaded)
+}
@@ -34,14 +34,16 @@
 //!
 //! ## Arbitrary Database Commands
 //!
-//! Any valid MongoDB database command can be sent to the server with the `command` and
-//! `command_cursor` functions.
+//! Any valid MongoDB database command can be sent to the server with the `run_command` and
+//! `command_cursor` functions. `run_command` routes write commands to the primary with the
+//! database's write concern, and other commands by read preference. `run_command_as`
+//! deserializes the reply into a struct instead, such as those in the `commands` module.
 //!
 //! ```no_run
 //! # #[macro_use] extern crate bson;
 //! # extern crate mongodb;
 //! #
-//! # use mongodb::{Client, CommandType, ThreadedClient};
+//! # use mongodb::{Client, ThreadedClient};
 //! # use mongodb::db::ThreadedDatabase;
 //! # use bson::Bson;
 //! # fn main() {
@@ -49,26 +51,63 @@
 //! #
 //! let db = client.db("movies");
 //! let cmd = doc! { "connectionStatus": 1 };
-//! let result = db.command(cmd, CommandType::Suppressed, None).unwrap();
+//! let result = db.run_command(cmd, None).unwrap();
 //! if let Some(&Bson::Document(ref doc)) = result.get("authInfo") {
 //!     // Read authentication info.
 //! }
 //! # }
 //! ```
+//!
+//! ## References
+//!
+//! Documents in older schemas refer to each other with `{ $ref, $id, $db }` sub-documents.
+//! `dbref::DBRef` parses and builds them, and `dereference` fetches the document one points to.
+//!
+//! ## Test Fixtures
+//!
+//! `load_fixtures` seeds collections from a stream of documents, in extended JSON or BSON, that
+//! each map collection names to arrays of documents. `load_fixtures_dir` reads a directory with
+//! one file per collection instead: `<collection>.json` holding an extended JSON array, or
+//! `<collection>.bson` holding concatenated BSON documents as written by `mongodump`.
+//!
+//! ```no_run
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! #
+//! let db = client.db("test");
+//! let fixtures = r#"{ "users": [{ "_id": 1, "name": "Ada" }], "orders": [] }"#;
+//! let loaded = db.load_fixtures(fixtures.as_bytes(), None).unwrap();
+//! assert_eq!(Some(&1), loaded.get("users"));
+//! ```
+pub mod commands;
+pub mod dbref;
+mod fixtures;
 pub mod options;
 pub mod roles;
 
 use auth::Authenticator;
 use bson::{self, bson, doc, Bson};
+use chrono::{DateTime, Utc};
 use {Client, CommandType, ThreadedClient, Result};
-use Error::{CursorNotFoundError, OperationError, ResponseError};
+use Error::{ArgumentError, CursorNotFoundError, DecoderError, OperationError, ResponseError};
 use coll::Collection;
 use coll::options::FindOptions;
-use common::{ReadPreference, merge_options, WriteConcern};
-use cursor::{Cursor, DEFAULT_BATCH_SIZE};
-use self::options::{CreateCollectionOptions, CreateUserOptions, UserInfoOptions};
+use common::{ReadMode, ReadPreference, merge_options, WriteConcern};
+use cursor::{Cursor, TypedCursor, DEFAULT_BATCH_SIZE};
+use uuid::UuidRepresentation;
+use wire_protocol::flags::OpQueryFlags;
+use self::commands::{CollectionSpecification, DatabaseStats, ProfileEntry, ProfilingLevel,
+                     ProfilingStatus};
+use self::dbref::DBRef;
+use self::options::{CollModOptions, CreateCollectionOptions, CreateUserOptions,
+                    CreateViewOptions, LoadFixturesOptions, TimeseriesOptions, UserInfoOptions};
 use semver::Version;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::io::Read;
+use std::path::Path;
 use std::sync::Arc;
 
 /// Interfaces with a MongoDB database.
@@ -97,6 +136,9 @@ pub trait ThreadedDatabase {
     ) -> Database;
     // Returns the version of the MongoDB instance.
     fn version(&self) -> Result<Version>;
+    /// Returns storage statistics for the database, with sizes divided by `scale`, such as 1024
+    /// for kilobytes.
+    fn stats(&self, scale: Option<i32>) -> Result<DatabaseStats>;
     /// Logs in a user using the SCRAM-SHA-1 mechanism.
     fn auth(&self, user: &str, password: &str) -> Result<()>;
     /// Creates a collection representation with inherited read and write controls.
@@ -109,6 +151,12 @@ pub trait ThreadedDatabase {
         read_preference: Option<ReadPreference>,
         write_concern: Option<WriteConcern>,
     ) -> Collection;
+    /// Returns a handle to the database that stores UUIDs in `representation`, or leaves them as
+    /// they are if `None`; see the `uuid` module.
+    fn with_uuid_representation(&self, representation: Option<UuidRepresentation>) -> Database;
+    /// Fetches the document `dbref` points to, looking in this database unless the reference
+    /// names another.
+    fn dereference(&self, dbref: &DBRef) -> Result<Option<bson::Document>>;
     /// Return a unique operational request id.
     fn get_req_id(&self) -> i32;
     /// Generates a cursor for a relevant operational command.
@@ -119,28 +167,80 @@ pub trait ThreadedDatabase {
         read_pref: ReadPreference,
     ) -> Result<Cursor>;
     /// Sends an administrative command over find_one.
+    ///
+    /// Superseded by `run_command`, which needs no `CommandType` and is not subject to the
+    /// rewriting applied to queries.
     fn command(
         &self,
         spec: bson::Document,
         cmd_type: CommandType,
         read_preference: Option<ReadPreference>,
     ) -> Result<bson::Document>;
-    /// Returns a list of collections within the database.
-    fn list_collections(&self, filter: Option<bson::Document>) -> Result<Cursor>;
+    /// Sends an administrative command over find_one, bounded by `timeout_ms` instead of the
+    /// client's default timeout.
+    fn command_with_timeout(
+        &self,
+        spec: bson::Document,
+        cmd_type: CommandType,
+        read_preference: Option<ReadPreference>,
+        timeout_ms: Option<i64>,
+    ) -> Result<bson::Document>;
+    /// Runs a database command and returns its reply.
+    ///
+    /// Write commands, such as `insert` or `createIndexes`, are sent to the primary with the
+    /// database's write concern unless the command specifies its own `writeConcern`. Other
+    /// commands are sent to a server selected by `read_preference`, or by the database's read
+    /// preference if none is given.
+    fn run_command(
+        &self,
+        spec: bson::Document,
+        read_preference: Option<ReadPreference>,
+    ) -> Result<bson::Document>;
+    /// Runs a command on the primary and deserializes its reply into `T`. Typed replies to
+    /// common commands are in the `commands` module.
+    fn run_command_as<T: DeserializeOwned>(&self, spec: bson::Document) -> Result<T>;
+    /// Returns a cursor over the collections and views within the database.
+    fn list_collections(
+        &self,
+        filter: Option<bson::Document>,
+    ) -> Result<TypedCursor<CollectionSpecification>>;
     /// Returns a list of collections within the database with a custom batch size.
     fn list_collections_with_batch_size(
         &self,
         filter: Option<bson::Document>,
         batch_size: i32,
-    ) -> Result<Cursor>;
+    ) -> Result<TypedCursor<CollectionSpecification>>;
     /// Returns a list of collection names within the database.
     fn collection_names(&self, filter: Option<bson::Document>) -> Result<Vec<String>>;
+    /// Whether a collection or view named `name` exists in the database.
+    fn collection_exists(&self, name: &str) -> Result<bool>;
+    /// Modifies a collection or view: its schema validator and validation settings, the TTL of
+    /// one of its indexes, or the source and pipeline of a view.
+    fn coll_mod(&self, name: &str, options: CollModOptions) -> Result<()>;
     /// Creates a new collection.
     ///
     /// Note that due to the implicit creation of collections during insertion, this
     /// method should only be used to instantiate capped collections.
     fn create_collection(&self, name: &str, options: Option<CreateCollectionOptions>)
         -> Result<()>;
+    /// Creates a time series collection, which stores measurements described by `timeseries`
+    /// efficiently, deleting them after `expire_after_seconds` if given. Requires MongoDB 5.0 or
+    /// later.
+    fn create_timeseries_collection(
+        &self,
+        name: &str,
+        timeseries: TimeseriesOptions,
+        expire_after_seconds: Option<i64>,
+    ) -> Result<()>;
+    /// Creates a read-only view named `name` of the documents `pipeline` produces from the
+    /// collection or view `source`. Requires MongoDB 3.4 or later.
+    fn create_view(
+        &self,
+        name: &str,
+        source: &str,
+        pipeline: Vec<bson::Document>,
+        options: Option<CreateViewOptions>,
+    ) -> Result<()>;
     /// Creates a new user.
     fn create_user(
         &self,
@@ -166,6 +266,38 @@ pub trait ThreadedDatabase {
         users: Vec<&str>,
         options: Option<UserInfoOptions>,
     ) -> Result<Vec<bson::Document>>;
+    /// Loads a stream of test fixtures, returning how many documents were loaded into each
+    /// collection.
+    fn load_fixtures<R: Read>(
+        &self,
+        reader: R,
+        options: Option<LoadFixturesOptions>,
+    ) -> Result<BTreeMap<String, i64>>;
+    /// Loads a directory of per-collection fixture files, returning how many documents were
+    /// loaded into each collection.
+    fn load_fixtures_dir<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: Option<LoadFixturesOptions>,
+    ) -> Result<BTreeMap<String, i64>>;
+    /// Sets which operations the profiler records, and optionally the threshold in milliseconds
+    /// above which operations are slow, returning the settings from before the change.
+    ///
+    /// Profiling applies to the database on the server that runs the command; on a replica set
+    /// that is the primary, and `mongos` does not support profiling.
+    fn set_profiling_level(
+        &self,
+        level: ProfilingLevel,
+        slow_ms: Option<i64>,
+    ) -> Result<ProfilingStatus>;
+    /// Returns the profiler's current settings.
+    fn get_profiling_level(&self) -> Result<ProfilingStatus>;
+    /// Returns the operations recorded by the profiler after `since`, most recent first.
+    fn slow_queries(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: Option<i64>,
+    ) -> Result<Vec<ProfileEntry>>;
 }
 
 impl ThreadedDatabase for Database {
@@ -217,6 +349,31 @@ impl ThreadedDatabase for Database {
         )
     }
 
+    fn with_uuid_representation(&self, representation: Option<UuidRepresentation>) -> Database {
+        Database::open(
+            self.client.with_uuid_representation(representation),
+            &self.name,
+            Some(self.read_preference.clone()),
+            Some(self.write_concern),
+        )
+    }
+
+    fn dereference(&self, dbref: &DBRef) -> Result<Option<bson::Document>> {
+        let db = match dbref.database {
+            Some(ref name) if *name != self.name => {
+                self.client.db_with_prefs(
+                    name,
+                    Some(self.read_preference.clone()),
+                    Some(self.write_concern),
+                )
+            }
+            _ => self.clone(),
+        };
+
+        let filter = doc! { "_id": dbref.id.clone() };
+        db.collection(&dbref.collection).find_one(Some(filter), None)
+    }
+
     fn get_req_id(&self) -> i32 {
         self.client.get_req_id()
     }
@@ -242,11 +399,22 @@ impl ThreadedDatabase for Database {
         cmd_type: CommandType,
         read_preference: Option<ReadPreference>,
     ) -> Result<bson::Document> {
+        self.command_with_timeout(spec, cmd_type, read_preference, None)
+    }
+
+    fn command_with_timeout(
+        &self,
+        spec: bson::Document,
+        cmd_type: CommandType,
+        read_preference: Option<ReadPreference>,
+        timeout_ms: Option<i64>,
+    ) -> Result<bson::Document> {
 
         let coll = self.collection("$cmd");
         let options = FindOptions {
             batch_size: Some(1),
             read_preference: read_preference,
+            timeout_ms: timeout_ms,
             ..FindOptions::new()
         };
         let res = coll.find_one_with_command_type(
@@ -259,7 +427,59 @@ impl ThreadedDatabase for Database {
         })
     }
 
-    fn list_collections(&self, filter: Option<bson::Document>) -> Result<Cursor> {
+    fn run_command(
+        &self,
+        mut spec: bson::Document,
+        read_preference: Option<ReadPreference>,
+    ) -> Result<bson::Document> {
+        let cmd_type = match spec.keys().next() {
+            Some(name) => CommandType::from_command_name(name),
+            None => return Err(ArgumentError(String::from("Command specification is empty."))),
+        };
+
+        let read_preference = if cmd_type.is_write_command() {
+            if !spec.contains_key("writeConcern") {
+                spec.insert("writeConcern", self.write_concern.to_bson());
+            }
+            ReadPreference::new(ReadMode::Primary, None)
+        } else {
+            read_preference.unwrap_or_else(|| self.read_preference.clone())
+        };
+
+        let options = FindOptions {
+            batch_size: Some(1),
+            limit: Some(1),
+            ..FindOptions::new()
+        };
+
+        let mut cursor = Cursor::query(
+            self.client.clone(),
+            format!("{}.$cmd", self.name),
+            OpQueryFlags::empty(),
+            spec.clone(),
+            options,
+            cmd_type,
+            false,
+            read_preference,
+        )?;
+
+        match cursor.next() {
+            Some(Ok(reply)) => Ok(reply),
+            Some(Err(err)) => Err(err),
+            None => Err(OperationError(format!("Failed to execute command with spec {:?}.", spec))),
+        }
+    }
+
+    fn run_command_as<T: DeserializeOwned>(&self, spec: bson::Document) -> Result<T> {
+        let primary = ReadPreference::new(ReadMode::Primary, None);
+        let reply = self.run_command(spec, Some(primary))?;
+        bson::from_bson(Bson::Document(reply)).map_err(DecoderError)
+    }
+
+    fn list_collections(
+        &self,
+        filter: Option<bson::Document>,
+    ) -> Result<TypedCursor<CollectionSpecification>> {
         self.list_collections_with_batch_size(filter, DEFAULT_BATCH_SIZE)
     }
 
@@ -267,7 +487,7 @@ impl ThreadedDatabase for Database {
         &self,
         filter: Option<bson::Document>,
         batch_size: i32,
-    ) -> Result<Cursor> {
+    ) -> Result<TypedCursor<CollectionSpecification>> {
 
         let mut spec = doc!{
             "listCollections": 1,
@@ -279,25 +499,37 @@ impl ThreadedDatabase for Database {
             spec.insert("filter", f);
         }
 
-        self.command_cursor(
+        let cursor = self.command_cursor(
             spec,
             CommandType::ListCollections,
             self.read_preference.to_owned(),
-        )
+        )?;
+        Ok(cursor.deserialize())
     }
 
     fn collection_names(&self, filter: Option<bson::Document>) -> Result<Vec<String>> {
         self.list_collections(filter)?
-            .filter_map(|result| match result {
-                Err(err) => Some(Err(err)),
-                Ok(mut doc) => match doc.remove("name") {
-                    Some(Bson::String(name)) => Some(Ok(name)),
-                    _ => None,
-                }
-            })
+            .map(|result| result.map(|spec| spec.name))
             .collect()
     }
 
+    fn collection_exists(&self, name: &str) -> Result<bool> {
+        match self.list_collections(Some(doc! { "name": name }))?.next() {
+            Some(Ok(_)) => Ok(true),
+            Some(Err(err)) => Err(err),
+            None => Ok(false),
+        }
+    }
+
+    fn coll_mod(&self, name: &str, options: CollModOptions) -> Result<()> {
+        let cmd = merge_options(doc! { "collMod": name }, options);
+        let mut result = self.command(cmd, CommandType::CollMod, None)?;
+        match result.remove("errmsg") {
+            Some(Bson::String(msg)) => Err(OperationError(msg)),
+            _ => Ok(()),
+        }
+    }
+
     fn version(&self) -> Result<Version> {
         let doc = doc! { "buildinfo": 1 };
         let out = self.command(doc, CommandType::BuildInfo, None)?;
@@ -315,6 +547,16 @@ impl ThreadedDatabase for Database {
         }
     }
 
+    fn stats(&self, scale: Option<i32>) -> Result<DatabaseStats> {
+        let mut spec = doc! { "dbStats": 1 };
+
+        if let Some(scale) = scale {
+            spec.insert("scale", scale);
+        }
+
+        self.run_command_as(spec)
+    }
+
     fn create_collection(
         &self,
         name: &str,
@@ -331,6 +573,46 @@ impl ThreadedDatabase for Database {
         Ok(())
     }
 
+    fn create_timeseries_collection(
+        &self,
+        name: &str,
+        timeseries: TimeseriesOptions,
+        expire_after_seconds: Option<i64>,
+    ) -> Result<()> {
+        let options = CreateCollectionOptions {
+            timeseries: Some(timeseries),
+            expire_after_seconds,
+            ..Default::default()
+        };
+
+        self.create_collection(name, Some(options))
+    }
+
+    fn create_view(
+        &self,
+        name: &str,
+        source: &str,
+        pipeline: Vec<bson::Document>,
+        options: Option<CreateViewOptions>,
+    ) -> Result<()> {
+        let stages: Vec<Bson> = pipeline.into_iter().map(Bson::Document).collect();
+        let mut doc = doc! {
+            "create": name,
+            "viewOn": source,
+            "pipeline": stages,
+        };
+
+        if let Some(view_options) = options {
+            doc = merge_options(doc, view_options);
+        }
+
+        let mut result = self.command(doc, CommandType::CreateCollection, None)?;
+        match result.remove("errmsg") {
+            Some(Bson::String(msg)) => Err(OperationError(msg)),
+            _ => Ok(()),
+        }
+    }
+
     fn create_user(
         &self,
         name: &str,
@@ -347,7 +629,7 @@ impl ThreadedDatabase for Database {
                 doc = merge_options(doc, user_options);
             }
             None => {
-                doc.insert("roles", Vec::new());
+                doc.insert("roles", Vec::<Bson>::new());
             }
         };
 
@@ -467,4 +749,55 @@ impl ThreadedDatabase for Database {
             })
             .collect()
     }
+
+    fn load_fixtures<R: Read>(
+        &self,
+        reader: R,
+        options: Option<LoadFixturesOptions>,
+    ) -> Result<BTreeMap<String, i64>> {
+        fixtures::load_fixtures(self, reader, options)
+    }
+
+    fn load_fixtures_dir<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: Option<LoadFixturesOptions>,
+    ) -> Result<BTreeMap<String, i64>> {
+        fixtures::load_fixtures_dir(self, dir.as_ref(), options)
+    }
+
+    fn set_profiling_level(
+        &self,
+        level: ProfilingLevel,
+        slow_ms: Option<i64>,
+    ) -> Result<ProfilingStatus> {
+        let mut spec = doc! { "profile": level.to_i32() };
+        if let Some(slow_ms) = slow_ms {
+            spec.insert("slowms", slow_ms);
+        }
+
+        self.run_command_as(spec)
+    }
+
+    fn get_profiling_level(&self) -> Result<ProfilingStatus> {
+        self.run_command_as(doc! { "profile": -1 })
+    }
+
+    fn slow_queries(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: Option<i64>,
+    ) -> Result<Vec<ProfileEntry>> {
+        let filter = since.map(|since| doc! { "ts": { "$gt": Bson::UtcDatetime(since) } });
+        let options = FindOptions {
+            sort: Some(doc! { "ts": -1 }),
+            limit,
+            ..FindOptions::new()
+        };
+
+        self.collection("system.profile")
+            .find(filter, Some(options))?
+            .deserialize()
+            .collect()
+    }
 }
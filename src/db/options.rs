@@ -1,9 +1,66 @@
 //! Options for database-level commands.
-use bson::{Bson, Document};
+use bson::{Bson, Document, doc};
 use common::WriteConcern;
 use db::roles::Role;
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+/// The expected interval between measurements in a time series collection, which determines
+/// how the server groups them into buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeseriesGranularity {
+    Seconds,
+    Minutes,
+    Hours,
+}
+
+impl TimeseriesGranularity {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            TimeseriesGranularity::Seconds => "seconds",
+            TimeseriesGranularity::Minutes => "minutes",
+            TimeseriesGranularity::Hours => "hours",
+        }
+    }
+}
+
+/// Describes the measurements stored in a time series collection.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimeseriesOptions {
+    /// The field holding each measurement's date.
+    pub time_field: String,
+    /// The field holding the metadata that identifies a series, such as the sensor a
+    /// measurement came from, which rarely changes.
+    pub meta_field: Option<String>,
+    /// Defaults to `Seconds`.
+    pub granularity: Option<TimeseriesGranularity>,
+}
+
+impl TimeseriesOptions {
+    pub fn new(time_field: &str) -> TimeseriesOptions {
+        TimeseriesOptions {
+            time_field: String::from(time_field),
+            meta_field: None,
+            granularity: None,
+        }
+    }
+}
+
+impl From<TimeseriesOptions> for Document {
+    fn from(options: TimeseriesOptions) -> Self {
+        let mut document = doc! { "timeField": options.time_field };
+
+        if let Some(meta_field) = options.meta_field {
+            document.insert("metaField", meta_field);
+        }
+
+        if let Some(granularity) = options.granularity {
+            document.insert("granularity", granularity.to_str());
+        }
+
+        document
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct CreateCollectionOptions {
     pub capped: Option<bool>,
     pub auto_index_id: Option<bool>,
@@ -11,8 +68,24 @@ pub struct CreateCollectionOptions {
     pub max: Option<i64>,
     pub use_power_of_two_sizes: Option<bool>,
     pub no_padding: Option<bool>,
+    /// Creates a time series collection; requires MongoDB 5.0 or later.
+    pub timeseries: Option<TimeseriesOptions>,
+    /// How long to keep the documents of a time series collection, after which they are
+    /// deleted automatically.
+    pub expire_after_seconds: Option<i64>,
 }
 
+options_builder!(CreateCollectionOptions, CreateCollectionOptionsBuilder {
+    capped: Option<bool>,
+    auto_index_id: Option<bool>,
+    size: Option<i64>,
+    max: Option<i64>,
+    use_power_of_two_sizes: Option<bool>,
+    no_padding: Option<bool>,
+    timeseries: Option<TimeseriesOptions>,
+    expire_after_seconds: Option<i64>,
+});
+
 impl CreateCollectionOptions {
     pub fn new() -> CreateCollectionOptions {
         Default::default()
@@ -53,6 +126,48 @@ impl From<CreateCollectionOptions> for Document {
             document.insert("flags", flags);
         }
 
+        if let Some(timeseries) = options.timeseries {
+            document.insert("timeseries", Document::from(timeseries));
+        }
+
+        if let Some(expire_after_seconds) = options.expire_after_seconds {
+            document.insert("expireAfterSeconds", Bson::I64(expire_after_seconds));
+        }
+
+        document
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CreateViewOptions {
+    /// The default collation of the view; views do not inherit the collation of their source.
+    pub collation: Option<Document>,
+    pub write_concern: Option<WriteConcern>,
+}
+
+options_builder!(CreateViewOptions, CreateViewOptionsBuilder {
+    collation: Option<Document>,
+    write_concern: Option<WriteConcern>,
+});
+
+impl CreateViewOptions {
+    pub fn new() -> CreateViewOptions {
+        Default::default()
+    }
+}
+
+impl From<CreateViewOptions> for Document {
+    fn from(options: CreateViewOptions) -> Self {
+        let mut document = Document::new();
+
+        if let Some(collation) = options.collation {
+            document.insert("collation", collation);
+        }
+
+        if let Some(write_concern) = options.write_concern {
+            document.insert("writeConcern", write_concern.to_bson());
+        }
+
         document
     }
 }
@@ -64,6 +179,12 @@ pub struct CreateUserOptions {
     pub write_concern: Option<WriteConcern>,
 }
 
+options_builder!(CreateUserOptions, CreateUserOptionsBuilder {
+    custom_data: Option<Document>,
+    roles: Vec<Role>,
+    write_concern: Option<WriteConcern>,
+});
+
 impl CreateUserOptions {
     pub fn new() -> CreateUserOptions {
         Default::default()
@@ -96,6 +217,11 @@ pub struct UserInfoOptions {
     pub show_privileges: Option<bool>,
 }
 
+options_builder!(UserInfoOptions, UserInfoOptionsBuilder {
+    show_credentials: Option<bool>,
+    show_privileges: Option<bool>,
+});
+
 impl UserInfoOptions {
     pub fn new() -> UserInfoOptions {
         Default::default()
@@ -117,3 +243,195 @@ impl From<UserInfoOptions> for Document {
         document
     }
 }
+
+/// How `load_fixtures` treats data already in the fixture collections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FixtureMode {
+    /// Removes every existing document from a collection before loading its fixtures.
+    Truncate,
+    /// Keeps existing documents, replacing those whose `_id` matches a fixture.
+    Merge,
+}
+
+impl Default for FixtureMode {
+    fn default() -> Self {
+        FixtureMode::Truncate
+    }
+}
+
+/// The encoding of a fixture stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FixtureFormat {
+    /// Extended JSON objects mapping collection names to arrays of documents.
+    Json,
+    /// Concatenated BSON documents mapping collection names to arrays of documents.
+    Bson,
+}
+
+impl Default for FixtureFormat {
+    fn default() -> Self {
+        FixtureFormat::Json
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LoadFixturesOptions {
+    /// Defaults to `FixtureMode::Truncate`.
+    pub mode: Option<FixtureMode>,
+    /// The encoding of a fixture stream; defaults to `FixtureFormat::Json`. Files loaded from a
+    /// directory are decoded according to their extension instead.
+    pub format: Option<FixtureFormat>,
+}
+
+options_builder!(LoadFixturesOptions, LoadFixturesOptionsBuilder {
+    mode: Option<FixtureMode>,
+    format: Option<FixtureFormat>,
+});
+
+impl LoadFixturesOptions {
+    pub fn new() -> LoadFixturesOptions {
+        Default::default()
+    }
+}
+
+/// Which documents schema validation applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationLevel {
+    /// No validation for inserts or updates.
+    Off,
+    /// Validates all inserts and updates.
+    Strict,
+    /// Validates inserts, and updates to documents that already satisfy the validator.
+    Moderate,
+}
+
+impl ValidationLevel {
+    fn to_str(self) -> &'static str {
+        match self {
+            ValidationLevel::Off => "off",
+            ValidationLevel::Strict => "strict",
+            ValidationLevel::Moderate => "moderate",
+        }
+    }
+}
+
+/// What the server does with writes that fail schema validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationAction {
+    /// Rejects the write.
+    Error,
+    /// Allows the write but logs the violation.
+    Warn,
+}
+
+impl ValidationAction {
+    fn to_str(self) -> &'static str {
+        match self {
+            ValidationAction::Error => "error",
+            ValidationAction::Warn => "warn",
+        }
+    }
+}
+
+/// An index to modify with `collMod`, identified by its key pattern or its name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollModIndex {
+    pub key_pattern: Option<Document>,
+    /// Used if `key_pattern` is not given.
+    pub name: Option<String>,
+    /// The new TTL of the index, which must already be a TTL index.
+    pub expire_after_seconds: Option<i32>,
+}
+
+impl CollModIndex {
+    pub fn new() -> CollModIndex {
+        Default::default()
+    }
+}
+
+impl From<CollModIndex> for Document {
+    fn from(index: CollModIndex) -> Self {
+        let mut document = Document::new();
+
+        if let Some(key_pattern) = index.key_pattern {
+            document.insert("keyPattern", key_pattern);
+        } else if let Some(name) = index.name {
+            document.insert("name", name);
+        }
+
+        if let Some(expire_after_seconds) = index.expire_after_seconds {
+            document.insert("expireAfterSeconds", expire_after_seconds);
+        }
+
+        document
+    }
+}
+
+/// Changes to make to a collection or view with `collMod`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CollModOptions {
+    /// The new schema validator, as a query filter or `$jsonSchema` document. An empty document
+    /// removes validation.
+    pub validator: Option<Document>,
+    pub validation_level: Option<ValidationLevel>,
+    pub validation_action: Option<ValidationAction>,
+    pub index: Option<CollModIndex>,
+    /// The new source collection of a view. Must be given together with `pipeline`.
+    pub view_on: Option<String>,
+    /// The new aggregation pipeline of a view.
+    pub pipeline: Option<Vec<Document>>,
+    pub write_concern: Option<WriteConcern>,
+}
+
+options_builder!(CollModOptions, CollModOptionsBuilder {
+    validator: Option<Document>,
+    validation_level: Option<ValidationLevel>,
+    validation_action: Option<ValidationAction>,
+    index: Option<CollModIndex>,
+    view_on: Option<String>,
+    pipeline: Option<Vec<Document>>,
+    write_concern: Option<WriteConcern>,
+});
+
+impl CollModOptions {
+    pub fn new() -> CollModOptions {
+        Default::default()
+    }
+}
+
+impl From<CollModOptions> for Document {
+    fn from(options: CollModOptions) -> Self {
+        let mut document = Document::new();
+
+        if let Some(validator) = options.validator {
+            document.insert("validator", validator);
+        }
+
+        if let Some(level) = options.validation_level {
+            document.insert("validationLevel", level.to_str());
+        }
+
+        if let Some(action) = options.validation_action {
+            document.insert("validationAction", action.to_str());
+        }
+
+        if let Some(index) = options.index {
+            document.insert("index", Document::from(index));
+        }
+
+        if let Some(view_on) = options.view_on {
+            document.insert("viewOn", view_on);
+        }
+
+        if let Some(pipeline) = options.pipeline {
+            let stages: Vec<Bson> = pipeline.into_iter().map(Bson::Document).collect();
+            document.insert("pipeline", stages);
+        }
+
+        if let Some(write_concern) = options.write_concern {
+            document.insert("writeConcern", write_concern.to_bson());
+        }
+
+        document
+    }
+}
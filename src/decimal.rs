@@ -0,0 +1,157 @@
+//! Decimal128 values, for amounts such as prices that doubles would round.
+//!
+//! With the `decimal128` feature, documents carry `Bson::Decimal128` values through inserts,
+//! queries and results unchanged, with all 34 significant digits. Without it, documents holding
+//! Decimal128 values fail to decode. Servers store Decimal128 values from MongoDB 3.4.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # use mongodb::decimal;
+//! #
+//! # fn main() {
+//! let client = Client::connect("localhost", 27017).unwrap();
+//! let orders = client.db("shop").collection("orders");
+//!
+//! orders.insert_one(doc! { "total": decimal::decimal("19.99").unwrap() }, None).unwrap();
+//! let large = orders
+//!     .find(Some(doc! { "total": decimal::gte(decimal::parse("10.00").unwrap()) }), None)
+//!     .unwrap();
+//! # }
+//! ```
+pub use bson::decimal128::Decimal128;
+
+use bson::{Bson, doc};
+
+use Error::ArgumentError;
+use Result;
+
+use std::cmp::Ordering;
+
+/// Parses a decimal such as `19.99`, `-1.5E+3`, `NaN` or `Infinity`.
+pub fn parse(s: &str) -> Result<Decimal128> {
+    if !is_decimal(s) {
+        return Err(ArgumentError(format!("'{}' is not a decimal.", s)));
+    }
+
+    Ok(Decimal128::from_str(s))
+}
+
+/// Parses a decimal into a `Bson::Decimal128` value.
+pub fn decimal(s: &str) -> Result<Bson> {
+    parse(s).map(Bson::Decimal128)
+}
+
+/// Converts an integer exactly.
+pub fn from_i64(n: i64) -> Decimal128 {
+    Decimal128::from_str(&n.to_string())
+}
+
+/// Converts a double to the decimal that prints the same, so that `0.1` becomes exactly `0.1`.
+pub fn from_f64(f: f64) -> Decimal128 {
+    if f.is_nan() {
+        Decimal128::from_str("NaN")
+    } else if f.is_infinite() {
+        Decimal128::from_str(if f > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        Decimal128::from_str(&format!("{:?}", f))
+    }
+}
+
+/// Converts a decimal to the nearest double.
+pub fn to_f64(d: &Decimal128) -> f64 {
+    d.to_string().parse().unwrap_or(f64::NAN)
+}
+
+/// Compares two numbers of any numeric BSON type by value, as the server does, without
+/// rounding decimals to doubles. Returns `None` if either is not a number or is NaN.
+pub fn compare(a: &Bson, b: &Bson) -> Option<Ordering> {
+    match (to_decimal(a), to_decimal(b)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b),
+        _ => None,
+    }
+}
+
+/// The condition that a field equals `value`.
+pub fn eq(value: Decimal128) -> Bson {
+    condition("$eq", value)
+}
+
+/// The condition that a field is greater than `value`.
+pub fn gt(value: Decimal128) -> Bson {
+    condition("$gt", value)
+}
+
+/// The condition that a field is greater than or equal to `value`.
+pub fn gte(value: Decimal128) -> Bson {
+    condition("$gte", value)
+}
+
+/// The condition that a field is less than `value`.
+pub fn lt(value: Decimal128) -> Bson {
+    condition("$lt", value)
+}
+
+/// The condition that a field is less than or equal to `value`.
+pub fn lte(value: Decimal128) -> Bson {
+    condition("$lte", value)
+}
+
+/// The condition that a field is at least `min` and less than `max`.
+pub fn range(min: Decimal128, max: Decimal128) -> Bson {
+    Bson::Document(doc! {
+        "$gte": Bson::Decimal128(min),
+        "$lt": Bson::Decimal128(max),
+    })
+}
+
+fn condition(operator: &str, value: Decimal128) -> Bson {
+    Bson::Document(doc! { operator: Bson::Decimal128(value) })
+}
+
+fn to_decimal(value: &Bson) -> Option<Decimal128> {
+    match *value {
+        Bson::Decimal128(ref d) => Some(d.clone()),
+        Bson::I32(i) => Some(Decimal128::from_i32(i)),
+        Bson::I64(i) => Some(from_i64(i)),
+        Bson::FloatingPoint(f) => Some(from_f64(f)),
+        _ => None,
+    }
+}
+
+// Whether `s` has the syntax of a decimal, which `Decimal128::from_str` panics on otherwise.
+fn is_decimal(s: &str) -> bool {
+    let unsigned = s.trim_start_matches(&['+', '-'][..]);
+    if s.len() - unsigned.len() > 1 {
+        return false;
+    }
+
+    match unsigned.to_ascii_lowercase().as_str() {
+        "nan" | "inf" | "infinity" => return true,
+        _ => (),
+    }
+
+    let (significand, exponent) = match unsigned.find(&['e', 'E'][..]) {
+        Some(index) => (&unsigned[..index], Some(&unsigned[index + 1..])),
+        None => (unsigned, None),
+    };
+
+    let mut parts = significand.splitn(2, '.');
+    let integer = parts.next().unwrap_or("");
+    let fraction = parts.next().unwrap_or("");
+    let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+
+    if integer.is_empty() && fraction.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+        return false;
+    }
+
+    match exponent {
+        Some(exponent) => {
+            let digits = exponent.trim_start_matches(&['+', '-'][..]);
+            exponent.len() - digits.len() <= 1 && !digits.is_empty() && all_digits(digits)
+        }
+        None => true,
+    }
+}
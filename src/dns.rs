@@ -0,0 +1,249 @@
+//! Host name resolution.
+//!
+//! New connections look up the addresses of their server with the client's `DnsResolver`, and
+//! the SRV records of a `mongodb+srv://` connection string are looked up with it unless
+//! `ClientOptions::srv_resolver` is given.
+//!
+//! By default, clients resolve host names with the operating system (`SystemResolver`) through a
+//! `CachingResolver`, which keeps each answer until its TTL runs out so that connection attempts
+//! do not wait on DNS. The operating system does not report TTLs, so its answers are kept for 30
+//! seconds. A connection attempt that fails on every address of a host drops the host from the
+//! cache, so that a server that moved is found at its new address on the next attempt.
+//!
+//! With the `trust-dns` feature, `TrustDnsResolver` queries the name servers of the system
+//! configuration itself, keeping answers for their TTLs and looking up SRV records too.
+//!
+//! Tests can route every host name to a server of their choosing:
+//!
+//! ```no_run
+//! # use mongodb::{Client, ClientOptions, ThreadedClient};
+//! # use mongodb::dns::{DnsResolver, Lookup};
+//! # use std::io;
+//! # use std::net::{IpAddr, Ipv4Addr};
+//! # use std::sync::Arc;
+//! #
+//! struct Loopback;
+//!
+//! impl DnsResolver for Loopback {
+//!     fn lookup_ip(&self, _: &str) -> io::Result<Lookup<IpAddr>> {
+//!         Ok(Lookup::new(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)], None))
+//!     }
+//! }
+//!
+//! let mut options = ClientOptions::new();
+//! options.dns_resolver = Some(Arc::new(Loopback));
+//!
+//! let client = Client::with_uri_and_options("mongodb://db.example.com", options).unwrap();
+//! ```
+use clock::Clock;
+use connstring::Host;
+
+#[cfg(feature = "trust-dns")]
+use trust_dns_resolver::Resolver;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a `CachingResolver` keeps answers that do not say how long they may be cached.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+/// The answer to a DNS query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lookup<T> {
+    pub records: Vec<T>,
+    /// How long the records may be cached, if known.
+    pub ttl: Option<Duration>,
+}
+
+impl<T> Lookup<T> {
+    pub fn new(records: Vec<T>, ttl: Option<Duration>) -> Lookup<T> {
+        Lookup { records, ttl }
+    }
+}
+
+/// Looks up host names and service records.
+pub trait DnsResolver: Send + Sync {
+    /// Returns the addresses of `hostname`.
+    fn lookup_ip(&self, hostname: &str) -> io::Result<Lookup<IpAddr>>;
+
+    /// Returns the target and port of each SRV record of `name`, such as
+    /// `_mongodb._tcp.cluster0.example.com`. Fails unless the resolver can look up SRV records.
+    fn lookup_srv(&self, name: &str) -> io::Result<Lookup<Host>> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "Cannot look up the SRV records of '{}'; enable the trust-dns feature or set \
+                 ClientOptions::srv_resolver.",
+                name
+            ),
+        ))
+    }
+
+    /// Forgets any answer kept for `hostname`, after its addresses could not be reached.
+    fn evict(&self, _hostname: &str) {}
+}
+
+/// Resolves host names with the operating system, as the standard library does.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemResolver;
+
+impl DnsResolver for SystemResolver {
+    fn lookup_ip(&self, hostname: &str) -> io::Result<Lookup<IpAddr>> {
+        let records = (hostname, 0).to_socket_addrs()?.map(|addr| addr.ip()).collect();
+        Ok(Lookup::new(records, None))
+    }
+}
+
+// A cached answer, and when it runs out.
+struct Cached<T> {
+    records: Vec<T>,
+    expires: Instant,
+}
+
+/// Keeps the answers of another resolver for their TTLs, or for a default TTL if they have none.
+pub struct CachingResolver {
+    inner: Arc<dyn DnsResolver>,
+    clock: Arc<dyn Clock>,
+    default_ttl: Duration,
+    addresses: Mutex<HashMap<String, Cached<IpAddr>>>,
+    services: Mutex<HashMap<String, Cached<Host>>>,
+}
+
+impl fmt::Debug for CachingResolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CachingResolver")
+            .field("inner", &"DnsResolver { .. }")
+            .field("clock", &self.clock)
+            .field("default_ttl", &self.default_ttl)
+            .finish()
+    }
+}
+
+impl CachingResolver {
+    /// Caches the answers of `inner`, keeping those without a TTL for `DEFAULT_TTL`.
+    pub fn new(inner: Arc<dyn DnsResolver>, clock: Arc<dyn Clock>) -> CachingResolver {
+        CachingResolver::with_default_ttl(inner, clock, DEFAULT_TTL)
+    }
+
+    /// Caches the answers of `inner`, keeping those without a TTL for `default_ttl`.
+    pub fn with_default_ttl(
+        inner: Arc<dyn DnsResolver>,
+        clock: Arc<dyn Clock>,
+        default_ttl: Duration,
+    ) -> CachingResolver {
+        CachingResolver {
+            inner,
+            clock,
+            default_ttl,
+            addresses: Mutex::new(HashMap::new()),
+            services: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Returns the cached answer for `name` if it has not run out, or else looks it up with
+    // `lookup` and caches the answer. The cache is not locked during the lookup, and is skipped
+    // if its lock is poisoned.
+    fn cached<T: Clone>(
+        &self,
+        cache: &Mutex<HashMap<String, Cached<T>>>,
+        name: &str,
+        lookup: impl FnOnce() -> io::Result<Lookup<T>>,
+    ) -> io::Result<Lookup<T>> {
+        let key = name.to_ascii_lowercase();
+
+        if let Ok(cache) = cache.lock() {
+            if let Some(cached) = cache.get(&key) {
+                let now = self.clock.now();
+                if now < cached.expires {
+                    return Ok(Lookup::new(cached.records.clone(), Some(cached.expires - now)));
+                }
+            }
+        }
+
+        let answer = lookup()?;
+        let ttl = answer.ttl.unwrap_or(self.default_ttl);
+        if ttl > Duration::from_secs(0) && !answer.records.is_empty() {
+            let cached = Cached {
+                records: answer.records.clone(),
+                expires: self.clock.now() + ttl,
+            };
+            if let Ok(mut cache) = cache.lock() {
+                cache.insert(key, cached);
+            }
+        }
+
+        Ok(Lookup::new(answer.records, Some(ttl)))
+    }
+}
+
+impl DnsResolver for CachingResolver {
+    fn lookup_ip(&self, hostname: &str) -> io::Result<Lookup<IpAddr>> {
+        self.cached(&self.addresses, hostname, || self.inner.lookup_ip(hostname))
+    }
+
+    fn lookup_srv(&self, name: &str) -> io::Result<Lookup<Host>> {
+        self.cached(&self.services, name, || self.inner.lookup_srv(name))
+    }
+
+    fn evict(&self, hostname: &str) {
+        if let Ok(mut addresses) = self.addresses.lock() {
+            addresses.remove(&hostname.to_ascii_lowercase());
+        }
+        self.inner.evict(hostname);
+    }
+}
+
+/// Resolves host names and SRV records by querying name servers directly, reporting the TTL of
+/// each answer.
+#[cfg(feature = "trust-dns")]
+pub struct TrustDnsResolver {
+    resolver: Resolver,
+}
+
+#[cfg(feature = "trust-dns")]
+impl fmt::Debug for TrustDnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TrustDnsResolver").finish()
+    }
+}
+
+#[cfg(feature = "trust-dns")]
+impl TrustDnsResolver {
+    /// Creates a resolver that queries the name servers of the system configuration, such as
+    /// `/etc/resolv.conf`.
+    pub fn from_system_conf() -> io::Result<TrustDnsResolver> {
+        Ok(TrustDnsResolver { resolver: Resolver::from_system_conf()? })
+    }
+
+    /// Creates a resolver that queries the name servers of `resolver`.
+    pub fn with_resolver(resolver: Resolver) -> TrustDnsResolver {
+        TrustDnsResolver { resolver }
+    }
+}
+
+#[cfg(feature = "trust-dns")]
+impl DnsResolver for TrustDnsResolver {
+    fn lookup_ip(&self, hostname: &str) -> io::Result<Lookup<IpAddr>> {
+        let lookup = self.resolver.lookup_ip(hostname)?;
+        let ttl = lookup.valid_until().saturating_duration_since(Instant::now());
+        Ok(Lookup::new(lookup.iter().collect(), Some(ttl)))
+    }
+
+    fn lookup_srv(&self, name: &str) -> io::Result<Lookup<Host>> {
+        let lookup = self.resolver.srv_lookup(name)?;
+        let ttl = lookup.as_lookup().valid_until().saturating_duration_since(Instant::now());
+        let records = lookup
+            .iter()
+            .map(|srv| {
+                let target = srv.target().to_utf8();
+                Host::new(String::from(target.trim_end_matches('.')), srv.port())
+            })
+            .collect();
+
+        Ok(Lookup::new(records, Some(ttl)))
+    }
+}
@@ -0,0 +1,135 @@
+//! Explicit encryption, for applications that encrypt and decrypt values themselves.
+use bson::{Bson, doc};
+use bson::spec::BinarySubtype;
+use chrono::Utc;
+
+use super::{Algorithm, AutoEncryptionOptions, Encrypter, ENCRYPTED_SUBTYPE, KmsProviders,
+            MasterKey};
+use super::crypto::{self, KEY_LEN};
+use Error::ArgumentError;
+use {Client, Result};
+
+/// Options for creating a data key.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DataKeyOptions {
+    /// Alternate names to refer to the key by instead of its id.
+    pub key_alt_names: Vec<String>,
+}
+
+impl DataKeyOptions {
+    pub fn new() -> DataKeyOptions {
+        Default::default()
+    }
+}
+
+/// Identifies the data key to encrypt a value with.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EncryptionKey {
+    /// The key's id, a 16-byte UUID.
+    Id(Vec<u8>),
+    /// One of the key's alternate names.
+    AltName(String),
+}
+
+/// Creates data keys, and encrypts and decrypts values with them.
+///
+/// Values encrypted here can be stored like any other, and are decrypted automatically by clients
+/// with `ClientOptions::auto_encryption` set that share the key vault.
+///
+/// ```no_run
+/// # extern crate mongodb;
+/// # use mongodb::{Bson, Client, ThreadedClient};
+/// # use mongodb::encryption::{Algorithm, ClientEncryption, EncryptionKey, KmsProviders,
+/// #                           MasterKey};
+/// #
+/// # fn main() {
+/// let mut kms_providers = KmsProviders::new();
+/// kms_providers.local = Some(vec![0; 96]);
+///
+/// let client = Client::connect("localhost", 27017).unwrap();
+/// let encryption =
+///     ClientEncryption::new(client, "encryption.__keyVault", kms_providers).unwrap();
+///
+/// let key_id = encryption.create_data_key(MasterKey::Local, None).unwrap();
+/// let ssn = Bson::String(String::from("123-45-6789"));
+/// let encrypted = encryption
+///     .encrypt(&ssn, EncryptionKey::Id(key_id), Algorithm::Deterministic)
+///     .unwrap();
+/// assert_eq!(ssn, encryption.decrypt(&encrypted).unwrap());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ClientEncryption {
+    encrypter: Encrypter,
+}
+
+impl ClientEncryption {
+    /// Creates a `ClientEncryption` that stores data keys in `key_vault_namespace`, as
+    /// `database.collection`, through `key_vault_client`.
+    pub fn new(
+        key_vault_client: Client,
+        key_vault_namespace: &str,
+        kms_providers: KmsProviders,
+    ) -> Result<ClientEncryption> {
+        let options = AutoEncryptionOptions::new(key_vault_namespace, kms_providers);
+
+        Ok(ClientEncryption { encrypter: Encrypter::new(options, &key_vault_client)? })
+    }
+
+    /// Creates a data key encrypted with `master_key`, stores it in the key vault and returns its
+    /// id.
+    pub fn create_data_key(
+        &self,
+        master_key: MasterKey,
+        options: Option<DataKeyOptions>,
+    ) -> Result<Vec<u8>> {
+        let options = options.unwrap_or_default();
+
+        let data_key = crypto::random_bytes(KEY_LEN)?;
+        let key_material = self.encrypter.kms_providers().wrap(&master_key, &data_key)?;
+
+        // Random UUIDs are version 4, variant 1.
+        let mut key_id = crypto::random_bytes(16)?;
+        key_id[6] = (key_id[6] & 0x0f) | 0x40;
+        key_id[8] = (key_id[8] & 0x3f) | 0x80;
+
+        let now = Bson::UtcDatetime(Utc::now());
+        let mut key_doc = doc! {
+            "_id": Bson::Binary(BinarySubtype::Uuid, key_id.clone()),
+            "keyMaterial": Bson::Binary(BinarySubtype::Generic, key_material),
+            "creationDate": now.clone(),
+            "updateDate": now,
+            "status": 0,
+            "masterKey": master_key.to_document(),
+        };
+
+        if !options.key_alt_names.is_empty() {
+            let names: Vec<Bson> = options.key_alt_names.into_iter().map(Bson::String).collect();
+            key_doc.insert("keyAltNames", names);
+        }
+
+        self.encrypter.key_vault().insert_one(key_doc, None)?;
+        Ok(key_id)
+    }
+
+    /// Encrypts `value` with the data key `key`, returning a binary value of subtype 6.
+    pub fn encrypt(&self, value: &Bson, key: EncryptionKey, algorithm: Algorithm) -> Result<Bson> {
+        let key_id = match key {
+            EncryptionKey::Id(id) => id,
+            EncryptionKey::AltName(name) => self.encrypter.key_id_for_alt_name(&name)?,
+        };
+
+        self.encrypter.encrypt_value(value, &key_id, algorithm)
+    }
+
+    /// Decrypts a value returned by `encrypt`.
+    pub fn decrypt(&self, value: &Bson) -> Result<Bson> {
+        match *value {
+            Bson::Binary(BinarySubtype::UserDefined(ENCRYPTED_SUBTYPE), ref blob) => {
+                self.encrypter.decrypt_value(blob)
+            }
+            _ => Err(ArgumentError(String::from("Only subtype 6 binary values can be decrypted."))),
+        }
+    }
+}
+
@@ -0,0 +1,161 @@
+//! The AEAD_AES_256_CBC_HMAC_SHA_512 algorithm used for encrypted fields and wrapped data keys.
+//!
+//! A 96-byte key is split into a MAC key, an encryption key and, for deterministic encryption,
+//! an IV key of 32 bytes each. The plaintext is encrypted with AES-256-CBC and PKCS#7 padding
+//! under a random IV, or under an IV derived from the plaintext when deterministic, and the IV
+//! and ciphertext are authenticated together with the associated data by a truncated
+//! HMAC-SHA-512 tag. The output is the IV, the ciphertext and the tag.
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::rand::rand_bytes;
+use openssl::sign::Signer;
+use openssl::symm::{self, Cipher};
+
+use Error::EncryptionError;
+use Result;
+
+/// The length of a data key or local master key.
+pub const KEY_LEN: usize = 96;
+
+const IV_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+
+/// Encrypts `plaintext` under `key`, authenticating `associated_data` along with it.
+pub fn encrypt(
+    key: &[u8],
+    associated_data: &[u8],
+    plaintext: &[u8],
+    deterministic: bool,
+) -> Result<Vec<u8>> {
+    check_key(key)?;
+    let (mac_key, enc_key, iv_key) = (&key[..32], &key[32..64], &key[64..]);
+    let associated_len = associated_data_len(associated_data);
+
+    let iv = if deterministic {
+        let mut iv = hmac_sha512(iv_key, &[associated_data, &associated_len, plaintext])?;
+        iv.truncate(IV_LEN);
+        iv
+    } else {
+        random_bytes(IV_LEN)?
+    };
+
+    let mut output = iv;
+    let ciphertext = symm::encrypt(Cipher::aes_256_cbc(), enc_key, Some(&output), plaintext)
+        .map_err(crypto_error)?;
+    output.extend(ciphertext);
+
+    let tag = hmac_sha512(mac_key, &[associated_data, &output, &associated_len])?;
+    output.extend_from_slice(&tag[..TAG_LEN]);
+
+    Ok(output)
+}
+
+/// Authenticates and decrypts the output of `encrypt`.
+pub fn decrypt(key: &[u8], associated_data: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    check_key(key)?;
+
+    // The smallest output is one block of padding between the IV and the tag.
+    if ciphertext.len() < IV_LEN + 16 + TAG_LEN ||
+        (ciphertext.len() - TAG_LEN) % 16 != 0
+    {
+        return Err(EncryptionError(String::from("Ciphertext has an invalid length.")));
+    }
+
+    let (mac_key, enc_key) = (&key[..32], &key[32..64]);
+    let (sealed, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+    let associated_len = associated_data_len(associated_data);
+
+    let expected = hmac_sha512(mac_key, &[associated_data, sealed, &associated_len])?;
+    if !memcmp::eq(&expected[..TAG_LEN], tag) {
+        return Err(EncryptionError(String::from(
+            "Ciphertext failed authentication; it was altered or encrypted with another key.",
+        )));
+    }
+
+    let (iv, encrypted) = sealed.split_at(IV_LEN);
+    symm::decrypt(Cipher::aes_256_cbc(), enc_key, Some(iv), encrypted).map_err(crypto_error)
+}
+
+/// Returns `len` cryptographically secure random bytes.
+pub fn random_bytes(len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    rand_bytes(&mut bytes).map_err(crypto_error)?;
+    Ok(bytes)
+}
+
+/// Computes the HMAC-SHA-256 of `data`.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    hmac(MessageDigest::sha256(), key, &[data])
+}
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> Result<Vec<u8>> {
+    hmac(MessageDigest::sha512(), key, parts)
+}
+
+fn hmac(digest: MessageDigest, key: &[u8], parts: &[&[u8]]) -> Result<Vec<u8>> {
+    let key = PKey::hmac(key).map_err(crypto_error)?;
+    let mut signer = Signer::new(digest, &key).map_err(crypto_error)?;
+
+    for part in parts {
+        signer.update(part).map_err(crypto_error)?;
+    }
+
+    signer.sign_to_vec().map_err(crypto_error)
+}
+
+// The length of the associated data in bits, as a 64-bit big-endian integer.
+fn associated_data_len(associated_data: &[u8]) -> [u8; 8] {
+    let bits = (associated_data.len() as u64) * 8;
+    let mut len = [0; 8];
+
+    for (i, byte) in len.iter_mut().enumerate() {
+        *byte = (bits >> (56 - 8 * i)) as u8;
+    }
+
+    len
+}
+
+fn check_key(key: &[u8]) -> Result<()> {
+    if key.len() != KEY_LEN {
+        return Err(EncryptionError(format!(
+            "Encryption keys must be {} bytes, not {}.",
+            KEY_LEN,
+            key.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn crypto_error(err: ErrorStack) -> ::Error {
+    EncryptionError(format!("Cryptographic operation failed: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, encrypt, KEY_LEN};
+
+    #[test]
+    fn round_trip() {
+        let key: Vec<u8> = (0..KEY_LEN as u8).collect();
+        let associated_data = b"associated";
+
+        let random = encrypt(&key, associated_data, b"secret", false).unwrap();
+        assert_eq!(16 + 16 + 32, random.len());
+        assert_eq!(b"secret".to_vec(), decrypt(&key, associated_data, &random).unwrap());
+        assert_ne!(random, encrypt(&key, associated_data, b"secret", false).unwrap());
+
+        let deterministic = encrypt(&key, associated_data, b"secret", true).unwrap();
+        assert_eq!(deterministic, encrypt(&key, associated_data, b"secret", true).unwrap());
+        assert_eq!(b"secret".to_vec(), decrypt(&key, associated_data, &deterministic).unwrap());
+
+        // Any change to the ciphertext or associated data fails authentication.
+        let mut altered = deterministic.clone();
+        altered[20] ^= 1;
+        assert!(decrypt(&key, associated_data, &altered).is_err());
+        assert!(decrypt(&key, b"other", &deterministic).is_err());
+        assert!(encrypt(&key[1..], associated_data, b"secret", true).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! Key management services, which encrypt the data keys stored in the key vault.
+use bson::{self, doc};
+use chrono::Utc;
+use data_encoding::BASE64;
+use hex;
+use openssl::sha::sha256;
+use openssl::ssl::{SslConnector, SslMethod};
+use serde_json::{self, json, Value};
+
+use super::crypto::{self, hmac_sha256, KEY_LEN};
+use Error::{ArgumentError, EncryptionError};
+use Result;
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const KMS_TIMEOUT_SECS: u64 = 10;
+
+/// Credentials for the key management services that protect data keys.
+#[derive(Clone, Default, PartialEq)]
+pub struct KmsProviders {
+    /// A 96-byte master key held by the application, for data keys created with
+    /// `MasterKey::Local`.
+    pub local: Option<Vec<u8>>,
+    /// Credentials for AWS KMS, for data keys created with `MasterKey::Aws`.
+    pub aws: Option<AwsCredentials>,
+}
+
+impl KmsProviders {
+    pub fn new() -> KmsProviders {
+        Default::default()
+    }
+
+    /// Encrypts a data key with the master key, returning the key material to store in the
+    /// key vault.
+    pub fn wrap(&self, master_key: &MasterKey, data_key: &[u8]) -> Result<Vec<u8>> {
+        match *master_key {
+            MasterKey::Local => crypto::encrypt(self.local_key()?, &[], data_key, false),
+            MasterKey::Aws { ref region, ref key, ref endpoint } => {
+                let body = json!({ "KeyId": key, "Plaintext": BASE64.encode(data_key) });
+                let reply = self.aws_request(region, endpoint, "TrentService.Encrypt", &body)?;
+                decode_field(&reply, "CiphertextBlob")
+            }
+        }
+    }
+
+    /// Decrypts the key material stored in the key vault with the master key.
+    pub fn unwrap(&self, master_key: &MasterKey, key_material: &[u8]) -> Result<Vec<u8>> {
+        let data_key = match *master_key {
+            MasterKey::Local => crypto::decrypt(self.local_key()?, &[], key_material)?,
+            MasterKey::Aws { ref region, ref endpoint, .. } => {
+                let body = json!({ "CiphertextBlob": BASE64.encode(key_material) });
+                let reply = self.aws_request(region, endpoint, "TrentService.Decrypt", &body)?;
+                decode_field(&reply, "Plaintext")?
+            }
+        };
+
+        if data_key.len() != KEY_LEN {
+            return Err(EncryptionError(String::from("Decrypted data key has an invalid length.")));
+        }
+
+        Ok(data_key)
+    }
+
+    fn local_key(&self) -> Result<&[u8]> {
+        match self.local {
+            Some(ref key) if key.len() == KEY_LEN => Ok(key),
+            Some(_) => {
+                Err(ArgumentError(format!("The local master key must be {} bytes.", KEY_LEN)))
+            }
+            None => Err(ArgumentError(String::from("No local master key is configured."))),
+        }
+    }
+
+    // Sends a request to the AWS KMS API, signed with Signature Version 4.
+    fn aws_request(
+        &self,
+        region: &str,
+        endpoint: &Option<String>,
+        target: &str,
+        body: &Value,
+    ) -> Result<Value> {
+        let credentials = match self.aws {
+            Some(ref credentials) => credentials,
+            None => return Err(ArgumentError(String::from("No AWS credentials are configured."))),
+        };
+
+        let host = match *endpoint {
+            Some(ref endpoint) => endpoint.clone(),
+            None => format!("kms.{}.amazonaws.com", region),
+        };
+        let body = body.to_string();
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let mut headers = vec![
+            (String::from("content-type"), String::from("application/x-amz-json-1.1")),
+            (String::from("host"), host.clone()),
+            (String::from("x-amz-date"), amz_date.clone()),
+            (String::from("x-amz-target"), String::from(target)),
+        ];
+
+        if let Some(ref token) = credentials.session_token {
+            headers.push((String::from("x-amz-security-token"), token.clone()));
+        }
+
+        headers.sort();
+
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let canonical_request = format!(
+            "POST\n/\n\n{}\n{}\n{}",
+            canonical_headers,
+            signed_headers,
+            hex::encode(sha256(body.as_bytes()))
+        );
+
+        let scope = format!("{}/{}/kms/aws4_request", date, region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(sha256(canonical_request.as_bytes()))
+        );
+
+        let mut signing_key = format!("AWS4{}", credentials.secret_access_key).into_bytes();
+        for part in &[date.as_str(), region, "kms", "aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, part.as_bytes())?;
+        }
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes())?);
+
+        let mut request = String::from("POST / HTTP/1.1\r\n");
+        for (name, value) in &headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str(&format!(
+            "authorization: AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, \
+             Signature={}\r\n",
+            credentials.access_key_id,
+            scope,
+            signed_headers,
+            signature
+        ));
+        request.push_str(&format!("content-length: {}\r\nconnection: close\r\n\r\n", body.len()));
+        request.push_str(&body);
+
+        let (status, reply) = https_post(&host, request.as_bytes())?;
+        let reply: Value = serde_json::from_slice(&reply).map_err(|err| {
+            EncryptionError(format!("Invalid reply from AWS KMS: {}", err))
+        })?;
+
+        if status != 200 {
+            let message = reply.get("message").or_else(|| reply.get("Message"));
+            return Err(EncryptionError(format!(
+                "AWS KMS request failed with status {}: {} {}",
+                status,
+                reply.get("__type").and_then(Value::as_str).unwrap_or_default(),
+                message.and_then(Value::as_str).unwrap_or_default()
+            )));
+        }
+
+        Ok(reply)
+    }
+}
+
+impl fmt::Debug for KmsProviders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Master keys and credentials are kept out of logs.
+        f.debug_struct("KmsProviders")
+            .field("local", &self.local.as_ref().map(|_| "<redacted>"))
+            .field("aws", &self.aws.as_ref().map(|aws| &aws.access_key_id))
+            .finish()
+    }
+}
+
+/// Credentials for AWS KMS.
+#[derive(Clone, PartialEq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Required for temporary credentials.
+    pub session_token: Option<String>,
+}
+
+/// The master key that a data key is encrypted with.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MasterKey {
+    /// The local master key in `KmsProviders::local`.
+    Local,
+    /// A customer master key in AWS KMS.
+    Aws {
+        region: String,
+        /// The Amazon Resource Name of the key.
+        key: String,
+        /// Overrides the KMS endpoint, as a host name with an optional port.
+        endpoint: Option<String>,
+    },
+}
+
+impl MasterKey {
+    /// Returns the `masterKey` document stored with a data key.
+    pub fn to_document(&self) -> bson::Document {
+        match *self {
+            MasterKey::Local => doc! { "provider": "local" },
+            MasterKey::Aws { ref region, ref key, ref endpoint } => {
+                let mut doc = doc! {
+                    "provider": "aws",
+                    "region": region.clone(),
+                    "key": key.clone(),
+                };
+
+                if let Some(ref endpoint) = *endpoint {
+                    doc.insert("endpoint", endpoint.clone());
+                }
+
+                doc
+            }
+        }
+    }
+
+    /// Parses the `masterKey` document stored with a data key.
+    pub fn from_document(doc: &bson::Document) -> Result<MasterKey> {
+        match doc.get_str("provider") {
+            Ok("local") => Ok(MasterKey::Local),
+            Ok("aws") => {
+                match (doc.get_str("region"), doc.get_str("key")) {
+                    (Ok(region), Ok(key)) => {
+                        Ok(MasterKey::Aws {
+                            region: String::from(region),
+                            key: String::from(key),
+                            endpoint: doc.get_str("endpoint").ok().map(String::from),
+                        })
+                    }
+                    _ => Err(EncryptionError(String::from("AWS master key has no region or key."))),
+                }
+            }
+            Ok(provider) => {
+                Err(EncryptionError(format!("Unsupported KMS provider '{}'.", provider)))
+            }
+            Err(_) => Err(EncryptionError(String::from("Master key has no provider."))),
+        }
+    }
+}
+
+// Sends an HTTP request over TLS, returning the status code and body of the response.
+fn https_post(host: &str, request: &[u8]) -> Result<(u32, Vec<u8>)> {
+    let (name, address) = match host.find(':') {
+        Some(index) => (&host[..index], String::from(host)),
+        None => (host, format!("{}:443", host)),
+    };
+
+    let stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(Duration::from_secs(KMS_TIMEOUT_SECS)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(KMS_TIMEOUT_SECS)))?;
+
+    let connector = SslConnector::builder(SslMethod::tls())
+        .map_err(|err| EncryptionError(format!("Failed to set up TLS: {}", err)))?
+        .build();
+    let mut stream = connector.connect(name, stream).map_err(|err| {
+        EncryptionError(format!("TLS handshake with {} failed: {}", host, err))
+    })?;
+
+    stream.write_all(request)?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+
+    parse_response(&response)
+}
+
+fn parse_response(response: &[u8]) -> Result<(u32, Vec<u8>)> {
+    let invalid = || EncryptionError(String::from("Invalid HTTP response from KMS."));
+
+    let split = response.windows(4).position(|window| window == b"\r\n\r\n").ok_or_else(invalid)?;
+    let head = String::from_utf8_lossy(&response[..split]).to_lowercase();
+    let body = &response[split + 4..];
+
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(invalid)?;
+
+    if !head.contains("transfer-encoding: chunked") {
+        return Ok((status, body.to_vec()));
+    }
+
+    // Each chunk is its length in hex, a line break, the data and another line break.
+    let mut decoded = Vec::new();
+    let mut rest = body;
+
+    loop {
+        let line_end = rest.windows(2).position(|window| window == b"\r\n").ok_or_else(invalid)?;
+        let len_str = String::from_utf8_lossy(&rest[..line_end]).into_owned();
+        let len = usize::from_str_radix(len_str.trim(), 16).map_err(|_| invalid())?;
+
+        if len == 0 {
+            return Ok((status, decoded));
+        }
+
+        let start = line_end + 2;
+        if rest.len() < start + len + 2 {
+            return Err(invalid());
+        }
+
+        decoded.extend_from_slice(&rest[start..start + len]);
+        rest = &rest[start + len + 2..];
+    }
+}
+
+fn decode_field(reply: &Value, field: &str) -> Result<Vec<u8>> {
+    let value = reply.get(field).and_then(Value::as_str).ok_or_else(|| {
+        EncryptionError(format!("AWS KMS reply has no {}.", field))
+    })?;
+
+    BASE64.decode(value.as_bytes()).map_err(|_| {
+        EncryptionError(format!("AWS KMS reply has an invalid {}.", field))
+    })
+}
@@ -0,0 +1,866 @@
+//! Client-side field level encryption.
+//!
+//! Fields holding sensitive data can be encrypted by the driver before they are sent to the
+//! server, so that the server only ever stores and sees ciphertext. Each value is encrypted with
+//! a data key, which is itself stored in a key vault collection, encrypted with a master key held
+//! by a key management service (KMS) or by the application.
+//!
+//! With `ClientOptions::auto_encryption` set, the client encrypts the fields that the schema map
+//! marks as encrypted in the commands it sends, and decrypts every encrypted value it reads back.
+//! Rather than relying on `libmongocrypt` and `mongocryptd`, the driver implements the following
+//! subset of automatic encryption itself:
+//!
+//! * Schemas are JSON schemas given in the schema map, of which only nested `properties`,
+//!   `encrypt` and `encryptMetadata` are interpreted. Each encrypted field needs a `keyId` of
+//!   one data key id and an `algorithm`; arrays of documents (`items`) are not searched.
+//! * Documents are encrypted in `insert` commands, and in replacement documents and `$set` or
+//!   `$setOnInsert` of `update` and `findAndModify`. Other update operators on encrypted fields
+//!   and pipeline updates fail.
+//! * Equality conditions on deterministically encrypted fields, whether literal or with `$eq`,
+//!   `$ne`, `$in` or `$nin`, are encrypted in the filters of `find`, `count`, `distinct`,
+//!   `delete`, `update` and `findAndModify`, and in leading `$match` stages of `aggregate`. Other
+//!   conditions on encrypted fields, and any condition on randomly encrypted fields, fail, as do
+//!   `$expr` and later aggregation stages that refer to encrypted fields, and `$where`.
+//! * Data keys can be protected by a local master key or by AWS KMS.
+//!
+//! `ClientEncryption` creates data keys, and encrypts and decrypts single values explicitly, for
+//! applications that choose what to encrypt themselves.
+//!
+//! Encryption requires the `encryption` feature, which uses OpenSSL for cryptography.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::{Bson, Client, ClientOptions, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # use mongodb::encryption::{AutoEncryptionOptions, KmsProviders};
+//! # use std::collections::BTreeMap;
+//! #
+//! # fn main() {
+//! # let key_id = Bson::Null;
+//! let mut kms_providers = KmsProviders::new();
+//! kms_providers.local = Some(vec![0; 96]);
+//!
+//! let schema = doc! {
+//!     "bsonType": "object",
+//!     "properties": {
+//!         "ssn": {
+//!             "encrypt": {
+//!                 "keyId": [key_id],
+//!                 "bsonType": "string",
+//!                 "algorithm": "AEAD_AES_256_CBC_HMAC_SHA_512-Deterministic",
+//!             },
+//!         },
+//!     },
+//! };
+//!
+//! let mut encryption = AutoEncryptionOptions::new("encryption.__keyVault", kms_providers);
+//! encryption.schema_map.insert(String::from("hr.people"), schema);
+//!
+//! let mut options = ClientOptions::new();
+//! options.auto_encryption = Some(encryption);
+//!
+//! let client = Client::connect_with_options("localhost", 27017, options).unwrap();
+//! let people = client.db("hr").collection("people");
+//! people.insert_one(doc! { "name": "Ann", "ssn": "123-45-6789" }, None).unwrap();
+//! let ann = people.find_one(Some(doc! { "ssn": "123-45-6789" }), None).unwrap();
+//! # }
+//! ```
+mod client_encryption;
+mod crypto;
+mod kms;
+
+pub use self::client_encryption::{ClientEncryption, DataKeyOptions, EncryptionKey};
+pub use self::kms::{AwsCredentials, KmsProviders, MasterKey};
+
+use bson::{self, Bson, doc};
+use bson::spec::BinarySubtype;
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use coll::Collection;
+use db::ThreadedDatabase;
+use Error::{ArgumentError, EncryptionError};
+use {Client, Result, ThreadedClient};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+
+/// The binary subtype of encrypted values.
+pub const ENCRYPTED_SUBTYPE: u8 = 6;
+
+/// How values are encrypted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// Encrypts equal values to equal ciphertexts, so that they can be queried for equality.
+    Deterministic,
+    /// Encrypts each value with a random IV, so that equal values cannot be told apart.
+    Random,
+}
+
+impl Algorithm {
+    /// The algorithm's name in schemas.
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Algorithm::Deterministic => "AEAD_AES_256_CBC_HMAC_SHA_512-Deterministic",
+            Algorithm::Random => "AEAD_AES_256_CBC_HMAC_SHA_512-Random",
+        }
+    }
+
+    /// Parses an algorithm's name in schemas.
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        match name {
+            "AEAD_AES_256_CBC_HMAC_SHA_512-Deterministic" => Some(Algorithm::Deterministic),
+            "AEAD_AES_256_CBC_HMAC_SHA_512-Random" => Some(Algorithm::Random),
+            _ => None,
+        }
+    }
+
+    // The first byte of values encrypted with the algorithm.
+    fn subtype(self) -> u8 {
+        match self {
+            Algorithm::Deterministic => 1,
+            Algorithm::Random => 2,
+        }
+    }
+}
+
+/// Options for automatically encrypting and decrypting fields.
+#[derive(Clone, Debug)]
+pub struct AutoEncryptionOptions {
+    /// The collection holding data keys, as `database.collection`.
+    pub key_vault_namespace: String,
+    pub kms_providers: KmsProviders,
+    /// The JSON schema of each namespace with encrypted fields, keyed by `database.collection`.
+    pub schema_map: BTreeMap<String, bson::Document>,
+    /// Whether to leave outgoing commands unencrypted, only decrypting what is read.
+    pub bypass_auto_encryption: bool,
+    /// The client to read data keys with; by default, a client without automatic encryption
+    /// connected to the same deployment.
+    pub key_vault_client: Option<Client>,
+}
+
+impl AutoEncryptionOptions {
+    pub fn new(key_vault_namespace: &str, kms_providers: KmsProviders) -> AutoEncryptionOptions {
+        AutoEncryptionOptions {
+            key_vault_namespace: String::from(key_vault_namespace),
+            kms_providers,
+            schema_map: BTreeMap::new(),
+            bypass_auto_encryption: false,
+            key_vault_client: None,
+        }
+    }
+}
+
+// A field that a schema marks as encrypted.
+#[derive(Clone, Debug, PartialEq)]
+struct EncryptedField {
+    path: Vec<String>,
+    key_id: Vec<u8>,
+    algorithm: Algorithm,
+}
+
+impl EncryptedField {
+    // Whether the field is at the path of dotted `segments`.
+    fn is_at(&self, segments: &[&str]) -> bool {
+        self.path.len() == segments.len() && self.is_under(segments)
+    }
+
+    // Whether the field is at or below the path of dotted `segments`.
+    fn is_under(&self, segments: &[&str]) -> bool {
+        self.path.len() >= segments.len() &&
+            self.path.iter().zip(segments).all(|(part, segment)| part == segment)
+    }
+
+    // Whether the path of dotted `segments` is below the field.
+    fn is_above(&self, segments: &[&str]) -> bool {
+        self.path.len() < segments.len() &&
+            self.path.iter().zip(segments).all(|(part, segment)| part == segment)
+    }
+
+    fn name(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// Encrypts and decrypts values with the data keys in a key vault.
+pub struct Encrypter {
+    key_vault: Collection,
+    kms_providers: KmsProviders,
+    schemas: BTreeMap<String, Vec<EncryptedField>>,
+    bypass_auto_encryption: bool,
+    // Decrypted data keys, by key id.
+    keys: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl fmt::Debug for Encrypter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Encrypter")
+            .field("key_vault", &self.key_vault.namespace)
+            .field("kms_providers", &self.kms_providers)
+            .field("schemas", &self.schemas.keys().collect::<Vec<_>>())
+            .field("bypass_auto_encryption", &self.bypass_auto_encryption)
+            .finish()
+    }
+}
+
+impl Encrypter {
+    /// Creates an encrypter that reads data keys with `key_vault_client`.
+    pub fn new(options: AutoEncryptionOptions, key_vault_client: &Client) -> Result<Encrypter> {
+        let (db, coll) = split_namespace(&options.key_vault_namespace).ok_or_else(|| {
+            ArgumentError(format!(
+                "Invalid key vault namespace '{}'.",
+                options.key_vault_namespace
+            ))
+        })?;
+
+        let mut schemas = BTreeMap::new();
+        for (namespace, schema) in &options.schema_map {
+            let mut fields = Vec::new();
+            parse_schema(schema, &[], None, None, &mut fields)?;
+            schemas.insert(namespace.clone(), fields);
+        }
+
+        Ok(Encrypter {
+            key_vault: key_vault_client.db(db).collection(coll),
+            kms_providers: options.kms_providers,
+            schemas,
+            bypass_auto_encryption: options.bypass_auto_encryption,
+            keys: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the key vault collection.
+    pub fn key_vault(&self) -> &Collection {
+        &self.key_vault
+    }
+
+    /// Returns the key management services that protect data keys.
+    pub fn kms_providers(&self) -> &KmsProviders {
+        &self.kms_providers
+    }
+
+    /// Encrypts `value` with the data key `key_id`, returning a binary value of subtype 6.
+    pub fn encrypt_value(&self, value: &Bson, key_id: &[u8], algorithm: Algorithm) -> Result<Bson> {
+        match *value {
+            Bson::Null => return Err(ArgumentError(String::from("Null cannot be encrypted."))),
+            Bson::FloatingPoint(_) |
+            Bson::Boolean(_) |
+            Bson::Document(_) |
+            Bson::Array(_) |
+            Bson::JavaScriptCodeWithScope(..) if algorithm == Algorithm::Deterministic => {
+                return Err(ArgumentError(String::from(
+                    "Doubles, booleans, documents and arrays can only be encrypted randomly.",
+                )))
+            }
+            _ => (),
+        }
+
+        if key_id.len() != 16 {
+            return Err(ArgumentError(String::from("Data key ids must be 16-byte UUIDs.")));
+        }
+
+        let (element_type, plaintext) = value_to_bytes(value)?;
+
+        let mut blob = Vec::with_capacity(plaintext.len() + 82);
+        blob.push(algorithm.subtype());
+        blob.extend_from_slice(key_id);
+        blob.push(element_type);
+
+        let key = self.data_key(key_id)?;
+        let ciphertext =
+            crypto::encrypt(&key, &blob, &plaintext, algorithm == Algorithm::Deterministic)?;
+        blob.extend(ciphertext);
+
+        Ok(Bson::Binary(BinarySubtype::UserDefined(ENCRYPTED_SUBTYPE), blob))
+    }
+
+    /// Decrypts the data of a binary value of subtype 6.
+    pub fn decrypt_value(&self, blob: &[u8]) -> Result<Bson> {
+        if blob.len() < 18 || (blob[0] != 1 && blob[0] != 2) {
+            return Err(EncryptionError(String::from("Invalid encrypted value.")));
+        }
+
+        let (associated_data, ciphertext) = blob.split_at(18);
+        let key = self.data_key(&associated_data[1..17])?;
+        let plaintext = crypto::decrypt(&key, associated_data, ciphertext)?;
+
+        value_from_bytes(associated_data[17], &plaintext)
+    }
+
+    /// Decrypts every encrypted value in `doc`.
+    pub fn decrypt_document(&self, doc: bson::Document) -> Result<bson::Document> {
+        let mut decrypted = bson::Document::new();
+
+        for (key, value) in doc {
+            decrypted.insert(key, self.decrypt_bson(value)?);
+        }
+
+        Ok(decrypted)
+    }
+
+    fn decrypt_bson(&self, value: Bson) -> Result<Bson> {
+        match value {
+            Bson::Binary(BinarySubtype::UserDefined(ENCRYPTED_SUBTYPE), ref blob) => {
+                self.decrypt_value(blob)
+            }
+            Bson::Document(doc) => Ok(Bson::Document(self.decrypt_document(doc)?)),
+            Bson::Array(values) => {
+                let values: Result<Vec<_>> =
+                    values.into_iter().map(|value| self.decrypt_bson(value)).collect();
+                Ok(Bson::Array(values?))
+            }
+            value => Ok(value),
+        }
+    }
+
+    /// Encrypts the fields the schema map marks as encrypted in a query or command sent to
+    /// `namespace`, which is `database.$cmd` for commands.
+    pub fn encrypt_query(
+        &self,
+        namespace: &str,
+        mut query: bson::Document,
+    ) -> Result<bson::Document> {
+        if self.bypass_auto_encryption || self.schemas.is_empty() {
+            return Ok(query);
+        }
+
+        // Queries with modifiers wrap the query or command in `$query`.
+        if let Some(Bson::Document(inner)) = query.remove("$query") {
+            let inner = self.encrypt_query(namespace, inner)?;
+            let mut wrapped = doc! { "$query": inner };
+            for (key, value) in query {
+                wrapped.insert(key, value);
+            }
+            return Ok(wrapped);
+        }
+
+        let (db, coll) = match split_namespace(namespace) {
+            Some(parts) => parts,
+            None => return Ok(query),
+        };
+
+        if coll != "$cmd" {
+            return match self.schemas.get(namespace) {
+                Some(fields) => self.encrypt_filter(fields, query),
+                None => Ok(query),
+            };
+        }
+
+        let (name, target) = match query.iter().next() {
+            Some((name, Bson::String(target))) => (name.clone(), target.clone()),
+            _ => return Ok(query),
+        };
+
+        let fields = match self.schemas.get(&format!("{}.{}", db, target)) {
+            Some(fields) => fields,
+            None => return Ok(query),
+        };
+
+        match name.as_str() {
+            "insert" => {
+                self.map_documents(&mut query, "documents", |doc| {
+                    self.encrypt_document(fields, doc)
+                })?;
+            }
+            "find" => self.map_field(&mut query, "filter", |doc| self.encrypt_filter(fields, doc))?,
+            "count" | "distinct" => {
+                self.map_field(&mut query, "query", |doc| self.encrypt_filter(fields, doc))?
+            }
+            "update" => {
+                self.map_documents(&mut query, "updates", |mut statement| {
+                    self.map_field(&mut statement, "q", |doc| self.encrypt_filter(fields, doc))?;
+                    if let Some(update) = statement.remove("u") {
+                        statement.insert("u", self.encrypt_update(fields, update)?);
+                    }
+                    Ok(statement)
+                })?;
+            }
+            "delete" => {
+                self.map_documents(&mut query, "deletes", |mut statement| {
+                    self.map_field(&mut statement, "q", |doc| self.encrypt_filter(fields, doc))?;
+                    Ok(statement)
+                })?;
+            }
+            "findAndModify" | "findandmodify" => {
+                self.map_field(&mut query, "query", |doc| self.encrypt_filter(fields, doc))?;
+                if let Some(update) = query.remove("update") {
+                    query.insert("update", self.encrypt_update(fields, update)?);
+                }
+            }
+            "aggregate" => {
+                let mut leading = true;
+                self.map_documents(&mut query, "pipeline", |mut stage| {
+                    leading = leading && stage.contains_key("$match");
+                    if leading {
+                        self.map_field(&mut stage, "$match", |doc| {
+                            self.encrypt_filter(fields, doc)
+                        })?;
+                        return Ok(stage);
+                    }
+
+                    // Later stages see documents reshaped by earlier ones, so they cannot be
+                    // rewritten.
+                    if let Some(field) = find_document_reference(fields, &stage) {
+                        return Err(EncryptionError(format!(
+                            "Only leading $match stages can refer to the encrypted field '{}'.",
+                            field.name()
+                        )));
+                    }
+                    Ok(stage)
+                })?;
+            }
+            _ => (),
+        }
+
+        Ok(query)
+    }
+
+    // Replaces the document at `key` in `doc`, if any, with the result of `f`.
+    fn map_field<F>(&self, doc: &mut bson::Document, key: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(bson::Document) -> Result<bson::Document>,
+    {
+        if let Some(&mut Bson::Document(ref mut inner)) = doc.get_mut(key) {
+            let value = ::std::mem::replace(inner, bson::Document::new());
+            *inner = f(value)?;
+        }
+        Ok(())
+    }
+
+    // Replaces each document in the array at `key` in `doc` with the result of `f`.
+    fn map_documents<F>(&self, doc: &mut bson::Document, key: &str, mut f: F) -> Result<()>
+    where
+        F: FnMut(bson::Document) -> Result<bson::Document>,
+    {
+        if let Some(&mut Bson::Array(ref mut values)) = doc.get_mut(key) {
+            for value in values.iter_mut() {
+                if let Bson::Document(ref mut inner) = *value {
+                    let taken = ::std::mem::replace(inner, bson::Document::new());
+                    *inner = f(taken)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn encrypt_document(
+        &self,
+        fields: &[EncryptedField],
+        mut doc: bson::Document,
+    ) -> Result<bson::Document> {
+        for field in fields {
+            self.encrypt_path(&mut doc, &field.path, field)?;
+        }
+        Ok(doc)
+    }
+
+    // Encrypts the value at `path` below `doc`, if present.
+    fn encrypt_path(
+        &self,
+        doc: &mut bson::Document,
+        path: &[String],
+        field: &EncryptedField,
+    ) -> Result<()> {
+        let (first, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+
+        if let Some(value) = doc.get_mut(first) {
+            if rest.is_empty() {
+                *value = self.encrypt_value(value, &field.key_id, field.algorithm)?;
+            } else if let Bson::Document(ref mut inner) = *value {
+                self.encrypt_path(inner, rest, field)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Encrypts the values at `key`, a dotted path, that the schema marks as encrypted.
+    fn encrypt_at_key(
+        &self,
+        fields: &[EncryptedField],
+        key: &str,
+        value: &mut Bson,
+        condition: bool,
+    ) -> Result<()> {
+        let segments: Vec<&str> = key.split('.').collect();
+
+        for field in fields {
+            if field.is_at(&segments) {
+                *value = if condition {
+                    self.encrypt_condition(field, value)?
+                } else {
+                    self.encrypt_value(value, &field.key_id, field.algorithm)?
+                };
+            } else if field.is_under(&segments) {
+                if condition && field.algorithm == Algorithm::Random {
+                    return Err(random_query_error(field));
+                }
+
+                if let Bson::Document(ref mut inner) = *value {
+                    // Operators on a document holding an encrypted field, such as `$gt` or
+                    // `$elemMatch`, would compare it with plaintext.
+                    if condition && inner.keys().any(|key| key.starts_with('$')) {
+                        return Err(EncryptionError(format!(
+                            "Unsupported condition on '{}', which holds the encrypted field '{}'.",
+                            key,
+                            field.name()
+                        )));
+                    }
+                    self.encrypt_path(inner, &field.path[segments.len()..], field)?;
+                }
+            } else if condition && field.is_above(&segments) {
+                return Err(EncryptionError(format!(
+                    "Cannot query on '{}', which is inside the encrypted field '{}'.",
+                    key,
+                    field.name()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn encrypt_filter(
+        &self,
+        fields: &[EncryptedField],
+        filter: bson::Document,
+    ) -> Result<bson::Document> {
+        let mut encrypted = bson::Document::new();
+
+        for (key, mut value) in filter {
+            match key.as_str() {
+                "$and" | "$or" | "$nor" => {
+                    if let Bson::Array(ref mut clauses) = value {
+                        for clause in clauses.iter_mut() {
+                            if let Bson::Document(ref mut inner) = *clause {
+                                let taken = ::std::mem::replace(inner, bson::Document::new());
+                                *inner = self.encrypt_filter(fields, taken)?;
+                            }
+                        }
+                    }
+                }
+                // Comments and text searches never compare a field's value.
+                "$comment" | "$text" => (),
+                "$where" => {
+                    return Err(EncryptionError(String::from(
+                        "$where is not supported on collections with encrypted fields.",
+                    )))
+                }
+                _ if key.starts_with('$') => {
+                    if let Some(field) = find_reference(fields, &value) {
+                        return Err(EncryptionError(format!(
+                            "Unsupported operator {} on the encrypted field '{}'.",
+                            key,
+                            field.name()
+                        )));
+                    }
+                }
+                _ => self.encrypt_at_key(fields, &key, &mut value, true)?,
+            }
+
+            encrypted.insert(key, value);
+        }
+
+        Ok(encrypted)
+    }
+
+    // Encrypts an equality condition on an encrypted field.
+    fn encrypt_condition(&self, field: &EncryptedField, condition: &Bson) -> Result<Bson> {
+        if field.algorithm == Algorithm::Random {
+            return Err(random_query_error(field));
+        }
+
+        let operators = match *condition {
+            Bson::Document(ref doc) if doc.keys().any(|key| key.starts_with('$')) => doc,
+            ref value => return self.encrypt_value(value, &field.key_id, field.algorithm),
+        };
+
+        let mut encrypted = bson::Document::new();
+
+        for (operator, operand) in operators {
+            let operand = match operator.as_str() {
+                "$eq" | "$ne" => self.encrypt_value(operand, &field.key_id, field.algorithm)?,
+                "$in" | "$nin" => {
+                    let values = match *operand {
+                        Bson::Array(ref values) => values,
+                        _ => return Err(ArgumentError(format!("{} needs an array.", operator))),
+                    };
+                    let values: Result<Vec<_>> = values
+                        .iter()
+                        .map(|value| self.encrypt_value(value, &field.key_id, field.algorithm))
+                        .collect();
+                    Bson::Array(values?)
+                }
+                "$exists" => operand.clone(),
+                _ => {
+                    return Err(EncryptionError(format!(
+                        "Unsupported operator {} on the encrypted field '{}'.",
+                        operator,
+                        field.name()
+                    )))
+                }
+            };
+
+            encrypted.insert(operator.clone(), operand);
+        }
+
+        Ok(Bson::Document(encrypted))
+    }
+
+    fn encrypt_update(&self, fields: &[EncryptedField], update: Bson) -> Result<Bson> {
+        let update = match update {
+            Bson::Document(update) => update,
+            _ => {
+                return Err(EncryptionError(String::from(
+                    "Pipeline updates are not supported on collections with encrypted fields.",
+                )))
+            }
+        };
+
+        if !update.keys().any(|key| key.starts_with('$')) {
+            return Ok(Bson::Document(self.encrypt_document(fields, update)?));
+        }
+
+        let mut encrypted = bson::Document::new();
+
+        for (operator, changes) in update {
+            let changes = match changes {
+                Bson::Document(changes) => changes,
+                changes => {
+                    encrypted.insert(operator, changes);
+                    continue;
+                }
+            };
+
+            let mut encrypted_changes = bson::Document::new();
+
+            for (key, mut value) in changes {
+                match operator.as_str() {
+                    "$set" | "$setOnInsert" => {
+                        self.encrypt_at_key(fields, &key, &mut value, false)?
+                    }
+                    "$unset" => (),
+                    _ => {
+                        let segments: Vec<&str> = key.split('.').collect();
+                        if let Some(field) = fields.iter().find(|f| f.is_under(&segments)) {
+                            return Err(EncryptionError(format!(
+                                "Unsupported update operator {} on the encrypted field '{}'.",
+                                operator,
+                                field.name()
+                            )));
+                        }
+                    }
+                }
+
+                encrypted_changes.insert(key, value);
+            }
+
+            encrypted.insert(operator, encrypted_changes);
+        }
+
+        Ok(Bson::Document(encrypted))
+    }
+
+    /// Returns the id of the data key with the alternate name `name`.
+    pub fn key_id_for_alt_name(&self, name: &str) -> Result<Vec<u8>> {
+        let key_doc = self.key_vault.find_one(Some(doc! { "keyAltNames": name }), None)?;
+
+        match key_doc.as_ref().and_then(|doc| doc.get("_id")) {
+            Some(Bson::Binary(BinarySubtype::Uuid, id)) => Ok(id.clone()),
+            Some(_) => Err(EncryptionError(format!("The data key '{}' has an invalid id.", name))),
+            None => Err(EncryptionError(format!("No data key is named '{}'.", name))),
+        }
+    }
+
+    // Returns the decrypted data key `key_id`, reading it from the key vault if necessary.
+    fn data_key(&self, key_id: &[u8]) -> Result<Vec<u8>> {
+        if let Some(key) = self.keys.lock()?.get(key_id) {
+            return Ok(key.clone());
+        }
+
+        let filter = doc! { "_id": Bson::Binary(BinarySubtype::Uuid, key_id.to_vec()) };
+        let key_doc = self.key_vault.find_one(Some(filter), None)?.ok_or_else(|| {
+            EncryptionError(String::from("The data key was not found in the key vault."))
+        })?;
+
+        let master_key = MasterKey::from_document(key_doc.get_document("masterKey").map_err(|_| {
+            EncryptionError(String::from("The data key has no master key."))
+        })?)?;
+
+        let key_material = match key_doc.get("keyMaterial") {
+            Some(Bson::Binary(_, material)) => material,
+            _ => return Err(EncryptionError(String::from("The data key has no key material."))),
+        };
+
+        let key = self.kms_providers.unwrap(&master_key, key_material)?;
+        self.keys.lock()?.insert(key_id.to_vec(), key.clone());
+        Ok(key)
+    }
+}
+
+// Collects the encrypted fields of a JSON schema, inheriting `encryptMetadata` from enclosing
+// schemas.
+fn parse_schema(
+    schema: &bson::Document,
+    prefix: &[String],
+    key_id: Option<&Vec<u8>>,
+    algorithm: Option<Algorithm>,
+    fields: &mut Vec<EncryptedField>,
+) -> Result<()> {
+    let (key_id, algorithm) = match schema.get_document("encryptMetadata") {
+        Ok(metadata) => {
+            let (id, alg) = parse_encrypt(metadata)?;
+            (id.or_else(|| key_id.cloned()), alg.or(algorithm))
+        }
+        Err(_) => (key_id.cloned(), algorithm),
+    };
+
+    let properties = match schema.get_document("properties") {
+        Ok(properties) => properties,
+        Err(_) => return Ok(()),
+    };
+
+    for (name, property) in properties {
+        let property = match *property {
+            Bson::Document(ref property) => property,
+            _ => continue,
+        };
+
+        let mut path = prefix.to_vec();
+        path.push(name.clone());
+
+        if let Ok(encrypt) = property.get_document("encrypt") {
+            let (id, alg) = parse_encrypt(encrypt)?;
+
+            match (id.or_else(|| key_id.clone()), alg.or(algorithm)) {
+                (Some(key_id), Some(algorithm)) => {
+                    fields.push(EncryptedField { path, key_id, algorithm });
+                }
+                _ => {
+                    return Err(ArgumentError(format!(
+                        "The encrypted field '{}' needs a keyId and an algorithm.",
+                        path.join(".")
+                    )))
+                }
+            }
+        } else {
+            parse_schema(property, &path, key_id.as_ref(), algorithm, fields)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Reads the key id and algorithm of an `encrypt` or `encryptMetadata` schema.
+fn parse_encrypt(encrypt: &bson::Document) -> Result<(Option<Vec<u8>>, Option<Algorithm>)> {
+    let key_id = match encrypt.get("keyId") {
+        Some(Bson::Array(ids)) => {
+            match ids.first() {
+                Some(Bson::Binary(BinarySubtype::Uuid, id)) if ids.len() == 1 => {
+                    Some(id.clone())
+                }
+                _ => {
+                    return Err(ArgumentError(String::from(
+                        "A schema's keyId must be an array of one UUID.",
+                    )))
+                }
+            }
+        }
+        Some(_) => {
+            return Err(ArgumentError(String::from(
+                "Key ids given by JSON pointers are not supported.",
+            )))
+        }
+        None => None,
+    };
+
+    let algorithm = match encrypt.get_str("algorithm") {
+        Ok(name) => {
+            Some(Algorithm::from_name(name).ok_or_else(|| {
+                ArgumentError(format!("Unknown encryption algorithm '{}'.", name))
+            })?)
+        }
+        Err(_) => None,
+    };
+
+    Ok((key_id, algorithm))
+}
+
+// Returns an encrypted field that `value`, part of a query expression or aggregation stage, refers
+// to by a `$`-prefixed field path or a field name, if any.
+fn find_reference<'a>(fields: &'a [EncryptedField], value: &Bson) -> Option<&'a EncryptedField> {
+    match *value {
+        Bson::String(ref path) if path.starts_with('$') && !path.starts_with("$$") => {
+            find_field(fields, &path[1..])
+        }
+        Bson::Array(ref values) => values.iter().filter_map(|v| find_reference(fields, v)).next(),
+        Bson::Document(ref doc) => find_document_reference(fields, doc),
+        _ => None,
+    }
+}
+
+fn find_document_reference<'a>(
+    fields: &'a [EncryptedField],
+    doc: &bson::Document,
+) -> Option<&'a EncryptedField> {
+    doc.iter()
+        .filter_map(|(key, value)| {
+            if key.starts_with('$') {
+                find_reference(fields, value)
+            } else {
+                find_field(fields, key).or_else(|| find_reference(fields, value))
+            }
+        })
+        .next()
+}
+
+// Returns an encrypted field at, below or above the dotted `path`, if any.
+fn find_field<'a>(fields: &'a [EncryptedField], path: &str) -> Option<&'a EncryptedField> {
+    let segments: Vec<&str> = path.split('.').collect();
+    fields.iter().find(|field| field.is_under(&segments) || field.is_above(&segments))
+}
+
+fn random_query_error(field: &EncryptedField) -> ::Error {
+    EncryptionError(format!(
+        "Cannot query on the randomly encrypted field '{}'.",
+        field.name()
+    ))
+}
+
+fn split_namespace(namespace: &str) -> Option<(&str, &str)> {
+    match namespace.find('.') {
+        Some(index) if index > 0 && index + 1 < namespace.len() => {
+            Some((&namespace[..index], &namespace[index + 1..]))
+        }
+        _ => None,
+    }
+}
+
+// Encodes a value as its BSON element type and the bytes that follow the element's name.
+fn value_to_bytes(value: &Bson) -> Result<(u8, Vec<u8>)> {
+    let mut doc = bson::Document::new();
+    doc.insert("", value.clone());
+
+    let mut buffer = Vec::new();
+    bson::encode_document(&mut buffer, &doc)?;
+
+    // The document's length, the element type and the empty name precede the value, and the
+    // document's terminator follows it.
+    Ok((buffer[4], buffer[6..buffer.len() - 1].to_vec()))
+}
+
+fn value_from_bytes(element_type: u8, bytes: &[u8]) -> Result<Bson> {
+    let mut buffer = Vec::with_capacity(bytes.len() + 7);
+    buffer.write_i32::<LittleEndian>(bytes.len() as i32 + 7)?;
+    buffer.push(element_type);
+    buffer.push(0);
+    buffer.extend_from_slice(bytes);
+    buffer.push(0);
+
+    let mut doc = bson::decode_document(&mut &buffer[..])?;
+    doc.remove("").ok_or_else(|| EncryptionError(String::from("Invalid decrypted value.")))
+}
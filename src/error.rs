@@ -1,8 +1,10 @@
 //! MongoDB Errors and Error Codes.
-use bson::{self, oid};
-use coll::error::{WriteException, BulkWriteException};
+use bson::{self, Bson, oid};
+use coll::error::{WriteException, BulkWriteException, WriteConcernError};
+use common::ReadPreference;
 use data_encoding;
 use std::{error, fmt, io, result, sync};
+use topology::TopologySnapshot;
 
 /// A type for results generated by MongoDB related functions, where the Err type is
 /// `mongodb::Error`.
@@ -32,6 +34,233 @@ impl fmt::Display for MaliciousServerErrorType {
     }
 }
 
+/// A limit on the results gathered by `Cursor::collect_typed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CollectLimit {
+    /// The maximum number of documents.
+    Documents(usize),
+    /// The maximum total size of the documents, as encoded in BSON.
+    Bytes(usize),
+}
+
+impl CollectLimit {
+    fn to_str(self) -> &'static str {
+        match self {
+            CollectLimit::Documents(_) => "The cursor returned more documents than allowed",
+            CollectLimit::Bytes(_) => "The cursor returned more bytes of documents than allowed",
+        }
+    }
+}
+
+impl fmt::Display for CollectLimit {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CollectLimit::Documents(max) => {
+                write!(fmt, "The cursor returned more than {} documents", max)
+            }
+            CollectLimit::Bytes(max) => {
+                write!(fmt, "The cursor returned more than {} bytes of documents", max)
+            }
+        }
+    }
+}
+
+/// A limit on the documents read from the server, set in `DecodeLimits`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DecodeLimit {
+    /// The deepest nesting of documents and arrays allowed.
+    Depth(usize),
+    /// The most elements allowed in an array.
+    ArrayLength(usize),
+}
+
+impl DecodeLimit {
+    fn to_str(self) -> &'static str {
+        match self {
+            DecodeLimit::Depth(_) => "The server returned a document nested deeper than allowed",
+            DecodeLimit::ArrayLength(_) => "The server returned an array longer than allowed",
+        }
+    }
+}
+
+impl fmt::Display for DecodeLimit {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeLimit::Depth(max) => {
+                write!(fmt, "The server returned a document nested more than {} levels deep", max)
+            }
+            DecodeLimit::ArrayLength(max) => {
+                write!(fmt, "The server returned an array of more than {} elements", max)
+            }
+        }
+    }
+}
+
+/// The error label servers attach to errors after which a write may be safely retried.
+pub const RETRYABLE_WRITE_ERROR: &str = "RetryableWriteError";
+/// The error label for errors after which a whole transaction may be retried.
+pub const TRANSIENT_TRANSACTION_ERROR: &str = "TransientTransactionError";
+/// The error label for errors after which the outcome of a commit is unknown.
+pub const UNKNOWN_TRANSACTION_COMMIT_RESULT: &str = "UnknownTransactionCommitResult";
+
+// Codes after which a write may be retried when the server does not label its errors, which
+// is the case before MongoDB 4.4.
+const RETRYABLE_WRITE_CODES: [i32; 12] = [
+    6, 7, 89, 91, 189, 262, 9001, 10107, 11600, 11602, 13435, 13436,
+];
+
+/// An error that the server reported in reply to a command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerError {
+    pub code: i32,
+    /// The name of the error code, which servers report as of MongoDB 3.4.
+    pub code_name: String,
+    pub message: String,
+    /// Labels classifying the error, such as `RetryableWriteError`.
+    pub labels: Vec<String>,
+    /// Additional information reported by the server, such as the rules a document failed
+    /// validation against as of MongoDB 5.0.
+    pub err_info: Option<bson::Document>,
+}
+
+impl ServerError {
+    /// Parses a command reply into a ServerError, if the reply reports one.
+    pub fn parse(reply: &bson::Document) -> Option<ServerError> {
+        match (reply.get("code"), reply.get("errmsg")) {
+            (Some(&Bson::I32(code)), Some(&Bson::String(ref message))) => {
+                Some(ServerError {
+                    code,
+                    code_name: reply.get_str("codeName").unwrap_or_default().to_owned(),
+                    message: message.to_owned(),
+                    labels: parse_labels(reply),
+                    err_info: reply.get_document("errInfo").ok().cloned(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the server attached the given label to this error.
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|l| l == label)
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.code_name.is_empty() {
+            write!(fmt, "{} (code {})", self.message, self.code)
+        } else {
+            write!(fmt, "{} (code {}, {})", self.message, self.code, self.code_name)
+        }
+    }
+}
+
+impl error::Error for ServerError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+/// A command that failed to reach the server or to receive a readable reply, along with where
+/// and when it was sent.
+#[derive(Debug)]
+pub struct CommandError {
+    /// The address of the server the command was sent to, as `host:port`.
+    pub host: String,
+    pub command_name: String,
+    pub request_id: i64,
+    pub operation_id: i64,
+    /// How long the command ran before failing, in nanoseconds.
+    pub duration: u64,
+    pub cause: Box<Error>,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} on {} (request {}, operation {}) failed after {} ms: {}",
+            self.command_name,
+            self.host,
+            self.request_id,
+            self.operation_id,
+            self.duration / 1_000_000,
+            self.cause
+        )
+    }
+}
+
+impl error::Error for CommandError {
+    fn description(&self) -> &str {
+        "Failed to execute command"
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        Some(&*self.cause)
+    }
+}
+
+/// No suitable server was found before server selection timed out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerSelectionError {
+    /// Why the last attempt to select a server failed.
+    pub message: String,
+    /// The read preference servers were selected by, or `None` for a write, which needs the
+    /// primary.
+    pub read_preference: Option<ReadPreference>,
+    /// The deployment as monitoring saw it when selection gave up.
+    pub topology: TopologySnapshot,
+}
+
+impl fmt::Display for ServerSelectionError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.read_preference {
+            Some(ref read_preference) => write!(
+                fmt,
+                "{} (read preference {}; topology {})",
+                self.message,
+                read_preference.to_document(),
+                self.topology
+            ),
+            None => write!(fmt, "{} (write; topology {})", self.message, self.topology),
+        }
+    }
+}
+
+impl error::Error for ServerSelectionError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Reads the `errorLabels` array of a server reply.
+pub fn parse_labels(reply: &bson::Document) -> Vec<String> {
+    match reply.get("errorLabels") {
+        Some(&Bson::Array(ref labels)) => {
+            labels
+                .iter()
+                .filter_map(|label| match *label {
+                    Bson::String(ref label) => Some(label.to_owned()),
+                    _ => None,
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// What an application may do after an operation fails, as reported by `Error::retry_hint`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RetryHint {
+    /// The operation may be retried as is.
+    RetryOperation,
+    /// The transaction the operation ran in must be aborted and retried from the start.
+    RetryTransaction,
+    /// Retrying the operation unchanged would fail the same way.
+    DoNotRetry,
+}
+
 /// The error type for MongoDB operations.
 #[derive(Debug)]
 pub enum Error {
@@ -55,12 +284,30 @@ pub enum Error {
     OperationError(String),
     /// A database operation returned an invalid reply.
     ResponseError(String),
+    /// An operation did not complete within its client-side timeout.
+    Timeout(String),
+    /// The connected deployment does not support the requested feature.
+    Unsupported(String),
+    /// A value could not be encrypted or decrypted client-side, or a data key could not be
+    /// obtained from the key vault or a key management service.
+    EncryptionError(String),
     /// A cursor operation failed to return a cursor.
     CursorNotFoundError,
     /// The application failed to secure a mutex due to a poisoned lock.
     PoisonLockError,
     /// A server error with a given code.
     CodedError(ErrorCode),
+    /// A command failed on the server.
+    ServerError(ServerError),
+    /// A `findAndModify` conflicted with a concurrent write to the same document.
+    WriteConflict(ServerError),
+    /// A `findAndModify` would have left a document that fails the collection's validator.
+    DocumentValidationFailure(ServerError),
+    /// No suitable server was found before server selection timed out.
+    ServerSelectionError(ServerSelectionError),
+    /// A command could not be sent or its reply could not be read. Errors reported by the
+    /// server and client-side timeouts are returned as is rather than wrapped.
+    CommandError(CommandError),
     /// The client was unable to emit the events to the listeners due to a poisoned lock;
     /// all event listeners were dropped, so they will have to be registered again. If the
     /// client is unable to emit a failure result, the error it failed to report is bundled
@@ -69,6 +316,10 @@ pub enum Error {
     /// The server that the client is attempting to authenticate to does not actually have
     /// the user's authentication information stored.
     MaliciousServerError(MaliciousServerErrorType),
+    /// A cursor returned more results than `Cursor::collect_typed` was allowed to gather.
+    CollectLimitExceeded(CollectLimit),
+    /// A document read from the server exceeded the client's `DecodeLimits`.
+    DecodeLimitExceeded(DecodeLimit),
     /// A standard error with a string description;
     /// a more specific error should generally be used.
     DefaultError(String),
@@ -140,6 +391,110 @@ impl<T> From<sync::PoisonError<T>> for Error {
     }
 }
 
+impl Error {
+    /// Whether the operation failed because it would have duplicated a unique key, so that a
+    /// retried insert can be treated as already applied.
+    pub fn is_duplicate_key(&self) -> bool {
+        match *self {
+            Error::WriteError(ref exception) => exception.is_duplicate_key(),
+            Error::BulkWriteError(ref exception) => exception.is_duplicate_key(),
+            Error::CodedError(code) => code == ErrorCode::DuplicateKey,
+            _ => false,
+        }
+    }
+
+    /// Returns the labels the server attached to this error.
+    pub fn labels(&self) -> &[String] {
+        match *self {
+            Error::ServerError(ref err) |
+            Error::WriteConflict(ref err) |
+            Error::DocumentValidationFailure(ref err) => &err.labels,
+            Error::WriteError(WriteException { write_concern_error: Some(ref err), .. }) |
+            Error::BulkWriteError(BulkWriteException {
+                write_concern_error: Some(ref err), ..
+            }) => &err.labels,
+            _ => &[],
+        }
+    }
+
+    /// Whether the server attached the given label to this error.
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels().iter().any(|l| l == label)
+    }
+
+    /// Whether the error came from the connection to the server rather than from the server.
+    pub fn is_network_error(&self) -> bool {
+        match *self {
+            Error::IoError(_) => true,
+            Error::CommandError(ref err) => err.cause.is_network_error(),
+            _ => false,
+        }
+    }
+
+    /// Whether a write that failed with this error may be retried without being applied twice.
+    ///
+    /// Servers running MongoDB 4.4 or later label such errors; for older servers, this falls
+    /// back to the error codes that indicate a primary stepdown or shutdown.
+    pub fn is_retryable_write(&self) -> bool {
+        if self.has_label(RETRYABLE_WRITE_ERROR) || self.is_network_error() {
+            return true;
+        }
+
+        let code = match *self {
+            Error::ServerError(ref err) |
+            Error::WriteConflict(ref err) if err.labels.is_empty() => err.code,
+            Error::CodedError(code) => code as i32,
+            Error::WriteError(WriteException { write_concern_error: Some(ref err), .. }) |
+            Error::BulkWriteError(BulkWriteException {
+                write_concern_error: Some(ref err), ..
+            }) if err.labels.is_empty() => err.code,
+            _ => return false,
+        };
+
+        RETRYABLE_WRITE_CODES.contains(&code)
+    }
+
+    /// Whether the transaction this error occurred in may be retried from the start.
+    pub fn is_transient_transaction_error(&self) -> bool {
+        self.has_label(TRANSIENT_TRANSACTION_ERROR)
+    }
+
+    /// Returns what the application may do after this error.
+    ///
+    /// Errors labelled as transient within a transaction call for retrying the transaction.
+    /// Write conflicts and retryable write errors outside one call for retrying the operation,
+    /// such as the next iteration of an optimistic concurrency loop. Anything else, including
+    /// document validation failures, is not worth retrying unchanged.
+    pub fn retry_hint(&self) -> RetryHint {
+        if self.is_transient_transaction_error() {
+            RetryHint::RetryTransaction
+        } else if self.is_write_conflict() || self.is_retryable_write() {
+            RetryHint::RetryOperation
+        } else {
+            RetryHint::DoNotRetry
+        }
+    }
+
+    /// Whether the operation conflicted with a concurrent write to the same document.
+    pub fn is_write_conflict(&self) -> bool {
+        match *self {
+            Error::WriteConflict(_) => true,
+            Error::ServerError(ref err) => err.code == ErrorCode::WriteConflict as i32,
+            Error::CodedError(code) => code == ErrorCode::WriteConflict,
+            _ => false,
+        }
+    }
+
+    /// Returns the write concern error carried by a failed write, if any.
+    pub fn write_concern_error(&self) -> Option<&WriteConcernError> {
+        match *self {
+            Error::WriteError(ref exception) => exception.write_concern_error.as_ref(),
+            Error::BulkWriteError(ref exception) => exception.write_concern_error.as_ref(),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -153,9 +508,25 @@ impl fmt::Display for Error {
             Error::ArgumentError(ref inner) => inner.fmt(fmt),
             Error::OperationError(ref inner) => inner.fmt(fmt),
             Error::ResponseError(ref inner) => inner.fmt(fmt),
+            Error::Timeout(ref inner) => inner.fmt(fmt),
+            Error::Unsupported(ref inner) => inner.fmt(fmt),
+            Error::EncryptionError(ref inner) => inner.fmt(fmt),
             Error::CursorNotFoundError => fmt.write_str("No cursor found for cursor operation."),
             Error::PoisonLockError => fmt.write_str("Socket lock poisoned while attempting to access."),
+            Error::CodedError(ErrorCode::QueryExceededMemoryLimitNoDiskUseAllowed) => {
+                write!(
+                    fmt,
+                    "{}: the operation exceeded the server's memory limit; set allow_disk_use \
+                     or add an index that supports the sort",
+                    ErrorCode::QueryExceededMemoryLimitNoDiskUseAllowed
+                )
+            }
             Error::CodedError(ref err) => write!(fmt, "{}", err),
+            Error::ServerError(ref inner) |
+            Error::WriteConflict(ref inner) |
+            Error::DocumentValidationFailure(ref inner) => inner.fmt(fmt),
+            Error::CommandError(ref inner) => inner.fmt(fmt),
+            Error::ServerSelectionError(ref inner) => inner.fmt(fmt),
             Error::EventListenerError(ref err) => {
                 match *err {
                     Some(ref e) => {
@@ -169,6 +540,8 @@ impl fmt::Display for Error {
                 }
             }
             Error::MaliciousServerError(ref err) => write!(fmt, "{}", err),
+            Error::CollectLimitExceeded(ref limit) => write!(fmt, "{}", limit),
+            Error::DecodeLimitExceeded(ref limit) => write!(fmt, "{}", limit),
             Error::DefaultError(ref inner) => inner.fmt(fmt),
         }
     }
@@ -187,6 +560,11 @@ impl error::Error for Error {
             Error::CursorNotFoundError => "No cursor found for cursor operation.",
             Error::PoisonLockError => "Socket lock poisoned while attempting to access.",
             Error::CodedError(ref err) => err.to_str(),
+            Error::ServerError(ref inner) |
+            Error::WriteConflict(ref inner) |
+            Error::DocumentValidationFailure(ref inner) => &inner.message,
+            Error::CommandError(_) => "Failed to execute command",
+            Error::ServerSelectionError(ref inner) => &inner.message,
             Error::EventListenerError(ref err) => {
                 match *err {
                     Some(_) => "Due to a poisoned lock on the listeners, unable to emit failure",
@@ -194,9 +572,14 @@ impl error::Error for Error {
                 }
             }
             Error::MaliciousServerError(err) => err.to_str(),
+            Error::CollectLimitExceeded(limit) => limit.to_str(),
+            Error::DecodeLimitExceeded(limit) => limit.to_str(),
             Error::ArgumentError(ref inner) |
             Error::OperationError(ref inner) |
             Error::ResponseError(ref inner) |
+            Error::Timeout(ref inner) |
+            Error::Unsupported(ref inner) |
+            Error::EncryptionError(ref inner) |
             Error::DefaultError(ref inner) => inner,
         }
     }
@@ -210,14 +593,24 @@ impl error::Error for Error {
             Error::OIDError(ref inner) => Some(inner),
             Error::FromHexError(ref inner) => Some(inner),
             Error::IoError(ref inner) => Some(inner),
+            Error::ServerError(ref inner) |
+            Error::WriteConflict(ref inner) |
+            Error::DocumentValidationFailure(ref inner) => Some(inner),
+            Error::CommandError(ref inner) => Some(&*inner.cause),
+            Error::ServerSelectionError(ref inner) => Some(inner),
             Error::ArgumentError(_) |
             Error::OperationError(_) |
             Error::ResponseError(_) |
+            Error::Timeout(_) |
+            Error::Unsupported(_) |
+            Error::EncryptionError(_) |
             Error::CursorNotFoundError |
             Error::PoisonLockError |
             Error::CodedError(_) |
             Error::EventListenerError(_) |
             Error::MaliciousServerError(_) |
+            Error::CollectLimitExceeded(_) |
+            Error::DecodeLimitExceeded(_) |
             Error::DefaultError(_) => None,
         }
     }
@@ -365,6 +758,9 @@ pub enum ErrorCode {
     IncompatibleShardingConfigVersion = 137,
     RemoteOplogStale = 138,
     JSInterpreterFailure = 139,
+    /// A blocking sort or group exceeded the server's memory limit. Set `allow_disk_use` to let
+    /// the server spill to disk, or add an index that supports the sort.
+    QueryExceededMemoryLimitNoDiskUseAllowed = 292,
     NotMaster = 10107,
     DuplicateKey = 11000,
     InterruptedAtShutdown = 11600,
@@ -544,6 +940,9 @@ impl ErrorCode {
             ErrorCode::IncompatibleShardingConfigVersion => "IncompatibleShardingConfigVersion",
             ErrorCode::RemoteOplogStale => "RemoteOplogStale",
             ErrorCode::JSInterpreterFailure => "JSInterpreterFailure",
+            ErrorCode::QueryExceededMemoryLimitNoDiskUseAllowed => {
+                "QueryExceededMemoryLimitNoDiskUseAllowed"
+            }
             ErrorCode::NotMaster => "NotMaster",
             ErrorCode::DuplicateKey => "DuplicateKey",
             ErrorCode::InterruptedAtShutdown => "InterruptedAtShutdown",
@@ -0,0 +1,330 @@
+//! Extended JSON v2.
+//!
+//! Converts documents to and from MongoDB Extended JSON, which writes BSON types JSON lacks as
+//! objects such as `{ "$oid": "..." }` and `{ "$date": ... }`. Canonical mode keeps every type,
+//! including the width of numbers, so documents round-trip exactly; relaxed mode writes numbers
+//! as plain JSON numbers and dates as ISO-8601 strings, for output meant to be read by people.
+//!
+//! Parsing accepts either mode, and reads plain JSON numbers as 32-bit integers if they fit,
+//! otherwise 64-bit integers or doubles. Unlike `Bson::from(serde_json::Value)`, it keeps
+//! integer widths, binary subtypes and timestamps intact.
+//!
+//! ```
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::ext_json::{self, Mode};
+//! #
+//! # fn main() {
+//! let doc = doc! { "n": 1i64, "ratio": 0.5 };
+//! let canonical = ext_json::to_string(&doc, Mode::Canonical);
+//! assert_eq!(r#"{"n":{"$numberLong":"1"},"ratio":{"$numberDouble":"0.5"}}"#, canonical);
+//! assert_eq!(r#"{"n":1,"ratio":0.5}"#, ext_json::to_string(&doc, Mode::Relaxed));
+//! assert_eq!(doc, ext_json::from_str(&canonical).unwrap());
+//! # }
+//! ```
+//!
+//! The bson crate has no MinKey, MaxKey, undefined or DBPointer values, so their extended JSON
+//! forms cannot be parsed.
+use bson::{self, Bson};
+use bson::oid::ObjectId;
+use bson::spec::BinarySubtype;
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use data_encoding::{BASE64, HEXLOWER_PERMISSIVE};
+use serde_json::{self, Map, Number, Value};
+
+#[cfg(feature = "decimal128")]
+use decimal;
+use Error::ArgumentError;
+use Result;
+
+/// How to write numbers and dates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// Every value keeps its type, so that documents round-trip exactly.
+    Canonical,
+    /// Numbers are plain JSON numbers and dates ISO-8601 strings, where that loses nothing a
+    /// reader would notice.
+    Relaxed,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Relaxed
+    }
+}
+
+/// Converts a document to an extended JSON string.
+pub fn to_string(doc: &bson::Document, mode: Mode) -> String {
+    document_to_value(doc, mode).to_string()
+}
+
+/// Converts a document to an extended JSON object.
+pub fn document_to_value(doc: &bson::Document, mode: Mode) -> Value {
+    let map = doc.iter().map(|(key, value)| (key.clone(), to_value(value, mode))).collect();
+    Value::Object(map)
+}
+
+/// Converts a value to extended JSON.
+pub fn to_value(bson: &Bson, mode: Mode) -> Value {
+    let relaxed = mode == Mode::Relaxed;
+
+    match *bson {
+        Bson::FloatingPoint(f) => {
+            match Number::from_f64(f) {
+                Some(ref number) if relaxed => Value::Number(number.clone()),
+                _ => wrap("$numberDouble", Value::String(double_string(f))),
+            }
+        }
+        Bson::String(ref s) => Value::String(s.clone()),
+        Bson::Array(ref values) => {
+            Value::Array(values.iter().map(|value| to_value(value, mode)).collect())
+        }
+        Bson::Document(ref doc) => document_to_value(doc, mode),
+        Bson::Boolean(b) => Value::Bool(b),
+        Bson::Null => Value::Null,
+        Bson::RegExp(ref pattern, ref options) => {
+            let mut options: Vec<char> = options.chars().collect();
+            options.sort();
+
+            let mut regex = Map::new();
+            regex.insert(String::from("pattern"), Value::String(pattern.clone()));
+            regex.insert(String::from("options"), Value::String(options.into_iter().collect()));
+            wrap("$regularExpression", Value::Object(regex))
+        }
+        Bson::JavaScriptCode(ref code) => wrap("$code", Value::String(code.clone())),
+        Bson::JavaScriptCodeWithScope(ref code, ref scope) => {
+            let mut map = Map::new();
+            map.insert(String::from("$code"), Value::String(code.clone()));
+            map.insert(String::from("$scope"), document_to_value(scope, mode));
+            Value::Object(map)
+        }
+        Bson::I32(n) if relaxed => Value::from(n),
+        Bson::I32(n) => wrap("$numberInt", Value::String(n.to_string())),
+        Bson::I64(n) if relaxed => Value::from(n),
+        Bson::I64(n) => wrap("$numberLong", Value::String(n.to_string())),
+        Bson::TimeStamp(ts) => {
+            let mut timestamp = Map::new();
+            timestamp.insert(String::from("t"), Value::from((ts as u64) >> 32));
+            timestamp.insert(String::from("i"), Value::from((ts as u64) & 0xffff_ffff));
+            wrap("$timestamp", Value::Object(timestamp))
+        }
+        Bson::Binary(subtype, ref bytes) => {
+            let mut binary = Map::new();
+            binary.insert(String::from("base64"), Value::String(BASE64.encode(bytes)));
+            binary.insert(
+                String::from("subType"),
+                Value::String(format!("{:02x}", u8::from(subtype))),
+            );
+            wrap("$binary", Value::Object(binary))
+        }
+        Bson::ObjectId(ref id) => wrap("$oid", Value::String(id.to_hex())),
+        Bson::UtcDatetime(ref date) => {
+            if relaxed && date.year() >= 1970 && date.year() <= 9999 {
+                let iso = date.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+                wrap("$date", Value::String(iso))
+            } else {
+                let millis = Value::String(date.timestamp_millis().to_string());
+                wrap("$date", wrap("$numberLong", millis))
+            }
+        }
+        Bson::Symbol(ref symbol) => wrap("$symbol", Value::String(symbol.clone())),
+        #[cfg(feature = "decimal128")]
+        Bson::Decimal128(ref d) => wrap("$numberDecimal", Value::String(d.to_string())),
+    }
+}
+
+/// Parses an extended JSON string holding a document.
+pub fn from_str(s: &str) -> Result<bson::Document> {
+    let value = serde_json::from_str(s).map_err(|err| invalid(&err.to_string()))?;
+    document_from_value(value)
+}
+
+/// Parses an extended JSON object into a document.
+pub fn document_from_value(value: Value) -> Result<bson::Document> {
+    match from_value(value)? {
+        Bson::Document(doc) => Ok(doc),
+        other => Err(invalid(&format!("expected a document, found {}", other))),
+    }
+}
+
+/// Parses an extended JSON value.
+pub fn from_value(value: Value) -> Result<Bson> {
+    match value {
+        Value::Null => Ok(Bson::Null),
+        Value::Bool(b) => Ok(Bson::Boolean(b)),
+        Value::Number(n) => Ok(number(&n)),
+        Value::String(s) => Ok(Bson::String(s)),
+        Value::Array(values) => {
+            values.into_iter().map(from_value).collect::<Result<_>>().map(Bson::Array)
+        }
+        Value::Object(map) => object(map),
+    }
+}
+
+fn wrap(key: &str, value: Value) -> Value {
+    let mut map = Map::new();
+    map.insert(String::from(key), value);
+    Value::Object(map)
+}
+
+fn double_string(f: f64) -> String {
+    if f.is_nan() {
+        String::from("NaN")
+    } else if f.is_infinite() {
+        String::from(if f > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        format!("{:?}", f)
+    }
+}
+
+fn invalid(reason: &str) -> ::Error {
+    ArgumentError(format!("Invalid extended JSON: {}.", reason))
+}
+
+fn number(n: &Number) -> Bson {
+    match n.as_i64() {
+        Some(n) if n >= i64::from(i32::MIN) && n <= i64::from(i32::MAX) => Bson::I32(n as i32),
+        Some(n) => Bson::I64(n),
+        None => Bson::FloatingPoint(n.as_f64().unwrap_or(f64::NAN)),
+    }
+}
+
+// Parses an object, which is either a type wrapper such as `{ "$oid": ... }` or a document.
+fn object(mut map: Map<String, Value>) -> Result<Bson> {
+    let key = match map.keys().next() {
+        Some(key) if key.starts_with('$') => key.clone(),
+        _ => return document(map),
+    };
+
+    let value = match (key.as_str(), map.len()) {
+        ("$code", 2) if map.contains_key("$scope") => {
+            let code = string(map.remove("$code"), "$code")?;
+            let scope = document_from_value(map.remove("$scope").unwrap_or(Value::Null))?;
+            return Ok(Bson::JavaScriptCodeWithScope(code, scope));
+        }
+        (_, 1) => map.remove(&key).unwrap_or(Value::Null),
+        _ => return document(map),
+    };
+
+    match key.as_str() {
+        "$oid" => {
+            let hex = string(Some(value), "$oid")?;
+            ObjectId::with_string(&hex).map(Bson::ObjectId).map_err(|_| invalid("bad $oid"))
+        }
+        "$symbol" => string(Some(value), "$symbol").map(Bson::Symbol),
+        "$code" => string(Some(value), "$code").map(Bson::JavaScriptCode),
+        "$numberInt" => {
+            let s = string(Some(value), "$numberInt")?;
+            s.parse().map(Bson::I32).map_err(|_| invalid("bad $numberInt"))
+        }
+        "$numberLong" => {
+            let s = string(Some(value), "$numberLong")?;
+            s.parse().map(Bson::I64).map_err(|_| invalid("bad $numberLong"))
+        }
+        "$numberDouble" => {
+            let f = match string(Some(value), "$numberDouble")?.as_str() {
+                "NaN" => f64::NAN,
+                "Infinity" => f64::INFINITY,
+                "-Infinity" => f64::NEG_INFINITY,
+                s => s.parse().map_err(|_| invalid("bad $numberDouble"))?,
+            };
+            Ok(Bson::FloatingPoint(f))
+        }
+        #[cfg(feature = "decimal128")]
+        "$numberDecimal" => {
+            let s = string(Some(value), "$numberDecimal")?;
+            decimal::parse(&s).map(Bson::Decimal128).map_err(|_| invalid("bad $numberDecimal"))
+        }
+        "$binary" => binary(value),
+        "$uuid" => {
+            let hex = string(Some(value), "$uuid")?.replace('-', "");
+            match HEXLOWER_PERMISSIVE.decode(hex.as_bytes()) {
+                Ok(ref bytes) if bytes.len() == 16 => {
+                    Ok(Bson::Binary(BinarySubtype::Uuid, bytes.clone()))
+                }
+                _ => Err(invalid("bad $uuid")),
+            }
+        }
+        "$date" => date(value),
+        "$timestamp" => {
+            let mut fields = fields(value, "$timestamp")?;
+            let t = unsigned(fields.remove("t"), "$timestamp")?;
+            let i = unsigned(fields.remove("i"), "$timestamp")?;
+            Ok(Bson::TimeStamp(((t << 32) | i) as i64))
+        }
+        "$regularExpression" => {
+            let mut fields = fields(value, "$regularExpression")?;
+            let pattern = string(fields.remove("pattern"), "$regularExpression")?;
+            let options = string(fields.remove("options"), "$regularExpression")?;
+            Ok(Bson::RegExp(pattern, options))
+        }
+        "$minKey" | "$maxKey" | "$undefined" | "$dbPointer" => {
+            Err(invalid(&format!("{} values are not supported", key)))
+        }
+        _ => {
+            map.insert(key, value);
+            document(map)
+        }
+    }
+}
+
+fn document(map: Map<String, Value>) -> Result<Bson> {
+    let mut doc = bson::Document::new();
+    for (key, value) in map {
+        doc.insert(key, from_value(value)?);
+    }
+
+    Ok(Bson::Document(doc))
+}
+
+fn string(value: Option<Value>, key: &str) -> Result<String> {
+    match value {
+        Some(Value::String(s)) => Ok(s),
+        _ => Err(invalid(&format!("{} needs a string", key))),
+    }
+}
+
+fn unsigned(value: Option<Value>, key: &str) -> Result<u64> {
+    match value.as_ref().and_then(Value::as_u64) {
+        Some(n) if n <= u64::from(u32::MAX) => Ok(n),
+        _ => Err(invalid(&format!("{} needs 32-bit unsigned integers", key))),
+    }
+}
+
+fn fields(value: Value, key: &str) -> Result<Map<String, Value>> {
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => Err(invalid(&format!("{} needs an object", key))),
+    }
+}
+
+fn binary(value: Value) -> Result<Bson> {
+    let mut fields = fields(value, "$binary")?;
+    let base64 = string(fields.remove("base64"), "$binary")?;
+    let subtype = string(fields.remove("subType"), "$binary")?;
+
+    let bytes = BASE64.decode(base64.as_bytes()).map_err(|_| invalid("bad $binary base64"))?;
+    let subtype = u8::from_str_radix(&subtype, 16).map_err(|_| invalid("bad $binary subType"))?;
+    Ok(Bson::Binary(BinarySubtype::from(subtype), bytes))
+}
+
+fn date(value: Value) -> Result<Bson> {
+    let millis = match value {
+        Value::String(iso) => {
+            return DateTime::parse_from_rfc3339(&iso)
+                .map(|date| Bson::UtcDatetime(date.with_timezone(&Utc)))
+                .map_err(|_| invalid("bad $date"));
+        }
+        Value::Object(mut map) => {
+            let s = string(map.remove("$numberLong"), "$date")?;
+            s.parse::<i64>().map_err(|_| invalid("bad $date"))?
+        }
+        Value::Number(ref n) => n.as_i64().ok_or_else(|| invalid("bad $date"))?,
+        _ => return Err(invalid("bad $date")),
+    };
+
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map(Bson::UtcDatetime)
+        .ok_or_else(|| invalid("$date out of range"))
+}
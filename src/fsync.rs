@@ -0,0 +1,142 @@
+//! Quiescing writes for filesystem snapshot backups.
+//!
+//! `ThreadedClient::fsync_lock` flushes pending writes to disk and blocks further writes on the
+//! server until the returned guard is unlocked or dropped, so a filesystem or volume snapshot
+//! taken in between is consistent. Reads continue to be served while the server is locked.
+//!
+//! The lock is taken on the primary the client selects, and released on that same server even if
+//! another member has become primary since. To back up a secondary, which is the usual practice,
+//! connect a client directly to that member. Locks nest: a server locked several
+//! times accepts writes again only after as many unlocks, which `FsyncLockGuard::unlock` reports.
+//!
+//! ```no_run
+//! # use mongodb::{Client, ThreadedClient};
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let guard = client.fsync_lock().unwrap();
+//! // ... take the filesystem snapshot ...
+//! guard.unlock().unwrap();
+//! ```
+use bson::{self, Bson, doc};
+
+use command_type::CommandType;
+use common::{ReadMode, ReadPreference};
+use connstring::Host;
+use coll::options::FindOptions;
+use cursor::Cursor;
+use pool::PooledStream;
+use timeout::Deadline;
+use wire_protocol::flags::OpQueryFlags;
+use Error::OperationError;
+use {Client, Result, ThreadedClient};
+
+/// Keeps a server locked against writes until unlocked or dropped.
+#[derive(Debug)]
+pub struct FsyncLockGuard {
+    client: Client,
+    host: Host,
+    lock_count: Option<i64>,
+    unlocked: bool,
+}
+
+impl FsyncLockGuard {
+    /// Flushes pending writes to disk and locks the primary against writes.
+    pub fn lock(client: &Client) -> Result<FsyncLockGuard> {
+        let deadline = deadline(client);
+        let (mut stream, _, _) = client.acquire_stream_with_deadline(primary(), deadline.clone())?;
+        stream.set_deadline(deadline)?;
+        let host = stream.host().clone();
+        let reply = run_command(client, &mut stream, doc! { "fsync": 1, "lock": true })?;
+
+        Ok(FsyncLockGuard {
+            client: client.clone(),
+            host,
+            lock_count: lock_count(&reply),
+            unlocked: false,
+        })
+    }
+
+    /// The server that was locked.
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    /// The number of locks held on the server after this one was taken, reported by servers
+    /// running 4.2 or later.
+    pub fn lock_count(&self) -> Option<i64> {
+        self.lock_count
+    }
+
+    /// Releases the lock, returning the number of locks still held on the server as reported by
+    /// servers running 4.2 or later. The server accepts writes again once none are held.
+    pub fn unlock(mut self) -> Result<Option<i64>> {
+        self.unlocked = true;
+        self.send_unlock()
+    }
+
+    // Unlocks the server that was locked, which need not be the primary any longer.
+    fn send_unlock(&self) -> Result<Option<i64>> {
+        let deadline = deadline(&self.client);
+        let mut stream = self.client.acquire_stream_on(&self.host, deadline.clone())?;
+        stream.set_deadline(deadline)?;
+        let reply = run_command(&self.client, &mut stream, doc! { "fsyncUnlock": 1 })?;
+
+        Ok(lock_count(&reply))
+    }
+}
+
+impl Drop for FsyncLockGuard {
+    fn drop(&mut self) {
+        // This ignores errors while unlocking; instead, unlock should be used explicitly to
+        // handle errors.
+        if !self.unlocked {
+            let _ = self.send_unlock();
+        }
+    }
+}
+
+fn primary() -> ReadPreference {
+    ReadPreference::new(ReadMode::Primary, None)
+}
+
+fn deadline(client: &Client) -> Deadline {
+    Deadline::after_ms_on(client.clock.clone(), client.timeout_ms)
+}
+
+// Runs an admin command on `stream`, returning its reply.
+fn run_command(
+    client: &Client,
+    stream: &mut PooledStream,
+    spec: bson::Document,
+) -> Result<bson::Document> {
+    let options = FindOptions {
+        batch_size: Some(1),
+        limit: Some(1),
+        ..FindOptions::new()
+    };
+
+    let mut cursor = Cursor::query_with_stream(
+        stream,
+        client.clone(),
+        String::from("admin.$cmd"),
+        OpQueryFlags::empty(),
+        spec.clone(),
+        options,
+        CommandType::RunCommand,
+        false,
+        None,
+    )?;
+
+    match cursor.next() {
+        Some(Ok(reply)) => Ok(reply),
+        Some(Err(err)) => Err(err),
+        None => Err(OperationError(format!("Failed to execute command with spec {:?}.", spec))),
+    }
+}
+
+fn lock_count(reply: &bson::Document) -> Option<i64> {
+    match reply.get("lockCount").cloned() {
+        Some(Bson::I32(count)) => Some(i64::from(count)),
+        Some(Bson::I64(count)) => Some(count),
+        _ => None,
+    }
+}
@@ -11,6 +11,7 @@ use Result;
 
 use super::Store;
 use coll::options::IndexOptions;
+use object_id;
 
 use std::error::Error as ErrorTrait;
 use std::io::Write;
@@ -230,7 +231,7 @@ impl File {
         if self.mode == Mode::Read && self.rcache.is_some() {
             {
                 let cache = self.rcache.as_ref().unwrap();
-                let _ = cache.lock()?;
+                drop(cache.lock()?);
             }
             self.rcache = None;
         }
@@ -253,7 +254,7 @@ impl File {
         vec_buf.extend(buf.iter().cloned());
 
         let document = doc! {
-            "_id": oid::ObjectId::new()?,
+            "_id": object_id::generate(),
             "files_id": self.doc.id.clone(),
             "n": n,
             "data": (BinarySubtype::Generic, vec_buf)
@@ -530,10 +531,10 @@ impl io::Read for File {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.assert_mode(Mode::Read)?;
 
-        let _ = match self.mutex.lock() {
+        drop(match self.mutex.lock() {
             Ok(guard) => guard,
             Err(_) => return Err(io::Error::new(io::ErrorKind::Other, PoisonLockError)),
-        };
+        });
 
         // End of File (EOF)
         if self.offset == self.doc.len {
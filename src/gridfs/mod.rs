@@ -36,6 +36,7 @@ use db::{Database, ThreadedDatabase};
 use coll::Collection;
 use coll::options::FindOptions;
 use cursor::Cursor;
+use object_id;
 use Error::{self, ArgumentError};
 use Result;
 
@@ -142,7 +143,7 @@ impl ThreadedStore for Store {
         Ok(File::with_name(
             self.clone(),
             name,
-            oid::ObjectId::new()?,
+            object_id::generate(),
             Mode::Write,
         ))
     }
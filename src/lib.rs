@@ -131,7 +131,9 @@ extern crate bufstream;
 extern crate byteorder;
 extern crate chrono;
 extern crate data_encoding;
-#[cfg(feature = "ssl")]
+#[cfg(any(feature = "tokio-compat", feature = "stream"))]
+extern crate futures_core;
+#[cfg(any(feature = "ssl", feature = "encryption"))]
 extern crate openssl;
 extern crate rand;
 #[macro_use]
@@ -140,57 +142,121 @@ extern crate semver;
 extern crate serde;
 #[macro_use(Serialize, Deserialize)]
 extern crate serde_derive;
+extern crate serde_json;
 extern crate separator;
 extern crate textnonce;
 extern crate time;
+#[cfg(feature = "tokio-compat")]
+extern crate tokio;
+#[cfg(feature = "trust-dns")]
+extern crate trust_dns_resolver;
 extern crate md5;
+#[cfg(feature = "metrics")]
+extern crate metrics;
 extern crate sha1;
 extern crate hmac;
 extern crate pbkdf2;
 extern crate hex;
 
+#[macro_use]
+mod builder_macros;
+
 pub mod db;
+#[cfg(feature = "tokio-compat")]
+pub mod tokio_compat;
+pub mod cache;
+pub mod clients;
+pub mod clock;
 pub mod coll;
 pub mod common;
+pub mod compat;
+pub mod compare;
 pub mod connstring;
 pub mod cursor;
+#[cfg(feature = "stream")]
+pub mod cursor_stream;
+#[cfg(feature = "decimal128")]
+pub mod decimal;
+pub mod dns;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 pub mod error;
+pub mod ext_json;
+pub mod fsync;
 pub mod gridfs;
+pub mod lock;
+pub mod log_file;
+pub mod migrations;
+pub mod object_id;
+pub mod oplog;
+pub mod outbox;
 pub mod pool;
+pub mod proxy;
+pub mod queue;
+pub mod raw;
+pub mod rollup;
+pub mod sessions;
+pub mod snapshot;
+#[cfg(feature = "spec-test-support")]
+pub mod spec;
+pub mod srv;
 pub mod stream;
+pub mod timeout;
 pub mod topology;
+pub mod trace_context;
+pub mod uuid;
 pub mod wire_protocol;
 
 mod apm;
 mod auth;
 mod command_type;
+#[cfg(feature = "ssl")]
+mod ocsp;
 
 pub use bson::*;
 
-pub use apm::{CommandStarted, CommandResult};
+pub use apm::{CommandStarted, CommandResult, ConnectionEstablished, HookFilter, HookHandle,
+              Operation};
 pub use command_type::CommandType;
 pub use error::{Error, ErrorCode, Result};
 
 use std::fmt;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::ops::DerefMut;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicIsize, Ordering};
 
 use apm::Listener;
-use common::{ReadPreference, ReadMode, WriteConcern};
-use connstring::ConnectionString;
+use clock::{Clock, SystemClock};
+use common::{ReadPreference, ReadMode, WriteConcern, WriteConcernErrorPolicy};
+use connstring::{ConnectionString, Host};
 use db::{Database, ThreadedDatabase};
-use error::Error::ResponseError;
+use db::commands::{BuildInfo, HostInfo, ServerStatus};
+#[cfg(feature = "encryption")]
+use encryption::{AutoEncryptionOptions, Encrypter};
+use error::Error::{ArgumentError, ResponseError, Unsupported};
+use fsync::FsyncLockGuard;
+use log_file::{LogFile, LogRotationOptions};
 use pool::PooledStream;
+use proxy::ProxyOptions;
+use serde::de::DeserializeOwned;
+use sessions::{ListSessionsOptions, Session, SessionOptions, SessionRecord};
+use dns::{CachingResolver, DnsResolver, SystemResolver};
+use snapshot::Snapshot;
+use srv::{SrvResolver, DEFAULT_RESCAN_SRV_INTERVAL_MS};
 use stream::StreamConnector;
-use topology::{Topology, TopologyDescription, TopologyType, DEFAULT_HEARTBEAT_FREQUENCY_MS,
-               DEFAULT_LOCAL_THRESHOLD_MS, DEFAULT_SERVER_SELECTION_TIMEOUT_MS};
+use timeout::Deadline;
+use topology::{Capabilities, ReplicaSetHealth, Topology, TopologyDescription, TopologySnapshot,
+               TopologyType, DEFAULT_HEARTBEAT_FREQUENCY_MS, DEFAULT_LOCAL_THRESHOLD_MS,
+               DEFAULT_SERVER_SELECTION_TIMEOUT_MS};
 use topology::server::Server;
+use trace_context::TraceContext;
+use uuid::UuidRepresentation;
+use wire_protocol::limits::DecodeLimits;
 
 pub const DRIVER_NAME: &'static str = "mongo-rust-driver-prototype";
 
+// How long `ping` waits for a reply unless the client's timeout is shorter.
+const PING_TIMEOUT_MS: i64 = 5000;
+
 /// Interfaces with a MongoDB server or replica set.
 pub struct ClientInner {
     /// Indicates how a server should be selected for read operations.
@@ -198,17 +264,55 @@ pub struct ClientInner {
     /// Describes the guarantees provided by MongoDB when reporting the success of a write
     /// operation.
     pub write_concern: WriteConcern,
+    /// Whether writes that were applied but did not satisfy their write concern fail.
+    pub write_concern_error_policy: WriteConcernErrorPolicy,
+    /// Bounds the total time of each operation, unless overridden by the operation's options.
+    pub timeout_ms: Option<i64>,
+    /// The source of time for timeouts, monitoring and server selection.
+    pub clock: Arc<dyn Clock>,
+    /// Looks up the addresses of servers for new connections; see the `dns` module.
+    pub dns_resolver: Arc<dyn DnsResolver>,
+    /// The proxy connections are tunneled through, if any; see the `proxy` module.
+    pub proxy: Option<ProxyOptions>,
+    /// Limits checked on each document read from the server before it is decoded.
+    pub decode_limits: DecodeLimits,
+    /// How UUIDs are stored, if in a legacy representation; see the `uuid` module.
+    pub uuid_representation: Option<UuidRepresentation>,
+    /// Encrypts outgoing commands and decrypts replies, if automatic encryption is enabled.
+    #[cfg(feature = "encryption")]
+    pub encrypter: Option<Arc<Encrypter>>,
+    /// The session the client's operations run in, for clients returned by
+    /// `ThreadedClient::start_session`.
+    pub session: Option<Arc<Session>>,
+    /// The source of the span attached to each command's comment; see the `trace_context`
+    /// module.
+    pub trace_context: Option<Arc<dyn TraceContext>>,
+    federated: Option<bool>,
     req_id: Arc<AtomicIsize>,
     topology: Topology,
-    listener: Listener,
-    log_file: Option<Mutex<File>>,
+    listener: Arc<Listener>,
+    log_file: Option<Arc<Mutex<LogFile>>>,
 }
 
 impl fmt::Debug for ClientInner {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("ClientInner")
+        let mut debug = f.debug_struct("ClientInner");
+        debug
             .field("read_preference", &self.read_preference)
             .field("write_concern", &self.write_concern)
+            .field("write_concern_error_policy", &self.write_concern_error_policy)
+            .field("timeout_ms", &self.timeout_ms)
+            .field("clock", &self.clock)
+            .field("dns_resolver", &"DnsResolver { .. }")
+            .field("proxy", &self.proxy)
+            .field("decode_limits", &self.decode_limits)
+            .field("uuid_representation", &self.uuid_representation);
+        #[cfg(feature = "encryption")]
+        debug.field("encrypter", &self.encrypter);
+        debug
+            .field("session", &self.session)
+            .field("trace_context", &self.trace_context.as_ref().map(|_| "TraceContext { .. }"))
+            .field("federated", &self.federated)
             .field("req_id", &self.req_id)
             .field("topology", &self.topology)
             .field("listener", &"Listener { .. }")
@@ -217,23 +321,85 @@ impl fmt::Debug for ClientInner {
     }
 }
 
+/// When a new client connects to its deployment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ConnectMode {
+    /// Returns the client at once and discovers the deployment in the background, so that the
+    /// first operations wait on discovery instead. A deployment that cannot be reached surfaces
+    /// as server selection errors.
+    #[default]
+    Lazy,
+    /// Blocks client creation until discovery has checked every seed and reached at least one
+    /// server, as `warm_up` does, failing if that takes longer than `timeout_ms`. The timeout must
+    /// be positive.
+    Eager { timeout_ms: i64 },
+}
+
 /// Configuration options for a client.
 #[derive(Default)]
 pub struct ClientOptions {
     /// File path for command logging.
     pub log_file: Option<String>,
+    /// When to rotate the command log file; by default it grows forever. See the `log_file`
+    /// module.
+    pub log_rotation: Option<LogRotationOptions>,
     /// Client-level server selection preferences for read operations.
     pub read_preference: Option<ReadPreference>,
     /// Client-level write guarantees when reporting a write success.
     pub write_concern: Option<WriteConcern>,
+    /// How writes that were applied but did not satisfy their write concern are surfaced;
+    /// reported in the result by default.
+    pub write_concern_error_policy: WriteConcernErrorPolicy,
     /// Frequency of server monitor updates; default 10000 ms.
     pub heartbeat_frequency_ms: u32,
     /// Timeout for selecting an appropriate server for operations; default 30000 ms.
     pub server_selection_timeout_ms: i64,
-    /// The size of the latency window for selecting suitable servers; default 15 ms.
+    /// How much slower than the fastest suitable server another may be and still be selected;
+    /// default 15 ms. Must not be negative.
+    ///
+    /// The field cannot tell a threshold left at the default from one set to 15 ms, so while it
+    /// holds 15 ms the `localThresholdMS` connection string option applies instead. Any other
+    /// value takes precedence over the connection string's.
     pub local_threshold_ms: i64,
+    /// Client-side limit on the total time of an operation, including server selection,
+    /// connection checkout and every round trip; default none. Zero means no limit. Must not be
+    /// negative.
+    pub timeout_ms: Option<i64>,
     /// Options for how to connect to the server.
     pub stream_connector: StreamConnector,
+    /// The source of time for timeouts, monitoring and server selection; defaults to the
+    /// system clock.
+    pub clock: Option<Arc<dyn Clock>>,
+    /// Whether the deployment is a federated endpoint, such as Atlas Data Federation or Online
+    /// Archive; detected from each server's `buildInfo` by default. Features federated endpoints
+    /// lack, such as sessions and writes, fail with `Error::Unsupported`.
+    pub federated: Option<bool>,
+    /// Limits on the nesting depth and array lengths of documents read from the server;
+    /// documents that exceed them fail with `Error::DecodeLimitExceeded`.
+    pub decode_limits: DecodeLimits,
+    /// How UUIDs are stored; defaults to the `uuidRepresentation` connection string option, or
+    /// standard. See the `uuid` module.
+    pub uuid_representation: Option<UuidRepresentation>,
+    /// Encrypts and decrypts fields automatically; see the `encryption` module.
+    #[cfg(feature = "encryption")]
+    pub auto_encryption: Option<AutoEncryptionOptions>,
+    /// Attaches the current span to each command's comment; see the `trace_context` module.
+    pub trace_context: Option<Arc<dyn TraceContext>>,
+    /// Whether creating the client waits for it to reach the deployment; lazy by default.
+    pub connect: ConnectMode,
+    /// Looks up host names and SRV records; defaults to the operating system's resolver, with
+    /// answers cached. See the `dns` module.
+    pub dns_resolver: Option<Arc<dyn DnsResolver>>,
+    /// Looks up the hosts of a `mongodb+srv://` connection string; defaults to the SRV records
+    /// of `dns_resolver`. See the `srv` module.
+    pub srv_resolver: Option<Arc<dyn SrvResolver>>,
+    /// A proxy to tunnel connections through; defaults to a SOCKS5 proxy given by the
+    /// `proxyHost` connection string options, if any. See the `proxy` module.
+    pub proxy: Option<ProxyOptions>,
+    /// How often the SRV records of a `mongodb+srv://` connection string are looked up again
+    /// while the deployment is sharded; defaults to the `rescanSrvIntervalMS` connection string
+    /// option, or 60000 ms. Must be positive.
+    pub rescan_srv_interval_ms: Option<i64>,
 }
 
 impl ClientOptions {
@@ -241,12 +407,27 @@ impl ClientOptions {
     pub fn new() -> ClientOptions {
         ClientOptions {
             log_file: None,
+            log_rotation: None,
             read_preference: None,
             write_concern: None,
+            write_concern_error_policy: WriteConcernErrorPolicy::Report,
             heartbeat_frequency_ms: DEFAULT_HEARTBEAT_FREQUENCY_MS,
             server_selection_timeout_ms: DEFAULT_SERVER_SELECTION_TIMEOUT_MS,
             local_threshold_ms: DEFAULT_LOCAL_THRESHOLD_MS,
+            timeout_ms: None,
             stream_connector: StreamConnector::default(),
+            clock: None,
+            federated: None,
+            decode_limits: DecodeLimits::default(),
+            uuid_representation: None,
+            #[cfg(feature = "encryption")]
+            auto_encryption: None,
+            trace_context: None,
+            connect: ConnectMode::Lazy,
+            dns_resolver: None,
+            srv_resolver: None,
+            proxy: None,
+            rescan_srv_interval_ms: None,
         }
     }
 
@@ -307,10 +488,38 @@ pub trait ThreadedClient: Sync + Sized {
         read_preference: Option<ReadPreference>,
         write_concern: Option<WriteConcern>,
     ) -> Database;
+    /// Returns a client that reads with `read_preference` by default, but otherwise shares this
+    /// client's servers, connection pools, options and event hooks.
+    fn with_read_preference(&self, read_preference: ReadPreference) -> Self;
+    /// Returns a client that writes with `write_concern` by default, but otherwise shares this
+    /// client's servers, connection pools, options and event hooks.
+    fn with_write_concern(&self, write_concern: WriteConcern) -> Self;
+    /// Returns a client that surfaces write concern errors according to `policy`, but otherwise
+    /// shares this client's servers, connection pools, options and event hooks.
+    fn with_write_concern_error_policy(&self, policy: WriteConcernErrorPolicy) -> Self;
+    /// Returns a client that stores UUIDs in `representation`, or leaves them as they are if
+    /// `None`, but otherwise shares this client's servers, connection pools, options and event
+    /// hooks.
+    fn with_uuid_representation(&self, representation: Option<UuidRepresentation>) -> Self;
     /// Acquires a connection stream from the pool, along with slave_ok and should_send_read_pref.
     fn acquire_stream(&self, read_pref: ReadPreference) -> Result<(PooledStream, bool, bool)>;
     /// Acquires a connection stream from the pool for write operations.
     fn acquire_write_stream(&self) -> Result<PooledStream>;
+    /// Acquires a connection stream for read operations, failing with `Error::Timeout` if
+    /// server selection and checkout do not complete before the deadline.
+    fn acquire_stream_with_deadline(
+        &self,
+        read_pref: ReadPreference,
+        deadline: Deadline,
+    ) -> Result<(PooledStream, bool, bool)>;
+    /// Acquires a connection stream for write operations, failing with `Error::Timeout` if
+    /// server selection and checkout do not complete before the deadline.
+    fn acquire_write_stream_with_deadline(&self, deadline: Deadline) -> Result<PooledStream>;
+    /// Acquires a connection stream to `host` without server selection, for operations that must
+    /// reach the same server as an earlier one, such as getMores and unlocking `fsync_lock`.
+    fn acquire_stream_on(&self, host: &Host, deadline: Deadline) -> Result<PooledStream>;
+    /// Returns the newest wire protocol version the server at `host` reported, if it is known.
+    fn max_wire_version(&self, host: &Host) -> Option<i64>;
     /// Returns a unique operational request id.
     fn get_req_id(&self) -> i32;
     /// Returns a list of all database names that exist on the server.
@@ -319,10 +528,90 @@ pub trait ThreadedClient: Sync + Sized {
     fn drop_database(&self, db_name: &str) -> Result<()>;
     /// Reports whether this instance is a primary, master, mongos, or standalone mongod instance.
     fn is_master(&self) -> Result<bool>;
+    /// Whether the client is connected to a federated endpoint, such as Atlas Data Federation or
+    /// Online Archive, which supports neither sessions nor transactions and only a subset of
+    /// commands.
+    fn is_federated(&self) -> bool;
+    /// Runs a command against the `admin` database and deserializes its reply into `T`.
+    fn admin_command_as<T: DeserializeOwned>(&self, spec: bson::Document) -> Result<T>;
+    /// Returns the state of the server the client reads from, such as its connection and
+    /// operation counts and memory use.
+    fn server_status(&self) -> Result<ServerStatus>;
+    /// Returns how the server the client reads from was built, such as its version.
+    fn build_info(&self) -> Result<BuildInfo>;
+    /// Returns the machine and operating system the server the client reads from runs on.
+    fn host_info(&self) -> Result<HostInfo>;
+    /// Flushes pending writes to disk and locks the primary against writes until the returned
+    /// guard is unlocked or dropped, for taking filesystem snapshot backups.
+    fn fsync_lock(&self) -> Result<FsyncLockGuard>;
+    /// Runs `f` with a `Snapshot` whose reads all see the same majority-committed point in
+    /// time, across any number of collections. Requires MongoDB 5.0 or later.
+    fn with_snapshot<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Snapshot) -> Result<T>;
+    /// Returns a client sharing this one's servers whose operations run in a new session, so
+    /// that its reads, even from secondaries, reflect its earlier writes and reads. See the
+    /// `sessions` module.
+    fn start_session(&self, options: Option<SessionOptions>) -> Result<Client>;
+    /// Lists the sessions persisted to `config.system.sessions` across the cluster.
+    fn list_sessions(&self, options: Option<ListSessionsOptions>) -> Result<Vec<SessionRecord>>;
+    /// Lists the sessions held in memory by the primary.
+    fn list_local_sessions(
+        &self,
+        options: Option<ListSessionsOptions>,
+    ) -> Result<Vec<SessionRecord>>;
+    /// Runs `ping` against a server selected by the client's read preference, failing if it
+    /// does not answer within 5 seconds, or the client's timeout if shorter. Suits liveness and
+    /// readiness probes.
+    fn ping(&self) -> Result<()>;
+    /// Whether server monitoring has reached any server, without contacting any server.
+    fn is_connected(&self) -> bool;
+    /// Waits for server monitoring to check every server of the deployment and reach at least
+    /// one, so that the first operations do not wait on discovery. Fails once server selection
+    /// would time out, or with `Error::Timeout` after the client's timeout.
+    fn warm_up(&self) -> Result<()>;
+    /// Summarizes replica set health from server monitoring, without contacting any server.
+    /// Useful for checking that a `w: majority` write can be acknowledged before a large batch.
+    fn replica_set_health(
+        &self,
+        read_preference: Option<ReadPreference>,
+    ) -> Result<ReplicaSetHealth>;
+    /// Summarizes what the connected deployment supports from server monitoring, without
+    /// contacting any server. Useful for startup diagnostics.
+    fn capabilities(&self) -> Result<Capabilities>;
+    /// Describes every known server as monitoring last saw it, without contacting any server.
+    fn topology_snapshot(&self) -> Result<TopologySnapshot>;
+    /// Writes the command log file through to disk. Does nothing without a log file.
+    fn flush_log(&self) -> Result<()>;
+    /// Flushes and closes the command log file, after which the client, and every client sharing
+    /// its servers, stops logging.
+    fn close_log(&self) -> Result<()>;
     /// Sets a function to be run every time a command starts.
     fn add_start_hook(&mut self, hook: fn(Client, &CommandStarted)) -> Result<()>;
     /// Sets a function to be run every time a command completes.
     fn add_completion_hook(&mut self, hook: fn(Client, &CommandResult)) -> Result<()>;
+    /// Sets a function to be run every time a connection to a server is established.
+    fn add_connection_hook(&mut self, hook: fn(Client, &ConnectionEstablished)) -> Result<()>;
+    /// Runs `hook` every time a command that `filter` matches starts, until the returned handle
+    /// is removed or dropped.
+    fn register_start_hook(
+        &mut self,
+        hook: fn(Client, &CommandStarted),
+        filter: Option<HookFilter>,
+    ) -> Result<HookHandle>;
+    /// Runs `hook` every time a command that `filter` matches completes, until the returned
+    /// handle is removed or dropped.
+    fn register_completion_hook(
+        &mut self,
+        hook: fn(Client, &CommandResult),
+        filter: Option<HookFilter>,
+    ) -> Result<HookHandle>;
+    /// Runs `hook` every time a connection to a server is established, until the returned handle
+    /// is removed or dropped.
+    fn register_connection_hook(
+        &mut self,
+        hook: fn(Client, &ConnectionEstablished),
+    ) -> Result<HookHandle>;
 }
 
 pub type Client = Arc<ClientInner>;
@@ -359,6 +648,7 @@ impl ThreadedClient for Client {
         description: Option<TopologyDescription>,
     ) -> Result<Client> {
 
+        let mut config = config;
         let client_options = options.unwrap_or_else(ClientOptions::new);
 
         let rp = client_options.read_preference.unwrap_or_else(|| {
@@ -367,19 +657,167 @@ impl ThreadedClient for Client {
         let wc = client_options.write_concern.unwrap_or_else(
             WriteConcern::new,
         );
+        rp.validate()?;
+        wc.validate()?;
+
+        let timeout_ms = match client_options.timeout_ms {
+            Some(timeout_ms) => Some(timeout_ms),
+            None => {
+                match config.options {
+                    Some(ref opts) => {
+                        match opts.options.get("timeoutMS") {
+                            Some(value) => Some(value.parse::<i64>().map_err(|_| {
+                                ArgumentError(format!("Invalid timeoutMS '{}'.", value))
+                            })?),
+                            None => None,
+                        }
+                    }
+                    None => None,
+                }
+            }
+        };
+
+        if let Some(timeout_ms) = timeout_ms {
+            if timeout_ms < 0 {
+                return Err(ArgumentError(format!(
+                    "timeoutMS must not be negative, but was {}.",
+                    timeout_ms
+                )));
+            }
+        }
+
+        let local_threshold_ms = match config.options {
+            Some(ref opts) if client_options.local_threshold_ms == DEFAULT_LOCAL_THRESHOLD_MS => {
+                match opts.options.get("localThresholdMS") {
+                    Some(value) => value.parse::<i64>().map_err(|_| {
+                        ArgumentError(format!("Invalid localThresholdMS '{}'.", value))
+                    })?,
+                    None => client_options.local_threshold_ms,
+                }
+            }
+            _ => client_options.local_threshold_ms,
+        };
+
+        if local_threshold_ms < 0 {
+            return Err(ArgumentError(format!(
+                "localThresholdMS must not be negative, but was {}.",
+                local_threshold_ms
+            )));
+        }
+
+        if let ConnectMode::Eager { timeout_ms } = client_options.connect {
+            if timeout_ms <= 0 {
+                return Err(ArgumentError(format!(
+                    "An eager connection's timeout must be positive, but was {} ms.",
+                    timeout_ms
+                )));
+            }
+        }
+
+        let rescan_srv_interval_ms = match client_options.rescan_srv_interval_ms {
+            Some(rescan_srv_interval_ms) => rescan_srv_interval_ms,
+            None => {
+                match config.options {
+                    Some(ref opts) => {
+                        match opts.options.get("rescanSrvIntervalMS") {
+                            Some(value) => value.parse::<i64>().map_err(|_| {
+                                ArgumentError(format!("Invalid rescanSrvIntervalMS '{}'.", value))
+                            })?,
+                            None => DEFAULT_RESCAN_SRV_INTERVAL_MS,
+                        }
+                    }
+                    None => DEFAULT_RESCAN_SRV_INTERVAL_MS,
+                }
+            }
+        };
+
+        if rescan_srv_interval_ms <= 0 {
+            return Err(ArgumentError(format!(
+                "rescanSrvIntervalMS must be positive, but was {}.",
+                rescan_srv_interval_ms
+            )));
+        }
+
+        let proxy = match client_options.proxy {
+            Some(proxy) => {
+                proxy.validate()?;
+                Some(proxy)
+            }
+            None => {
+                match config.options {
+                    Some(ref opts) => ProxyOptions::from_connection_options(opts)?,
+                    None => None,
+                }
+            }
+        };
+
+        let stream_connector = match config.options {
+            Some(ref opts) => client_options.stream_connector.with_connection_options(opts)?,
+            None => client_options.stream_connector,
+        };
 
-        let listener = Listener::new();
+        let clock = client_options.clock.unwrap_or_else(|| Arc::new(SystemClock));
+        let dns_resolver = client_options.dns_resolver.unwrap_or_else(|| {
+            Arc::new(CachingResolver::new(Arc::new(SystemResolver), clock.clone()))
+        });
+
+        let srv_resolver = match client_options.srv_resolver {
+            Some(resolver) => resolver,
+            None => {
+                let dns_resolver = dns_resolver.clone();
+                Arc::new(move |name: &str| Ok(dns_resolver.lookup_srv(name)?.records))
+            }
+        };
+
+        if let Some(ref hostname) = config.srv {
+            config.hosts = srv::resolve_hosts(&*srv_resolver, hostname)?;
+        }
+
+        let uuid_representation = match client_options.uuid_representation {
+            Some(representation) => Some(representation),
+            None => {
+                match config.options {
+                    Some(ref opts) => {
+                        match opts.options.get("uuidRepresentation") {
+                            Some(value) => Some(value.parse::<UuidRepresentation>()?),
+                            None => None,
+                        }
+                    }
+                    None => None,
+                }
+            }
+        };
+
+        // Data keys are read with a separate client, so that reading them is never encrypted.
+        #[cfg(feature = "encryption")]
+        let encrypter = match client_options.auto_encryption {
+            Some(mut encryption) => {
+                let key_vault_client = match encryption.key_vault_client.take() {
+                    Some(client) => client,
+                    None => {
+                        let mut options = ClientOptions::new();
+                        options.stream_connector = stream_connector.clone();
+                        options.clock = Some(clock.clone());
+                        options.timeout_ms = timeout_ms;
+                        options.dns_resolver = Some(dns_resolver.clone());
+                        options.proxy = proxy.clone();
+                        options.srv_resolver = Some(srv_resolver.clone());
+                        Client::with_config(config.clone(), Some(options), None)?
+                    }
+                };
+                Some(Arc::new(Encrypter::new(encryption, &key_vault_client)?))
+            }
+            None => None,
+        };
+
+        let listener = Arc::new(Listener::new());
         let file = match client_options.log_file {
             Some(string) => {
                 let _ = listener.add_start_hook(log_command_started);
                 let _ = listener.add_completion_hook(log_command_completed);
-                Some(Mutex::new(
-                    OpenOptions::new()
-                        .write(true)
-                        .append(true)
-                        .create(true)
-                        .open(&string)?
-                ))
+                let _ = listener.add_connection_hook(log_connection_established);
+                let rotation = client_options.log_rotation.unwrap_or_default();
+                Some(Arc::new(Mutex::new(LogFile::open(&string, rotation)?)))
             }
             None => None,
         };
@@ -389,11 +827,23 @@ impl ThreadedClient for Client {
             topology: Topology::new(
                 config.clone(),
                 description,
-                client_options.stream_connector.clone(),
+                stream_connector.clone(),
             )?,
             listener: listener,
             read_preference: rp,
             write_concern: wc,
+            write_concern_error_policy: client_options.write_concern_error_policy,
+            timeout_ms: timeout_ms,
+            clock,
+            dns_resolver,
+            proxy,
+            federated: client_options.federated,
+            decode_limits: client_options.decode_limits,
+            uuid_representation,
+            #[cfg(feature = "encryption")]
+            encrypter,
+            session: None,
+            trace_context: client_options.trace_context,
             log_file: file,
         });
 
@@ -403,21 +853,35 @@ impl ThreadedClient for Client {
             let mut top = top_description.write()?;
             top.heartbeat_frequency_ms = client_options.heartbeat_frequency_ms;
             top.server_selection_timeout_ms = client_options.server_selection_timeout_ms;
-            top.local_threshold_ms = client_options.local_threshold_ms;
+            top.local_threshold_ms = local_threshold_ms;
 
-            for host in config.hosts {
+            for host in config.hosts.iter().cloned() {
                 let server = Server::new(
                     client.clone(),
                     host.clone(),
                     top_description.clone(),
                     true,
-                    client_options.stream_connector.clone(),
+                    stream_connector.clone(),
                 );
 
                 top.servers.insert(host, server);
             }
         }
 
+        if let Some(hostname) = config.srv {
+            client.topology.start_srv_polling(
+                &client,
+                srv_resolver,
+                hostname,
+                rescan_srv_interval_ms,
+            );
+        }
+
+        if let ConnectMode::Eager { timeout_ms } = client_options.connect {
+            let deadline = Deadline::after_ms_on(client.clock.clone(), Some(timeout_ms));
+            client.topology.warm_up(&client, deadline)?;
+        }
+
         Ok(client)
     }
 
@@ -434,15 +898,81 @@ impl ThreadedClient for Client {
         Database::open(self.clone(), db_name, read_preference, write_concern)
     }
 
+    fn with_read_preference(&self, read_preference: ReadPreference) -> Client {
+        share_client(
+            self,
+            read_preference,
+            self.write_concern,
+            self.write_concern_error_policy,
+            self.uuid_representation,
+            self.session.clone(),
+        )
+    }
+
+    fn with_write_concern(&self, write_concern: WriteConcern) -> Client {
+        share_client(
+            self,
+            self.read_preference.clone(),
+            write_concern,
+            self.write_concern_error_policy,
+            self.uuid_representation,
+            self.session.clone(),
+        )
+    }
+
+    fn with_write_concern_error_policy(&self, policy: WriteConcernErrorPolicy) -> Client {
+        share_client(
+            self,
+            self.read_preference.clone(),
+            self.write_concern,
+            policy,
+            self.uuid_representation,
+            self.session.clone(),
+        )
+    }
+
+    fn with_uuid_representation(&self, representation: Option<UuidRepresentation>) -> Client {
+        share_client(
+            self,
+            self.read_preference.clone(),
+            self.write_concern,
+            self.write_concern_error_policy,
+            representation,
+            self.session.clone(),
+        )
+    }
+
     fn acquire_stream(
         &self,
         read_preference: ReadPreference,
     ) -> Result<(PooledStream, bool, bool)> {
-        self.topology.acquire_stream(self.clone(), read_preference)
+        let deadline = Deadline::after_ms_on(self.clock.clone(), self.timeout_ms);
+        self.acquire_stream_with_deadline(read_preference, deadline)
     }
 
     fn acquire_write_stream(&self) -> Result<PooledStream> {
-        self.topology.acquire_write_stream(self.clone())
+        let deadline = Deadline::after_ms_on(self.clock.clone(), self.timeout_ms);
+        self.acquire_write_stream_with_deadline(deadline)
+    }
+
+    fn acquire_stream_with_deadline(
+        &self,
+        read_preference: ReadPreference,
+        deadline: Deadline,
+    ) -> Result<(PooledStream, bool, bool)> {
+        self.topology.acquire_stream(self.clone(), read_preference, deadline)
+    }
+
+    fn acquire_write_stream_with_deadline(&self, deadline: Deadline) -> Result<PooledStream> {
+        self.topology.acquire_write_stream(self.clone(), deadline)
+    }
+
+    fn acquire_stream_on(&self, host: &Host, deadline: Deadline) -> Result<PooledStream> {
+        self.topology.acquire_stream_on(self.clone(), host, deadline)
+    }
+
+    fn max_wire_version(&self, host: &Host) -> Option<i64> {
+        self.topology.max_wire_version(host)
     }
 
     fn get_req_id(&self) -> i32 {
@@ -493,6 +1023,162 @@ impl ThreadedClient for Client {
         }
     }
 
+    fn is_federated(&self) -> bool {
+        match self.federated {
+            Some(federated) => federated,
+            None => {
+                match self.topology.description.read() {
+                    Ok(description) => description.is_federated(),
+                    Err(_) => false,
+                }
+            }
+        }
+    }
+
+    fn admin_command_as<T: DeserializeOwned>(&self, spec: bson::Document) -> Result<T> {
+        self.db("admin").run_command_as(spec)
+    }
+
+    fn server_status(&self) -> Result<ServerStatus> {
+        self.admin_command_as(doc! { "serverStatus": 1 })
+    }
+
+    fn build_info(&self) -> Result<BuildInfo> {
+        self.admin_command_as(doc! { "buildInfo": 1 })
+    }
+
+    fn host_info(&self) -> Result<HostInfo> {
+        self.admin_command_as(doc! { "hostInfo": 1 })
+    }
+
+    fn fsync_lock(&self) -> Result<FsyncLockGuard> {
+        FsyncLockGuard::lock(self)
+    }
+
+    fn with_snapshot<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Snapshot) -> Result<T>,
+    {
+        f(&Snapshot::new(self.clone()))
+    }
+
+    fn start_session(&self, options: Option<SessionOptions>) -> Result<Client> {
+        if self.is_federated() {
+            return Err(Unsupported(String::from(
+                "Federated endpoints do not support sessions.",
+            )));
+        }
+
+        let session = Arc::new(Session::new(options.unwrap_or_default()));
+        Ok(share_client(
+            self,
+            self.read_preference.clone(),
+            self.write_concern,
+            self.write_concern_error_policy,
+            self.uuid_representation,
+            Some(session),
+        ))
+    }
+
+    fn list_sessions(&self, options: Option<ListSessionsOptions>) -> Result<Vec<SessionRecord>> {
+        if self.is_federated() {
+            return Err(Unsupported(String::from(
+                "Federated endpoints do not support sessions.",
+            )));
+        }
+
+        let stage = bson::Document::from(options.unwrap_or_default());
+        let coll = self.db("config").collection("system.sessions");
+        let cursor = coll.aggregate(vec![doc! { "$listSessions": stage }], None)?;
+
+        cursor.map(|result| SessionRecord::from_document(&result?)).collect()
+    }
+
+    fn list_local_sessions(
+        &self,
+        options: Option<ListSessionsOptions>,
+    ) -> Result<Vec<SessionRecord>> {
+        if self.is_federated() {
+            return Err(Unsupported(String::from(
+                "Federated endpoints do not support sessions.",
+            )));
+        }
+
+        let stage = bson::Document::from(options.unwrap_or_default());
+        let spec = doc! {
+            "aggregate": 1,
+            "pipeline": [{ "$listLocalSessions": stage }],
+            "cursor": {},
+        };
+        let primary = ReadPreference::new(ReadMode::Primary, None);
+        let cursor = self.db("admin").command_cursor(spec, CommandType::Aggregate, primary)?;
+
+        cursor.map(|result| SessionRecord::from_document(&result?)).collect()
+    }
+
+    fn ping(&self) -> Result<()> {
+        let timeout_ms = match self.timeout_ms {
+            Some(timeout_ms) if timeout_ms > 0 => timeout_ms.min(PING_TIMEOUT_MS),
+            _ => PING_TIMEOUT_MS,
+        };
+
+        self.db("admin").command_with_timeout(
+            doc! { "ping": 1 },
+            CommandType::RunCommand,
+            None,
+            Some(timeout_ms),
+        )?;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        match self.topology.description.read() {
+            Ok(description) => description.is_connected(),
+            Err(_) => false,
+        }
+    }
+
+    fn warm_up(&self) -> Result<()> {
+        let deadline = Deadline::after_ms_on(self.clock.clone(), self.timeout_ms);
+        self.topology.warm_up(self, deadline)
+    }
+
+    fn replica_set_health(
+        &self,
+        read_preference: Option<ReadPreference>,
+    ) -> Result<ReplicaSetHealth> {
+        let description = self.topology.description.read()?;
+        Ok(description.replica_set_health(read_preference.as_ref()))
+    }
+
+    fn capabilities(&self) -> Result<Capabilities> {
+        let description = self.topology.description.read()?;
+        let mut capabilities = description.capabilities();
+        if let Some(federated) = self.federated {
+            capabilities.federated = federated;
+        }
+        Ok(capabilities)
+    }
+
+    fn topology_snapshot(&self) -> Result<TopologySnapshot> {
+        let description = self.topology.description.read()?;
+        Ok(description.snapshot())
+    }
+
+    fn flush_log(&self) -> Result<()> {
+        match self.log_file {
+            Some(ref mutex) => Ok(mutex.lock()?.flush()?),
+            None => Ok(()),
+        }
+    }
+
+    fn close_log(&self) -> Result<()> {
+        match self.log_file {
+            Some(ref mutex) => Ok(mutex.lock()?.close()?),
+            None => Ok(()),
+        }
+    }
+
     fn add_start_hook(&mut self, hook: fn(Client, &CommandStarted)) -> Result<()> {
         self.listener.add_start_hook(hook)
     }
@@ -500,6 +1186,64 @@ impl ThreadedClient for Client {
     fn add_completion_hook(&mut self, hook: fn(Client, &CommandResult)) -> Result<()> {
         self.listener.add_completion_hook(hook)
     }
+
+    fn add_connection_hook(&mut self, hook: fn(Client, &ConnectionEstablished)) -> Result<()> {
+        self.listener.add_connection_hook(hook)
+    }
+
+    fn register_start_hook(
+        &mut self,
+        hook: fn(Client, &CommandStarted),
+        filter: Option<HookFilter>,
+    ) -> Result<HookHandle> {
+        self.listener.register_start_hook(hook, filter)
+    }
+
+    fn register_completion_hook(
+        &mut self,
+        hook: fn(Client, &CommandResult),
+        filter: Option<HookFilter>,
+    ) -> Result<HookHandle> {
+        self.listener.register_completion_hook(hook, filter)
+    }
+
+    fn register_connection_hook(
+        &mut self,
+        hook: fn(Client, &ConnectionEstablished),
+    ) -> Result<HookHandle> {
+        self.listener.register_connection_hook(hook)
+    }
+}
+
+// Creates a client with its own defaults on top of the servers and hooks of `client`.
+fn share_client(
+    client: &ClientInner,
+    read_preference: ReadPreference,
+    write_concern: WriteConcern,
+    write_concern_error_policy: WriteConcernErrorPolicy,
+    uuid_representation: Option<UuidRepresentation>,
+    session: Option<Arc<Session>>,
+) -> Client {
+    Arc::new(ClientInner {
+        read_preference,
+        write_concern,
+        write_concern_error_policy,
+        uuid_representation,
+        timeout_ms: client.timeout_ms,
+        clock: client.clock.clone(),
+        dns_resolver: client.dns_resolver.clone(),
+        proxy: client.proxy.clone(),
+        decode_limits: client.decode_limits,
+        #[cfg(feature = "encryption")]
+        encrypter: client.encrypter.clone(),
+        session,
+        trace_context: client.trace_context.clone(),
+        federated: client.federated,
+        req_id: client.req_id.clone(),
+        topology: client.topology.clone(),
+        listener: client.listener.clone(),
+        log_file: client.log_file.clone(),
+    })
 }
 
 fn log_command_started(client: Client, command_started: &CommandStarted) {
@@ -513,7 +1257,7 @@ fn log_command_started(client: Client, command_started: &CommandStarted) {
         Err(_) => return,
     };
 
-    let _ = writeln!(guard.deref_mut(), "{}", command_started);
+    let _ = guard.write_line(command_started);
 }
 
 fn log_command_completed(client: Client, command_result: &CommandResult) {
@@ -527,5 +1271,19 @@ fn log_command_completed(client: Client, command_result: &CommandResult) {
         Err(_) => return,
     };
 
-    let _ = writeln!(guard.deref_mut(), "{}", command_result);
+    let _ = guard.write_line(command_result);
+}
+
+fn log_connection_established(client: Client, established: &ConnectionEstablished) {
+    let mutex = match client.log_file {
+        Some(ref mutex) => mutex,
+        None => return,
+    };
+
+    let mut guard = match mutex.lock() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+
+    let _ = guard.write_line(established);
 }
@@ -0,0 +1,321 @@
+//! Lease-based distributed locks.
+//!
+//! A `Lock` provides mutual exclusion between processes sharing a MongoDB deployment. Each lock is
+//! a single document in a collection; holding the lock means owning that document until its lease
+//! expires. A holder that dies without releasing the lock therefore only blocks others until the
+//! lease runs out.
+//!
+//! Every successful acquisition increments the lock's fencing token. Because a paused holder may
+//! resume after its lease has expired and been taken over, writes guarded by a lock should carry
+//! the token and be rejected by their target when a higher token has already been seen.
+//!
+//! Leases are timed by the server's clock rather than the holders', so that processes whose
+//! clocks disagree still agree on when a lease has expired. Locks therefore require MongoDB 4.2 or
+//! later, which can compute a document's fields from the current time in an update.
+//!
+//! ```no_run
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # use mongodb::lock::Lock;
+//! #
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let coll = client.db("app").collection("locks");
+//! let lock = Lock::new(&coll, "nightly-report", None);
+//!
+//! if let Some(guard) = lock.try_acquire().unwrap() {
+//!     println!("acquired with fencing token {}", guard.token());
+//!     // ... do work; the lease is renewed in the background ...
+//!     guard.release().unwrap();
+//! }
+//! # }
+//! ```
+use bson::{Bson, doc};
+
+use coll::Collection;
+use coll::options::{FindOneAndUpdateOptions, ReturnDocument};
+use db::{Database, ThreadedDatabase};
+use error::ErrorCode;
+use object_id;
+use Error::{OperationError, ResponseError, ServerError};
+use {Error, Result};
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration as StdDuration, Instant};
+
+/// The default lease duration; 30000 ms.
+pub const DEFAULT_LOCK_LEASE_MS: i64 = 30000;
+
+// How long `acquire` waits between attempts.
+const ACQUIRE_RETRY_MS: u64 = 100;
+
+/// Options for lock acquisition and renewal.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LockOptions {
+    /// How long an acquisition or renewal holds the lock; defaults to 30000 ms.
+    pub lease_ms: Option<i64>,
+    /// Whether a background thread renews the lease while it is held; defaults to true.
+    pub auto_renew: Option<bool>,
+    /// How often the background thread renews the lease; defaults to a third of the lease.
+    pub renew_interval_ms: Option<i64>,
+}
+
+impl LockOptions {
+    pub fn new() -> LockOptions {
+        Default::default()
+    }
+}
+
+/// A named lock stored as a document in a collection.
+///
+/// Each `Lock` value has its own owner identity; clones share it.
+#[derive(Clone, Debug)]
+pub struct Lock {
+    db: Database,
+    coll: String,
+    name: String,
+    owner: String,
+    lease_ms: i64,
+    auto_renew: bool,
+    renew_interval_ms: i64,
+}
+
+/// Proof of holding a lock. The lock is released when the guard is dropped.
+#[derive(Debug)]
+pub struct LockGuard {
+    lock: Lock,
+    token: i64,
+    lost: Arc<AtomicBool>,
+    stop: Option<Sender<()>>,
+    renewer: Option<JoinHandle<()>>,
+    released: bool,
+}
+
+impl Lock {
+    /// Creates a handle to the lock named `name` in the given collection.
+    pub fn new(coll: &Collection, name: &str, options: Option<LockOptions>) -> Lock {
+        let options = options.unwrap_or_default();
+        let lease_ms = options.lease_ms.unwrap_or(DEFAULT_LOCK_LEASE_MS);
+
+        let owner = object_id::generate().to_hex();
+
+        Lock {
+            db: coll.db.clone(),
+            coll: coll.name(),
+            name: name.to_owned(),
+            owner,
+            lease_ms,
+            auto_renew: options.auto_renew.unwrap_or(true),
+            renew_interval_ms: options.renew_interval_ms.unwrap_or(lease_ms / 3),
+        }
+    }
+
+    /// The name of the lock.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Attempts to acquire the lock once, returning `None` if it is held by someone else.
+    pub fn try_acquire(&self) -> Result<Option<LockGuard>> {
+        let coll = self.db.collection(&self.coll);
+
+        let mut options = FindOneAndUpdateOptions::new();
+        options.return_document = Some(ReturnDocument::After);
+        options.upsert = Some(true);
+
+        // Creates the lock document if the lock has never been taken, or takes it over if its
+        // lease has expired. A lock that is still held fails the filter, so the upsert collides
+        // with its document instead.
+        let taken = coll.find_one_and_update(
+            doc! {
+                "_id": &self.name,
+                "$expr": { "$lt": ["$expires_at", "$$NOW"] },
+            },
+            vec![doc! {
+                "$set": {
+                    "owner": &self.owner,
+                    "expires_at": self.expiry(),
+                    "token": { "$add": [{ "$ifNull": ["$token", 0i64] }, 1i64] },
+                },
+            }],
+            Some(options),
+        );
+
+        let doc = match taken {
+            Ok(Some(doc)) => doc,
+            Ok(None) => return Ok(None),
+            Err(ref err) if Lock::is_duplicate_key(err) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        match doc.get("token") {
+            Some(&Bson::I64(token)) => Ok(Some(self.guard(token))),
+            Some(&Bson::I32(token)) => Ok(Some(self.guard(i64::from(token)))),
+            _ => Err(ResponseError(
+                format!("Lock '{}' has a non-integer fencing token.", self.name),
+            )),
+        }
+    }
+
+    /// Acquires the lock, retrying until it succeeds or `wait_ms` milliseconds have elapsed.
+    pub fn acquire(&self, wait_ms: i64) -> Result<LockGuard> {
+        let deadline = Instant::now() + StdDuration::from_millis(wait_ms.max(0) as u64);
+
+        loop {
+            if let Some(guard) = self.try_acquire()? {
+                return Ok(guard);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(OperationError(format!(
+                    "Timed out after {} ms waiting for lock '{}'.",
+                    wait_ms,
+                    self.name
+                )));
+            }
+
+            thread::sleep(StdDuration::from_millis(ACQUIRE_RETRY_MS));
+        }
+    }
+
+    fn guard(&self, token: i64) -> LockGuard {
+        let lost = Arc::new(AtomicBool::new(false));
+
+        let (stop, renewer) = if self.auto_renew && self.renew_interval_ms > 0 {
+            let (tx, rx) = mpsc::channel();
+            let lock = self.clone();
+            let thread_lost = lost.clone();
+            let interval = StdDuration::from_millis(self.renew_interval_ms as u64);
+
+            let handle = thread::spawn(move || loop {
+                match rx.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => {
+                        if !lock.renew(token).unwrap_or(false) {
+                            thread_lost.store(true, Ordering::SeqCst);
+                            return;
+                        }
+                    }
+                    // Stopped explicitly, or the guard went away.
+                    _ => return,
+                }
+            });
+
+            (Some(tx), Some(handle))
+        } else {
+            (None, None)
+        };
+
+        LockGuard {
+            lock: self.clone(),
+            token,
+            lost,
+            stop,
+            renewer,
+            released: false,
+        }
+    }
+
+    // The expression for when a lease taken now expires, by the server's clock.
+    fn expiry(&self) -> Bson {
+        Bson::Document(doc! { "$add": ["$$NOW", self.lease_ms] })
+    }
+
+    // Whether an acquisition collided with a lock that is still held. findAndModify reports the
+    // collision as a command failure rather than a write error.
+    fn is_duplicate_key(err: &Error) -> bool {
+        match *err {
+            ServerError(ref err) => err.code == ErrorCode::DuplicateKey as i32,
+            ref err => err.is_duplicate_key(),
+        }
+    }
+
+    // Extends the lease, returning false if this owner no longer holds the lock at `token`.
+    fn renew(&self, token: i64) -> Result<bool> {
+        let coll = self.db.collection(&self.coll);
+
+        let renewed = coll.find_one_and_update(
+            doc! {
+                "_id": &self.name,
+                "owner": &self.owner,
+                "token": token,
+            },
+            vec![doc! { "$set": { "expires_at": self.expiry() } }],
+            None,
+        )?;
+
+        Ok(renewed.is_some())
+    }
+
+    fn release(&self, token: i64) -> Result<()> {
+        let coll = self.db.collection(&self.coll);
+
+        // The document is kept, rather than deleted, so the fencing token keeps increasing.
+        let filter = doc! {
+            "_id": &self.name,
+            "owner": &self.owner,
+            "token": token,
+        };
+
+        let update = doc! {
+            "$currentDate": { "expires_at": true },
+            "$unset": { "owner": "" },
+        };
+
+        coll.update_one(filter, update, None).map(drop)
+    }
+}
+
+impl LockGuard {
+    /// The fencing token of this acquisition. Tokens strictly increase across acquisitions.
+    pub fn token(&self) -> i64 {
+        self.token
+    }
+
+    /// Whether background renewal has found that the lease was lost.
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::SeqCst)
+    }
+
+    /// Extends the lease immediately, failing if the lock is no longer held.
+    pub fn renew(&self) -> Result<()> {
+        if !self.is_lost() && self.lock.renew(self.token)? {
+            return Ok(());
+        }
+
+        self.lost.store(true, Ordering::SeqCst);
+        Err(OperationError(
+            format!("Lost the lease on lock '{}'.", self.lock.name),
+        ))
+    }
+
+    /// Releases the lock.
+    pub fn release(mut self) -> Result<()> {
+        self.stop_renewer();
+        self.released = true;
+        self.lock.release(self.token)
+    }
+
+    fn stop_renewer(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+
+        if let Some(renewer) = self.renewer.take() {
+            let _ = renewer.join();
+        }
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        self.stop_renewer();
+
+        if !self.released {
+            let _ = self.lock.release(self.token);
+        }
+    }
+}
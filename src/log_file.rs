@@ -0,0 +1,209 @@
+//! The command log file.
+//!
+//! A client created with `ClientOptions::log_file` writes a line to the file for every command
+//! started and completed and every connection established. By default the file grows forever;
+//! `LogRotationOptions` rotate it once it reaches a size or an age, keeping a bounded number of
+//! older files next to it as `<path>.1` (the newest) through `<path>.<max_files>`.
+//!
+//! `ThreadedClient::flush_log` writes the log through to disk, and `ThreadedClient::close_log`
+//! closes it, after which the client no longer logs.
+//!
+//! ```no_run
+//! # use mongodb::{Client, ClientOptions, ThreadedClient};
+//! # use mongodb::log_file::LogRotationOptions;
+//! #
+//! let mut rotation = LogRotationOptions::new();
+//! rotation.max_size_bytes = Some(64 * 1024 * 1024);
+//! rotation.max_files = Some(3);
+//!
+//! let mut options = ClientOptions::with_log_file("/var/log/app/mongodb.log");
+//! options.log_rotation = Some(rotation);
+//! let client = Client::connect_with_options("localhost", 27017, options).unwrap();
+//! ```
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// The number of rotated files kept if `max_files` is not given.
+pub const DEFAULT_MAX_LOG_FILES: usize = 5;
+
+/// When to rotate the command log file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogRotationOptions {
+    /// Rotate before a line would take the file past this many bytes; default none.
+    pub max_size_bytes: Option<u64>,
+    /// Rotate once the file has been written to for this long since it was opened or last
+    /// rotated; default none.
+    pub max_age: Option<Duration>,
+    /// How many rotated files to keep; defaults to 5. With zero, rotation truncates the file.
+    pub max_files: Option<usize>,
+}
+
+impl LogRotationOptions {
+    pub fn new() -> LogRotationOptions {
+        Default::default()
+    }
+}
+
+/// A log file that rotates as configured, shared by a client's logging hooks.
+#[derive(Debug)]
+pub struct LogFile {
+    path: PathBuf,
+    rotation: LogRotationOptions,
+    // None once closed.
+    file: Option<File>,
+    size: u64,
+    opened: Instant,
+}
+
+impl LogFile {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P, rotation: LogRotationOptions) -> io::Result<LogFile> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(LogFile {
+            path,
+            rotation,
+            file: Some(file),
+            size,
+            opened: Instant::now(),
+        })
+    }
+
+    /// The path of the file currently written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether `close` has been called.
+    pub fn is_closed(&self) -> bool {
+        self.file.is_none()
+    }
+
+    /// Appends `line` and a newline, rotating first if the line would exceed the size limit or
+    /// the file is too old. Does nothing once closed.
+    pub fn write_line<T: fmt::Display>(&mut self, line: T) -> io::Result<()> {
+        if self.file.is_none() {
+            return Ok(());
+        }
+
+        let line = format!("{}\n", line);
+        if self.needs_rotation(line.len() as u64) {
+            self.rotate()?;
+        }
+
+        if let Some(ref mut file) = self.file {
+            file.write_all(line.as_bytes())?;
+            self.size += line.len() as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the current file to `<path>.1`, shifting older files up and deleting any beyond
+    /// `max_files`, and starts a new file.
+    ///
+    /// If the files cannot be moved, logging continues at `path`, in the current file if it is
+    /// still there, and the error is returned.
+    pub fn rotate(&mut self) -> io::Result<()> {
+        if self.file.is_none() {
+            return Ok(());
+        }
+
+        // Close the file before renaming it, which Windows requires.
+        self.file = None;
+
+        let rotated = self.shift_files();
+        let file = open_append(&self.path)?;
+        match rotated {
+            Ok(()) => {
+                self.size = 0;
+                self.opened = Instant::now();
+            }
+            Err(_) => {
+                if let Ok(metadata) = file.metadata() {
+                    self.size = metadata.len();
+                }
+            }
+        }
+        self.file = Some(file);
+
+        rotated
+    }
+
+    // Moves the current and rotated files up one place, deleting those beyond `max_files`.
+    fn shift_files(&self) -> io::Result<()> {
+        let max_files = self.rotation.max_files.unwrap_or(DEFAULT_MAX_LOG_FILES);
+
+        if max_files == 0 {
+            return remove_if_exists(&self.path);
+        }
+
+        remove_if_exists(&self.rotated_path(max_files))?;
+        for n in (1..max_files).rev() {
+            rename_if_exists(&self.rotated_path(n), &self.rotated_path(n + 1))?;
+        }
+        rename_if_exists(&self.path, &self.rotated_path(1))
+    }
+
+    /// Writes everything logged so far through to disk.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.file {
+            Some(ref mut file) => {
+                file.flush()?;
+                file.sync_data()
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Flushes and closes the file. Later lines are discarded.
+    pub fn close(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.file = None;
+        Ok(())
+    }
+
+    /// The path of the `n`th most recent rotated file.
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    fn needs_rotation(&self, len: u64) -> bool {
+        let too_large = match self.rotation.max_size_bytes {
+            Some(max) => self.size > 0 && self.size + len > max,
+            None => false,
+        };
+
+        let too_old = match self.rotation.max_age {
+            Some(max_age) => self.opened.elapsed() >= max_age,
+            None => false,
+        };
+
+        too_large || too_old
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+fn rename_if_exists(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
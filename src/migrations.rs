@@ -0,0 +1,260 @@
+//! Versioned schema migrations.
+//!
+//! A `MigrationRunner` applies an ordered set of `Migration`s to a database and records each
+//! applied version in the `schema_migrations` collection. Only one runner may migrate a database
+//! at a time; mutual exclusion is provided by a `Lock` whose lease expires on its own if the
+//! runner holding it dies mid-run.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::{Client, Result, ThreadedClient};
+//! # use mongodb::db::{Database, ThreadedDatabase};
+//! # use mongodb::migrations::{Migration, MigrationRunner};
+//! #
+//! struct AddEmailIndex;
+//!
+//! impl Migration for AddEmailIndex {
+//!     fn version(&self) -> i64 { 1 }
+//!     fn name(&self) -> &str { "add_email_index" }
+//!
+//!     fn up(&self, db: &Database) -> Result<()> {
+//!         db.collection("users").create_index(doc!{ "email": 1 }, None).map(drop)
+//!     }
+//!
+//!     fn down(&self, db: &Database) -> Result<()> {
+//!         db.collection("users").drop_index(doc!{ "email": 1 }, None)
+//!     }
+//! }
+//!
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let mut runner = MigrationRunner::new(client.db("app"));
+//! runner.add(AddEmailIndex).unwrap();
+//!
+//! let applied = runner.up().unwrap();
+//! println!("applied migrations {:?}", applied);
+//! # }
+//! ```
+use bson::{Bson, doc};
+use chrono::Utc;
+
+use coll::options::FindOptions;
+use db::{Database, ThreadedDatabase};
+use lock::{Lock, LockGuard, LockOptions};
+use Error::{ArgumentError, OperationError, ResponseError, WriteError};
+use Result;
+
+use std::fmt;
+
+/// The collection recording which migration versions have been applied.
+pub const MIGRATIONS_COLLECTION: &str = "schema_migrations";
+/// The collection holding the lock document of the active runner.
+pub const MIGRATIONS_LEASE_COLLECTION: &str = "schema_migrations_lease";
+/// How long a runner may hold the migration lock without renewing it; default 60000 ms.
+pub const DEFAULT_LEASE_MS: i64 = 60000;
+
+const LOCK_NAME: &str = "schema_migrations";
+
+/// A single, reversible schema change.
+pub trait Migration {
+    /// A unique version number. Migrations are applied in ascending version order.
+    fn version(&self) -> i64;
+    /// A human-readable name, recorded alongside the version once applied.
+    fn name(&self) -> &str;
+    /// Applies the schema change.
+    fn up(&self, db: &Database) -> Result<()>;
+    /// Reverts the schema change.
+    fn down(&self, db: &Database) -> Result<()>;
+}
+
+/// Applies and reverts migrations against a single database.
+pub struct MigrationRunner {
+    db: Database,
+    // Registered migrations, sorted by version.
+    migrations: Vec<Box<dyn Migration>>,
+    lock: Lock,
+}
+
+impl fmt::Debug for MigrationRunner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let versions: Vec<_> = self.migrations.iter().map(|m| m.version()).collect();
+
+        f.debug_struct("MigrationRunner")
+            .field("db", &self.db.name)
+            .field("migrations", &versions)
+            .field("lock", &self.lock)
+            .finish()
+    }
+}
+
+impl MigrationRunner {
+    /// Creates a runner for the database with the default lease duration.
+    pub fn new(db: Database) -> MigrationRunner {
+        MigrationRunner::with_lease_ms(db, DEFAULT_LEASE_MS)
+    }
+
+    /// Creates a runner for the database with a specified lease duration.
+    ///
+    /// The lease is renewed in the background while migrations run.
+    pub fn with_lease_ms(db: Database, lease_ms: i64) -> MigrationRunner {
+        let mut options = LockOptions::new();
+        options.lease_ms = Some(lease_ms);
+
+        let lock = Lock::new(
+            &db.collection(MIGRATIONS_LEASE_COLLECTION),
+            LOCK_NAME,
+            Some(options),
+        );
+
+        MigrationRunner {
+            db,
+            migrations: Vec::new(),
+            lock,
+        }
+    }
+
+    /// Registers a migration. Fails if another migration already uses the same version.
+    pub fn add<M: Migration + 'static>(&mut self, migration: M) -> Result<()> {
+        let version = migration.version();
+
+        match self.migrations.binary_search_by_key(&version, |m| m.version()) {
+            Ok(_) => Err(ArgumentError(
+                format!("A migration with version {} is already registered.", version),
+            )),
+            Err(index) => {
+                self.migrations.insert(index, Box::new(migration));
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the versions recorded as applied, in ascending order.
+    pub fn applied_versions(&self) -> Result<Vec<i64>> {
+        let mut options = FindOptions::new();
+        options.sort = Some(doc!{ "_id": 1 });
+        options.projection = Some(doc!{ "_id": 1 });
+
+        let coll = self.db.collection(MIGRATIONS_COLLECTION);
+        let mut versions = Vec::new();
+
+        for result in coll.find(None, Some(options))? {
+            match result?.get("_id") {
+                Some(&Bson::I64(v)) => versions.push(v),
+                Some(&Bson::I32(v)) => versions.push(v as i64),
+                _ => {
+                    return Err(ResponseError(format!(
+                        "Found a non-integer version in '{}'.",
+                        MIGRATIONS_COLLECTION
+                    )))
+                }
+            }
+        }
+
+        Ok(versions)
+    }
+
+    /// Returns the registered versions that have not yet been applied, in ascending order.
+    pub fn pending_versions(&self) -> Result<Vec<i64>> {
+        let applied = self.applied_versions()?;
+
+        Ok(
+            self.migrations
+                .iter()
+                .map(|m| m.version())
+                .filter(|v| !applied.contains(v))
+                .collect(),
+        )
+    }
+
+    /// Applies all pending migrations in ascending version order, returning the versions that
+    /// were applied.
+    ///
+    /// If a migration fails, the migrations applied before it remain recorded and the error is
+    /// returned; the failed migration is not recorded.
+    pub fn up(&self) -> Result<Vec<i64>> {
+        self.with_lock(|guard| {
+            let applied = self.applied_versions()?;
+            let coll = self.db.collection(MIGRATIONS_COLLECTION);
+            let mut ran = Vec::new();
+
+            for migration in &self.migrations {
+                let version = migration.version();
+                if applied.contains(&version) {
+                    continue;
+                }
+
+                guard.renew()?;
+                migration.up(&self.db)?;
+
+                let record = doc! {
+                    "_id": version,
+                    "name": migration.name(),
+                    "applied_at": Utc::now(),
+                };
+
+                let result = coll.insert_one(record, None)?;
+                if let Some(exception) = result.write_exception {
+                    return Err(WriteError(exception));
+                }
+
+                ran.push(version);
+            }
+
+            Ok(ran)
+        })
+    }
+
+    /// Reverts the most recently applied migration, returning its version, or `None` if no
+    /// migrations have been applied.
+    pub fn down(&self) -> Result<Option<i64>> {
+        self.with_lock(|guard| {
+            let version = match self.applied_versions()?.pop() {
+                Some(version) => version,
+                None => return Ok(None),
+            };
+
+            let migration = match self.migrations.iter().find(|m| m.version() == version) {
+                Some(migration) => migration,
+                None => {
+                    return Err(ArgumentError(format!(
+                        "Applied migration version {} is not registered with this runner.",
+                        version
+                    )))
+                }
+            };
+
+            guard.renew()?;
+            migration.down(&self.db)?;
+
+            let coll = self.db.collection(MIGRATIONS_COLLECTION);
+            coll.delete_one(doc!{ "_id": version }, None)?;
+
+            Ok(Some(version))
+        })
+    }
+
+    // Runs `f` while holding the migration lock, releasing it afterwards.
+    fn with_lock<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&LockGuard) -> Result<T>,
+    {
+        let guard = match self.lock.try_acquire()? {
+            Some(guard) => guard,
+            None => {
+                return Err(OperationError(format!(
+                    "Schema migrations for database '{}' are locked by another runner.",
+                    self.db.name
+                )))
+            }
+        };
+
+        let result = f(&guard);
+        let released = guard.release();
+
+        // A failed migration takes precedence over a failed release.
+        let value = result?;
+        released?;
+        Ok(value)
+    }
+}
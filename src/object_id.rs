@@ -0,0 +1,94 @@
+//! ObjectId generation and helpers.
+//!
+//! Ids follow the current ObjectId specification: a 4-byte timestamp in seconds, a 5-byte random
+//! value chosen once per process, and a 3-byte counter that starts at a random value. Unlike
+//! `bson::oid::ObjectId::new`, generation never looks up the hostname and cannot fail.
+//!
+//! Since ids start with their creation time, they sort roughly in insertion order, and the
+//! `created_*` conditions select documents by when their `_id` was generated.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate chrono;
+//! # extern crate mongodb;
+//! # use chrono::{Duration, Utc};
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # use mongodb::object_id;
+//! #
+//! # fn main() {
+//! let client = Client::connect("localhost", 27017).unwrap();
+//! let events = client.db("app").collection("events");
+//!
+//! let since = Utc::now() - Duration::hours(1);
+//! let recent = events.find(Some(doc! { "_id": object_id::created_after(since) }), None).unwrap();
+//! # }
+//! ```
+use bson::{Bson, doc};
+use bson::oid::ObjectId;
+use chrono::{DateTime, TimeZone, Utc};
+
+use Error::ArgumentError;
+use Result;
+
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const COUNTER_MASK: u32 = 0x00ff_ffff;
+
+// The process's random value and the counter's random start, chosen on first use.
+static PROCESS_UNIQUE: OnceLock<([u8; 5], u32)> = OnceLock::new();
+static NEXT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Generates a new ObjectId.
+pub fn generate() -> ObjectId {
+    let timestamp = Utc::now().timestamp() as u32;
+    let (process, start) = *PROCESS_UNIQUE.get_or_init(|| (rand::random(), rand::random()));
+    let count = start.wrapping_add(NEXT_COUNT.fetch_add(1, Ordering::SeqCst) as u32) & COUNTER_MASK;
+
+    let mut bytes = [0; 12];
+    bytes[0..4].copy_from_slice(&timestamp.to_be_bytes());
+    bytes[4..9].copy_from_slice(&process);
+    bytes[9..12].copy_from_slice(&count.to_be_bytes()[1..]);
+    ObjectId::with_bytes(bytes)
+}
+
+/// Parses an id from its 24 hex digits.
+pub fn parse(s: &str) -> Result<ObjectId> {
+    ObjectId::with_string(s).map_err(|_| ArgumentError(format!("'{}' is not an ObjectId.", s)))
+}
+
+/// Returns the id's 24 hex digits.
+pub fn to_hex(id: &ObjectId) -> String {
+    id.to_hex()
+}
+
+/// Returns the time the id was generated, to the second.
+pub fn generation_time(id: &ObjectId) -> DateTime<Utc> {
+    Utc.timestamp_opt(i64::from(id.timestamp()), 0).unwrap()
+}
+
+/// Returns the smallest id generated at `time`, for range queries. Times before 1970 or after
+/// 2106 are clamped to the range ObjectIds can hold.
+pub fn from_time(time: DateTime<Utc>) -> ObjectId {
+    let seconds = time.timestamp().max(0).min(i64::from(u32::MAX));
+    ObjectId::with_timestamp(seconds as u32)
+}
+
+/// The condition that an id was generated at or after `time`.
+pub fn created_after(time: DateTime<Utc>) -> Bson {
+    Bson::Document(doc! { "$gte": from_time(time) })
+}
+
+/// The condition that an id was generated before `time`.
+pub fn created_before(time: DateTime<Utc>) -> Bson {
+    Bson::Document(doc! { "$lt": from_time(time) })
+}
+
+/// The condition that an id was generated at or after `start` and before `end`.
+pub fn created_between(start: DateTime<Utc>, end: DateTime<Utc>) -> Bson {
+    Bson::Document(doc! {
+        "$gte": from_time(start),
+        "$lt": from_time(end),
+    })
+}
@@ -0,0 +1,264 @@
+//! Revocation checking of server certificates with OCSP.
+//!
+//! TLS connections that verify the server's certificate ask the server to staple an OCSP
+//! response to the handshake. A stapled response that is signed by a trusted issuer and reports
+//! the certificate as revoked fails the connection, as does one that cannot be verified.
+//!
+//! Without a usable stapled response, the OCSP responders listed in the certificate are asked for
+//! its status, unless `tlsDisableOCSPEndpointCheck` is set. Responders that cannot be reached or
+//! whose answers cannot be trusted are passed over, so that an OCSP outage does not take the
+//! deployment down with it; only a trusted answer that the certificate is revoked fails the
+//! connection.
+//!
+//! Responders are reached the way the server is, through the client's DNS resolver and proxy,
+//! and must answer before the connection's deadline. Their answers are kept until the next
+//! update they give, so that later connections to servers with the same certificate need not
+//! ask again.
+use openssl::hash::MessageDigest;
+use openssl::ocsp::{OcspCertId, OcspCertStatus, OcspFlag, OcspRequest, OcspResponse,
+                    OcspResponseStatus};
+use openssl::ssl::SslRef;
+use openssl::stack::StackRef;
+use openssl::x509::{X509, X509Ref};
+use openssl::x509::store::X509StoreRef;
+
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// How long a responder is given to answer before it is passed over.
+const RESPONDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The clock skew, in seconds, allowed when checking that a response is current.
+const MAX_CLOCK_SKEW_SECS: u32 = 300;
+
+// The most bytes read from a responder.
+const MAX_RESPONSE_LEN: usize = 1 << 20;
+
+// Responders' answers, by the DER encoding of the certificate they are about. Each is verified
+// again when it is used, which fails once its next update has passed.
+static RESPONSES: OnceLock<Mutex<HashMap<Vec<u8>, Vec<u8>>>> = OnceLock::new();
+
+/// Checks that the certificate the server presented on `ssl` has not been revoked, asking the
+/// certificate's OCSP responders if the server stapled no usable response and
+/// `query_responders` is set.
+///
+/// Responders are connected to with `connect`, given their host, port and how long the
+/// connection may take, and must answer before `deadline`.
+pub fn check(
+    ssl: &SslRef,
+    query_responders: bool,
+    deadline: Option<Instant>,
+    connect: &dyn Fn(&str, u16, Duration) -> io::Result<TcpStream>,
+) -> io::Result<()> {
+    // A certificate trusted directly, rather than through an issuer, cannot be checked.
+    let chain = match ssl.verified_chain() {
+        Some(chain) => chain,
+        None => return Ok(()),
+    };
+    let (leaf, issuer) = match (chain.get(0), chain.get(1)) {
+        (Some(leaf), Some(issuer)) => (leaf, issuer),
+        _ => return Ok(()),
+    };
+    let store = ssl.ssl_context().cert_store();
+
+    if let Some(stapled) = ssl.ocsp_status() {
+        let status = read_response(stapled, chain, store, leaf, issuer).map_err(|err| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("The server stapled an invalid OCSP response: {}", err),
+            )
+        })?;
+
+        match status {
+            Some((status, _)) if status == OcspCertStatus::REVOKED => return Err(revoked()),
+            Some((status, _)) if status == OcspCertStatus::GOOD => return Ok(()),
+            _ => (),
+        }
+    }
+
+    if !query_responders {
+        return Ok(());
+    }
+
+    let key = leaf.to_der()?;
+    let responses = RESPONSES.get_or_init(|| Mutex::new(HashMap::new()));
+    let cached = responses.lock().unwrap_or_else(|err| err.into_inner()).get(&key).cloned();
+    if let Some(response) = cached {
+        match read_response(&response, chain, store, leaf, issuer) {
+            Ok(Some((status, _))) if status == OcspCertStatus::REVOKED => return Err(revoked()),
+            Ok(Some((status, _))) if status == OcspCertStatus::GOOD => return Ok(()),
+            _ => {
+                responses.lock().unwrap_or_else(|err| err.into_inner()).remove(&key);
+            }
+        }
+    }
+
+    let responders = match leaf.ocsp_responders() {
+        Ok(responders) => responders,
+        Err(_) => return Ok(()),
+    };
+
+    for url in &responders {
+        let end = match deadline {
+            Some(deadline) => cmp::min(deadline, Instant::now() + RESPONDER_TIMEOUT),
+            None => Instant::now() + RESPONDER_TIMEOUT,
+        };
+
+        let response = match query_responder(url, leaf, issuer, end, connect) {
+            Ok(response) => response,
+            Err(_) => continue,
+        };
+
+        let status = match read_response(&response, chain, store, leaf, issuer) {
+            Ok(Some((status, expires))) => {
+                if expires {
+                    let mut responses = responses.lock().unwrap_or_else(|err| err.into_inner());
+                    responses.insert(key.clone(), response);
+                }
+                status
+            }
+            _ => continue,
+        };
+
+        if status == OcspCertStatus::REVOKED {
+            return Err(revoked());
+        } else if status == OcspCertStatus::GOOD {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+fn revoked() -> io::Error {
+    io::Error::new(ErrorKind::PermissionDenied, "The server certificate has been revoked.")
+}
+
+// Verifies an OCSP response against the trusted certificates and returns the status it gives
+// `leaf`, with whether the response gives a next update it may be kept until, or `None` if the
+// responder could not answer or the response does not cover `leaf`.
+fn read_response(
+    der: &[u8],
+    chain: &StackRef<X509>,
+    store: &X509StoreRef,
+    leaf: &X509Ref,
+    issuer: &X509Ref,
+) -> io::Result<Option<(OcspCertStatus, bool)>> {
+    let response = OcspResponse::from_der(der)?;
+    if response.status() != OcspResponseStatus::SUCCESSFUL {
+        return Ok(None);
+    }
+
+    let basic = response.basic()?;
+    basic.verify(chain, store, OcspFlag::empty())?;
+
+    let id = OcspCertId::from_cert(MessageDigest::sha1(), leaf, issuer)?;
+    match basic.find_status(&id) {
+        Some(status) => {
+            status.check_validity(MAX_CLOCK_SKEW_SECS, None)?;
+            Ok(Some((status.status, status.next_update().is_some())))
+        }
+        None => Ok(None),
+    }
+}
+
+// Asks the responder at `url` for the status of `leaf`, returning the DER-encoded response if
+// it answers before `end`.
+fn query_responder(
+    url: &str,
+    leaf: &X509Ref,
+    issuer: &X509Ref,
+    end: Instant,
+    connect: &dyn Fn(&str, u16, Duration) -> io::Result<TcpStream>,
+) -> io::Result<Vec<u8>> {
+    let rest = match url.strip_prefix("http://") {
+        Some(rest) => rest,
+        None => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unsupported OCSP responder '{}'.", url),
+            ))
+        }
+    };
+
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rfind(':') {
+        Some(idx) if !authority.ends_with(']') => {
+            let port = authority[idx + 1..].parse::<u16>().map_err(|_| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid OCSP responder '{}'.", url),
+                )
+            })?;
+            (&authority[..idx], port)
+        }
+        _ => (authority, 80),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let mut request = OcspRequest::new()?;
+    request.add_id(OcspCertId::from_cert(MessageDigest::sha1(), leaf, issuer)?)?;
+    let body = request.to_der()?;
+
+    let mut stream = connect(host, port, remaining(end)?)?;
+    stream.set_write_timeout(Some(remaining(end)?))?;
+
+    let head = format!(
+        "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/ocsp-request\r\n\
+         Content-Length: {}\r\n\r\n",
+        path,
+        authority,
+        body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&body)?;
+
+    // Each read may take only what is left of the time allowed, so that a responder that
+    // trickles its answer cannot hold the connection past its deadline.
+    let mut response = Vec::new();
+    let mut buf = [0; 4096];
+    while response.len() < MAX_RESPONSE_LEN {
+        stream.set_read_timeout(Some(remaining(end)?))?;
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..read]);
+    }
+
+    let header_len = match response.windows(4).position(|window| window == b"\r\n\r\n") {
+        Some(idx) => idx + 4,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "The OCSP responder sent an incomplete response.",
+            ))
+        }
+    };
+
+    let headers = String::from_utf8_lossy(&response[..header_len]).into_owned();
+    let status_line = headers.lines().next().unwrap_or("");
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("The OCSP responder did not answer: {}.", status_line),
+        ));
+    }
+
+    Ok(response.split_off(header_len))
+}
+
+// Returns the time left before `end`, failing if there is none.
+fn remaining(end: Instant) -> io::Result<Duration> {
+    match end.checked_duration_since(Instant::now()) {
+        Some(remaining) if remaining > Duration::from_secs(0) => Ok(remaining),
+        _ => Err(io::Error::new(ErrorKind::TimedOut, "The OCSP responder did not answer in time.")),
+    }
+}
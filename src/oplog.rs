@@ -0,0 +1,154 @@
+//! Reading the replication oplog.
+//!
+//! Servers without change streams, before MongoDB 3.6, still record every write in the capped
+//! collection `local.oplog.rs` of each replica set member. An `OplogReader` tails it and parses
+//! each entry into an `OplogEntry`, so that changes can be fed to caches, search indexes or other
+//! stores. The position of the last entry read can be saved and passed back to resume later, as
+//! long as the oplog has not wrapped around past it in the meantime.
+//!
+//! Reading the oplog requires the `read` role on the `local` database, and a replica set;
+//! standalone servers keep no oplog.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::oplog::{OplogOperation, OplogReader};
+//! #
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let filter = doc! { "ns": "shop.orders" };
+//! for entry in OplogReader::new(&client, Some(filter), None) {
+//!     let entry = entry.unwrap();
+//!     if let OplogOperation::Insert { document } = entry.operation {
+//!         println!("new order at {}: {}", entry.timestamp, document);
+//!     }
+//! }
+//! # }
+//! ```
+use bson::{self, Bson};
+
+use coll::options::TailOptions;
+use coll::tail::Tail;
+use db::ThreadedDatabase;
+use Error::ResponseError;
+use {Client, Result, ThreadedClient};
+
+/// A change recorded in the oplog.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OplogOperation {
+    /// The document was inserted.
+    Insert { document: bson::Document },
+    /// The document selected by `query`, by its `_id`, was changed by `update`, which is either
+    /// a replacement document or a description of the changes in a server-specific format.
+    Update {
+        query: bson::Document,
+        update: bson::Document,
+    },
+    /// The document selected by `query`, by its `_id`, was deleted.
+    Delete { query: bson::Document },
+    /// A command, such as `create`, `drop` or the `applyOps` that commits a transaction, was run
+    /// on the database of the entry's namespace.
+    Command { command: bson::Document },
+    /// A no-op entry, written periodically by idle primaries.
+    Noop { message: bson::Document },
+}
+
+/// An entry of the oplog.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OplogEntry {
+    /// The entry's position in the oplog, as a BSON timestamp.
+    pub timestamp: i64,
+    /// The namespace written to, as `db.collection`, or `db.$cmd` for commands.
+    pub namespace: String,
+    pub operation: OplogOperation,
+}
+
+impl OplogEntry {
+    /// Parses an entry from its document in `local.oplog.rs`.
+    pub fn from_document(mut doc: bson::Document) -> Result<OplogEntry> {
+        let timestamp = doc.get_time_stamp("ts")
+            .map_err(|_| ResponseError(String::from("Oplog entry has no timestamp.")))?;
+
+        let namespace = match doc.remove("ns") {
+            Some(Bson::String(ns)) => ns,
+            _ => return Err(ResponseError(String::from("Oplog entry has no namespace."))),
+        };
+
+        let op = match doc.remove("op") {
+            Some(Bson::String(op)) => op,
+            _ => return Err(ResponseError(String::from("Oplog entry has no operation."))),
+        };
+
+        let o = take_document(&mut doc, "o")?;
+
+        let operation = match op.as_str() {
+            "i" => OplogOperation::Insert { document: o },
+            "u" => {
+                OplogOperation::Update {
+                    query: take_document(&mut doc, "o2")?,
+                    update: o,
+                }
+            }
+            "d" => OplogOperation::Delete { query: o },
+            "c" => OplogOperation::Command { command: o },
+            "n" => OplogOperation::Noop { message: o },
+            _ => return Err(ResponseError(format!("Unknown oplog operation '{}'.", op))),
+        };
+
+        Ok(OplogEntry {
+            timestamp,
+            namespace,
+            operation,
+        })
+    }
+}
+
+/// A blocking iterator over the entries of the oplog.
+#[derive(Debug)]
+pub struct OplogReader {
+    tail: Tail,
+}
+
+impl OplogReader {
+    /// Tails the oplog of the member `client` reads from, returning the entries that match
+    /// `filter`, such as `{ "ns": "shop.orders" }`.
+    ///
+    /// Set `TailOptions::resume_after` to the timestamp of the last entry already processed, as
+    /// a `Bson::TimeStamp`, to continue after it. The resume field is always `ts`.
+    pub fn new(
+        client: &Client,
+        filter: Option<bson::Document>,
+        options: Option<TailOptions>,
+    ) -> OplogReader {
+        let mut options = options.unwrap_or_default();
+        options.resume_field = Some(String::from("ts"));
+        options.oplog_replay = true;
+
+        let coll = client.db("local").collection("oplog.rs");
+        OplogReader { tail: coll.tail(filter, Some(options)) }
+    }
+
+    /// Returns the timestamp of the last entry read, from which a later reader can resume.
+    pub fn last_timestamp(&self) -> Option<i64> {
+        match self.tail.last_position() {
+            Some(&Bson::TimeStamp(ts)) => Some(ts),
+            _ => None,
+        }
+    }
+}
+
+impl Iterator for OplogReader {
+    type Item = Result<OplogEntry>;
+
+    fn next(&mut self) -> Option<Result<OplogEntry>> {
+        self.tail.next().map(|result| result.and_then(OplogEntry::from_document))
+    }
+}
+
+fn take_document(doc: &mut bson::Document, key: &str) -> Result<bson::Document> {
+    match doc.remove(key) {
+        Some(Bson::Document(value)) => Ok(value),
+        _ => Err(ResponseError(format!("Oplog entry has no '{}' document.", key))),
+    }
+}
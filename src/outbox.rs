@@ -0,0 +1,282 @@
+//! The transactional outbox pattern.
+//!
+//! Services record events in an outbox collection with `Outbox::publish`, and one or more named
+//! consumers deliver them elsewhere with `Outbox::poll` or `Outbox::run`. Each consumer keeps a
+//! checkpoint of the last event it delivered, advanced only after its handler succeeds, so every
+//! event is delivered at least once per consumer; handlers must tolerate duplicates.
+//!
+//! The driver supports neither multi-document transactions nor change streams. `publish` is
+//! therefore a single insert, which applications should issue only after their own write has
+//! succeeded, and consumers discover new events by polling. Events are delivered in order of
+//! their `created_at` time, ties broken by `_id`, and checkpoints record both. Because client
+//! clocks are not strictly ordered across processes, consumers only read events older than a
+//! settle window, so that an event inserted slightly late is not skipped past.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # use mongodb::outbox::Outbox;
+//! #
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let outbox = Outbox::new(&client.db("app").collection("outbox"), None);
+//! outbox.publish("user.created", doc!{ "user_id": 42 }).unwrap();
+//!
+//! let delivered = outbox.poll("mailer", |message| {
+//!     println!("{}: {}", message.topic, message.payload);
+//!     Ok(())
+//! }).unwrap();
+//! # }
+//! ```
+use bson::{self, Bson, doc, oid};
+use chrono::{DateTime, Duration, Utc};
+
+use coll::Collection;
+use coll::options::{FindOptions, UpdateOptions};
+use db::{Database, ThreadedDatabase};
+use object_id;
+use Error::{ResponseError, WriteError};
+use Result;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration as StdDuration;
+
+/// The default number of events read per poll.
+pub const DEFAULT_OUTBOX_BATCH_SIZE: i32 = 100;
+/// The default delay between empty polls in `Outbox::run`; 1000 ms.
+pub const DEFAULT_OUTBOX_POLL_INTERVAL_MS: i64 = 1000;
+/// The default age an event must reach before consumers read it; 1000 ms.
+pub const DEFAULT_OUTBOX_SETTLE_MS: i64 = 1000;
+
+/// Options for outbox consumption.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutboxOptions {
+    /// The collection holding consumer checkpoints; defaults to the outbox collection's name
+    /// suffixed with `.checkpoints`.
+    pub checkpoint_collection: Option<String>,
+    /// How many events are read per poll; defaults to 100.
+    pub batch_size: Option<i32>,
+    /// How long `run` sleeps after a poll that delivered nothing; defaults to 1000 ms.
+    pub poll_interval_ms: Option<i64>,
+    /// How old an event must be before consumers read it; defaults to 1000 ms.
+    pub settle_ms: Option<i64>,
+}
+
+impl OutboxOptions {
+    pub fn new() -> OutboxOptions {
+        Default::default()
+    }
+}
+
+/// An event recorded in the outbox.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutboxMessage {
+    pub id: oid::ObjectId,
+    pub topic: String,
+    pub payload: bson::Document,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An outbox collection and its consumer checkpoints.
+#[derive(Clone, Debug)]
+pub struct Outbox {
+    db: Database,
+    coll: String,
+    checkpoints: String,
+    batch_size: i32,
+    poll_interval_ms: i64,
+    settle_ms: i64,
+}
+
+impl Outbox {
+    /// Creates an outbox stored in the given collection.
+    pub fn new(coll: &Collection, options: Option<OutboxOptions>) -> Outbox {
+        let options = options.unwrap_or_default();
+        let name = coll.name();
+
+        Outbox {
+            db: coll.db.clone(),
+            checkpoints: options.checkpoint_collection.unwrap_or_else(
+                || format!("{}.checkpoints", name),
+            ),
+            coll: name,
+            batch_size: options.batch_size.unwrap_or(DEFAULT_OUTBOX_BATCH_SIZE),
+            poll_interval_ms: options.poll_interval_ms.unwrap_or(
+                DEFAULT_OUTBOX_POLL_INTERVAL_MS,
+            ),
+            settle_ms: options.settle_ms.unwrap_or(DEFAULT_OUTBOX_SETTLE_MS),
+        }
+    }
+
+    /// Returns the collection holding events.
+    pub fn collection(&self) -> Collection {
+        self.db.collection(&self.coll)
+    }
+
+    /// Records an event, returning its id.
+    pub fn publish(&self, topic: &str, payload: bson::Document) -> Result<oid::ObjectId> {
+        let id = object_id::generate();
+
+        let event = doc! {
+            "_id": id.clone(),
+            "topic": topic,
+            "payload": payload,
+            "created_at": Utc::now(),
+        };
+
+        let result = self.collection().insert_one(event, None)?;
+        match result.write_exception {
+            Some(exception) => Err(WriteError(exception)),
+            None => Ok(id),
+        }
+    }
+
+    /// Returns the id of the last event delivered to `consumer`, if any.
+    pub fn checkpoint(&self, consumer: &str) -> Result<Option<oid::ObjectId>> {
+        Ok(self.checkpoint_position(consumer)?.map(|(_, id)| id))
+    }
+
+    // Returns the creation time and id of the last event delivered to `consumer`, if any.
+    fn checkpoint_position(
+        &self,
+        consumer: &str,
+    ) -> Result<Option<(Option<DateTime<Utc>>, oid::ObjectId)>> {
+        let checkpoints = self.db.collection(&self.checkpoints);
+
+        match checkpoints.find_one(Some(doc!{ "_id": consumer }), None)? {
+            Some(mut doc) => {
+                let created_at = match doc.get("last_created_at") {
+                    Some(&Bson::UtcDatetime(created_at)) => Some(created_at),
+                    _ => None,
+                };
+
+                match doc.remove("last_id") {
+                    Some(Bson::ObjectId(id)) => Ok(Some((created_at, id))),
+                    _ => Ok(None),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Delivers the next batch of events to `handler` in order of their creation time, returning
+    /// how many were delivered.
+    ///
+    /// The checkpoint advances after each event the handler accepts. If the handler fails,
+    /// delivery stops and its error is returned; the failed event is redelivered next time.
+    pub fn poll<F>(&self, consumer: &str, mut handler: F) -> Result<usize>
+    where
+        F: FnMut(&OutboxMessage) -> Result<()>,
+    {
+        let settled = Utc::now() - Duration::milliseconds(self.settle_ms);
+        let mut filter = doc!{ "created_at": { "$lt": settled } };
+
+        // Events after the checkpoint: created later, or at the same time with a greater id.
+        match self.checkpoint_position(consumer)? {
+            Some((Some(last_created_at), last_id)) => {
+                filter.insert(
+                    "$or",
+                    vec![
+                        Bson::Document(doc!{ "created_at": { "$gt": last_created_at } }),
+                        Bson::Document(doc!{
+                            "created_at": last_created_at,
+                            "_id": { "$gt": last_id },
+                        }),
+                    ],
+                );
+            }
+            // Checkpoints saved before creation times were recorded hold only the id.
+            Some((None, last_id)) => {
+                filter.insert("_id", doc!{ "$gt": last_id });
+            }
+            None => (),
+        }
+
+        let mut options = FindOptions::new();
+        options.sort = Some(doc!{ "created_at": 1, "_id": 1 });
+        options.limit = Some(i64::from(self.batch_size));
+        options.batch_size = Some(self.batch_size);
+
+        let mut delivered = 0;
+
+        for result in self.collection().find(Some(filter), Some(options))? {
+            let message = Outbox::message_from_document(result?)?;
+            handler(&message)?;
+            self.save_checkpoint(consumer, &message)?;
+            delivered += 1;
+        }
+
+        Ok(delivered)
+    }
+
+    /// Delivers events to `handler` until `stop` is set or the handler fails, sleeping between
+    /// polls that find nothing to deliver.
+    pub fn run<F>(&self, consumer: &str, mut handler: F, stop: &AtomicBool) -> Result<()>
+    where
+        F: FnMut(&OutboxMessage) -> Result<()>,
+    {
+        let interval = StdDuration::from_millis(self.poll_interval_ms.max(0) as u64);
+
+        while !stop.load(Ordering::SeqCst) {
+            if self.poll(consumer, &mut handler)? == 0 {
+                thread::sleep(interval);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn save_checkpoint(&self, consumer: &str, message: &OutboxMessage) -> Result<()> {
+        let mut options = UpdateOptions::new();
+        options.upsert = Some(true);
+
+        let result = self.db.collection(&self.checkpoints).update_one(
+            doc!{ "_id": consumer },
+            doc! {
+                "$set": {
+                    "last_id": message.id.clone(),
+                    "last_created_at": message.created_at,
+                    "updated_at": Utc::now(),
+                },
+            },
+            Some(options),
+        )?;
+
+        match result.write_exception {
+            Some(exception) => Err(WriteError(exception)),
+            None => Ok(()),
+        }
+    }
+
+    fn message_from_document(mut doc: bson::Document) -> Result<OutboxMessage> {
+        let id = match doc.remove("_id") {
+            Some(Bson::ObjectId(id)) => id,
+            _ => return Err(ResponseError(String::from("Outbox event has no ObjectId _id."))),
+        };
+
+        let topic = match doc.remove("topic") {
+            Some(Bson::String(topic)) => topic,
+            _ => return Err(ResponseError(format!("Outbox event {} has no topic.", id))),
+        };
+
+        let payload = match doc.remove("payload") {
+            Some(Bson::Document(payload)) => payload,
+            _ => bson::Document::new(),
+        };
+
+        let created_at = match doc.get("created_at") {
+            Some(&Bson::UtcDatetime(created_at)) => created_at,
+            _ => return Err(ResponseError(format!("Outbox event {} has no created_at.", id))),
+        };
+
+        Ok(OutboxMessage {
+            id,
+            topic,
+            payload,
+            created_at,
+        })
+    }
+}
@@ -3,22 +3,33 @@ use error::Error::{self, ArgumentError, OperationError};
 use error::Result;
 
 use Client;
+use apm::{ConnectionEstablished, EventRunner};
+#[cfg(feature = "metrics")]
+use apm::metrics;
 use coll::options::FindOptions;
 use command_type::CommandType;
 use connstring::Host;
 use cursor::Cursor;
-use stream::{Stream, StreamConnector};
+use stream::{ConnectTimings, Stream, StreamConnector};
+use timeout::Deadline;
 use wire_protocol::flags::OpQueryFlags;
+use wire_protocol::limits::DecodeLimits;
+use wire_protocol::operations::{Message, RawReply};
 
-use bson::{bson, doc};
+use bson::{bson, doc, Bson};
 use bufstream::BufStream;
 
-use std::fmt;
+use std::{fmt, io, mem};
+use std::io::Write;
 use std::sync::{Arc, Condvar, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 pub static DEFAULT_POOL_SIZE: usize = 5;
 
+// The largest buffer a connection keeps for reuse once returned to the pool.
+const MAX_IDLE_BUFFER_BYTES: usize = 1024 * 1024;
+
 /// Handles threaded connections to a MongoDB server.
 #[derive(Clone)]
 pub struct ConnectionPool {
@@ -46,10 +57,27 @@ struct Pool {
     // The current number of open connections.
     pub len: Arc<AtomicUsize>,
     // The idle socket pool.
-    sockets: Vec<BufStream<Stream>>,
+    sockets: Vec<Idle>,
     // The pool iteration. When a server monitor fails to execute ismaster,
     // the connection pool is cleared and the iteration is incremented.
     iteration: usize,
+    // The server's address, as a label for metrics.
+    #[cfg(feature = "metrics")]
+    host: Host,
+}
+
+#[cfg(feature = "metrics")]
+impl Pool {
+    // Publishes the number of open connections.
+    fn record_open(&self) {
+        metrics::connections_open(&self.host, self.len.load(Ordering::SeqCst));
+    }
+}
+
+// An idle socket and the buffer its messages are encoded and read into.
+struct Idle {
+    socket: BufStream<Stream>,
+    buffer: Vec<u8>,
 }
 
 /// Holds an available socket, with logic to return the socket
@@ -58,6 +86,8 @@ pub struct PooledStream {
     // This socket option will always be Some(stream) until it is
     // returned to the pool using take().
     socket: Option<BufStream<Stream>>,
+    // The server the stream is connected to.
+    host: Host,
     // A reference to the pool that the stream was taken from.
     pool: Arc<Mutex<Pool>>,
     // A reference to the waiting condvar associated with the pool.
@@ -66,6 +96,12 @@ pub struct PooledStream {
     iteration: usize,
     // Whether the handshake occurred successfully.
     successful_handshake: bool,
+    // The deadline of the operation using the stream, applied as socket timeouts.
+    deadline: Deadline,
+    // Whether the stream was left in an unknown state and must not be reused.
+    discarded: bool,
+    // Scratch space for encoding messages and reading replies, kept with the socket.
+    buffer: Vec<u8>,
 }
 
 impl PooledStream {
@@ -73,6 +109,77 @@ impl PooledStream {
     pub fn get_socket(&mut self) -> &mut BufStream<Stream> {
         self.socket.as_mut().unwrap()
     }
+
+    /// Returns the server the stream is connected to.
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    /// Bounds subsequent reads and writes on the stream by the deadline.
+    pub fn set_deadline(&mut self, deadline: Deadline) -> Result<()> {
+        if deadline.is_none() && self.deadline.is_none() {
+            return Ok(());
+        }
+
+        let remaining = deadline.remaining("connection checkout")?;
+        self.get_socket().get_ref().set_timeout(remaining)?;
+        self.deadline = deadline;
+        Ok(())
+    }
+
+    /// Writes a message to the socket, encoding it in the stream's reusable buffer first.
+    ///
+    /// The encoded message is written straight to the connection, rather than copied through the
+    /// socket's write buffer, so that it goes out in as few system calls as the connection
+    /// allows and is sent at once, since connections disable Nagle's algorithm.
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let socket = self.socket.as_mut().unwrap();
+        // Anything already written through the socket's buffer must go first.
+        socket.flush()?;
+        message.write_with_buffer(socket.get_mut(), &mut self.buffer)
+    }
+
+    /// Reads a reply from the socket into the stream's reusable buffer and decodes it.
+    pub fn read_reply(&mut self, limits: &DecodeLimits) -> Result<Message> {
+        let socket = self.socket.as_mut().unwrap();
+        Message::read_with_buffer(socket, limits, &mut self.buffer)
+    }
+
+    /// Reads a reply from the socket into the stream's reusable buffer, leaving its documents
+    /// encoded.
+    pub fn read_raw_reply(&mut self, limits: &DecodeLimits) -> Result<RawReply> {
+        let socket = self.socket.as_mut().unwrap();
+        RawReply::read_with_buffer(socket, limits, &mut self.buffer)
+    }
+
+    /// Returns the deadline bounding reads and writes on the stream.
+    pub fn deadline(&self) -> Deadline {
+        self.deadline.clone()
+    }
+
+    /// Prevents the stream from being returned to the pool, e.g. after a partial read or write.
+    pub fn discard(&mut self) {
+        self.discarded = true;
+    }
+
+    /// Discards the stream if `result` is a failure, since a failed read or write leaves the
+    /// connection mid-message. Socket timeouts caused by the deadline become `Error::Timeout`.
+    pub fn check<T>(&mut self, result: Result<T>) -> Result<T> {
+        match result {
+            Ok(val) => Ok(val),
+            Err(Error::IoError(ref err))
+                if !self.deadline.is_none() &&
+                       (err.kind() == io::ErrorKind::TimedOut ||
+                            err.kind() == io::ErrorKind::WouldBlock) => {
+                self.discard();
+                Err(self.deadline.timeout("a network round trip"))
+            }
+            Err(err) => {
+                self.discard();
+                Err(err)
+            }
+        }
+    }
 }
 
 impl Drop for PooledStream {
@@ -82,11 +189,42 @@ impl Drop for PooledStream {
             return;
         }
 
+        if self.discarded {
+            if let Ok(locked) = self.pool.lock() {
+                if self.iteration == locked.iteration {
+                    let _ = locked.len.fetch_sub(1, Ordering::SeqCst);
+                    #[cfg(feature = "metrics")]
+                    locked.record_open();
+                    // A new connection may now be opened in place of this one.
+                    self.wait_lock.notify_one();
+                }
+            }
+            return;
+        }
+
+        // Pooled sockets block indefinitely until the next operation sets a deadline.
+        if !self.deadline.is_none() {
+            if let Some(ref socket) = self.socket {
+                if socket.get_ref().set_timeout(None).is_err() {
+                    return;
+                }
+            }
+        }
+
         // Attempt to lock and return the socket to the pool,
         // or give up if the pool lock has been poisoned.
         if let Ok(mut locked) = self.pool.lock() {
             if self.iteration == locked.iteration {
-                locked.sockets.push(self.socket.take().unwrap());
+                // Don't hold on to the space taken by an unusually large message.
+                let mut buffer = mem::take(&mut self.buffer);
+                if buffer.capacity() > MAX_IDLE_BUFFER_BYTES {
+                    buffer = Vec::new();
+                }
+
+                locked.sockets.push(Idle {
+                    socket: self.socket.take().unwrap(),
+                    buffer,
+                });
                 // Notify waiting threads that the pool has been repopulated.
                 self.wait_lock.notify_one();
             }
@@ -103,14 +241,16 @@ impl ConnectionPool {
     /// Returns a connection pool with a specified capped size.
     pub fn with_size(host: Host, connector: StreamConnector, size: usize) -> ConnectionPool {
         ConnectionPool {
-            host: host,
             wait_lock: Arc::new(Condvar::new()),
             inner: Arc::new(Mutex::new(Pool {
                 len: Arc::new(AtomicUsize::new(0)),
                 size: size,
                 sockets: Vec::with_capacity(size),
                 iteration: 0,
+                #[cfg(feature = "metrics")]
+                host: host.clone(),
             })),
+            host: host,
             stream_connector: connector,
         }
     }
@@ -134,6 +274,8 @@ impl ConnectionPool {
             locked.iteration += 1;
             locked.sockets.clear();
             locked.len.store(0, Ordering::SeqCst);
+            #[cfg(feature = "metrics")]
+            locked.record_open();
         }
     }
 
@@ -141,6 +283,16 @@ impl ConnectionPool {
     /// the pool has not reached its maximum size, a new socket will connect.
     /// Otherwise, the function will block until a socket is returned to the pool.
     pub fn acquire_stream(&self, client: Client) -> Result<PooledStream> {
+        self.acquire_stream_with_deadline(client, Deadline::none())
+    }
+
+    /// Attempts to acquire a connected socket as with `acquire_stream`, failing with an
+    /// `Error::Timeout` if none can be acquired before the deadline.
+    pub fn acquire_stream_with_deadline(
+        &self,
+        client: Client,
+        deadline: Deadline,
+    ) -> Result<PooledStream> {
         let mut locked = self.inner.lock()?;
         if locked.size == 0 {
             return Err(OperationError(String::from(
@@ -150,60 +302,116 @@ impl ConnectionPool {
 
         loop {
             // Acquire available existing socket
-            if let Some(stream) = locked.sockets.pop() {
+            if let Some(idle) = locked.sockets.pop() {
+                #[cfg(feature = "metrics")]
+                metrics::connection_checked_out(&self.host);
+
                 return Ok(PooledStream {
-                    socket: Some(stream),
+                    socket: Some(idle.socket),
+                    host: self.host.clone(),
                     pool: self.inner.clone(),
                     wait_lock: self.wait_lock.clone(),
                     iteration: locked.iteration,
                     successful_handshake: true,
+                    deadline: Deadline::none(),
+                    discarded: false,
+                    buffer: idle.buffer,
                 });
             }
 
             // Attempt to make a new connection
             let len = locked.len.load(Ordering::SeqCst);
             if len < locked.size {
-                let socket = self.connect()?;
+                let (socket, timings) = self.connect(&client, &deadline)?;
                 let mut stream = PooledStream {
                     socket: Some(socket),
+                    host: self.host.clone(),
                     pool: self.inner.clone(),
                     wait_lock: self.wait_lock.clone(),
                     iteration: locked.iteration,
                     successful_handshake: false,
+                    deadline: Deadline::none(),
+                    discarded: false,
+                    buffer: Vec::new(),
+                };
+
+                stream.set_deadline(deadline)?;
+
+                let start = Instant::now();
+                let server_connection_id = self.handshake(client.clone(), &mut stream)?;
+                let handshake = start.elapsed();
+
+                let connection_string = match stream.get_socket().get_ref().peer_addr() {
+                    Ok(addr) => addr.to_string(),
+                    Err(_) => String::new(),
+                };
+                let established = ConnectionEstablished {
+                    host: self.host.to_string(),
+                    connection_string,
+                    server_connection_id,
+                    dns_duration: as_nanos(timings.dns),
+                    tcp_duration: as_nanos(timings.tcp),
+                    tls_duration: timings.tls.map(as_nanos),
+                    handshake_duration: as_nanos(handshake),
                 };
 
-                self.handshake(client, &mut stream)?;
                 let _ = locked.len.fetch_add(1, Ordering::SeqCst);
+
+                #[cfg(feature = "metrics")]
+                {
+                    locked.record_open();
+                    metrics::connection_checked_out(&self.host);
+                }
+
+                // Hooks may use the client, so they must not run while the pool is locked.
+                drop(locked);
+                let _ = client.run_connection_hooks(&established);
                 return Ok(stream);
             }
 
             // Release lock and wait for pool to be repopulated
-            locked = self.wait_lock.wait(locked)?;
+            locked = match deadline.remaining("connection checkout")? {
+                Some(remaining) => self.wait_lock.wait_timeout(locked, remaining)?.0,
+                None => self.wait_lock.wait(locked)?,
+            };
         }
     }
 
     // Connects to a MongoDB server as defined by the initial configuration.
-    fn connect(&self) -> Result<BufStream<Stream>> {
-        match self.stream_connector.connect(
+    fn connect(
+        &self,
+        client: &Client,
+        deadline: &Deadline,
+    ) -> Result<(BufStream<Stream>, ConnectTimings)> {
+        let timeout = deadline.remaining("connection establishment")?;
+
+        match self.stream_connector.connect_with_proxy(
             &self.host.host_name[..],
             self.host.port,
+            timeout,
+            &*client.dns_resolver,
+            client.proxy.as_ref(),
         ) {
-            Ok(s) => Ok(BufStream::new(s)),
+            Err(ref e) if timeout.is_some() && e.kind() == io::ErrorKind::TimedOut => {
+                Err(deadline.timeout("connection establishment"))
+            }
+            Ok((s, timings)) => Ok((BufStream::new(s), timings)),
             Err(e) => Err(Error::from(e)),
         }
     }
 
-    // This sends the client metadata to the server as described by the handshake spec.
+    // This sends the client metadata to the server as described by the handshake spec, returning
+    // the server's id for the connection if it reports one.
     //
     // See https://github.com/mongodb/specifications/blob/master/source/mongodb-handshake/handshake.rst
-    fn handshake(&self, client: Client, stream: &mut PooledStream) -> Result<()> {
+    fn handshake(&self, client: Client, stream: &mut PooledStream) -> Result<Option<i64>> {
         let mut options = FindOptions::new();
         options.limit = Some(1);
         options.batch_size = Some(1);
 
         let flags = OpQueryFlags::with_find_options(&options);
 
-        Cursor::query_with_stream(
+        let mut cursor = Cursor::query_with_stream(
             stream,
             client,
             String::from("local.$cmd"),
@@ -229,6 +437,22 @@ impl ConnectionPool {
 
         stream.successful_handshake = true;
 
-        Ok(())
+        let connection_id = match cursor.next() {
+            Some(Ok(reply)) => {
+                match reply.get("connectionId") {
+                    Some(&Bson::I32(id)) => Some(i64::from(id)),
+                    Some(&Bson::I64(id)) => Some(id),
+                    Some(&Bson::FloatingPoint(id)) => Some(id as i64),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        Ok(connection_id)
     }
 }
+
+fn as_nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
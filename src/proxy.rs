@@ -0,0 +1,309 @@
+//! Connecting through a proxy.
+//!
+//! A client given a proxy, either as `ClientOptions::proxy` or with the `proxyHost`,
+//! `proxyPort`, `proxyUsername` and `proxyPassword` connection string options, opens every
+//! connection to the proxy and asks it for a tunnel to the server, for deployments that are only
+//! reachable through a bastion host. TLS, when enabled, runs through the tunnel to the server.
+//!
+//! Connection string options configure a SOCKS5 proxy, on port 1080 by default. An HTTP proxy
+//! that accepts `CONNECT` requests is configured with `ProxyOptions::http`:
+//!
+//! ```no_run
+//! # use mongodb::{Client, ClientOptions, ThreadedClient};
+//! # use mongodb::proxy::ProxyOptions;
+//! #
+//! let mut options = ClientOptions::new();
+//! options.proxy = Some(ProxyOptions::http("bastion.example.com", 3128));
+//!
+//! let client = Client::with_uri_and_options("mongodb://db.internal:27017", options).unwrap();
+//! ```
+//!
+//! The proxy is looked up with the client's `DnsResolver`, while server host names are passed to
+//! the proxy as they are, to be resolved on its side.
+use connstring::ConnectionOptions;
+use Error::ArgumentError;
+use Result;
+
+use data_encoding::BASE64;
+
+use std::fmt;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{IpAddr, TcpStream};
+
+/// The port of a SOCKS5 proxy unless `proxyPort` says otherwise.
+pub const DEFAULT_SOCKS5_PORT: u16 = 1080;
+
+// The most bytes read for the status line and headers of an HTTP proxy's reply.
+const MAX_HTTP_RESPONSE_LEN: usize = 8192;
+
+/// The protocol spoken to a proxy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProxyKind {
+    /// A SOCKS5 proxy, as described by RFC 1928.
+    Socks5,
+    /// An HTTP proxy accepting `CONNECT` requests.
+    Http,
+}
+
+/// A proxy to tunnel connections through.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ProxyOptions {
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    /// The username to authenticate to the proxy with, if it requires one.
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for ProxyOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProxyOptions")
+            .field("kind", &self.kind)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl ProxyOptions {
+    /// A SOCKS5 proxy without authentication.
+    pub fn socks5(host: &str, port: u16) -> ProxyOptions {
+        ProxyOptions {
+            kind: ProxyKind::Socks5,
+            host: String::from(host),
+            port,
+            username: None,
+            password: None,
+        }
+    }
+
+    /// An HTTP proxy without authentication.
+    pub fn http(host: &str, port: u16) -> ProxyOptions {
+        ProxyOptions { kind: ProxyKind::Http, ..ProxyOptions::socks5(host, port) }
+    }
+
+    /// Authenticates to the proxy with `username` and `password`.
+    pub fn with_credentials(mut self, username: &str, password: &str) -> ProxyOptions {
+        self.username = Some(String::from(username));
+        self.password = Some(String::from(password));
+        self
+    }
+
+    /// Reads a SOCKS5 proxy from the `proxyHost`, `proxyPort`, `proxyUsername` and
+    /// `proxyPassword` connection string options, if `proxyHost` is given.
+    pub fn from_connection_options(options: &ConnectionOptions) -> Result<Option<ProxyOptions>> {
+        let host = match options.get("proxyHost") {
+            Some(host) => host,
+            None => {
+                let dependent = ["proxyPort", "proxyUsername", "proxyPassword"];
+                return match dependent.iter().find(|key| options.get(key).is_some()) {
+                    Some(key) => Err(ArgumentError(format!("{} requires proxyHost.", key))),
+                    None => Ok(None),
+                };
+            }
+        };
+
+        let port = match options.get("proxyPort") {
+            Some(value) => value.parse::<u16>().map_err(|_| {
+                ArgumentError(format!("Invalid proxyPort '{}'.", value))
+            })?,
+            None => DEFAULT_SOCKS5_PORT,
+        };
+
+        let mut proxy = ProxyOptions::socks5(host, port);
+        proxy.username = options.get("proxyUsername").cloned();
+        proxy.password = options.get("proxyPassword").cloned();
+        proxy.validate()?;
+
+        Ok(Some(proxy))
+    }
+
+    /// Checks that the proxy has a host, and a username and password given together that
+    /// the protocol can carry.
+    pub fn validate(&self) -> Result<()> {
+        if self.host.is_empty() {
+            return Err(ArgumentError(String::from("The proxy host must not be empty.")));
+        }
+
+        match (&self.username, &self.password) {
+            (Some(username), Some(password)) => {
+                if username.is_empty() {
+                    return Err(ArgumentError(
+                        String::from("The proxy username must not be empty."),
+                    ));
+                }
+
+                // SOCKS5 sends each with a one-byte length.
+                let too_long = username.len() > 255 || password.len() > 255;
+                if self.kind == ProxyKind::Socks5 && too_long {
+                    return Err(ArgumentError(String::from(
+                        "A SOCKS5 proxy username and password must each be at most 255 bytes.",
+                    )));
+                }
+
+                Ok(())
+            }
+            (None, None) => Ok(()),
+            _ => Err(ArgumentError(String::from(
+                "A proxy username and password must be given together.",
+            ))),
+        }
+    }
+
+    /// Asks the proxy, over `stream`, for a tunnel to `host` and `port`. Once this returns,
+    /// everything written to `stream` reaches the server.
+    pub fn tunnel(&self, stream: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+        match self.kind {
+            ProxyKind::Socks5 => self.socks5_connect(stream, host, port),
+            ProxyKind::Http => self.http_connect(stream, host, port),
+        }
+    }
+
+    fn socks5_connect(&self, stream: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+        let credentials = match (&self.username, &self.password) {
+            (Some(username), Some(password)) => Some((username, password)),
+            _ => None,
+        };
+
+        // Offer no authentication, or username and password authentication if configured.
+        let greeting: &[u8] = match credentials {
+            Some(_) => &[0x05, 0x02, 0x00, 0x02],
+            None => &[0x05, 0x01, 0x00],
+        };
+        stream.write_all(greeting)?;
+
+        let mut reply = [0; 2];
+        stream.read_exact(&mut reply)?;
+        if reply[0] != 0x05 {
+            return Err(proxy_error("The proxy does not speak SOCKS5."));
+        }
+
+        match (reply[1], credentials) {
+            (0x00, _) => (),
+            (0x02, Some((username, password))) => {
+                let mut auth = vec![0x01, username.len() as u8];
+                auth.extend_from_slice(username.as_bytes());
+                auth.push(password.len() as u8);
+                auth.extend_from_slice(password.as_bytes());
+                stream.write_all(&auth)?;
+
+                stream.read_exact(&mut reply)?;
+                if reply[1] != 0x00 {
+                    return Err(io::Error::new(
+                        ErrorKind::PermissionDenied,
+                        "The SOCKS5 proxy rejected the username and password.",
+                    ));
+                }
+            }
+            _ => return Err(proxy_error("The SOCKS5 proxy accepts none of the offered methods.")),
+        }
+
+        let mut request = vec![0x05, 0x01, 0x00];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(0x01);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(0x04);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                if host.len() > 255 {
+                    return Err(proxy_error("The host name is too long for SOCKS5."));
+                }
+                request.push(0x03);
+                request.push(host.len() as u8);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request)?;
+
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply)?;
+        if reply[1] != 0x00 {
+            return Err(proxy_error(&format!(
+                "The SOCKS5 proxy could not connect to {}:{}: {}.",
+                host,
+                port,
+                socks5_reply_message(reply[1])
+            )));
+        }
+
+        // Skip the address the proxy bound, which is of no use to the client.
+        let address_len = match reply[3] {
+            0x01 => 4,
+            0x04 => 16,
+            0x03 => {
+                let mut len = [0; 1];
+                stream.read_exact(&mut len)?;
+                len[0] as usize
+            }
+            _ => return Err(proxy_error("The SOCKS5 proxy sent an unknown address type.")),
+        };
+        let mut bound = vec![0; address_len + 2];
+        stream.read_exact(&mut bound)
+    }
+
+    fn http_connect(&self, stream: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+        let authority = if host.contains(':') {
+            format!("[{}]:{}", host, port)
+        } else {
+            format!("{}:{}", host, port)
+        };
+
+        let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            let credentials = BASE64.encode(format!("{}:{}", username, password).as_bytes());
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+
+        // Read a byte at a time, so that nothing the server sends through the tunnel is consumed.
+        let mut response = Vec::new();
+        let mut byte = [0; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_HTTP_RESPONSE_LEN {
+                return Err(proxy_error("The HTTP proxy's response headers are too long."));
+            }
+            stream.read_exact(&mut byte)?;
+            response.push(byte[0]);
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or("");
+        let status = status_line.split_whitespace().nth(1).unwrap_or("");
+        if !status.starts_with('2') {
+            return Err(proxy_error(&format!(
+                "The HTTP proxy could not connect to {}: {}.",
+                authority,
+                status_line
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn proxy_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message)
+}
+
+fn socks5_reply_message(reply: u8) -> &'static str {
+    match reply {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
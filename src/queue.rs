@@ -0,0 +1,323 @@
+//! At-least-once job queues.
+//!
+//! A `Queue` stores jobs as documents in a collection. Consumers claim a job by atomically
+//! hiding it for a visibility timeout; a consumer that acknowledges the job deletes it, while one
+//! that crashes or stalls simply lets the timeout lapse, after which the job becomes claimable
+//! again. Jobs that keep failing are moved to a dead-letter collection once they exceed the
+//! configured number of attempts.
+//!
+//! The driver does not support change streams, so `Queue::wait` discovers new jobs by polling.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # use mongodb::queue::Queue;
+//! #
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let queue = Queue::new(&client.db("app").collection("emails"), None);
+//! queue.ensure_indexes().unwrap();
+//! queue.push(doc!{ "to": "user@example.com" }).unwrap();
+//!
+//! if let Some(job) = queue.wait(5000).unwrap() {
+//!     println!("sending {}", job.payload);
+//!     queue.ack(&job).unwrap();
+//! }
+//! # }
+//! ```
+use bson::{self, Bson, doc};
+use chrono::{DateTime, Duration, Utc};
+
+use coll::Collection;
+use coll::options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument};
+use db::{Database, ThreadedDatabase};
+use object_id;
+use Error::{ResponseError, WriteError};
+use Result;
+
+use std::time::Duration as StdDuration;
+
+/// The default visibility timeout of a claimed job; 30000 ms.
+pub const DEFAULT_VISIBILITY_TIMEOUT_MS: i64 = 30000;
+/// The default number of claims a job may receive before it is dead-lettered.
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+/// The default delay between polls in `Queue::wait`; 500 ms.
+pub const DEFAULT_POLL_INTERVAL_MS: i64 = 500;
+
+/// Options for queue behavior.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueueOptions {
+    /// How long a claimed job stays hidden from other consumers; defaults to 30000 ms.
+    pub visibility_timeout_ms: Option<i64>,
+    /// How many claims a job may receive before being dead-lettered; defaults to 5.
+    pub max_attempts: Option<i32>,
+    /// The dead-letter collection; defaults to the queue collection's name suffixed with
+    /// `.dead`.
+    pub dead_letter_collection: Option<String>,
+    /// If set, `ensure_indexes` adds a TTL index expiring dead-lettered jobs after this many
+    /// seconds.
+    pub dead_letter_expire_after_seconds: Option<i32>,
+    /// How long `wait` sleeps between polls; defaults to 500 ms.
+    pub poll_interval_ms: Option<i64>,
+}
+
+impl QueueOptions {
+    pub fn new() -> QueueOptions {
+        Default::default()
+    }
+}
+
+/// A job claimed from a queue.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Job {
+    /// The job's `_id`.
+    pub id: Bson,
+    /// The document passed to `push`.
+    pub payload: bson::Document,
+    /// How many times the job has been claimed, including this claim.
+    pub attempts: i32,
+    // Identifies this claim, so a consumer whose timeout lapsed cannot ack a later claim.
+    claim: String,
+}
+
+/// A job queue backed by a collection.
+#[derive(Clone, Debug)]
+pub struct Queue {
+    db: Database,
+    coll: String,
+    dead_letter: String,
+    visibility_timeout_ms: i64,
+    max_attempts: i32,
+    dead_letter_expire_after_seconds: Option<i32>,
+    poll_interval_ms: i64,
+}
+
+impl Queue {
+    /// Creates a queue over the given collection.
+    pub fn new(coll: &Collection, options: Option<QueueOptions>) -> Queue {
+        let options = options.unwrap_or_default();
+        let name = coll.name();
+
+        Queue {
+            db: coll.db.clone(),
+            dead_letter: options.dead_letter_collection.unwrap_or_else(
+                || format!("{}.dead", name),
+            ),
+            coll: name,
+            visibility_timeout_ms: options.visibility_timeout_ms.unwrap_or(
+                DEFAULT_VISIBILITY_TIMEOUT_MS,
+            ),
+            max_attempts: options.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS),
+            dead_letter_expire_after_seconds: options.dead_letter_expire_after_seconds,
+            poll_interval_ms: options.poll_interval_ms.unwrap_or(DEFAULT_POLL_INTERVAL_MS),
+        }
+    }
+
+    /// Returns the collection holding pending and claimed jobs.
+    pub fn collection(&self) -> Collection {
+        self.db.collection(&self.coll)
+    }
+
+    /// Returns the collection holding dead-lettered jobs.
+    pub fn dead_letter_collection(&self) -> Collection {
+        self.db.collection(&self.dead_letter)
+    }
+
+    /// Creates the indexes used to claim jobs, and the dead-letter TTL index if configured.
+    pub fn ensure_indexes(&self) -> Result<()> {
+        self.collection().create_index(
+            doc!{ "visible_at": 1 },
+            None,
+        )?;
+
+        if let Some(seconds) = self.dead_letter_expire_after_seconds {
+            let mut options = IndexOptions::new();
+            options.expire_after_seconds = Some(seconds);
+            self.dead_letter_collection().create_index(
+                doc!{ "failed_at": 1 },
+                Some(options),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds a job that is immediately claimable, returning its `_id`.
+    pub fn push(&self, payload: bson::Document) -> Result<Bson> {
+        self.push_delayed(payload, 0)
+    }
+
+    /// Adds a job that becomes claimable after `delay_ms` milliseconds, returning its `_id`.
+    pub fn push_delayed(&self, payload: bson::Document, delay_ms: i64) -> Result<Bson> {
+        let now = self.now();
+        let id = Bson::ObjectId(object_id::generate());
+
+        let job = doc! {
+            "_id": id.clone(),
+            "payload": payload,
+            "attempts": 0,
+            "created_at": now,
+            "visible_at": now + Duration::milliseconds(delay_ms),
+        };
+
+        let result = self.collection().insert_one(job, None)?;
+        match result.write_exception {
+            Some(exception) => Err(WriteError(exception)),
+            None => Ok(id),
+        }
+    }
+
+    /// Claims the oldest visible job, hiding it from other consumers for the visibility timeout.
+    /// Returns `None` if no job is currently visible.
+    ///
+    /// A claimed job that has exceeded the maximum number of attempts is moved to the dead-letter
+    /// collection instead of being returned.
+    pub fn claim(&self) -> Result<Option<Job>> {
+        loop {
+            let now = self.now();
+            let claim = object_id::generate().to_hex();
+
+            let mut options = FindOneAndUpdateOptions::new();
+            options.return_document = Some(ReturnDocument::After);
+            options.sort = Some(doc!{ "visible_at": 1 });
+
+            let claimed = self.collection().find_one_and_update(
+                doc!{ "visible_at": { "$lte": now } },
+                doc! {
+                    "$set": {
+                        "visible_at": now + Duration::milliseconds(self.visibility_timeout_ms),
+                        "claim": &claim,
+                    },
+                    "$inc": { "attempts": 1 },
+                },
+                Some(options),
+            )?;
+
+            let doc = match claimed {
+                Some(doc) => doc,
+                None => return Ok(None),
+            };
+
+            let job = Queue::job_from_document(doc, claim)?;
+            if job.attempts <= self.max_attempts {
+                return Ok(Some(job));
+            }
+
+            let reason = format!("Exceeded {} attempts.", self.max_attempts);
+            self.dead_letter(&job, &reason)?;
+        }
+    }
+
+    /// Claims a job, polling until one becomes visible or `timeout_ms` milliseconds have elapsed.
+    pub fn wait(&self, timeout_ms: i64) -> Result<Option<Job>> {
+        let clock = self.db.client.clock.clone();
+        let deadline = clock.now() + StdDuration::from_millis(timeout_ms.max(0) as u64);
+        let interval = StdDuration::from_millis(self.poll_interval_ms.max(0) as u64);
+
+        loop {
+            if let Some(job) = self.claim()? {
+                return Ok(Some(job));
+            }
+
+            let now = clock.now();
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            clock.sleep(::std::cmp::min(interval, deadline - now));
+        }
+    }
+
+    /// Acknowledges a job as done, deleting it. Returns false if the claim had already lapsed
+    /// and the job was claimed again.
+    pub fn ack(&self, job: &Job) -> Result<bool> {
+        let result = self.collection().delete_one(Queue::claim_filter(job), None)?;
+        Ok(result.deleted_count > 0)
+    }
+
+    /// Returns a job to the queue, making it claimable again after `delay_ms` milliseconds.
+    /// Returns false if the claim had already lapsed.
+    pub fn nack(&self, job: &Job, delay_ms: i64) -> Result<bool> {
+        let visible_at = self.now() + Duration::milliseconds(delay_ms);
+        let update = doc! {
+            "$set": { "visible_at": visible_at },
+            "$unset": { "claim": "" },
+        };
+
+        let result = self.collection().update_one(Queue::claim_filter(job), update, None)?;
+        Ok(result.matched_count > 0)
+    }
+
+    /// Extends the visibility timeout of a claimed job by `extend_ms` milliseconds from now.
+    /// Returns false if the claim had already lapsed.
+    pub fn touch(&self, job: &Job, extend_ms: i64) -> Result<bool> {
+        let visible_at = self.now() + Duration::milliseconds(extend_ms);
+        let update = doc!{ "$set": { "visible_at": visible_at } };
+
+        let result = self.collection().update_one(Queue::claim_filter(job), update, None)?;
+        Ok(result.matched_count > 0)
+    }
+
+    /// Moves a claimed job to the dead-letter collection, recording why. Returns false if the
+    /// claim had already lapsed.
+    pub fn dead_letter(&self, job: &Job, reason: &str) -> Result<bool> {
+        let dead = doc! {
+            "_id": job.id.clone(),
+            "payload": job.payload.clone(),
+            "attempts": job.attempts,
+            "reason": reason,
+            "failed_at": self.now(),
+        };
+
+        // Copy before deleting, so a crash in between duplicates the job rather than losing it.
+        // The copy then already exists when the job is dead-lettered again, so only the delete
+        // remains to be done.
+        let result = self.dead_letter_collection().insert_one(dead, None)?;
+        if let Some(exception) = result.write_exception {
+            if !exception.is_duplicate_key() {
+                return Err(WriteError(exception));
+            }
+        }
+
+        self.ack(job)
+    }
+
+    // The current time on the client's clock.
+    fn now(&self) -> DateTime<Utc> {
+        self.db.client.clock.utc_now()
+    }
+
+    fn claim_filter(job: &Job) -> bson::Document {
+        doc! {
+            "_id": job.id.clone(),
+            "claim": &job.claim,
+        }
+    }
+
+    fn job_from_document(mut doc: bson::Document, claim: String) -> Result<Job> {
+        let id = match doc.remove("_id") {
+            Some(id) => id,
+            None => return Err(ResponseError(String::from("Claimed job has no _id."))),
+        };
+
+        let payload = match doc.remove("payload") {
+            Some(Bson::Document(payload)) => payload,
+            _ => bson::Document::new(),
+        };
+
+        let attempts = match doc.get("attempts") {
+            Some(&Bson::I32(n)) => n,
+            Some(&Bson::I64(n)) => n as i32,
+            _ => 1,
+        };
+
+        Ok(Job {
+            id,
+            payload,
+            attempts,
+            claim,
+        })
+    }
+}
@@ -0,0 +1,554 @@
+//! Lazily parsed views over encoded documents.
+//!
+//! Decoding a reply into `bson::Document`s allocates every key and value of every document, which
+//! dominates the cost of reading from consumers that only look at a couple of fields of each.
+//! `Collection::find_raw` instead returns a `RawCursor` whose documents stay as the server
+//! encoded them. A `RawDocument` borrows the encoded bytes and parses only the elements walked
+//! over to find a field; strings, binary data and nested documents are returned as borrowed
+//! slices of the same buffer.
+//!
+//! Raw documents are exactly what the server sent, so `find_raw` fails on clients with a
+//! `uuid_representation` or automatic encryption, whose documents must be decoded; use `find`
+//! with those. `to_document` decodes a raw document fully when needed.
+//!
+//! ```no_run
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! #
+//! let client = Client::connect("localhost", 27017).unwrap();
+//! let coll = client.db("shop").collection("orders");
+//!
+//! let mut total = 0;
+//! for doc in coll.find_raw(None, None).unwrap() {
+//!     let doc = doc.unwrap();
+//!     if let Some(quantity) = doc.as_raw().get_i32("quantity").unwrap() {
+//!         total += quantity as i64;
+//!     }
+//! }
+//! ```
+use bson::{self, Bson, DecoderError};
+use bson::oid::ObjectId;
+use bson::spec::BinarySubtype;
+use byteorder::{ByteOrder, LittleEndian};
+use chrono::{TimeZone, Utc};
+use Result;
+
+use std::str;
+
+/// An encoded document, borrowed from the buffer it was read into.
+///
+/// Only the document's length and terminator are checked when it is created; elements are
+/// checked as they are parsed, and a malformed one is reported as an `Error::DecoderError` by
+/// whichever call reaches it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RawDocument<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> RawDocument<'a> {
+    /// Views `bytes`, which must hold exactly one encoded document.
+    pub fn new(bytes: &'a [u8]) -> Result<RawDocument<'a>> {
+        if bytes.len() < 5 {
+            return Err(syntax_error("a document takes at least 5 bytes"));
+        }
+
+        let length = LittleEndian::read_i32(&bytes[..4]);
+        if length < 0 || length as usize != bytes.len() {
+            return Err(syntax_error("document length does not match its bytes"));
+        }
+
+        if bytes[bytes.len() - 1] != 0 {
+            return Err(syntax_error("document is not null-terminated"));
+        }
+
+        Ok(RawDocument { bytes })
+    }
+
+    /// The encoded document.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Whether the document has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.len() == 5
+    }
+
+    /// Iterates over the document's fields in order, parsing each as it is reached.
+    pub fn iter(&self) -> Iter<'a> {
+        Iter {
+            bytes: self.bytes,
+            pos: 4,
+            done: false,
+        }
+    }
+
+    /// Returns the value of the first field named `key`, parsing fields only up to it.
+    pub fn get(&self, key: &str) -> Result<Option<RawBson<'a>>> {
+        for field in self.iter() {
+            let (name, value) = field?;
+            if name == key {
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the string field `key`, or `None` if there is no such field.
+    pub fn get_str(&self, key: &str) -> Result<Option<&'a str>> {
+        self.get_as(key, "a string", |value| match value {
+            RawBson::String(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns the 32-bit integer field `key`, or `None` if there is no such field.
+    pub fn get_i32(&self, key: &str) -> Result<Option<i32>> {
+        self.get_as(key, "a 32-bit integer", |value| match value {
+            RawBson::I32(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns the 64-bit integer field `key`, or `None` if there is no such field.
+    pub fn get_i64(&self, key: &str) -> Result<Option<i64>> {
+        self.get_as(key, "a 64-bit integer", |value| match value {
+            RawBson::I64(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns the double field `key`, or `None` if there is no such field.
+    pub fn get_f64(&self, key: &str) -> Result<Option<f64>> {
+        self.get_as(key, "a double", |value| match value {
+            RawBson::FloatingPoint(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns the boolean field `key`, or `None` if there is no such field.
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        self.get_as(key, "a boolean", |value| match value {
+            RawBson::Boolean(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns the object id field `key`, or `None` if there is no such field.
+    pub fn get_object_id(&self, key: &str) -> Result<Option<ObjectId>> {
+        self.get_as(key, "an object id", |value| match value {
+            RawBson::ObjectId(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns the datetime field `key` in milliseconds since the epoch, or `None` if there is
+    /// no such field.
+    pub fn get_utc_datetime(&self, key: &str) -> Result<Option<i64>> {
+        self.get_as(key, "a datetime", |value| match value {
+            RawBson::UtcDatetime(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns the embedded document field `key`, or `None` if there is no such field.
+    pub fn get_document(&self, key: &str) -> Result<Option<RawDocument<'a>>> {
+        self.get_as(key, "a document", |value| match value {
+            RawBson::Document(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns the array field `key`, or `None` if there is no such field.
+    pub fn get_array(&self, key: &str) -> Result<Option<RawArray<'a>>> {
+        self.get_as(key, "an array", |value| match value {
+            RawBson::Array(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Decodes the whole document.
+    pub fn to_document(&self) -> Result<bson::Document> {
+        Ok(bson::decode_document(&mut &self.bytes[..])?)
+    }
+
+    /// Copies the document into an owned buffer.
+    pub fn to_raw_document_buf(&self) -> RawDocumentBuf {
+        RawDocumentBuf { bytes: self.bytes.to_vec() }
+    }
+
+    // Fails if the field is present but `convert` does not accept its type.
+    fn get_as<T, F>(&self, key: &str, expected: &str, convert: F) -> Result<Option<T>>
+    where
+        F: FnOnce(RawBson<'a>) -> Option<T>,
+    {
+        match self.get(key)? {
+            Some(value) => match convert(value) {
+                Some(value) => Ok(Some(value)),
+                None => Err(DecoderError::InvalidType(
+                    format!("field `{}` is not {}", key, expected),
+                ).into()),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'a> IntoIterator for RawDocument<'a> {
+    type Item = Result<(&'a str, RawBson<'a>)>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// An encoded document that owns its bytes, such as one returned by a `RawCursor`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawDocumentBuf {
+    bytes: Vec<u8>,
+}
+
+impl RawDocumentBuf {
+    /// Takes ownership of `bytes`, which must hold exactly one encoded document.
+    pub fn new(bytes: Vec<u8>) -> Result<RawDocumentBuf> {
+        RawDocument::new(&bytes)?;
+        Ok(RawDocumentBuf { bytes })
+    }
+
+    /// Encodes `document`.
+    pub fn from_document(document: &bson::Document) -> Result<RawDocumentBuf> {
+        let mut bytes = Vec::new();
+        bson::encode_document(&mut bytes, document)?;
+        Ok(RawDocumentBuf { bytes })
+    }
+
+    /// Borrows the document to read its fields.
+    pub fn as_raw(&self) -> RawDocument<'_> {
+        RawDocument { bytes: &self.bytes }
+    }
+
+    /// The encoded document.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the encoded document.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Decodes the whole document.
+    pub fn to_document(&self) -> Result<bson::Document> {
+        self.as_raw().to_document()
+    }
+}
+
+/// An encoded array, borrowed from the buffer it was read into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RawArray<'a> {
+    document: RawDocument<'a>,
+}
+
+impl<'a> RawArray<'a> {
+    /// The array as the document it is encoded as, keyed by index.
+    pub fn as_document(&self) -> RawDocument<'a> {
+        self.document
+    }
+
+    /// Whether the array has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.document.is_empty()
+    }
+
+    /// Iterates over the array's elements in order, parsing each as it is reached.
+    pub fn iter(&self) -> ArrayIter<'a> {
+        ArrayIter { inner: self.document.iter() }
+    }
+
+    /// Returns the element at `index`, parsing elements only up to it.
+    pub fn get(&self, index: usize) -> Result<Option<RawBson<'a>>> {
+        match self.iter().nth(index) {
+            Some(value) => value.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Decodes the whole array.
+    pub fn to_vec(&self) -> Result<Vec<Bson>> {
+        self.iter().map(|value| value.and_then(|value| value.to_bson())).collect()
+    }
+}
+
+impl<'a> IntoIterator for RawArray<'a> {
+    type Item = Result<RawBson<'a>>;
+    type IntoIter = ArrayIter<'a>;
+
+    fn into_iter(self) -> ArrayIter<'a> {
+        self.iter()
+    }
+}
+
+/// A parsed BSON value whose strings, binary data and nested documents are borrowed.
+///
+/// Unlike `Bson`, this holds the deprecated types a document may contain, which fail to decode.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawBson<'a> {
+    FloatingPoint(f64),
+    String(&'a str),
+    Array(RawArray<'a>),
+    Document(RawDocument<'a>),
+    Boolean(bool),
+    Null,
+    RegExp(&'a str, &'a str),
+    JavaScriptCode(&'a str),
+    JavaScriptCodeWithScope(&'a str, RawDocument<'a>),
+    I32(i32),
+    I64(i64),
+    TimeStamp(i64),
+    Binary(BinarySubtype, &'a [u8]),
+    ObjectId(ObjectId),
+    /// Milliseconds since the epoch.
+    UtcDatetime(i64),
+    Symbol(&'a str),
+    /// The little-endian IEEE 754 decimal128 encoding.
+    Decimal128([u8; 16]),
+    Undefined,
+    DbPointer(&'a str, ObjectId),
+    MinKey,
+    MaxKey,
+}
+
+impl<'a> RawBson<'a> {
+    /// Decodes the value, as a decoded document would hold it.
+    pub fn to_bson(&self) -> Result<Bson> {
+        let bson = match *self {
+            RawBson::FloatingPoint(value) => Bson::FloatingPoint(value),
+            RawBson::String(value) => Bson::String(value.to_owned()),
+            RawBson::Array(value) => Bson::Array(value.to_vec()?),
+            RawBson::Document(value) => Bson::Document(value.to_document()?),
+            RawBson::Boolean(value) => Bson::Boolean(value),
+            RawBson::Null => Bson::Null,
+            RawBson::RegExp(pattern, options) => {
+                Bson::RegExp(pattern.to_owned(), options.to_owned())
+            }
+            RawBson::JavaScriptCode(code) => Bson::JavaScriptCode(code.to_owned()),
+            RawBson::JavaScriptCodeWithScope(code, scope) => {
+                Bson::JavaScriptCodeWithScope(code.to_owned(), scope.to_document()?)
+            }
+            RawBson::I32(value) => Bson::I32(value),
+            RawBson::I64(value) => Bson::I64(value),
+            RawBson::TimeStamp(value) => Bson::TimeStamp(value),
+            RawBson::Binary(subtype, bytes) => Bson::Binary(subtype, bytes.to_vec()),
+            RawBson::ObjectId(ref id) => Bson::ObjectId(id.clone()),
+            RawBson::UtcDatetime(millis) => match Utc.timestamp_millis_opt(millis).single() {
+                Some(date) => Bson::UtcDatetime(date),
+                None => return Err(DecoderError::InvalidTimestamp(millis).into()),
+            },
+            RawBson::Symbol(value) => Bson::Symbol(value.to_owned()),
+            RawBson::Decimal128(bytes) => return decode_decimal128(bytes),
+            RawBson::Undefined | RawBson::DbPointer(..) | RawBson::MinKey | RawBson::MaxKey => {
+                return Err(DecoderError::InvalidType(
+                    format!("{:?} has no `Bson` representation", self),
+                ).into());
+            }
+        };
+
+        Ok(bson)
+    }
+}
+
+/// An iterator over the fields of a `RawDocument`.
+///
+/// After yielding an error for a malformed field, the iterator ends.
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<(&'a str, RawBson<'a>)>;
+
+    fn next(&mut self) -> Option<Result<(&'a str, RawBson<'a>)>> {
+        // The last byte is the document's terminator.
+        if self.done || self.pos >= self.bytes.len() - 1 {
+            return None;
+        }
+
+        match read_element(self.bytes, self.pos) {
+            Ok((key, value, end)) => {
+                self.pos = end;
+                Some(Ok((key, value)))
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// An iterator over the elements of a `RawArray`.
+#[derive(Clone, Debug)]
+pub struct ArrayIter<'a> {
+    inner: Iter<'a>,
+}
+
+impl<'a> Iterator for ArrayIter<'a> {
+    type Item = Result<RawBson<'a>>;
+
+    fn next(&mut self) -> Option<Result<RawBson<'a>>> {
+        self.inner.next().map(|field| field.map(|(_, value)| value))
+    }
+}
+
+// Parses the element at `pos`, returning its key, value and the offset after it.
+fn read_element(bytes: &[u8], pos: usize) -> Result<(&str, RawBson<'_>, usize)> {
+    let element_type = bytes[pos];
+    let (key, pos) = read_cstring(bytes, pos + 1)?;
+
+    let (value, end) = match element_type {
+        0x01 => (RawBson::FloatingPoint(LittleEndian::read_f64(slice(bytes, pos, 8)?)), pos + 8),
+        0x02 => {
+            let (value, end) = read_string(bytes, pos)?;
+            (RawBson::String(value), end)
+        }
+        0x03 | 0x04 => {
+            let document = read_document(bytes, pos)?;
+            let end = pos + document.bytes.len();
+            if element_type == 0x03 {
+                (RawBson::Document(document), end)
+            } else {
+                (RawBson::Array(RawArray { document }), end)
+            }
+        }
+        0x05 => {
+            let len = read_len(bytes, pos)?;
+            let subtype = BinarySubtype::from(slice(bytes, pos + 4, 1)?[0]);
+            (RawBson::Binary(subtype, slice(bytes, pos + 5, len)?), pos + 5 + len)
+        }
+        0x06 => (RawBson::Undefined, pos),
+        0x07 => (RawBson::ObjectId(read_object_id(bytes, pos)?), pos + 12),
+        0x08 => match slice(bytes, pos, 1)?[0] {
+            0 => (RawBson::Boolean(false), pos + 1),
+            1 => (RawBson::Boolean(true), pos + 1),
+            _ => return Err(syntax_error("boolean is neither 0 nor 1")),
+        },
+        0x09 => (RawBson::UtcDatetime(LittleEndian::read_i64(slice(bytes, pos, 8)?)), pos + 8),
+        0x0A => (RawBson::Null, pos),
+        0x0B => {
+            let (pattern, pos) = read_cstring(bytes, pos)?;
+            let (options, pos) = read_cstring(bytes, pos)?;
+            (RawBson::RegExp(pattern, options), pos)
+        }
+        0x0C => {
+            let (namespace, pos) = read_string(bytes, pos)?;
+            (RawBson::DbPointer(namespace, read_object_id(bytes, pos)?), pos + 12)
+        }
+        0x0D => {
+            let (code, end) = read_string(bytes, pos)?;
+            (RawBson::JavaScriptCode(code), end)
+        }
+        0x0E => {
+            let (symbol, end) = read_string(bytes, pos)?;
+            (RawBson::Symbol(symbol), end)
+        }
+        0x0F => {
+            let len = read_len(bytes, pos)?;
+            let (code, scope_pos) = read_string(bytes, pos + 4)?;
+            let scope = read_document(bytes, scope_pos)?;
+            if scope_pos + scope.bytes.len() != pos + len {
+                return Err(syntax_error("code with scope length does not match its contents"));
+            }
+            (RawBson::JavaScriptCodeWithScope(code, scope), pos + len)
+        }
+        0x10 => (RawBson::I32(LittleEndian::read_i32(slice(bytes, pos, 4)?)), pos + 4),
+        0x11 => (RawBson::TimeStamp(LittleEndian::read_i64(slice(bytes, pos, 8)?)), pos + 8),
+        0x12 => (RawBson::I64(LittleEndian::read_i64(slice(bytes, pos, 8)?)), pos + 8),
+        0x13 => {
+            let mut decimal = [0; 16];
+            decimal.copy_from_slice(slice(bytes, pos, 16)?);
+            (RawBson::Decimal128(decimal), pos + 16)
+        }
+        0x7F => (RawBson::MaxKey, pos),
+        0xFF => (RawBson::MinKey, pos),
+        _ => return Err(DecoderError::UnrecognizedElementType(element_type).into()),
+    };
+
+    // Values may not run into the document's terminator.
+    if end >= bytes.len() {
+        return Err(DecoderError::EndOfStream.into());
+    }
+
+    Ok((key, value, end))
+}
+
+// Returns `len` bytes at `pos`.
+fn slice(bytes: &[u8], pos: usize, len: usize) -> Result<&[u8]> {
+    match pos.checked_add(len) {
+        Some(end) if end <= bytes.len() => Ok(&bytes[pos..end]),
+        _ => Err(DecoderError::EndOfStream.into()),
+    }
+}
+
+// Reads a non-negative little-endian i32 at `pos`.
+fn read_len(bytes: &[u8], pos: usize) -> Result<usize> {
+    let len = LittleEndian::read_i32(slice(bytes, pos, 4)?);
+    if len < 0 {
+        Err(syntax_error("negative length"))
+    } else {
+        Ok(len as usize)
+    }
+}
+
+// Reads the null-terminated string at `pos`, returning it and the offset after it.
+fn read_cstring(bytes: &[u8], pos: usize) -> Result<(&str, usize)> {
+    let rest = bytes.get(pos..).unwrap_or_default();
+    match rest.iter().position(|&byte| byte == 0) {
+        Some(len) => Ok((utf8(&rest[..len])?, pos + len + 1)),
+        None => Err(DecoderError::EndOfStream.into()),
+    }
+}
+
+// Reads the length-prefixed, null-terminated string at `pos`.
+fn read_string(bytes: &[u8], pos: usize) -> Result<(&str, usize)> {
+    let len = read_len(bytes, pos)?;
+    let value = slice(bytes, pos + 4, len)?;
+
+    match value.split_last() {
+        Some((&0, value)) => Ok((utf8(value)?, pos + 4 + len)),
+        _ => Err(syntax_error("string is not null-terminated")),
+    }
+}
+
+fn read_document(bytes: &[u8], pos: usize) -> Result<RawDocument<'_>> {
+    let len = read_len(bytes, pos)?;
+    RawDocument::new(slice(bytes, pos, len)?)
+}
+
+fn read_object_id(bytes: &[u8], pos: usize) -> Result<ObjectId> {
+    let mut id = [0; 12];
+    id.copy_from_slice(slice(bytes, pos, 12)?);
+    Ok(ObjectId::with_bytes(id))
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str> {
+    str::from_utf8(bytes).map_err(|err| syntax_error(&err.to_string()))
+}
+
+// Decodes the value through a single-field document, as the decoder only reads whole documents.
+fn decode_decimal128(bytes: [u8; 16]) -> Result<Bson> {
+    let mut document = vec![24, 0, 0, 0, 0x13, b'v', 0];
+    document.extend_from_slice(&bytes);
+    document.push(0);
+
+    let mut document = bson::decode_document(&mut &document[..])?;
+    Ok(document.remove("v").unwrap_or(Bson::Null))
+}
+
+fn syntax_error(message: &str) -> ::Error {
+    DecoderError::SyntaxError(String::from(message)).into()
+}
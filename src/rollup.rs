@@ -0,0 +1,232 @@
+//! Rollups of time series measurements into time buckets.
+//!
+//! Summarizing metrics, such as the hourly average of each sensor's readings, takes a pipeline
+//! that truncates each measurement's time with `$dateTrunc`, groups on the truncated time and
+//! any metadata fields, and applies an accumulator to each measured field. A `RollupSpec`
+//! describes the rollup and builds that pipeline; `Collection::rollup` runs it. Each result has
+//! the bucket's start time under the spec's time field, the grouping fields under their names,
+//! and one field per aggregation, sorted by time.
+//!
+//! `$dateTrunc` requires MongoDB 5.0 or later. Rollups work on any collection, but are meant
+//! for time series collections, whose storage they read efficiently.
+//!
+//! ```no_run
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::db::ThreadedDatabase;
+//! # use mongodb::rollup::{Accumulator, RollupSpec, RollupUnit};
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let mut spec = RollupSpec::new("timestamp", RollupUnit::Hour);
+//! spec.group_by.push((String::from("sensor"), String::from("metadata.sensor")));
+//! spec.add("avg_temp", Accumulator::Avg, "temp");
+//! spec.add("max_temp", Accumulator::Max, "temp");
+//!
+//! let coll = client.db("metrics").collection("weather");
+//! for bucket in coll.rollup(&spec, None).unwrap() {
+//!     println!("{}", bucket.unwrap());
+//! }
+//! ```
+use bson::{self, Bson, doc};
+
+use Error::ArgumentError;
+use Result;
+
+/// The unit of time that measurements are bucketed by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RollupUnit {
+    Millisecond,
+    Second,
+    Minute,
+    Hour,
+    Day,
+    /// Weeks starting on Sunday.
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl RollupUnit {
+    /// The unit's name in `$dateTrunc`.
+    pub fn to_str(self) -> &'static str {
+        match self {
+            RollupUnit::Millisecond => "millisecond",
+            RollupUnit::Second => "second",
+            RollupUnit::Minute => "minute",
+            RollupUnit::Hour => "hour",
+            RollupUnit::Day => "day",
+            RollupUnit::Week => "week",
+            RollupUnit::Month => "month",
+            RollupUnit::Quarter => "quarter",
+            RollupUnit::Year => "year",
+        }
+    }
+}
+
+/// How the values of a field within a bucket are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Accumulator {
+    Avg,
+    Min,
+    Max,
+    Sum,
+    /// The number of measurements in the bucket; the field is ignored.
+    Count,
+    /// The value of the bucket's earliest measurement.
+    First,
+    /// The value of the bucket's latest measurement.
+    Last,
+    StdDevPop,
+}
+
+impl Accumulator {
+    // The accumulator expression applied to `field`.
+    fn to_bson(self, field: &str) -> Bson {
+        let field = format!("${}", field);
+        let expression = match self {
+            Accumulator::Avg => doc! { "$avg": field },
+            Accumulator::Min => doc! { "$min": field },
+            Accumulator::Max => doc! { "$max": field },
+            Accumulator::Sum => doc! { "$sum": field },
+            Accumulator::Count => doc! { "$sum": 1 },
+            Accumulator::First => doc! { "$first": field },
+            Accumulator::Last => doc! { "$last": field },
+            Accumulator::StdDevPop => doc! { "$stdDevPop": field },
+        };
+        Bson::Document(expression)
+    }
+
+    // Whether the accumulator depends on the order of the measurements.
+    fn is_ordered(self) -> bool {
+        self == Accumulator::First || self == Accumulator::Last
+    }
+}
+
+/// A field computed for each bucket of a rollup.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RollupAggregation {
+    /// The name of the field in each result.
+    pub output: String,
+    pub accumulator: Accumulator,
+    /// The path of the measured field, such as `temp` or `readings.temp`.
+    pub field: String,
+}
+
+/// A rollup of measurements into buckets of time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RollupSpec {
+    /// The field holding each measurement's time.
+    pub time_field: String,
+    pub unit: RollupUnit,
+    /// The number of units in each bucket; defaults to 1.
+    pub bin_size: Option<i64>,
+    /// The time zone buckets are aligned to, as an Olson name or UTC offset; defaults to UTC.
+    pub timezone: Option<String>,
+    /// Additional fields to group by, such as the source of each measurement, as
+    /// `(output name, field path)` pairs.
+    pub group_by: Vec<(String, String)>,
+    /// A filter selecting the measurements to roll up.
+    pub filter: Option<bson::Document>,
+    pub aggregations: Vec<RollupAggregation>,
+}
+
+impl RollupSpec {
+    /// Creates a spec bucketing by `unit` of the time in `time_field`, with no aggregations.
+    pub fn new(time_field: &str, unit: RollupUnit) -> RollupSpec {
+        RollupSpec {
+            time_field: String::from(time_field),
+            unit,
+            bin_size: None,
+            timezone: None,
+            group_by: Vec::new(),
+            filter: None,
+            aggregations: Vec::new(),
+        }
+    }
+
+    /// Adds an aggregation of `field`, reported in each result as `output`.
+    pub fn add(&mut self, output: &str, accumulator: Accumulator, field: &str) -> &mut RollupSpec {
+        self.aggregations.push(RollupAggregation {
+            output: String::from(output),
+            accumulator,
+            field: String::from(field),
+        });
+        self
+    }
+
+    /// Builds the aggregation pipeline for the rollup.
+    pub fn pipeline(&self) -> Result<Vec<bson::Document>> {
+        self.validate()?;
+
+        let mut pipeline = Vec::new();
+
+        if let Some(ref filter) = self.filter {
+            pipeline.push(doc! { "$match": filter.clone() });
+        }
+
+        if self.aggregations.iter().any(|agg| agg.accumulator.is_ordered()) {
+            pipeline.push(doc! { "$sort": { self.time_field.clone(): 1 } });
+        }
+
+        let mut trunc = doc! {
+            "date": format!("${}", self.time_field),
+            "unit": self.unit.to_str(),
+        };
+
+        if let Some(bin_size) = self.bin_size {
+            trunc.insert("binSize", bin_size);
+        }
+
+        if let Some(ref timezone) = self.timezone {
+            trunc.insert("timezone", timezone.clone());
+        }
+
+        let mut keys = bson::Document::new();
+        let mut project = doc! { "_id": 0, self.time_field.clone(): "$_id.time" };
+
+        for &(ref name, ref field) in &self.group_by {
+            keys.insert(name.clone(), format!("${}", field));
+            project.insert(name.clone(), format!("$_id.keys.{}", name));
+        }
+
+        let mut group = doc! { "_id": { "time": { "$dateTrunc": trunc }, "keys": keys } };
+
+        for agg in &self.aggregations {
+            group.insert(agg.output.clone(), agg.accumulator.to_bson(&agg.field));
+            project.insert(agg.output.clone(), 1);
+        }
+
+        pipeline.push(doc! { "$group": group });
+        pipeline.push(doc! { "$sort": { "_id.time": 1 } });
+        pipeline.push(doc! { "$project": project });
+
+        Ok(pipeline)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.aggregations.is_empty() {
+            return Err(ArgumentError(String::from("A rollup needs at least one aggregation.")));
+        }
+
+        if let Some(bin_size) = self.bin_size {
+            if bin_size < 1 {
+                return Err(ArgumentError(String::from("A rollup's bin size must be positive.")));
+            }
+        }
+
+        let mut names = vec![self.time_field.as_str()];
+        names.extend(self.group_by.iter().map(|&(ref name, _)| name.as_str()));
+        names.extend(self.aggregations.iter().map(|agg| agg.output.as_str()));
+
+        for (i, name) in names.iter().enumerate() {
+            if name.is_empty() || name.starts_with('$') || (i > 0 && name.contains('.')) {
+                return Err(ArgumentError(format!("Invalid rollup field name '{}'.", name)));
+            }
+
+            if names[..i].contains(name) {
+                return Err(ArgumentError(format!("Duplicate rollup field name '{}'.", name)));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+//! Causally consistent sessions, and administrative introspection of server sessions.
+//!
+//! # Causal consistency
+//!
+//! Reads from secondaries may not yet reflect writes just made on the primary.
+//! `ThreadedClient::start_session` returns a client whose operations form a causally consistent
+//! session: the driver tracks the `operationTime` of every reply in the session, and sends each
+//! later read with an `afterClusterTime` read concern, so that the server waits until it has
+//! applied everything the session has already written or read.
+//!
+//! The driver does not send session ids, so a session is tracked by the driver alone and needs
+//! no cleanup. Causal consistency requires MongoDB 3.6 or later on a replica set or sharded
+//! cluster, and writes acknowledged by a majority for the guarantee to survive failovers.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::common::{ReadMode, ReadPreference};
+//! # use mongodb::db::ThreadedDatabase;
+//! #
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let secondary = ReadPreference::new(ReadMode::Secondary, None);
+//! let session = client.start_session(None).unwrap();
+//! let profiles = session.db("app").collection("profiles");
+//!
+//! profiles.insert_one(doc! { "_id": "ann", "theme": "dark" }, None).unwrap();
+//!
+//! // The secondary waits for the insert before answering.
+//! let ann = session
+//!     .with_read_preference(secondary)
+//!     .db("app")
+//!     .collection("profiles")
+//!     .find_one(Some(doc! { "_id": "ann" }), None)
+//!     .unwrap();
+//! # }
+//! ```
+//!
+//! # Listing sessions
+//!
+//! `ThreadedClient::list_sessions` reads the sessions the cluster has persisted to
+//! `config.system.sessions`, using the `$listSessions` aggregation stage. Servers persist their
+//! sessions periodically, every 5 minutes by default, so recently started sessions may be missing.
+//! `ThreadedClient::list_local_sessions` uses `$listLocalSessions` to read the sessions held in
+//! memory by the primary instead.
+//!
+//! Both require MongoDB 3.6 or later. Listing the sessions of users other than the current one
+//! requires the `listSessions` privilege.
+//!
+//! ```no_run
+//! # use mongodb::{Client, ThreadedClient};
+//! # use mongodb::sessions::ListSessionsOptions;
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let mut options = ListSessionsOptions::new();
+//! options.all_users = Some(true);
+//!
+//! for session in client.list_sessions(Some(options)).unwrap() {
+//!     println!("{:?} last used by {:?} at {:?}", session.lsid, session.user, session.last_use);
+//! }
+//! ```
+use bson::{self, Bson, doc};
+use chrono::{DateTime, Utc};
+
+use common::{ReadConcern, ReadConcernLevel};
+use Error::ResponseError;
+use Result;
+
+use std::sync::Mutex;
+
+/// Options for starting a session.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionOptions {
+    /// Whether reads in the session reflect the session's earlier operations, even when served
+    /// by secondaries; defaults to true.
+    pub causal_consistency: Option<bool>,
+}
+
+impl SessionOptions {
+    pub fn new() -> SessionOptions {
+        Default::default()
+    }
+}
+
+/// The state of a session started by `ThreadedClient::start_session`, shared by every handle
+/// derived from the session's client.
+#[derive(Debug)]
+pub struct Session {
+    options: SessionOptions,
+    operation_time: Mutex<Option<i64>>,
+}
+
+impl Session {
+    pub fn new(options: SessionOptions) -> Session {
+        Session {
+            options,
+            operation_time: Mutex::new(None),
+        }
+    }
+
+    pub fn options(&self) -> &SessionOptions {
+        &self.options
+    }
+
+    /// Whether reads in the session are sent with the session's operation time.
+    pub fn is_causally_consistent(&self) -> bool {
+        self.options.causal_consistency != Some(false)
+    }
+
+    /// Returns the latest operation time, as a BSON timestamp, of the session's replies, or
+    /// None before the first reply.
+    pub fn operation_time(&self) -> Option<i64> {
+        match self.operation_time.lock() {
+            Ok(time) => *time,
+            Err(_) => None,
+        }
+    }
+
+    /// Advances the session's operation time to `time`, unless it is already later.
+    pub fn advance_operation_time(&self, time: i64) {
+        if let Ok(mut current) = self.operation_time.lock() {
+            // Timestamps order by their seconds in the high bits, then their increment.
+            if current.map_or(true, |current| (current as u64) < (time as u64)) {
+                *current = Some(time);
+            }
+        }
+    }
+
+    /// Returns the cluster time reads in the session must reflect, if causally consistent and
+    /// after the first reply.
+    pub fn after_cluster_time(&self) -> Option<i64> {
+        if self.is_causally_consistent() {
+            self.operation_time()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the read concern for a read in the session, given the read's own read concern.
+    /// Snapshot reads at a given time are left as they are.
+    pub fn read_concern(&self, read_concern: Option<ReadConcern>) -> Option<ReadConcern> {
+        let time = match self.after_cluster_time() {
+            Some(time) => time,
+            None => return read_concern,
+        };
+
+        let mut read_concern =
+            read_concern.unwrap_or_else(|| ReadConcern::new(ReadConcernLevel::Local));
+
+        if read_concern.at_cluster_time.is_none() {
+            read_concern.after_cluster_time = Some(time);
+        }
+
+        Some(read_concern)
+    }
+}
+
+/// Options for listing sessions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListSessionsOptions {
+    /// Whether to list the sessions of all users; defaults to false. Takes precedence over
+    /// `users`.
+    pub all_users: Option<bool>,
+    /// The users whose sessions to list, as `(user, database)` pairs; defaults to the current
+    /// user.
+    pub users: Option<Vec<(String, String)>>,
+}
+
+impl ListSessionsOptions {
+    pub fn new() -> ListSessionsOptions {
+        Default::default()
+    }
+}
+
+// The specification of a `$listSessions` or `$listLocalSessions` stage.
+impl From<ListSessionsOptions> for bson::Document {
+    fn from(options: ListSessionsOptions) -> Self {
+        if options.all_users == Some(true) {
+            return doc! { "allUsers": true };
+        }
+
+        match options.users {
+            Some(users) => {
+                let users: Vec<_> = users
+                    .into_iter()
+                    .map(|(user, db)| Bson::Document(doc! { "user": user, "db": db }))
+                    .collect();
+                doc! { "users": users }
+            }
+            None => bson::Document::new(),
+        }
+    }
+}
+
+/// A server session, as listed by `$listSessions` or `$listLocalSessions`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionRecord {
+    /// The session id, as sent with commands in the `lsid` field.
+    pub lsid: bson::Document,
+    /// The user that started the session, as `user@database`, if authentication is enabled.
+    pub user: Option<String>,
+    /// When the session was last used.
+    pub last_use: Option<DateTime<Utc>>,
+}
+
+impl SessionRecord {
+    /// Reads a session record from a document produced by `$listSessions` or
+    /// `$listLocalSessions`.
+    pub fn from_document(doc: &bson::Document) -> Result<SessionRecord> {
+        let id = match doc.get("_id") {
+            Some(&Bson::Document(ref id)) => id,
+            _ => return Err(ResponseError(String::from("Session record has no _id."))),
+        };
+
+        let lsid = match id.get("id") {
+            Some(session_id) => doc! { "id": session_id.clone() },
+            None => return Err(ResponseError(String::from("Session record has no session id."))),
+        };
+
+        let user = match doc.get("user") {
+            Some(&Bson::Document(ref user)) => user.get_str("name").ok().map(String::from),
+            _ => None,
+        };
+
+        let last_use = match doc.get("lastUse") {
+            Some(&Bson::UtcDatetime(last_use)) => Some(last_use),
+            _ => None,
+        };
+
+        Ok(SessionRecord { lsid, user, last_use })
+    }
+}
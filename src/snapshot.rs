@@ -0,0 +1,158 @@
+//! Consistent reads across collections at a single point in time.
+//!
+//! Reports that read several collections one after another can see writes made in between,
+//! such as an order counted in one collection whose line items are missing from the next.
+//! `ThreadedClient::with_snapshot` runs a closure whose reads all see the data as of the same
+//! majority-committed point in time, using the `snapshot` read concern.
+//!
+//! The driver does not support sessions, so a snapshot is identified by its cluster time alone:
+//! the first read through a `Snapshot` lets the server pick the time and every later read asks
+//! for the same time. Snapshot reads require MongoDB 5.0 or later on a replica set or sharded
+//! cluster, and fail with a `SnapshotTooOld` server error once the server no longer keeps history
+//! for the time, after `minSnapshotHistoryWindowInSeconds` (5 minutes by default).
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # use mongodb::{Client, ThreadedClient};
+//! #
+//! # fn main() {
+//! # let client = Client::connect("localhost", 27017).unwrap();
+//! let (orders, items) = client.with_snapshot(|snapshot| {
+//!     let orders = snapshot.collection("shop", "orders").count_documents(doc! {})?;
+//!     let items = snapshot.collection("shop", "items").count_documents(doc! {})?;
+//!     Ok((orders, items))
+//! }).unwrap();
+//! # }
+//! ```
+use bson::{self, Bson, doc};
+
+use coll::Collection;
+use coll::options::{AggregateOptions, FindOptions};
+use common::{ReadConcern, ReadConcernLevel};
+use cursor::Cursor;
+use db::ThreadedDatabase;
+use {Client, Result, ThreadedClient};
+
+use std::cell::Cell;
+
+/// A point in time that reads are served at, created by `ThreadedClient::with_snapshot`.
+#[derive(Debug)]
+pub struct Snapshot {
+    client: Client,
+    at_cluster_time: Cell<Option<i64>>,
+}
+
+impl Snapshot {
+    pub fn new(client: Client) -> Snapshot {
+        Snapshot {
+            client,
+            at_cluster_time: Cell::new(None),
+        }
+    }
+
+    /// Returns the cluster time, as a BSON timestamp, that the snapshot reads at, or None
+    /// before its first read.
+    pub fn at_cluster_time(&self) -> Option<i64> {
+        self.at_cluster_time.get()
+    }
+
+    /// Returns the read concern that reads in the snapshot are sent with.
+    pub fn read_concern(&self) -> ReadConcern {
+        ReadConcern {
+            level: ReadConcernLevel::Snapshot,
+            at_cluster_time: self.at_cluster_time.get(),
+            after_cluster_time: None,
+        }
+    }
+
+    /// Returns a handle for reading the collection `coll` of the database `db` in the snapshot.
+    pub fn collection<'a>(&'a self, db: &str, coll: &str) -> SnapshotCollection<'a> {
+        SnapshotCollection {
+            snapshot: self,
+            coll: self.client.db(db).collection(coll),
+        }
+    }
+
+    // Pins the snapshot to the time the server read at, if this was its first read.
+    fn record(&self, cursor: &Cursor) {
+        if self.at_cluster_time.get().is_none() {
+            self.at_cluster_time.set(cursor.at_cluster_time());
+        }
+    }
+}
+
+/// A collection read within a `Snapshot`.
+#[derive(Debug)]
+pub struct SnapshotCollection<'a> {
+    snapshot: &'a Snapshot,
+    coll: Collection,
+}
+
+impl<'a> SnapshotCollection<'a> {
+    /// Returns the underlying collection, whose reads are not part of the snapshot.
+    pub fn collection(&self) -> &Collection {
+        &self.coll
+    }
+
+    /// Returns a cursor over the documents matching `filter` as of the snapshot. Any read
+    /// concern in `options` is replaced by the snapshot's.
+    pub fn find(
+        &self,
+        filter: Option<bson::Document>,
+        options: Option<FindOptions>,
+    ) -> Result<Cursor> {
+        let mut options = options.unwrap_or_default();
+        options.read_concern = Some(self.snapshot.read_concern());
+
+        let cursor = self.coll.find(filter, Some(options))?;
+        self.snapshot.record(&cursor);
+        Ok(cursor)
+    }
+
+    /// Returns the first document matching `filter` as of the snapshot, or None.
+    pub fn find_one(
+        &self,
+        filter: Option<bson::Document>,
+        options: Option<FindOptions>,
+    ) -> Result<Option<bson::Document>> {
+        let mut options = options.unwrap_or_default();
+        options.limit = Some(-1);
+
+        match self.find(filter, Some(options))?.next() {
+            Some(result) => result.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Runs an aggregation pipeline as of the snapshot. Any read concern in `options` is
+    /// replaced by the snapshot's.
+    pub fn aggregate(
+        &self,
+        pipeline: Vec<bson::Document>,
+        options: Option<AggregateOptions>,
+    ) -> Result<Cursor> {
+        let mut options = options.unwrap_or_default();
+        options.read_concern = Some(self.snapshot.read_concern());
+
+        let cursor = self.coll.aggregate(pipeline, Some(options))?;
+        self.snapshot.record(&cursor);
+        Ok(cursor)
+    }
+
+    /// Counts the documents matching `filter` as of the snapshot.
+    pub fn count_documents(&self, filter: bson::Document) -> Result<i64> {
+        let pipeline = vec![doc! { "$match": filter }, doc! { "$count": "n" }];
+
+        match self.aggregate(pipeline, None)?.next() {
+            Some(result) => {
+                match result?.remove("n") {
+                    Some(Bson::I32(n)) => Ok(i64::from(n)),
+                    Some(Bson::I64(n)) => Ok(n),
+                    _ => Ok(0),
+                }
+            }
+            None => Ok(0),
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use bson::{Bson, Document};
-use json::FromValue;
-use mongodb::coll::options::{AggregateOptions, CountOptions, FindOneAndDeleteOptions,
-                             FindOneAndUpdateOptions, FindOptions};
+use super::super::FromValue;
+use coll::options::{AggregateOptions, CountOptions, FindOneAndDeleteOptions,
+                    FindOneAndUpdateOptions, FindOptions};
 use serde_json::{Map, Value};
 
 pub enum Arguments {
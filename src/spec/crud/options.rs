@@ -1,8 +1,8 @@
 use bson::Bson;
-use json::FromValue;
+use super::super::FromValue;
 
-use mongodb::coll::options::{AggregateOptions, CountOptions, FindOneAndDeleteOptions,
-                             FindOneAndUpdateOptions, FindOptions, ReturnDocument};
+use coll::options::{AggregateOptions, CountOptions, FindOneAndDeleteOptions,
+                    FindOneAndUpdateOptions, FindOptions, ReturnDocument};
 
 use serde_json::{Map, Value};
 
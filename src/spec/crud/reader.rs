@@ -1,6 +1,5 @@
 use bson::{Bson, Document};
-use serde_json::{self, Map, Value};
-use std::fs::File;
+use serde_json::{Map, Value};
 
 use super::arguments::Arguments;
 use super::outcome::Outcome;
@@ -119,10 +118,7 @@ pub trait SuiteContainer: Sized {
 
 impl SuiteContainer for Value {
     fn from_file(path: &str) -> Result<Value, String> {
-        let mut file = File::open(path).expect(&format!("Unable to open file: {}", path));
-        Ok(serde_json::from_reader(&mut file).expect(
-            &format!("Invalid JSON file: {}", path),
-        ))
+        super::super::read_json_file(path)
     }
 
     fn get_suite(&self) -> Result<Suite, String> {
@@ -130,8 +126,8 @@ impl SuiteContainer for Value {
                                  Value::Object(ref object) => object.clone(),
                                  "`get_suite` requires a JSON object");
 
-        let data = try!(get_data(&object));
-        let tests = try!(get_tests(&object));
+        let data = get_data(&object)?;
+        let tests = get_tests(&object)?;
 
         Ok(Suite {
             data: data,
@@ -0,0 +1,49 @@
+//! Readers for the MongoDB driver specification test suites.
+//!
+//! These parse the JSON files of the CRUD, server discovery and monitoring, and server selection
+//! specifications into driver types, so that crates wrapping the driver can run the same
+//! conformance suites against their own abstractions. They are only built with the
+//! `spec-test-support` feature.
+//!
+//! ```no_run
+//! # extern crate mongodb;
+//! # extern crate serde_json;
+//! # use mongodb::spec::crud::reader::SuiteContainer;
+//! # use serde_json::Value;
+//! #
+//! # fn main() {
+//! let json = Value::from_file("specifications/source/crud/tests/read/count.json").unwrap();
+//! for test in json.get_suite().unwrap().tests {
+//!     // Run `test.operation` against a collection seeded with the suite's `data`, then
+//!     // compare the result against `test.outcome`.
+//! }
+//! # }
+//! ```
+#[macro_use]
+mod macros;
+
+pub mod crud;
+pub mod sdam;
+pub mod server_selection;
+
+use serde_json::{self, Map, Value};
+use std::fs::File;
+
+/// Parses a value from a JSON object, for values that cannot fail to parse.
+pub trait FromValue: Sized {
+    fn from_json(object: &Map<String, Value>) -> Self;
+}
+
+/// Parses a value from a JSON object, describing what was malformed on failure.
+pub trait FromValueResult: Sized {
+    fn from_json(object: &Map<String, Value>) -> Result<Self, String>;
+}
+
+// Reads the JSON file of a test suite.
+fn read_json_file(path: &str) -> Result<Value, String> {
+    let file = File::open(path).map_err(|err| {
+        format!("Unable to open file {}: {}", path, err)
+    })?;
+
+    serde_json::from_reader(file).map_err(|err| format!("Invalid JSON file {}: {}", path, err))
+}
@@ -1,6 +1,6 @@
-use mongodb::connstring::{self, Host};
-use mongodb::topology::TopologyType;
-use mongodb::topology::server::ServerType;
+use connstring::{self, Host};
+use topology::TopologyType;
+use topology::server::ServerType;
 
 use serde_json::{Map, Value};
 use std::collections::HashMap;
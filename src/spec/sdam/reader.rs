@@ -1,5 +1,4 @@
-use serde_json::{self, Map, Value};
-use std::fs::File;
+use serde_json::{Map, Value};
 
 use super::responses::Responses;
 use super::outcome::Outcome;
@@ -13,12 +12,12 @@ impl Phase {
     fn from_json(object: &Map<String, Value>) -> Result<Phase, String> {
         let operation = val_or_err!(object.get("responses"),
                                     Some(&Value::Array(ref array)) =>
-                                    try!(Responses::from_json(array)),
+                                    Responses::from_json(array)?,
                                     "No `responses` array found.");
 
         let outcome = val_or_err!(object.get("outcome"),
                                   Some(&Value::Object(ref obj)) =>
-                                  try!(Outcome::from_json(obj)),
+                                  Outcome::from_json(obj)?,
                                   "No `outcome` object found.");
 
         Ok(Phase {
@@ -63,10 +62,7 @@ pub trait SuiteContainer: Sized {
 
 impl SuiteContainer for Value {
     fn from_file(path: &str) -> Result<Value, String> {
-        let mut file = File::open(path).expect(&format!("Unable to open file: {}", path));
-        Ok(serde_json::from_reader(&mut file).expect(
-            &format!("Invalid JSON file: {}", path),
-        ))
+        super::super::read_json_file(path)
     }
 
     fn get_suite(&self) -> Result<Suite, String> {
@@ -79,7 +75,7 @@ impl SuiteContainer for Value {
                               "`get_suite` requires a connection uri");
 
 
-        let phases = try!(get_phases(&object));
+        let phases = get_phases(&object)?;
         Ok(Suite {
             uri: uri,
             phases: phases,
@@ -1,5 +1,5 @@
 use bson::{Bson, Document};
-use mongodb::connstring::{self, Host};
+use connstring::{self, Host};
 use serde_json::Value;
 
 pub struct Responses {
@@ -1,4 +1,4 @@
-use mongodb::common::{ReadMode, ReadPreference};
+use common::{ReadMode, ReadPreference, TagSet};
 use serde_json::{Map, Value};
 use std::collections::BTreeMap;
 use std::str::FromStr;
@@ -35,7 +35,7 @@ impl FromValueResult for ReadPreference {
                     _ => return Err("tags must be string => string maps.".to_owned()),
                 }
             }
-            tag_sets.push(tags);
+            tag_sets.push(TagSet::from(tags));
         }
 
         Ok(ReadPreference::new(mode, Some(tag_sets)))
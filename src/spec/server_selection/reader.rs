@@ -1,11 +1,10 @@
-use json::FromValueResult;
+use super::super::FromValueResult;
 
-use mongodb::common::ReadPreference;
-use mongodb::topology::TopologyType;
+use common::ReadPreference;
+use topology::TopologyType;
 
-use serde_json::{self, Value};
+use serde_json::Value;
 
-use std::fs::File;
 use std::str::FromStr;
 
 use super::server::Server;
@@ -48,11 +47,7 @@ pub trait SuiteContainer: Sized {
 
 impl SuiteContainer for Value {
     fn from_file(path: &str) -> Result<Value, String> {
-        let mut file = File::open(path).expect(&format!("Unable to open file: {}", path));
-        Ok(serde_json::from_reader(&mut file).expect(&format!(
-            "Invalid JSON file: {}",
-            path
-        )))
+        super::super::read_json_file(path)
     }
 
     fn get_suite(&self) -> Result<Suite, String> {
@@ -68,17 +63,17 @@ impl SuiteContainer for Value {
 
         let read_preference = val_or_err!(object.get("read_preference"),
                                           Some(&Value::Object(ref object)) =>
-                                          try!(ReadPreference::from_json(object)),
+                                          ReadPreference::from_json(object)?,
                                           "suite requires a read_preference object.");
 
         let in_latency_window = val_or_err!(object.get("in_latency_window"),
                                            Some(&Value::Array(ref array)) =>
-                                           try!(get_server_array(array)),
+                                           get_server_array(array)?,
                                            "suite requires an in_latency_window array.");
 
         let suitable_servers = val_or_err!(object.get("suitable_servers"),
                                            Some(&Value::Array(ref array)) =>
-                                           try!(get_server_array(array)),
+                                           get_server_array(array)?,
                                            "suite requires a suitable_servers array.");
 
         let topology_obj = val_or_err!(object.get("topology_description"),
@@ -87,7 +82,7 @@ impl SuiteContainer for Value {
 
         let top_servers = val_or_err!(topology_obj.get("servers"),
                                       Some(&Value::Array(ref array)) =>
-                                      try!(get_server_array(array)),
+                                      get_server_array(array)?,
                                       "topology requires an array of servers.");
 
         let ttype = val_or_err!(topology_obj.get("type"),
@@ -1,5 +1,5 @@
-use mongodb::connstring::{self, Host};
-use mongodb::topology::server::ServerType;
+use connstring::{self, Host};
+use topology::server::ServerType;
 
 use serde_json::{Map, Value};
 use std::collections::BTreeMap;
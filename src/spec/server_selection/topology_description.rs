@@ -1,4 +1,4 @@
-use mongodb::topology::TopologyType;
+use topology::TopologyType;
 use super::server::Server;
 
 pub struct TopologyDescription {
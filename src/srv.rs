@@ -0,0 +1,97 @@
+//! Seed lists from DNS SRV records.
+//!
+//! A `mongodb+srv://cluster0.example.com` connection string names a DNS name rather than its
+//! hosts. The client looks up the SRV records of `_mongodb._tcp.cluster0.example.com` and seeds
+//! the topology with their targets, each of which must be in the `example.com` domain.
+//!
+//! While the deployment is sharded, or not yet discovered, the client looks the records up again
+//! every `rescanSrvIntervalMS` (60 seconds by default), starting to monitor mongos hosts that
+//! were added and dropping those that were removed. A lookup that fails, finds no records, or
+//! finds a host outside the domain leaves the hosts as they were.
+//!
+//! The records are looked up with the client's `DnsResolver`, which needs the `trust-dns`
+//! feature to look up SRV records; see the `dns` module. Otherwise, any function returning the
+//! hosts of a record is an `SrvResolver`, and is given to the client as
+//! `ClientOptions::srv_resolver`:
+//!
+//! ```no_run
+//! # use mongodb::{Client, ClientOptions, ThreadedClient};
+//! # use mongodb::connstring::Host;
+//! # use std::sync::Arc;
+//! #
+//! # fn lookup_srv(name: &str) -> Vec<(String, u16)> { Vec::new() }
+//! #
+//! let mut options = ClientOptions::new();
+//! options.srv_resolver = Some(Arc::new(|name: &str| {
+//!     Ok(lookup_srv(name)
+//!         .into_iter()
+//!         .map(|(target, port)| Host::new(target, port))
+//!         .collect())
+//! }));
+//!
+//! let client = Client::with_uri_and_options(
+//!     "mongodb+srv://cluster0.example.com/?rescanSrvIntervalMS=30000",
+//!     options,
+//! ).unwrap();
+//! ```
+use connstring::Host;
+use Error::ArgumentError;
+use Result;
+
+/// How often the SRV records are looked up again unless `rescanSrvIntervalMS` says otherwise.
+pub const DEFAULT_RESCAN_SRV_INTERVAL_MS: i64 = 60000;
+
+/// A source of DNS SRV records.
+pub trait SrvResolver: Send + Sync {
+    /// Returns the target and port of each SRV record of `name`, such as
+    /// `_mongodb._tcp.cluster0.example.com`.
+    fn resolve(&self, name: &str) -> Result<Vec<Host>>;
+}
+
+impl<F> SrvResolver for F
+where
+    F: Fn(&str) -> Result<Vec<Host>> + Send + Sync,
+{
+    fn resolve(&self, name: &str) -> Result<Vec<Host>> {
+        self(name)
+    }
+}
+
+/// Looks up the hosts of the SRV connection string for `hostname`, failing if there are none or
+/// if any of them is outside the domain of `hostname`.
+pub fn resolve_hosts(resolver: &dyn SrvResolver, hostname: &str) -> Result<Vec<Host>> {
+    let domain = match hostname.find('.') {
+        Some(idx) => &hostname[idx..],
+        None => {
+            return Err(ArgumentError(format!(
+                "The SRV hostname '{}' has no domain.",
+                hostname
+            )))
+        }
+    };
+
+    let mut hosts = Vec::new();
+    for host in resolver.resolve(&format!("_mongodb._tcp.{}", hostname))? {
+        let host_name = host.host_name.trim_end_matches('.').to_ascii_lowercase();
+        if !host_name.ends_with(domain) {
+            return Err(ArgumentError(format!(
+                "The SRV record host '{}' is not in the domain '{}'.",
+                host_name,
+                &domain[1..]
+            )));
+        }
+
+        let host = Host::new(host_name, host.port);
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+
+    if hosts.is_empty() {
+        return Err(ArgumentError(
+            format!("No SRV records were found for '{}'.", hostname),
+        ));
+    }
+
+    Ok(hosts)
+}
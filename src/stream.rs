@@ -1,10 +1,32 @@
-use std::io::{BufReader, Read, Result, Write};
+use connstring::ConnectionOptions;
+use dns::{DnsResolver, SystemResolver};
+use error::Error::ArgumentError;
 #[cfg(feature = "ssl")]
-use std::io::{Error, ErrorKind};
-use std::net::{SocketAddr, TcpStream};
+use ocsp;
+use proxy::ProxyOptions;
 
+use std::io::{BufReader, Error, ErrorKind, IoSlice, Read, Result, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "ssl")]
+use openssl::ssl::{Ssl, SslContext, SslFiletype, SslMethod, SslOptions, SslStream, SslVerifyMode,
+                   StatusType};
+#[cfg(feature = "ssl")]
+use openssl::x509::store::X509Lookup;
 #[cfg(feature = "ssl")]
-use openssl::ssl::{Ssl, SslContext, SslFiletype, SslMethod, SslOptions, SslStream, SslVerifyMode};
+use openssl::x509::verify::X509VerifyFlags;
+
+/// Time spent in each phase of opening a connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConnectTimings {
+    /// Resolving the host name.
+    pub dns: Duration,
+    /// Opening the TCP connection to the first address that accepted it.
+    pub tcp: Duration,
+    /// The TLS handshake, for encrypted connections.
+    pub tls: Option<Duration>,
+}
 
 /// Encapsulates the functionality for how to connect to the server.
 #[derive(Clone)]
@@ -15,11 +37,19 @@ pub enum StreamConnector {
     /// Connect to the server through a TCP stream encrypted with SSL.
     ///
     /// Note that it's invalid to have one of certificate_file and key_file set but not the other.
+    ///
+    /// When `verify_peer` is set, the server's certificate is also checked for revocation: with
+    /// the certificate revocation lists in `crl_file`, which must include one from the
+    /// certificate's issuer, and with OCSP. A response stapled by the server is always checked,
+    /// while the certificate's OCSP responders are asked only if `disable_ocsp_endpoint_check`
+    /// is unset.
     Ssl {
         ca_file: Option<String>,
         certificate_file: Option<String>,
         key_file: Option<String>,
         verify_peer: bool,
+        crl_file: Option<String>,
+        disable_ocsp_endpoint_check: bool,
     },
 }
 
@@ -60,6 +90,8 @@ impl StreamConnector {
             certificate_file: Some(String::from(certificate_file)),
             key_file: Some(String::from(key_file)),
             verify_peer: verify_peer,
+            crl_file: None,
+            disable_ocsp_endpoint_check: false,
         }
     }
 
@@ -86,18 +118,140 @@ impl StreamConnector {
             certificate_file: None,
             key_file: None,
             verify_peer: verify_peer,
+            crl_file: None,
+            disable_ocsp_endpoint_check: false,
+        }
+    }
+
+    #[cfg(feature = "ssl")]
+    /// Checks server certificates against the PEM-encoded certificate revocation lists in
+    /// `crl_file`. Has no effect on connections without SSL.
+    pub fn with_crl_file(mut self, crl_file: &str) -> Self {
+        if let StreamConnector::Ssl { crl_file: ref mut file, .. } = self {
+            *file = Some(String::from(crl_file));
+        }
+        self
+    }
+
+    #[cfg(feature = "ssl")]
+    /// Sets whether the OCSP responders of server certificates are asked whether they have been
+    /// revoked when the server staples no OCSP response. Has no effect on connections without
+    /// SSL.
+    pub fn with_ocsp_endpoint_check(mut self, enabled: bool) -> Self {
+        if let StreamConnector::Ssl { ref mut disable_ocsp_endpoint_check, .. } = self {
+            *disable_ocsp_endpoint_check = !enabled;
+        }
+        self
+    }
+
+    /// Applies the `tlsCRLFile` and `tlsDisableOCSPEndpointCheck` connection string options,
+    /// which require an SSL connection. A CRL file already set on the connector is kept, and
+    /// either the connector or the connection string can disable the OCSP endpoint check.
+    pub fn with_connection_options(self, options: &ConnectionOptions) -> ::Result<Self> {
+        let crl_file = options.get("tlsCRLFile");
+        let disable_ocsp_endpoint_check = match options.get("tlsDisableOCSPEndpointCheck") {
+            Some(value) => Some(value.parse::<bool>().map_err(|_| {
+                ArgumentError(format!("Invalid tlsDisableOCSPEndpointCheck '{}'.", value))
+            })?),
+            None => None,
+        };
+
+        match self {
+            StreamConnector::Tcp => {
+                if crl_file.is_some() {
+                    return Err(ArgumentError(String::from("tlsCRLFile requires SSL.")));
+                }
+                if disable_ocsp_endpoint_check.is_some() {
+                    return Err(ArgumentError(
+                        String::from("tlsDisableOCSPEndpointCheck requires SSL."),
+                    ));
+                }
+                Ok(StreamConnector::Tcp)
+            }
+            #[cfg(feature = "ssl")]
+            StreamConnector::Ssl {
+                ca_file,
+                certificate_file,
+                key_file,
+                verify_peer,
+                crl_file: file,
+                disable_ocsp_endpoint_check: disabled,
+            } => {
+                Ok(StreamConnector::Ssl {
+                    ca_file,
+                    certificate_file,
+                    key_file,
+                    verify_peer,
+                    crl_file: file.or_else(|| crl_file.cloned()),
+                    disable_ocsp_endpoint_check: disabled ||
+                        disable_ocsp_endpoint_check.unwrap_or(false),
+                })
+            }
         }
     }
 
     pub fn connect(&self, hostname: &str, port: u16) -> Result<Stream> {
+        self.connect_with_timeout(hostname, port, None)
+    }
+
+    /// Connects to the server, failing if the TCP connection cannot be established within
+    /// `timeout`.
+    pub fn connect_with_timeout(
+        &self,
+        hostname: &str,
+        port: u16,
+        timeout: Option<Duration>,
+    ) -> Result<Stream> {
+        self.connect_with_timings(hostname, port, timeout).map(|(stream, _)| stream)
+    }
+
+    /// Connects to the server like `connect_with_timeout`, also returning how long each phase
+    /// of opening the connection took.
+    pub fn connect_with_timings(
+        &self,
+        hostname: &str,
+        port: u16,
+        timeout: Option<Duration>,
+    ) -> Result<(Stream, ConnectTimings)> {
+        self.connect_with_resolver(hostname, port, timeout, &SystemResolver)
+    }
+
+    /// Connects to the server like `connect_with_timings`, looking up the addresses of
+    /// `hostname` with `resolver`.
+    pub fn connect_with_resolver(
+        &self,
+        hostname: &str,
+        port: u16,
+        timeout: Option<Duration>,
+        resolver: &dyn DnsResolver,
+    ) -> Result<(Stream, ConnectTimings)> {
+        self.connect_with_proxy(hostname, port, timeout, resolver, None)
+    }
+
+    /// Connects to the server like `connect_with_resolver`, through a tunnel opened by `proxy`
+    /// if one is given. The proxy is looked up with `resolver`, and `hostname` is left for the
+    /// proxy to resolve.
+    pub fn connect_with_proxy(
+        &self,
+        hostname: &str,
+        port: u16,
+        timeout: Option<Duration>,
+        resolver: &dyn DnsResolver,
+        proxy: Option<&ProxyOptions>,
+    ) -> Result<(Stream, ConnectTimings)> {
+        let mut timings = ConnectTimings::default();
+        #[cfg(feature = "ssl")]
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
         match *self {
             StreamConnector::Tcp => {
-                let stream = TcpStream::connect((hostname, port))?;
+                let stream = tcp_connect(hostname, port, timeout, resolver, proxy, &mut timings)?;
                 stream.set_nodelay(true)?;
-                Ok(Stream::Tcp {
+                let stream = Stream::Tcp {
                     read_half: BufReader::new(stream.try_clone()?),
                     write_half: stream,
-                })
+                };
+                Ok((stream, timings))
             }
             #[cfg(feature = "ssl")]
             StreamConnector::Ssl {
@@ -105,8 +259,13 @@ impl StreamConnector {
                 ref certificate_file,
                 ref key_file,
                 verify_peer,
+                ref crl_file,
+                disable_ocsp_endpoint_check,
             } => {
-                let inner_stream = TcpStream::connect((hostname, port))?;
+                let inner_stream =
+                    tcp_connect(hostname, port, timeout, resolver, proxy, &mut timings)?;
+                inner_stream.set_read_timeout(timeout)?;
+                inner_stream.set_write_timeout(timeout)?;
                 inner_stream.set_nodelay(true)?;
 
                 let mut ssl_context = SslContext::builder(SslMethod::tls())?;
@@ -126,6 +285,14 @@ impl StreamConnector {
                     ssl_context.set_private_key_file(file, SslFiletype::PEM)?;
                 }
 
+                if let Some(crl_file) = crl_file {
+                    ssl_context
+                        .cert_store_mut()
+                        .add_lookup(X509Lookup::file())?
+                        .load_crl_file(crl_file, SslFiletype::PEM)?;
+                    ssl_context.cert_store_mut().set_flags(X509VerifyFlags::CRL_CHECK)?;
+                }
+
                 let verify = if verify_peer {
                     SslVerifyMode::PEER
                 } else {
@@ -135,9 +302,28 @@ impl StreamConnector {
 
                 let mut ssl = Ssl::new(&ssl_context.build())?;
                 ssl.set_hostname(hostname)?;
+                if verify_peer {
+                    ssl.set_status_type(StatusType::OCSP)?;
+                }
 
+                let start = Instant::now();
                 match ssl.connect(inner_stream) {
-                    Ok(s) => Ok(Stream::Ssl(s)),
+                    Ok(s) => {
+                        if verify_peer {
+                            // OCSP responders are reached the same way as the server.
+                            let connect = |host: &str, port, timeout| {
+                                let timeout = Some(timeout);
+                                let mut timings = ConnectTimings::default();
+                                tcp_connect(host, port, timeout, resolver, proxy, &mut timings)
+                            };
+                            ocsp::check(s.ssl(), !disable_ocsp_endpoint_check, deadline, &connect)?;
+                        }
+                        timings.tls = Some(start.elapsed());
+                        // The handshake timeout must not linger past connection establishment.
+                        s.get_ref().set_read_timeout(None)?;
+                        s.get_ref().set_write_timeout(None)?;
+                        Ok((Stream::Ssl(s), timings))
+                    }
                     Err(e) => Err(Error::new(ErrorKind::Other, e)),
                 }
             }
@@ -145,6 +331,83 @@ impl StreamConnector {
     }
 }
 
+// Opens a TCP connection to the server, directly or through a tunnel opened by `proxy`. The time
+// taken to open the tunnel counts towards connecting.
+fn tcp_connect(
+    hostname: &str,
+    port: u16,
+    timeout: Option<Duration>,
+    resolver: &dyn DnsResolver,
+    proxy: Option<&ProxyOptions>,
+    timings: &mut ConnectTimings,
+) -> Result<TcpStream> {
+    let proxy = match proxy {
+        Some(proxy) => proxy,
+        None => return open_tcp(hostname, port, timeout, resolver, timings),
+    };
+
+    let mut stream = open_tcp(&proxy.host, proxy.port, timeout, resolver, timings)?;
+
+    let start = Instant::now();
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    proxy.tunnel(&mut stream, hostname, port).map_err(|err| match err.kind() {
+        // Sockets report a read timeout as a read that would block.
+        ErrorKind::WouldBlock => {
+            Error::new(ErrorKind::TimedOut, "The proxy did not reply in time.")
+        }
+        _ => err,
+    })?;
+    stream.set_read_timeout(None)?;
+    stream.set_write_timeout(None)?;
+    timings.tcp += start.elapsed();
+
+    Ok(stream)
+}
+
+// Opens a TCP connection, trying each resolved address in turn, and records how long
+// resolution and connecting took. A host whose addresses all fail is evicted from the
+// resolver's cache.
+fn open_tcp(
+    hostname: &str,
+    port: u16,
+    timeout: Option<Duration>,
+    resolver: &dyn DnsResolver,
+    timings: &mut ConnectTimings,
+) -> Result<TcpStream> {
+    let start = Instant::now();
+    let addrs: Vec<_> = match hostname.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => {
+            let lookup = resolver.lookup_ip(hostname)?;
+            lookup.records.into_iter().map(|ip| SocketAddr::new(ip, port)).collect()
+        }
+    };
+    timings.dns = start.elapsed();
+
+    let start = Instant::now();
+    let mut last_err = None;
+    for addr in addrs {
+        let result = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+            None => TcpStream::connect(addr),
+        };
+
+        match result {
+            Ok(stream) => {
+                timings.tcp = start.elapsed();
+                return Ok(stream);
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+
+    resolver.evict(hostname);
+    Err(last_err.unwrap_or_else(|| {
+        Error::new(ErrorKind::InvalidInput, "Could not resolve to any addresses.")
+    }))
+}
+
 pub enum Stream {
     Tcp {
         read_half: BufReader<TcpStream>,
@@ -177,6 +440,17 @@ impl Write for Stream {
         }
     }
 
+    // Forwarded so that the socket sends several buffers with one system call.
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        match *self {
+            Stream::Tcp {
+                ref mut write_half, ..
+            } => write_half.write_vectored(bufs),
+            #[cfg(feature = "ssl")]
+            Stream::Ssl(ref mut s) => s.write_vectored(bufs),
+        }
+    }
+
     fn flush(&mut self) -> Result<()> {
         match *self {
             Stream::Tcp {
@@ -189,6 +463,20 @@ impl Write for Stream {
 }
 
 impl Stream {
+    /// Sets the read and write timeouts of the underlying socket; `None` blocks indefinitely.
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        let socket = match *self {
+            Stream::Tcp { ref write_half, .. } => write_half,
+            #[cfg(feature = "ssl")]
+            Stream::Ssl(ref stream) => stream.get_ref(),
+        };
+
+        // A zero duration is rejected by the socket, and means the time is already up.
+        let timeout = timeout.map(|t| ::std::cmp::max(t, Duration::from_millis(1)));
+        socket.set_read_timeout(timeout)?;
+        socket.set_write_timeout(timeout)
+    }
+
     pub fn peer_addr(&self) -> Result<SocketAddr> {
         match *self {
             Stream::Tcp { ref write_half, .. } => write_half.peer_addr(),
@@ -0,0 +1,109 @@
+//! Client-side operation deadlines.
+//!
+//! A `Deadline` bounds the total time an operation may spend in server selection, connection
+//! checkout and establishment, and network round trips, including the getMores of a cursor. It is
+//! derived from the `timeout_ms` of an operation's options, falling back to the client's
+//! `timeout_ms`; a timeout of zero disables the limit. Deadlines are measured with the client's
+//! clock.
+use clock::{Clock, SystemClock};
+use Error::Timeout;
+use Result;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The point in time by which an operation must complete.
+#[derive(Clone, Debug)]
+pub struct Deadline {
+    // When the operation expires, and the timeout it was derived from.
+    expires: Option<(Instant, i64)>,
+    // The clock the expiry is measured with; unset for deadlines that never expire.
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl Deadline {
+    /// Returns a deadline that never expires.
+    pub fn none() -> Deadline {
+        Deadline {
+            expires: None,
+            clock: None,
+        }
+    }
+
+    /// Returns a deadline `timeout_ms` milliseconds from now; `None` or zero never expire.
+    pub fn after_ms(timeout_ms: Option<i64>) -> Deadline {
+        Deadline::after_ms_on(Arc::new(SystemClock), timeout_ms)
+    }
+
+    /// Returns a deadline `timeout_ms` milliseconds from now as measured by `clock`; `None` or
+    /// zero never expire.
+    pub fn after_ms_on(clock: Arc<dyn Clock>, timeout_ms: Option<i64>) -> Deadline {
+        match timeout_ms {
+            Some(ms) if ms > 0 => Deadline {
+                expires: Some((clock.now() + Duration::from_millis(ms as u64), ms)),
+                clock: Some(clock),
+            },
+            _ => Deadline::none(),
+        }
+    }
+
+    /// Whether this deadline never expires.
+    pub fn is_none(&self) -> bool {
+        self.expires.is_none()
+    }
+
+    /// Returns the time left before the deadline, or `None` if it never expires.
+    ///
+    /// Returns an `Error::Timeout` naming `stage` if the deadline has already passed.
+    pub fn remaining(&self, stage: &str) -> Result<Option<Duration>> {
+        let (expires, clock) = match (self.expires, self.clock.as_ref()) {
+            (Some((expires, _)), Some(clock)) => (expires, clock),
+            _ => return Ok(None),
+        };
+
+        let now = clock.now();
+        if now >= expires {
+            return Err(self.timeout(stage));
+        }
+
+        Ok(Some(expires - now))
+    }
+
+    /// Returns the time left before the deadline in milliseconds, rounded up, or `None` if it
+    /// never expires.
+    ///
+    /// Returns an `Error::Timeout` naming `stage` if the deadline has already passed.
+    pub fn remaining_ms(&self, stage: &str) -> Result<Option<i64>> {
+        let remaining = match self.remaining(stage)? {
+            Some(remaining) => remaining,
+            None => return Ok(None),
+        };
+
+        let ms = (remaining.as_nanos() + 999_999) / 1_000_000;
+        Ok(Some(ms.min(i64::MAX as u128) as i64))
+    }
+
+    /// Returns the error reported when the deadline passes during `stage`.
+    pub fn timeout(&self, stage: &str) -> ::Error {
+        let timeout_ms = self.expires.map_or(0, |(_, ms)| ms);
+        Timeout(format!(
+            "Operation exceeded its timeout of {} ms during {}.",
+            timeout_ms,
+            stage
+        ))
+    }
+}
+
+impl PartialEq for Deadline {
+    fn eq(&self, other: &Deadline) -> bool {
+        self.expires == other.expires
+    }
+}
+
+impl Eq for Deadline {}
+
+impl Default for Deadline {
+    fn default() -> Self {
+        Deadline::none()
+    }
+}
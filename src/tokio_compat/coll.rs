@@ -0,0 +1,166 @@
+//! Collections for use from async code.
+use bson::{self, Bson};
+
+use Result;
+use coll::options::{AggregateOptions, CountOptions, DistinctOptions, FindOptions,
+                    InsertManyOptions, ReplaceOptions, UpdateModifications, UpdateOptions};
+use coll::results::{DeleteResult, InsertManyResult, InsertOneResult, UpdateResult};
+use common::WriteConcern;
+
+use super::{blocking, Blocking, Cursor};
+
+use std::sync::Arc;
+
+/// A collection for use from async code.
+#[derive(Clone, Debug)]
+pub struct Collection {
+    inner: Arc<::coll::Collection>,
+}
+
+impl Collection {
+    /// The blocking collection.
+    pub fn blocking(&self) -> &::coll::Collection {
+        &self.inner
+    }
+
+    /// The collection's name, without the database.
+    pub fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    /// Returns a cursor over the documents matching `filter`.
+    pub fn find(
+        &self,
+        filter: Option<bson::Document>,
+        options: Option<FindOptions>,
+    ) -> Blocking<Cursor> {
+        self.run(move |coll| coll.find(filter, options).map(Cursor::from))
+    }
+
+    /// Returns the first document matching `filter`.
+    pub fn find_one(
+        &self,
+        filter: Option<bson::Document>,
+        options: Option<FindOptions>,
+    ) -> Blocking<Option<bson::Document>> {
+        self.run(move |coll| coll.find_one(filter, options))
+    }
+
+    /// Runs an aggregation pipeline and returns a cursor over its results.
+    pub fn aggregate(
+        &self,
+        pipeline: Vec<bson::Document>,
+        options: Option<AggregateOptions>,
+    ) -> Blocking<Cursor> {
+        self.run(move |coll| coll.aggregate(pipeline, options).map(Cursor::from))
+    }
+
+    /// Counts the documents matching `filter`.
+    pub fn count(
+        &self,
+        filter: Option<bson::Document>,
+        options: Option<CountOptions>,
+    ) -> Blocking<i64> {
+        self.run(move |coll| coll.count(filter, options))
+    }
+
+    /// Returns the distinct values of a field among the documents matching `filter`.
+    pub fn distinct(
+        &self,
+        field_name: &str,
+        filter: Option<bson::Document>,
+        options: Option<DistinctOptions>,
+    ) -> Blocking<Vec<Bson>> {
+        let field_name = String::from(field_name);
+        self.run(move |coll| coll.distinct(&field_name, filter, options))
+    }
+
+    /// Inserts a document.
+    pub fn insert_one(
+        &self,
+        doc: bson::Document,
+        write_concern: Option<WriteConcern>,
+    ) -> Blocking<InsertOneResult> {
+        self.run(move |coll| coll.insert_one(doc, write_concern))
+    }
+
+    /// Inserts documents.
+    pub fn insert_many(
+        &self,
+        docs: Vec<bson::Document>,
+        options: Option<InsertManyOptions>,
+    ) -> Blocking<InsertManyResult> {
+        self.run(move |coll| coll.insert_many(docs, options))
+    }
+
+    /// Replaces the first document matching `filter`.
+    pub fn replace_one(
+        &self,
+        filter: bson::Document,
+        replacement: bson::Document,
+        options: Option<ReplaceOptions>,
+    ) -> Blocking<UpdateResult> {
+        self.run(move |coll| coll.replace_one(filter, replacement, options))
+    }
+
+    /// Updates the first document matching `filter`.
+    pub fn update_one<U: Into<UpdateModifications>>(
+        &self,
+        filter: bson::Document,
+        update: U,
+        options: Option<UpdateOptions>,
+    ) -> Blocking<UpdateResult> {
+        let update = update.into();
+        self.run(move |coll| coll.update_one(filter, update, options))
+    }
+
+    /// Updates every document matching `filter`.
+    pub fn update_many<U: Into<UpdateModifications>>(
+        &self,
+        filter: bson::Document,
+        update: U,
+        options: Option<UpdateOptions>,
+    ) -> Blocking<UpdateResult> {
+        let update = update.into();
+        self.run(move |coll| coll.update_many(filter, update, options))
+    }
+
+    /// Deletes the first document matching `filter`.
+    pub fn delete_one(
+        &self,
+        filter: bson::Document,
+        write_concern: Option<WriteConcern>,
+    ) -> Blocking<DeleteResult> {
+        self.run(move |coll| coll.delete_one(filter, write_concern))
+    }
+
+    /// Deletes every document matching `filter`.
+    pub fn delete_many(
+        &self,
+        filter: bson::Document,
+        write_concern: Option<WriteConcern>,
+    ) -> Blocking<DeleteResult> {
+        self.run(move |coll| coll.delete_many(filter, write_concern))
+    }
+
+    /// Drops the collection.
+    pub fn drop(&self) -> Blocking<()> {
+        self.run(|coll| coll.drop())
+    }
+
+    // Runs an operation on the blocking collection.
+    fn run<T, F>(&self, operation: F) -> Blocking<T>
+    where
+        F: FnOnce(&::coll::Collection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let coll = self.inner.clone();
+        blocking(move || operation(&coll))
+    }
+}
+
+impl From<::coll::Collection> for Collection {
+    fn from(coll: ::coll::Collection) -> Collection {
+        Collection { inner: Arc::new(coll) }
+    }
+}
@@ -0,0 +1,80 @@
+//! Cursors for use from async code.
+use bson;
+use futures_core::Stream;
+use tokio::task::{self, JoinHandle};
+
+use Result;
+
+use super::join_error;
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A stream of the documents a query or aggregation returns.
+///
+/// The cursor reads a batch from the server when it runs out of documents, and stops at the
+/// first error.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct Cursor {
+    buffer: VecDeque<bson::Document>,
+    state: State,
+}
+
+#[derive(Debug)]
+enum State {
+    Idle(Box<::cursor::Cursor>),
+    Reading(JoinHandle<(Box<::cursor::Cursor>, Result<Vec<bson::Document>>)>),
+    Done,
+}
+
+impl From<::cursor::Cursor> for Cursor {
+    fn from(cursor: ::cursor::Cursor) -> Cursor {
+        Cursor {
+            buffer: VecDeque::new(),
+            state: State::Idle(Box::new(cursor)),
+        }
+    }
+}
+
+impl Stream for Cursor {
+    type Item = Result<bson::Document>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(doc) = self.buffer.pop_front() {
+                return Poll::Ready(Some(Ok(doc)));
+            }
+
+            match mem::replace(&mut self.state, State::Done) {
+                State::Idle(mut cursor) => {
+                    let handle = task::spawn_blocking(move || {
+                        let batch = cursor.next_buffered();
+                        (cursor, batch)
+                    });
+                    self.state = State::Reading(handle);
+                }
+                State::Reading(mut handle) => {
+                    match Pin::new(&mut handle).poll(cx) {
+                        Poll::Pending => {
+                            self.state = State::Reading(handle);
+                            return Poll::Pending;
+                        }
+                        Poll::Ready(Ok((cursor, Ok(batch)))) => {
+                            if !batch.is_empty() {
+                                self.buffer.extend(batch);
+                                self.state = State::Idle(cursor);
+                            }
+                        }
+                        Poll::Ready(Ok((_, Err(err)))) => return Poll::Ready(Some(Err(err))),
+                        Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(join_error(err)))),
+                    }
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Futures over the blocking driver, for use from Tokio.
+//!
+//! With the `tokio-compat` feature, `Client`, `Database` and `Collection` mirror the blocking types with
+//! methods that return futures, and `Cursor` is a `Stream` of documents. In async code, `.await`
+//! the futures as usual; elsewhere, a runtime can block on them:
+//!
+//! ```no_run
+//! # #[macro_use] extern crate bson;
+//! # extern crate mongodb;
+//! # extern crate tokio;
+//! # use mongodb::tokio_compat::Client;
+//! #
+//! # fn main() {
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let client = Client::with_uri("mongodb://localhost:27017").unwrap();
+//! let users = client.db("app").collection("users");
+//!
+//! runtime.block_on(users.insert_one(doc! { "name": "Ada" }, None)).unwrap();
+//! let ada = runtime.block_on(users.find_one(Some(doc! { "name": "Ada" }), None)).unwrap();
+//! # }
+//! ```
+//!
+//! This is not an async driver: the driver has no non-blocking connections yet, so each operation
+//! runs the blocking driver on Tokio's blocking thread pool, occupying one of its threads until
+//! the reply arrives. The runtime's worker threads are never blocked, but the
+//! number of operations in flight is bounded by the size of the blocking pool rather than by the
+//! number of connections. The futures must be polled from within a Tokio runtime. A cursor reads
+//! each batch from the server the same way, handing out the documents already read without
+//! waiting.
+//!
+//! The crate is written in the 2015 edition, in which `async fn` is not available, so the futures
+//! are the named `Blocking` type and `Cursor` rather than `async fn` methods.
+//!
+//! `Client::blocking`, `Database::blocking` and `Collection::blocking` return the underlying
+//! blocking types for operations without an async counterpart.
+pub mod coll;
+pub mod cursor;
+
+pub use self::coll::Collection;
+pub use self::cursor::Cursor;
+
+use bson;
+use tokio::task::{self, JoinHandle};
+
+use {ClientOptions, Result, ThreadedClient};
+use Error::OperationError;
+use common::ReadPreference;
+use db::ThreadedDatabase;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A driver operation, run on Tokio's blocking thread pool once first polled.
+#[must_use = "futures do nothing unless polled"]
+pub struct Blocking<T> {
+    operation: Option<Box<dyn FnOnce() -> Result<T> + Send>>,
+    handle: Option<JoinHandle<Result<T>>>,
+}
+
+impl<T> fmt::Debug for Blocking<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Blocking").field("started", &self.handle.is_some()).finish()
+    }
+}
+
+impl<T: Send + 'static> Future for Blocking<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<T>> {
+        if let Some(operation) = self.operation.take() {
+            self.handle = Some(task::spawn_blocking(operation));
+        }
+
+        let handle = match self.handle {
+            Some(ref mut handle) => handle,
+            None => panic!("`Blocking` polled after completion"),
+        };
+
+        let result = match Pin::new(handle).poll(cx) {
+            Poll::Ready(Ok(result)) => result,
+            Poll::Ready(Err(err)) => Err(join_error(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        self.handle = None;
+        Poll::Ready(result)
+    }
+}
+
+// Prepares `operation` to run on the blocking thread pool.
+fn blocking<T, F>(operation: F) -> Blocking<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    Blocking {
+        operation: Some(Box::new(operation)),
+        handle: None,
+    }
+}
+
+fn join_error(err: task::JoinError) -> ::Error {
+    OperationError(format!("The operation's task failed: {}", err))
+}
+
+/// A client for use from async code.
+#[derive(Clone, Debug)]
+pub struct Client {
+    inner: ::Client,
+}
+
+impl Client {
+    /// Creates a client directly connected to a single server.
+    pub fn connect(host: &str, port: u16) -> Result<Client> {
+        ::Client::connect(host, port).map(Client::from)
+    }
+
+    /// Creates a client directly connected to a single server, with options.
+    pub fn connect_with_options(host: &str, port: u16, options: ClientOptions) -> Result<Client> {
+        ::Client::connect_with_options(host, port, options).map(Client::from)
+    }
+
+    /// Creates a client connected to the deployment a connection string describes.
+    pub fn with_uri(uri: &str) -> Result<Client> {
+        ::Client::with_uri(uri).map(Client::from)
+    }
+
+    /// Creates a client connected to the deployment a connection string describes, with options.
+    pub fn with_uri_and_options(uri: &str, options: ClientOptions) -> Result<Client> {
+        ::Client::with_uri_and_options(uri, options).map(Client::from)
+    }
+
+    /// The blocking client, which shares this client's connections.
+    pub fn blocking(&self) -> &::Client {
+        &self.inner
+    }
+
+    /// Returns a handle to a database.
+    pub fn db(&self, db_name: &str) -> Database {
+        Database::from(self.inner.db(db_name))
+    }
+
+    /// Lists the names of the databases on the server.
+    pub fn database_names(&self) -> Blocking<Vec<String>> {
+        let client = self.inner.clone();
+        blocking(move || client.database_names())
+    }
+
+    /// Drops a database.
+    pub fn drop_database(&self, db_name: &str) -> Blocking<()> {
+        let client = self.inner.clone();
+        let db_name = String::from(db_name);
+        blocking(move || client.drop_database(&db_name))
+    }
+}
+
+impl From<::Client> for Client {
+    fn from(client: ::Client) -> Client {
+        Client { inner: client }
+    }
+}
+
+/// A database for use from async code.
+#[derive(Clone, Debug)]
+pub struct Database {
+    inner: ::db::Database,
+}
+
+impl Database {
+    /// The blocking database.
+    pub fn blocking(&self) -> &::db::Database {
+        &self.inner
+    }
+
+    /// The database's name.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns a handle to a collection in the database.
+    pub fn collection(&self, coll_name: &str) -> Collection {
+        Collection::from(self.inner.collection(coll_name))
+    }
+
+    /// Lists the names of the collections in the database that match `filter`.
+    pub fn collection_names(&self, filter: Option<bson::Document>) -> Blocking<Vec<String>> {
+        let db = self.inner.clone();
+        blocking(move || db.collection_names(filter))
+    }
+
+    /// Runs a command and returns its reply.
+    pub fn run_command(
+        &self,
+        spec: bson::Document,
+        read_preference: Option<ReadPreference>,
+    ) -> Blocking<bson::Document> {
+        let db = self.inner.clone();
+        blocking(move || db.run_command(spec, read_preference))
+    }
+
+    /// Drops a collection.
+    pub fn drop_collection(&self, name: &str) -> Blocking<()> {
+        let db = self.inner.clone();
+        let name = String::from(name);
+        blocking(move || db.drop_collection(&name))
+    }
+
+    /// Drops the database.
+    pub fn drop_database(&self) -> Blocking<()> {
+        let db = self.inner.clone();
+        blocking(move || db.drop_database())
+    }
+}
+
+impl From<::db::Database> for Database {
+    fn from(db: ::db::Database) -> Database {
+        Database { inner: db }
+    }
+}
@@ -4,24 +4,28 @@ pub mod monitor;
 
 use {Client, Result};
 use Error::{self, ArgumentError, OperationError};
+use error::ServerSelectionError;
+#[cfg(feature = "metrics")]
+use apm::metrics;
 
 use bson::oid;
 
 use common::{ReadPreference, ReadMode};
 use connstring::{ConnectionString, Host};
 use pool::PooledStream;
+use srv::{self, SrvResolver};
 use stream::StreamConnector;
+use timeout::Deadline;
 
 use rand::{thread_rng, Rng};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::i64;
 use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 use std::thread;
-use std::time::Duration;
-use time;
+use std::time::{Duration, Instant};
 
 use self::server::{Server, ServerDescription, ServerType};
 
@@ -29,6 +33,10 @@ pub const DEFAULT_HEARTBEAT_FREQUENCY_MS: u32 = 10000;
 pub const DEFAULT_LOCAL_THRESHOLD_MS: i64 = 15;
 pub const DEFAULT_SERVER_SELECTION_TIMEOUT_MS: i64 = 30000;
 
+// The longest a background thread waits between readings of the client's clock, which may move
+// without real time passing.
+const CLOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Describes the type of topology for a server set.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum TopologyType {
@@ -95,6 +103,135 @@ pub struct Topology {
     pub description: Arc<RwLock<TopologyDescription>>,
 }
 
+/// A summary of replica set health, derived from server monitoring.
+///
+/// The summary is only as recent as the last heartbeat from each server, so it may trail the
+/// replica set by up to the heartbeat frequency.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplicaSetHealth {
+    /// The primary, if one is known.
+    pub primary: Option<Host>,
+    /// The number of reachable secondaries.
+    pub healthy_secondaries: usize,
+    /// The number of reachable secondaries that match the read preference's tag sets.
+    pub eligible_secondaries: usize,
+    /// The number of data-bearing members in the replica set configuration. Arbiters are not
+    /// counted.
+    pub data_bearing_members: usize,
+    /// The largest estimated replication lag among reachable secondaries, in milliseconds. This
+    /// is `None` if no secondary is reachable or the servers predate MongoDB 3.4, which is the
+    /// first version to report when each member last applied a write.
+    pub max_replication_lag_ms: Option<i64>,
+}
+
+impl ReplicaSetHealth {
+    /// Whether a majority of data-bearing members is reachable to acknowledge `w: majority`
+    /// writes.
+    pub fn majority_satisfiable(&self) -> bool {
+        self.primary.is_some() && self.healthy_secondaries + 1 > self.data_bearing_members / 2
+    }
+}
+
+/// What the connected deployment supports, derived from server monitoring.
+///
+/// Like `ReplicaSetHealth`, this is only as recent as the last heartbeat from each server.
+/// Retryable writes and transactions are reported as the deployment supports them; this driver
+/// does not retry writes itself, but `Error::is_retryable_write` tells which failed writes an
+/// application may safely retry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub topology_type: TopologyType,
+    /// The highest wire version every data-bearing server supports, or `None` if none is known.
+    pub wire_version: Option<i64>,
+    /// How long servers keep idle sessions; the smallest value any data-bearing server reports.
+    pub logical_session_timeout_minutes: Option<i64>,
+    /// Whether every data-bearing server supports sessions, as of MongoDB 3.6.
+    pub sessions_supported: bool,
+    /// Whether the deployment supports retryable writes, which requires sessions and a replica
+    /// set or sharded cluster.
+    pub retryable_writes_available: bool,
+    /// Whether the deployment supports transactions: replica sets as of MongoDB 4.0 and sharded
+    /// clusters as of MongoDB 4.2.
+    pub transactions_supported: bool,
+    /// The wire protocol compressor in use. This driver does not negotiate compression, so this
+    /// is always `None`.
+    pub compression: Option<String>,
+    /// Whether the client is connected to a federated endpoint.
+    pub federated: bool,
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fn yes_no(value: bool) -> &'static str {
+            if value { "yes" } else { "no" }
+        }
+
+        write!(fmt, "topology: {:?}, wire version: ", self.topology_type)?;
+        match self.wire_version {
+            Some(version) => write!(fmt, "{}", version)?,
+            None => fmt.write_str("unknown")?,
+        }
+
+        write!(
+            fmt,
+            ", sessions: {}, retryable writes: {}, transactions: {}, compression: {}, \
+             federated: {}",
+            yes_no(self.sessions_supported),
+            yes_no(self.retryable_writes_available),
+            yes_no(self.transactions_supported),
+            self.compression.as_ref().map_or("none", |compressor| compressor.as_str()),
+            yes_no(self.federated),
+        )
+    }
+}
+
+/// A server as monitoring last saw it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerSnapshot {
+    pub host: Host,
+    pub server_type: ServerType,
+    /// The average round-trip time of the server's monitoring checks, in milliseconds.
+    pub round_trip_time: Option<i64>,
+    /// The last error monitoring encountered for the server, if any.
+    pub error: Option<String>,
+}
+
+impl fmt::Display for ServerSnapshot {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}: {:?}, rtt ", self.host, self.server_type)?;
+        match self.round_trip_time {
+            Some(rtt) => write!(fmt, "{} ms", rtt)?,
+            None => fmt.write_str("unknown")?,
+        }
+
+        match self.error {
+            Some(ref err) => write!(fmt, ", last error: {}", err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The deployment as monitoring last saw it, for diagnosing why no server could be selected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopologySnapshot {
+    pub topology_type: TopologyType,
+    /// Every known server, ordered by host.
+    pub servers: Vec<ServerSnapshot>,
+}
+
+impl fmt::Display for TopologySnapshot {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{:?} [", self.topology_type)?;
+        for (i, server) in self.servers.iter().enumerate() {
+            if i > 0 {
+                fmt.write_str("; ")?;
+            }
+            write!(fmt, "{}", server)?;
+        }
+        fmt.write_str("]")
+    }
+}
+
 impl FromStr for TopologyType {
     type Err = Error;
 
@@ -134,7 +271,12 @@ impl TopologyDescription {
     }
 
     /// Returns the nearest server stream, calculated by round trip time.
-    fn get_nearest_from_vec(&self, client: Client, servers: &mut Vec<Host>) -> Result<(PooledStream, ServerType)> {
+    fn get_nearest_from_vec(
+        &self,
+        client: Client,
+        servers: &mut Vec<Host>,
+        deadline: Deadline,
+    ) -> Result<(PooledStream, ServerType)> {
         servers.sort_by(|a, b| {
             let mut a_rtt = i64::MAX;
             let mut b_rtt = i64::MAX;
@@ -158,7 +300,9 @@ impl TopologyDescription {
                 if let Ok(description) = server.description.read() {
                     if description.round_trip_time.is_none() {
                         break;
-                    } else if let Ok(stream) = server.acquire_stream(client.clone()) {
+                    } else if let Ok(stream) =
+                        server.acquire_stream(client.clone(), deadline.clone())
+                    {
                         return Ok((stream, description.server_type));
                     }
                 }
@@ -170,13 +314,18 @@ impl TopologyDescription {
     }
 
     /// Returns a random server stream from the vector.
-    fn get_rand_from_vec(&self, client: Client, servers: &mut Vec<Host>) -> Result<(PooledStream, ServerType)> {
+    fn get_rand_from_vec(
+        &self,
+        client: Client,
+        servers: &mut Vec<Host>,
+        deadline: Deadline,
+    ) -> Result<(PooledStream, ServerType)> {
         while !servers.is_empty() {
             let len = servers.len();
             let index = thread_rng().gen_range(0, len);
 
             if let Some(server) = self.servers.get(&servers[index]) {
-                if let Ok(stream) = server.acquire_stream(client.clone()) {
+                if let Ok(stream) = server.acquire_stream(client.clone(), deadline.clone()) {
                     if let Ok(description) = server.description.read() {
                         return Ok((stream, description.server_type));
                     }
@@ -194,7 +343,10 @@ impl TopologyDescription {
         &self,
         client: Client,
         read_preference: &ReadPreference,
+        deadline: Deadline,
     ) -> Result<(PooledStream, bool, bool)> {
+        read_preference.validate()?;
+
         let (mut hosts, rand) = self.choose_hosts(read_preference)?;
 
         // Filter hosts by tagsets
@@ -211,7 +363,7 @@ impl TopologyDescription {
                 mode: ReadMode::PrimaryPreferred,
                 ..read_preference.clone()
             };
-            return self.acquire_stream(client, &read_pref);
+            return self.acquire_stream(client, &read_pref, deadline);
         }
 
         // If no servers are available, request an update from all monitors.
@@ -226,9 +378,9 @@ impl TopologyDescription {
 
         // Retrieve a server stream from the list of acceptable hosts.
         let (pooled_stream, server_type) = if rand {
-            self.get_rand_from_vec(client, &mut hosts)?
+            self.get_rand_from_vec(client, &mut hosts, deadline)?
         } else {
-            self.get_nearest_from_vec(client, &mut hosts)?
+            self.get_nearest_from_vec(client, &mut hosts, deadline)?
         };
 
         // Determine how to handle server-side logic based on ReadMode and TopologyType.
@@ -240,7 +392,9 @@ impl TopologyDescription {
                         match read_preference.mode {
                             ReadMode::Primary => (false, false),
                             ReadMode::SecondaryPreferred => {
-                                (true, !read_preference.tag_sets.is_empty())
+                                let options = !read_preference.tag_sets.is_empty() ||
+                                    read_preference.hedge.is_some();
+                                (true, options)
                             }
                             ReadMode::Secondary |
                             ReadMode::PrimaryPreferred |
@@ -260,7 +414,11 @@ impl TopologyDescription {
             TopologyType::Sharded => {
                 match read_preference.mode {
                     ReadMode::Primary => (false, false),
-                    ReadMode::SecondaryPreferred => (true, !read_preference.tag_sets.is_empty()),
+                    ReadMode::SecondaryPreferred => {
+                        let options = !read_preference.tag_sets.is_empty() ||
+                            read_preference.hedge.is_some();
+                        (true, options)
+                    }
                     ReadMode::Secondary |
                     ReadMode::PrimaryPreferred |
                     ReadMode::Nearest => (true, true),
@@ -272,7 +430,7 @@ impl TopologyDescription {
     }
 
     /// Returns a server stream for write operations.
-    pub fn acquire_write_stream(&self, client: Client) -> Result<PooledStream> {
+    pub fn acquire_write_stream(&self, client: Client, deadline: Deadline) -> Result<PooledStream> {
         let (mut hosts, rand) = self.choose_write_hosts();
 
         // If no servers are available, request an update from all monitors.
@@ -283,10 +441,184 @@ impl TopologyDescription {
         }
 
         if rand {
-            Ok(self.get_rand_from_vec(client, &mut hosts)?.0)
+            Ok(self.get_rand_from_vec(client, &mut hosts, deadline)?.0)
         } else {
-            Ok(self.get_nearest_from_vec(client, &mut hosts)?.0)
+            Ok(self.get_nearest_from_vec(client, &mut hosts, deadline)?.0)
+        }
+    }
+
+    /// Whether any known server is a federated endpoint.
+    pub fn is_federated(&self) -> bool {
+        self.servers
+            .values()
+            .any(|server| server.description.read().unwrap().federated == Some(true))
+    }
+
+    /// Captures each server's type, round-trip time and last error.
+    pub fn snapshot(&self) -> TopologySnapshot {
+        let mut servers: Vec<_> = self.servers
+            .iter()
+            .map(|(host, server)| {
+                let description = server.description.read().unwrap();
+                ServerSnapshot {
+                    host: host.clone(),
+                    server_type: description.server_type,
+                    round_trip_time: description.round_trip_time,
+                    error: description.err.as_ref().as_ref().map(|err| err.to_string()),
+                }
+            })
+            .collect();
+        servers.sort_by_key(|server| server.host.to_string());
+
+        TopologySnapshot {
+            topology_type: self.topology_type,
+            servers,
+        }
+    }
+
+    /// Whether any server answered its last monitoring check.
+    pub fn is_connected(&self) -> bool {
+        self.servers.values().any(|server| {
+            server.description.read().unwrap().server_type != ServerType::Unknown
+        })
+    }
+
+    /// Whether every known server has been checked by its monitor, successfully or not.
+    pub fn is_discovered(&self) -> bool {
+        self.servers.values().all(|server| {
+            let description = server.description.read().unwrap();
+            description.server_type != ServerType::Unknown || description.err.is_some()
+        })
+    }
+
+    // Describes why no server could be reached, from the first monitoring error found.
+    fn discovery_error(&self) -> Error {
+        for server in self.servers.values() {
+            if let Some(ref err) = *server.description.read().unwrap().err {
+                return OperationError(format!("No servers could be reached: {}", err));
+            }
+        }
+
+        OperationError(String::from("No servers could be reached."))
+    }
+
+    /// Summarizes what the deployment supports from the latest server descriptions.
+    pub fn capabilities(&self) -> Capabilities {
+        let mut wire_versions = Vec::new();
+        let mut session_timeouts = Vec::new();
+        let mut sessions_supported = true;
+        let mut standalone = false;
+        let mut data_bearing = 0;
+
+        for server in self.servers.values() {
+            let description = server.description.read().unwrap();
+            match description.server_type {
+                ServerType::Standalone => standalone = true,
+                ServerType::Mongos | ServerType::RSPrimary | ServerType::RSSecondary => (),
+                _ => continue,
+            }
+
+            data_bearing += 1;
+            if description.max_wire_version >= 0 {
+                wire_versions.push(description.max_wire_version);
+            }
+
+            match description.logical_session_timeout_minutes {
+                Some(timeout) => session_timeouts.push(timeout),
+                None => sessions_supported = false,
+            }
+        }
+
+        let wire_version = wire_versions.into_iter().min();
+        let sessions_supported = sessions_supported && data_bearing > 0;
+        let wire_at_least = |version| wire_version >= Some(version);
+
+        let transactions_supported = sessions_supported && match self.topology_type {
+            TopologyType::ReplicaSetWithPrimary | TopologyType::ReplicaSetNoPrimary => {
+                wire_at_least(7)
+            }
+            TopologyType::Sharded => wire_at_least(8),
+            _ => false,
+        };
+
+        Capabilities {
+            topology_type: self.topology_type,
+            wire_version,
+            logical_session_timeout_minutes: if sessions_supported {
+                session_timeouts.into_iter().min()
+            } else {
+                None
+            },
+            sessions_supported,
+            retryable_writes_available: sessions_supported && !standalone && wire_at_least(6),
+            transactions_supported,
+            compression: None,
+            federated: self.is_federated(),
+        }
+    }
+
+    /// Summarizes replica set health from the latest server descriptions. Secondaries that
+    /// match the tag sets of `read_preference` are counted as eligible; all reachable
+    /// secondaries are eligible if no read preference is given.
+    pub fn replica_set_health(&self, read_preference: Option<&ReadPreference>) -> ReplicaSetHealth {
+        let mut health = ReplicaSetHealth::default();
+        let mut members = HashSet::new();
+        let mut primary_age = None;
+        let mut secondaries = Vec::new();
+
+        for (host, server) in &self.servers {
+            let description = server.description.read().unwrap();
+            members.extend(description.hosts.iter().cloned());
+            members.extend(description.passives.iter().cloned());
+
+            // Servers' clocks may disagree with each other and with the client's, so writes are
+            // only compared through how long before its check each server applied its last
+            // write, and when each check happened by the client's clock.
+            let age = description.last_write_age();
+            let written = match (description.last_update_time, age) {
+                (Some(updated), Some(age)) => Some(updated - age),
+                _ => None,
+            };
+
+            match description.server_type {
+                ServerType::RSPrimary => {
+                    health.primary = Some(host.clone());
+                    primary_age = age;
+                }
+                ServerType::RSSecondary => {
+                    secondaries.push((host.clone(), age, written));
+                }
+                _ => (),
+            }
+        }
+
+        health.healthy_secondaries = secondaries.len();
+        health.data_bearing_members = members.len().max(secondaries.len() + 1);
+
+        // Estimate lag against the primary if it is known, or else against the most
+        // up-to-date secondary.
+        let latest_write = secondaries.iter().filter_map(|secondary| secondary.2).max();
+        let has_primary = health.primary.is_some();
+
+        health.max_replication_lag_ms = secondaries
+            .iter()
+            .filter_map(|(_, age, written)| {
+                let lag = match (has_primary, primary_age, *age) {
+                    (true, Some(primary_age), Some(age)) => age - primary_age,
+                    (false, _, _) => latest_write?.signed_duration_since((*written)?),
+                    _ => return None,
+                };
+                Some(lag.num_milliseconds().max(0))
+            })
+            .max();
+
+        let mut eligible: Vec<_> = secondaries.into_iter().map(|(host, _, _)| host).collect();
+        if let Some(read_preference) = read_preference {
+            self.filter_hosts(&mut eligible, read_preference);
         }
+        health.eligible_secondaries = eligible.len();
+
+        health
     }
 
     /// Filters a given set of hosts based on the provided read preference tag sets.
@@ -305,23 +637,7 @@ impl TopologyDescription {
 
                     // Check whether the read preference tags are contained
                     // within the server description tags.
-                    let mut valid = true;
-                    for (key, val) in tags {
-                        match description.tags.get(key) {
-                            Some(v) => {
-                                if val != v {
-                                    valid = false;
-                                    break;
-                                }
-                            }
-                            None => {
-                                valid = false;
-                                break;
-                            }
-                        }
-                    }
-
-                    if valid {
+                    if tags.matches(&description.tags) {
                         tag_filter = Some(tags);
                         break;
                     }
@@ -362,17 +678,7 @@ impl TopologyDescription {
                         let description = server.description.read().unwrap();
 
                         // Validate tag sets.
-                        for (key, val) in tag_filter {
-                            match description.tags.get(key) {
-                                Some(v) => {
-                                    if val != v {
-                                        return false;
-                                    }
-                                }
-                                None => return false,
-                            }
-                        }
-                        true
+                        tag_filter.matches(&description.tags)
                     } else {
                         false
                     }
@@ -423,7 +729,7 @@ impl TopologyDescription {
             return;
         }
 
-        let high_rtt = shortest_rtt + self.local_threshold_ms;
+        let high_rtt = shortest_rtt.saturating_add(self.local_threshold_ms);
 
         // Filter hosts by the latency window [shortest_rtt, high_rtt].
         hosts.retain(|host| {
@@ -827,6 +1133,36 @@ impl TopologyDescription {
         self.check_if_has_primary();
     }
 
+    /// Replaces the servers of a sharded or unknown topology with `hosts`, as looked up from the
+    /// SRV records of the connection string, monitoring new hosts and dropping the rest. Other
+    /// topologies are left alone, as their members are discovered from the servers themselves.
+    pub fn update_srv_hosts(
+        &mut self,
+        hosts: Vec<Host>,
+        client: Client,
+        top_arc: Arc<RwLock<TopologyDescription>>,
+    ) {
+        match self.topology_type {
+            TopologyType::Sharded | TopologyType::Unknown => (),
+            _ => return,
+        }
+
+        self.servers.retain(|host, _| hosts.contains(host));
+
+        for host in hosts {
+            if !self.servers.contains_key(&host) {
+                let server = Server::new(
+                    client.clone(),
+                    host.clone(),
+                    top_arc.clone(),
+                    true,
+                    self.stream_connector.clone(),
+                );
+                self.servers.insert(host, server);
+            }
+        }
+    }
+
     // Begins monitoring hosts that are not currently being monitored.
     fn add_missing_hosts(
         &mut self,
@@ -908,14 +1244,38 @@ impl Topology {
         client: Client,
         read_preference: Option<ReadPreference>,
         write: bool,
+        deadline: Deadline,
     ) -> Result<(PooledStream, bool, bool)> {
         // Note start of server selection.
-        let time = time::get_time();
-        let start_ms = time.sec * 1000 + (time.nsec as i64) / 1000000;
+        let clock = client.clock.clone();
+        let start = clock.now();
+
+        let result = self.select_stream(client, read_preference, write, deadline, start);
+
+        #[cfg(feature = "metrics")]
+        metrics::server_selected(write, clock.now() - start, result.is_ok());
+
+        result
+    }
+
+    // Retries server selection until a stream is acquired or selection started at `start` times
+    // out.
+    fn select_stream(
+        &self,
+        client: Client,
+        read_preference: Option<ReadPreference>,
+        write: bool,
+        deadline: Deadline,
+        start: Instant,
+    ) -> Result<(PooledStream, bool, bool)> {
+        let clock = client.clock.clone();
 
         loop {
+            deadline.remaining("server selection")?;
+
             let result = if write {
-                match self.description.read()?.acquire_write_stream(client.clone()) {
+                let description = self.description.read()?;
+                match description.acquire_write_stream(client.clone(), deadline.clone()) {
                     Ok(stream) => Ok((stream, false, false)),
                     Err(err) => Err(err),
                 }
@@ -923,39 +1283,176 @@ impl Topology {
                 self.description.read()?.acquire_stream(
                     client.clone(),
                     read_preference.as_ref().unwrap(),
+                    deadline.clone(),
                 )
             };
 
             match result {
                 Ok(stream) => return Ok(stream),
+                // An invalid read preference will not become valid by waiting.
+                Err(err @ ArgumentError(_)) => return Err(err),
                 Err(err) => {
                     // Check duration of current server selection and return an error if
                     // overdue.
-                    let end_time = time::get_time();
-                    let end_ms = end_time.sec * 1000 + (end_time.nsec as i64) / 1000000;
-                    if end_ms - start_ms >= self.description.read()?.server_selection_timeout_ms {
-                        return Err(err);
+                    let elapsed = clock.now() - start;
+                    let description = self.description.read()?;
+                    let timeout_ms = description.server_selection_timeout_ms;
+                    if elapsed >= Duration::from_millis(timeout_ms.max(0) as u64) {
+                        return Err(Error::ServerSelectionError(ServerSelectionError {
+                            message: err.to_string(),
+                            read_preference,
+                            topology: description.snapshot(),
+                        }));
                     }
                 }
             };
 
-            // Otherwise, sleep for a little while.
-            thread::sleep(Duration::from_millis(500));
+            // Otherwise, sleep for a little while, waking up in time to report a timeout.
+            let pause = Duration::from_millis(500);
+            match deadline.remaining("server selection")? {
+                Some(remaining) if remaining < pause => clock.sleep(remaining),
+                _ => clock.sleep(pause),
+            }
+        }
+    }
+
+    /// Waits until every known server has been checked and at least one answered, including
+    /// servers discovered along the way. Fails with the last monitoring error once server
+    /// selection would time out, or with `Error::Timeout` once the deadline passes.
+    pub fn warm_up(&self, client: &Client, deadline: Deadline) -> Result<()> {
+        let clock = client.clock.clone();
+        let start = clock.now();
+
+        // Check every server now rather than waiting for its next heartbeat.
+        for server in self.description.read()?.servers.values() {
+            server.request_update();
+        }
+
+        loop {
+            deadline.remaining("initial discovery")?;
+
+            {
+                let description = self.description.read()?;
+                if description.is_discovered() && description.is_connected() {
+                    return Ok(());
+                }
+
+                let elapsed = clock.now() - start;
+                let timeout_ms = description.server_selection_timeout_ms;
+                if elapsed >= Duration::from_millis(timeout_ms.max(0) as u64) {
+                    return Err(description.discovery_error());
+                }
+            }
+
+            // Monitors report within a round trip, so check back sooner than server selection.
+            let pause = Duration::from_millis(50);
+            match deadline.remaining("initial discovery")? {
+                Some(remaining) if remaining < pause => clock.sleep(remaining),
+                _ => clock.sleep(pause),
+            }
         }
     }
 
+    /// Looks up the SRV records of `hostname` every `interval_ms` of the client's clock in the
+    /// background, updating the servers with `TopologyDescription::update_srv_hosts`. Lookups
+    /// that fail are skipped. Polling stops once the monitors of the topology's servers have all
+    /// stopped, or the topology is found to be neither sharded nor unknown.
+    pub fn start_srv_polling(
+        &self,
+        client: &Client,
+        resolver: Arc<dyn SrvResolver>,
+        hostname: String,
+        interval_ms: i64,
+    ) {
+        let client = client.clone();
+        let top_arc = self.description.clone();
+        let interval = Duration::from_millis(interval_ms.max(0) as u64);
+
+        thread::spawn(move || {
+            // Whether a monitor has been seen running, since they start on their own threads.
+            let mut monitored = false;
+
+            loop {
+                let due = client.clock.now() + interval;
+                loop {
+                    let remaining = due.saturating_duration_since(client.clock.now());
+                    if remaining == Duration::from_secs(0) {
+                        break;
+                    }
+                    thread::sleep(remaining.min(CLOCK_POLL_INTERVAL));
+                }
+
+                let topology_type = match top_arc.read() {
+                    Ok(description) => {
+                        if description.servers.values().any(Server::is_monitored) {
+                            monitored = true;
+                        } else if monitored {
+                            return;
+                        }
+                        description.topology_type
+                    }
+                    Err(_) => return,
+                };
+
+                if topology_type != TopologyType::Sharded &&
+                    topology_type != TopologyType::Unknown
+                {
+                    return;
+                }
+
+                // Look the records up without holding the lock, as DNS may be slow.
+                let hosts = match srv::resolve_hosts(&*resolver, &hostname) {
+                    Ok(hosts) => hosts,
+                    Err(_) => continue,
+                };
+
+                if let Ok(mut description) = top_arc.write() {
+                    description.update_srv_hosts(hosts, client.clone(), top_arc.clone());
+                }
+            }
+        });
+    }
+
     /// Returns a server stream for read operations.
     pub fn acquire_stream(
         &self,
         client: Client,
         read_preference: ReadPreference,
+        deadline: Deadline,
     ) -> Result<(PooledStream, bool, bool)> {
-        self.acquire_stream_private(client, Some(read_preference), false)
+        self.acquire_stream_private(client, Some(read_preference), false, deadline)
     }
 
     /// Returns a server stream for write operations.
-    pub fn acquire_write_stream(&self, client: Client) -> Result<PooledStream> {
-        let (stream, _, _) = self.acquire_stream_private(client, None, true)?;
+    pub fn acquire_write_stream(&self, client: Client, deadline: Deadline) -> Result<PooledStream> {
+        let (stream, _, _) = self.acquire_stream_private(client, None, true, deadline)?;
         Ok(stream)
     }
+
+    /// Returns a stream to `host` without server selection, failing if the host is no longer
+    /// part of the topology.
+    pub fn acquire_stream_on(
+        &self,
+        client: Client,
+        host: &Host,
+        deadline: Deadline,
+    ) -> Result<PooledStream> {
+        let description = self.description.read()?;
+        match description.servers.get(host) {
+            Some(server) => server.acquire_stream(client, deadline),
+            None => Err(OperationError(format!(
+                "The server {} is no longer part of the deployment.",
+                host
+            ))),
+        }
+    }
+
+    /// Returns the newest wire protocol version `host` reported, if it is part of the topology
+    /// and has been checked.
+    pub fn max_wire_version(&self, host: &Host) -> Option<i64> {
+        let description = self.description.read().ok()?;
+        let server = description.servers.get(host)?;
+        let max_wire_version = server.description.read().ok()?.max_wire_version;
+        if max_wire_version >= 0 { Some(max_wire_version) } else { None }
+    }
 }
@@ -14,19 +14,72 @@ use stream::StreamConnector;
 use wire_protocol::flags::OpQueryFlags;
 
 use std::fmt;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Duration;
 
-use time;
-
 use super::server::{ServerDescription, ServerType};
-use super::{DEFAULT_HEARTBEAT_FREQUENCY_MS, TopologyDescription};
+use super::{CLOCK_POLL_INTERVAL, DEFAULT_HEARTBEAT_FREQUENCY_MS, TopologyDescription};
 
 const DEFAULT_MAX_BSON_OBJECT_SIZE: i64 = 16 * 1024 * 1024;
 const DEFAULT_MAX_MESSAGE_SIZE_BYTES: i64 = 48000000;
 
+// The weight of each new sample in the average round-trip time.
+const RTT_ALPHA: f64 = 0.2;
+// How many of the latest samples the minimum round-trip time is taken over.
+const MIN_RTT_SAMPLES: usize = 10;
+
+/// Tracks the round-trip times of a server's monitoring checks, in milliseconds.
+///
+/// The average weights each new sample by 0.2 against the previous average, so that one slow
+/// check moves it only a little. The minimum is taken over the last 10 samples.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RoundTripTimes {
+    average: Option<f64>,
+    samples: VecDeque<i64>,
+}
+
+impl RoundTripTimes {
+    pub fn new() -> RoundTripTimes {
+        Default::default()
+    }
+
+    /// Adds the round-trip time of a check.
+    pub fn add_sample(&mut self, round_trip_time: i64) {
+        let sample = round_trip_time as f64;
+        self.average = Some(match self.average {
+            Some(average) => RTT_ALPHA * sample + (1.0 - RTT_ALPHA) * average,
+            None => sample,
+        });
+
+        if self.samples.len() == MIN_RTT_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(round_trip_time);
+    }
+
+    /// The exponentially weighted moving average of the samples, or `None` before the first.
+    pub fn average(&self) -> Option<i64> {
+        self.average.map(|average| average.round() as i64)
+    }
+
+    /// The smallest of the latest samples, or `None` until there are at least two, since a
+    /// single sample may include the time to open the connection.
+    pub fn minimum(&self) -> Option<i64> {
+        if self.samples.len() < 2 {
+            return None;
+        }
+        self.samples.iter().cloned().min()
+    }
+
+    /// Forgets every sample, as when the server becomes unreachable.
+    pub fn reset(&mut self) {
+        self.average = None;
+        self.samples.clear();
+    }
+}
+
 /// The result of an isMaster operation.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct IsMasterResult {
@@ -56,6 +109,9 @@ pub struct IsMasterResult {
     pub primary: Option<Host>,
     pub hidden: bool,
     pub set_version: Option<i64>,
+    pub last_write_date: Option<DateTime<Utc>>,
+    /// Reported by servers running 3.6 or later, which support sessions.
+    pub logical_session_timeout_minutes: Option<i64>,
 }
 
 /// Monitors and updates server and topology information.
@@ -74,8 +130,10 @@ pub struct Monitor {
     personal_pool: Arc<ConnectionPool>,
     // Owned copy of the topology's heartbeat frequency.
     heartbeat_frequency_ms: AtomicUsize,
-    // Used for condvar functionality.
-    dummy_lock: Mutex<()>,
+    // The round-trip times of the server's checks since it last became unreachable.
+    round_trip_times: Mutex<RoundTripTimes>,
+    // Whether a server has requested an immediate update since the last check.
+    update_requested: Mutex<bool>,
     // To allow servers to request an immediate update, this
     // condvar can be notified to wake up the monitor.
     condvar: Condvar,
@@ -127,6 +185,8 @@ impl IsMasterResult {
             primary: None,
             hidden: false,
             set_version: None,
+            last_write_date: None,
+            logical_session_timeout_minutes: None,
         };
 
         if let Some(&Bson::Boolean(b)) = doc.get("ismaster") {
@@ -137,12 +197,22 @@ impl IsMasterResult {
             result.local_time = Some(datetime);
         }
 
-        if let Some(&Bson::I64(v)) = doc.get("minWireVersion") {
-            result.min_wire_version = v;
+        match doc.get("minWireVersion") {
+            Some(&Bson::I32(v)) => result.min_wire_version = i64::from(v),
+            Some(&Bson::I64(v)) => result.min_wire_version = v,
+            _ => (),
+        }
+
+        match doc.get("maxWireVersion") {
+            Some(&Bson::I32(v)) => result.max_wire_version = i64::from(v),
+            Some(&Bson::I64(v)) => result.max_wire_version = v,
+            _ => (),
         }
 
-        if let Some(&Bson::I64(v)) = doc.get("maxWireVersion") {
-            result.max_wire_version = v;
+        match doc.get("logicalSessionTimeoutMinutes") {
+            Some(&Bson::I32(v)) => result.logical_session_timeout_minutes = Some(i64::from(v)),
+            Some(&Bson::I64(v)) => result.logical_session_timeout_minutes = Some(v),
+            _ => (),
         }
 
         if let Some(&Bson::String(ref s)) = doc.get("msg") {
@@ -208,6 +278,12 @@ impl IsMasterResult {
             result.set_version = Some(v);
         }
 
+        if let Some(&Bson::Document(ref last_write)) = doc.get("lastWrite") {
+            if let Some(&Bson::UtcDatetime(datetime)) = last_write.get("lastWriteDate") {
+                result.last_write_date = Some(datetime);
+            }
+        }
+
         if let Some(&Bson::Document(ref doc)) = doc.get("tags") {
             for (k, v) in doc {
                 if let Bson::String(ref tag) = *v {
@@ -248,7 +324,8 @@ impl Monitor {
             top_description: top_description,
             server_description: server_description,
             heartbeat_frequency_ms: AtomicUsize::new(DEFAULT_HEARTBEAT_FREQUENCY_MS as usize),
-            dummy_lock: Mutex::new(()),
+            round_trip_times: Mutex::new(RoundTripTimes::new()),
+            update_requested: Mutex::new(false),
             condvar: Condvar::new(),
             running: Arc::new(AtomicBool::new(false)),
         }
@@ -256,6 +333,7 @@ impl Monitor {
 
     // Set server description error field.
     fn set_err(&self, err: Error) {
+        self.round_trip_times.lock().unwrap().reset();
         {
             let mut server_description = self.server_description.write().unwrap();
             server_description.set_err(err);
@@ -273,7 +351,7 @@ impl Monitor {
         let flags = OpQueryFlags::with_find_options(&options);
         let filter = doc!{ "isMaster": 1_i32 };
         let mut stream = self.personal_pool.acquire_stream(self.client.clone())?;
-        let time_start = time::get_time();
+        let start = self.client.clock.now();
         let cursor = Cursor::query_with_stream(
             &mut stream,
             self.client.clone(),
@@ -285,20 +363,50 @@ impl Monitor {
             false,
             None,
         )?;
-        let time_end = time::get_time();
+        let elapsed = self.client.clock.now() - start;
 
-        let sec_start_ms: i64 = time_start.sec * 1000;
-        let start_ms = sec_start_ms + time_start.nsec as i64 / 1000000;
+        let round_trip_time =
+            elapsed.as_secs() as i64 * 1000 + i64::from(elapsed.subsec_nanos()) / 1000000;
 
-        let sec_end_ms: i64 = time_end.sec * 1000;
-        let end_ms = sec_end_ms + time_end.nsec as i64 / 1000000;
+        Ok((cursor, round_trip_time))
+    }
 
-        let round_trip_time = end_ms - start_ms;
+    // Checks once whether the server is a federated endpoint, which reports a `dataLake`
+    // document in its `buildInfo` reply. Failures are retried on the next heartbeat.
+    fn detect_federation(&self) {
+        if self.server_description.read().unwrap().federated.is_some() {
+            return;
+        }
 
-        Ok((cursor, round_trip_time))
+        let mut options = FindOptions::new();
+        options.limit = Some(1);
+        options.batch_size = Some(1);
+
+        let flags = OpQueryFlags::with_find_options(&options);
+        let reply = self.personal_pool.acquire_stream(self.client.clone()).and_then(|mut stream| {
+            Cursor::query_with_stream(
+                &mut stream,
+                self.client.clone(),
+                String::from("admin.$cmd"),
+                flags,
+                doc! { "buildInfo": 1 },
+                options,
+                CommandType::BuildInfo,
+                false,
+                None,
+            )
+        });
+
+        if let Ok(mut cursor) = reply {
+            if let Some(Ok(build_info)) = cursor.next() {
+                let federated = build_info.contains_key("dataLake");
+                self.server_description.write().unwrap().federated = Some(federated);
+            }
+        }
     }
 
     pub fn request_update(&self) {
+        *self.update_requested.lock().unwrap() = true;
         self.condvar.notify_one();
     }
 
@@ -314,8 +422,17 @@ impl Monitor {
         {
             let mut server_description = self.server_description.write().unwrap();
             match ismaster_result {
-                Ok(ismaster) => server_description.update(ismaster, round_trip_time),
+                Ok(ismaster) => {
+                    let mut round_trip_times = self.round_trip_times.lock().unwrap();
+                    round_trip_times.add_sample(round_trip_time);
+
+                    server_description.update(ismaster, round_trip_times.average().unwrap());
+                    server_description.min_round_trip_time = round_trip_times.minimum();
+                    let now = self.client.clock.utc_now();
+                    server_description.set_update_time(now, round_trip_time);
+                }
                 Err(err) => {
+                    self.round_trip_times.lock().unwrap().reset();
                     server_description.set_err(err);
                     return Err(OperationError(
                         String::from("Failed to parse ismaster result."),
@@ -343,6 +460,9 @@ impl Monitor {
         match cursor.next() {
             Some(Ok(doc)) => {
                 if let Ok(description) = self.update_server_description(doc, round_trip_time) {
+                    // Known before the server becomes selectable, so that the first operations
+                    // on a federated endpoint are already checked.
+                    self.detect_federation();
                     self.update_top_description(description);
                 }
             }
@@ -388,8 +508,6 @@ impl Monitor {
 
         self.running.store(true, Ordering::SeqCst);
 
-        let mut guard = self.dummy_lock.lock().unwrap();
-
         loop {
             if !self.running.load(Ordering::SeqCst) {
                 break;
@@ -404,11 +522,23 @@ impl Monitor {
                 );
             }
 
+            // The next check is due a heartbeat later on the client's clock, or as soon as an
+            // update is requested.
             let frequency = self.heartbeat_frequency_ms.load(Ordering::SeqCst) as u64;
-            guard = self.condvar
-                .wait_timeout(guard, Duration::from_millis(frequency))
-                .unwrap()
-                .0;
+            let due = self.client.clock.now() + Duration::from_millis(frequency);
+            let mut requested = self.update_requested.lock().unwrap();
+            while !*requested && self.running.load(Ordering::SeqCst) {
+                let remaining = due.saturating_duration_since(self.client.clock.now());
+                if remaining == Duration::from_secs(0) {
+                    break;
+                }
+
+                requested = self.condvar
+                    .wait_timeout(requested, remaining.min(CLOCK_POLL_INTERVAL))
+                    .unwrap()
+                    .0;
+            }
+            *requested = false;
         }
     }
 }
@@ -3,9 +3,11 @@ use {Client, Result};
 use Error::{self, OperationError};
 
 use bson::oid;
+use chrono::{DateTime, Duration, Utc};
 use connstring::Host;
 use pool::{ConnectionPool, PooledStream};
 use stream::StreamConnector;
+use timeout::Deadline;
 
 use std::collections::BTreeMap;
 use std::str::FromStr;
@@ -16,12 +18,6 @@ use std::thread;
 use super::monitor::{IsMasterResult, Monitor};
 use super::TopologyDescription;
 
-/// Server round trip time is calculated as an exponentially-weighted moving
-/// averaging formula with a weighting factor. A factor of 0.2 places approximately
-/// 85% of the RTT weight on the 9 most recent observations. Using a divisor instead
-/// of a floating point provides the closest integer accuracy.
-pub const ROUND_TRIP_DIVISOR: i64 = 5;
-
 /// Describes the server role within a server set.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ServerType {
@@ -56,8 +52,12 @@ pub struct ServerDescription {
     pub server_type: ServerType,
     /// Any error encountered while monitoring this server.
     pub err: Arc<Option<Error>>,
-    /// The average round-trip time over the last 5 monitoring checks.
+    /// The moving average of the round-trip times of monitoring checks, in milliseconds, which
+    /// latency-based server selection compares.
     pub round_trip_time: Option<i64>,
+    /// The smallest round-trip time of the last 10 monitoring checks, in milliseconds, once
+    /// there have been at least two.
+    pub min_round_trip_time: Option<i64>,
     /// The minimum wire version supported by this server.
     pub min_wire_version: i64,
     /// The maximum wire version supported by this server.
@@ -80,6 +80,21 @@ pub struct ServerDescription {
     pub primary: Option<Host>,
     /// The current replica set version number.
     pub set_version: Option<i64>,
+    /// When this server last applied a write, as reported by servers running 3.4 or later.
+    pub last_write_date: Option<DateTime<Utc>>,
+    /// When this description was last updated from a successful isMaster response, by the
+    /// client's clock.
+    pub last_update_time: Option<DateTime<Utc>>,
+    /// The server's own time when it answered the last isMaster.
+    pub local_time: Option<DateTime<Utc>>,
+    /// How far the server's clock is estimated to run ahead of the client's, in milliseconds,
+    /// allowing half the round trip for the reply to arrive. Negative if it runs behind.
+    pub clock_skew_ms: Option<i64>,
+    /// Whether the server is a federated endpoint, such as Atlas Data Federation or Online
+    /// Archive, once the monitor has checked its `buildInfo`.
+    pub federated: Option<bool>,
+    /// How long the server keeps idle sessions, if it supports sessions.
+    pub logical_session_timeout_minutes: Option<i64>,
 }
 
 /// Holds status and connection information about a single server.
@@ -118,7 +133,8 @@ impl ServerDescription {
         Default::default()
     }
 
-    // Updates the server description using an isMaster server response.
+    // Updates the server description using an isMaster server response and the average
+    // round-trip time of the server's checks, as tracked by its monitor.
     pub fn update(&mut self, ismaster: IsMasterResult, round_trip_time: i64) {
         if !ismaster.ok {
             self.set_err(OperationError(
@@ -138,16 +154,10 @@ impl ServerDescription {
         self.election_id = ismaster.election_id;
         self.primary = ismaster.primary;
         self.set_version = ismaster.set_version;
-        self.round_trip_time = match self.round_trip_time {
-            Some(old_rtt) => {
-                // (rtt / div) + (old_rtt * (div-1)/div)
-                Some(
-                    round_trip_time / ROUND_TRIP_DIVISOR +
-                        (old_rtt / (ROUND_TRIP_DIVISOR)) * (ROUND_TRIP_DIVISOR - 1),
-                )
-            }
-            None => Some(round_trip_time),
-        };
+        self.last_write_date = ismaster.last_write_date;
+        self.logical_session_timeout_minutes = ismaster.logical_session_timeout_minutes;
+        self.local_time = ismaster.local_time;
+        self.round_trip_time = Some(round_trip_time);
 
         let set_name_empty = self.set_name.is_empty();
         let msg_empty = ismaster.msg.is_empty();
@@ -171,6 +181,24 @@ impl ServerDescription {
         }
     }
 
+    /// Records when a successful isMaster reply arrived by the client's clock, estimating the
+    /// server's clock skew from the time the reply reports.
+    pub fn set_update_time(&mut self, now: DateTime<Utc>, round_trip_time: i64) {
+        self.last_update_time = Some(now);
+        self.clock_skew_ms = self.local_time.map(|local_time| {
+            let answered = now - Duration::milliseconds(round_trip_time / 2);
+            local_time.signed_duration_since(answered).num_milliseconds()
+        });
+    }
+
+    /// How long before its last check the server applied its last write. This is measured by
+    /// the server's own clock when it reports its time, so it is unaffected by clock skew.
+    pub fn last_write_age(&self) -> Option<Duration> {
+        let last_write_date = self.last_write_date?;
+        let checked = self.local_time.or(self.last_update_time)?;
+        Some(checked.signed_duration_since(last_write_date))
+    }
+
     // Sets an encountered error and reverts the server type to Unknown.
     pub fn set_err(&mut self, err: Error) {
         self.err = Arc::new(Some(err));
@@ -181,6 +209,7 @@ impl ServerDescription {
     pub fn clear(&mut self) {
         self.election_id = None;
         self.round_trip_time = None;
+        self.min_round_trip_time = None;
         self.server_type = ServerType::Unknown;
         self.set_name = String::new();
     }
@@ -233,12 +262,17 @@ impl Server {
     }
 
     /// Returns a server stream from the connection pool.
-    pub fn acquire_stream(&self, client: Client) -> Result<PooledStream> {
-        self.pool.acquire_stream(client)
+    pub fn acquire_stream(&self, client: Client, deadline: Deadline) -> Result<PooledStream> {
+        self.pool.acquire_stream_with_deadline(client, deadline)
     }
 
     /// Request an update from the monitor on the server status.
     pub fn request_update(&self) {
         self.monitor.request_update();
     }
+
+    /// Whether the server's monitor is running. It stops once the server is dropped.
+    pub fn is_monitored(&self) -> bool {
+        self.monitor.running.load(Ordering::SeqCst)
+    }
 }
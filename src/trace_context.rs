@@ -0,0 +1,144 @@
+//! Trace context in query comments.
+//!
+//! A client created with `ClientOptions::trace_context` asks it for the current span before each
+//! query and command, and attaches the span as a W3C `traceparent` string, such as
+//! `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`, in the command's `comment` (or a
+//! legacy query's `$comment`). The comment shows up in `system.profile`, the server's slow query
+//! log and `currentOp`, so slow operations can be traced back to the requests that sent them.
+//!
+//! Commands that already have a comment keep it. `find` takes a comment on every server; other
+//! commands, including `aggregate`, take one as of MongoDB 4.4, and are sent without it to older
+//! servers.
+//! Authentication and monitoring commands are never commented.
+//!
+//! Any function returning the current span is a `TraceContext`. With OpenTelemetry, for example,
+//! it would read the trace id, span id and sampled flag of
+//! `Context::current().span().span_context()`:
+//!
+//! ```no_run
+//! # use mongodb::{Client, ClientOptions, ThreadedClient};
+//! # use mongodb::trace_context::SpanContext;
+//! # use std::sync::Arc;
+//! #
+//! # fn active_span() -> Option<([u8; 16], [u8; 8], bool)> { None }
+//! #
+//! let mut options = ClientOptions::new();
+//! options.trace_context = Some(Arc::new(|| {
+//!     active_span().map(|(trace_id, span_id, sampled)| {
+//!         SpanContext::new(trace_id, span_id, sampled)
+//!     })
+//! }));
+//!
+//! let client = Client::connect_with_options("localhost", 27017, options).unwrap();
+//! ```
+use bson::{self, Bson, doc};
+
+use std::fmt;
+
+/// The wire version of MongoDB 4.4, the first to accept a comment on every command.
+const COMMENT_ALL_COMMANDS_WIRE_VERSION: i64 = 9;
+
+/// Identifies the span an operation runs in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpanContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    /// Whether the trace is recorded.
+    pub sampled: bool,
+}
+
+impl SpanContext {
+    pub fn new(trace_id: [u8; 16], span_id: [u8; 8], sampled: bool) -> SpanContext {
+        SpanContext {
+            trace_id,
+            span_id,
+            sampled,
+        }
+    }
+
+    /// Returns the span as a W3C `traceparent` header value.
+    pub fn traceparent(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for SpanContext {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let flags = if self.sampled { "01" } else { "00" };
+        write!(
+            fmt,
+            "00-{}-{}-{}",
+            hex::encode(self.trace_id),
+            hex::encode(self.span_id),
+            flags
+        )
+    }
+}
+
+/// A source of the current span, such as a tracing library's active context.
+pub trait TraceContext: Send + Sync {
+    /// Returns the span the calling thread is in, if any.
+    fn current_span(&self) -> Option<SpanContext>;
+}
+
+impl<F> TraceContext for F
+where
+    F: Fn() -> Option<SpanContext> + Send + Sync,
+{
+    fn current_span(&self) -> Option<SpanContext> {
+        self()
+    }
+}
+
+/// Attaches `span` as the comment of a query or command sent to `namespace`, unless it has one.
+///
+/// Commands other than `find` are only commented if `wire_version`, the lowest wire version of
+/// the deployment's servers, is known to accept it.
+pub fn attach(
+    query: bson::Document,
+    namespace: &str,
+    span: &SpanContext,
+    wire_version: Option<i64>,
+) -> bson::Document {
+    if !namespace.ends_with(".$cmd") {
+        return attach_to_query(query, span);
+    }
+
+    let mut query = query;
+    match query.get_mut("$query") {
+        Some(Bson::Document(command)) => attach_to_command(command, span, wire_version),
+        _ => attach_to_command(&mut query, span, wire_version),
+    }
+    query
+}
+
+// Legacy queries carry their comment as a `$comment` modifier next to `$query`.
+fn attach_to_query(query: bson::Document, span: &SpanContext) -> bson::Document {
+    if query.contains_key("$comment") {
+        return query;
+    }
+
+    let mut query = if query.contains_key("$query") {
+        query
+    } else {
+        doc! { "$query": query }
+    };
+
+    query.insert("$comment", span.traceparent());
+    query
+}
+
+fn attach_to_command(command: &mut bson::Document, span: &SpanContext, wire_version: Option<i64>) {
+    if command.contains_key("comment") {
+        return;
+    }
+
+    let accepted = match command.keys().next().map(String::as_str) {
+        Some("find") => true,
+        _ => wire_version >= Some(COMMENT_ALL_COMMANDS_WIRE_VERSION),
+    };
+
+    if accepted {
+        command.insert("comment", span.traceparent());
+    }
+}
@@ -0,0 +1,173 @@
+//! UUID representations in BSON.
+//!
+//! BSON stores UUIDs as binary values. The standard representation is subtype 4 with the bytes in
+//! RFC 4122 order, but older drivers stored UUIDs as subtype 3, each in its own byte order. Data
+//! written by those drivers only round-trips if it is read and written in the same
+//! representation.
+//!
+//! A client, database or collection with a legacy `UuidRepresentation` translates between the
+//! two: standard UUIDs in documents it sends are stored in the legacy representation, and legacy
+//! UUIDs in documents it reads are returned as standard UUIDs. Applications then only deal with
+//! standard UUIDs, which `UuidExt::from_uuid` and `UuidExt::to_uuid` convert to and from bytes.
+use bson::{self, Bson};
+use bson::spec::BinarySubtype;
+
+use Error::{self, ArgumentError};
+use Result;
+
+use std::str::FromStr;
+
+/// How UUIDs are stored in BSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UuidRepresentation {
+    /// Subtype 4, in RFC 4122 byte order.
+    Standard,
+    /// Subtype 3, with the first three groups little-endian, as written by the legacy C# driver.
+    CSharpLegacy,
+    /// Subtype 3, with each half of the bytes reversed, as written by the legacy Java driver.
+    JavaLegacy,
+    /// Subtype 3, in RFC 4122 byte order, as written by the legacy Python driver.
+    PythonLegacy,
+}
+
+impl UuidRepresentation {
+    /// The representation's name in connection strings.
+    pub fn to_str(self) -> &'static str {
+        match self {
+            UuidRepresentation::Standard => "standard",
+            UuidRepresentation::CSharpLegacy => "csharpLegacy",
+            UuidRepresentation::JavaLegacy => "javaLegacy",
+            UuidRepresentation::PythonLegacy => "pythonLegacy",
+        }
+    }
+
+    fn subtype(self) -> BinarySubtype {
+        match self {
+            UuidRepresentation::Standard => BinarySubtype::Uuid,
+            _ => BinarySubtype::UuidOld,
+        }
+    }
+
+    // Reorders standard bytes into the representation's order. Each reordering is its own
+    // inverse, so this also restores standard bytes.
+    fn reorder(self, bytes: &mut [u8]) {
+        match self {
+            UuidRepresentation::Standard | UuidRepresentation::PythonLegacy => (),
+            UuidRepresentation::CSharpLegacy => {
+                bytes[0..4].reverse();
+                bytes[4..6].reverse();
+                bytes[6..8].reverse();
+            }
+            UuidRepresentation::JavaLegacy => {
+                bytes[0..8].reverse();
+                bytes[8..16].reverse();
+            }
+        }
+    }
+}
+
+impl FromStr for UuidRepresentation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "standard" => Ok(UuidRepresentation::Standard),
+            "csharpLegacy" => Ok(UuidRepresentation::CSharpLegacy),
+            "javaLegacy" => Ok(UuidRepresentation::JavaLegacy),
+            "pythonLegacy" => Ok(UuidRepresentation::PythonLegacy),
+            _ => Err(ArgumentError(format!("Unknown UUID representation '{}'.", s))),
+        }
+    }
+}
+
+/// Conversions between UUIDs, as 16 bytes in RFC 4122 order, and BSON values.
+pub trait UuidExt {
+    /// Stores `uuid` in `representation`.
+    fn from_uuid(uuid: [u8; 16], representation: UuidRepresentation) -> Self;
+    /// Reads the UUID in a binary value. Subtype 4 is always standard; subtype 3 is read in
+    /// `representation`, which must be a legacy one.
+    fn to_uuid(&self, representation: UuidRepresentation) -> Result<[u8; 16]>;
+}
+
+impl UuidExt for Bson {
+    fn from_uuid(uuid: [u8; 16], representation: UuidRepresentation) -> Bson {
+        let mut bytes = uuid.to_vec();
+        representation.reorder(&mut bytes);
+        Bson::Binary(representation.subtype(), bytes)
+    }
+
+    fn to_uuid(&self, representation: UuidRepresentation) -> Result<[u8; 16]> {
+        let (subtype, bytes) = match *self {
+            Bson::Binary(subtype, ref bytes) if bytes.len() == 16 => (subtype, bytes),
+            _ => return Err(ArgumentError(String::from("Value is not a 16-byte binary UUID."))),
+        };
+
+        let mut uuid = [0; 16];
+        uuid.copy_from_slice(bytes);
+
+        match subtype {
+            BinarySubtype::Uuid => Ok(uuid),
+            BinarySubtype::UuidOld if representation != UuidRepresentation::Standard => {
+                representation.reorder(&mut uuid);
+                Ok(uuid)
+            }
+            BinarySubtype::UuidOld => {
+                Err(ArgumentError(String::from(
+                    "Legacy UUIDs need a legacy representation to be read.",
+                )))
+            }
+            _ => Err(ArgumentError(String::from("Value is not a binary UUID."))),
+        }
+    }
+}
+
+/// Stores the standard UUIDs in `doc` in `representation`.
+pub fn encode_document(doc: bson::Document, representation: UuidRepresentation) -> bson::Document {
+    if representation == UuidRepresentation::Standard {
+        return doc;
+    }
+
+    map_document(doc, &|value| match value {
+        Bson::Binary(BinarySubtype::Uuid, mut bytes) if bytes.len() == 16 => {
+            representation.reorder(&mut bytes);
+            Bson::Binary(BinarySubtype::UuidOld, bytes)
+        }
+        value => value,
+    })
+}
+
+/// Returns the legacy UUIDs in `doc`, stored in `representation`, as standard UUIDs.
+pub fn decode_document(doc: bson::Document, representation: UuidRepresentation) -> bson::Document {
+    if representation == UuidRepresentation::Standard {
+        return doc;
+    }
+
+    map_document(doc, &|value| match value {
+        Bson::Binary(BinarySubtype::UuidOld, mut bytes) if bytes.len() == 16 => {
+            representation.reorder(&mut bytes);
+            Bson::Binary(BinarySubtype::Uuid, bytes)
+        }
+        value => value,
+    })
+}
+
+// Applies `f` to every value in `doc` other than documents and arrays, which are searched.
+fn map_document<F>(doc: bson::Document, f: &F) -> bson::Document
+where
+    F: Fn(Bson) -> Bson,
+{
+    doc.into_iter().map(|(key, value)| (key, map_value(value, f))).collect()
+}
+
+fn map_value<F>(value: Bson, f: &F) -> Bson
+where
+    F: Fn(Bson) -> Bson,
+{
+    match value {
+        Bson::Document(doc) => Bson::Document(map_document(doc, f)),
+        Bson::Array(values) => {
+            Bson::Array(values.into_iter().map(|value| map_value(value, f)).collect())
+        }
+        value => f(value),
+    }
+}
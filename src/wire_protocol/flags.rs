@@ -25,6 +25,15 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Represents the bit vector of flags for an OP_MSG message.
+    pub struct OpMsgFlags: u32 {
+        const CHECKSUM_PRESENT = 0b00000001;
+        const MORE_TO_COME     = 0b00000010;
+        const EXHAUST_ALLOWED  = 1 << 16;
+    }
+}
+
 bitflags! {
     /// Represents the bit vector of flags for an OP_QUERY message.
     pub struct OpQueryFlags: i32 {
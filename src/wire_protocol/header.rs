@@ -15,6 +15,7 @@ pub enum OpCode {
     Insert = 2002,
     Query = 2004,
     GetMore = 2005,
+    Msg = 2013,
 }
 
 impl OpCode {
@@ -35,6 +36,7 @@ impl OpCode {
             2002 => Some(OpCode::Insert),
             2004 => Some(OpCode::Query),
             2005 => Some(OpCode::GetMore),
+            2013 => Some(OpCode::Msg),
             _ => None,
         }
     }
@@ -48,6 +50,7 @@ impl fmt::Display for OpCode {
             OpCode::Insert => fmt.write_str("OP_INSERT"),
             OpCode::Query => fmt.write_str("OP_QUERY"),
             OpCode::GetMore => fmt.write_str("OP_GET_MORE"),
+            OpCode::Msg => fmt.write_str("OP_MSG"),
         }
     }
 }
@@ -106,6 +109,12 @@ impl Header {
         Header::new_request(message_length, request_id, OpCode::GetMore)
     }
 
+    /// Constructs a new Header for an OP_MSG, with `response_to` set to 0 and
+    /// `op_code` set to `Msg`.
+    pub fn new_msg(message_length: i32, request_id: i32) -> Header {
+        Header::new_request(message_length, request_id, OpCode::Msg)
+    }
+
     /// Writes the serialized Header to a buffer.
     ///
     /// # Arguments
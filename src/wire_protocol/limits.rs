@@ -0,0 +1,146 @@
+//! Limits on the shape of documents read from the server.
+//!
+//! Documents are decoded recursively, so a deeply nested document, such as one stored by another
+//! tenant of a shared collection, can exhaust the stack of the thread reading it. Each document
+//! in a reply is first scanned without recursion and rejected with
+//! `Error::DecodeLimitExceeded` if it nests deeper or holds longer arrays than the client's
+//! `DecodeLimits` allow.
+use byteorder::{ByteOrder, LittleEndian};
+use error::DecodeLimit;
+use Error::DecodeLimitExceeded;
+use Result;
+
+/// The nesting depth allowed by default, which is the most the server allows.
+pub const DEFAULT_MAX_DEPTH: usize = 200;
+
+/// Limits checked on each document read from the server before it is decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DecodeLimits {
+    /// The deepest nesting of documents and arrays allowed, counting the top-level document as
+    /// one; defaults to `DEFAULT_MAX_DEPTH`.
+    pub max_depth: usize,
+    /// The most elements allowed in any array; unlimited by default.
+    pub max_array_len: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_array_len: usize::MAX,
+        }
+    }
+}
+
+// A document or array being scanned.
+struct Level {
+    // The offset of the level's terminating null byte.
+    end: usize,
+    is_array: bool,
+    len: usize,
+}
+
+impl DecodeLimits {
+    /// Checks an encoded document against the limits.
+    ///
+    /// Malformed documents are not reported here, but by the decoder that reads them next.
+    pub fn check(&self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() < 5 {
+            return Ok(());
+        }
+
+        let mut levels = vec![Level { end: bytes.len() - 1, is_array: false, len: 0 }];
+        let mut pos = 4;
+
+        while let Some(level) = levels.last_mut() {
+            if pos >= level.end {
+                pos = level.end + 1;
+                levels.pop();
+                continue;
+            }
+
+            let element_type = bytes[pos];
+            pos = match skip_cstring(bytes, pos + 1) {
+                Some(pos) => pos,
+                None => return Ok(()),
+            };
+
+            if level.is_array {
+                level.len += 1;
+                if level.len > self.max_array_len {
+                    return Err(DecodeLimitExceeded(DecodeLimit::ArrayLength(self.max_array_len)));
+                }
+            }
+
+            let skip = match element_type {
+                // Embedded document or array.
+                0x03 | 0x04 => None,
+                // Code with scope: a total length and string, then the scope document.
+                0x0F => {
+                    pos = match read_len(bytes, pos + 4) {
+                        Some(len) => pos + 8 + len,
+                        None => return Ok(()),
+                    };
+                    None
+                }
+                0x06 | 0x0A | 0x7F | 0xFF => Some(0),
+                0x08 => Some(1),
+                0x10 => Some(4),
+                0x01 | 0x09 | 0x11 | 0x12 => Some(8),
+                0x07 => Some(12),
+                0x13 => Some(16),
+                0x02 | 0x0D | 0x0E => read_len(bytes, pos).map(|len| 4 + len),
+                0x05 => read_len(bytes, pos).map(|len| 5 + len),
+                0x0C => read_len(bytes, pos).map(|len| 16 + len),
+                0x0B => {
+                    pos = match skip_cstring(bytes, pos).and_then(|p| skip_cstring(bytes, p)) {
+                        Some(pos) => pos,
+                        None => return Ok(()),
+                    };
+                    Some(0)
+                }
+                _ => return Ok(()),
+            };
+
+            match skip {
+                Some(len) => pos += len,
+                None => {
+                    let len = match read_len(bytes, pos) {
+                        Some(len) if len >= 5 && pos + len <= level.end => len,
+                        _ => return Ok(()),
+                    };
+
+                    let end = pos + len - 1;
+                    let is_array = element_type == 0x04;
+                    if levels.len() >= self.max_depth {
+                        return Err(DecodeLimitExceeded(DecodeLimit::Depth(self.max_depth)));
+                    }
+
+                    levels.push(Level { end, is_array, len: 0 });
+                    pos += 4;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Reads a non-negative little-endian i32 at `pos`.
+fn read_len(bytes: &[u8], pos: usize) -> Option<usize> {
+    if pos + 4 > bytes.len() {
+        return None;
+    }
+
+    let len = LittleEndian::read_i32(&bytes[pos..pos + 4]);
+    if len < 0 {
+        None
+    } else {
+        Some(len as usize)
+    }
+}
+
+// Returns the offset after the null-terminated string at `pos`.
+fn skip_cstring(bytes: &[u8], pos: usize) -> Option<usize> {
+    bytes.get(pos..)?.iter().position(|&byte| byte == 0).map(|len| pos + len + 1)
+}
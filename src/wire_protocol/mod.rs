@@ -2,4 +2,5 @@
 
 mod header;
 pub mod flags;
+pub mod limits;
 pub mod operations;
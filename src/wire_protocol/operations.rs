@@ -1,12 +1,15 @@
 //! Wire protocol operational client-server communication logic.
 use bson;
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
 use Error::{ArgumentError, ResponseError};
 use Result;
+use raw::RawDocumentBuf;
 use wire_protocol::header::{Header, OpCode};
-use wire_protocol::flags::{OpInsertFlags, OpQueryFlags, OpReplyFlags, OpUpdateFlags};
+use wire_protocol::flags::{OpInsertFlags, OpMsgFlags, OpQueryFlags, OpReplyFlags, OpUpdateFlags};
+use wire_protocol::limits::DecodeLimits;
 
-use std::io::{Read, Write};
+use std::cmp;
+use std::io::{self, Read, Write};
 use std::mem;
 use std::result::Result::{Ok, Err};
 
@@ -23,11 +26,25 @@ impl ByteLength for bson::Document {
     /// Returns the number of bytes in the serialized BSON document, or an
     /// Error if the document couldn't be serialized.
     fn byte_length(&self) -> Result<i32> {
-        let mut temp_buffer = Vec::new();
+        let mut counter = ByteCounter(0);
 
-        bson::encode_document(&mut temp_buffer, self)?;
+        bson::encode_document(&mut counter, self)?;
 
-        Ok(temp_buffer.len() as i32)
+        Ok(counter.0 as i32)
+    }
+}
+
+// Counts the bytes written to it, to measure a document without keeping its encoding.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
@@ -103,28 +120,17 @@ pub enum Message {
         /// Uniquely identifies the cursor being returned.
         cursor_id: i64,
     },
+    OpMsg {
+        /// The message header.
+        header: Header,
+        /// A bit vector of message options.
+        flags: OpMsgFlags,
+        /// The command, sent as the message's single body section.
+        document: bson::Document,
+    },
 }
 
 impl Message {
-    /// Constructs a new message for a reply.
-    fn new_reply(
-        header: Header,
-        flags: i32,
-        cursor_id: i64,
-        starting_from: i32,
-        number_returned: i32,
-        documents: Vec<bson::Document>,
-    ) -> Message {
-        Message::OpReply {
-            header: header,
-            flags: OpReplyFlags::from_bits_truncate(flags),
-            cursor_id: cursor_id,
-            starting_from: starting_from,
-            number_returned: number_returned,
-            documents: documents,
-        }
-    }
-
     /// Constructs a new message for an update.
     pub fn new_update(
         request_id: i32,
@@ -259,6 +265,29 @@ impl Message {
         }
     }
 
+    /// Constructs a new OP_MSG carrying a command, which must name its database in a `$db`
+    /// field. With `OpMsgFlags::MORE_TO_COME`, the server sends no reply.
+    pub fn new_msg(
+        request_id: i32,
+        flags: OpMsgFlags,
+        document: bson::Document,
+    ) -> Result<Message> {
+        let header_length = mem::size_of::<Header>() as i32;
+        let flags_length = mem::size_of::<u32>() as i32;
+
+        // The section's kind byte precedes its document.
+        let section_length = 1 + document.byte_length()?;
+
+        let total_length = header_length + flags_length + section_length;
+        let header = Header::new_msg(total_length, request_id);
+
+        Ok(Message::OpMsg {
+            header: header,
+            flags: flags,
+            document: document,
+        })
+    }
+
     /// Writes a serialized BSON document to a given buffer.
     ///
     /// # Arguments
@@ -270,11 +299,7 @@ impl Message {
     ///
     /// Returns nothing on success, or an Error on failure.
     fn write_bson_document<W: Write>(buffer: &mut W, bson: &bson::Document) -> Result<()> {
-        let mut temp_buffer = Vec::new();
-
-        bson::encode_document(&mut temp_buffer, bson)?;
-        buffer.write_all(&temp_buffer)?;
-
+        bson::encode_document(buffer, bson)?;
         Ok(())
     }
 
@@ -459,6 +484,36 @@ impl Message {
         Ok(())
     }
 
+    /// Writes a serialized OP_MSG to a given buffer.
+    ///
+    /// # Arguments
+    ///
+    /// `buffer` - The buffer to write to.
+    /// `header` - The header for the given message.
+    /// `flags` - Bit vector of message options.
+    /// `document` - The command, written as a body section.
+    ///
+    /// # Return value
+    ///
+    /// Returns nothing on success, or an Error on failure.
+    fn write_msg<W: Write>(
+        buffer: &mut W,
+        header: &Header,
+        flags: &OpMsgFlags,
+        document: &bson::Document,
+    ) -> Result<()> {
+
+        header.write(buffer)?;
+        buffer.write_u32::<LittleEndian>(flags.bits())?;
+
+        // Kind 0 marks a body section, which holds a single document.
+        buffer.write_u8(0)?;
+        Message::write_bson_document(buffer, document)?;
+
+        let _ = buffer.flush();
+        Ok(())
+    }
+
     /// Attemps to write the serialized message to a buffer.
     ///
     /// # Arguments
@@ -469,6 +524,22 @@ impl Message {
     ///
     /// Returns nothing on success, or an error string on failure.
     pub fn write<W: Write>(&self, buffer: &mut W) -> Result<()> {
+        self.write_with_buffer(buffer, &mut Vec::new())
+    }
+
+    /// Attempts to write the serialized message to a buffer as `write` does, encoding the whole
+    /// message into `scratch` first, so that a connection can reuse one allocation for every
+    /// message and a message that fails to encode writes nothing.
+    pub fn write_with_buffer<W: Write>(&self, buffer: &mut W, scratch: &mut Vec<u8>) -> Result<()> {
+        scratch.clear();
+        self.encode(scratch)?;
+        buffer.write_all(scratch)?;
+        buffer.flush()?;
+        Ok(())
+    }
+
+    // Serializes the message to `buffer`.
+    fn encode(&self, buffer: &mut Vec<u8>) -> Result<()> {
         match *self {
             // Only the server should send replies
             Message::OpReply { .. } => {
@@ -515,6 +586,11 @@ impl Message {
                 number_to_return,
                 cursor_id,
             } => Message::write_get_more(buffer, header, namespace, number_to_return, cursor_id),
+            Message::OpMsg {
+                ref header,
+                ref flags,
+                ref document,
+            } => Message::write_msg(buffer, header, flags, document),
         }
     }
 
@@ -523,38 +599,36 @@ impl Message {
     /// # Arguments
     ///
     /// `buffer` - The buffer to read from.
+    /// `header` - The reply's header, already read.
+    /// `limits` - The limits to check each document against before decoding it.
+    /// `scratch` - Space to read the reply into, reused across messages.
     ///
     /// # Return value
     ///
     /// Returns the reply message on success, or an Error on failure.
-    fn read_reply<R: Read>(buffer: &mut R, header: Header) -> Result<Message> {
-        let mut length = header.message_length - mem::size_of::<Header>() as i32;
-
-        // Read flags
-        let flags = buffer.read_i32::<LittleEndian>()?;
-        length -= mem::size_of::<i32>() as i32;
-
-        // Read cursor_id
-        let cid = buffer.read_i64::<LittleEndian>()?;
-        length -= mem::size_of::<i64>() as i32;
-
-        // Read starting_from
-        let sf = buffer.read_i32::<LittleEndian>()?;
-        length -= mem::size_of::<i32>() as i32;
-
-        // Read number_returned
-        let nr = buffer.read_i32::<LittleEndian>()?;
-        length -= mem::size_of::<i32>() as i32;
-
-        let mut v = Vec::new();
+    fn read_reply<R: Read>(
+        buffer: &mut R,
+        header: Header,
+        limits: &DecodeLimits,
+        scratch: &mut Vec<u8>,
+    ) -> Result<Message> {
+        let body = ReplyBody::read(buffer, &header, scratch)?;
 
-        while length > 0 {
-            let bson = bson::decode_document(buffer)?;
-            length -= bson.byte_length()?;
-            v.push(bson);
+        let mut v = Vec::with_capacity(body.capacity());
+        for document in body.documents() {
+            let document = document?;
+            limits.check(document)?;
+            v.push(bson::decode_document(&mut &document[..])?);
         }
 
-        Ok(Message::new_reply(header, flags, cid, sf, nr, v))
+        Ok(Message::OpReply {
+            header,
+            flags: body.flags,
+            cursor_id: body.cursor_id,
+            starting_from: body.starting_from,
+            number_returned: body.number_returned,
+            documents: v,
+        })
     }
 
     /// Attempts to read a serialized reply Message from a buffer.
@@ -570,16 +644,206 @@ impl Message {
     where
         T: Read + Write,
     {
-        let header = Header::read(buffer)?;
-        match header.op_code {
-            OpCode::Reply => Message::read_reply(buffer, header),
-            opcode => {
-                Err(ResponseError(format!(
-                    "Expected to read OpCode::Reply but instead found \
-                                           opcode {}",
-                    opcode
-                )))
-            }
+        Message::read_with_limits(buffer, &DecodeLimits::default())
+    }
+
+    /// Attempts to read a serialized reply Message from a buffer, rejecting documents that
+    /// exceed the given limits before decoding them.
+    ///
+    /// # Arguments
+    ///
+    /// `buffer` - The buffer to read from.
+    /// `limits` - The limits to check each document against.
+    ///
+    /// # Return value
+    ///
+    /// Returns the reply message on success, or an Error on failure.
+    pub fn read_with_limits<T>(buffer: &mut T, limits: &DecodeLimits) -> Result<Message>
+    where
+        T: Read + Write,
+    {
+        Message::read_with_buffer(buffer, limits, &mut Vec::new())
+    }
+
+    /// Attempts to read a serialized reply Message from a buffer as `read_with_limits` does,
+    /// reading the reply into `scratch` and decoding its documents from there, so that a
+    /// connection can reuse one allocation for every reply.
+    pub fn read_with_buffer<T>(
+        buffer: &mut T,
+        limits: &DecodeLimits,
+        scratch: &mut Vec<u8>,
+    ) -> Result<Message>
+    where
+        T: Read + Write,
+    {
+        let header = read_reply_header(buffer)?;
+        Message::read_reply(buffer, header, limits, scratch)
+    }
+}
+
+/// A reply message whose documents are left encoded, to be read as `RawDocument`s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawReply {
+    /// The message header.
+    pub header: Header,
+    /// A Bit vector of reply options.
+    pub flags: OpReplyFlags,
+    /// Uniquely identifies the cursor being returned.
+    pub cursor_id: i64,
+    /// The starting position for the cursor.
+    pub starting_from: i32,
+    /// The total number of documents being returned.
+    pub number_returned: i32,
+    /// The documents being returned.
+    pub documents: Vec<RawDocumentBuf>,
+}
+
+impl RawReply {
+    /// Attempts to read a serialized reply from a buffer without decoding its documents, which
+    /// are still checked against the given limits.
+    ///
+    /// # Arguments
+    ///
+    /// `buffer` - The buffer to read from.
+    /// `limits` - The limits to check each document against.
+    ///
+    /// # Return value
+    ///
+    /// Returns the reply on success, or an Error on failure.
+    pub fn read_with_limits<T>(buffer: &mut T, limits: &DecodeLimits) -> Result<RawReply>
+    where
+        T: Read + Write,
+    {
+        RawReply::read_with_buffer(buffer, limits, &mut Vec::new())
+    }
+
+    /// Attempts to read a serialized reply as `read_with_limits` does, reading it into
+    /// `scratch` first.
+    pub fn read_with_buffer<T>(
+        buffer: &mut T,
+        limits: &DecodeLimits,
+        scratch: &mut Vec<u8>,
+    ) -> Result<RawReply>
+    where
+        T: Read + Write,
+    {
+        let header = read_reply_header(buffer)?;
+        let body = ReplyBody::read(buffer, &header, scratch)?;
+
+        let mut v = Vec::with_capacity(body.capacity());
+        for document in body.documents() {
+            let document = document?;
+            limits.check(document)?;
+            v.push(RawDocumentBuf::new(document.to_vec())?);
+        }
+
+        Ok(RawReply {
+            header,
+            flags: body.flags,
+            cursor_id: body.cursor_id,
+            starting_from: body.starting_from,
+            number_returned: body.number_returned,
+            documents: v,
+        })
+    }
+}
+
+// Reads a message header, which must be a reply's.
+fn read_reply_header<R: Read>(buffer: &mut R) -> Result<Header> {
+    let header = Header::read(buffer)?;
+    match header.op_code {
+        OpCode::Reply => Ok(header),
+        opcode => {
+            Err(ResponseError(format!(
+                "Expected to read OpCode::Reply but instead found \
+                                       opcode {}",
+                opcode
+            )))
         }
     }
 }
+
+// The length of a reply's fields before its documents: flags, cursor id, starting position and
+// number of documents returned.
+const REPLY_FIELDS_LENGTH: usize = 20;
+
+// The body of a reply, read into a buffer.
+struct ReplyBody<'a> {
+    flags: OpReplyFlags,
+    cursor_id: i64,
+    starting_from: i32,
+    number_returned: i32,
+    // The encoded documents, one after another.
+    documents: &'a [u8],
+}
+
+impl<'a> ReplyBody<'a> {
+    // Reads the body of the reply with `header` into `scratch`, replacing its contents.
+    fn read<R: Read>(buffer: &mut R, header: &Header, scratch: &'a mut Vec<u8>) -> Result<Self> {
+        let length = header.message_length as i64 - mem::size_of::<Header>() as i64;
+        if length < REPLY_FIELDS_LENGTH as i64 {
+            return Err(ResponseError(format!(
+                "Reply has an invalid message length {}.",
+                header.message_length
+            )));
+        }
+
+        scratch.clear();
+        buffer.take(length as u64).read_to_end(scratch)?;
+        if scratch.len() as i64 != length {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let (fields, documents) = scratch.split_at(REPLY_FIELDS_LENGTH);
+        Ok(ReplyBody {
+            flags: OpReplyFlags::from_bits_truncate(LittleEndian::read_i32(&fields[..4])),
+            cursor_id: LittleEndian::read_i64(&fields[4..12]),
+            starting_from: LittleEndian::read_i32(&fields[12..16]),
+            number_returned: LittleEndian::read_i32(&fields[16..]),
+            documents,
+        })
+    }
+
+    // The most documents the reply can hold: the count it claims, bounded by its length, since
+    // the smallest document takes five bytes.
+    fn capacity(&self) -> usize {
+        cmp::min(self.number_returned.max(0) as usize, self.documents.len() / 5)
+    }
+
+    // Splits the reply's documents apart, checking each one's length.
+    fn documents(&self) -> ReplyDocuments<'a> {
+        ReplyDocuments { remaining: self.documents }
+    }
+}
+
+struct ReplyDocuments<'a> {
+    remaining: &'a [u8],
+}
+
+impl<'a> Iterator for ReplyDocuments<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Result<&'a [u8]>> {
+        if self.remaining.is_empty() {
+            return None;
+        }
+
+        let document_length = if self.remaining.len() < 4 {
+            self.remaining.len() as i32
+        } else {
+            LittleEndian::read_i32(&self.remaining[..4])
+        };
+
+        if document_length < 5 || document_length as usize > self.remaining.len() {
+            self.remaining = &[];
+            return Some(Err(ResponseError(format!(
+                "Reply contains a document of invalid length {}.",
+                document_length
+            ))));
+        }
+
+        let (document, rest) = self.remaining.split_at(document_length as usize);
+        self.remaining = rest;
+        Some(Ok(document))
+    }
+}
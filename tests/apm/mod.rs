@@ -1,9 +1,16 @@
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use bson::Bson;
-use mongodb::{Client, ClientOptions, CommandResult, ThreadedClient};
+use mongodb::{Client, ClientOptions, CommandResult, CommandStarted, ConnectionEstablished,
+              HookFilter, HookHandle, Operation, ThreadedClient};
+use mongodb::coll::options::FindOptions;
+use mongodb::connstring::ConnectionString;
 use mongodb::db::ThreadedDatabase;
+use mongodb::log_file::{LogFile, LogRotationOptions};
 use rand;
 
 fn timed_query(_client: Client, command_result: &CommandResult) {
@@ -53,14 +60,26 @@ fn command_duration() {
     coll.find(Some(doc), None).unwrap();
 }
 
+// Splits the operation id out of a log line, leaving the rest of the line intact.
+fn split_operation_id(line: &str) -> (String, i64) {
+    let start = line.find(" op#").expect("log line has no operation id") + 1;
+    let digits = &line[start + 3..];
+    let end = start + 3 + digits.find(' ').unwrap();
+    let id = line[start + 3..end].parse().unwrap();
+    (format!("{}{}", &line[..start], &line[end + 1..]), id)
+}
+
 fn read_first_non_monitor_line(file: &mut BufReader<&File>, line: &mut String) {
     loop {
         file.read_line(line).unwrap();
-        if !line.starts_with("COMMAND.is_master") {
+        if !line.starts_with("COMMAND.is_master") && !line.starts_with("CONNECTION ") {
             break;
         }
         line.clear();
     }
+
+    let (stripped, _) = split_operation_id(line);
+    *line = stripped;
 }
 
 #[test]
@@ -206,3 +225,241 @@ fn logging() {
 
     fs::remove_file("test_log.txt").unwrap();
 }
+
+#[test]
+fn nested_operations() {
+    assert_eq!(None, Operation::current_id());
+
+    let outer_id = {
+        let outer = Operation::start();
+        assert_eq!(Some(outer.id()), Operation::current_id());
+
+        {
+            let inner = Operation::start();
+            assert_eq!(outer.id(), inner.id());
+        }
+
+        // Ending a nested operation leaves the outer one active.
+        assert_eq!(Some(outer.id()), Operation::current_id());
+        outer.id()
+    };
+
+    assert_eq!(None, Operation::current_id());
+    assert!(Operation::start().id() != outer_id);
+}
+
+#[test]
+fn operation_ids() {
+    let _ = fs::remove_file("test_operation_log.txt");
+
+    let client_options = ClientOptions::with_log_file("test_operation_log.txt");
+    let client = Client::connect_with_options("localhost", 27017, client_options).unwrap();
+    let coll = client.db("test-apm-mod").collection("operation_ids");
+    coll.drop().unwrap();
+    coll.insert_many((1..6).map(|i| doc! { "_id": i }).collect(), None).unwrap();
+
+    let mut options = FindOptions::new();
+    options.batch_size = Some(2);
+
+    let cursor = coll.find(None, Some(options.clone())).unwrap();
+    let first_id = cursor.operation_id();
+    assert_eq!(5, cursor.count());
+
+    let second_id = coll.find(None, Some(options)).unwrap().operation_id();
+    assert!(first_id != second_id);
+
+    let f = File::open("test_operation_log.txt").unwrap();
+    let ids: Vec<_> = BufReader::new(&f)
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| line.starts_with("COMMAND.find") || line.starts_with("COMMAND.get_more"))
+        .map(|line| split_operation_id(&line).1)
+        .collect();
+
+    // The first cursor's find and both of its getMores are one operation.
+    assert!(ids.len() >= 3);
+    assert!(ids[..3].iter().all(|&id| id == first_id));
+    assert!(ids.contains(&second_id));
+
+    coll.drop().unwrap();
+    fs::remove_file("test_operation_log.txt").unwrap();
+}
+
+static CONNECTIONS_ESTABLISHED: AtomicUsize = AtomicUsize::new(0);
+
+fn count_connection(_client: Client, established: &ConnectionEstablished) {
+    assert_eq!("localhost:27017", established.host);
+    assert_eq!(None, established.tls_duration);
+    CONNECTIONS_ESTABLISHED.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn connection_established() {
+    let _ = fs::remove_file("test_connection_log.txt");
+
+    let client_options = ClientOptions::with_log_file("test_connection_log.txt");
+    let mut client = Client::connect_with_options("localhost", 27017, client_options).unwrap();
+    client.add_connection_hook(count_connection).unwrap();
+
+    // Each new pooled connection runs the hooks, while reused connections do not.
+    let db = client.db("test-apm-mod");
+    db.version().unwrap();
+    let established = CONNECTIONS_ESTABLISHED.load(Ordering::SeqCst);
+    assert!(established >= 1);
+    db.version().unwrap();
+    assert_eq!(established, CONNECTIONS_ESTABLISHED.load(Ordering::SeqCst));
+
+    let f = File::open("test_connection_log.txt").unwrap();
+    let lines: Vec<_> = BufReader::new(&f)
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| line.starts_with("CONNECTION localhost:27017 "))
+        .collect();
+
+    // The monitor's connection and the pooled one.
+    assert!(lines.len() >= 2);
+    assert!(lines.iter().all(|line| line.contains(" ESTABLISHED: dns ")));
+
+    fs::remove_file("test_connection_log.txt").unwrap();
+}
+
+#[test]
+fn log_rotation() {
+    let path = "test_rotation_log.txt";
+    let line = "x".repeat(39);
+
+    for path in &[path, "test_rotation_log.txt.1", "test_rotation_log.txt.2"] {
+        let _ = fs::remove_file(path);
+    }
+
+    let mut log = LogFile::open(path, log_rotation_options(100, 2)).unwrap();
+
+    // Each file holds two 40-byte lines; the oldest are dropped past two rotated files.
+    for _ in 0..7 {
+        log.write_line(&line).unwrap();
+    }
+
+    let lines = |path: &Path| fs::read_to_string(path).unwrap().lines().count();
+    assert_eq!(1, lines(Path::new(path)));
+    assert_eq!(2, lines(&log.rotated_path(1)));
+    assert_eq!(2, lines(&log.rotated_path(2)));
+    assert!(!log.rotated_path(3).exists());
+
+    // Closed logs discard lines.
+    log.close().unwrap();
+    assert!(log.is_closed());
+    log.write_line(&line).unwrap();
+    assert_eq!(1, lines(Path::new(path)));
+
+    // Without rotated files to keep, rotation truncates the file.
+    let mut log = LogFile::open(path, log_rotation_options(100, 0)).unwrap();
+    log.rotate().unwrap();
+    assert_eq!(0, lines(Path::new(path)));
+
+    for n in 1..3 {
+        fs::remove_file(log.rotated_path(n)).unwrap();
+    }
+
+    // A rotation that fails keeps logging to the current file.
+    let mut log = LogFile::open(path, log_rotation_options(100, 1)).unwrap();
+    log.write_line(&line).unwrap();
+    fs::create_dir(log.rotated_path(1)).unwrap();
+    assert!(log.rotate().is_err());
+    assert!(!log.is_closed());
+    log.write_line(&line).unwrap();
+    assert_eq!(2, lines(Path::new(path)));
+    fs::remove_dir(log.rotated_path(1)).unwrap();
+    fs::remove_file(path).unwrap();
+
+    // Clients flush and close their log file on request.
+    let mut options = ClientOptions::with_log_file(path);
+    options.log_rotation = Some(log_rotation_options(1024, 1));
+    let client = Client::with_config(ConnectionString::new("localhost", 27017), Some(options), None)
+        .unwrap();
+    client.flush_log().unwrap();
+    client.close_log().unwrap();
+
+    fs::remove_file(path).unwrap();
+}
+
+fn log_rotation_options(max_size_bytes: u64, max_files: usize) -> LogRotationOptions {
+    let mut rotation = LogRotationOptions::new();
+    rotation.max_size_bytes = Some(max_size_bytes);
+    rotation.max_files = Some(max_files);
+    rotation
+}
+
+static FILTERED_COMPLETIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_filtered(_client: Client, result: &CommandResult) {
+    assert_eq!("test-apm-mod", result.database_name());
+    assert_eq!(Some("hook_filter"), result.collection_name());
+    FILTERED_COMPLETIONS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn hook_filter() {
+    let mut filter = HookFilter::new();
+    assert!(filter.matches("app", None, "is_master"));
+
+    filter.databases = Some(vec![String::from("app")]);
+    filter.collections = Some(vec![String::from("users")]);
+    filter.excluded_command_names = Some(vec![String::from("get_more")]);
+    assert!(filter.matches("app", Some("users"), "find"));
+    assert!(!filter.matches("app", Some("users"), "get_more"));
+    assert!(!filter.matches("app", Some("orders"), "find"));
+    assert!(!filter.matches("admin", Some("users"), "find"));
+    assert!(!filter.matches("app", None, "is_master"));
+
+    filter.collections = None;
+    filter.command_names = Some(vec![String::from("insert_one")]);
+    assert!(filter.matches("app", None, "insert_one"));
+    assert!(!filter.matches("app", None, "find"));
+}
+
+#[test]
+fn registered_hooks() {
+    let mut client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-apm-mod");
+    let coll = db.collection("hook_filter");
+    let other = db.collection("hook_filter_other");
+    coll.drop().unwrap();
+
+    let mut filter = HookFilter::new();
+    filter.collections = Some(vec![String::from("hook_filter")]);
+    let handle = client.register_completion_hook(count_filtered, Some(filter)).unwrap();
+
+    // Only commands on the filtered collection run the hook.
+    coll.insert_one(doc! { "_id": 1 }, None).unwrap();
+    other.insert_one(doc! { "_id": 1 }, None).unwrap();
+    db.version().unwrap();
+    assert_eq!(1, FILTERED_COMPLETIONS.load(Ordering::SeqCst));
+
+    handle.remove();
+    coll.insert_one(doc! { "_id": 2 }, None).unwrap();
+    assert_eq!(1, FILTERED_COMPLETIONS.load(Ordering::SeqCst));
+
+    other.drop().unwrap();
+}
+
+static SELF_REMOVING_HANDLE: Mutex<Option<HookHandle>> = Mutex::new(None);
+static SELF_REMOVING_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+fn remove_self(_client: Client, _started: &CommandStarted) {
+    SELF_REMOVING_RUNS.fetch_add(1, Ordering::SeqCst);
+    SELF_REMOVING_HANDLE.lock().unwrap().take();
+}
+
+#[test]
+fn hook_removed_from_within_itself() {
+    let mut client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-apm-mod");
+
+    let handle = client.register_start_hook(remove_self, None).unwrap();
+    *SELF_REMOVING_HANDLE.lock().unwrap() = Some(handle);
+
+    // Dropping the handle inside the hook removes it rather than deadlocking.
+    db.version().unwrap();
+    db.version().unwrap();
+    assert_eq!(1, SELF_REMOVING_RUNS.load(Ordering::SeqCst));
+}
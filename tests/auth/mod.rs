@@ -1,7 +1,7 @@
 use bson::Bson;
 use mongodb::{CommandType, Client, ThreadedClient};
 use mongodb::db::ThreadedDatabase;
-use mongodb::error::Error::OperationError;
+use mongodb::error::Error::{OperationError, ServerError};
 
 fn doc_vec_find(vec: &[Bson], key: &str, val: &str) -> Option<Bson> {
     vec.iter()
@@ -40,10 +40,10 @@ fn invalid_user() {
     };
 
     match db.auth("test-auth-mod-invalid_user-saghm", "some_password") {
-        Err(OperationError(_)) => (),
+        Err(OperationError(_)) | Err(ServerError(_)) => (),
         Err(_) => {
             panic!(
-                "Expected OperationError for invalid authentication, but got some other error instead"
+                "Expected an authentication failure, but got some other error instead"
             )
         }
         _ => panic!("Authentication succeeded despite invalid credentials"),
@@ -92,10 +92,10 @@ fn invalid_password() {
     ).unwrap();
 
     match db.auth("test-auth-mod-invalid_password-saghm", "wrong_password") {
-        Err(OperationError(_)) => (),
+        Err(OperationError(_)) | Err(ServerError(_)) => (),
         Err(_) => {
             panic!(
-                "Expected OperationError for invalid authentication, but got some other error instead"
+                "Expected an authentication failure, but got some other error instead"
             )
         }
         _ => panic!("Authentication succeeded despite invalid credentials"),
@@ -1,4 +1,5 @@
 use mongodb::coll::Collection;
+use mongodb::coll::options::FindOptions;
 use mongodb::cursor::Cursor;
 use mongodb::db::ThreadedDatabase;
 use mongodb::{Client, Result, ThreadedClient};
@@ -41,3 +42,63 @@ fn aggregate_batch_size() {
 fn find_batch_size() {
     test_batch_size("find_batch_size", |coll| coll.find(None, None));
 }
+
+#[test]
+fn get_more_batch_size() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("get_more_batch_size");
+    let coll = db.collection("get_more_batch_size");
+    coll.drop().unwrap();
+
+    let contents = (0..100).map(|i| doc! { "x": i }).collect();
+    coll.insert_many(contents, None).unwrap();
+
+    let mut options = FindOptions::new();
+    options.batch_size = Some(10);
+    options.get_more_batch_size = Some(30);
+
+    let mut cursor = coll.find(None, Some(options)).unwrap();
+    assert_eq!(10, cursor.drain_current_batch().unwrap().len());
+    assert_eq!(30, cursor.drain_current_batch().unwrap().len());
+
+    cursor.set_batch_size(50);
+    assert_eq!(50, cursor.drain_current_batch().unwrap().len());
+    assert_eq!(10, cursor.drain_current_batch().unwrap().len());
+}
+
+#[test]
+fn adaptive_batch_size() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("adaptive_batch_size");
+    let coll = db.collection("adaptive_batch_size");
+    coll.drop().unwrap();
+
+    let contents = (0..1000).map(|i| doc! { "x": i }).collect();
+    coll.insert_many(contents, None).unwrap();
+
+    let mut options = FindOptions::new();
+    options.batch_size = Some(10);
+    options.adaptive_batch_size = true;
+
+    // Small documents let each getMore request twice as many as the last.
+    let mut cursor = coll.find(None, Some(options)).unwrap();
+    let sizes: Vec<_> = (0..5).map(|_| cursor.drain_current_batch().unwrap().len()).collect();
+    assert_eq!(vec![10, 10, 20, 40, 80], sizes);
+    assert_eq!(160, cursor.batch_size());
+
+    // Growth stops at as many documents as fit in a 16MB reply.
+    coll.drop().unwrap();
+    let filler = "x".repeat(1024 * 1024);
+    let contents = (0..20).map(|i| doc! { "x": i, "filler": filler.clone() }).collect();
+    coll.insert_many(contents, None).unwrap();
+
+    let mut options = FindOptions::new();
+    options.batch_size = Some(1);
+    options.adaptive_batch_size = true;
+
+    let mut cursor = coll.find(None, Some(options)).unwrap();
+    for _ in 0..5 {
+        cursor.drain_current_batch().unwrap();
+    }
+    assert!(cursor.batch_size() < 16);
+}
@@ -1,5 +1,5 @@
 use bson::Bson;
-use mongodb::coll::options::WriteModel;
+use mongodb::coll::options::{BulkWriteOptions, WriteModel};
 use mongodb::{Client, ThreadedClient};
 use mongodb::db::ThreadedDatabase;
 
@@ -204,3 +204,74 @@ fn bulk_ordered_mix() {
     check_value_in_tree!(result.inserted_ids, 12, 104);
     check_value_in_tree!(result.upserted_ids, 8, 6);
 }
+
+#[test]
+fn bulk_upserts() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-bulk");
+    let coll = db.collection("bulk_upserts");
+    coll.drop().unwrap();
+
+    // Consecutive updates are sent in a single batch, whose reply lists every upsert.
+    let models = vec![
+        WriteModel::UpdateOne {
+            filter: doc! { "_id": 1 },
+            update: doc! { "$set": { "x": 11 } },
+            upsert: Some(true),
+        },
+        WriteModel::UpdateOne {
+            filter: doc! { "_id": 1 },
+            update: doc! { "$set": { "x": 12 } },
+            upsert: Some(true),
+        },
+        WriteModel::UpdateMany {
+            filter: doc! { "_id": 2 },
+            update: doc! { "$set": { "x": 21 } },
+            upsert: Some(true),
+        },
+    ];
+
+    let result = coll.bulk_write(models, true);
+
+    assert!(result.bulk_write_exception.is_none());
+    assert_eq!(result.matched_count, 1);
+    assert_eq!(result.modified_count, 1);
+    assert_eq!(result.upserted_count, 2);
+    assert_eq!(result.upserted_ids.get(&0), Some(&Bson::I32(1)));
+    assert_eq!(result.upserted_ids.get(&2), Some(&Bson::I32(2)));
+}
+
+#[test]
+fn bulk_verbose_results() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-bulk");
+    let coll = db.collection("bulk_verbose_results");
+    coll.drop().unwrap();
+
+    let models = vec![
+        WriteModel::InsertOne { document: doc! { "_id": 1 } },
+        WriteModel::InsertOne { document: doc! { "_id": 2 } },
+        WriteModel::DeleteOne { filter: doc! { "_id": 1 } },
+        WriteModel::InsertOne { document: doc! { "_id": 3 } },
+    ];
+
+    let result = coll.bulk_write(models.clone(), true);
+    assert_eq!(None, result.batch_replies);
+    coll.drop().unwrap();
+
+    let options = BulkWriteOptions {
+        verbose_results: Some(true),
+        ..BulkWriteOptions::new()
+    };
+    let result = coll.bulk_write_with_options(models, Some(options));
+    let replies = result.batch_replies.expect("Expected batch replies.");
+
+    let batches: Vec<_> = replies.iter().map(|reply| (reply.start_index, reply.len)).collect();
+    assert_eq!(vec![(0, 2), (2, 1), (3, 1)], batches);
+
+    let counts: Vec<_> = replies
+        .iter()
+        .map(|reply| reply.reply.as_ref().and_then(|reply| reply.get("n")).cloned())
+        .collect();
+    assert_eq!(vec![Some(Bson::I32(2)), Some(Bson::I32(1)), Some(Bson::I32(1))], counts);
+}
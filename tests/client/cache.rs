@@ -0,0 +1,83 @@
+use mongodb::{Client, Error, ThreadedClient};
+use mongodb::cache::{CacheOptions, CachedCollection};
+use mongodb::db::ThreadedDatabase;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Profile {
+    name: String,
+    visits: i32,
+}
+
+#[test]
+fn read_through_and_expiry() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-cache-read_through_and_expiry");
+    db.drop_database().unwrap();
+
+    let mut options = CacheOptions::new();
+    options.ttl_ms = Some(200);
+    let cache = CachedCollection::new(&db.collection("profiles"), Some(options));
+    cache.ensure_indexes().unwrap();
+
+    let ada = Profile {
+        name: String::from("Ada"),
+        visits: 1,
+    };
+
+    assert_eq!(None, cache.get("ada").unwrap());
+    assert_eq!(ada, cache.get_or_load("ada", || Ok(ada.clone())).unwrap());
+
+    // A hit does not call the loader.
+    let cached = cache
+        .get_or_load("ada", || Err(Error::ArgumentError(String::from("unexpected load"))))
+        .unwrap();
+    assert_eq!(ada, cached);
+
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(None, cache.get("ada").unwrap());
+
+    cache.insert("ada", &ada).unwrap();
+    assert!(cache.invalidate("ada").unwrap());
+    assert_eq!(None, cache.get("ada").unwrap());
+}
+
+#[test]
+fn concurrent_misses_load_once() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-cache-concurrent_misses_load_once");
+    db.drop_database().unwrap();
+
+    let cache: CachedCollection<Profile> = CachedCollection::new(&db.collection("profiles"), None);
+    let loads = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let cache = cache.clone();
+            let loads = loads.clone();
+
+            thread::spawn(move || {
+                cache
+                    .get_or_load("grace", || {
+                        loads.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(100));
+                        Ok(Profile {
+                            name: String::from("Grace"),
+                            visits: 3,
+                        })
+                    })
+                    .unwrap()
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!(3, handle.join().unwrap().visits);
+    }
+
+    assert_eq!(1, loads.load(Ordering::SeqCst));
+}
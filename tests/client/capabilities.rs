@@ -0,0 +1,205 @@
+use mongodb::{Client, Error, ThreadedClient};
+use mongodb::connstring::{self, ConnectionString, Host};
+use mongodb::stream::StreamConnector;
+use mongodb::topology::{Capabilities, TopologyDescription, TopologyType};
+use mongodb::topology::monitor::IsMasterResult;
+use mongodb::topology::server::{Server, ServerType};
+
+use std::sync::{Arc, RwLock};
+
+fn host(port: u16) -> Host {
+    connstring::parse_host(&format!("localhost:{}", port)).unwrap()
+}
+
+// Describes a server as monitoring would after an isMaster reporting the given wire version and
+// session timeout.
+fn add_server(
+    topology: &mut TopologyDescription,
+    client: &Client,
+    port: u16,
+    server_type: ServerType,
+    max_wire_version: i64,
+    session_timeout: Option<i64>,
+) {
+    let host = host(port);
+    let server = Server::new(
+        client.clone(),
+        host.clone(),
+        Arc::new(RwLock::new(TopologyDescription::new(StreamConnector::default()))),
+        false,
+        StreamConnector::default(),
+    );
+
+    {
+        let mut description = server.description.write().unwrap();
+        description.server_type = server_type;
+        description.max_wire_version = max_wire_version;
+        description.logical_session_timeout_minutes = session_timeout;
+    }
+
+    topology.servers.insert(host, server);
+}
+
+fn client() -> Client {
+    Client::with_config(ConnectionString::new("i-dont-exist", 27017), None, None).unwrap()
+}
+
+#[test]
+fn replica_set_capabilities() {
+    let client = client();
+    let mut topology = TopologyDescription::new(StreamConnector::default());
+    topology.topology_type = TopologyType::ReplicaSetWithPrimary;
+
+    add_server(&mut topology, &client, 27017, ServerType::RSPrimary, 8, Some(30));
+    add_server(&mut topology, &client, 27018, ServerType::RSSecondary, 7, Some(20));
+    add_server(&mut topology, &client, 27019, ServerType::RSArbiter, 6, None);
+
+    let capabilities = topology.capabilities();
+    assert_eq!(Some(7), capabilities.wire_version);
+    assert_eq!(Some(20), capabilities.logical_session_timeout_minutes);
+    assert!(capabilities.sessions_supported);
+    assert!(capabilities.retryable_writes_available);
+    assert!(capabilities.transactions_supported);
+    assert_eq!(None, capabilities.compression);
+    assert_eq!(
+        "topology: ReplicaSetWithPrimary, wire version: 7, sessions: yes, retryable writes: yes, \
+         transactions: yes, compression: none, federated: no",
+        capabilities.to_string()
+    );
+
+    // A member that predates sessions disables them for the whole deployment.
+    add_server(&mut topology, &client, 27020, ServerType::RSSecondary, 5, None);
+
+    let capabilities = topology.capabilities();
+    assert_eq!(Some(5), capabilities.wire_version);
+    assert_eq!(None, capabilities.logical_session_timeout_minutes);
+    assert!(!capabilities.sessions_supported);
+    assert!(!capabilities.retryable_writes_available);
+    assert!(!capabilities.transactions_supported);
+}
+
+#[test]
+fn sharded_and_standalone_capabilities() {
+    let client = client();
+    let mut topology = TopologyDescription::new(StreamConnector::default());
+    topology.topology_type = TopologyType::Sharded;
+
+    add_server(&mut topology, &client, 27017, ServerType::Mongos, 7, Some(30));
+
+    let capabilities = topology.capabilities();
+    assert!(capabilities.retryable_writes_available);
+    // Sharded transactions require MongoDB 4.2.
+    assert!(!capabilities.transactions_supported);
+
+    let mut topology = TopologyDescription::new(StreamConnector::default());
+    topology.topology_type = TopologyType::Single;
+
+    add_server(&mut topology, &client, 27017, ServerType::Standalone, 8, Some(30));
+
+    let capabilities = topology.capabilities();
+    assert!(capabilities.sessions_supported);
+    assert!(!capabilities.retryable_writes_available);
+    assert!(!capabilities.transactions_supported);
+
+    let capabilities = TopologyDescription::new(StreamConnector::default()).capabilities();
+    assert_eq!(None, capabilities.wire_version);
+    assert!(!capabilities.sessions_supported);
+}
+
+#[test]
+fn topology_snapshot() {
+    let client = client();
+    let mut topology = TopologyDescription::new(StreamConnector::default());
+    topology.topology_type = TopologyType::ReplicaSetNoPrimary;
+
+    add_server(&mut topology, &client, 27018, ServerType::RSSecondary, 8, Some(30));
+    add_server(&mut topology, &client, 27017, ServerType::Unknown, 0, None);
+    topology.servers[&host(27018)].description.write().unwrap().round_trip_time = Some(3);
+    topology.servers[&host(27017)]
+        .description
+        .write()
+        .unwrap()
+        .set_err(Error::OperationError(String::from("connection refused")));
+
+    let snapshot = topology.snapshot();
+    assert_eq!(TopologyType::ReplicaSetNoPrimary, snapshot.topology_type);
+    assert_eq!(host(27017), snapshot.servers[0].host);
+    assert_eq!(Some(String::from("connection refused")), snapshot.servers[0].error);
+    assert_eq!(ServerType::RSSecondary, snapshot.servers[1].server_type);
+    assert_eq!(
+        "ReplicaSetNoPrimary [localhost:27017: Unknown, rtt unknown, last error: connection \
+         refused; localhost:27018: RSSecondary, rtt 3 ms]",
+        snapshot.to_string()
+    );
+}
+
+#[test]
+fn is_master_session_timeout() {
+    let reply = doc! {
+        "ok": 1,
+        "ismaster": true,
+        "minWireVersion": 0,
+        "maxWireVersion": 8,
+        "logicalSessionTimeoutMinutes": 30,
+    };
+
+    let result = IsMasterResult::new(reply).unwrap();
+    assert_eq!(0, result.min_wire_version);
+    assert_eq!(8, result.max_wire_version);
+    assert_eq!(Some(30), result.logical_session_timeout_minutes);
+}
+
+#[test]
+fn client_capabilities() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    client.is_master().unwrap();
+
+    let capabilities = client.capabilities().unwrap();
+    let wire_version = capabilities.wire_version.expect("Expected a known wire version.");
+
+    let formatted = capabilities.to_string();
+    let prefix = format!(
+        "topology: {:?}, wire version: {}, ",
+        capabilities.topology_type,
+        wire_version
+    );
+    assert!(formatted.starts_with(&prefix), "{}", formatted);
+    assert!(formatted.ends_with("compression: none, federated: no"), "{}", formatted);
+}
+
+#[test]
+fn capabilities_display() {
+    let capabilities = Capabilities {
+        topology_type: TopologyType::ReplicaSetWithPrimary,
+        wire_version: Some(8),
+        logical_session_timeout_minutes: Some(30),
+        sessions_supported: true,
+        retryable_writes_available: true,
+        transactions_supported: true,
+        compression: None,
+        federated: false,
+    };
+
+    assert_eq!(
+        "topology: ReplicaSetWithPrimary, wire version: 8, sessions: yes, retryable writes: yes, \
+         transactions: yes, compression: none, federated: no",
+        capabilities.to_string()
+    );
+
+    let unknown = Capabilities {
+        topology_type: TopologyType::Unknown,
+        wire_version: None,
+        logical_session_timeout_minutes: None,
+        sessions_supported: false,
+        retryable_writes_available: false,
+        transactions_supported: false,
+        compression: None,
+        federated: false,
+    };
+
+    assert_eq!(
+        "topology: Unknown, wire version: unknown, sessions: no, retryable writes: no, \
+         transactions: no, compression: none, federated: no",
+        unknown.to_string()
+    );
+}
@@ -1,9 +1,23 @@
 use bson::Bson;
 
-use mongodb::{Client, ThreadedClient};
+use mongodb::{Client, Error, ErrorCode, ThreadedClient};
 use mongodb::db::ThreadedDatabase;
-use mongodb::coll::options::{FindOptions, FindOneAndUpdateOptions, IndexModel, IndexOptions,
-                             ReturnDocument};
+use mongodb::db::options::CollModOptions;
+use mongodb::error::RetryHint;
+use mongodb::common::{Acknowledgment, ReadConcern, ReadConcernLevel, WriteConcern,
+                      WriteConcernErrorPolicy};
+use mongodb::coll::Collection;
+use mongodb::connstring::ConnectionString;
+use mongodb::coll::options::{AggregateToCollectionOptions, CountOptions, DistinctOptions,
+                             ExplainVerbosity, ExportFormat, FindOptions, FindOneAndUpdateOptions,
+                             IndexModel, IndexOptions, InsertManyOptions, MapReduceAction,
+                             MapReduceOptions, MapReduceOutput, ReturnDocument, TextSearchOptions,
+                             UpdateOptions, WriteModel};
+use mongodb::coll::results::{AggregateProgress, BulkUpdateResult, ExplainSummary, MapReduceResult,
+                             UpdateResult};
+
+use std::thread;
+use std::time::Duration;
 
 #[test]
 fn find_sorted() {
@@ -50,6 +64,245 @@ fn find_sorted() {
     assert!(cursor.next().is_none());
 }
 
+#[test]
+fn find_with_index_options() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("find_with_index_options");
+
+    coll.drop().expect("Failed to drop collection");
+    coll.insert_many(
+        vec![
+            doc! { "n": 1, "title": "Jaws" },
+            doc! { "n": 2, "title": "Back to the Future" },
+            doc! { "n": 3, "title": "Dobby" },
+        ],
+        None,
+    ).expect("Failed to insert documents.");
+    coll.create_index(doc! { "n": 1 }, None).expect(
+        "Failed to create index.",
+    );
+
+    let mut opts = FindOptions::new();
+    opts.min = Some(doc! { "n": 2 });
+    opts.max = Some(doc! { "n": 3 });
+    opts.return_key = Some(true);
+    opts.comment = Some(Bson::from("find_with_index_options"));
+
+    let results: Vec<_> = coll.find(None, Some(opts))
+        .expect("Failed to execute find command.")
+        .map(|doc| doc.expect("Failed to read document."))
+        .collect();
+
+    assert_eq!(vec![doc! { "n": 2 }], results);
+
+    let mut opts = FindOptions::new();
+    opts.show_record_id = Some(true);
+
+    let doc = coll.find_one(Some(doc! { "n": 1 }), Some(opts))
+        .expect("Failed to execute find command.")
+        .expect("Expected a document.");
+
+    assert!(doc.contains_key("$recordId"));
+}
+
+#[test]
+fn find_with_hint() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("find_with_hint");
+
+    coll.drop().expect("Failed to drop collection");
+    coll.insert_many(
+        vec![doc! { "n": 2, "title": "Jaws" }, doc! { "n": 1, "title": "Dobby" }],
+        None,
+    ).expect("Failed to insert documents.");
+    let name = coll.create_index(doc! { "n": 1 }, None).expect("Failed to create index.");
+
+    let titles = |opts: FindOptions| -> Vec<String> {
+        coll.find(None, Some(opts))
+            .expect("Failed to execute find command.")
+            .map(|doc| doc.unwrap().get_str("title").unwrap().to_owned())
+            .collect()
+    };
+
+    // Scanning the hinted index returns documents in index order.
+    let mut opts = FindOptions::new();
+    opts.hint = Some(name);
+    assert_eq!(vec!["Dobby", "Jaws"], titles(opts));
+
+    let mut opts = FindOptions::new();
+    opts.hint_doc = Some(doc! { "n": 1 });
+    opts.allow_disk_use = Some(true);
+    assert_eq!(vec!["Dobby", "Jaws"], titles(opts));
+
+    let mut opts = FindOptions::new();
+    opts.hint = Some(String::from("missing_1"));
+    opts.allow_disk_use = Some(false);
+    assert!(coll.find(None, Some(opts)).and_then(|mut cursor| cursor.next_n(1)).is_err());
+}
+
+#[test]
+fn find_with_command_options() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("find_with_command_options");
+
+    coll.drop().expect("Failed to drop collection");
+    coll.insert_many(
+        (0..10).map(|n| doc! { "n": n }).collect(),
+        None,
+    ).expect("Failed to insert documents.");
+
+    let mut opts = FindOptions::new();
+    opts.allow_disk_use = Some(true);
+    opts.sort = Some(doc! { "n": -1 });
+    opts.skip = Some(1);
+    opts.limit = Some(3);
+    opts.batch_size = Some(2);
+    opts.projection = Some(doc! { "_id": 0 });
+
+    let results: Vec<_> = coll.find(None, Some(opts))
+        .expect("Failed to execute find command.")
+        .map(|doc| doc.expect("Failed to read document."))
+        .collect();
+
+    assert_eq!(vec![doc! { "n": 8 }, doc! { "n": 7 }, doc! { "n": 6 }], results);
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Movie {
+    title: String,
+    year: i32,
+}
+
+#[test]
+fn find_as() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("find_as");
+
+    coll.drop().expect("Failed to drop collection");
+    coll.insert_many(
+        vec![
+            doc! { "title": "Jaws", "year": 1975 },
+            doc! { "title": "Back to the Future", "year": 1985 },
+            doc! { "title": "Dobby" },
+        ],
+        None,
+    ).expect("Failed to insert documents.");
+
+    let mut opts = FindOptions::new();
+    opts.sort = Some(doc! { "year": 1 });
+
+    let mut cursor = coll.find_as::<Movie>(Some(doc! { "year": { "$exists": true } }), Some(opts))
+        .expect("Failed to execute find command.");
+
+    assert_eq!(
+        Movie { title: String::from("Jaws"), year: 1975 },
+        cursor.next().unwrap().unwrap()
+    );
+    assert_eq!(
+        Movie { title: String::from("Back to the Future"), year: 1985 },
+        cursor.next().unwrap().unwrap()
+    );
+    assert!(cursor.next().is_none());
+
+    // A document missing a field is reported, rather than ending iteration.
+    let movie = coll.find_one_as::<Movie>(Some(doc! { "title": "Dobby" }), None);
+    match movie {
+        Err(Error::DecoderError(_)) => (),
+        other => panic!("Expected a decoder error, got {:?}", other),
+    }
+
+    let movie = coll.find_one_as::<Movie>(Some(doc! { "title": "Jaws" }), None)
+        .expect("Failed to execute find command.");
+    assert_eq!(Some(Movie { title: String::from("Jaws"), year: 1975 }), movie);
+}
+
+#[test]
+fn parallel_scan() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("parallel_scan");
+
+    coll.drop().expect("Failed to drop collection");
+    let docs = (0..1000).map(|i| doc! { "_id": i }).collect();
+    coll.insert_many(docs, None).expect("Failed to insert documents.");
+
+    let cursors = coll.parallel_scan(4, None).expect("Failed to split collection.");
+    assert!(cursors.len() > 1 && cursors.len() <= 4);
+
+    // The cursors read every document exactly once, even from other threads.
+    let threads: Vec<_> = cursors
+        .into_iter()
+        .map(|cursor| thread::spawn(move || cursor.map(Result::unwrap).collect::<Vec<_>>()))
+        .collect();
+
+    let mut ids: Vec<_> = threads
+        .into_iter()
+        .flat_map(|thread| thread.join().unwrap())
+        .map(|doc| doc.get_i32("_id").unwrap())
+        .collect();
+    ids.sort();
+    assert_eq!((0..1000).collect::<Vec<_>>(), ids);
+
+    // `_id`s of another type than the bounds are read too.
+    coll.insert_one(doc! { "_id": "a" }, None).expect("Failed to insert document.");
+    let cursors = coll.parallel_scan(4, None).expect("Failed to split collection.");
+    let count: usize = cursors.into_iter().map(|cursor| cursor.count()).sum();
+    assert_eq!(1001, count);
+
+    // A single cursor reads the whole collection.
+    let cursors = coll.parallel_scan(1, None).expect("Failed to split collection.");
+    assert_eq!(1, cursors.len());
+
+    match coll.parallel_scan(0, None) {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}", other.map(|c| c.len())),
+    }
+
+    let mut options = FindOptions::new();
+    options.limit = Some(10);
+    match coll.parallel_scan(4, Some(options)) {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}", other.map(|c| c.len())),
+    }
+}
+
+#[test]
+fn export_and_import() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let source = db.collection("export_source");
+    let target = db.collection("export_target");
+
+    source.drop().expect("Failed to drop collection");
+    let docs: Vec<_> = (0..2500)
+        .map(|i| doc! { "_id": i, "name": format!("user {}", i) })
+        .collect();
+    source.insert_many(docs.clone(), None).expect("Failed to insert documents.");
+
+    for &format in &[ExportFormat::Bson, ExportFormat::ExtendedJson] {
+        let mut dump = Vec::new();
+        assert_eq!(2500, source.export_to_writer(&mut dump, format).unwrap());
+
+        target.drop().expect("Failed to drop collection");
+        assert_eq!(2500, target.import_from_reader(&dump[..], format).unwrap());
+
+        let mut opts = FindOptions::new();
+        opts.sort = Some(doc! { "_id": 1 });
+        let imported: Vec<_> = target.find(None, Some(opts)).unwrap().map(Result::unwrap).collect();
+        assert_eq!(docs, imported);
+    }
+
+    // A truncated dump is an error, after the documents before it are imported.
+    let mut dump = Vec::new();
+    source.export_to_writer(&mut dump, ExportFormat::Bson).unwrap();
+    target.drop().expect("Failed to drop collection");
+    assert!(target.import_from_reader(&dump[..dump.len() - 1], ExportFormat::Bson).is_err());
+}
+
 #[test]
 fn find_and_insert() {
     let client = Client::connect("localhost", 27017).unwrap();
@@ -126,7 +379,7 @@ fn find_one_and_delete() {
         .expect("Failed to insert documents.");
 
     // Find and Delete document
-    let result = coll.find_one_and_delete(doc2.clone(), None).expect(
+    let result = coll.find_one_and_delete(&doc2, None).expect(
         "Failed to execute find_one_and_delete command.",
     );
 
@@ -170,7 +423,7 @@ fn find_one_and_replace() {
         .expect("Failed to insert documents into collection.");
 
     // Replace single document
-    let result = coll.find_one_and_replace(doc2.clone(), doc3.clone(), None)
+    let result = coll.find_one_and_replace(&doc2, &doc3, None)
         .expect("Failed to execute find_one_and_replace command.");
 
     match result.unwrap().get("title") {
@@ -202,7 +455,7 @@ fn find_one_and_replace() {
     // Replace with 'new' option
     let mut opts = FindOneAndUpdateOptions::new();
     opts.return_document = Some(ReturnDocument::After);
-    let result = coll.find_one_and_replace(doc3.clone(), doc2.clone(), Some(opts))
+    let result = coll.find_one_and_replace(&doc3, &doc2, Some(opts))
         .expect("Failed to execute find_one_and_replace command.");
 
     match result.unwrap().get("title") {
@@ -230,7 +483,7 @@ fn find_one_and_update() {
     // Update single document
     let update = doc! { "$set": { "director": "Robert Zemeckis" } };
 
-    let result = coll.find_one_and_update(doc2.clone(), update, None)
+    let result = coll.find_one_and_update(&doc2, update, None)
         .expect("Failed to execute find_one_and_update command.");
 
     match result.unwrap().get("title") {
@@ -254,6 +507,75 @@ fn find_one_and_update() {
     }
 }
 
+#[test]
+fn find_one_and_update_typed_errors() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("find_one_and_update_typed_errors");
+
+    coll.drop().expect("Failed to drop collection");
+    coll.insert_one(doc! { "title": "Jaws", "year": 1975 }, None).unwrap();
+
+    let mut options = CollModOptions::new();
+    options.validator = Some(doc! { "year": { "$type": "number" } });
+    db.coll_mod("find_one_and_update_typed_errors", options).unwrap();
+
+    let unset = doc! { "$unset": { "year": 1 } };
+    let err = coll.find_one_and_update(doc! { "title": "Jaws" }, unset, None)
+        .expect_err("Expected the update to fail validation.");
+    match err {
+        Error::DocumentValidationFailure(ref err) => assert_eq!(121, err.code),
+        _ => panic!("Expected a document validation failure, got {:?}.", err),
+    }
+    assert_eq!(RetryHint::DoNotRetry, err.retry_hint());
+
+    skip_if_db_version_below!(db, 5, 0);
+
+    let options = FindOneAndUpdateOptions {
+        return_document: Some(ReturnDocument::After),
+        let_vars: Some(doc! { "target": "Jaws" }),
+        ..FindOneAndUpdateOptions::new()
+    };
+    let filter = doc! { "$expr": { "$eq": ["$title", "$$target"] } };
+    let result = coll.find_one_and_update(filter, doc! { "$inc": { "year": 1 } }, Some(options))
+        .unwrap()
+        .expect("Expected a document to match the filter.");
+    assert_eq!(Some(&Bson::I32(1976)), result.get("year"));
+}
+
+#[test]
+fn bypass_document_validation() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("bypass_document_validation");
+
+    coll.drop().expect("Failed to drop collection");
+    coll.insert_one(doc! { "title": "Jaws", "year": 1975 }, None).unwrap();
+
+    let mut options = CollModOptions::new();
+    options.validator = Some(doc! { "year": { "$type": "number" } });
+    db.coll_mod("bypass_document_validation", options).unwrap();
+
+    let legacy = vec![doc! { "title": "Duel", "year": "1971" }];
+    let result = coll.insert_many(legacy.clone(), None).unwrap();
+    assert!(result.bulk_write_exception.is_some());
+
+    let options = InsertManyOptions::builder().bypass_document_validation(true).build();
+    let result = coll.insert_many(legacy, options).unwrap();
+    assert!(result.bulk_write_exception.is_none());
+
+    let options = UpdateOptions::builder().bypass_document_validation(true).build();
+    let update = doc! { "$set": { "year": "1975" } };
+    let result = coll.update_one(doc! { "title": "Jaws" }, update, options).unwrap();
+    assert!(result.write_exception.is_none());
+    assert_eq!(1, result.modified_count);
+
+    let options = FindOneAndUpdateOptions::builder().bypass_document_validation(true).build();
+    let unset = doc! { "$unset": { "year": 1 } };
+    let result = coll.find_one_and_update(doc! { "title": "Duel" }, unset, options).unwrap();
+    assert!(result.is_some());
+}
+
 #[test]
 fn aggregate() {
     let client = Client::connect("localhost", 27017).unwrap();
@@ -304,6 +626,64 @@ fn aggregate() {
     assert!(vec.contains(&"f".to_owned()));
 }
 
+#[test]
+fn aggregate_to_collection() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("aggregate_to_collection");
+    let out = db.collection("aggregate_to_collection_out");
+
+    coll.drop().expect("Failed to drop collection");
+    out.drop().expect("Failed to drop collection");
+
+    skip_if_db_version_below!(db, 3, 6);
+
+    let docs = (0..1000).map(|i| doc! { "n": i, "even": i % 2 == 0 }).collect();
+    coll.insert_many(docs, None).unwrap();
+
+    let pipeline = vec![
+        doc! { "$group": { "_id": "$even", "count": { "$sum": 1 } } },
+        doc! { "$out": "aggregate_to_collection_out" },
+    ];
+
+    let mut options = AggregateToCollectionOptions::new();
+    options.progress_interval_ms = Some(1);
+
+    let mut reports = Vec::new();
+    coll.aggregate_to_collection_with_progress(pipeline, Some(options), |progress| {
+        reports.push(progress.secs_running)
+    }).unwrap();
+    assert!(reports.iter().all(|&secs| secs >= 0));
+
+    let count = out.find_one(Some(doc! { "_id": true }), None).unwrap().unwrap();
+    assert_eq!(Some(&Bson::I32(500)), count.get("count"));
+
+    let pipeline = vec![doc! { "$match": {} }];
+    match coll.aggregate_to_collection(pipeline, None) {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}", other),
+    }
+}
+
+#[test]
+fn aggregate_progress_from_current_op() {
+    let op = doc! {
+        "opid": 1234,
+        "secs_running": 12i64,
+        "docsExamined": 5000,
+        "command": { "aggregate": "events", "comment": "aggregate_to_collection 0" },
+    };
+
+    let progress = AggregateProgress::from_current_op(op.clone());
+    assert_eq!(12, progress.secs_running);
+    assert_eq!(Some(5000), progress.docs_examined);
+    assert_eq!(op, progress.op);
+
+    let progress = AggregateProgress::from_current_op(doc! { "opid": 1234 });
+    assert_eq!(0, progress.secs_running);
+    assert_eq!(None, progress.docs_examined);
+}
+
 #[test]
 fn count() {
     let client = Client::connect("localhost", 27017).unwrap();
@@ -450,6 +830,70 @@ fn distinct() {
     assert!(titles.contains(&"12 Angry Men".to_owned()));
 }
 
+#[test]
+fn distinct_with_options() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("distinct_with_options");
+
+    coll.drop().expect("Failed to drop database");
+    skip_if_db_version_below!(db, 3, 4);
+
+    coll.insert_many(
+        vec![
+            doc! { "title": "jaws" },
+            doc! { "title": "Jaws" },
+            doc! { "title": "Alien" },
+        ],
+        None,
+    ).expect("Failed to insert documents.");
+
+    // A strength 2 collation compares case-insensitively.
+    let mut options = DistinctOptions::new();
+    options.max_time_ms = Some(5000);
+    options.collation = Some(doc! { "locale": "en", "strength": 2 });
+    options.read_concern = Some(ReadConcern::new(ReadConcernLevel::Local));
+
+    let distinct_titles = coll.distinct("title", None, Some(options)).expect(
+        "Failed to execute 'distinct'.",
+    );
+    assert_eq!(2, distinct_titles.len());
+}
+
+#[test]
+fn max_time_ms_exceeded() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("max_time_ms_exceeded");
+
+    coll.drop().expect("Failed to drop database");
+    coll.insert_many(vec![doc! { "x": 1 }, doc! { "x": 2 }, doc! { "x": 3 }], None)
+        .expect("Failed to insert documents.");
+
+    let slow = doc! { "$where": "sleep(100) || true" };
+
+    let mut find_options = FindOptions::new();
+    find_options.max_time_ms = Some(10);
+    match coll.find_one(Some(slow.clone()), Some(find_options)) {
+        Err(Error::CodedError(ErrorCode::ExceededTimeLimit)) => (),
+        other => panic!("Expected ExceededTimeLimit, got {:?}", other),
+    }
+
+    let mut count_options = CountOptions::new();
+    count_options.max_time_ms = Some(10);
+    match coll.count(Some(slow.clone()), Some(count_options)) {
+        Err(Error::CodedError(ErrorCode::ExceededTimeLimit)) => (),
+        other => panic!("Expected ExceededTimeLimit, got {:?}", other),
+    }
+
+    let mut update_options = FindOneAndUpdateOptions::new();
+    update_options.max_time_ms = Some(10);
+    match coll.find_one_and_update(slow, doc! { "$set": { "y": 1 } }, Some(update_options)) {
+        Err(Error::CodedError(ErrorCode::ExceededTimeLimit)) => (),
+        other => panic!("Expected ExceededTimeLimit, got {:?}", other),
+    }
+}
+
 #[test]
 fn insert_many() {
     let client = Client::connect("localhost", 27017).unwrap();
@@ -484,6 +928,148 @@ fn insert_many() {
     }
 }
 
+#[test]
+fn insert_as() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("insert_as");
+
+    coll.drop().expect("Failed to drop collection");
+
+    let jaws = Movie { title: String::from("Jaws"), year: 1975 };
+    let result = coll.insert_one_as(&jaws, None).expect("Failed to insert document.");
+    let id = result.inserted_id.expect("Expected a generated _id.");
+
+    let movies = vec![
+        Movie { title: String::from("Back to the Future"), year: 1985 },
+        Movie { title: String::from("Dobby"), year: 2002 },
+    ];
+    let result = coll.insert_many_as(&movies, None).expect("Failed to insert documents.");
+    assert_eq!(2, result.inserted_ids.expect("Expected generated _ids.").len());
+
+    let found = coll.find_one_as::<Movie>(Some(doc! { "_id": id }), None)
+        .expect("Failed to execute find command.");
+    assert_eq!(Some(jaws), found);
+    assert_eq!(3, coll.count(None, None).unwrap());
+
+    // Values must serialize to documents.
+    match coll.insert_one_as(&5, None) {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}", other),
+    }
+}
+
+#[test]
+fn write_concern_error_policy() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("write_concern_error_policy");
+
+    coll.drop().expect("Failed to drop collection");
+
+    // No deployment under test has fifty members, so the write is applied but its write
+    // concern can never be satisfied.
+    let mut wc = WriteConcern::new();
+    wc.w = Acknowledgment::Nodes(50);
+    wc.w_timeout = Some(Duration::from_secs(1));
+
+    let result = coll.insert_one(doc! { "title": "Jaws" }, Some(wc))
+        .expect("Failed to insert document.");
+    assert!(result.inserted_id.is_some());
+    match result.write_exception {
+        Some(ref exception) => assert!(exception.write_concern_error.is_some()),
+        None => panic!("Expected the write concern error to be reported."),
+    }
+
+    let coll = client
+        .with_write_concern_error_policy(WriteConcernErrorPolicy::Fail)
+        .db("test-client-coll")
+        .collection("write_concern_error_policy");
+
+    match coll.insert_one(doc! { "title": "Back to the Future" }, Some(wc)) {
+        Err(Error::WriteError(ref exception)) => assert!(exception.write_concern_error.is_some()),
+        other => panic!("Expected a write concern error, got {:?}", other),
+    }
+
+    match coll.delete_one(doc! { "title": "Jaws" }, Some(wc)) {
+        Err(Error::WriteError(ref exception)) => assert!(exception.write_concern_error.is_some()),
+        other => panic!("Expected a write concern error, got {:?}", other),
+    }
+
+    // Both writes were still applied.
+    assert_eq!(1, coll.count(None, None).unwrap());
+
+    // A bulk write sends no batches after the first write concern error.
+    let coll = client
+        .with_write_concern(wc)
+        .with_write_concern_error_policy(WriteConcernErrorPolicy::Fail)
+        .db("test-client-coll")
+        .collection("write_concern_error_policy");
+
+    let requests = vec![
+        WriteModel::InsertOne { document: doc! { "title": "Alien" } },
+        WriteModel::DeleteMany { filter: doc! {} },
+    ];
+    let result = coll.bulk_write(requests, false);
+    match result.bulk_write_exception {
+        Some(ref exception) => assert!(exception.write_concern_error.is_some()),
+        None => panic!("Expected the write concern error to be reported."),
+    }
+    assert_eq!(1, result.inserted_count);
+    assert_eq!(2, coll.count(None, None).unwrap());
+}
+
+#[test]
+fn unacknowledged_writes() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("unacknowledged_writes");
+
+    coll.drop().expect("Failed to drop collection");
+
+    let mut wc = WriteConcern::new();
+    wc.w = Acknowledgment::Nodes(0);
+
+    // Each write returns before it is applied, and may reach the server on another connection
+    // than the next, so the test waits for each one.
+    let wait_for = |expected: Option<i32>| {
+        let mut year = None;
+        for _ in 0..100 {
+            year = coll.find_one(None, None).unwrap().map(|doc| doc.get_i32("year").unwrap_or(0));
+            if year == expected {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(expected, year);
+    };
+
+    let result = coll.insert_one(doc! { "title": "Jaws" }, Some(wc)).unwrap();
+    assert!(!result.acknowledged);
+    assert!(result.inserted_id.is_some());
+    wait_for(Some(0));
+
+    // The others go to their own collection, so that they cannot be mistaken for the first.
+    let other = db.collection("unacknowledged_writes_other");
+    other.drop().expect("Failed to drop collection");
+    let options = InsertManyOptions { write_concern: Some(wc), ..InsertManyOptions::new() };
+    let result = other.insert_many(vec![doc! { "title": "Jaws 2" }], Some(options)).unwrap();
+    assert!(!result.acknowledged);
+    let result = other.insert_one(doc! { "title": "Jaws 3" }, None).unwrap();
+    assert!(result.acknowledged);
+
+    let options = UpdateOptions { write_concern: Some(wc), ..UpdateOptions::new() };
+    let result = coll.update_one(doc! { "title": "Jaws" }, doc! { "$set": { "year": 1975 } },
+                                 Some(options)).unwrap();
+    assert!(!result.acknowledged);
+    assert_eq!(0, result.modified_count);
+    wait_for(Some(1975));
+
+    let result = coll.delete_one(doc! { "title": "Jaws" }, Some(wc)).unwrap();
+    assert!(!result.acknowledged);
+    wait_for(None);
+}
+
 #[test]
 fn delete_one() {
     let client = Client::connect("localhost", 27017).unwrap();
@@ -500,7 +1086,7 @@ fn delete_one() {
         .expect("Failed to insert documents.");
 
     // Delete document
-    coll.delete_one(doc2.clone(), None).expect(
+    coll.delete_one(&doc2, None).expect(
         "Failed to delete document.",
     );
     let mut cursor = coll.find(None, None).expect(
@@ -520,6 +1106,82 @@ fn delete_one() {
     assert!(cursor.next().is_none());
 }
 
+#[test]
+fn borrowed_arguments() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("borrowed_arguments");
+
+    coll.drop().expect("Failed to drop collection");
+
+    // Documents can be passed by reference, and filters and options without `Some`.
+    let doc = doc! { "_id": 1, "title": "Jaws" };
+    coll.insert_one(&doc, None).expect("Failed to insert document.");
+    assert_eq!(Some(doc.clone()), coll.find_one(&doc, None).unwrap());
+    assert_eq!(1, coll.find(&doc, None).unwrap().count());
+    assert_eq!(1, coll.count(&doc, None).unwrap());
+    assert_eq!(vec![Bson::from("Jaws")], coll.distinct("title", &doc, None).unwrap());
+
+    let mut wc = WriteConcern::new();
+    wc.j = true;
+    coll.delete_one(&doc, wc).expect("Failed to delete document.");
+    assert_eq!(0, coll.count(doc! {}, CountOptions::new()).unwrap());
+}
+
+#[test]
+fn fluent_builders() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("fluent_builders");
+
+    coll.drop().expect("Failed to drop collection");
+
+    let docs = (0..5).map(|i| doc! { "_id": i, "n": i }).collect();
+    coll.insert_many(docs, None).unwrap();
+
+    let found: Vec<_> = coll.query()
+        .filter(doc! { "n": { "$gte": 1 } })
+        .sort(doc! { "n": -1 })
+        .skip(1)
+        .limit(2)
+        .projection(doc! { "_id": 0 })
+        .run()
+        .unwrap()
+        .map(|doc| doc.unwrap())
+        .collect();
+    assert_eq!(vec![doc! { "n": 3 }, doc! { "n": 2 }], found);
+    assert_eq!(4, coll.query().filter(doc! { "n": { "$gte": 1 } }).count().unwrap());
+
+    let result = coll.update().filter(doc! { "_id": 0 }).set("s", "a").inc("n", 10).one().unwrap();
+    assert_eq!(1, result.modified_count);
+    assert_eq!(
+        Some(doc! { "_id": 0, "n": 10, "s": "a" }),
+        coll.query().filter(doc! { "_id": 0 }).one().unwrap()
+    );
+
+    let result = coll.update().filter(doc! { "n": { "$lt": 5 } }).unset("n").many().unwrap();
+    assert_eq!(4, result.modified_count);
+
+    match coll.update().filter(doc! { "_id": 0 }).one() {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}", other),
+    }
+
+    // Updates and deletes without a filter must ask for every document.
+    match coll.update().set("s", "b").many() {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}", other),
+    }
+    assert_eq!(5, coll.update().all().set("s", "b").many().unwrap().modified_count);
+
+    assert_eq!(1, coll.delete().filter(doc! { "_id": 0 }).one().unwrap().deleted_count);
+    match coll.delete().many() {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}", other),
+    }
+    assert_eq!(4, coll.delete().all().many().unwrap().deleted_count);
+}
+
 #[test]
 fn delete_many() {
     let client = Client::connect("localhost", 27017).unwrap();
@@ -536,7 +1198,7 @@ fn delete_many() {
         .expect("Failed to insert documents into collection.");
 
     // Delete document
-    coll.delete_many(doc2.clone(), None).expect(
+    coll.delete_many(&doc2, None).expect(
         "Failed to delete documents.",
     );
     let mut cursor = coll.find(None, None).expect(
@@ -573,7 +1235,7 @@ fn replace_one() {
         .expect("Failed to insert documents into collection.");
 
     // Replace single document
-    coll.replace_one(doc2.clone(), doc3.clone(), None).expect(
+    coll.replace_one(&doc2, &doc3, None).expect(
         "Failed to replace document.",
     );
     let mut cursor = coll.find(None, None).expect(
@@ -616,7 +1278,7 @@ fn update_one() {
     // Update single document
     let update = doc! { "$set": { "director": "Robert Zemeckis" } };
 
-    coll.update_one(doc2.clone(), update, None).expect(
+    coll.update_one(&doc2, update, None).expect(
         "Failed to update document.",
     );
 
@@ -635,6 +1297,61 @@ fn update_one() {
     }
 }
 
+#[test]
+fn update_one_upsert() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("update_one_upsert");
+
+    coll.drop().expect("Failed to drop database");
+
+    let mut options = UpdateOptions::new();
+    options.upsert = Some(true);
+
+    let update = doc! { "$set": { "director": "Steven Spielberg" } };
+    let result = coll.update_one(doc! { "title": "Jaws" }, update.clone(), Some(options.clone()))
+        .expect("Failed to update document.");
+
+    assert_eq!(0, result.matched_count);
+    assert_eq!(0, result.modified_count);
+    let id = result.upserted_id.expect("Expected the id of the upserted document.");
+
+    let doc = coll.find_one(Some(doc! { "title": "Jaws" }), None)
+        .expect("Failed to execute find command.")
+        .expect("Expected the upserted document.");
+    assert_eq!(Some(&id), doc.get("_id"));
+
+    let result = coll.update_one(doc! { "title": "Jaws" }, update, Some(options))
+        .expect("Failed to update document.");
+
+    assert_eq!(1, result.matched_count);
+    assert_eq!(0, result.modified_count);
+    assert!(result.upserted_id.is_none());
+}
+
+#[test]
+fn update_result_upserted_id() {
+    let reply = doc! {
+        "ok": 1,
+        "n": 1,
+        "nModified": 0,
+        "upserted": [{ "index": 0, "_id": "jaws" }],
+    };
+
+    let result = UpdateResult::new(reply.clone(), None);
+    assert_eq!(0, result.matched_count);
+    assert_eq!(Some(Bson::String(String::from("jaws"))), result.upserted_id);
+
+    let result = UpdateResult::with_bulk_result(BulkUpdateResult::new(reply, None));
+    assert_eq!(0, result.matched_count);
+    assert_eq!(Some(Bson::String(String::from("jaws"))), result.upserted_id);
+
+    let result = UpdateResult::new(doc! { "ok": 1, "n": 2i64, "nModified": 2i64 }, None);
+    assert_eq!(2, result.matched_count);
+    assert_eq!(2, result.modified_count);
+    assert!(result.upserted_id.is_none());
+}
+
 #[test]
 fn update_many() {
     let client = Client::connect("localhost", 27017).unwrap();
@@ -656,7 +1373,7 @@ fn update_many() {
     // Update single document
     let update = doc! { "$set": { "director": "Robert Zemeckis" } };
 
-    coll.update_many(doc2.clone(), update, None).expect(
+    coll.update_many(&doc2, update, None).expect(
         "Failed to update documents.",
     );
 
@@ -681,6 +1398,69 @@ fn update_many() {
     }
 }
 
+#[test]
+fn update_with_pipeline() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("update_with_pipeline");
+
+    coll.drop().expect("Failed to drop collection");
+
+    skip_if_db_version_below!(db, 4, 2);
+
+    let docs = vec![
+        doc! { "_id": 1, "first": "Ada", "last": "Lovelace", "scores": [90, 80] },
+        doc! { "_id": 2, "first": "Alan", "last": "Turing", "scores": [70, 100] },
+    ];
+    coll.insert_many(docs, None).unwrap();
+
+    // Compute a field from others and rename a field, neither of which update operators can do.
+    let pipeline = vec![
+        doc! { "$set": { "name": { "$concat": ["$first", " ", "$last"] } } },
+        doc! { "$set": { "surname": "$last" } },
+        doc! { "$unset": ["first", "last"] },
+    ];
+    let result = coll.update_one(doc! { "_id": 1 }, pipeline, None).unwrap();
+    assert_eq!(1, result.modified_count);
+
+    let doc = coll.find_one(Some(doc! { "_id": 1 }), None).unwrap().unwrap();
+    assert_eq!(Some("Ada Lovelace"), doc.get_str("name").ok());
+    assert_eq!(Some("Lovelace"), doc.get_str("surname").ok());
+    assert!(doc.get("first").is_none());
+
+    let pipeline = vec![doc! { "$set": { "average": { "$avg": "$scores" } } }];
+    let result = coll.update_many(doc! {}, pipeline, None).unwrap();
+    assert_eq!(2, result.modified_count);
+
+    let pipeline = vec![doc! { "$set": { "best": { "$max": "$scores" } } }];
+    let mut options = FindOneAndUpdateOptions::new();
+    options.return_document = Some(ReturnDocument::After);
+    let doc = coll.find_one_and_update(doc! { "_id": 2 }, pipeline, Some(options))
+        .unwrap()
+        .unwrap();
+    assert_eq!(Some(&Bson::I32(100)), doc.get("best"));
+}
+
+#[test]
+fn invalid_update_pipelines() {
+    let client = Client::with_config(ConnectionString::new("i-dont-exist", 27017), None, None)
+        .unwrap();
+    let coll = client.db("test-client-coll").collection("invalid_update_pipelines");
+
+    let invalid = vec![
+        vec![],
+        vec![doc! { "name": "Ada" }],
+        vec![doc! { "$set": { "a": 1 }, "$unset": "b" }],
+    ];
+
+    for pipeline in invalid {
+        match coll.update_many(doc! {}, pipeline, None) {
+            Err(Error::ArgumentError(_)) => (),
+            other => panic!("Expected an argument error, got {:?}", other),
+        }
+    }
+}
+
 #[test]
 fn create_list_drop_indexes() {
     let client = Client::connect("localhost", 27017).unwrap();
@@ -910,3 +1690,326 @@ fn drop_all_indexes() {
 
     assert_eq!(1, results.len());
 }
+
+#[test]
+fn create_and_update_ttl_index() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("create_and_update_ttl_index");
+
+    coll.drop().expect("Failed to drop database.");
+
+    let name = coll.create_ttl_index("created_at", 3600).unwrap();
+    assert_eq!("created_at_1", name);
+
+    let expiry = |coll: &Collection| {
+        let index = coll.list_indexes()
+            .unwrap()
+            .map(Result::unwrap)
+            .find(|index| index.get_str("name") == Ok("created_at_1"))
+            .expect("Expected the TTL index to exist.");
+
+        match index.get("expireAfterSeconds") {
+            Some(&Bson::I32(seconds)) => i64::from(seconds),
+            Some(&Bson::I64(seconds)) => seconds,
+            Some(&Bson::FloatingPoint(seconds)) => seconds as i64,
+            other => panic!("Expected a numeric expiry, got {:?}", other),
+        }
+    };
+
+    assert_eq!(3600, expiry(&coll));
+
+    coll.update_ttl(&name, 60).unwrap();
+    assert_eq!(60, expiry(&coll));
+
+    assert!(coll.update_ttl("missing_1", 60).is_err());
+}
+
+#[test]
+fn rename_and_drop() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let source = db.collection("rename_source");
+    let target = db.collection("rename_target");
+
+    source.drop_with_write_concern(None, true).unwrap();
+    target.drop_with_write_concern(None, true).unwrap();
+    assert!(source.drop_with_write_concern(None, false).is_err());
+
+    source.insert_one(doc! { "x": 1 }, None).unwrap();
+    target.insert_one(doc! { "x": 2 }, None).unwrap();
+
+    assert!(source.rename("rename_target", false).is_err());
+
+    let renamed = source.rename("rename_target", true).unwrap();
+    assert_eq!("rename_target", renamed.name());
+    assert!(!db.collection_exists("rename_source").unwrap());
+
+    let doc = renamed.find_one(None, None).unwrap().unwrap();
+    assert_eq!(Some(&Bson::I32(1)), doc.get("x"));
+
+    let mut wc = WriteConcern::new();
+    wc.w = Acknowledgment::Nodes(1);
+    renamed.drop_with_write_concern(Some(wc), false).unwrap();
+}
+
+#[test]
+fn explain_summaries() {
+    // A find on an indexed field, as explained with executionStats.
+    let find = ExplainSummary::from_document(doc! {
+        "queryPlanner": {
+            "winningPlan": {
+                "stage": "FETCH",
+                "inputStage": {
+                    "stage": "IXSCAN",
+                    "keyPattern": { "a": 1 },
+                    "indexName": "a_1",
+                },
+            },
+        },
+        "executionStats": {
+            "nReturned": 3,
+            "executionTimeMillis": 1,
+            "totalKeysExamined": 3,
+            "totalDocsExamined": Bson::I64(3),
+        },
+        "ok": 1.0,
+    });
+
+    let plan = find.winning_plan.unwrap();
+    assert_eq!("FETCH", plan.stage);
+    assert_eq!("IXSCAN", plan.children[0].stage);
+    assert_eq!(Some(doc! { "a": 1 }), plan.children[0].key_pattern);
+    assert!(!plan.has_collection_scan());
+    assert_eq!(vec![String::from("a_1")], find.index_names);
+
+    let stats = find.execution_stats.unwrap();
+    assert_eq!(3, stats.n_returned);
+    assert_eq!(3, stats.total_docs_examined);
+
+    // An aggregation whose query runs in a $cursor stage, on a server using the slot-based
+    // engine.
+    let aggregate = ExplainSummary::from_document(doc! {
+        "stages": [
+            {
+                "$cursor": {
+                    "queryPlanner": {
+                        "winningPlan": {
+                            "queryPlan": { "stage": "COLLSCAN" },
+                            "slotBasedPlan": {},
+                        },
+                    },
+                },
+            },
+            { "$group": { "_id": "$a" } },
+        ],
+        "ok": 1.0,
+    });
+
+    assert!(aggregate.winning_plan.unwrap().has_collection_scan());
+    assert!(aggregate.index_names.is_empty());
+    assert_eq!(None, aggregate.execution_stats);
+
+    // A find on a sharded cluster, where each shard reports its own plan.
+    let sharded = ExplainSummary::from_document(doc! {
+        "queryPlanner": {
+            "winningPlan": {
+                "stage": "SHARD_MERGE",
+                "shards": [
+                    {
+                        "shardName": "shard0",
+                        "winningPlan": { "stage": "IXSCAN", "indexName": "a_1" },
+                    },
+                    {
+                        "shardName": "shard1",
+                        "winningPlan": { "stage": "COLLSCAN" },
+                    },
+                ],
+            },
+        },
+        "ok": 1.0,
+    });
+
+    let plan = sharded.winning_plan.unwrap();
+    assert_eq!(2, plan.children.len());
+    assert!(plan.has_collection_scan());
+    assert_eq!(vec![String::from("a_1")], sharded.index_names);
+}
+
+#[test]
+fn explain() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("explain");
+    db.drop_database().unwrap();
+
+    coll.insert_many(vec![doc! { "a": 1 }, doc! { "a": 2 }, doc! { "a": 3 }], None).unwrap();
+    coll.create_index(doc! { "a": 1 }, None).unwrap();
+
+    let summary = coll.explain_find(Some(doc! { "a": 2 }), None, ExplainVerbosity::ExecutionStats)
+        .unwrap();
+    assert_eq!(vec![String::from("a_1")], summary.index_names);
+    assert_eq!(1, summary.execution_stats.unwrap().n_returned);
+
+    let summary = coll.explain_find(None, None, ExplainVerbosity::QueryPlanner).unwrap();
+    assert!(summary.winning_plan.unwrap().has_collection_scan());
+    assert_eq!(None, summary.execution_stats);
+
+    let pipeline = vec![doc! { "$match": { "a": { "$gt": 1 } } }];
+    let summary = coll.explain_aggregate(pipeline, None, ExplainVerbosity::QueryPlanner).unwrap();
+    assert_eq!(vec![String::from("a_1")], summary.index_names);
+
+    coll.explain_count(Some(doc! { "a": 1 }), None, ExplainVerbosity::ExecutionStats).unwrap();
+
+    coll.explain_update(doc! { "a": 1 }, doc! { "$set": { "b": 1 } }, true,
+                        ExplainVerbosity::ExecutionStats)
+        .unwrap();
+    coll.explain_delete(doc! { "a": 1 }, false, ExplainVerbosity::ExecutionStats).unwrap();
+
+    // Explaining writes leaves the collection unchanged.
+    assert_eq!(3, coll.count(None, None).unwrap());
+    assert_eq!(None, coll.find_one(Some(doc! { "b": 1 }), None).unwrap());
+}
+
+#[test]
+fn map_reduce() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("map_reduce");
+    let out = db.collection("map_reduce_out");
+    coll.drop().unwrap();
+    out.drop().unwrap();
+
+    coll.insert_many(vec![
+        doc! { "tag": "a", "n": 1 },
+        doc! { "tag": "a", "n": 2 },
+        doc! { "tag": "b", "n": 3 },
+        doc! { "tag": "c", "n": 4 },
+    ], None).unwrap();
+
+    let map = "function() { emit(this.tag, this.n); }";
+    let reduce = "function(key, values) { return Array.sum(values); }";
+
+    let mut options = MapReduceOptions::new();
+    options.query = Some(doc! { "tag": { "$ne": "c" } });
+    options.sort = Some(doc! { "tag": 1 });
+    options.finalize = Some(String::from("function(key, value) { return value * factor; }"));
+    options.scope = Some(doc! { "factor": 10 });
+
+    let results: Vec<_> = match coll.map_reduce(map, reduce, Some(options)).unwrap() {
+        MapReduceResult::Inline(cursor) => cursor.map(|doc| doc.unwrap()).collect(),
+        MapReduceResult::Collection(_) => panic!("Expected inline results"),
+    };
+
+    assert_eq!(2, results.len());
+    assert_eq!(Some(&Bson::String(String::from("a"))), results[0].get("_id"));
+    assert_eq!(Some(&Bson::FloatingPoint(30.0)), results[0].get("value"));
+    assert_eq!(Some(&Bson::FloatingPoint(30.0)), results[1].get("value"));
+
+    let mut options = MapReduceOptions::new();
+    options.out = Some(MapReduceOutput::Collection {
+        name: String::from("map_reduce_out"),
+        db: None,
+        action: MapReduceAction::Replace,
+    });
+
+    let target = match coll.map_reduce(map, reduce, Some(options.clone())).unwrap() {
+        MapReduceResult::Collection(target) => target,
+        MapReduceResult::Inline(_) => panic!("Expected an output collection"),
+    };
+
+    assert_eq!("map_reduce_out", target.name());
+    assert_eq!(3, target.count(None, None).unwrap());
+
+    // Reducing into the existing output adds each new value to the stored one.
+    options.out = Some(MapReduceOutput::Collection {
+        name: String::from("map_reduce_out"),
+        db: None,
+        action: MapReduceAction::Reduce,
+    });
+    options.query = Some(doc! { "tag": "b" });
+    coll.map_reduce(map, reduce, Some(options)).unwrap();
+
+    let b = target.find_one(Some(doc! { "_id": "b" }), None).unwrap().unwrap();
+    assert_eq!(Some(&Bson::FloatingPoint(6.0)), b.get("value"));
+}
+
+#[test]
+fn text_search_options() {
+    let mut search = TextSearchOptions::new("coffee -shop");
+    search.language = Some(String::from("none"));
+    search.case_sensitive = Some(true);
+
+    let expected = doc! {
+        "$text": { "$search": "coffee -shop", "$language": "none", "$caseSensitive": true },
+    };
+    assert_eq!(expected, search.to_filter());
+
+    let mut options = FindOptions::new();
+    options.projection = Some(doc! { "_id": 0, "title": 1 });
+    options.sort = Some(doc! { "year": -1 });
+
+    let options = TextSearchOptions::sort_by_score("score", Some(options));
+    let projection = doc! { "_id": 0, "title": 1, "score": { "$meta": "textScore" } };
+    assert_eq!(Some(projection), options.projection);
+    let sort = doc! { "score": { "$meta": "textScore" }, "year": -1 };
+    assert_eq!(Some(sort), options.sort);
+
+    // Exclusions are kept alongside the score.
+    let mut options = FindOptions::new();
+    options.projection = Some(doc! { "body": 0 });
+
+    let options = TextSearchOptions::sort_by_score("score", Some(options));
+    let projection = doc! { "body": 0, "score": { "$meta": "textScore" } };
+    assert_eq!(Some(projection), options.projection);
+
+    let model = IndexModel::text(&[("title", 10), ("body", 1)], Some("spanish"));
+    assert_eq!(doc! { "title": "text", "body": "text" }, model.keys);
+    assert_eq!(Some(doc! { "title": 10, "body": 1 }), model.options.weights);
+    assert_eq!("title_text_body_text", model.name().unwrap());
+}
+
+#[test]
+fn text_search() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-coll");
+    let coll = db.collection("text_search");
+    coll.drop().unwrap();
+
+    coll.insert_many(vec![
+        doc! { "title": "Coffee", "body": "A guide to brewing tea" },
+        doc! { "title": "Tea", "body": "Coffee shops that also serve tea" },
+        doc! { "title": "Cake", "body": "Baking without coffee" },
+        doc! { "title": "Bread", "body": "Flour, water and salt" },
+    ], None).unwrap();
+
+    let name = coll.create_text_index(&[("title", 10), ("body", 1)], Some("english")).unwrap();
+    assert_eq!("title_text_body_text", name);
+
+    let search = TextSearchOptions::new("coffee");
+    let options = TextSearchOptions::sort_by_score("score", None);
+    let results: Vec<_> = coll.find(Some(search.to_filter()), Some(options))
+        .unwrap()
+        .map(|doc| doc.unwrap())
+        .collect();
+
+    assert_eq!(3, results.len());
+    assert_eq!(Some(&Bson::String(String::from("Coffee"))), results[0].get("title"));
+    assert!(results.iter().all(|doc| doc.get_f64("score").is_ok()));
+
+    let mut options = FindOptions::new();
+    options.projection = Some(doc! { "body": 0 });
+    let options = TextSearchOptions::sort_by_score("score", Some(options));
+    let results: Vec<_> = coll.find(Some(search.to_filter()), Some(options))
+        .unwrap()
+        .map(|doc| doc.unwrap())
+        .collect();
+
+    assert_eq!(3, results.len());
+    assert!(results.iter().all(|doc| !doc.contains_key("body") && doc.contains_key("title")));
+    assert!(results.iter().all(|doc| doc.get_f64("score").is_ok()));
+
+    let mut filter = TextSearchOptions::new("coffee -shops").to_filter();
+    filter.insert("title", doc! { "$ne": "Coffee" });
+    assert_eq!(1, coll.count(Some(filter), None).unwrap());
+}
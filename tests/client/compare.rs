@@ -0,0 +1,54 @@
+use bson::Bson;
+use mongodb::compare::{self, CompareOptions, NumEq};
+
+#[test]
+fn arrays_compare_in_order() {
+    let arr = |values: Vec<i32>| Bson::Array(values.into_iter().map(Bson::I32).collect());
+
+    assert!(compare::bson_eq(&arr(vec![1, 2]), &arr(vec![1, 2]), None));
+    assert!(!compare::bson_eq(&arr(vec![1, 2]), &arr(vec![2, 1]), None));
+    assert!(!compare::bson_eq(&arr(vec![1, 2]), &arr(vec![2, 1, 3]), None));
+    assert!(!compare::bson_eq(&arr(vec![1, 2]), &arr(vec![1, 2, 3]), None));
+    assert!(!compare::bson_eq(&arr(vec![1, 1]), &arr(vec![1]), None));
+}
+
+#[test]
+fn numbers_compare_by_value() {
+    assert!(compare::bson_eq(&Bson::I32(1), &Bson::I64(1), None));
+    assert!(compare::bson_eq(&Bson::I64(1), &Bson::FloatingPoint(1.0), None));
+    assert!(compare::bson_eq(&Bson::FloatingPoint(0.3), &Bson::FloatingPoint(0.1 + 0.2), None));
+    assert!(!compare::bson_eq(&Bson::I32(1), &Bson::FloatingPoint(1.5), None));
+    assert!(!compare::bson_eq(&Bson::I32(1), &Bson::String(String::from("1")), None));
+    assert!(Bson::I32(3).int_eq(3));
+    assert!(Bson::FloatingPoint(2.0).int_eq(2));
+
+    let mut options = CompareOptions::new();
+    options.numeric_type_insensitive = Some(false);
+
+    assert!(!compare::bson_eq(&Bson::I32(1), &Bson::I64(1), Some(options)));
+    assert!(compare::bson_eq(&Bson::I64(1), &Bson::I64(1), Some(options)));
+}
+
+#[test]
+fn documents_compare_recursively() {
+    let actual = doc! { "a": 1i64, "b": { "c": [1, 2.0] } };
+
+    assert!(compare::document_eq(&actual, &doc! { "a": 1, "b": { "c": [1, 2] } }, None));
+    assert!(!compare::document_eq(&actual, &doc! { "b": { "c": [1, 2] }, "a": 1 }, None));
+    assert!(!compare::document_eq(&actual, &doc! { "a": 1 }, None));
+    assert!(!compare::document_eq(&actual, &doc! { "a": 1, "b": { "c": [2, 1] } }, None));
+
+    let mut options = CompareOptions::new();
+    options.ignore_field_order = Some(true);
+
+    assert!(compare::document_eq(
+        &actual,
+        &doc! { "b": { "c": [1, 2] }, "a": 1 },
+        Some(options),
+    ));
+    assert!(!compare::document_eq(
+        &actual,
+        &doc! { "b": { "c": [1, 2] }, "d": 1 },
+        Some(options),
+    ));
+}
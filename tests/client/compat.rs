@@ -0,0 +1,18 @@
+use mongodb::compat::*;
+
+#[test]
+fn compat_api() {
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    let client = Client::with_config(config, None, None).unwrap();
+
+    let db: Database = client.db("app");
+    let coll: Collection = db.collection("people");
+    assert_eq!("app.people", coll.namespace);
+
+    let options = FindOptions {
+        read_preference: Some(ReadPreference::new(ReadMode::Secondary, None)),
+        ..FindOptions::new()
+    };
+    assert!(!options.requires_find_command());
+    assert_eq!(WriteConcern::new(), client.write_concern);
+}
@@ -170,7 +170,7 @@ macro_rules! run_insert_one_test {
 macro_rules! run_replace_one_test {
     ( $db:expr, $coll:expr, $filter:expr, $replacement:expr, $upsert:expr,
         $outcome:expr ) => {{
-            let options = ReplaceOptions { upsert: $upsert, write_concern: None };
+            let options = ReplaceOptions { upsert: $upsert, ..ReplaceOptions::new() };
             let actual = $coll.replace_one($filter, $replacement, Some(options)).unwrap();
 
             let (matched, modified, upserted) = match $outcome.result {
@@ -185,10 +185,7 @@ macro_rules! run_replace_one_test {
             assert!(matched.int_eq(actual.matched_count as i64));
             assert!(modified.int_eq(actual.modified_count as i64));
 
-            let id = match actual.upserted_id {
-                Some(Bson::Document(ref doc)) => doc.get("_id"),
-                _ => None
-            };
+            let id = actual.upserted_id.as_ref();
 
             match (upserted, id) {
                 (None, None) => (),
@@ -224,10 +221,7 @@ macro_rules! run_update_test {
           assert!(matched.int_eq(actual.matched_count as i64));
           assert!(modified.int_eq(actual.modified_count as i64));
 
-          let id = match actual.upserted_id {
-	          Some(Bson::Document(ref doc)) => doc.get("_id"),
-              _ => None
-          };
+          let id = actual.upserted_id.as_ref();
 
           match (upserted, id) {
               (None, None) => (),
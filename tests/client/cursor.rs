@@ -1,10 +1,12 @@
 use bson::{Bson, Document};
 
-use mongodb::{Client, CommandType, ThreadedClient};
+use mongodb::{Client, CommandResult, CommandStarted, CommandType, ThreadedClient};
 use mongodb::common::{ReadMode, ReadPreference};
 use mongodb::coll::options::FindOptions;
+use mongodb::connstring;
 use mongodb::db::ThreadedDatabase;
 use mongodb::cursor::Cursor;
+use mongodb::error::{CollectLimit, Error};
 use mongodb::wire_protocol::flags::OpQueryFlags;
 
 #[test]
@@ -82,3 +84,136 @@ fn cursor_features() {
         };
     }
 }
+
+#[test]
+fn cursor_prefetch() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-cursor");
+    let coll = db.collection("cursor_prefetch");
+
+    coll.drop().expect("Failed to drop collection.");
+
+    let docs = (0..10).map(|i| doc! { "foo": i as i64 }).collect();
+    assert!(coll.insert_many(docs, None).is_ok());
+
+    let mut options = FindOptions::new();
+    options.batch_size = Some(3);
+    options.sort = Some(doc! { "foo": 1 });
+
+    let mut cursor = coll.find(None, Some(options)).expect(
+        "Failed to execute find command.",
+    );
+    cursor.set_prefetch(true);
+
+    let values: Vec<_> = cursor
+        .map(|doc| match doc.expect("Failed to read document.").get("foo") {
+            Some(&Bson::I64(i)) => i,
+            _ => panic!("Wrong value returned from Cursor#next"),
+        })
+        .collect();
+
+    assert_eq!((0..10).collect::<Vec<i64>>(), values);
+
+    // Prefetching stops at the limit.
+    let mut options = FindOptions::new();
+    options.batch_size = Some(2);
+    options.limit = Some(5);
+
+    let mut cursor = coll.find(None, Some(options)).expect(
+        "Failed to execute find command.",
+    );
+    cursor.set_prefetch(true);
+
+    assert_eq!(5, cursor.count());
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Counter {
+    foo: i64,
+}
+
+#[test]
+fn cursor_collect_typed() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-cursor");
+    let coll = db.collection("cursor_collect_typed");
+
+    coll.drop().expect("Failed to drop collection.");
+
+    let docs = (0..10).map(|i| doc! { "foo": i as i64 }).collect();
+    coll.insert_many(docs, None).unwrap();
+
+    let mut options = FindOptions::new();
+    options.batch_size = Some(3);
+    options.projection = Some(doc! { "_id": 0 });
+
+    let mut cursor = coll.find(None, Some(options.clone())).unwrap();
+    let counters: Vec<Counter> = cursor.collect_typed(10, 1024).unwrap();
+    let expected: Vec<_> = (0..10).map(|foo| Counter { foo }).collect();
+    assert_eq!(expected, counters);
+
+    let mut cursor = coll.find(None, Some(options.clone())).unwrap();
+    match cursor.collect_typed::<Counter>(9, 1024) {
+        Err(Error::CollectLimitExceeded(CollectLimit::Documents(9))) => (),
+        other => panic!("Expected the document limit to be exceeded, got {:?}", other),
+    }
+
+    // Each `{ foo: <i64> }` document takes 18 bytes.
+    let mut cursor = coll.find(None, Some(options)).unwrap();
+    match cursor.collect_typed::<Counter>(10, 100) {
+        Err(Error::CollectLimitExceeded(CollectLimit::Bytes(100))) => (),
+        other => panic!("Expected the byte limit to be exceeded, got {:?}", other),
+    }
+    assert_eq!(Some(6), cursor.next().map(|doc| doc.unwrap().get_i64("foo").unwrap()));
+}
+
+fn started_on_localhost(_client: Client, command: &CommandStarted) {
+    assert_eq!("localhost:27017", command.host);
+}
+
+fn completed_on_localhost(_client: Client, result: &CommandResult) {
+    assert_eq!("localhost:27017", result.host());
+}
+
+#[test]
+fn cursor_address() {
+    let mut client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-cursor");
+    let coll = db.collection("cursor_address");
+
+    coll.drop().expect("Failed to drop collection.");
+    coll.insert_one(doc! { "foo": 1 }, None).unwrap();
+
+    client.add_start_hook(started_on_localhost).unwrap();
+    client.add_completion_hook(completed_on_localhost).unwrap();
+
+    let cursor = coll.find(None, None).unwrap();
+    assert_eq!(connstring::parse_host("localhost:27017").unwrap(), cursor.address());
+}
+
+#[test]
+fn cursor_documents_with_error_codes() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-cursor");
+    let coll = db.collection("cursor_documents_with_error_codes");
+
+    coll.drop().expect("Failed to drop collection.");
+
+    // A document that happens to have the code for an exceeded time limit is still a result.
+    coll.insert_one(doc! { "code": 50, "name": "timeout" }, None).unwrap();
+
+    let mut cursor = coll.find(Some(doc! { "code": 50 }), None)
+        .expect("Failed to find a document with a code field.");
+    let found = cursor.next().expect("Expected a document.").unwrap();
+    assert_eq!(Some(&Bson::String("timeout".to_owned())), found.get("name"));
+
+    // So are documents with the codes for sorts and groups that exceeded their memory limit.
+    for &code in &[292, 16819, 16945] {
+        coll.insert_one(doc! { "code": code, "name": "memory" }, None).unwrap();
+
+        let mut cursor = coll.find(Some(doc! { "code": code }), None)
+            .expect("Failed to find a document with a code field.");
+        let found = cursor.next().expect("Expected a document.").unwrap();
+        assert_eq!(Some(&Bson::String("memory".to_owned())), found.get("name"));
+    }
+}
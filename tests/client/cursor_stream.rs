@@ -0,0 +1,54 @@
+use futures_core::Stream;
+use mongodb::{Client, CommandType, ThreadedClient};
+use mongodb::connstring::ConnectionString;
+use mongodb::cursor::Cursor;
+use mongodb::cursor_stream::{CursorStream, ThreadExecutor};
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::{self, Thread};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Polls the stream to its end from this thread, parking while it waits for the worker.
+fn collect(mut stream: CursorStream) -> Vec<i32> {
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut values = Vec::new();
+
+    loop {
+        match Pin::new(&mut stream).poll_next(&mut cx) {
+            Poll::Ready(Some(doc)) => values.push(doc.unwrap().get_i32("x").unwrap()),
+            Poll::Ready(None) => return values,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+fn cursor(n: i32) -> Cursor {
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    let host = config.hosts[0].clone();
+    let client = Client::with_config(config, None, None).unwrap();
+    let docs = (0..n).map(|x| doc! { "x": x }).collect();
+    Cursor::from_documents(client, String::from("test.stream"), host, docs, CommandType::Find)
+}
+
+#[test]
+fn cursor_into_stream() {
+    let stream = cursor(500).into_stream(ThreadExecutor);
+    assert_eq!((0..500).collect::<Vec<_>>(), collect(stream));
+
+    // Any function taking the work is an executor.
+    let stream = cursor(3).into_stream(|work: Box<dyn FnOnce() + Send>| {
+        thread::spawn(work);
+    });
+    assert_eq!(vec![0, 1, 2], collect(stream));
+}
+
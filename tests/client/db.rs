@@ -1,7 +1,18 @@
 use bson::{self, Bson};
-use mongodb::{Client, ThreadedClient};
+use chrono::{Duration, Utc};
+use mongodb::{Client, CommandType, ThreadedClient};
+use mongodb::coll::options::IndexOptions;
+use mongodb::common::{ReadMode, ReadPreference, TagSet};
 use mongodb::db::ThreadedDatabase;
-use mongodb::db::options::CreateUserOptions;
+use mongodb::db::commands::{BuildInfo, CollectionSpecification, CollectionStats,
+                            CollectionType, DatabaseStats, HostInfo, ProfileEntry,
+                            ProfilingLevel, ProfilingStatus, ReplSetGetStatus, ServerStatus,
+                            ValidationResult};
+use mongodb::db::options::{CollModIndex, CollModOptions, CreateCollectionOptions,
+                           CreateUserOptions, CreateViewOptions, FixtureFormat, FixtureMode,
+                           LoadFixturesOptions, TimeseriesGranularity, TimeseriesOptions,
+                           ValidationAction, ValidationLevel};
+use mongodb::db::dbref::DBRef;
 use mongodb::db::roles::{AllDatabaseRole, SingleDatabaseRole, Role};
 
 #[test]
@@ -15,11 +26,11 @@ fn create_collection() {
     db.create_collection("test2", None).unwrap();
 
     // Check for namespaces
-    let mut cursor = db.list_collections_with_batch_size(None, 1).expect(
+    let cursor = db.list_collections_with_batch_size(None, 1).expect(
         "Failed to execute list_collections command.",
     );
 
-    let results = cursor.next_n(5).unwrap();
+    let results: Vec<_> = cursor.take(5).map(Result::unwrap).collect();
 
     let db_version = db.version().unwrap();
     let v3_1 = db_version.major <= 3 && db_version.minor <= 1;
@@ -28,23 +39,15 @@ fn create_collection() {
     assert_eq!(result_size, results.len());
 
     if v3_1 {
-        match results[0].get("name") {
-            Some(&Bson::String(ref name)) => assert_eq!("system.indexes", name),
-            _ => panic!("Expected BSON string!"),
-        }
+        assert_eq!("system.indexes", results[0].name);
     }
 
     let db1 = if v3_1 { "test1" } else { "test2" };
     let db2 = if v3_1 { "test2" } else { "test1" };
 
-    match results[result_size - 2].get("name") {
-        Some(&Bson::String(ref name)) => assert_eq!(db1, name),
-        _ => panic!("Expected BSON string!"),
-    }
-    match results[result_size - 1].get("name") {
-        Some(&Bson::String(ref name)) => assert_eq!(db2, name),
-        _ => panic!("Expected BSON string!"),
-    }
+    assert_eq!(db1, results[result_size - 2].name);
+    assert_eq!(db2, results[result_size - 1].name);
+    assert_eq!(CollectionType::Collection, results[result_size - 1].collection_type);
 }
 
 #[test]
@@ -63,11 +66,11 @@ fn list_collections() {
         .expect("Failed to insert placeholder document into collection");
 
     // Check for namespaces
-    let mut cursor = db.list_collections_with_batch_size(None, 1).expect(
+    let cursor = db.list_collections_with_batch_size(None, 1).expect(
         "Failed to execute list_collections command.",
     );
 
-    let results = cursor.next_n(5).unwrap();
+    let results: Vec<_> = cursor.take(5).map(Result::unwrap).collect();
 
     let db_version = db.version().unwrap();
     let v3_1 = db_version.major <= 3 && db_version.minor <= 1;
@@ -76,24 +79,242 @@ fn list_collections() {
     assert_eq!(result_size, results.len());
 
     if v3_1 {
-        match results[0].get("name") {
-            Some(&Bson::String(ref name)) => assert_eq!("system.indexes", name),
-            _ => panic!("Expected BSON string!"),
-        };
+        assert_eq!("system.indexes", results[0].name);
     }
 
     let db1 = if v3_1 { "test" } else { "test2" };
     let db2 = if v3_1 { "test2" } else { "test" };
 
-    match results[result_size - 2].get("name") {
-        Some(&Bson::String(ref name)) => assert_eq!(db1, name),
-        _ => panic!("Expected BSON string!"),
+    assert_eq!(db1, results[result_size - 2].name);
+    assert_eq!(db2, results[result_size - 1].name);
+}
+
+#[test]
+fn collection_exists() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-collection_exists");
+    db.drop_database().unwrap();
+
+    assert!(!db.collection_exists("people").unwrap());
+    db.create_collection("people", None).unwrap();
+    assert!(db.collection_exists("people").unwrap());
+
+    skip_if_db_version_below!(db, 3, 4);
+
+    db.create_view("adults", "people", vec![], None).unwrap();
+    assert!(db.collection_exists("adults").unwrap());
+
+    let spec = db.list_collections(Some(doc! { "name": "adults" }))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(CollectionType::View, spec.collection_type);
+    assert_eq!(Some(&Bson::String(String::from("people"))), spec.options.get("viewOn"));
+    assert!(spec.id_index.is_none());
+}
+
+#[test]
+fn decode_collection_specification() {
+    let reply = doc! {
+        "name": "adults",
+        "type": "view",
+        "options": { "viewOn": "people", "pipeline": [] },
+        "info": { "readOnly": true },
+    };
+    let spec: CollectionSpecification = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!(CollectionType::View, spec.collection_type);
+    assert!(spec.info.unwrap().read_only);
+    assert!(spec.id_index.is_none());
+
+    // Servers before 3.4 report neither the type nor info.
+    let reply = doc! { "name": "people", "options": {} };
+    let spec: CollectionSpecification = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!(CollectionType::Collection, spec.collection_type);
+    assert!(spec.info.is_none());
+
+    let uuid = Bson::Binary(bson::spec::BinarySubtype::Uuid, vec![0; 16]);
+    let reply = doc! {
+        "name": "weather",
+        "type": "someFutureType",
+        "info": { "readOnly": false, "uuid": uuid },
+        "idIndex": { "v": 2, "key": { "_id": 1 }, "name": "_id_" },
     };
+    let spec: CollectionSpecification = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!(CollectionType::Other, spec.collection_type);
+    assert!(spec.info.unwrap().uuid.is_some());
+    assert!(spec.id_index.is_some());
+}
 
-    match results[result_size - 1].get("name") {
-        Some(&Bson::String(ref name)) => assert_eq!(db2, name),
-        _ => panic!("Expected BSON string!"),
-    }
+#[test]
+fn create_view() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-create_view");
+    db.drop_database().unwrap();
+
+    skip_if_db_version_below!(db, 3, 4);
+
+    let coll = db.collection("people");
+    let docs = vec![
+        doc! { "name": "ann", "age": 30 },
+        doc! { "name": "bo", "age": 12 },
+        doc! { "name": "al", "age": 45 },
+    ];
+    coll.insert_many(docs, None).unwrap();
+
+    let pipeline = vec![
+        doc! { "$match": { "age": { "$gte": 18 } } },
+        doc! { "$project": { "_id": 0, "name": 1 } },
+    ];
+    let mut options = CreateViewOptions::new();
+    options.collation = Some(doc! { "locale": "en", "strength": 2 });
+    db.create_view("adults", "people", pipeline, Some(options)).unwrap();
+
+    let spec = db.list_collections(Some(doc! { "name": "adults" }))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(CollectionType::View, spec.collection_type);
+
+    let adults: Vec<_> = db.collection("adults")
+        .find(Some(doc! { "name": "AL" }), None)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(vec![doc! { "name": "al" }], adults);
+
+    assert!(db.collection("adults").insert_one(doc! { "name": "cy" }, None).is_err());
+    assert!(db.create_view("adults", "people", vec![], None).is_err());
+}
+
+#[test]
+fn timeseries_options() {
+    let mut timeseries = TimeseriesOptions::new("timestamp");
+    timeseries.meta_field = Some(String::from("sensor"));
+    timeseries.granularity = Some(TimeseriesGranularity::Minutes);
+
+    let options = CreateCollectionOptions {
+        timeseries: Some(timeseries),
+        expire_after_seconds: Some(86400),
+        ..Default::default()
+    };
+
+    let expected = doc! {
+        "timeseries": { "timeField": "timestamp", "metaField": "sensor", "granularity": "minutes" },
+        "expireAfterSeconds": 86400i64,
+    };
+    assert_eq!(expected, bson::Document::from(options));
+}
+
+#[test]
+fn create_timeseries_collection() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-create_timeseries_collection");
+    db.drop_database().unwrap();
+
+    skip_if_db_version_below!(db, 5, 0);
+
+    let mut timeseries = TimeseriesOptions::new("timestamp");
+    timeseries.meta_field = Some(String::from("sensor"));
+    timeseries.granularity = Some(TimeseriesGranularity::Hours);
+    db.create_timeseries_collection("weather", timeseries, Some(3600)).unwrap();
+
+    let spec = db.list_collections(Some(doc! { "name": "weather" }))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(CollectionType::Timeseries, spec.collection_type);
+
+    let options = spec.options.get_document("timeseries").unwrap();
+    assert_eq!(Ok("timestamp"), options.get_str("timeField"));
+    assert_eq!(Ok("sensor"), options.get_str("metaField"));
+    assert_eq!(Ok("hours"), options.get_str("granularity"));
+
+    // Measurements must have a date in the time field.
+    let coll = db.collection("weather");
+    coll.insert_one(doc! { "timestamp": Utc::now(), "sensor": 1, "temp": 12 }, None).unwrap();
+    assert!(coll.insert_one(doc! { "sensor": 1, "temp": 12 }, None).is_err());
+}
+
+#[test]
+fn coll_mod() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-coll_mod");
+    db.drop_database().unwrap();
+    db.create_collection("people", None).unwrap();
+
+    skip_if_db_version_below!(db, 3, 4);
+
+    let mut options = CollModOptions::new();
+    options.validator = Some(doc! { "age": { "$gte": 0 } });
+    options.validation_level = Some(ValidationLevel::Moderate);
+    options.validation_action = Some(ValidationAction::Error);
+    db.coll_mod("people", options).unwrap();
+
+    let coll = db.collection("people");
+    assert!(coll.insert_one(doc! { "age": -1 }, None).is_err());
+    coll.insert_one(doc! { "age": 30 }, None).unwrap();
+
+    let spec = db.list_collections(Some(doc! { "name": "people" }))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(Some(&Bson::String(String::from("moderate"))), spec.options.get("validationLevel"));
+
+    let mut index_options = IndexOptions::new();
+    index_options.expire_after_seconds = Some(60);
+    coll.create_index(doc! { "created": 1 }, Some(index_options)).unwrap();
+
+    let mut index = CollModIndex::new();
+    index.key_pattern = Some(doc! { "created": 1 });
+    index.expire_after_seconds = Some(120);
+    let mut options = CollModOptions::new();
+    options.index = Some(index);
+    db.coll_mod("people", options).unwrap();
+
+    db.create_view("adults", "people", vec![], None).unwrap();
+
+    let mut options = CollModOptions::new();
+    options.view_on = Some(String::from("people"));
+    options.pipeline = Some(vec![doc! { "$match": { "age": { "$gte": 18 } } }]);
+    db.coll_mod("adults", options).unwrap();
+    assert_eq!(1, db.collection("adults").count(None, None).unwrap());
+
+    assert!(db.coll_mod("missing", CollModOptions::new()).is_err());
+}
+
+#[test]
+fn coll_mod_options_document() {
+    let mut index = CollModIndex::new();
+    index.name = Some(String::from("created_1"));
+    index.expire_after_seconds = Some(3600);
+
+    let mut options = CollModOptions::new();
+    options.validator = Some(doc! { "$jsonSchema": { "required": ["name"] } });
+    options.validation_level = Some(ValidationLevel::Strict);
+    options.validation_action = Some(ValidationAction::Warn);
+    options.index = Some(index);
+
+    let expected = doc! {
+        "validator": { "$jsonSchema": { "required": ["name"] } },
+        "validationLevel": "strict",
+        "validationAction": "warn",
+        "index": { "name": "created_1", "expireAfterSeconds": 3600 },
+    };
+    assert_eq!(expected, bson::Document::from(options));
+
+    let mut options = CollModOptions::new();
+    options.view_on = Some(String::from("people"));
+    options.pipeline = Some(vec![doc! { "$match": { "age": { "$gte": 18 } } }]);
+
+    let expected = doc! {
+        "viewOn": "people",
+        "pipeline": [{ "$match": { "age": { "$gte": 18 } } }],
+    };
+    assert_eq!(expected, bson::Document::from(options));
 }
 
 #[test]
@@ -193,3 +414,470 @@ fn get_version() {
     let db = client.db("test-client-db-get_version");
     let _ = db.version().unwrap();
 }
+
+#[test]
+fn load_fixtures() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-load_fixtures");
+    db.drop_database().unwrap();
+
+    let coll = db.collection("users");
+    coll.insert_one(doc! { "_id": 0, "name": "Stale" }, None).unwrap();
+
+    let fixtures = r#"
+        { "users": [{ "_id": 1, "name": "Ada" }, { "_id": 2, "name": "Grace" }] }
+        { "orders": [{ "_id": { "$oid": "5d1b5b5b5b5b5b5b5b5b5b5b" }, "user": 1 }] }
+    "#;
+
+    let loaded = db.load_fixtures(fixtures.as_bytes(), None).unwrap();
+    assert_eq!(Some(&2), loaded.get("users"));
+    assert_eq!(Some(&1), loaded.get("orders"));
+
+    // Truncation removed the existing document.
+    assert_eq!(2, coll.count(None, None).unwrap());
+
+    let order = db.collection("orders").find_one(None, None).unwrap().unwrap();
+    match order.get("_id") {
+        Some(&Bson::ObjectId(_)) => (),
+        _ => panic!("Expected extended JSON to decode to an ObjectId."),
+    }
+
+    // Merging replaces matching documents and keeps the rest.
+    let mut options = LoadFixturesOptions::new();
+    options.mode = Some(FixtureMode::Merge);
+    options.format = Some(FixtureFormat::Bson);
+
+    let mut bytes = Vec::new();
+    let merge = doc! {
+        "users": [{ "_id": 2, "name": "Hopper" }, { "_id": 3, "name": "Barbara" }],
+    };
+    bson::encode_document(&mut bytes, &merge).unwrap();
+
+    db.load_fixtures(&bytes[..], Some(options)).unwrap();
+
+    assert_eq!(3, coll.count(None, None).unwrap());
+    let grace = coll.find_one(Some(doc! { "_id": 2 }), None).unwrap().unwrap();
+    assert_eq!(Some(&Bson::String(String::from("Hopper"))), grace.get("name"));
+}
+
+#[test]
+fn load_fixtures_dir() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-load_fixtures_dir");
+    db.drop_database().unwrap();
+
+    let dir = ::std::env::temp_dir().join("test-client-db-load_fixtures_dir");
+    ::std::fs::create_dir_all(&dir).unwrap();
+    ::std::fs::write(dir.join("users.json"), r#"[{ "_id": 1 }, { "_id": 2 }]"#).unwrap();
+    ::std::fs::write(dir.join("README.md"), "ignored").unwrap();
+
+    let mut bytes = Vec::new();
+    bson::encode_document(&mut bytes, &doc! { "_id": 1 }).unwrap();
+    bson::encode_document(&mut bytes, &doc! { "_id": 2 }).unwrap();
+    bson::encode_document(&mut bytes, &doc! { "_id": 3 }).unwrap();
+    ::std::fs::write(dir.join("orders.bson"), bytes).unwrap();
+
+    let loaded = db.load_fixtures_dir(&dir, None).unwrap();
+    assert_eq!(2, loaded.len());
+    assert_eq!(Some(&2), loaded.get("users"));
+    assert_eq!(Some(&3), loaded.get("orders"));
+    assert_eq!(3, db.collection("orders").count(None, None).unwrap());
+}
+
+#[test]
+fn run_command_as() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-run_command_as");
+
+    let build_info: BuildInfo = db.run_command_as(doc! { "buildInfo": 1 }).unwrap();
+    assert_eq!(db.version().unwrap().to_string(), build_info.version);
+    assert_eq!(64, build_info.bits);
+
+    let status: ServerStatus = client.admin_command_as(doc! { "serverStatus": 1 }).unwrap();
+    assert_eq!(build_info.version, status.version);
+    assert!(status.connections.unwrap().current >= 1);
+
+    #[derive(Deserialize)]
+    struct Ping {
+        ok: f64,
+    }
+
+    let ping: Ping = client.admin_command_as(doc! { "ping": 1 }).unwrap();
+    assert_eq!(1.0, ping.ok);
+}
+
+#[test]
+fn server_monitoring() {
+    let client = Client::connect("localhost", 27017).unwrap();
+
+    let build_info = client.build_info().unwrap();
+    assert_eq!(4, build_info.version_array.len());
+
+    let status = client.server_status().unwrap();
+    assert_eq!(build_info.version, status.version);
+    assert!(status.opcounters.unwrap().command >= 1);
+    assert_eq!(build_info.bits, status.mem.unwrap().bits);
+
+    let host_info = client.host_info().unwrap();
+    assert!(host_info.system.num_cores >= 1);
+    assert!(!host_info.os.os_type.is_empty());
+}
+
+#[test]
+fn run_command() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-run_command");
+    db.drop_database().unwrap();
+
+    let insert = doc! {
+        "insert": "people",
+        "documents": [{ "_id": 1, "name": "Ada" }, { "_id": 2, "name": "Grace" }],
+    };
+    let reply = db.run_command(insert, None).unwrap();
+    assert_eq!(Some(&Bson::I32(2)), reply.get("n"));
+
+    let count = doc! { "count": "people" };
+    let primary = ReadPreference::new(ReadMode::Primary, None);
+    let reply = db.run_command(count, Some(primary)).unwrap();
+    assert_eq!(2, reply.get("n").and_then(Bson::as_i32).unwrap());
+
+    assert!(db.run_command(doc! {}, None).is_err());
+}
+
+#[test]
+fn run_command_types() {
+    assert_eq!(CommandType::InsertMany, CommandType::from_command_name("insert"));
+    assert_eq!(CommandType::FindOneAndUpdate, CommandType::from_command_name("findandmodify"));
+    assert_eq!(CommandType::RunCommand, CommandType::from_command_name("ping"));
+    assert!(CommandType::from_command_name("createIndexes").is_write_command());
+    assert!(!CommandType::from_command_name("count").is_write_command());
+}
+
+#[test]
+fn read_preference_document() {
+    let tags = TagSet::new().insert("dc", "east").insert("rack", "2");
+    let read_pref = ReadPreference::new(ReadMode::SecondaryPreferred, Some(vec![tags]))
+        .with_tag_set(TagSet::new());
+    assert_eq!(
+        doc! {
+            "mode": "secondaryPreferred",
+            "tags": [{ "dc": "east", "rack": "2" }, {}],
+        },
+        read_pref.to_document()
+    );
+
+    let read_pref = ReadPreference::new(ReadMode::Nearest, None);
+    assert_eq!(doc! { "mode": "nearest" }, read_pref.to_document());
+
+    let read_pref = read_pref.with_hedge(true);
+    assert_eq!(doc! { "mode": "nearest", "hedge": { "enabled": true } }, read_pref.to_document());
+    assert!(read_pref.validate().is_ok());
+}
+
+#[test]
+fn read_preference_validation() {
+    let primary = ReadPreference::new(ReadMode::Primary, Some(vec![TagSet::new()]));
+    assert!(primary.validate().is_ok());
+
+    let tagged = primary.clone().with_tag_set(TagSet::new().insert("dc", "east"));
+    assert!(tagged.validate().is_err());
+
+    assert!(primary.with_hedge(false).validate().is_err());
+}
+
+#[test]
+fn decode_repl_set_get_status() {
+    let reply = doc! {
+        "set": "rs0",
+        "myState": 1,
+        "term": 3_i64,
+        "members": [
+            {
+                "_id": 0,
+                "name": "db1:27017",
+                "health": 1.0,
+                "state": 1,
+                "stateStr": "PRIMARY",
+                "uptime": 120,
+                "self": true,
+            },
+            {
+                "_id": 1,
+                "name": "db2:27017",
+                "health": 1.0,
+                "state": 2,
+                "stateStr": "SECONDARY",
+                "uptime": 118,
+                "pingMs": 1_i64,
+                "syncSourceHost": "db1:27017",
+            },
+        ],
+        "ok": 1.0,
+    };
+
+    let status: ReplSetGetStatus = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!("rs0", status.set);
+    assert_eq!(Some(3), status.term);
+    assert!(status.members[0].is_self);
+    assert_eq!(None, status.members[0].ping_ms);
+    assert_eq!("SECONDARY", status.members[1].state_str);
+    assert_eq!(Some(String::from("db1:27017")), status.members[1].sync_source_host);
+}
+
+#[test]
+fn profiling() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-profiling");
+    db.drop_database().unwrap();
+
+    skip_if_db_version_below!(db, 3, 6);
+
+    db.set_profiling_level(ProfilingLevel::Off, None).unwrap();
+    db.drop_collection("system.profile").unwrap();
+
+    let previous = db.set_profiling_level(ProfilingLevel::All, Some(50)).unwrap();
+    assert_eq!(ProfilingLevel::Off, previous.level);
+
+    let status = db.get_profiling_level().unwrap();
+    assert_eq!(ProfilingLevel::All, status.level);
+    assert_eq!(50, status.slow_ms);
+
+    let start = Utc::now() - Duration::seconds(1);
+    let coll = db.collection("people");
+    coll.insert_one(doc! { "name": "ann" }, None).unwrap();
+    coll.find_one(Some(doc! { "name": "ann" }), None).unwrap();
+
+    db.set_profiling_level(ProfilingLevel::Off, Some(100)).unwrap();
+
+    let entries = db.slow_queries(Some(start), Some(10)).unwrap();
+    assert!(entries.iter().any(|entry| entry.ns == "test-client-db-profiling.people"));
+    assert!(entries.windows(2).all(|pair| pair[0].ts.0 >= pair[1].ts.0));
+    assert_eq!(1, db.slow_queries(None, Some(1)).unwrap().len());
+}
+
+#[test]
+fn decode_profiling_replies() {
+    let reply = doc! { "was": 1, "slowms": 100, "sampleRate": 1.0, "ok": 1.0 };
+    let status: ProfilingStatus = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!(ProfilingLevel::SlowOperations, status.level);
+    assert_eq!(100, status.slow_ms);
+    assert_eq!(Some(1.0), status.sample_rate);
+
+    let reply = doc! { "was": 3, "slowms": 100, "ok": 1.0 };
+    assert!(bson::from_bson::<ProfilingStatus>(Bson::Document(reply)).is_err());
+
+    let ts = Utc::now();
+    let entry = doc! {
+        "op": "query",
+        "ns": "app.people",
+        "command": { "find": "people", "filter": { "name": "ann" } },
+        "keysExamined": 0,
+        "docsExamined": 1000,
+        "nreturned": 1,
+        "millis": 120,
+        "planSummary": "COLLSCAN",
+        "ts": Bson::UtcDatetime(ts),
+        "client": "127.0.0.1",
+        "user": "",
+    };
+    let entry: ProfileEntry = bson::from_bson(Bson::Document(entry)).unwrap();
+    assert_eq!("query", entry.op);
+    assert_eq!(120, entry.millis);
+    assert_eq!(Some(1000), entry.docs_examined);
+    assert_eq!(Some(String::from("COLLSCAN")), entry.plan_summary);
+    assert_eq!(ts.timestamp_millis(), entry.ts.0.timestamp_millis());
+}
+
+#[test]
+fn decode_monitoring_replies() {
+    let reply = doc! {
+        "host": "db1:27017",
+        "version": "4.0.3",
+        "process": "mongod",
+        "pid": 4242i64,
+        "uptime": 3600.0,
+        "uptimeMillis": 3600000i64,
+        "connections": { "current": 12, "available": 838848, "totalCreated": 40 },
+        "opcounters": {
+            "insert": 10i64,
+            "query": 20i64,
+            "update": 3,
+            "delete": 0,
+            "getmore": 7,
+            "command": 100i64,
+        },
+        "mem": { "bits": 64, "resident": 120, "virtual": 1100, "supported": true },
+        "ok": 1.0,
+    };
+    let status: ServerStatus = bson::from_bson(Bson::Document(reply)).unwrap();
+    let opcounters = status.opcounters.unwrap();
+    assert_eq!(20, opcounters.query);
+    assert_eq!(3, opcounters.update);
+    let mem = status.mem.unwrap();
+    assert_eq!(120, mem.resident_mb);
+    assert_eq!(1100, mem.virtual_mb);
+    assert!(mem.supported);
+
+    let reply = doc! {
+        "system": {
+            "currentTime": Bson::UtcDatetime(Utc::now()),
+            "hostname": "db1",
+            "cpuAddrSize": 64,
+            "memSizeMB": 16000i64,
+            "numCores": 8,
+            "cpuArch": "x86_64",
+            "numaEnabled": false,
+        },
+        "os": { "type": "Linux", "name": "Ubuntu", "version": "18.04" },
+        "extra": { "pageSize": 4096i64 },
+        "ok": 1.0,
+    };
+    let host_info: HostInfo = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!("db1", host_info.system.hostname);
+    assert_eq!(16000, host_info.system.mem_size_mb);
+    assert_eq!(None, host_info.system.mem_limit_mb);
+    assert_eq!(8, host_info.system.num_cores);
+    assert_eq!("Linux", host_info.os.os_type);
+}
+
+#[test]
+fn stats() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-db-stats");
+    db.drop_database().unwrap();
+
+    let coll = db.collection("people");
+    let docs = (0..100).map(|i| doc! { "i": i, "name": "x".repeat(100) }).collect();
+    coll.insert_many(docs, None).unwrap();
+
+    let stats = db.stats(None).unwrap();
+    assert_eq!("test-client-db-stats", stats.db);
+    assert_eq!(100, stats.objects);
+    assert!(stats.data_size > 10000.0);
+
+    let kb = db.stats(Some(1024)).unwrap();
+    assert!(kb.data_size < stats.data_size);
+
+    let stats = coll.stats(Some(1024)).unwrap();
+    assert_eq!(100, stats.count);
+    assert!(stats.index_sizes.contains_key("_id_"));
+
+    let result = coll.validate(true).unwrap();
+    assert!(result.valid);
+    assert!(result.errors.is_empty());
+
+    assert!(db.collection("missing").stats(None).is_err());
+}
+
+#[test]
+fn decode_stats_replies() {
+    let reply = doc! {
+        "ns": "app.people",
+        "size": 11600,
+        "count": 100,
+        "avgObjSize": 116,
+        "storageSize": 4096i64,
+        "capped": false,
+        "nindexes": 2,
+        "indexSizes": { "_id_": 4096, "name_1": 8192i64 },
+        "totalIndexSize": 12288,
+        "scaleFactor": 1,
+        "ok": 1.0,
+    };
+    let stats: CollectionStats = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!(100, stats.count);
+    assert_eq!(Some(116.0), stats.avg_obj_size);
+    assert_eq!(Some(&8192), stats.index_sizes.get("name_1"));
+
+    let reply = doc! {
+        "db": "app",
+        "collections": 3,
+        "views": 0,
+        "objects": 100i64,
+        "avgObjSize": 116.0,
+        "dataSize": 11600.0,
+        "storageSize": 4096.0,
+        "indexes": 4,
+        "indexSize": 12288.0,
+        "totalSize": 16384.0,
+        "scaleFactor": 1.0,
+        "ok": 1.0,
+    };
+    let stats: DatabaseStats = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!(3, stats.collections);
+    assert_eq!(Some(16384.0), stats.total_size);
+
+    // Older servers report integer sizes.
+    let reply = doc! {
+        "db": "app",
+        "collections": 3,
+        "objects": 100,
+        "avgObjSize": 116.0,
+        "dataSize": 11600,
+        "storageSize": 4096,
+        "indexes": 4,
+        "indexSize": 12288,
+        "ok": 1.0,
+    };
+    let stats: DatabaseStats = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert_eq!(11600.0, stats.data_size);
+
+    let reply = doc! {
+        "ns": "app.people",
+        "nrecords": 99,
+        "nIndexes": 1,
+        "keysPerIndex": { "_id_": 100 },
+        "valid": false,
+        "warnings": [],
+        "errors": ["index _id_ has 100 keys but 99 records"],
+        "ok": 1.0,
+    };
+    let result: ValidationResult = bson::from_bson(Bson::Document(reply)).unwrap();
+    assert!(!result.valid);
+    assert_eq!(1, result.errors.len());
+    assert_eq!(Some(&100), result.keys_per_index.get("_id_"));
+}
+
+#[test]
+fn parse_dbrefs() {
+    let doc = doc! { "$ref": "users", "$id": 7, "$db": "accounts", "role": "owner" };
+    assert!(DBRef::is_dbref(&doc));
+
+    let dbref = DBRef::from_document(&doc).unwrap();
+    assert_eq!("users", dbref.collection);
+    assert_eq!(Bson::I32(7), dbref.id);
+    assert_eq!(Some(String::from("accounts")), dbref.database);
+    assert_eq!(doc! { "role": "owner" }, dbref.extra);
+    assert_eq!(doc, dbref.to_document());
+
+    let built = bson::Document::from(DBRef::new("users", Bson::I32(7)));
+    assert_eq!(doc! { "$ref": "users", "$id": 7 }, built);
+
+    assert!(!DBRef::is_dbref(&doc! { "$ref": "users" }));
+    assert!(DBRef::from_document(&doc! { "$id": 7 }).is_err());
+    assert!(DBRef::from_document(&doc! { "$ref": "users", "$id": 7, "$db": 1 }).is_err());
+}
+
+#[test]
+fn dereference() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-dereference");
+    let other = client.db("test-dereference-other");
+    db.drop_database().unwrap();
+    other.drop_database().unwrap();
+
+    db.collection("users").insert_one(doc! { "_id": 1, "name": "Ada" }, None).unwrap();
+    other.collection("users").insert_one(doc! { "_id": 1, "name": "Grace" }, None).unwrap();
+
+    let local = DBRef::new("users", Bson::I32(1));
+    let found = db.dereference(&local).unwrap().unwrap();
+    assert_eq!("Ada", found.get_str("name").unwrap());
+
+    let mut remote = local.clone();
+    remote.database = Some(String::from("test-dereference-other"));
+    let found = db.dereference(&remote).unwrap().unwrap();
+    assert_eq!("Grace", found.get_str("name").unwrap());
+
+    let missing = DBRef::new("users", Bson::I32(2));
+    assert_eq!(None, db.dereference(&missing).unwrap());
+}
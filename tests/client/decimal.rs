@@ -0,0 +1,79 @@
+use bson::Bson;
+use mongodb::{Client, ThreadedClient};
+use mongodb::compare;
+use mongodb::db::ThreadedDatabase;
+use mongodb::decimal::{self, Decimal128};
+
+use std::cmp::Ordering;
+
+#[test]
+fn parse_decimals() {
+    for s in &["19.99", "-1.5E+3", "+.5", "7.", "1e-10", "NaN", "-Infinity", "inf"] {
+        assert!(decimal::parse(s).is_ok(), "Expected {} to parse.", s);
+    }
+
+    for s in &["", ".", "1.2.3", "--1", "1e", "1e+-2", "twelve", "0x10"] {
+        assert!(decimal::parse(s).is_err(), "Expected {} not to parse.", s);
+    }
+
+    let price = decimal::parse("12345678901234567890.123456789").unwrap();
+    assert_eq!("12345678901234567890.123456789", price.to_string());
+    assert_eq!(1.5, decimal::to_f64(&decimal::parse("1.50").unwrap()));
+}
+
+#[test]
+fn compare_decimals() {
+    let tenth = decimal::decimal("0.1").unwrap();
+    assert_eq!(Some(Ordering::Equal), decimal::compare(&tenth, &Bson::FloatingPoint(0.1)));
+    let two = decimal::decimal("2.00").unwrap();
+    assert_eq!(Some(Ordering::Equal), decimal::compare(&two, &Bson::I64(2)));
+    assert_eq!(Some(Ordering::Less), decimal::compare(&tenth, &Bson::I32(1)));
+    assert_eq!(None, decimal::compare(&tenth, &Bson::String(String::from("0.1"))));
+    assert_eq!(None, decimal::compare(&decimal::decimal("NaN").unwrap(), &tenth));
+
+    // Decimals compare by value with other numbers in structural comparisons.
+    assert!(compare::document_eq(
+        &doc! { "total": decimal::decimal("10.50").unwrap() },
+        &doc! { "total": 10.5 },
+        None
+    ));
+
+    let min = decimal::from_i64(10);
+    let max = decimal::from_f64(20.5);
+    assert_eq!(
+        Bson::Document(doc! {
+            "$gte": Bson::Decimal128(Decimal128::from_i32(10)),
+            "$lt": Bson::Decimal128(decimal::parse("20.5").unwrap()),
+        }),
+        decimal::range(min.clone(), max)
+    );
+    assert_eq!(Bson::Document(doc! { "$gt": Bson::Decimal128(min.clone()) }), decimal::gt(min));
+}
+
+#[test]
+fn decimal_round_trip() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-decimal");
+    skip_if_db_version_below!(db, 3, 4);
+
+    let coll = db.collection("decimal_round_trip");
+    coll.drop().unwrap();
+
+    let prices = ["0.1", "19.99", "12345678901234567890.123456789", "-0.000001"];
+    for (i, price) in prices.iter().enumerate() {
+        coll.insert_one(doc! { "_id": i as i32, "price": decimal::decimal(price).unwrap() }, None)
+            .unwrap();
+    }
+
+    // Every digit survives the round trip.
+    for (i, price) in prices.iter().enumerate() {
+        let doc = coll.find_one(Some(doc! { "_id": i as i32 }), None).unwrap().unwrap();
+        match doc.get("price") {
+            Some(Bson::Decimal128(value)) => assert_eq!(*price, value.to_string()),
+            other => panic!("Expected a decimal, got {:?}", other),
+        }
+    }
+
+    let filter = doc! { "price": decimal::range(decimal::from_i64(0), decimal::from_i64(20)) };
+    assert_eq!(2, coll.count(Some(filter), None).unwrap());
+}
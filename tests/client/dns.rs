@@ -0,0 +1,117 @@
+use mongodb::{Client, ClientOptions, ThreadedClient};
+use mongodb::clock::MockClock;
+use mongodb::connstring::ConnectionString;
+use mongodb::dns::{CachingResolver, DnsResolver, Lookup, DEFAULT_TTL};
+use mongodb::stream::StreamConnector;
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Answers every query with the loopback address and `ttl`, recording the names looked up.
+struct Recording {
+    ttl: Option<Duration>,
+    lookups: Mutex<Vec<String>>,
+}
+
+impl Recording {
+    fn new(ttl: Option<Duration>) -> Arc<Recording> {
+        Arc::new(Recording { ttl, lookups: Mutex::new(Vec::new()) })
+    }
+
+    fn lookups(&self) -> Vec<String> {
+        self.lookups.lock().unwrap().clone()
+    }
+}
+
+impl DnsResolver for Recording {
+    fn lookup_ip(&self, hostname: &str) -> io::Result<Lookup<IpAddr>> {
+        self.lookups.lock().unwrap().push(String::from(hostname));
+        Ok(Lookup::new(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)], self.ttl))
+    }
+}
+
+#[test]
+fn caching_resolver_keeps_answers_until_ttl() {
+    let clock = Arc::new(MockClock::new());
+    let inner = Recording::new(None);
+    let resolver = CachingResolver::new(inner.clone(), clock.clone());
+
+    let answer = resolver.lookup_ip("db.example.com").unwrap();
+    assert_eq!(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)], answer.records);
+    assert_eq!(Some(DEFAULT_TTL), answer.ttl);
+
+    // Host names are cached regardless of case.
+    clock.advance(Duration::from_secs(10));
+    let answer = resolver.lookup_ip("DB.example.com").unwrap();
+    assert_eq!(Some(DEFAULT_TTL - Duration::from_secs(10)), answer.ttl);
+    assert_eq!(1, inner.lookups().len());
+
+    clock.advance(DEFAULT_TTL);
+    resolver.lookup_ip("db.example.com").unwrap();
+    assert_eq!(2, inner.lookups().len());
+
+    resolver.evict("db.example.com");
+    resolver.lookup_ip("db.example.com").unwrap();
+    assert_eq!(3, inner.lookups().len());
+}
+
+#[test]
+fn caching_resolver_honors_answer_ttl() {
+    let clock = Arc::new(MockClock::new());
+    let inner = Recording::new(Some(Duration::from_secs(5)));
+    let resolver = CachingResolver::new(inner.clone(), clock.clone());
+
+    resolver.lookup_ip("db.example.com").unwrap();
+    clock.advance(Duration::from_secs(4));
+    resolver.lookup_ip("db.example.com").unwrap();
+    assert_eq!(1, inner.lookups().len());
+
+    clock.advance(Duration::from_secs(1));
+    resolver.lookup_ip("db.example.com").unwrap();
+    assert_eq!(2, inner.lookups().len());
+
+    // Answers that may not be cached are looked up every time.
+    let inner = Recording::new(Some(Duration::from_secs(0)));
+    let resolver = CachingResolver::new(inner.clone(), clock.clone());
+    resolver.lookup_ip("db.example.com").unwrap();
+    resolver.lookup_ip("db.example.com").unwrap();
+    assert_eq!(2, inner.lookups().len());
+}
+
+#[test]
+fn ip_literals_skip_resolver() {
+    let resolver = Recording::new(None);
+    let timeout = Some(Duration::from_secs(1));
+
+    // Nothing listens on port 1, so only resolution succeeds.
+    let result = StreamConnector::Tcp.connect_with_resolver("127.0.0.1", 1, timeout, &*resolver);
+    assert!(result.is_err());
+    assert!(resolver.lookups().is_empty());
+
+    let result =
+        StreamConnector::Tcp.connect_with_resolver("db.example.com", 1, timeout, &*resolver);
+    assert!(result.is_err());
+    assert_eq!(vec!["db.example.com"], resolver.lookups());
+}
+
+#[test]
+fn client_connects_through_dns_resolver() {
+    let resolver = Recording::new(None);
+    let mut options = ClientOptions::new();
+    options.dns_resolver = Some(resolver.clone());
+
+    let config = ConnectionString::new("db.example.invalid", 1);
+    let _client = Client::with_config(config, Some(options), None).unwrap();
+
+    // The server's monitor connects in the background.
+    for _ in 0..200 {
+        if !resolver.lookups().is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(25));
+    }
+    assert_eq!(Some("db.example.invalid"), resolver.lookups().first().map(String::as_str));
+}
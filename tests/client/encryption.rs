@@ -0,0 +1,259 @@
+use bson::{Bson, Document};
+use bson::spec::BinarySubtype;
+use mongodb::{Client, ClientOptions, Error, ThreadedClient};
+use mongodb::db::ThreadedDatabase;
+use mongodb::encryption::{Algorithm, AutoEncryptionOptions, ClientEncryption, DataKeyOptions,
+                          EncryptionKey, KmsProviders, MasterKey};
+use rand;
+
+const KEY_VAULT: &str = "test-encryption.__keyVault";
+
+fn local_kms_providers() -> KmsProviders {
+    let mut kms_providers = KmsProviders::new();
+    kms_providers.local = Some((0..96).map(|_| rand::random::<u8>()).collect());
+    kms_providers
+}
+
+fn schema(key_id: &[u8]) -> Document {
+    doc! {
+        "bsonType": "object",
+        "encryptMetadata": {
+            "keyId": [Bson::Binary(BinarySubtype::Uuid, key_id.to_vec())],
+        },
+        "properties": {
+            "ssn": {
+                "encrypt": {
+                    "bsonType": "string",
+                    "algorithm": Algorithm::Deterministic.to_str(),
+                },
+            },
+            "medical": {
+                "properties": {
+                    "notes": {
+                        "encrypt": {
+                            "bsonType": "string",
+                            "algorithm": Algorithm::Random.to_str(),
+                        },
+                    },
+                },
+            },
+        },
+    }
+}
+
+#[test]
+fn algorithm_names() {
+    for algorithm in &[Algorithm::Deterministic, Algorithm::Random] {
+        assert_eq!(Some(*algorithm), Algorithm::from_name(algorithm.to_str()));
+    }
+
+    assert_eq!(None, Algorithm::from_name("AES"));
+
+    let aws = MasterKey::Aws {
+        region: String::from("us-east-1"),
+        key: String::from("arn:aws:kms:us-east-1:123456789012:key/example"),
+        endpoint: None,
+    };
+    assert_eq!(aws, MasterKey::from_document(&aws.to_document()).unwrap());
+    assert_eq!(MasterKey::Local, MasterKey::from_document(&doc! { "provider": "local" }).unwrap());
+    assert!(MasterKey::from_document(&doc! { "provider": "gcp" }).is_err());
+}
+
+#[test]
+fn invalid_auto_encryption_options() {
+    let mut encryption = AutoEncryptionOptions::new("no-collection", local_kms_providers());
+    let mut options = ClientOptions::new();
+    options.auto_encryption = Some(encryption.clone());
+
+    match Client::connect_with_options("localhost", 27017, options) {
+        Err(Error::ArgumentError(_)) => (),
+        result => panic!("Expected an argument error, got {:?}", result),
+    }
+
+    encryption.key_vault_namespace = String::from(KEY_VAULT);
+    encryption.schema_map.insert(
+        String::from("test-encryption.people"),
+        doc! {
+            "properties": {
+                "ssn": { "encrypt": { "algorithm": "AES" } },
+            },
+        },
+    );
+    let mut options = ClientOptions::new();
+    options.auto_encryption = Some(encryption);
+
+    match Client::connect_with_options("localhost", 27017, options) {
+        Err(Error::ArgumentError(_)) => (),
+        result => panic!("Expected an argument error, got {:?}", result),
+    }
+}
+
+#[test]
+fn auto_encryption() {
+    let plain = Client::connect("localhost", 27017).unwrap();
+    plain.drop_database("test-encryption").unwrap();
+
+    // Stores a data key encrypted with the local master key, as `ClientEncryption` would.
+    let kms_providers = local_kms_providers();
+    let key_id: Vec<u8> = (0..16).map(|_| rand::random::<u8>()).collect();
+    let data_key: Vec<u8> = (0..96).map(|_| rand::random::<u8>()).collect();
+    let key_material = kms_providers.wrap(&MasterKey::Local, &data_key).unwrap();
+
+    plain
+        .db("test-encryption")
+        .collection("__keyVault")
+        .insert_one(
+            doc! {
+                "_id": Bson::Binary(BinarySubtype::Uuid, key_id.clone()),
+                "keyMaterial": Bson::Binary(BinarySubtype::Generic, key_material),
+                "masterKey": MasterKey::Local.to_document(),
+                "status": 0,
+            },
+            None,
+        )
+        .unwrap();
+
+    let mut encryption = AutoEncryptionOptions::new(KEY_VAULT, kms_providers);
+    encryption.schema_map.insert(String::from("test-encryption.people"), schema(&key_id));
+    let mut options = ClientOptions::new();
+    options.auto_encryption = Some(encryption);
+
+    let client = Client::connect_with_options("localhost", 27017, options).unwrap();
+    let people = client.db("test-encryption").collection("people");
+
+    people
+        .insert_one(
+            doc! {
+                "name": "Ann",
+                "ssn": "123-45-6789",
+                "medical": { "notes": "allergic to penicillin" },
+            },
+            None,
+        )
+        .unwrap();
+
+    // The server only sees ciphertext.
+    let stored = plain
+        .db("test-encryption")
+        .collection("people")
+        .find_one(None, None)
+        .unwrap()
+        .unwrap();
+
+    for value in &[stored.get("ssn"), stored.get_document("medical").unwrap().get("notes")] {
+        match *value {
+            Some(&Bson::Binary(BinarySubtype::UserDefined(6), _)) => (),
+            other => panic!("Expected an encrypted value, got {:?}", other),
+        }
+    }
+
+    // Deterministically encrypted fields can be queried for equality, and results are decrypted.
+    let ann = people
+        .find_one(Some(doc! { "ssn": "123-45-6789" }), None)
+        .unwrap()
+        .expect("Expected to find the document by its encrypted field.");
+    assert_eq!("Ann", ann.get_str("name").unwrap());
+    assert_eq!("123-45-6789", ann.get_str("ssn").unwrap());
+    assert_eq!(
+        "allergic to penicillin",
+        ann.get_document("medical").unwrap().get_str("notes").unwrap()
+    );
+
+    let found = people
+        .count(Some(doc! { "ssn": { "$in": ["000-00-0000", "123-45-6789"] } }), None)
+        .unwrap();
+    assert_eq!(1, found);
+
+    people
+        .update_one(doc! { "ssn": "123-45-6789" }, doc! { "$set": { "ssn": "987-65-4321" } }, None)
+        .unwrap();
+    assert!(people.find_one(Some(doc! { "ssn": "987-65-4321" }), None).unwrap().is_some());
+
+    // Randomly encrypted fields cannot be queried.
+    match people.find_one(Some(doc! { "medical.notes": "allergic to penicillin" }), None) {
+        Err(Error::EncryptionError(_)) => (),
+        result => panic!("Expected an encryption error, got {:?}", result),
+    }
+
+    // Queries that cannot be encrypted fail rather than compare encrypted fields with plaintext.
+    let filters = vec![
+        doc! { "$expr": { "$eq": ["$ssn", "987-65-4321"] } },
+        doc! { "$where": "this.ssn == '987-65-4321'" },
+        doc! { "medical": { "$exists": true, "$ne": null } },
+    ];
+    for filter in filters {
+        match people.find_one(Some(filter.clone()), None) {
+            Err(Error::EncryptionError(_)) => (),
+            result => panic!("Expected an encryption error for {}, got {:?}", filter, result),
+        }
+    }
+
+    let pipeline = vec![
+        doc! { "$match": { "name": "Ann" } },
+        doc! { "$project": { "name": 1, "ssn": 1 } },
+        doc! { "$match": { "ssn": "987-65-4321" } },
+    ];
+    match people.aggregate(pipeline, None) {
+        Err(Error::EncryptionError(_)) => (),
+        result => panic!("Expected an encryption error, got {:?}", result.map(|_| ())),
+    }
+
+    let pipeline = vec![
+        doc! { "$match": { "ssn": "987-65-4321" } },
+        doc! { "$group": { "_id": null, "count": { "$sum": 1 } } },
+    ];
+    let counts: Vec<_> = people.aggregate(pipeline, None).unwrap().collect();
+    assert_eq!(1, counts.len());
+}
+
+#[test]
+fn explicit_encryption() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    client.drop_database("test-encryption-explicit").unwrap();
+
+    let encryption = ClientEncryption::new(
+        client.clone(),
+        "test-encryption-explicit.__keyVault",
+        local_kms_providers(),
+    ).unwrap();
+
+    let mut options = DataKeyOptions::new();
+    options.key_alt_names = vec![String::from("ssn-key")];
+    let key_id = encryption.create_data_key(MasterKey::Local, Some(options)).unwrap();
+    assert_eq!(16, key_id.len());
+
+    let key_doc = client
+        .db("test-encryption-explicit")
+        .collection("__keyVault")
+        .find_one(None, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(Some(&Bson::Binary(BinarySubtype::Uuid, key_id.clone())), key_doc.get("_id"));
+    assert_eq!(&doc! { "provider": "local" }, key_doc.get_document("masterKey").unwrap());
+
+    let ssn = Bson::String(String::from("123-45-6789"));
+    let by_id = EncryptionKey::Id(key_id);
+    let by_name = EncryptionKey::AltName(String::from("ssn-key"));
+
+    // Deterministic encryption gives the same ciphertext for the same value and key.
+    let deterministic = encryption.encrypt(&ssn, by_id.clone(), Algorithm::Deterministic).unwrap();
+    assert_eq!(
+        deterministic,
+        encryption.encrypt(&ssn, by_name.clone(), Algorithm::Deterministic).unwrap()
+    );
+    assert_eq!(ssn, encryption.decrypt(&deterministic).unwrap());
+
+    let random = encryption.encrypt(&ssn, by_name.clone(), Algorithm::Random).unwrap();
+    assert_ne!(random, encryption.encrypt(&ssn, by_name, Algorithm::Random).unwrap());
+    assert_eq!(ssn, encryption.decrypt(&random).unwrap());
+
+    // Documents can only be encrypted randomly.
+    let document = Bson::Document(doc! { "street": "1 Main St" });
+    assert!(encryption.encrypt(&document, by_id.clone(), Algorithm::Deterministic).is_err());
+    let encrypted = encryption.encrypt(&document, by_id, Algorithm::Random).unwrap();
+    assert_eq!(document, encryption.decrypt(&encrypted).unwrap());
+
+    let unknown = EncryptionKey::AltName(String::from("unknown"));
+    assert!(encryption.encrypt(&ssn, unknown, Algorithm::Random).is_err());
+    assert!(encryption.decrypt(&ssn).is_err());
+}
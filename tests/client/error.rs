@@ -1,6 +1,9 @@
 use mongodb::common::WriteConcern;
-use mongodb::coll::error::{BulkWriteException, WriteConcernError, WriteError};
-use mongodb::Error;
+use mongodb::coll::error::{BulkWriteException, WriteConcernError, WriteError, WriteException};
+use mongodb::{Error, ErrorCode};
+use mongodb::error::{self, CommandError, RetryHint, ServerError};
+
+use std::io;
 
 #[test]
 fn validate_write_result() {
@@ -129,3 +132,190 @@ fn parse_invalid_write_error() {
     let result = WriteError::parse(doc);
     assert!(result.is_err());
 }
+
+#[test]
+fn parse_err_info() {
+    let doc =
+        doc! {
+            "ok": 1,
+            "n": 0,
+            "writeConcernError": {
+                "code": 64,
+                "errmsg": "waiting for replication timed out",
+                "errInfo": { "wtimeout": true }
+            },
+            "writeErrors": [{
+                "index": 0,
+                "code": 121,
+                "errmsg": "Document failed validation",
+                "errInfo": { "failingDocumentId": 1 }
+            }]
+        };
+
+    let err = BulkWriteException::validate_bulk_write_result(doc, WriteConcern::new())
+        .unwrap_err();
+
+    let wc_err = err.write_concern_error().expect("Expected a write concern error.");
+    assert_eq!(Some(doc! { "wtimeout": true }), wc_err.err_info);
+
+    match err {
+        Error::BulkWriteError(ref exception) => {
+            let info = exception.write_errors[0].err_info.clone();
+            assert_eq!(Some(doc! { "failingDocumentId": 1 }), info);
+        }
+        _ => panic!("Expected BulkWriteError."),
+    }
+}
+
+#[test]
+fn is_duplicate_key() {
+    let duplicate = doc! {
+        "index": 1,
+        "code": 11000,
+        "errmsg": "E11000 duplicate key error collection: test.coll index: _id_ dup key"
+    };
+    let other = doc! { "index": 0, "code": 121, "errmsg": "Document failed validation" };
+
+    let doc = doc! { "ok": 1, "n": 0, "writeErrors": [other.clone(), duplicate] };
+    let err = BulkWriteException::validate_bulk_write_result(doc, WriteConcern::new())
+        .unwrap_err();
+    assert!(err.is_duplicate_key());
+    assert!(err.write_concern_error().is_none());
+
+    let doc = doc! { "ok": 1, "n": 0, "writeErrors": [other] };
+    let err = BulkWriteException::validate_bulk_write_result(doc, WriteConcern::new())
+        .unwrap_err();
+    assert!(!err.is_duplicate_key());
+
+    let exception = WriteException::new(None, Some(WriteError::new(11000, "duplicate key")));
+    assert!(exception.is_duplicate_key());
+    assert!(Error::WriteError(exception).is_duplicate_key());
+    assert!(Error::CodedError(ErrorCode::DuplicateKey).is_duplicate_key());
+    assert!(!Error::CodedError(ErrorCode::BadValue).is_duplicate_key());
+}
+
+#[test]
+fn memory_limit_error_guidance() {
+    let err = Error::CodedError(ErrorCode::QueryExceededMemoryLimitNoDiskUseAllowed);
+    assert!(err.to_string().contains("allow_disk_use"));
+}
+
+#[test]
+fn error_labels() {
+    let reply = doc! {
+        "ok": 0,
+        "errmsg": "operation was interrupted",
+        "code": 11602,
+        "codeName": "InterruptedDueToReplStateChange",
+        "errorLabels": ["RetryableWriteError", "TransientTransactionError"]
+    };
+
+    let err = ServerError::parse(&reply).expect("Expected a server error.");
+    assert_eq!(11602, err.code);
+    assert_eq!("InterruptedDueToReplStateChange", err.code_name);
+
+    let err = Error::ServerError(err);
+    assert_eq!(&["RetryableWriteError", "TransientTransactionError"], err.labels());
+    assert!(err.has_label(error::RETRYABLE_WRITE_ERROR));
+    assert!(!err.has_label(error::UNKNOWN_TRANSACTION_COMMIT_RESULT));
+    assert!(err.is_retryable_write());
+    assert!(err.is_transient_transaction_error());
+    assert!(!err.is_network_error());
+
+    // Servers that label errors decide retryability themselves.
+    let reply = doc! {
+        "ok": 0,
+        "errmsg": "operation was interrupted",
+        "code": 11602,
+        "errorLabels": ["TransientTransactionError"]
+    };
+    assert!(!Error::ServerError(ServerError::parse(&reply).unwrap()).is_retryable_write());
+
+    // Older servers don't, so their codes are consulted instead.
+    let reply = doc! { "ok": 0, "errmsg": "not master", "code": 10107 };
+    assert!(Error::ServerError(ServerError::parse(&reply).unwrap()).is_retryable_write());
+
+    let reply = doc! { "ok": 0, "errmsg": "bad value", "code": 2 };
+    assert!(!Error::ServerError(ServerError::parse(&reply).unwrap()).is_retryable_write());
+
+    assert!(ServerError::parse(&doc! { "ok": 1 }).is_none());
+
+    let err = Error::IoError(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+    assert!(err.is_network_error());
+    assert!(err.is_retryable_write());
+}
+
+#[test]
+fn retry_hints() {
+    let reply = doc! {
+        "ok": 0,
+        "errmsg": "Document failed validation",
+        "code": 121,
+        "codeName": "DocumentValidationFailure",
+        "errInfo": { "failingDocumentId": 1, "details": { "operatorName": "$jsonSchema" } }
+    };
+    let err = ServerError::parse(&reply).unwrap();
+    let details = err.err_info.clone().expect("Expected errInfo to be parsed.");
+    assert!(details.contains_key("failingDocumentId"));
+    assert_eq!(RetryHint::DoNotRetry, Error::DocumentValidationFailure(err).retry_hint());
+
+    let reply = doc! { "ok": 0, "errmsg": "WriteConflict error", "code": 112 };
+    let err = ServerError::parse(&reply).unwrap();
+    assert!(Error::ServerError(err.clone()).is_write_conflict());
+    assert_eq!(RetryHint::RetryOperation, Error::WriteConflict(err).retry_hint());
+
+    // Within a transaction, the server labels the conflict and the transaction must be retried.
+    let reply = doc! {
+        "ok": 0,
+        "errmsg": "WriteConflict error",
+        "code": 112,
+        "errorLabels": ["TransientTransactionError"]
+    };
+    let err = Error::WriteConflict(ServerError::parse(&reply).unwrap());
+    assert_eq!(RetryHint::RetryTransaction, err.retry_hint());
+
+    let reply = doc! { "ok": 0, "errmsg": "not master", "code": 10107 };
+    let err = Error::ServerError(ServerError::parse(&reply).unwrap());
+    assert_eq!(RetryHint::RetryOperation, err.retry_hint());
+
+    let err = Error::ArgumentError(String::from("bad filter"));
+    assert_eq!(RetryHint::DoNotRetry, err.retry_hint());
+}
+
+#[test]
+fn write_concern_error_labels() {
+    let doc = doc! {
+        "ok": 1,
+        "n": 1,
+        "writeConcernError": {
+            "code": 91,
+            "errmsg": "Replication is being shut down"
+        },
+        "errorLabels": ["RetryableWriteError"]
+    };
+
+    let err = BulkWriteException::validate_bulk_write_result(doc, WriteConcern::new())
+        .unwrap_err();
+    assert!(err.has_label(error::RETRYABLE_WRITE_ERROR));
+    assert!(err.is_retryable_write());
+}
+
+#[test]
+fn command_error_context() {
+    let cause = Error::IoError(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+    let err = Error::CommandError(CommandError {
+        host: String::from("db1.example.com:27017"),
+        command_name: String::from("find"),
+        request_id: 42,
+        operation_id: 7,
+        duration: 12_500_000,
+        cause: Box::new(cause),
+    });
+
+    assert_eq!(
+        "find on db1.example.com:27017 (request 42, operation 7) failed after 12 ms: reset",
+        format!("{}", err)
+    );
+    assert!(err.is_network_error());
+    assert!(err.is_retryable_write());
+}
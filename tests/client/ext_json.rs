@@ -0,0 +1,119 @@
+use bson::Bson;
+use bson::oid::ObjectId;
+use bson::spec::BinarySubtype;
+use chrono::{TimeZone, Utc};
+use mongodb::ext_json::{self, Mode};
+use serde_json::json;
+
+fn all_types() -> ::bson::Document {
+    doc! {
+        "double": 1.5,
+        "whole": 2.0,
+        "nan": f64::NAN,
+        "string": "text",
+        "array": [1, "two"],
+        "doc": { "nested": true },
+        "null": Bson::Null,
+        "regex": Bson::RegExp(String::from("^a"), String::from("im")),
+        "code": Bson::JavaScriptCode(String::from("x + 1")),
+        "scoped": Bson::JavaScriptCodeWithScope(String::from("x"), doc! { "x": 1 }),
+        "int": 1,
+        "long": 1i64,
+        "timestamp": Bson::TimeStamp((7i64 << 32) | 3),
+        "binary": Bson::Binary(BinarySubtype::Uuid, vec![0xab; 16]),
+        "oid": ObjectId::with_string("5f3e8a5b1c9d440000a1b2c3").unwrap(),
+        "date": Utc.timestamp_millis_opt(1_500_000_000_123).unwrap(),
+        "ancient": Utc.timestamp_millis_opt(-62_000_000_000_000).unwrap(),
+        "symbol": Bson::Symbol(String::from("sym")),
+        "$set": { "operator": 1 },
+    }
+}
+
+#[test]
+fn canonical_round_trip() {
+    let doc = all_types();
+    let json = ext_json::to_string(&doc, Mode::Canonical);
+    let parsed = ext_json::from_str(&json).unwrap();
+
+    // NaN never equals itself, so compare it separately.
+    match parsed.get("nan") {
+        Some(&Bson::FloatingPoint(f)) => assert!(f.is_nan()),
+        other => panic!("Expected NaN, got {:?}", other),
+    }
+
+    let without_nan = |mut doc: ::bson::Document| {
+        doc.remove("nan");
+        doc
+    };
+    assert_eq!(without_nan(doc), without_nan(parsed));
+
+    let value = ext_json::document_to_value(&all_types(), Mode::Canonical);
+    assert_eq!(json!({ "$numberInt": "1" }), value["int"]);
+    assert_eq!(json!({ "$numberLong": "1" }), value["long"]);
+    assert_eq!(json!({ "$numberDouble": "2.0" }), value["whole"]);
+    assert_eq!(json!({ "$numberDouble": "NaN" }), value["nan"]);
+    assert_eq!(json!({ "$timestamp": { "t": 7, "i": 3 } }), value["timestamp"]);
+    assert_eq!(json!({ "$oid": "5f3e8a5b1c9d440000a1b2c3" }), value["oid"]);
+    assert_eq!(json!({ "$date": { "$numberLong": "1500000000123" } }), value["date"]);
+    assert_eq!(
+        json!({ "$regularExpression": { "pattern": "^a", "options": "im" } }),
+        value["regex"]
+    );
+    assert_eq!(
+        json!({ "$binary": { "base64": "q6urq6urq6urq6urq6urqw==", "subType": "04" } }),
+        value["binary"]
+    );
+}
+
+#[test]
+fn relaxed_output() {
+    let value = ext_json::document_to_value(&all_types(), Mode::Relaxed);
+
+    assert_eq!(json!(1), value["int"]);
+    assert_eq!(json!(1), value["long"]);
+    assert_eq!(json!(1.5), value["double"]);
+    assert_eq!(json!({ "$numberDouble": "NaN" }), value["nan"]);
+    assert_eq!(json!({ "$date": "2017-07-14T02:40:00.123Z" }), value["date"]);
+    // Dates before 1970 keep the canonical form.
+    assert_eq!(json!({ "$date": { "$numberLong": "-62000000000000" } }), value["ancient"]);
+
+    // Relaxed numbers parse as the narrowest integer type that holds them.
+    let parsed = ext_json::from_str(r#"{ "small": 1, "large": 5000000000, "real": 1.0 }"#);
+    assert_eq!(doc! { "small": 1, "large": 5_000_000_000i64, "real": 1.0 }, parsed.unwrap());
+}
+
+#[test]
+fn parse_other_forms() {
+    let parsed = ext_json::from_str(
+        r#"{
+            "uuid": { "$uuid": "abababab-abab-abab-abab-abababababab" },
+            "legacy_date": { "$date": 1500000000123 },
+            "iso_date": { "$date": "2017-07-14T02:40:00.123+00:00" }
+        }"#,
+    ).unwrap();
+
+    let date = Bson::UtcDatetime(Utc.timestamp_millis_opt(1_500_000_000_123).unwrap());
+    assert_eq!(
+        doc! {
+            "uuid": Bson::Binary(BinarySubtype::Uuid, vec![0xab; 16]),
+            "legacy_date": date.clone(),
+            "iso_date": date,
+        },
+        parsed
+    );
+
+    assert!(ext_json::from_str(r#"{ "n": { "$numberInt": "1.5" } }"#).is_err());
+    assert!(ext_json::from_str(r#"{ "k": { "$minKey": 1 } }"#).is_err());
+    assert!(ext_json::from_str("[1, 2]").is_err());
+}
+
+#[cfg(feature = "decimal128")]
+#[test]
+fn decimal_round_trip() {
+    use mongodb::decimal;
+
+    let doc = doc! { "price": decimal::decimal("19.99").unwrap() };
+    let json = ext_json::to_string(&doc, Mode::Relaxed);
+    assert_eq!(r#"{"price":{"$numberDecimal":"19.99"}}"#, json);
+    assert_eq!(doc, ext_json::from_str(&json).unwrap());
+}
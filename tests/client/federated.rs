@@ -0,0 +1,59 @@
+use mongodb::{Client, CommandType, Error, ThreadedClient};
+use mongodb::connstring::{self, ConnectionString};
+use mongodb::stream::StreamConnector;
+use mongodb::topology::TopologyDescription;
+use mongodb::topology::server::{Server, ServerType};
+use mongodb::ClientOptions;
+
+use std::sync::{Arc, RwLock};
+
+#[test]
+fn federated_topology() {
+    let client = Client::with_config(ConnectionString::new("i-dont-exist", 27017), None, None)
+        .unwrap();
+    let mut topology = TopologyDescription::new(StreamConnector::default());
+
+    let host = connstring::parse_host("localhost:27017").unwrap();
+    let server = Server::new(
+        client.clone(),
+        host.clone(),
+        Arc::new(RwLock::new(TopologyDescription::new(StreamConnector::default()))),
+        false,
+        StreamConnector::default(),
+    );
+    server.description.write().unwrap().server_type = ServerType::Mongos;
+    topology.servers.insert(host, server.clone());
+    assert!(!topology.is_federated());
+
+    server.description.write().unwrap().federated = Some(false);
+    assert!(!topology.is_federated());
+
+    server.description.write().unwrap().federated = Some(true);
+    assert!(topology.is_federated());
+}
+
+#[test]
+fn federated_unsupported() {
+    let mut options = ClientOptions::new();
+    options.federated = Some(true);
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    let client = Client::with_config(config, Some(options), None).unwrap();
+    assert!(client.is_federated());
+
+    match client.list_local_sessions(None) {
+        Err(Error::Unsupported(_)) => (),
+        other => panic!("Expected an Unsupported error, got {:?}", other),
+    }
+
+    assert!(!CommandType::InsertOne.is_federated_supported());
+    assert!(!CommandType::CreateIndexes.is_federated_supported());
+    assert!(CommandType::Aggregate.is_federated_supported());
+    assert!(CommandType::Find.is_federated_supported());
+}
+
+#[test]
+fn federated_detection() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    client.is_master().unwrap();
+    assert!(!client.is_federated());
+}
@@ -0,0 +1,47 @@
+use bson::Bson;
+use mongodb::coll::filter;
+use mongodb::coll::update;
+
+#[test]
+fn filters() {
+    assert_eq!(doc! { "a": 1 }, filter::eq("a", 1).into_document());
+    assert_eq!(doc! { "a": { "$lte": 2.5 } }, filter::lte("a", 2.5).into_document());
+    assert_eq!(doc! { "a": { "$in": [1, 2] } }, filter::in_("a", vec![1, 2]).into_document());
+    assert_eq!(doc! { "a": { "$exists": false } }, filter::exists("a", false).into_document());
+    assert_eq!(
+        doc! { "a": Bson::RegExp(String::from("^x"), String::from("i")) },
+        filter::regex("a", "^x", "i").into_document()
+    );
+
+    // Chains of the same combinator stay flat; different ones nest.
+    let filter = filter::eq("a", 1).and(filter::gt("b", 5)).and(filter::ne("c", "x"));
+    assert_eq!(
+        doc! { "$and": [{ "a": 1 }, { "b": { "$gt": 5 } }, { "c": { "$ne": "x" } }] },
+        filter.into_document()
+    );
+
+    let filter = filter::eq("a", 1).or(filter::eq("a", 2)).and(filter::exists("b", true));
+    assert_eq!(
+        doc! { "$and": [{ "$or": [{ "a": 1 }, { "a": 2 }] }, { "b": { "$exists": true } }] },
+        filter.into_document()
+    );
+}
+
+#[test]
+fn updates() {
+    let update = update::set("x", 3).set("y", "z").inc("n", 1).push("tags", "new").unset("old");
+    assert_eq!(
+        doc! {
+            "$set": { "x": 3, "y": "z" },
+            "$inc": { "n": 1 },
+            "$push": { "tags": "new" },
+            "$unset": { "old": "" },
+        },
+        update.into_document()
+    );
+
+    assert_eq!(
+        doc! { "$rename": { "a": "b" }, "$currentDate": { "at": true } },
+        update::rename("a", "b").current_date("at").into_document()
+    );
+}
@@ -0,0 +1,22 @@
+use mongodb::{Client, ThreadedClient};
+use mongodb::db::ThreadedDatabase;
+
+#[test]
+fn fsync_lock() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-fsync");
+    skip_if_db_version_below!(db, 4, 2);
+
+    let outer = client.fsync_lock().unwrap();
+    assert_eq!(Some(1), outer.lock_count());
+    assert_eq!(27017, outer.host().port);
+
+    // Dropping a guard unlocks once, leaving the outer lock held.
+    {
+        let inner = client.fsync_lock().unwrap();
+        assert_eq!(Some(2), inner.lock_count());
+    }
+
+    assert_eq!(Some(0), outer.unlock().unwrap());
+    db.collection("writable").insert_one(doc! { "x": 1 }, None).unwrap();
+}
@@ -0,0 +1,86 @@
+use mongodb::{Client, ClientOptions, ConnectMode, Error, ThreadedClient};
+use mongodb::clock::{Clock, MockClock};
+use mongodb::connstring::ConnectionString;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn ping_and_warm_up() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    client.warm_up().unwrap();
+    assert!(client.is_connected());
+    client.ping().unwrap();
+}
+
+#[test]
+fn eager_connect() {
+    let mut options = ClientOptions::new();
+    options.connect = ConnectMode::Eager { timeout_ms: 5000 };
+
+    let client = Client::connect_with_options("localhost", 27017, options).unwrap();
+    assert!(client.is_connected());
+}
+
+#[test]
+fn unreachable_deployment() {
+    let clock = Arc::new(MockClock::new());
+    let mut options = ClientOptions::new();
+    options.clock = Some(clock.clone());
+
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    let client = Client::with_config(config, Some(options), None).unwrap();
+
+    // Discovery gives up once server selection would, after 30 s of the mock clock's time.
+    let start = clock.now();
+    match client.warm_up() {
+        Err(Error::OperationError(_)) => (),
+        other => panic!("Expected no servers to be reachable, got {:?}", other),
+    }
+    assert!(clock.now() - start >= Duration::from_secs(30));
+    assert!(!client.is_connected());
+
+    // A ping waits only 5 s.
+    let start = clock.now();
+    match client.ping() {
+        Err(Error::Timeout(_)) => (),
+        other => panic!("Expected the ping to time out, got {:?}", other),
+    }
+    let elapsed = clock.now() - start;
+    assert!(elapsed >= Duration::from_secs(5) && elapsed < Duration::from_secs(30));
+}
+
+#[test]
+fn eager_connect_to_unreachable_deployment() {
+    let clock = Arc::new(MockClock::new());
+    let config = ConnectionString::new("i-dont-exist", 27017);
+
+    let mut options = ClientOptions::new();
+    options.clock = Some(clock.clone());
+    assert!(Client::with_config(config.clone(), Some(options), None).is_ok());
+
+    let mut options = ClientOptions::new();
+    options.clock = Some(clock.clone());
+    options.connect = ConnectMode::Eager { timeout_ms: 1000 };
+
+    let start = clock.now();
+    match Client::with_config(config, Some(options), None) {
+        Err(Error::Timeout(_)) => (),
+        other => panic!("Expected connecting to time out, got {:?}", other.map(|_| ())),
+    }
+    assert!(clock.now() - start >= Duration::from_secs(1));
+}
+
+#[test]
+fn eager_connect_requires_timeout() {
+    let config = ConnectionString::new("i-dont-exist", 27017);
+
+    for &timeout_ms in &[0, -1] {
+        let mut options = ClientOptions::new();
+        options.connect = ConnectMode::Eager { timeout_ms };
+        match Client::with_config(config.clone(), Some(options), None) {
+            Err(Error::ArgumentError(_)) => (),
+            other => panic!("Expected an argument error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use bson::{self, Bson, Document};
+use mongodb::coll::options::{AggregateOptions, FindOptions, IndexModel};
+use mongodb::common::{merge_options, DocumentExt};
+
+fn keys(doc: &Document) -> Vec<&str> {
+    doc.keys().map(String::as_str).collect()
+}
+
+// Encodes and decodes a document, as happens when it is sent to the server.
+fn round_trip(doc: &Document) -> Document {
+    let mut bytes = Vec::new();
+    bson::encode_document(&mut bytes, doc).unwrap();
+    bson::decode_document(&mut &bytes[..]).unwrap()
+}
+
+#[test]
+fn ensure_first_key() {
+    let mut doc = doc! { "a": 1, "b": 2, "cmd": "coll", "c": 3 };
+
+    assert!(doc.ensure_first_key("cmd"));
+    assert_eq!(vec!["cmd", "a", "b", "c"], keys(&doc));
+    assert_eq!(Some(&Bson::String(String::from("coll"))), doc.get("cmd"));
+
+    assert!(doc.ensure_first_key("cmd"));
+    assert_eq!(vec!["cmd", "a", "b", "c"], keys(&doc));
+
+    assert!(!doc.ensure_first_key("missing"));
+    assert_eq!(vec!["cmd", "a", "b", "c"], keys(&doc));
+}
+
+#[test]
+fn merged_commands_keep_their_key_order() {
+    let mut options = AggregateOptions::new();
+    options.batch_size = 10;
+
+    // The options repeat "cursor", which must not move it, or the command name, around.
+    let spec = doc! { "aggregate": "coll", "cursor": {}, "pipeline": [] };
+    let merged = round_trip(&merge_options(spec, options));
+
+    assert_eq!("aggregate", keys(&merged)[0]);
+    assert_eq!(vec!["aggregate", "cursor", "pipeline"], &keys(&merged)[..3]);
+    assert_eq!(Some(&Bson::Document(doc! { "batchSize": 10 })), merged.get("cursor"));
+}
+
+#[test]
+fn sort_and_index_keys_keep_their_order() {
+    let mut options = FindOptions::new();
+    options.sort = Some(doc! { "z": 1, "a": -1, "m": 1 });
+
+    let command = round_trip(&merge_options(doc! { "find": "coll" }, options));
+    match command.get("sort") {
+        Some(&Bson::Document(ref sort)) => assert_eq!(vec!["z", "a", "m"], keys(sort)),
+        _ => panic!("Expected a sort document."),
+    }
+
+    let model = IndexModel::new(doc! { "z": 1, "a": -1 }, None);
+    assert_eq!("z_1_a_-1", model.name().unwrap());
+
+    let index = round_trip(&model.to_bson().unwrap());
+    match index.get("key") {
+        Some(&Bson::Document(ref key)) => assert_eq!(vec!["z", "a"], keys(key)),
+        _ => panic!("Expected an index key document."),
+    }
+}
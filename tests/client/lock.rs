@@ -0,0 +1,111 @@
+use bson::Bson;
+use mongodb::{Client, CommandType, ThreadedClient};
+use mongodb::db::ThreadedDatabase;
+use mongodb::lock::{Lock, LockOptions};
+
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn exclusive_and_fenced() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-lock-exclusive_and_fenced");
+    db.drop_database().unwrap();
+    let coll = db.collection("locks");
+
+    let first = Lock::new(&coll, "job", None);
+    let second = Lock::new(&coll, "job", None);
+
+    let guard = first.try_acquire().unwrap().expect("Expected to acquire lock.");
+    assert_eq!(1, guard.token());
+    assert!(second.try_acquire().unwrap().is_none());
+    assert!(second.acquire(200).is_err());
+
+    guard.release().unwrap();
+
+    let guard = second.try_acquire().unwrap().expect("Expected to acquire lock.");
+    assert_eq!(2, guard.token());
+
+    // Dropping the guard releases the lock as well.
+    drop(guard);
+    assert_eq!(3, first.try_acquire().unwrap().unwrap().token());
+}
+
+#[test]
+fn expired_lease_taken_over() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-lock-expired_lease_taken_over");
+    db.drop_database().unwrap();
+    let coll = db.collection("locks");
+
+    let mut options = LockOptions::new();
+    options.lease_ms = Some(100);
+    options.auto_renew = Some(false);
+
+    let first = Lock::new(&coll, "job", Some(options));
+    let second = Lock::new(&coll, "job", None);
+
+    let stale = first.try_acquire().unwrap().unwrap();
+    thread::sleep(Duration::from_millis(200));
+
+    let guard = second.try_acquire().unwrap().expect("Expected to take over lock.");
+    assert!(guard.token() > stale.token());
+    assert!(stale.renew().is_err());
+    assert!(stale.is_lost());
+}
+
+#[test]
+fn auto_renewal_keeps_lease() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-lock-auto_renewal_keeps_lease");
+    db.drop_database().unwrap();
+    let coll = db.collection("locks");
+
+    let mut options = LockOptions::new();
+    options.lease_ms = Some(300);
+    options.renew_interval_ms = Some(50);
+
+    let first = Lock::new(&coll, "job", Some(options));
+    let second = Lock::new(&coll, "job", None);
+
+    let guard = first.try_acquire().unwrap().unwrap();
+    thread::sleep(Duration::from_millis(600));
+
+    assert!(!guard.is_lost());
+    assert!(second.try_acquire().unwrap().is_none());
+}
+
+#[test]
+fn lease_timed_by_server_clock() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-lock-lease_timed_by_server_clock");
+    db.drop_database().unwrap();
+    let coll = db.collection("locks");
+
+    let mut options = LockOptions::new();
+    options.lease_ms = Some(60000);
+    options.auto_renew = Some(false);
+
+    let lock = Lock::new(&coll, "job", Some(options));
+    let guard = lock.try_acquire().unwrap().unwrap();
+
+    let reply = db.command(doc! { "isMaster": 1 }, CommandType::IsMaster, None).unwrap();
+    let server_now = match reply.get("localTime") {
+        Some(&Bson::UtcDatetime(now)) => now,
+        other => panic!("Expected the server's time, got {:?}.", other),
+    };
+
+    let lock_doc = coll.find_one(Some(doc! { "_id": "job" }), None).unwrap().unwrap();
+    let expires_at = match lock_doc.get("expires_at") {
+        Some(&Bson::UtcDatetime(expires_at)) => expires_at,
+        other => panic!("Expected an expiry date, got {:?}.", other),
+    };
+
+    let lease = (expires_at - server_now).num_milliseconds();
+    assert!(lease > 50000 && lease <= 60000, "lease of {} ms", lease);
+
+    // Released leases expire at once, so the lock can be taken again straight away.
+    guard.release().unwrap();
+    let guard = lock.try_acquire().unwrap().expect("Expected to reacquire lock.");
+    assert_eq!(2, guard.token());
+}
@@ -0,0 +1,134 @@
+use bson::Bson;
+use chrono::{Duration, Utc};
+use mongodb::{Client, Result, ThreadedClient};
+use mongodb::db::{Database, ThreadedDatabase};
+use mongodb::migrations::{Migration, MigrationRunner, MIGRATIONS_COLLECTION,
+                          MIGRATIONS_LEASE_COLLECTION};
+
+struct CreateCollection {
+    version: i64,
+    name: &'static str,
+}
+
+impl Migration for CreateCollection {
+    fn version(&self) -> i64 {
+        self.version
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn up(&self, db: &Database) -> Result<()> {
+        db.create_collection(self.name, None)
+    }
+
+    fn down(&self, db: &Database) -> Result<()> {
+        db.drop_collection(self.name)
+    }
+}
+
+fn runner(db: Database) -> MigrationRunner {
+    let mut runner = MigrationRunner::new(db);
+    runner
+        .add(CreateCollection { version: 2, name: "second" })
+        .unwrap();
+    runner
+        .add(CreateCollection { version: 1, name: "first" })
+        .unwrap();
+    runner
+}
+
+#[test]
+fn up_applies_pending_in_order() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-migrations-up_applies_pending_in_order");
+    db.drop_database().unwrap();
+
+    let runner = runner(db.clone());
+    assert_eq!(vec![1, 2], runner.pending_versions().unwrap());
+    assert_eq!(vec![1, 2], runner.up().unwrap());
+    assert!(runner.pending_versions().unwrap().is_empty());
+
+    let names = db.collection_names(None).unwrap();
+    assert!(names.contains(&"first".to_owned()));
+    assert!(names.contains(&"second".to_owned()));
+
+    let record = db.collection(MIGRATIONS_COLLECTION)
+        .find_one(Some(doc!{ "_id": 1i64 }), None)
+        .unwrap()
+        .expect("Expected migration record.");
+    assert_eq!(Some(&Bson::String("first".to_owned())), record.get("name"));
+
+    // Running again is a no-op, and the lease is released: its document is kept for the fencing
+    // token, but has no owner.
+    assert!(runner.up().unwrap().is_empty());
+    let lease = db.collection(MIGRATIONS_LEASE_COLLECTION)
+        .find_one(None, None)
+        .unwrap()
+        .expect("Expected the lease document to be kept.");
+    assert!(lease.get("owner").is_none());
+}
+
+#[test]
+fn down_reverts_latest() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-migrations-down_reverts_latest");
+    db.drop_database().unwrap();
+
+    let runner = runner(db.clone());
+    runner.up().unwrap();
+
+    assert_eq!(Some(2), runner.down().unwrap());
+    assert_eq!(vec![1], runner.applied_versions().unwrap());
+    assert!(!db.collection_names(None).unwrap().contains(&"second".to_owned()));
+
+    assert_eq!(Some(1), runner.down().unwrap());
+    assert_eq!(None, runner.down().unwrap());
+}
+
+#[test]
+fn duplicate_version_rejected() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-migrations-duplicate_version_rejected");
+
+    let mut runner = runner(db);
+    assert!(
+        runner
+            .add(CreateCollection { version: 1, name: "other" })
+            .is_err()
+    );
+}
+
+#[test]
+fn held_lease_blocks_runner() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-migrations-held_lease_blocks_runner");
+    db.drop_database().unwrap();
+
+    // Simulate another runner holding an unexpired lease.
+    db.collection(MIGRATIONS_LEASE_COLLECTION)
+        .insert_one(
+            doc! {
+                "_id": "schema_migrations",
+                "owner": "someone-else",
+                "expires_at": Utc::now() + Duration::minutes(5),
+            },
+            None,
+        )
+        .unwrap();
+
+    let runner = runner(db.clone());
+    assert!(runner.up().is_err());
+    assert!(runner.applied_versions().unwrap().is_empty());
+
+    // An expired lease can be taken over.
+    db.collection(MIGRATIONS_LEASE_COLLECTION)
+        .update_one(
+            doc!{ "_id": "schema_migrations" },
+            doc!{ "$set": { "expires_at": Utc::now() - Duration::minutes(5) } },
+            None,
+        )
+        .unwrap();
+    assert_eq!(vec![1, 2], runner.up().unwrap());
+}
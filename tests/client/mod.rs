@@ -1,19 +1,61 @@
+#[cfg(feature = "tokio-compat")]
+mod tokio_compat;
 mod batch_size;
 mod bulk;
+mod cache;
+mod capabilities;
+mod clients;
 mod coll;
+mod compat;
+mod compare;
 mod connstring;
 mod crud_spec;
 mod db;
+mod dns;
+#[cfg(feature = "decimal128")]
+mod decimal;
 mod cursor;
+#[cfg(feature = "stream")]
+mod cursor_stream;
+#[cfg(feature = "encryption")]
+mod encryption;
 mod error;
+mod ext_json;
+mod federated;
+mod filter;
+mod fsync;
 mod gridfs;
 mod handshake;
+mod health;
+mod key_order;
+mod lock;
+mod migrations;
+mod monitor;
+mod object_id;
+mod oplog;
+mod outbox;
+mod proxy;
+mod queue;
+mod raw;
+mod replica_set_health;
+mod rollup;
+mod sessions;
+mod snapshot;
+mod srv;
+mod stage;
+mod stream;
+mod timeout;
+mod trace_context;
+mod uuid;
 mod wire_protocol;
 
 use bson;
-use mongodb::{Client, ThreadedClient};
+use mongodb::{Client, ClientOptions, ThreadedClient};
+use mongodb::common::{Acknowledgment, ReadMode, ReadPreference, WriteConcern};
+use mongodb::connstring::ConnectionString;
 use mongodb::db::ThreadedDatabase;
 use std::thread;
+use std::time::Duration;
 
 #[test]
 fn is_master() {
@@ -130,3 +172,93 @@ fn is_sync() {
     assert!(results.contains(&"test-client-mod-is_sync".to_owned()));
     assert!(results.contains(&"test-client-mod-is_sync_2".to_owned()));
 }
+
+#[test]
+fn with_overridden_defaults() {
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    let client = Client::with_config(config, None, None).unwrap();
+
+    let secondary = ReadPreference::new(ReadMode::SecondaryPreferred, None);
+    let reader = client.with_read_preference(secondary.clone());
+    assert_eq!(secondary, reader.read_preference);
+    assert_eq!(client.write_concern, reader.write_concern);
+
+    let mut journaled = WriteConcern::new();
+    journaled.j = true;
+    let writer = reader.with_write_concern(journaled);
+    assert_eq!(journaled, writer.write_concern);
+    assert_eq!(secondary, writer.read_preference);
+    assert_eq!(journaled, writer.db("app").write_concern);
+
+    // The handles share the original's servers and request ids.
+    let id = client.get_req_id();
+    assert_eq!(id + 1, writer.get_req_id());
+}
+
+#[test]
+fn write_concern_helpers() {
+    assert_eq!(doc! { "w": 1, "j": false }, WriteConcern::acknowledged().to_bson());
+    assert_eq!(doc! { "w": 0, "j": false }, WriteConcern::unacknowledged().to_bson());
+    assert!(!WriteConcern::unacknowledged().is_acknowledged());
+
+    let majority = WriteConcern::majority_with_timeout(Duration::from_millis(2500));
+    assert_eq!(Acknowledgment::Majority, majority.w);
+    assert_eq!(doc! { "w": "majority", "j": false, "wtimeout": 2500 }, majority.to_bson());
+    assert!(majority.validate().is_ok());
+
+    let mut journaled = WriteConcern::unacknowledged();
+    journaled.j = true;
+    assert!(journaled.validate().is_err());
+
+    let too_long = WriteConcern::majority_with_timeout(Duration::from_secs(60 * 60 * 24 * 30));
+    assert!(too_long.validate().is_err());
+    assert_eq!(Some(&bson::Bson::I32(i32::MAX)), too_long.to_bson().get("wtimeout"));
+
+    // Timeouts are rounded up to whole milliseconds, since 0 means no timeout.
+    let short = WriteConcern::majority_with_timeout(Duration::from_micros(1500));
+    assert_eq!(Some(&bson::Bson::I32(2)), short.to_bson().get("wtimeout"));
+    let short = WriteConcern::majority_with_timeout(Duration::from_micros(10));
+    assert_eq!(Some(&bson::Bson::I32(1)), short.to_bson().get("wtimeout"));
+
+    let mut options = ClientOptions::new();
+    options.write_concern = Some(journaled);
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    assert!(Client::with_config(config, Some(options), None).is_err());
+}
+
+#[test]
+fn timeout_validation() {
+    assert!(Client::with_uri("mongodb://i-dont-exist:27017/?timeoutMS=0").is_ok());
+    assert!(Client::with_uri("mongodb://i-dont-exist:27017/?timeoutMS=-1").is_err());
+
+    let mut options = ClientOptions::new();
+    options.timeout_ms = Some(-100);
+    assert!(Client::with_uri_and_options("mongodb://i-dont-exist:27017", options).is_err());
+}
+
+#[test]
+fn local_threshold_validation() {
+    let uri = "mongodb://i-dont-exist:27017/?localThresholdMS=50";
+    assert!(Client::with_uri(uri).is_ok());
+    assert!(Client::with_uri("mongodb://i-dont-exist:27017/?localThresholdMS=-1").is_err());
+    assert!(Client::with_uri("mongodb://i-dont-exist:27017/?localThresholdMS=soon").is_err());
+
+    let mut options = ClientOptions::new();
+    options.local_threshold_ms = -5;
+    assert!(Client::with_uri_and_options(uri, options).is_err());
+
+    let mut options = ClientOptions::new();
+    options.local_threshold_ms = 0;
+    assert!(Client::with_uri_and_options("mongodb://i-dont-exist:27017", options).is_ok());
+
+    // A threshold set in the options takes precedence over the URI's.
+    let mut options = ClientOptions::new();
+    options.local_threshold_ms = 0;
+    let uri = "mongodb://i-dont-exist:27017/?localThresholdMS=-1";
+    assert!(Client::with_uri_and_options(uri, options).is_ok());
+
+    // A threshold of 15 ms cannot be told from the default, so the URI's applies instead.
+    let mut options = ClientOptions::new();
+    options.local_threshold_ms = 15;
+    assert!(Client::with_uri_and_options(uri, options).is_err());
+}
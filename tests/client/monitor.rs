@@ -0,0 +1,44 @@
+use mongodb::topology::monitor::RoundTripTimes;
+
+#[test]
+fn round_trip_time_average() {
+    let mut rtts = RoundTripTimes::new();
+    assert_eq!(None, rtts.average());
+
+    rtts.add_sample(10);
+    assert_eq!(Some(10), rtts.average());
+
+    // One slow check moves the average by a fifth of the difference.
+    rtts.add_sample(110);
+    assert_eq!(Some(30), rtts.average());
+
+    rtts.add_sample(30);
+    assert_eq!(Some(30), rtts.average());
+
+    rtts.reset();
+    assert_eq!(None, rtts.average());
+    rtts.add_sample(50);
+    assert_eq!(Some(50), rtts.average());
+}
+
+#[test]
+fn round_trip_time_minimum() {
+    let mut rtts = RoundTripTimes::new();
+    rtts.add_sample(5);
+    assert_eq!(None, rtts.minimum());
+
+    rtts.add_sample(20);
+    assert_eq!(Some(5), rtts.minimum());
+
+    // Only the last 10 samples count.
+    for _ in 0..9 {
+        rtts.add_sample(20);
+    }
+    assert_eq!(Some(20), rtts.minimum());
+
+    rtts.add_sample(8);
+    assert_eq!(Some(8), rtts.minimum());
+
+    rtts.reset();
+    assert_eq!(None, rtts.minimum());
+}
@@ -0,0 +1,46 @@
+use bson::Bson;
+use chrono::{Duration, TimeZone, Utc};
+use mongodb::object_id;
+
+#[test]
+fn generate_object_ids() {
+    let before = Utc::now().timestamp() as u32;
+    let first = object_id::generate();
+    let second = object_id::generate();
+    let after = Utc::now().timestamp() as u32;
+
+    assert!(first.timestamp() >= before && first.timestamp() <= after);
+
+    // Ids from the same process share their random value and count up from a random start.
+    // Other tests may generate ids in between.
+    assert_eq!(first.bytes()[4..9], second.bytes()[4..9]);
+    let step = second.counter().wrapping_sub(first.counter()) & 0x00ff_ffff;
+    assert!((1..1000).contains(&step));
+    assert_ne!(first, second);
+}
+
+#[test]
+fn object_id_helpers() {
+    let id = object_id::parse("5f1d7a2b3c4d5e6f70819203").unwrap();
+    assert_eq!("5f1d7a2b3c4d5e6f70819203", object_id::to_hex(&id));
+    assert_eq!(id, object_id::parse(&id.to_string()).unwrap());
+    assert!(object_id::parse("5f1d7a2b").is_err());
+    assert!(object_id::parse("zz1d7a2b3c4d5e6f70819203").is_err());
+
+    let time = Utc.timestamp_opt(0x5f1d_7a2b, 0).unwrap();
+    assert_eq!(time, object_id::generation_time(&id));
+
+    // The smallest id of a second sorts before every id generated during it.
+    let start = object_id::from_time(time);
+    assert_eq!(time, object_id::generation_time(&start));
+    assert!(start < id);
+    assert!(id < object_id::from_time(time + Duration::seconds(1)));
+
+    let end = time + Duration::days(1);
+    assert_eq!(
+        Bson::Document(doc! { "$gte": start.clone(), "$lt": object_id::from_time(end) }),
+        object_id::created_between(time, end)
+    );
+    assert_eq!(Bson::Document(doc! { "$gte": start.clone() }), object_id::created_after(time));
+    assert_eq!(Bson::Document(doc! { "$lt": start }), object_id::created_before(time));
+}
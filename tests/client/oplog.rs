@@ -0,0 +1,141 @@
+use bson::Bson;
+use mongodb::{Client, ThreadedClient};
+use mongodb::coll::options::TailOptions;
+use mongodb::db::ThreadedDatabase;
+use mongodb::db::options::CreateCollectionOptions;
+use mongodb::oplog::{OplogEntry, OplogOperation, OplogReader};
+use mongodb::topology::TopologyType;
+
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn parse_oplog_entries() {
+    let insert = OplogEntry::from_document(doc! {
+        "ts": Bson::TimeStamp(5 << 32 | 1),
+        "t": 1i64,
+        "op": "i",
+        "ns": "shop.orders",
+        "o": { "_id": 1, "total": 10 },
+    }).unwrap();
+
+    assert_eq!(5 << 32 | 1, insert.timestamp);
+    assert_eq!("shop.orders", insert.namespace);
+    assert_eq!(
+        OplogOperation::Insert { document: doc! { "_id": 1, "total": 10 } },
+        insert.operation
+    );
+
+    let update = OplogEntry::from_document(doc! {
+        "ts": Bson::TimeStamp(5 << 32 | 2),
+        "op": "u",
+        "ns": "shop.orders",
+        "o": { "$v": 1, "$set": { "total": 12 } },
+        "o2": { "_id": 1 },
+    }).unwrap();
+
+    assert_eq!(
+        OplogOperation::Update {
+            query: doc! { "_id": 1 },
+            update: doc! { "$v": 1, "$set": { "total": 12 } },
+        },
+        update.operation
+    );
+
+    let drop = OplogEntry::from_document(doc! {
+        "ts": Bson::TimeStamp(5 << 32 | 3),
+        "op": "c",
+        "ns": "shop.$cmd",
+        "o": { "drop": "orders" },
+    }).unwrap();
+
+    assert_eq!(OplogOperation::Command { command: doc! { "drop": "orders" } }, drop.operation);
+
+    // Updates must say which document they changed.
+    assert!(OplogEntry::from_document(doc! {
+        "ts": Bson::TimeStamp(5 << 32 | 4),
+        "op": "u",
+        "ns": "shop.orders",
+        "o": { "total": 1 },
+    }).is_err());
+
+    assert!(OplogEntry::from_document(doc! {
+        "ts": Bson::TimeStamp(5 << 32 | 5),
+        "op": "x",
+        "ns": "shop.orders",
+        "o": {},
+    }).is_err());
+}
+
+#[test]
+fn tail_capped_collection() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-oplog");
+    let coll = db.collection("tail");
+    coll.drop().unwrap();
+
+    let mut options = CreateCollectionOptions::new();
+    options.capped = Some(true);
+    options.size = Some(100_000);
+    db.create_collection("tail", Some(options)).unwrap();
+
+    coll.insert_one(doc! { "_id": 1 }, None).unwrap();
+
+    let mut options = TailOptions::new();
+    options.retry_interval_ms = Some(100);
+    options.idle_timeout_ms = Some(3000);
+
+    let writer = client.clone();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        let coll = writer.db("test-client-oplog").collection("tail");
+        coll.insert_one(doc! { "_id": 2 }, None).unwrap();
+        coll.insert_one(doc! { "_id": 3 }, None).unwrap();
+    });
+
+    let mut tail = coll.tail(None, Some(options.clone()));
+    let ids: Vec<_> = tail.by_ref()
+        .take(3)
+        .map(|doc| doc.unwrap().get_i32("_id").unwrap())
+        .collect();
+    handle.join().unwrap();
+
+    assert_eq!(vec![1, 2, 3], ids);
+    assert_eq!(Some(&Bson::I32(3)), tail.last_position());
+
+    // Resuming after the last document waits for new ones, until the idle timeout passes.
+    options.resume_after = Some(Bson::I32(3));
+    assert!(coll.tail(None, Some(options)).next().is_none());
+}
+
+#[test]
+fn oplog_reader() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    if client.capabilities().unwrap().topology_type != TopologyType::ReplicaSetWithPrimary {
+        return;
+    }
+
+    let db = client.db("test-client-oplog");
+    let coll = db.collection("orders");
+    coll.drop().unwrap();
+
+    coll.insert_one(doc! { "_id": 1 }, None).unwrap();
+    coll.update_one(doc! { "_id": 1 }, doc! { "$set": { "total": 5 } }, None).unwrap();
+    coll.delete_one(doc! { "_id": 1 }, None).unwrap();
+
+    let mut options = TailOptions::new();
+    options.idle_timeout_ms = Some(2000);
+
+    let filter = doc! { "ns": "test-client-oplog.orders" };
+    let mut reader = OplogReader::new(&client, Some(filter), Some(options));
+    let entries: Vec<_> = reader.by_ref().map(|entry| entry.unwrap()).collect();
+
+    let last = &entries[entries.len() - 3..];
+    assert_eq!(OplogOperation::Insert { document: doc! { "_id": 1 } }, last[0].operation);
+    match last[1].operation {
+        OplogOperation::Update { ref query, .. } => assert_eq!(&doc! { "_id": 1 }, query),
+        ref other => panic!("Expected an update, got {:?}", other),
+    }
+    assert_eq!(OplogOperation::Delete { query: doc! { "_id": 1 } }, last[2].operation);
+    assert_eq!(Some(last[2].timestamp), reader.last_timestamp());
+}
@@ -0,0 +1,118 @@
+use bson::Bson;
+use chrono::{Duration, Utc};
+use mongodb::{Client, Error, ThreadedClient};
+use mongodb::db::ThreadedDatabase;
+use mongodb::object_id;
+use mongodb::outbox::{Outbox, OutboxOptions};
+
+fn outbox(name: &str) -> Outbox {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db(name);
+    db.drop_database().unwrap();
+
+    let mut options = OutboxOptions::new();
+    options.settle_ms = Some(0);
+    Outbox::new(&db.collection("outbox"), Some(options))
+}
+
+#[test]
+fn delivers_in_order_and_checkpoints() {
+    let outbox = outbox("test-client-outbox-delivers_in_order_and_checkpoints");
+
+    let first = outbox.publish("user.created", doc!{ "n": 1 }).unwrap();
+    let second = outbox.publish("user.deleted", doc!{ "n": 2 }).unwrap();
+
+    let mut seen = Vec::new();
+    let delivered = outbox
+        .poll("mailer", |message| {
+            seen.push((message.topic.clone(), message.payload.get("n").cloned()));
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(2, delivered);
+    assert_eq!(
+        vec![
+            (String::from("user.created"), Some(Bson::I32(1))),
+            (String::from("user.deleted"), Some(Bson::I32(2))),
+        ],
+        seen
+    );
+    assert_eq!(Some(second.clone()), outbox.checkpoint("mailer").unwrap());
+
+    // Already delivered events are not delivered again, but other consumers still see them.
+    assert_eq!(0, outbox.poll("mailer", |_| Ok(())).unwrap());
+    assert_eq!(None, outbox.checkpoint("audit").unwrap());
+
+    let mut ids = Vec::new();
+    outbox
+        .poll("audit", |message| {
+            ids.push(message.id.clone());
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(vec![first, second], ids);
+}
+
+#[test]
+fn failed_delivery_is_retried() {
+    let outbox = outbox("test-client-outbox-failed_delivery_is_retried");
+
+    let first = outbox.publish("a", doc!{}).unwrap();
+    let second = outbox.publish("b", doc!{}).unwrap();
+
+    let result = outbox.poll("mailer", |message| if message.topic == "b" {
+        Err(Error::OperationError(String::from("broker unavailable")))
+    } else {
+        Ok(())
+    });
+    assert!(result.is_err());
+    assert_eq!(Some(first.clone()), outbox.checkpoint("mailer").unwrap());
+
+    let mut ids = Vec::new();
+    outbox
+        .poll("mailer", |message| {
+            ids.push(message.id.clone());
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(vec![second], ids);
+}
+
+#[test]
+fn checkpoints_follow_creation_time() {
+    let outbox = outbox("test-client-outbox-checkpoints_follow_creation_time");
+    let coll = outbox.collection();
+
+    // Another process may publish a later event with a smaller ObjectId.
+    let smaller = object_id::generate();
+    let larger = object_id::generate();
+    let now = Utc::now();
+
+    let event = doc! {
+        "_id": larger.clone(),
+        "topic": "a",
+        "payload": {},
+        "created_at": now - Duration::seconds(10),
+    };
+    coll.insert_one(event, None).unwrap();
+    assert_eq!(1, outbox.poll("mailer", |_| Ok(())).unwrap());
+
+    let event = doc! {
+        "_id": smaller.clone(),
+        "topic": "b",
+        "payload": {},
+        "created_at": now - Duration::seconds(5),
+    };
+    coll.insert_one(event, None).unwrap();
+
+    let mut ids = Vec::new();
+    outbox
+        .poll("mailer", |message| {
+            ids.push(message.id.clone());
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(vec![smaller.clone()], ids);
+    assert_eq!(Some(smaller), outbox.checkpoint("mailer").unwrap());
+}
@@ -0,0 +1,157 @@
+use mongodb::{Client, ClientOptions, ThreadedClient};
+use mongodb::dns::SystemResolver;
+use mongodb::proxy::{ProxyKind, ProxyOptions, DEFAULT_SOCKS5_PORT};
+use mongodb::stream::StreamConnector;
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// Starts a proxy that accepts one connection, runs `handshake` on it and then echoes what the
+// client sends through the tunnel.
+fn fake_proxy<F>(handshake: F) -> (u16, JoinHandle<()>)
+where
+    F: FnOnce(&mut TcpStream) -> bool + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        if handshake(&mut stream) {
+            let mut buf = [0; 4];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(&buf).unwrap();
+        }
+    });
+
+    (port, handle)
+}
+
+fn read_bytes(stream: &mut TcpStream, len: usize) -> Vec<u8> {
+    let mut buf = vec![0; len];
+    stream.read_exact(&mut buf).unwrap();
+    buf
+}
+
+fn read_http_request(stream: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    while !request.ends_with(b"\r\n\r\n") {
+        request.extend(read_bytes(stream, 1));
+    }
+    String::from_utf8(request).unwrap()
+}
+
+fn connect(proxy: &ProxyOptions) -> ::std::io::Result<()> {
+    let timeout = Some(Duration::from_secs(5));
+    let (mut stream, _) = StreamConnector::Tcp.connect_with_proxy(
+        "db.example.invalid",
+        27017,
+        timeout,
+        &SystemResolver,
+        Some(proxy),
+    )?;
+
+    stream.write_all(b"ping")?;
+    let mut echo = [0; 4];
+    stream.read_exact(&mut echo)?;
+    assert_eq!(b"ping", &echo);
+    Ok(())
+}
+
+#[test]
+fn socks5_tunnel() {
+    let (port, proxy_thread) = fake_proxy(|stream| {
+        assert_eq!(vec![0x05, 0x02, 0x00, 0x02], read_bytes(stream, 4));
+        stream.write_all(&[0x05, 0x02]).unwrap();
+
+        let auth = b"\x01\x04user\x06secret".to_vec();
+        assert_eq!(auth, read_bytes(stream, auth.len()));
+        stream.write_all(&[0x01, 0x00]).unwrap();
+
+        let mut request = b"\x05\x01\x00\x03\x12db.example.invalid".to_vec();
+        request.extend_from_slice(&27017u16.to_be_bytes());
+        assert_eq!(request, read_bytes(stream, request.len()));
+        stream.write_all(&[0x05, 0x00, 0x00, 0x01, 10, 0, 0, 1, 0x69, 0x89]).unwrap();
+        true
+    });
+
+    let proxy = ProxyOptions::socks5("127.0.0.1", port).with_credentials("user", "secret");
+    connect(&proxy).unwrap();
+    proxy_thread.join().unwrap();
+}
+
+#[test]
+fn socks5_connect_refused() {
+    let (port, proxy_thread) = fake_proxy(|stream| {
+        read_bytes(stream, 3);
+        stream.write_all(&[0x05, 0x00]).unwrap();
+        read_bytes(stream, 4 + 1 + 18 + 2);
+        stream.write_all(&[0x05, 0x05, 0x00, 0x01, 0, 0, 0, 0, 0, 0]).unwrap();
+        false
+    });
+
+    let err = connect(&ProxyOptions::socks5("127.0.0.1", port)).unwrap_err();
+    assert!(err.to_string().contains("connection refused"), "{}", err);
+    proxy_thread.join().unwrap();
+}
+
+#[test]
+fn http_connect_tunnel() {
+    let (port, proxy_thread) = fake_proxy(|stream| {
+        let request = read_http_request(stream);
+        let request_line = "CONNECT db.example.invalid:27017 HTTP/1.1\r\n";
+        assert!(request.starts_with(request_line), "{}", request);
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"), "{}", request);
+        stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").unwrap();
+        true
+    });
+
+    let proxy = ProxyOptions::http("127.0.0.1", port).with_credentials("user", "secret");
+    connect(&proxy).unwrap();
+    proxy_thread.join().unwrap();
+}
+
+#[test]
+fn http_connect_rejected() {
+    let (port, proxy_thread) = fake_proxy(|stream| {
+        read_http_request(stream);
+        stream.write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").unwrap();
+        false
+    });
+
+    let err = connect(&ProxyOptions::http("127.0.0.1", port)).unwrap_err();
+    assert!(err.to_string().contains("407 Proxy Authentication Required"), "{}", err);
+    proxy_thread.join().unwrap();
+}
+
+#[test]
+fn proxy_uri_options() {
+    let uri = "mongodb://i-dont-exist:27017/?proxyHost=bastion&proxyUsername=user&proxyPassword=pw";
+    let client = Client::with_uri(uri).unwrap();
+    let proxy = client.proxy.clone().unwrap();
+    assert_eq!(ProxyKind::Socks5, proxy.kind);
+    assert_eq!("bastion", proxy.host);
+    assert_eq!(DEFAULT_SOCKS5_PORT, proxy.port);
+    assert_eq!(Some(String::from("user")), proxy.username);
+    assert!(!format!("{:?}", proxy).contains("pw"));
+
+    let client = Client::with_uri("mongodb://i-dont-exist/?proxyHost=bastion&proxyPort=3128");
+    assert_eq!(3128, client.unwrap().proxy.clone().unwrap().port);
+
+    let invalid_uris = vec![
+        "mongodb://i-dont-exist/?proxyPort=1080",
+        "mongodb://i-dont-exist/?proxyHost=bastion&proxyPort=socks",
+        "mongodb://i-dont-exist/?proxyHost=bastion&proxyUsername=user",
+    ];
+    for uri in invalid_uris {
+        assert!(Client::with_uri(uri).is_err(), "{}", uri);
+    }
+
+    // Options given to the client take precedence over the connection string.
+    let mut options = ClientOptions::new();
+    options.proxy = Some(ProxyOptions::http("gateway", 8080));
+    let client = Client::with_uri_and_options(uri, options).unwrap();
+    assert_eq!(ProxyKind::Http, client.proxy.clone().unwrap().kind);
+}
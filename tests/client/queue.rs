@@ -0,0 +1,109 @@
+use bson::Bson;
+use mongodb::{Client, ThreadedClient};
+use mongodb::db::ThreadedDatabase;
+use mongodb::queue::{Queue, QueueOptions};
+
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn claim_and_ack() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-queue-claim_and_ack");
+    db.drop_database().unwrap();
+
+    let queue = Queue::new(&db.collection("jobs"), None);
+    queue.ensure_indexes().unwrap();
+
+    let first = queue.push(doc!{ "n": 1 }).unwrap();
+    queue.push(doc!{ "n": 2 }).unwrap();
+
+    let job = queue.claim().unwrap().expect("Expected a visible job.");
+    assert_eq!(first, job.id);
+    assert_eq!(Some(&Bson::I32(1)), job.payload.get("n"));
+    assert_eq!(1, job.attempts);
+
+    assert!(queue.ack(&job).unwrap());
+    assert!(!queue.ack(&job).unwrap());
+
+    let job = queue.claim().unwrap().expect("Expected a visible job.");
+    assert_eq!(Some(&Bson::I32(2)), job.payload.get("n"));
+
+    // The only remaining job is claimed, so nothing is visible.
+    assert!(queue.claim().unwrap().is_none());
+    assert!(queue.wait(100).unwrap().is_none());
+}
+
+#[test]
+fn lapsed_claim_redelivered() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-queue-lapsed_claim_redelivered");
+    db.drop_database().unwrap();
+
+    let mut options = QueueOptions::new();
+    options.visibility_timeout_ms = Some(100);
+    options.poll_interval_ms = Some(50);
+    let queue = Queue::new(&db.collection("jobs"), Some(options));
+
+    queue.push(doc!{ "n": 1 }).unwrap();
+    let stale = queue.claim().unwrap().unwrap();
+
+    let job = queue.wait(1000).unwrap().expect("Expected job to be redelivered.");
+    assert_eq!(stale.id, job.id);
+    assert_eq!(2, job.attempts);
+
+    // The first consumer's claim is no longer valid.
+    assert!(!queue.ack(&stale).unwrap());
+    assert!(queue.nack(&job, 0).unwrap());
+    assert!(queue.claim().unwrap().is_some());
+}
+
+#[test]
+fn exhausted_job_dead_lettered() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-queue-exhausted_job_dead_lettered");
+    db.drop_database().unwrap();
+
+    let mut options = QueueOptions::new();
+    options.max_attempts = Some(1);
+    let queue = Queue::new(&db.collection("jobs"), Some(options));
+
+    let id = queue.push(doc!{ "n": 1 }).unwrap();
+    let job = queue.claim().unwrap().unwrap();
+    assert!(queue.nack(&job, 0).unwrap());
+
+    thread::sleep(Duration::from_millis(10));
+    assert!(queue.claim().unwrap().is_none());
+
+    let dead = queue
+        .dead_letter_collection()
+        .find_one(Some(doc!{ "_id": id }), None)
+        .unwrap()
+        .expect("Expected job in dead-letter collection.");
+    assert_eq!(Some(&Bson::I32(2)), dead.get("attempts"));
+    assert_eq!(0, queue.collection().count(None, None).unwrap());
+}
+
+#[test]
+fn job_dead_lettered_twice() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-queue-job_dead_lettered_twice");
+    db.drop_database().unwrap();
+
+    let queue = Queue::new(&db.collection("jobs"), None);
+    let id = queue.push(doc!{ "n": 1 }).unwrap();
+    let job = queue.claim().unwrap().unwrap();
+
+    // A consumer that crashed after copying the job, but before deleting it, left a copy behind.
+    queue
+        .dead_letter_collection()
+        .insert_one(doc!{ "_id": id.clone(), "reason": "crashed" }, None)
+        .unwrap();
+
+    assert!(queue.dead_letter(&job, "failed").unwrap());
+    assert!(!queue.dead_letter(&job, "failed").unwrap());
+
+    assert_eq!(1, queue.dead_letter_collection().count(None, None).unwrap());
+    assert_eq!(0, queue.collection().count(None, None).unwrap());
+    assert!(queue.claim().unwrap().is_none());
+}
@@ -0,0 +1,176 @@
+use bson::{self, Bson};
+use bson::oid::ObjectId;
+use bson::spec::BinarySubtype;
+use mongodb::{Client, Error, ThreadedClient};
+use mongodb::coll::options::FindOptions;
+use mongodb::db::ThreadedDatabase;
+use mongodb::raw::{RawBson, RawDocument, RawDocumentBuf};
+use mongodb::uuid::UuidRepresentation;
+
+fn order() -> bson::Document {
+    doc! {
+        "_id": ObjectId::with_string("5f3e8a5b1c9d440000a1b2c3").unwrap(),
+        "customer": "ada",
+        "quantity": 3,
+        "total": 1250i64,
+        "price": 4.5,
+        "paid": true,
+        "note": Bson::Null,
+        "items": ["tea", { "sku": 7 }],
+        "address": { "city": "London", "zip": "N1" },
+        "payload": Bson::Binary(BinarySubtype::Generic, vec![1, 2, 3]),
+        "pattern": Bson::RegExp(String::from("^a"), String::from("i")),
+        "scoped": Bson::JavaScriptCodeWithScope(String::from("x"), doc! { "x": 1 }),
+    }
+}
+
+fn encode(document: &bson::Document) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bson::encode_document(&mut bytes, document).unwrap();
+    bytes
+}
+
+#[test]
+fn reads_fields() {
+    let bytes = encode(&order());
+    let doc = RawDocument::new(&bytes).unwrap();
+
+    assert_eq!(Some("ada"), doc.get_str("customer").unwrap());
+    assert_eq!(Some(3), doc.get_i32("quantity").unwrap());
+    assert_eq!(Some(1250), doc.get_i64("total").unwrap());
+    assert_eq!(Some(4.5), doc.get_f64("price").unwrap());
+    assert_eq!(Some(true), doc.get_bool("paid").unwrap());
+    assert_eq!(Some(RawBson::Null), doc.get("note").unwrap());
+    assert_eq!(None, doc.get("missing").unwrap());
+    assert_eq!(
+        Some(ObjectId::with_string("5f3e8a5b1c9d440000a1b2c3").unwrap()),
+        doc.get_object_id("_id").unwrap()
+    );
+
+    let address = doc.get_document("address").unwrap().unwrap();
+    assert_eq!(Some("London"), address.get_str("city").unwrap());
+
+    let items = doc.get_array("items").unwrap().unwrap();
+    assert_eq!(Some(RawBson::String("tea")), items.get(0).unwrap());
+    assert_eq!(None, items.get(2).unwrap());
+    match items.get(1).unwrap() {
+        Some(RawBson::Document(item)) => assert_eq!(Some(7), item.get_i32("sku").unwrap()),
+        other => panic!("Expected a document, got {:?}", other),
+    }
+
+    assert_eq!(
+        Some(RawBson::Binary(BinarySubtype::Generic, &[1, 2, 3][..])),
+        doc.get("payload").unwrap()
+    );
+    assert_eq!(Some(RawBson::RegExp("^a", "i")), doc.get("pattern").unwrap());
+    match doc.get("scoped").unwrap() {
+        Some(RawBson::JavaScriptCodeWithScope(code, scope)) => {
+            assert_eq!("x", code);
+            assert_eq!(doc! { "x": 1 }, scope.to_document().unwrap());
+        }
+        other => panic!("Expected code with scope, got {:?}", other),
+    }
+}
+
+#[test]
+fn iterates_in_order() {
+    let bytes = encode(&order());
+    let doc = RawDocument::new(&bytes).unwrap();
+
+    let keys: Vec<_> = doc.iter().map(|field| field.unwrap().0).collect();
+    let expected: Vec<_> = order().keys().cloned().collect();
+    assert_eq!(expected, keys);
+}
+
+#[test]
+fn converts_to_document() {
+    let buf = RawDocumentBuf::from_document(&order()).unwrap();
+    assert_eq!(order(), buf.to_document().unwrap());
+
+    let values = buf.as_raw().get_array("items").unwrap().unwrap().to_vec().unwrap();
+    assert_eq!(vec![Bson::from("tea"), Bson::from(doc! { "sku": 7 })], values);
+
+    let owned = buf.as_raw().get_document("address").unwrap().unwrap().to_raw_document_buf();
+    assert_eq!(doc! { "city": "London", "zip": "N1" }, owned.to_document().unwrap());
+}
+
+#[test]
+fn rejects_wrong_type() {
+    let bytes = encode(&order());
+    let doc = RawDocument::new(&bytes).unwrap();
+
+    match doc.get_i64("quantity") {
+        Err(Error::DecoderError(_)) => (),
+        other => panic!("Expected a decoder error, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_malformed_documents() {
+    let mut bytes = encode(&doc! { "a": "text", "b": 1 });
+
+    // The length prefix must match the bytes.
+    assert!(RawDocument::new(&bytes[..bytes.len() - 1]).is_err());
+    assert!(RawDocumentBuf::new(vec![5, 0, 0, 0]).is_err());
+
+    // A string whose length runs past the document is found when it is read.
+    bytes[7] = 100;
+    let doc = RawDocument::new(&bytes).unwrap();
+    assert!(doc.get("b").is_err());
+
+    let fields: Vec<_> = doc.iter().collect();
+    assert_eq!(1, fields.len());
+    assert!(fields[0].is_err());
+}
+
+#[test]
+fn find_raw() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-raw");
+    let coll = db.collection("find_raw");
+
+    coll.drop().expect("Failed to drop collection");
+    let docs = (0..250).map(|i| doc! { "n": i, "name": format!("doc {}", i) }).collect();
+    coll.insert_many(docs, None).expect("Failed to insert documents.");
+
+    let mut opts = FindOptions::new();
+    opts.sort = Some(doc! { "n": 1 });
+    opts.batch_size = Some(100);
+
+    // Reads past the first batch.
+    let cursor = coll.find_raw(None, Some(opts)).expect("Failed to execute find.");
+    let mut count = 0;
+    for (i, doc) in cursor.enumerate() {
+        let doc = doc.expect("Failed to read document.");
+        assert_eq!(Some(i as i32), doc.as_raw().get_i32("n").unwrap());
+        count += 1;
+    }
+    assert_eq!(250, count);
+
+    // Options that need the `find` command are read from its reply.
+    let mut opts = FindOptions::new();
+    opts.allow_disk_use = Some(true);
+    opts.limit = Some(5);
+
+    let filter = doc! { "n": { "$gte": 100 } };
+    let docs: Vec<_> = coll.find_raw(Some(filter), Some(opts))
+        .expect("Failed to execute find.")
+        .map(|doc| doc.unwrap())
+        .collect();
+    assert_eq!(5, docs.len());
+    assert!(docs.iter().all(|doc| doc.as_raw().get_i32("n").unwrap() >= Some(100)));
+}
+
+#[test]
+fn find_raw_needs_undecoded_documents() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client
+        .db("test-client-raw")
+        .with_uuid_representation(Some(UuidRepresentation::JavaLegacy));
+    let coll = db.collection("find_raw_needs_undecoded_documents");
+
+    match coll.find_raw(None, None) {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,121 @@
+use chrono::{Duration, Utc};
+use mongodb::{Client, ThreadedClient};
+use mongodb::common::{ReadMode, ReadPreference, TagSet};
+use mongodb::connstring::{self, ConnectionString, Host};
+use mongodb::stream::StreamConnector;
+use mongodb::topology::TopologyDescription;
+use mongodb::topology::server::{Server, ServerType};
+
+use std::sync::{Arc, RwLock};
+
+fn host(port: u16) -> Host {
+    connstring::parse_host(&format!("localhost:{}", port)).unwrap()
+}
+
+// Describes a replica set member as monitoring would, with its last write trailing its last
+// check by `behind_ms`.
+fn add_member(
+    topology: &mut TopologyDescription,
+    client: &Client,
+    port: u16,
+    server_type: ServerType,
+    behind_ms: i64,
+    dc: &str,
+) {
+    let host = host(port);
+    let server = Server::new(
+        client.clone(),
+        host.clone(),
+        Arc::new(RwLock::new(TopologyDescription::new(StreamConnector::default()))),
+        false,
+        StreamConnector::default(),
+    );
+
+    {
+        let now = Utc::now();
+        let mut description = server.description.write().unwrap();
+        description.server_type = server_type;
+        description.hosts = (27017..27021).map(self::host).collect();
+        description.tags.insert(String::from("dc"), String::from(dc));
+        description.last_update_time = Some(now);
+        description.last_write_date = Some(now - Duration::milliseconds(behind_ms));
+    }
+
+    topology.servers.insert(host, server);
+}
+
+fn read_preference(dc: &str) -> ReadPreference {
+    let tags = TagSet::new().insert("dc", dc);
+    ReadPreference::new(ReadMode::Secondary, Some(vec![tags]))
+}
+
+#[test]
+fn replica_set_health() {
+    let client = Client::with_config(ConnectionString::new("i-dont-exist", 27017), None, None)
+        .unwrap();
+    let mut topology = TopologyDescription::new(StreamConnector::default());
+
+    add_member(&mut topology, &client, 27017, ServerType::RSPrimary, 100, "east");
+    add_member(&mut topology, &client, 27018, ServerType::RSSecondary, 600, "east");
+    add_member(&mut topology, &client, 27019, ServerType::RSSecondary, 2100, "west");
+    add_member(&mut topology, &client, 27020, ServerType::Unknown, 0, "west");
+
+    let health = topology.replica_set_health(None);
+    assert_eq!(Some(host(27017)), health.primary);
+    assert_eq!(2, health.healthy_secondaries);
+    assert_eq!(2, health.eligible_secondaries);
+    assert_eq!(4, health.data_bearing_members);
+    assert_eq!(Some(2000), health.max_replication_lag_ms);
+    assert!(health.majority_satisfiable());
+
+    let health = topology.replica_set_health(Some(&read_preference("west")));
+    assert_eq!(2, health.healthy_secondaries);
+    assert_eq!(1, health.eligible_secondaries);
+
+    let health = topology.replica_set_health(Some(&read_preference("north")));
+    assert_eq!(0, health.eligible_secondaries);
+}
+
+#[test]
+fn replica_set_health_without_primary() {
+    let client = Client::with_config(ConnectionString::new("i-dont-exist", 27017), None, None)
+        .unwrap();
+    let mut topology = TopologyDescription::new(StreamConnector::default());
+
+    add_member(&mut topology, &client, 27018, ServerType::RSSecondary, 600, "east");
+    add_member(&mut topology, &client, 27019, ServerType::RSSecondary, 2100, "west");
+
+    let health = topology.replica_set_health(None);
+    assert_eq!(None, health.primary);
+    assert_eq!(2, health.healthy_secondaries);
+    // Without a primary, lag is measured against the most recent secondary write.
+    let lag = health.max_replication_lag_ms.unwrap();
+    assert!(lag > 1400 && lag <= 1500);
+    assert!(!health.majority_satisfiable());
+}
+
+#[test]
+fn replica_set_health_with_clock_skew() {
+    let client = Client::with_config(ConnectionString::new("i-dont-exist", 27017), None, None)
+        .unwrap();
+    let mut topology = TopologyDescription::new(StreamConnector::default());
+
+    add_member(&mut topology, &client, 27017, ServerType::RSPrimary, 100, "east");
+    add_member(&mut topology, &client, 27018, ServerType::RSSecondary, 600, "east");
+
+    // The secondary's clock runs ten seconds ahead, which shows in the times it reports but not
+    // in how far its last write trails them.
+    {
+        let server = &topology.servers[&host(27018)];
+        let mut description = server.description.write().unwrap();
+        let now = description.last_update_time.unwrap();
+        let local_time = now + Duration::seconds(10);
+        description.local_time = Some(local_time);
+        description.last_write_date = Some(local_time - Duration::milliseconds(600));
+        description.set_update_time(now + Duration::milliseconds(20), 40);
+        assert_eq!(Some(10000), description.clock_skew_ms);
+    }
+
+    let health = topology.replica_set_health(None);
+    assert_eq!(Some(500), health.max_replication_lag_ms);
+}
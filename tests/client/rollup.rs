@@ -0,0 +1,114 @@
+use bson::Bson;
+use chrono::{TimeZone, Utc};
+use mongodb::{Client, Error, ThreadedClient};
+use mongodb::db::ThreadedDatabase;
+use mongodb::rollup::{Accumulator, RollupSpec, RollupUnit};
+
+#[test]
+fn rollup_pipeline() {
+    let mut spec = RollupSpec::new("ts", RollupUnit::Minute);
+    spec.bin_size = Some(15);
+    spec.timezone = Some(String::from("Europe/Paris"));
+    spec.filter = Some(doc! { "metadata.site": "lyon" });
+    spec.group_by.push((String::from("sensor"), String::from("metadata.sensor")));
+    spec.add("avg_temp", Accumulator::Avg, "temp")
+        .add("readings", Accumulator::Count, "temp");
+
+    let pipeline = spec.pipeline().unwrap();
+    assert_eq!(
+        vec![
+            doc! { "$match": { "metadata.site": "lyon" } },
+            doc! {
+                "$group": {
+                    "_id": {
+                        "time": {
+                            "$dateTrunc": {
+                                "date": "$ts",
+                                "unit": "minute",
+                                "binSize": 15i64,
+                                "timezone": "Europe/Paris",
+                            },
+                        },
+                        "keys": { "sensor": "$metadata.sensor" },
+                    },
+                    "avg_temp": { "$avg": "$temp" },
+                    "readings": { "$sum": 1 },
+                },
+            },
+            doc! { "$sort": { "_id.time": 1 } },
+            doc! {
+                "$project": {
+                    "_id": 0,
+                    "ts": "$_id.time",
+                    "sensor": "$_id.keys.sensor",
+                    "avg_temp": 1,
+                    "readings": 1,
+                },
+            },
+        ],
+        pipeline
+    );
+
+    // Order-dependent accumulators see the measurements sorted by time.
+    let mut spec = RollupSpec::new("ts", RollupUnit::Day);
+    spec.add("closing", Accumulator::Last, "price");
+    assert_eq!(doc! { "$sort": { "ts": 1 } }, spec.pipeline().unwrap()[0]);
+}
+
+#[test]
+fn invalid_rollups() {
+    let mut spec = RollupSpec::new("ts", RollupUnit::Hour);
+    match spec.pipeline() {
+        Err(Error::ArgumentError(_)) => (),
+        other => panic!("Expected an argument error, got {:?}.", other),
+    }
+
+    spec.add("avg", Accumulator::Avg, "value");
+    spec.bin_size = Some(0);
+    assert!(spec.pipeline().is_err());
+
+    spec.bin_size = None;
+    spec.add("ts", Accumulator::Max, "value");
+    assert!(spec.pipeline().is_err());
+
+    let mut spec = RollupSpec::new("ts", RollupUnit::Hour);
+    spec.add("stats.avg", Accumulator::Avg, "value");
+    assert!(spec.pipeline().is_err());
+}
+
+#[test]
+fn rollup() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-rollup");
+    skip_if_db_version_below!(db, 5, 0);
+
+    let coll = db.collection("rollup");
+    coll.drop().unwrap();
+
+    let hour = |h: u32, m: u32| {
+        Bson::UtcDatetime(Utc.with_ymd_and_hms(2021, 6, 1, h, m, 0).unwrap())
+    };
+    coll.insert_many(
+        vec![
+            doc! { "ts": hour(9, 0), "sensor": "a", "temp": 20.0 },
+            doc! { "ts": hour(9, 30), "sensor": "a", "temp": 22.0 },
+            doc! { "ts": hour(9, 45), "sensor": "b", "temp": 30.0 },
+            doc! { "ts": hour(10, 15), "sensor": "a", "temp": 25.0 },
+        ],
+        None,
+    ).unwrap();
+
+    let mut spec = RollupSpec::new("ts", RollupUnit::Hour);
+    spec.filter = Some(doc! { "sensor": "a" });
+    spec.add("avg_temp", Accumulator::Avg, "temp")
+        .add("first_temp", Accumulator::First, "temp");
+
+    let results: Vec<_> = coll.rollup(&spec, None).unwrap().map(|doc| doc.unwrap()).collect();
+    assert_eq!(
+        vec![
+            doc! { "ts": hour(9, 0), "avg_temp": 21.0, "first_temp": 20.0 },
+            doc! { "ts": hour(10, 0), "avg_temp": 25.0, "first_temp": 25.0 },
+        ],
+        results
+    );
+}
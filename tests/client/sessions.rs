@@ -0,0 +1,133 @@
+use bson::{self, Bson};
+use chrono::Utc;
+use mongodb::{Client, ThreadedClient};
+use mongodb::common::{ReadConcern, ReadConcernLevel, ReadMode, ReadPreference};
+use mongodb::connstring;
+use mongodb::db::ThreadedDatabase;
+use mongodb::sessions::{ListSessionsOptions, Session, SessionOptions, SessionRecord};
+
+#[test]
+fn list_local_sessions() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("admin");
+    skip_if_db_version_below!(db, 3, 6);
+
+    let reply: bson::Document = db.run_command_as(doc! { "startSession": 1 }).unwrap();
+    let lsid = match reply.get("id") {
+        Some(&Bson::Document(ref id)) => id.clone(),
+        other => panic!("Expected a session id, got {:?}", other),
+    };
+
+    let mut options = ListSessionsOptions::new();
+    options.all_users = Some(true);
+
+    let sessions = client.list_local_sessions(Some(options)).unwrap();
+    let session = sessions.iter().find(|session| session.lsid == lsid).unwrap();
+    assert!(session.last_use.is_some());
+
+    db.run_command_as::<bson::Document>(doc! { "endSessions": [lsid] }).unwrap();
+}
+
+#[test]
+fn session_record_from_document() {
+    let doc = doc! {
+        "_id": {
+            "id": "0f7b3c2a",
+            "uid": "e3b0c442",
+        },
+        "user": { "name": "auditor@admin" },
+        "lastUse": Bson::UtcDatetime(Utc::now()),
+    };
+
+    let record = SessionRecord::from_document(&doc).unwrap();
+    assert_eq!(doc! { "id": "0f7b3c2a" }, record.lsid);
+    assert_eq!(Some(String::from("auditor@admin")), record.user);
+    assert!(record.last_use.is_some());
+
+    assert!(SessionRecord::from_document(&doc! { "lastUse": 1 }).is_err());
+
+    let mut options = ListSessionsOptions::new();
+    assert_eq!(doc! {}, bson::Document::from(options.clone()));
+
+    options.users = Some(vec![(String::from("auditor"), String::from("admin"))]);
+    assert_eq!(
+        doc! { "users": [{ "user": "auditor", "db": "admin" }] },
+        bson::Document::from(options.clone())
+    );
+
+    options.all_users = Some(true);
+    assert_eq!(doc! { "allUsers": true }, bson::Document::from(options));
+}
+
+#[test]
+fn causal_read_concern() {
+    let session = Session::new(SessionOptions::new());
+    assert!(session.is_causally_consistent());
+    assert_eq!(None, session.read_concern(None));
+
+    // Operation times only move forward.
+    let time = (1_600_000_000 << 32) + 2;
+    session.advance_operation_time(time);
+    session.advance_operation_time(time - 1);
+    assert_eq!(Some(time), session.operation_time());
+
+    let read_concern = session.read_concern(None).unwrap();
+    assert_eq!(ReadConcernLevel::Local, read_concern.level);
+    assert_eq!(
+        doc! { "level": "local", "afterClusterTime": Bson::TimeStamp(time) },
+        read_concern.to_bson()
+    );
+
+    let majority = session.read_concern(Some(ReadConcern::new(ReadConcernLevel::Majority)));
+    assert_eq!(ReadConcernLevel::Majority, majority.unwrap().level);
+    assert_eq!(Some(time), majority.unwrap().after_cluster_time);
+
+    let mut snapshot = ReadConcern::new(ReadConcernLevel::Snapshot);
+    snapshot.at_cluster_time = Some(time - 10);
+    assert_eq!(Some(snapshot), session.read_concern(Some(snapshot)));
+
+    let mut options = SessionOptions::new();
+    options.causal_consistency = Some(false);
+    let session = Session::new(options);
+    session.advance_operation_time(time);
+    assert_eq!(None, session.after_cluster_time());
+    assert_eq!(None, session.read_concern(None));
+}
+
+#[test]
+fn start_session() {
+    let config = connstring::parse("mongodb://localhost/").unwrap();
+    let client = Client::with_config(config, None, None).unwrap();
+    assert!(client.session.is_none());
+
+    let session = client.start_session(None).unwrap();
+    assert!(session.session.is_some());
+
+    // Handles derived from the session's client stay in the session.
+    let secondary = ReadPreference::new(ReadMode::Secondary, None);
+    let reader = session.with_read_preference(secondary);
+    assert!(reader.session.is_some());
+    assert!(reader.db("app").client.session.is_some());
+}
+
+#[test]
+fn causal_consistency() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-sessions");
+    skip_if_db_version_below!(db, 3, 6);
+
+    let session = client.start_session(None).unwrap();
+    let coll = session.db("test-sessions").collection("causal_consistency");
+    coll.drop().unwrap();
+
+    coll.insert_one(doc! { "_id": 1, "theme": "dark" }, None).unwrap();
+
+    let secondary = ReadPreference::new(ReadMode::SecondaryPreferred, None);
+    let reader = session.with_read_preference(secondary);
+    let found = reader
+        .db("test-sessions")
+        .collection("causal_consistency")
+        .find_one(Some(doc! { "_id": 1 }), None)
+        .unwrap();
+    assert_eq!(Some(doc! { "_id": 1, "theme": "dark" }), found);
+}
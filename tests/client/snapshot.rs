@@ -0,0 +1,73 @@
+use bson::{self, Bson};
+use mongodb::{Client, ThreadedClient};
+use mongodb::coll::options::FindOptions;
+use mongodb::common::{ReadConcern, ReadConcernLevel};
+use mongodb::db::ThreadedDatabase;
+use mongodb::topology::TopologyType;
+
+#[test]
+fn snapshot_read_concern() {
+    let mut read_concern = ReadConcern::new(ReadConcernLevel::Snapshot);
+    assert_eq!(doc! { "level": "snapshot" }, read_concern.to_bson());
+
+    read_concern.at_cluster_time = Some(7 << 32 | 1);
+    assert_eq!(
+        doc! { "level": "snapshot", "atClusterTime": Bson::TimeStamp(7 << 32 | 1) },
+        read_concern.to_bson()
+    );
+
+    let options = FindOptions {
+        read_concern: Some(read_concern),
+        ..FindOptions::new()
+    };
+    assert!(options.requires_find_command());
+    let document = bson::Document::from(options);
+    assert_eq!(Some(&Bson::Document(read_concern.to_bson())), document.get("readConcern"));
+
+    let client = Client::connect("i-dont-exist", 27017).unwrap();
+    client
+        .with_snapshot(|snapshot| {
+            assert_eq!(None, snapshot.at_cluster_time());
+            assert_eq!(ReadConcern::new(ReadConcernLevel::Snapshot), snapshot.read_concern());
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn with_snapshot() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let db = client.db("test-client-snapshot");
+    skip_if_db_version_below!(db, 5, 0);
+    if client.capabilities().unwrap().topology_type == TopologyType::Single {
+        return;
+    }
+
+    let orders = db.collection("orders");
+    let items = db.collection("items");
+    orders.drop().unwrap();
+    items.drop().unwrap();
+
+    orders.insert_one(doc! { "_id": 1 }, None).unwrap();
+    items.insert_one(doc! { "order": 1 }, None).unwrap();
+
+    let (order_count, item_count) = client
+        .with_snapshot(|snapshot| {
+            let order_count = snapshot
+                .collection("test-client-snapshot", "orders")
+                .count_documents(doc! {})?;
+            assert!(snapshot.at_cluster_time().is_some());
+
+            // Writes made after the snapshot's time are not visible within it.
+            items.insert_one(doc! { "order": 2 }, None).unwrap();
+
+            let items = snapshot.collection("test-client-snapshot", "items");
+            let item_count = items.count_documents(doc! {})?;
+            assert!(items.find_one(Some(doc! { "order": 2 }), None)?.is_none());
+            Ok((order_count, item_count))
+        })
+        .unwrap();
+
+    assert_eq!(1, order_count);
+    assert_eq!(1, item_count);
+}
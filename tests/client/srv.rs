@@ -0,0 +1,139 @@
+use mongodb::{Client, ClientOptions, Error, ThreadedClient};
+use mongodb::clock::MockClock;
+use mongodb::connstring::{self, Host};
+use mongodb::dns::{DnsResolver, Lookup};
+use mongodb::srv;
+
+use std::io;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+const URI: &str = "mongodb+srv://cluster0.example.invalid/?rescanSrvIntervalMS=10";
+
+fn host(name: &str) -> Host {
+    connstring::parse_host(name).unwrap()
+}
+
+// Answers SRV queries for the test cluster, and no others.
+struct SrvRecords;
+
+impl DnsResolver for SrvRecords {
+    fn lookup_ip(&self, hostname: &str) -> io::Result<Lookup<IpAddr>> {
+        Err(io::Error::new(io::ErrorKind::NotFound, hostname))
+    }
+
+    fn lookup_srv(&self, name: &str) -> io::Result<Lookup<Host>> {
+        assert_eq!("_mongodb._tcp.cluster0.example.invalid", name);
+        Ok(Lookup::new(vec![Host::new(String::from("shard-00.example.invalid."), 27017)], None))
+    }
+}
+
+// Returns the hosts of the client's topology. Server selection is avoided, as it would wait on,
+// and so move, the client's mock clock.
+fn topology_hosts(client: &Client) -> Vec<String> {
+    let snapshot = client.topology_snapshot().unwrap();
+    snapshot.servers.iter().map(|server| server.host.to_string()).collect()
+}
+
+#[test]
+fn resolve_hosts() {
+    let resolver = |name: &str| {
+        assert_eq!("_mongodb._tcp.cluster0.example.com", name);
+        Ok(vec![
+            host("Shard-00.example.com.:27017"),
+            host("shard-01.example.com:27018"),
+            host("shard-00.example.com:27017"),
+        ])
+    };
+
+    let hosts = srv::resolve_hosts(&resolver, "cluster0.example.com").unwrap();
+    assert_eq!(vec![host("shard-00.example.com:27017"), host("shard-01.example.com:27018")], hosts);
+
+    let outside = |_: &str| Ok(vec![host("shard-00.example.com"), host("shard-00.evil.com")]);
+    assert!(srv::resolve_hosts(&outside, "cluster0.example.com").is_err());
+
+    let empty = |_: &str| Ok(Vec::new());
+    assert!(srv::resolve_hosts(&empty, "cluster0.example.com").is_err());
+}
+
+#[test]
+fn srv_lookup_through_dns_resolver() {
+    // The system resolver cannot look up SRV records.
+    match Client::with_uri(URI) {
+        Err(Error::IoError(ref err)) if err.kind() == io::ErrorKind::Unsupported => (),
+        other => panic!("Expected an unsupported lookup, got {:?}", other),
+    }
+
+    let mut options = ClientOptions::new();
+    options.clock = Some(Arc::new(MockClock::new()));
+    options.dns_resolver = Some(Arc::new(SrvRecords));
+    let client = Client::with_uri_and_options(URI, options).unwrap();
+    assert_eq!(vec!["shard-00.example.invalid:27017"], topology_hosts(&client));
+
+    let mut options = ClientOptions::new();
+    options.srv_resolver = Some(Arc::new(|_: &str| Ok(vec![host("a.example.invalid")])));
+    let uri = "mongodb+srv://cluster0.example.invalid/?rescanSrvIntervalMS=0";
+    assert!(Client::with_uri_and_options(uri, options).is_err());
+}
+
+#[test]
+fn srv_polling_follows_records() {
+    let records = Arc::new(Mutex::new(vec![
+        host("shard-00.example.invalid"),
+        host("shard-01.example.invalid"),
+    ]));
+    let lookups = Arc::new(AtomicUsize::new(0));
+
+    let clock = Arc::new(MockClock::new());
+    let mut options = ClientOptions::new();
+    options.clock = Some(clock.clone());
+    let (resolver_records, resolver_lookups) = (records.clone(), lookups.clone());
+    options.srv_resolver = Some(Arc::new(move |_: &str| {
+        resolver_lookups.fetch_add(1, Ordering::SeqCst);
+        Ok(resolver_records.lock().unwrap().clone())
+    }));
+
+    let client = Client::with_uri_and_options(URI, options).unwrap();
+    assert_eq!(
+        vec!["shard-00.example.invalid:27017", "shard-01.example.invalid:27017"],
+        topology_hosts(&client)
+    );
+
+    *records.lock().unwrap() = vec![
+        host("shard-01.example.invalid"),
+        host("shard-02.example.invalid:27018"),
+    ];
+
+    // Polling waits on the client's clock, which nothing has moved yet.
+    let seen = lookups.load(Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(seen, lookups.load(Ordering::SeqCst));
+    assert_eq!(
+        vec!["shard-00.example.invalid:27017", "shard-01.example.invalid:27017"],
+        topology_hosts(&client)
+    );
+
+    let expected = vec!["shard-01.example.invalid:27017", "shard-02.example.invalid:27018"];
+    let mut hosts = topology_hosts(&client);
+    for _ in 0..200 {
+        if hosts == expected {
+            break;
+        }
+        clock.advance(Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(25));
+        hosts = topology_hosts(&client);
+    }
+    assert_eq!(expected, hosts);
+
+    // A lookup that finds a host outside the domain leaves the hosts as they were.
+    records.lock().unwrap().push(host("shard-03.evil.invalid"));
+    let seen = lookups.load(Ordering::SeqCst);
+    while lookups.load(Ordering::SeqCst) < seen + 2 {
+        clock.advance(Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(expected, topology_hosts(&client));
+}
@@ -0,0 +1,37 @@
+use mongodb::coll::filter;
+use mongodb::coll::stage::{self, Pipeline};
+
+#[test]
+fn stages() {
+    let pipeline = stage::project(doc! { "tags": 1, "author": 1 })
+        .then(stage::unwind("tags"))
+        .then(stage::lookup("authors", "author", "_id", "authors"))
+        .then(stage::skip(5))
+        .then(stage::merge("tag_counts"));
+
+    assert_eq!(
+        vec![
+            doc! { "$project": { "tags": 1, "author": 1 } },
+            doc! { "$unwind": "$tags" },
+            doc! {
+                "$lookup": {
+                    "from": "authors",
+                    "localField": "author",
+                    "foreignField": "_id",
+                    "as": "authors",
+                }
+            },
+            doc! { "$skip": 5i64 },
+            doc! { "$merge": { "into": "tag_counts" } },
+        ],
+        pipeline.into_documents()
+    );
+
+    let pipeline: Pipeline = vec![stage::match_(filter::eq("a", 1)), stage::out("out")]
+        .into_iter()
+        .collect();
+    assert_eq!(
+        vec![doc! { "$match": { "a": 1 } }, doc! { "$out": "out" }],
+        pipeline.into_documents()
+    );
+}
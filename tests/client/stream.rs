@@ -0,0 +1,33 @@
+use mongodb::stream::{Stream, StreamConnector};
+use std::io::{IoSlice, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+#[test]
+fn tcp_stream_writes() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (mut socket, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        socket.read_to_end(&mut received).unwrap();
+        received
+    });
+
+    let mut stream = StreamConnector::Tcp.connect("127.0.0.1", port).unwrap();
+    match stream {
+        Stream::Tcp { ref write_half, .. } => assert!(write_half.nodelay().unwrap()),
+        #[cfg(feature = "ssl")]
+        Stream::Ssl(_) => panic!("Expected a TCP stream"),
+    }
+
+    // Vectored writes reach the socket in order.
+    let slices = [IoSlice::new(b"header"), IoSlice::new(b""), IoSlice::new(b"body")];
+    let written = stream.write_vectored(&slices).unwrap();
+    assert!(written > 0);
+    stream.write_all(&b"headerbody"[written..]).unwrap();
+    stream.flush().unwrap();
+    drop(stream);
+
+    assert_eq!(b"headerbody".to_vec(), server.join().unwrap());
+}
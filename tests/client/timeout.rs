@@ -0,0 +1,170 @@
+use mongodb::{Client, ClientOptions, CommandType, Error, ThreadedClient};
+use mongodb::clock::{Clock, MockClock};
+use mongodb::common::{ReadMode, ReadPreference};
+use mongodb::coll::options::{BulkWriteOptions, CountOptions, DeleteOptions, FindOptions,
+                              InsertManyOptions, UpdateOptions, WriteModel};
+use mongodb::connstring::ConnectionString;
+use mongodb::db::ThreadedDatabase;
+use mongodb::topology::server::ServerType;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn populate(client: &Client, name: &str) {
+    let coll = client.db("test-client-timeout").collection(name);
+    coll.drop().expect("Failed to drop collection");
+    coll.insert_many(vec![doc! { "x": 1 }, doc! { "x": 2 }], None)
+        .expect("Failed to insert documents.");
+}
+
+#[test]
+fn client_timeout_bounds_operation() {
+    let mut options = ClientOptions::new();
+    options.timeout_ms = Some(100);
+
+    let client = Client::connect_with_options("localhost", 27017, options).unwrap();
+    populate(&client, "client_timeout_bounds_operation");
+
+    let coll = client.db("test-client-timeout").collection("client_timeout_bounds_operation");
+    let slow = doc! { "$where": "sleep(500) || true" };
+
+    match coll.find_one(Some(slow.clone()), None) {
+        Err(Error::Timeout(_)) => (),
+        other => panic!("Expected a timeout, got {:?}", other),
+    }
+
+    // The connection that timed out is discarded rather than reused mid-reply.
+    assert_eq!(2, coll.count(None, None).unwrap());
+
+    // A per-operation timeout of zero lifts the client's limit.
+    let mut count_options = CountOptions::new();
+    count_options.timeout_ms = Some(0);
+    assert_eq!(2, coll.count(Some(slow), Some(count_options)).unwrap());
+}
+
+#[test]
+fn operation_timeout_bounds_find() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    populate(&client, "operation_timeout_bounds_find");
+
+    let coll = client.db("test-client-timeout").collection("operation_timeout_bounds_find");
+    let slow = doc! { "$where": "sleep(500) || true" };
+
+    let mut find_options = FindOptions::new();
+    find_options.timeout_ms = Some(100);
+
+    match coll.find(Some(slow), Some(find_options)) {
+        Err(Error::Timeout(_)) => (),
+        other => panic!("Expected a timeout, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn operation_timeout_bounds_writes() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    populate(&client, "operation_timeout_bounds_writes");
+
+    let coll = client.db("test-client-timeout").collection("operation_timeout_bounds_writes");
+    let slow = doc! { "$where": "sleep(500) || true" };
+
+    let mut update_options = UpdateOptions::new();
+    update_options.timeout_ms = Some(100);
+    match coll.update_many(slow.clone(), doc! { "$set": { "y": 1 } }, update_options) {
+        Err(Error::Timeout(_)) => (),
+        other => panic!("Expected a timeout, got {:?}", other),
+    }
+
+    let mut delete_options = DeleteOptions::new();
+    delete_options.timeout_ms = Some(100);
+    match coll.delete_many_with_options(slow, delete_options) {
+        Err(Error::Timeout(_)) => (),
+        other => panic!("Expected a timeout, got {:?}", other),
+    }
+}
+
+#[test]
+fn write_timeout_with_mock_clock() {
+    let clock = Arc::new(MockClock::new());
+    let mut options = ClientOptions::new();
+    options.clock = Some(clock.clone());
+
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    let client = Client::with_config(config, Some(options), None).unwrap();
+    let coll = client.db("test-client-timeout").collection("write_timeout_with_mock_clock");
+
+    // Server selection gives up at the write's timeout rather than after 30 s.
+    let start = clock.now();
+    let mut insert_options = InsertManyOptions::new();
+    insert_options.timeout_ms = Some(100);
+    match coll.insert_many(vec![doc! { "x": 1 }], Some(insert_options)) {
+        Err(Error::Timeout(_)) => (),
+        other => panic!("Expected a timeout, got {:?}", other),
+    }
+    assert!(clock.now() - start < Duration::from_secs(30));
+
+    let start = clock.now();
+    let mut bulk_options = BulkWriteOptions::new();
+    bulk_options.timeout_ms = Some(100);
+    let requests = vec![
+        WriteModel::InsertOne { document: doc! { "x": 1 } },
+        WriteModel::DeleteOne { filter: doc! { "x": 1 } },
+        WriteModel::InsertOne { document: doc! { "x": 2 } },
+    ];
+    let result = coll.bulk_write_with_options(requests, Some(bulk_options));
+    let exception = result.bulk_write_exception.expect("Expected unprocessed writes.");
+    assert!(!exception.unprocessed_requests.is_empty());
+    assert!(clock.now() - start < Duration::from_secs(30));
+}
+
+#[test]
+fn server_selection_timeout_with_mock_clock() {
+    let clock = Arc::new(MockClock::new());
+    let mut options = ClientOptions::new();
+    options.clock = Some(clock.clone());
+
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    let client = Client::with_config(config, Some(options), None).unwrap();
+
+    let start = clock.now();
+    let real_start = Instant::now();
+    let result = client.db("test-client-timeout").command(
+        doc! { "ping": 1 },
+        CommandType::Suppressed,
+        None,
+    );
+
+    // Server selection gives up after 30 s of the mock clock's time, not of real time.
+    assert!(result.is_err());
+    assert!(clock.now() - start >= Duration::from_secs(30));
+    assert!(real_start.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn server_selection_error_describes_topology() {
+    let clock = Arc::new(MockClock::new());
+    let mut options = ClientOptions::new();
+    options.clock = Some(clock.clone());
+
+    let config = ConnectionString::new("i-dont-exist", 27017);
+    let client = Client::with_config(config, Some(options), None).unwrap();
+    let read_preference = ReadPreference::new(ReadMode::Secondary, None);
+
+    let result = client.db("test-client-timeout").command(
+        doc! { "ping": 1 },
+        CommandType::Suppressed,
+        Some(read_preference.clone()),
+    );
+
+    let err = match result {
+        Err(Error::ServerSelectionError(err)) => err,
+        other => panic!("Expected server selection to fail, got {:?}", other),
+    };
+    assert_eq!(Some(read_preference), err.read_preference);
+    assert_eq!(1, err.topology.servers.len());
+    assert_eq!("i-dont-exist", err.topology.servers[0].host.host_name);
+    assert_eq!(ServerType::Unknown, err.topology.servers[0].server_type);
+
+    let message = Error::ServerSelectionError(err).to_string();
+    assert!(message.contains("read preference { mode: \"secondary\" }"), "{}", message);
+    assert!(message.contains("i-dont-exist:27017: Unknown"), "{}", message);
+}
@@ -0,0 +1,52 @@
+use futures_core::Stream;
+use mongodb::tokio_compat::{Client, Cursor};
+use mongodb::coll::options::FindOptions;
+use tokio::runtime::{Builder, Runtime};
+
+use std::future;
+use std::pin::Pin;
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().build().unwrap()
+}
+
+// Reads the cursor's remaining documents, as `TryStreamExt::try_collect` would.
+fn collect(runtime: &Runtime, mut cursor: Cursor) -> Vec<i32> {
+    let mut values = Vec::new();
+    while let Some(doc) =
+        runtime.block_on(future::poll_fn(|cx| Pin::new(&mut cursor).poll_next(cx)))
+    {
+        values.push(doc.unwrap().get_i32("x").unwrap());
+    }
+    values
+}
+
+#[test]
+fn async_crud() {
+    let runtime = runtime();
+    let client = Client::connect("localhost", 27017).unwrap();
+    let coll = client.db("test-client-async").collection("async_crud");
+    runtime.block_on(coll.drop()).unwrap();
+
+    let docs = (0..10).map(|x| doc! { "x": x }).collect();
+    runtime.block_on(coll.insert_many(docs, None)).unwrap();
+    runtime.block_on(coll.update_one(doc! { "x": 0 }, doc! { "$set": { "y": 1 } }, None)).unwrap();
+    runtime.block_on(coll.delete_one(doc! { "x": 9 }, None)).unwrap();
+
+    assert_eq!(9, runtime.block_on(coll.count(None, None)).unwrap());
+    let updated = runtime.block_on(coll.find_one(Some(doc! { "y": 1 }), None)).unwrap();
+    assert_eq!(Some(0), updated.map(|doc| doc.get_i32("x").unwrap()));
+
+    // The cursor reads the documents over several batches.
+    let mut options = FindOptions::new();
+    options.batch_size = Some(2);
+    options.sort = Some(doc! { "x": 1 });
+    let cursor = runtime.block_on(coll.find(None, Some(options))).unwrap();
+    assert_eq!((0..9).collect::<Vec<_>>(), collect(&runtime, cursor));
+
+    options = FindOptions::new();
+    options.limit = Some(3);
+    options.sort = Some(doc! { "x": -1 });
+    let cursor = runtime.block_on(coll.find(None, Some(options))).unwrap();
+    assert_eq!(vec![8, 7, 6], collect(&runtime, cursor));
+}
@@ -0,0 +1,51 @@
+use bson::Bson;
+use mongodb::trace_context::{self, SpanContext, TraceContext};
+
+fn span() -> SpanContext {
+    let mut trace_id = [0; 16];
+    trace_id[15] = 0xab;
+    SpanContext::new(trace_id, [1, 2, 3, 4, 5, 6, 7, 8], true)
+}
+
+#[test]
+fn traceparent() {
+    assert_eq!(
+        "00-000000000000000000000000000000ab-0102030405060708-01",
+        span().traceparent()
+    );
+
+    let unsampled = SpanContext { sampled: false, ..span() };
+    assert!(unsampled.traceparent().ends_with("-00"));
+
+    let source = || Some(span());
+    assert_eq!(Some(span()), source.current_span());
+}
+
+#[test]
+fn attach_trace_comments() {
+    let traceparent = Bson::String(span().traceparent());
+
+    // Legacy queries are wrapped to carry a $comment.
+    let query = trace_context::attach(doc! { "x": 1 }, "app.users", &span(), None);
+    assert_eq!(doc! { "$query": { "x": 1 }, "$comment": traceparent.clone() }, query);
+
+    // find takes a comment from any server; wrapped commands get it inside.
+    let find = doc! { "$query": { "find": "users" }, "$readPreference": { "mode": "secondary" } };
+    let find = trace_context::attach(find, "app.$cmd", &span(), Some(6));
+    assert_eq!(Some(&traceparent), find.get_document("$query").unwrap().get("comment"));
+
+    // Other commands only take one from MongoDB 4.4.
+    for command in [doc! { "count": "users" }, doc! { "aggregate": "users", "pipeline": [] }] {
+        let old = trace_context::attach(command.clone(), "app.$cmd", &span(), Some(8));
+        assert_eq!(command, old);
+        let new = trace_context::attach(command, "app.$cmd", &span(), Some(9));
+        assert_eq!(Some(&traceparent), new.get("comment"));
+    }
+
+    // Comments the application set are kept.
+    let commented = doc! { "find": "users", "comment": "report" };
+    assert_eq!(
+        commented.clone(),
+        trace_context::attach(commented, "app.$cmd", &span(), Some(9))
+    );
+}
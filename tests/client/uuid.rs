@@ -0,0 +1,103 @@
+use bson::Bson;
+use bson::spec::BinarySubtype;
+use mongodb::{Client, ThreadedClient};
+use mongodb::connstring;
+use mongodb::db::ThreadedDatabase;
+use mongodb::uuid::{self, UuidExt, UuidRepresentation};
+
+const UUID: [u8; 16] = [
+    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+    0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+];
+
+#[test]
+fn uuid_representations() {
+    let cases = vec![
+        (UuidRepresentation::Standard, BinarySubtype::Uuid, UUID.to_vec()),
+        (UuidRepresentation::PythonLegacy, BinarySubtype::UuidOld, UUID.to_vec()),
+        (
+            UuidRepresentation::JavaLegacy,
+            BinarySubtype::UuidOld,
+            vec![
+                0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00,
+                0xff, 0xee, 0xdd, 0xcc, 0xbb, 0xaa, 0x99, 0x88,
+            ],
+        ),
+        (
+            UuidRepresentation::CSharpLegacy,
+            BinarySubtype::UuidOld,
+            vec![
+                0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66,
+                0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+            ],
+        ),
+    ];
+
+    for (representation, subtype, bytes) in cases {
+        let value = Bson::from_uuid(UUID, representation);
+        assert_eq!(Bson::Binary(subtype, bytes), value);
+        assert_eq!(UUID, value.to_uuid(representation).unwrap());
+        assert_eq!(
+            representation,
+            representation.to_str().parse::<UuidRepresentation>().unwrap()
+        );
+    }
+
+    // Standard UUIDs read the same in any representation, but legacy ones need to know theirs.
+    let standard = Bson::from_uuid(UUID, UuidRepresentation::Standard);
+    assert_eq!(UUID, standard.to_uuid(UuidRepresentation::JavaLegacy).unwrap());
+    let legacy = Bson::from_uuid(UUID, UuidRepresentation::JavaLegacy);
+    assert!(legacy.to_uuid(UuidRepresentation::Standard).is_err());
+    assert!(Bson::I32(1).to_uuid(UuidRepresentation::Standard).is_err());
+    assert!("unknown".parse::<UuidRepresentation>().is_err());
+}
+
+#[test]
+fn uuid_documents() {
+    let doc = doc! {
+        "_id": Bson::from_uuid(UUID, UuidRepresentation::Standard),
+        "owners": [{ "id": Bson::from_uuid(UUID, UuidRepresentation::Standard) }],
+        "name": "Ann",
+    };
+
+    let encoded = uuid::encode_document(doc.clone(), UuidRepresentation::CSharpLegacy);
+    assert_eq!(
+        &Bson::from_uuid(UUID, UuidRepresentation::CSharpLegacy),
+        encoded.get("_id").unwrap()
+    );
+    assert_eq!(doc, uuid::decode_document(encoded, UuidRepresentation::CSharpLegacy));
+    assert_eq!(doc, uuid::encode_document(doc.clone(), UuidRepresentation::Standard));
+}
+
+#[test]
+fn uuid_representation_option() {
+    let config = connstring::parse("mongodb://localhost/?uuidRepresentation=javaLegacy").unwrap();
+    let client = Client::with_config(config, None, None).unwrap();
+    assert_eq!(Some(UuidRepresentation::JavaLegacy), client.uuid_representation);
+
+    let db = client.db("app").with_uuid_representation(Some(UuidRepresentation::PythonLegacy));
+    assert_eq!(Some(UuidRepresentation::PythonLegacy), db.client.uuid_representation);
+    assert_eq!(Some(UuidRepresentation::JavaLegacy), client.uuid_representation);
+
+    let config = connstring::parse("mongodb://localhost/?uuidRepresentation=rust").unwrap();
+    assert!(Client::with_config(config, None, None).is_err());
+}
+
+#[test]
+fn legacy_uuid_round_trip() {
+    let client = Client::connect("localhost", 27017).unwrap();
+    let plain = client.db("test-uuid").collection("legacy_uuid_round_trip");
+    plain.drop().unwrap();
+
+    let java = plain.with_uuid_representation(Some(UuidRepresentation::JavaLegacy));
+    let id = Bson::from_uuid(UUID, UuidRepresentation::Standard);
+    java.insert_one(doc! { "_id": id.clone() }, None).unwrap();
+
+    // The server stores the UUID as the legacy Java driver would have.
+    let stored = plain.find_one(None, None).unwrap().unwrap();
+    assert_eq!(Some(&Bson::from_uuid(UUID, UuidRepresentation::JavaLegacy)), stored.get("_id"));
+
+    // Queries are translated too, and results come back as standard UUIDs.
+    let found = java.find_one(Some(doc! { "_id": id.clone() }), None).unwrap().unwrap();
+    assert_eq!(Some(&id), found.get("_id"));
+}
@@ -1,8 +1,12 @@
-use bson::{Bson, Document};
+use bson::{self, Bson, Document};
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
 use mongodb::{Client, ThreadedClient};
 use mongodb::db::ThreadedDatabase;
-use mongodb::wire_protocol::flags::{OpInsertFlags, OpQueryFlags, OpUpdateFlags};
+use mongodb::error::{DecodeLimit, Error};
+use mongodb::wire_protocol::flags::{OpInsertFlags, OpMsgFlags, OpQueryFlags, OpUpdateFlags};
+use mongodb::wire_protocol::limits::DecodeLimits;
 use mongodb::wire_protocol::operations::Message;
+use std::io::Cursor;
 use std::net::TcpStream;
 
 #[test]
@@ -296,3 +300,164 @@ fn insert_update_then_query() {
         Err(_) => panic!("Could not connect to server"),
     }
 }
+
+fn encode(doc: &Document) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bson::encode_document(&mut bytes, doc).unwrap();
+    bytes
+}
+
+// Nests `{ "a": ... }` `depth` levels deep, counting the outermost document.
+fn nested(depth: usize) -> Document {
+    (1..depth).fold(doc! { "leaf": true }, |inner, _| doc! { "a": inner })
+}
+
+#[test]
+fn decode_limits() {
+    let limits = DecodeLimits { max_depth: 5, max_array_len: 3 };
+
+    assert!(limits.check(&encode(&nested(5))).is_ok());
+    match limits.check(&encode(&nested(6))) {
+        Err(Error::DecodeLimitExceeded(DecodeLimit::Depth(5))) => (),
+        other => panic!("Expected the depth limit to be exceeded, got {:?}", other),
+    }
+
+    // Arrays count towards the depth, as does the scope of JavaScript code.
+    let doc = doc! { "a": [[[{ "b": [1, 2, 3] }]]] };
+    match limits.check(&encode(&doc)) {
+        Err(Error::DecodeLimitExceeded(DecodeLimit::Depth(5))) => (),
+        other => panic!("Expected the depth limit to be exceeded, got {:?}", other),
+    }
+
+    let code = Bson::JavaScriptCodeWithScope(String::from("x"), nested(5));
+    assert!(limits.check(&encode(&doc! { "code": code })).is_err());
+
+    let doc = doc! {
+        "s": "string",
+        "n": 1.5,
+        "id": bson::oid::ObjectId::with_bytes([1; 12]),
+        "re": Bson::RegExp(String::from("^a"), String::from("i")),
+        "bin": Bson::Binary(bson::spec::BinarySubtype::Generic, vec![0; 10]),
+        "list": [1, "two", { "three": 3 }],
+        "null": Bson::Null,
+    };
+    assert!(limits.check(&encode(&doc)).is_ok());
+
+    let doc = doc! { "outer": { "list": [1, 2, 3, 4] } };
+    match limits.check(&encode(&doc)) {
+        Err(Error::DecodeLimitExceeded(DecodeLimit::ArrayLength(3))) => (),
+        other => panic!("Expected the array limit to be exceeded, got {:?}", other),
+    }
+
+    assert!(DecodeLimits::default().check(&encode(&nested(200))).is_ok());
+    assert!(DecodeLimits::default().check(&encode(&nested(201))).is_err());
+}
+
+// Serializes a reply holding the encoded documents.
+fn reply(docs: &[Vec<u8>]) -> Vec<u8> {
+    let body_len: usize = docs.iter().map(Vec::len).sum();
+
+    let mut reply = Vec::new();
+    for field in &[16 + 20 + body_len as i32, 1, 0, 1] {
+        reply.write_i32::<LittleEndian>(*field).unwrap();
+    }
+    reply.write_i32::<LittleEndian>(0).unwrap();
+    reply.write_i64::<LittleEndian>(0).unwrap();
+    reply.write_i32::<LittleEndian>(0).unwrap();
+    reply.write_i32::<LittleEndian>(docs.len() as i32).unwrap();
+    for doc in docs {
+        reply.extend_from_slice(doc);
+    }
+    reply
+}
+
+#[test]
+fn read_reply_with_limits() {
+    let docs = vec![encode(&doc! { "ok": 1 }), encode(&nested(10))];
+    let reply = reply(&docs);
+
+    match Message::read(&mut Cursor::new(reply.clone())).unwrap() {
+        Message::OpReply { documents, .. } => assert_eq!(2, documents.len()),
+        other => panic!("Expected a reply, got {:?}", other),
+    }
+
+    let limits = DecodeLimits { max_depth: 5, ..DecodeLimits::default() };
+    match Message::read_with_limits(&mut Cursor::new(reply), &limits) {
+        Err(Error::DecodeLimitExceeded(DecodeLimit::Depth(5))) => (),
+        other => panic!("Expected the depth limit to be exceeded, got {:?}", other),
+    }
+}
+
+#[test]
+fn read_reply_overstating_count() {
+    // The count a reply claims does not decide how much is allocated for its documents.
+    let mut reply = reply(&[encode(&doc! { "ok": 1 })]);
+    LittleEndian::write_i32(&mut reply[32..36], i32::MAX);
+
+    match Message::read(&mut Cursor::new(reply)).unwrap() {
+        Message::OpReply { documents, .. } => assert_eq!(vec![doc! { "ok": 1 }], documents),
+        other => panic!("Expected a reply, got {:?}", other),
+    }
+}
+
+#[test]
+fn reuse_buffers() {
+    let mut scratch = Vec::new();
+
+    // Messages encode the same with a reused buffer, which holds the last one encoded.
+    let namespace = String::from("db.coll");
+    let query = Message::new_query(1, OpQueryFlags::empty(), namespace, 0, 0, doc! { "x": 1 }, None)
+        .unwrap();
+    let mut expected = Vec::new();
+    query.write(&mut expected).unwrap();
+
+    for _ in 0..2 {
+        let mut written = Vec::new();
+        query.write_with_buffer(&mut written, &mut scratch).unwrap();
+        assert_eq!(expected, written);
+        assert_eq!(expected, scratch);
+    }
+
+    // Replies are read into the buffer, replacing what it held.
+    let large = reply(&[encode(&doc! { "s": "a".repeat(1000) })]);
+    let small = reply(&[encode(&doc! { "n": 1 }), encode(&doc! { "n": 2 })]);
+
+    let limits = DecodeLimits::default();
+    Message::read_with_buffer(&mut Cursor::new(large), &limits, &mut scratch).unwrap();
+    let capacity = scratch.capacity();
+
+    match Message::read_with_buffer(&mut Cursor::new(small), &limits, &mut scratch).unwrap() {
+        Message::OpReply { documents, .. } => {
+            assert_eq!(vec![doc! { "n": 1 }, doc! { "n": 2 }], documents);
+        }
+        other => panic!("Expected a reply, got {:?}", other),
+    }
+    assert_eq!(capacity, scratch.capacity());
+
+    // A reply cut short is an error rather than a partial read.
+    let mut truncated = reply(&[encode(&doc! { "n": 1 })]);
+    truncated.pop();
+    let read = Message::read_with_buffer(&mut Cursor::new(truncated), &limits, &mut scratch);
+    assert!(read.is_err());
+}
+
+#[test]
+fn encode_op_msg() {
+    let command = doc! { "insert": "coll", "$db": "db" };
+    let message = Message::new_msg(7, OpMsgFlags::MORE_TO_COME, command.clone()).unwrap();
+
+    let mut written = Vec::new();
+    message.write(&mut written).unwrap();
+
+    let mut expected = Vec::new();
+    let body = encode(&command);
+    expected.write_i32::<LittleEndian>(16 + 4 + 1 + body.len() as i32).unwrap();
+    expected.write_i32::<LittleEndian>(7).unwrap();
+    expected.write_i32::<LittleEndian>(0).unwrap();
+    expected.write_i32::<LittleEndian>(2013).unwrap();
+    expected.write_u32::<LittleEndian>(2).unwrap();
+    expected.push(0);
+    expected.extend(body);
+
+    assert_eq!(expected, written);
+}
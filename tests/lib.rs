@@ -38,17 +38,23 @@
     wrong_pub_self_convention,
 ))]
 
-#[macro_use(ulps_eq)]
-extern crate approx;
+extern crate byteorder;
 #[macro_use(bson, doc)]
 extern crate bson;
+extern crate chrono;
+#[cfg(any(feature = "tokio-compat", feature = "stream"))]
+extern crate futures_core;
 extern crate mongodb;
+#[cfg(feature = "ssl")]
+extern crate openssl;
 extern crate rand;
 extern crate semver;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde;
 extern crate serde_json;
+#[cfg(feature = "tokio-compat")]
+extern crate tokio;
 
 #[macro_export]
 macro_rules! skip_if_db_version_below {
@@ -1,7 +1,28 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use mongodb::{Client, ClientOptions, ThreadedClient};
+use mongodb::connstring;
+use mongodb::dns::{DnsResolver, Lookup};
 use mongodb::db::ThreadedDatabase;
+use mongodb::stream::StreamConnector;
+use openssl::asn1::{Asn1Integer, Asn1Time};
+use openssl::bn::BigNum;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::ocsp::{OcspResponse, OcspResponseStatus};
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::ssl::{SslAcceptor, SslMethod};
+use openssl::x509::{CrlNumber, X509, X509Builder, X509CrlBuilder, X509Extension, X509Name,
+                    X509NameBuilder, X509RevokedBuilder};
+use openssl::x509::extension::{AuthorityKeyIdentifier, BasicConstraints};
 
 #[test]
 fn ssl_connect_and_insert() {
@@ -42,3 +63,304 @@ fn unauthenticated_ssl_connect_and_insert() {
 
     coll.insert_one(doc, None).unwrap();
 }
+
+// A certificate authority, and a server certificate it issued, generated for a test.
+struct Pki {
+    dir: PathBuf,
+    ca_key: PKey<Private>,
+    ca: X509,
+    server_key: PKey<Private>,
+    server: X509,
+}
+
+const SERVER_SERIAL: u32 = 2;
+
+fn name(common_name: &str) -> X509Name {
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", common_name).unwrap();
+    name.build()
+}
+
+fn serial_number(serial: u32) -> Asn1Integer {
+    BigNum::from_u32(serial).unwrap().to_asn1_integer().unwrap()
+}
+
+fn certificate_builder(
+    subject: &str,
+    issuer: &str,
+    serial: u32,
+    key: &PKey<Private>,
+) -> X509Builder {
+    let mut builder = X509Builder::new().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_serial_number(&serial_number(serial)).unwrap();
+    builder.set_subject_name(&name(subject)).unwrap();
+    builder.set_issuer_name(&name(issuer)).unwrap();
+    builder.set_pubkey(key).unwrap();
+    builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    builder
+}
+
+// Issues a server certificate that names `ocsp_responder` as its OCSP responder, if given.
+#[allow(deprecated)]
+fn pki(test: &str, ocsp_responder: Option<&str>) -> Pki {
+    let dir = ::std::env::temp_dir().join(format!("test-ssl-{}", test));
+    fs::create_dir_all(&dir).unwrap();
+
+    let ca_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut builder = certificate_builder("test-ca", "test-ca", 1, &ca_key);
+    builder.append_extension(BasicConstraints::new().critical().ca().build().unwrap()).unwrap();
+    builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+    let ca = builder.build();
+
+    let server_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut builder = certificate_builder("127.0.0.1", "test-ca", SERVER_SERIAL, &server_key);
+    if let Some(url) = ocsp_responder {
+        let access = format!("OCSP;URI:{}", url);
+        let extension = {
+            let context = builder.x509v3_context(Some(&ca), None);
+            X509Extension::new_nid(None, Some(&context), Nid::INFO_ACCESS, &access).unwrap()
+        };
+        builder.append_extension(extension).unwrap();
+    }
+    builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+    let server = builder.build();
+
+    fs::write(dir.join("ca.pem"), ca.to_pem().unwrap()).unwrap();
+
+    Pki { dir, ca_key, ca, server_key, server }
+}
+
+impl Pki {
+    // Writes a CRL from the certificate authority that revokes the certificates with the given
+    // serial numbers, and returns its path.
+    fn crl(&self, revoked_serials: &[u32]) -> String {
+        let mut builder = X509CrlBuilder::new().unwrap();
+        builder.set_issuer_name(self.ca.subject_name()).unwrap();
+        builder.set_last_update(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_next_update(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        let identifier = {
+            let issuer = X509Builder::new().unwrap();
+            let context = issuer.x509v3_context(Some(&self.ca), None);
+            AuthorityKeyIdentifier::new().issuer(true).build(&context).unwrap()
+        };
+        builder.append_extension(identifier).unwrap();
+        let number = CrlNumber::new(BigNum::from_u32(1).unwrap()).unwrap().build().unwrap();
+        builder.append_extension(number).unwrap();
+        for &serial in revoked_serials {
+            let mut revoked = X509RevokedBuilder::new().unwrap();
+            revoked.set_serial_number(&serial_number(serial)).unwrap();
+            revoked.set_revocation_date(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+            builder.add_revoked(revoked.build()).unwrap();
+        }
+        builder.sign(&self.ca_key, MessageDigest::sha256()).unwrap();
+
+        let path = self.dir.join(format!("crl-{}.pem", revoked_serials.len()));
+        fs::write(&path, builder.build().unwrap().to_pem().unwrap()).unwrap();
+        path.to_str().unwrap().to_owned()
+    }
+
+    fn connector(&self) -> StreamConnector {
+        let ca_file = self.dir.join("ca.pem");
+        StreamConnector::with_unauthenticated_ssl(Some(ca_file.to_str().unwrap()), true)
+    }
+
+    // Starts a server that accepts one TLS connection, stapling `ocsp_response` if given, and
+    // connects to it with `connector`.
+    fn connect(
+        &self,
+        connector: &StreamConnector,
+        ocsp_response: Option<Vec<u8>>,
+    ) -> io::Result<()> {
+        let timeout = Some(Duration::from_secs(5));
+        self.connect_with(ocsp_response, |port| {
+            connector.connect_with_timeout("127.0.0.1", port, timeout).map(|_| ())
+        })
+    }
+
+    // Starts a server like `connect`, and connects to its port with `connect`.
+    fn connect_with<F>(&self, ocsp_response: Option<Vec<u8>>, connect: F) -> io::Result<()>
+    where
+        F: FnOnce(u16) -> io::Result<()>,
+    {
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&self.server_key).unwrap();
+        acceptor.set_certificate(&self.server).unwrap();
+        if let Some(response) = ocsp_response {
+            acceptor
+                .set_status_callback(move |ssl| {
+                    ssl.set_ocsp_status(&response)?;
+                    Ok(true)
+                })
+                .unwrap();
+        }
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let _ = acceptor.accept(stream);
+        });
+
+        let result = connect(port);
+        server.join().unwrap();
+        result
+    }
+}
+
+// Starts an OCSP responder that fails every request, returning its URL and a count of the
+// requests it received.
+fn failing_ocsp_responder() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://127.0.0.1:{}/ocsp", listener.local_addr().unwrap().port());
+    let requests = Arc::new(AtomicUsize::new(0));
+
+    let counter = requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut byte = [0; 1];
+            while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                request.push(byte[0]);
+            }
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = stream.write_all(b"HTTP/1.0 500 Internal Server Error\r\n\r\n");
+        }
+    });
+
+    (url, requests)
+}
+
+// Starts an OCSP responder that accepts connections but never answers, returning its URL.
+fn silent_ocsp_responder() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://127.0.0.1:{}/ocsp", listener.local_addr().unwrap().port());
+
+    thread::spawn(move || {
+        let streams: Vec<_> = listener.incoming().collect();
+        drop(streams);
+    });
+
+    url
+}
+
+// Resolves every host name to the loopback address.
+struct Loopback;
+
+impl DnsResolver for Loopback {
+    fn lookup_ip(&self, _: &str) -> io::Result<Lookup<IpAddr>> {
+        Ok(Lookup::new(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)], None))
+    }
+}
+
+#[test]
+fn crl_file_revokes_server_certificate() {
+    let pki = pki("crl", None);
+    pki.connect(&pki.connector(), None).unwrap();
+
+    let connector = pki.connector().with_crl_file(&pki.crl(&[]));
+    pki.connect(&connector, None).unwrap();
+
+    let connector = pki.connector().with_crl_file(&pki.crl(&[SERVER_SERIAL]));
+    assert!(pki.connect(&connector, None).is_err());
+
+    // The revoked certificate is not checked without verifying the server.
+    let ca_file = pki.dir.join("ca.pem");
+    let connector =
+        StreamConnector::with_unauthenticated_ssl(Some(ca_file.to_str().unwrap()), false)
+            .with_crl_file(&pki.crl(&[SERVER_SERIAL]));
+    pki.connect(&connector, None).unwrap();
+}
+
+#[test]
+fn stapled_ocsp_response() {
+    let pki = pki("stapled", None);
+    let connector = pki.connector().with_ocsp_endpoint_check(false);
+
+    // A responder that could not answer says nothing about the certificate.
+    let try_later = OcspResponse::create(OcspResponseStatus::TRY_LATER, None).unwrap();
+    pki.connect(&connector, Some(try_later.to_der().unwrap())).unwrap();
+
+    let err = pki.connect(&connector, Some(b"not an OCSP response".to_vec())).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+}
+
+#[test]
+fn ocsp_endpoint_check_soft_fails() {
+    let (url, requests) = failing_ocsp_responder();
+    let pki = pki("endpoint", Some(&url));
+
+    pki.connect(&pki.connector(), None).unwrap();
+    assert_eq!(1, requests.load(Ordering::SeqCst));
+
+    pki.connect(&pki.connector().with_ocsp_endpoint_check(false), None).unwrap();
+    assert_eq!(1, requests.load(Ordering::SeqCst));
+}
+
+#[test]
+fn ocsp_endpoint_check_within_deadline() {
+    let pki = pki("deadline", Some(&silent_ocsp_responder()));
+    let connector = pki.connector();
+
+    // The responder is passed over once the connection's deadline has passed.
+    let start = Instant::now();
+    let timeout = Some(Duration::from_secs(1));
+    pki.connect_with(None, |port| {
+        connector.connect_with_timeout("127.0.0.1", port, timeout).map(|_| ())
+    }).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(4));
+}
+
+#[test]
+fn ocsp_endpoint_check_uses_resolver() {
+    let (url, requests) = failing_ocsp_responder();
+    let url = url.replace("127.0.0.1", "ocsp.example.invalid");
+    let pki = pki("resolver", Some(&url));
+    let connector = pki.connector();
+
+    let timeout = Some(Duration::from_secs(5));
+    pki.connect_with(None, |port| {
+        connector.connect_with_resolver("127.0.0.1", port, timeout, &Loopback).map(|_| ())
+    }).unwrap();
+    assert_eq!(1, requests.load(Ordering::SeqCst));
+}
+
+#[test]
+fn revocation_uri_options() {
+    let uri = "mongodb://localhost/?tlsCRLFile=/etc/ssl/crl.pem&tlsDisableOCSPEndpointCheck=true";
+    let options = connstring::parse(uri).unwrap().options.unwrap();
+
+    let connector = StreamConnector::with_unauthenticated_ssl(None, true)
+        .with_connection_options(&options)
+        .unwrap();
+    match connector {
+        StreamConnector::Ssl { crl_file, disable_ocsp_endpoint_check, .. } => {
+            assert_eq!(Some(String::from("/etc/ssl/crl.pem")), crl_file);
+            assert!(disable_ocsp_endpoint_check);
+        }
+        _ => panic!("Expected an SSL connector."),
+    }
+
+    // A CRL file given to the connector takes precedence over the connection string.
+    let connector = StreamConnector::with_unauthenticated_ssl(None, true)
+        .with_crl_file("/tmp/crl.pem")
+        .with_connection_options(&options)
+        .unwrap();
+    match connector {
+        StreamConnector::Ssl { crl_file, .. } => {
+            assert_eq!(Some(String::from("/tmp/crl.pem")), crl_file)
+        }
+        _ => panic!("Expected an SSL connector."),
+    }
+
+    // The options need SSL.
+    assert!(Client::with_uri(uri).is_err());
+    assert!(Client::with_uri("mongodb://localhost/?tlsDisableOCSPEndpointCheck=false").is_err());
+
+    let options = ClientOptions::with_unauthenticated_ssl(None, true);
+    let uri = "mongodb://localhost/?tlsDisableOCSPEndpointCheck=yes";
+    assert!(Client::with_uri_and_options(uri, options).is_err());
+}